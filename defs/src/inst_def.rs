@@ -0,0 +1,332 @@
+use proc_macro::TokenStream;
+use proc_macro_error::{proc_macro2::Ident, *};
+use proc_quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{
+    braced, bracketed, parenthesized, parse_macro_input, punctuated::Punctuated, Attribute, Error,
+    Expr, LitInt, LitStr, Path, Token,
+};
+
+pub fn run(item: TokenStream) -> TokenStream {
+    TokenStream::from(expand(parse_macro_input!(item as Insts)))
+}
+
+type TS = proc_macro2::TokenStream;
+
+struct Insts(Vec<Inst>);
+
+/// One instruction. Every key is optional; an instruction without `asm` is a
+/// pseudo instruction that only gets an opcode. `mem` is the size the asm printer
+/// puts in front of a memory operand (`byte`, `word`, `dword` or `qword`).
+///
+/// ```ignore
+/// ADDri32 {
+///     asm: "add",
+///     defs: [GR32],
+///     uses: [GR32, i32],
+///     tie: (0, 0),
+///     encoding: Ri(0x81, 0, 4),
+///     pattern: Add i32 (GR32, i32),
+/// }
+/// MOVmr32 {
+///     asm: "mov",
+///     uses: [mem, GR32],
+///     mem: dword,
+///     pattern: Store Void (mem, GR32),
+/// }
+/// ```
+struct Inst {
+    attrs: Vec<Attribute>,
+    name: Ident,
+    asm: Option<LitStr>,
+    defs: Vec<Operand>,
+    uses: Vec<Operand>,
+    imp_defs: Vec<Operand>,
+    imp_uses: Vec<Operand>,
+    ties: Vec<(LitInt, LitInt)>,
+    encoding: Option<Expr>,
+    mem: Option<Ident>,
+    patterns: Vec<Pattern>,
+}
+
+enum Operand {
+    Class(Ident),
+    Specific(Path),
+    AnyReg,
+    Any,
+    Mem,
+    Addr,
+    FrameIndex,
+    Block,
+    Imm(Ident),
+}
+
+/// `<IR opcode> <type> (<operands>)`
+struct Pattern {
+    opcode: Ident,
+    ty: Ident,
+    operands: Vec<Operand>,
+}
+
+impl Parse for Insts {
+    fn parse(input: ParseStream) -> Result<Self, Error> {
+        let mut insts = vec![];
+        while !input.is_empty() {
+            insts.push(input.parse()?);
+        }
+        Ok(Insts(insts))
+    }
+}
+
+impl Parse for Inst {
+    fn parse(input: ParseStream) -> Result<Self, Error> {
+        let mut inst = Inst {
+            attrs: input.call(Attribute::parse_outer)?,
+            name: input.parse()?,
+            asm: None,
+            defs: vec![],
+            uses: vec![],
+            imp_defs: vec![],
+            imp_uses: vec![],
+            ties: vec![],
+            encoding: None,
+            mem: None,
+            patterns: vec![],
+        };
+        let body;
+        braced!(body in input);
+        while !body.is_empty() {
+            let key = body.parse::<Ident>()?;
+            body.parse::<Token![:]>()?;
+            match key.to_string().as_str() {
+                "asm" => inst.asm = Some(body.parse()?),
+                "defs" => inst.defs = parse_operands(&body)?,
+                "uses" => inst.uses = parse_operands(&body)?,
+                "imp_defs" => inst.imp_defs = parse_operands(&body)?,
+                "imp_uses" => inst.imp_uses = parse_operands(&body)?,
+                "tie" => {
+                    let pair;
+                    parenthesized!(pair in body);
+                    let def = pair.parse()?;
+                    pair.parse::<Token![,]>()?;
+                    inst.ties.push((def, pair.parse()?))
+                }
+                "encoding" => inst.encoding = Some(body.parse()?),
+                "mem" => {
+                    let word = body.parse::<Ident>()?;
+                    match word.to_string().as_str() {
+                        "byte" | "word" | "dword" | "qword" => inst.mem = Some(word),
+                        _ => return Err(Error::new(word.span(), "unknown memory operand size")),
+                    }
+                }
+                "pattern" => inst.patterns.push(body.parse()?),
+                _ => return Err(Error::new(key.span(), "unknown key")),
+            }
+            if !body.is_empty() {
+                body.parse::<Token![,]>()?;
+            }
+        }
+        Ok(inst)
+    }
+}
+
+impl Parse for Pattern {
+    fn parse(input: ParseStream) -> Result<Self, Error> {
+        let opcode = input.parse()?;
+        let ty = input.parse()?;
+        let operands;
+        parenthesized!(operands in input);
+        let operands = Punctuated::<Operand, Token![,]>::parse_terminated(&operands)?;
+        Ok(Pattern {
+            opcode,
+            ty,
+            operands: operands.into_iter().collect(),
+        })
+    }
+}
+
+impl Parse for Operand {
+    fn parse(input: ParseStream) -> Result<Self, Error> {
+        let path = input.parse::<Path>()?;
+        if path.segments.len() > 1 {
+            return Ok(Operand::Specific(path));
+        }
+        let ident = path.segments[0].ident.clone();
+        Ok(match ident.to_string().as_str() {
+            "any_reg" => Operand::AnyReg,
+            "any" => Operand::Any,
+            "mem" => Operand::Mem,
+            "addr" => Operand::Addr,
+            "fi" => Operand::FrameIndex,
+            "block" => Operand::Block,
+            "i8" | "i16" | "i32" | "i64" | "f32" | "f64" => {
+                Operand::Imm(Ident::new(&ident.to_string().to_uppercase(), ident.span()))
+            }
+            _ => Operand::Class(ident),
+        })
+    }
+}
+
+fn parse_operands(input: ParseStream) -> Result<Vec<Operand>, Error> {
+    let operands;
+    bracketed!(operands in input);
+    Ok(
+        Punctuated::<Operand, Token![,]>::parse_terminated(&operands)?
+            .into_iter()
+            .collect(),
+    )
+}
+
+impl Operand {
+    fn target_operand(&self) -> TS {
+        match self {
+            Operand::Class(_) | Operand::Specific(_) | Operand::AnyReg => {
+                let reg = self.target_register();
+                quote! { TargetOperand::Register(#reg) }
+            }
+            Operand::Any => quote! { TargetOperand::Any },
+            Operand::Mem => quote! { TargetOperand::Mem },
+            Operand::Addr => quote! { TargetOperand::Addr },
+            Operand::FrameIndex => quote! { TargetOperand::FrameIndex },
+            Operand::Block => quote! { TargetOperand::Block },
+            Operand::Imm(i) => quote! { TargetOperand::Immediate(TargetImmediate::#i) },
+        }
+    }
+
+    fn target_register(&self) -> TS {
+        match self {
+            Operand::Class(c) => quote! { TargetRegister::RegClass(RegisterClassKind::#c) },
+            Operand::Specific(r) => quote! { TargetRegister::Specific(#r.as_phys_reg()) },
+            Operand::AnyReg => quote! { TargetRegister::Any },
+            _ => abort_call_site!("expected a register"),
+        }
+    }
+
+    fn select_operand(&self) -> TS {
+        match self {
+            Operand::Class(c) => quote! { SelectOperand::Reg(RegisterClassKind::#c) },
+            Operand::Imm(i) => quote! { SelectOperand::Imm(TargetImmediate::#i) },
+            Operand::Mem => quote! { SelectOperand::Mem },
+            _ => abort_call_site!("operand can't appear in a pattern"),
+        }
+    }
+}
+
+fn expand(insts: Insts) -> TS {
+    let insts = insts.0;
+
+    let variants = insts.iter().map(|inst| {
+        let attrs = &inst.attrs;
+        let name = &inst.name;
+        quote! { #(#attrs)* #name }
+    });
+
+    let real_insts = insts.iter().filter(|inst| inst.asm.is_some());
+
+    let defs = real_insts.clone().map(|inst| {
+        let name = &inst.name;
+        let asm = inst.asm.as_ref().unwrap();
+        let mut def = quote! { TargetInstDef::new(#asm, TargetOpcode::#name) };
+        if !inst.uses.is_empty() {
+            let uses = inst.uses.iter().map(Operand::target_operand);
+            def = quote! { #def.set_uses(vec![#(#uses),*]) };
+        }
+        if !inst.defs.is_empty() {
+            let defs = inst.defs.iter().map(Operand::target_register);
+            def = quote! { #def.set_defs(vec![#(#defs),*]) };
+        }
+        for (d, u) in &inst.ties {
+            def = quote! { #def.add_tie(DefOrUseReg::Def(#d), DefOrUseReg::Use(#u)) };
+        }
+        if !inst.imp_defs.is_empty() {
+            let regs = inst.imp_defs.iter().map(Operand::target_register);
+            def = quote! { #def.set_imp_def(vec![#(#regs),*]) };
+        }
+        if !inst.imp_uses.is_empty() {
+            let regs = inst.imp_uses.iter().map(Operand::target_register);
+            def = quote! { #def.set_imp_use(vec![#(#regs),*]) };
+        }
+        quote! { pub static ref #name: TargetInstDef = #def; }
+    });
+
+    let inst_def_arms = real_insts.map(|inst| {
+        let name = &inst.name;
+        quote! { Self::#name => Some(&*inst::#name), }
+    });
+
+    let encoding_arms = insts.iter().filter_map(|inst| {
+        let name = &inst.name;
+        inst.encoding
+            .as_ref()
+            .map(|enc| quote! { Self::#name => Some(Encoding::#enc), })
+    });
+
+    let mem_word_arms = insts.iter().filter_map(|inst| {
+        let name = &inst.name;
+        inst.mem.as_ref().map(|word| {
+            let word = word.to_string();
+            quote! { Self::#name => Some(#word), }
+        })
+    });
+
+    let select_arms = insts.iter().flat_map(|inst| {
+        let name = &inst.name;
+        inst.patterns.iter().map(move |pat| {
+            let opcode = &pat.opcode;
+            let ty = &pat.ty;
+            let operands = pat.operands.iter().map(Operand::select_operand);
+            quote! {
+                (IROpcode::#opcode, MVType::#ty, [#(#operands),*]) => Some(Self::#name),
+            }
+        })
+    });
+
+    quote! {
+        #[derive(Debug, Clone, PartialEq, Copy)]
+        pub enum TargetOpcode {
+            #(#variants),*
+        }
+
+        #[allow(non_upper_case_globals)]
+        mod inst {
+            use super::*;
+
+            lazy_static! {
+                #(#defs)*
+            }
+        }
+
+        impl TargetOpcode {
+            pub fn inst_def(&self) -> Option<&TargetInstDef> {
+                match self {
+                    #(#inst_def_arms)*
+                    _ => None,
+                }
+            }
+
+            pub fn encoding(&self) -> Option<Encoding> {
+                match self {
+                    #(#encoding_arms)*
+                    _ => None,
+                }
+            }
+
+            /// The size of the memory operand, as written in front of `ptr` in assembly.
+            pub fn mem_word(&self) -> Option<&'static str> {
+                match self {
+                    #(#mem_word_arms)*
+                    _ => None,
+                }
+            }
+
+            /// Picks the instruction whose pattern matches an IR operation of type `ty`
+            /// applied to `operands`.
+            pub fn select(opcode: IROpcode, ty: MVType, operands: &[SelectOperand]) -> Option<Self> {
+                match (opcode, ty, operands) {
+                    #(#select_arms)*
+                    _ => None,
+                }
+            }
+        }
+    }
+}
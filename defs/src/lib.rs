@@ -10,6 +10,7 @@ extern crate syn;
 
 use proc_macro_error::*;
 
+mod inst_def;
 mod isel_pat;
 mod node_gen;
 mod register;
@@ -30,3 +31,9 @@ pub fn isel_pat(item: TokenStream) -> TokenStream {
 pub fn node_gen(item: TokenStream) -> TokenStream {
     node_gen::run(item)
 }
+
+#[proc_macro_error]
+#[proc_macro]
+pub fn instructions(item: TokenStream) -> TokenStream {
+    inst_def::run(item)
+}
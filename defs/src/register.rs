@@ -4,7 +4,7 @@ use proc_macro_error::{
     *,
 };
 use proc_quote::quote;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use syn::parse::{Parse, ParseStream};
 use syn::{parse_macro_input, Error, LitInt, Token};
 
@@ -176,6 +176,8 @@ impl DefinitionConstructible for Registers {
         let mut registers_info_new = quote![];
         let mut physregs_name = quote![];
         let mut str2reg = quote! {};
+        // Classes may share register names (e.g. f32 and f64 classes on the same registers)
+        let mut str2reg_names = HashSet::new();
         let mut reg_class_kind_size_in_bits = quote! {};
         let mut ret_val_regs = quote! {};
         let mut regs_total_num = 0;
@@ -284,10 +286,12 @@ impl DefinitionConstructible for Registers {
                     #registers_info_new
                     phys_regs_list.push(f(&mut arena, #class_name::#r));
                 };
-                str2reg = quote! {
-                    #str2reg
-                    #name => #class_name::#r.as_phys_reg(),
-                };
+                if str2reg_names.insert(name.clone()) {
+                    str2reg = quote! {
+                        #str2reg
+                        #name => #class_name::#r.as_phys_reg(),
+                    };
+                }
                 trt_physreg_sub = quote! {
                     #trt_physreg_sub
                     #class_name::#r.sub_reg(),
@@ -0,0 +1,19 @@
+#include "assert.h"
+
+char low_byte(int x) { return (char)x; }
+
+int main() {
+  int i = 0x1234;
+  char c = (char)i;
+  assert(c == 0x34);
+  assert(low_byte(0x1ff) == 0 - 1);
+  long l = 0x123456789;
+  short s = (short)l;
+  assert(s == 0x6789);
+  int a = 42;
+  long addr = (long)&a;
+  int *p = (int *)addr;
+  *p = 7;
+  assert(a == 7);
+  return 0;
+}
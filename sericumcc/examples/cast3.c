@@ -0,0 +1,17 @@
+#include "assert.h"
+
+double u2d(unsigned long x) { return x; }
+unsigned long d2u(double x) { return x; }
+
+int main() {
+  unsigned long big = 2147483648;
+  big = big * 4294967296;
+  unsigned long bigger = big + 4611686018427387904;
+  double two63 = 4294967296.0 * 2147483648.0;
+  assert(u2d(big) == two63);
+  assert(u2d(bigger) == two63 * 1.5);
+  assert(u2d(12345) == 12345.0);
+  assert(d2u(two63 * 1.5) == bigger);
+  assert(d2u(12345.75) == 12345);
+  return 0;
+}
@@ -0,0 +1,16 @@
+#include "assert.h"
+
+float scale(float x, int n) { return x * n + 0.5; }
+
+double widen(float x) { return x; }
+
+int main() {
+  float f = 1.5;
+  double d = widen(f);
+  float g = d / 4;
+  assert(scale(f, 3) == 5.0);
+  assert(d == 1.5);
+  assert(g == 0.375);
+  assert(scale(g, 8) > f);
+  return 0;
+}
@@ -0,0 +1,23 @@
+#include "assert.h"
+
+int add_short(short a, short b) { return a + b; }
+
+long mul_long(long a, long b) { return a * b; }
+
+int main() {
+  short s = 0 - 1234;
+  short t = 30000;
+  int i = s;
+  long l = 100000;
+  assert(i == 0 - 1234);
+  assert(add_short(s, 34) == 0 - 1200);
+  assert(add_short(t, t) == 60000);
+  assert(t / 7 == 4285);
+  assert(t % 7 == 5);
+  assert(mul_long(l, l) == 10000000000);
+  assert(mul_long(l, l) / l == l);
+  assert(s < t);
+  l = s;
+  assert(l == 0 - 1234);
+  return 0;
+}
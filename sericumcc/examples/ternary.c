@@ -0,0 +1,38 @@
+#include "assert.h"
+
+int max(int a, int b) { return a > b ? a : b; }
+
+int min(int a, int b) {
+  int m;
+  if (a < b)
+    m = a;
+  else
+    m = b;
+  return m;
+}
+
+long abs_l(long x) { return x < 0 ? 0 - x : x; }
+
+unsigned clamp(unsigned x) { return x > 100 ? 100 : x; }
+
+double fmax2(double a, double b) { return a > b ? a : b; }
+
+int main() {
+  assert(max(3, 5) == 5);
+  assert(max(0 - 3, 0 - 5) == 0 - 3);
+  assert(min(3, 5) == 3);
+  assert(min(7, 0 - 7) == 0 - 7);
+  assert(abs_l(0 - 10) == 10);
+  assert(abs_l(12) == 12);
+  assert(clamp(1000) == 100);
+  assert(clamp(42) == 42);
+  assert(fmax2(1.5, 2.5) == 2.5);
+  int i = 0;
+  int s = 0;
+  while (i < 10) {
+    s = s + (i % 2 == 0 ? i : 0);
+    i = i + 1;
+  }
+  assert(s == 20);
+  return 0;
+}
@@ -24,6 +24,7 @@ pub struct FunctionCodeGenerator<'a> {
     builder: IRBuilderWithModuleAndFuncId<'a>,
     compound_types: &'a mut CompoundTypes,
     variables: &'a mut Variables,
+    ret_ty: Type,
 }
 
 pub struct Variables(Vec<FxHashMap<String, Variable>>);
@@ -140,13 +141,17 @@ impl<'a> FunctionCodeGenerator<'a> {
         body: &AST,
     ) -> Result<Value> {
         let func_ty = ty.conv(compound_types, &module.types);
-        let params_ty: Vec<types::Type> = match ty {
-            Type::Func(id) => compound_types[*id]
-                .as_func()
-                .1
-                .iter()
-                .map(|p| p.conv(compound_types, &module.types))
-                .collect(),
+        let (ret_ty, params_ty): (Type, Vec<types::Type>) = match ty {
+            Type::Func(id) => {
+                let (ret, params, _) = compound_types[*id].as_func();
+                (
+                    ret,
+                    params
+                        .iter()
+                        .map(|p| p.conv(compound_types, &module.types))
+                        .collect(),
+                )
+            }
             _ => panic!(),
         };
         let func_id = module.create_function_with_type(name.as_str(), func_ty);
@@ -166,6 +171,7 @@ impl<'a> FunctionCodeGenerator<'a> {
             },
             compound_types,
             variables,
+            ret_ty,
         };
 
         for (i, name) in param_names.iter().enumerate() {
@@ -211,6 +217,9 @@ impl<'a> FunctionCodeGenerator<'a> {
             ast::Kind::Int { n, bits: 32 } => {
                 Ok((Value::new_imm_int32(*n as i32), Type::Int(Sign::Signed)))
             }
+            ast::Kind::Int { n, bits: 64 } => {
+                Ok((Value::new_imm_int64(*n), Type::Long(Sign::Signed)))
+            }
             ast::Kind::Float(f) => Ok((Value::new_imm_f64(*f), Type::Double)),
             ast::Kind::String(s) => self.generate_string(s),
             ast::Kind::Char(c) => Ok((
@@ -512,8 +521,8 @@ impl<'a> FunctionCodeGenerator<'a> {
                     ],
                 );
             } else {
-                let (val, _) = self.generate(val)?;
-                let val = self.do_type_cast(val, sericum_ty)?;
+                let (val, val_ty) = self.generate(val)?;
+                let val = self.do_type_cast(val, val_ty.is_signed(), sericum_ty, ty.is_signed())?;
                 self.builder.build_store(val, alloca);
             }
         }
@@ -523,7 +532,7 @@ impl<'a> FunctionCodeGenerator<'a> {
 
     fn generate_assign(&mut self, dst: &AST, src: &AST) -> Result<(Value, Type)> {
         let (dst, dst_ty) = self.generate(retrieve_from_load(dst))?;
-        let (src, _ty) = self.generate(src)?;
+        let (src, src_ty) = self.generate(src)?;
         let (cast_ty, cast_ty_s) = if let Type::Pointer(id) = dst_ty {
             let inner = self.compound_types[id].as_pointer();
             (
@@ -533,7 +542,7 @@ impl<'a> FunctionCodeGenerator<'a> {
         } else {
             panic!()
         };
-        let src = self.do_type_cast(src, cast_ty_s)?;
+        let src = self.do_type_cast(src, src_ty.is_signed(), cast_ty_s, cast_ty.is_signed())?;
         self.builder.build_store(src, dst);
         Ok((self.builder.build_load(dst), cast_ty))
     }
@@ -566,13 +575,21 @@ impl<'a> FunctionCodeGenerator<'a> {
     }
 
     fn generate_type_cast(&mut self, expr: &AST, to: &Type) -> Result<(Value, Type)> {
-        let (val, _ty) = self.generate(expr)?;
+        let (val, ty) = self.generate(expr)?;
         let to_ = to.conv(self.compound_types, &self.builder.module().unwrap().types);
-        let val = self.do_type_cast(val, to_)?;
+        let val = self.do_type_cast(val, ty.is_signed(), to_, to.is_signed())?;
         Ok((val, *to))
     }
 
-    fn do_type_cast(&mut self, from: Value, to: types::Type) -> Result<Value> {
+    /// Converts `from` to `to`. `signed` and `to_signed` tell whether the source and the
+    /// destination are signed integers.
+    fn do_type_cast(
+        &mut self,
+        from: Value,
+        signed: bool,
+        to: types::Type,
+        to_signed: bool,
+    ) -> Result<Value> {
         use sericum::types::TypeSize;
         let from_ty = self.builder.func_ref().get_value_type(&from);
         let ty_sz = from_ty.size_in_byte(&self.builder.module().unwrap().types);
@@ -582,15 +599,41 @@ impl<'a> FunctionCodeGenerator<'a> {
             return Ok(from);
         }
 
+        if let Some(imm) = cast_int_immediate(&from, to) {
+            return Ok(imm);
+        }
+
+        match (from_ty.is_float(), to.is_float()) {
+            (true, true) if ty_sz < to_sz => return Ok(self.builder.build_fpext(from, to)),
+            (true, true) => return Ok(self.builder.build_fptrunc(from, to)),
+            (false, true) if signed => return Ok(self.builder.build_sitofp(from, to)),
+            (false, true) => return Ok(self.builder.build_uitofp(from, to)),
+            (true, false) if to_signed => return Ok(self.builder.build_fptosi(from, to)),
+            (true, false) => return Ok(self.builder.build_fptoui(from, to)),
+            (false, false) => {}
+        }
+
+        if from_ty.is_pointer() && to.is_integer() {
+            return Ok(self.builder.build_ptrtoint(from, to));
+        }
+
+        if from_ty.is_integer() && to.is_pointer() {
+            return Ok(self.builder.build_inttoptr(from, to));
+        }
+
         if ty_sz == to_sz {
             return Ok(self.builder.build_bitcast(from, to));
         }
 
         if ty_sz < to_sz {
+            // Comparison results (i1) are always 0 or 1
+            if signed && from_ty != types::Type::i1 {
+                return Ok(self.builder.build_sext(from, to));
+            }
             return Ok(self.builder.build_zext(from, to));
         }
 
-        todo!()
+        Ok(self.builder.build_trunc(from, to))
     }
 
     fn generate_unary_op(&mut self, op: ast::UnaryOp, expr: &AST) -> Result<(Value, Type)> {
@@ -625,10 +668,22 @@ impl<'a> FunctionCodeGenerator<'a> {
         };
 
         if matches!(conv_ty, Type::Float | Type::Double) {
+            let ty = conv_ty.conv(self.compound_types, &self.builder.module().unwrap().types);
+            let lhs = self.do_type_cast(lhs, lty.is_signed(), ty, conv_ty.is_signed())?;
+            let rhs = self.do_type_cast(rhs, rty.is_signed(), ty, conv_ty.is_signed())?;
             return self.generate_float_binary_op(conv_ty, op, lhs, rhs);
         }
 
         if conv_ty.is_int() {
+            // Integer promotion: operands narrower than int are computed as int
+            let conv_ty = if conv_ty.priority() < Type::Int(Sign::Signed).priority() {
+                Type::Int(Sign::Signed)
+            } else {
+                conv_ty
+            };
+            let ty = conv_ty.conv(self.compound_types, &self.builder.module().unwrap().types);
+            let lhs = self.do_type_cast(lhs, lty.is_signed(), ty, conv_ty.is_signed())?;
+            let rhs = self.do_type_cast(rhs, rty.is_signed(), ty, conv_ty.is_signed())?;
             return self.generate_int_binary_op(conv_ty, op, lhs, rhs);
         }
 
@@ -737,6 +792,13 @@ impl<'a> FunctionCodeGenerator<'a> {
     ) -> Result<(Value, Type)> {
         let (cond, _) = self.generate(cond)?;
 
+        // Both arms can be evaluated unconditionally.
+        if is_cheap_and_pure(then_) && is_cheap_and_pure(else_) {
+            let (then_, ty) = self.generate(then_)?;
+            let (else_, _) = self.generate(else_)?;
+            return Ok((self.builder.build_select(cond, then_, else_), ty));
+        }
+
         let then_block = self.builder.append_basic_block();
         let else_block = self.builder.append_basic_block();
         let merge_block = self.builder.append_basic_block();
@@ -776,7 +838,7 @@ impl<'a> FunctionCodeGenerator<'a> {
         let f = retrieve_from_load(f);
         let mut args_ = vec![];
         for arg in args {
-            args_.push(self.generate(arg).unwrap().0);
+            args_.push(self.generate(arg).unwrap());
         }
         match &f.kind {
             ast::Kind::Variable(_, name) => {
@@ -784,9 +846,24 @@ impl<'a> FunctionCodeGenerator<'a> {
                     .variables
                     .find_var(name.as_str())
                     .ok_or_else(|| Error::Message(f.loc, "variable not found".to_string()))?;
-                let i = self.compound_types[var.ty].as_pointer();
-                let ret = self.compound_types[i].as_func().0;
-                Ok((self.builder.build_call(var.val, args_), ret))
+                let (val, ty) = (var.val, var.ty);
+                let i = self.compound_types[ty].as_pointer();
+                let (ret, params, _) = self.compound_types[i].as_func();
+                let params = params.clone();
+                // Convert arguments to the types of the corresponding parameters, if any.
+                let mut casted_args = vec![];
+                for (i, (arg, arg_ty)) in args_.into_iter().enumerate() {
+                    casted_args.push(match params.get(i) {
+                        Some(param_ty) if !param_ty.is_compound() && !arg_ty.is_compound() => {
+                            let to_signed = param_ty.is_signed();
+                            let param_ty = param_ty
+                                .conv(self.compound_types, &self.builder.module().unwrap().types);
+                            self.do_type_cast(arg, arg_ty.is_signed(), param_ty, to_signed)?
+                        }
+                        _ => arg,
+                    });
+                }
+                Ok((self.builder.build_call(val, casted_args), ret))
             }
             _ => unimplemented!(),
         }
@@ -794,7 +871,9 @@ impl<'a> FunctionCodeGenerator<'a> {
 
     fn generate_return(&mut self, val: Option<&AST>) -> Result<(Value, Type)> {
         let val = if let Some(val) = val {
-            self.generate(val)?.0
+            let (val, ty) = self.generate(val)?;
+            let ret_ty = self.builder.func_ref().get_return_type();
+            self.do_type_cast(val, ty.is_signed(), ret_ty, self.ret_ty.is_signed())?
         } else {
             Value::None
         };
@@ -844,3 +923,36 @@ pub fn retrieve_from_load<'a>(ast: &'a AST) -> &'a AST {
         _ => ast,
     }
 }
+
+fn cast_int_immediate(val: &Value, to: types::Type) -> Option<Value> {
+    use sericum::ir::value::ImmediateValue;
+    let i = match val {
+        Value::Immediate(ImmediateValue::Int8(i)) => *i as i64,
+        Value::Immediate(ImmediateValue::Int16(i)) => *i as i64,
+        Value::Immediate(ImmediateValue::Int32(i)) => *i as i64,
+        Value::Immediate(ImmediateValue::Int64(i)) => *i,
+        _ => return None,
+    };
+    match to {
+        types::Type::i8 => Some(Value::new_imm_int8(i as i8)),
+        types::Type::i16 => Some(Value::new_imm_int16(i as i16)),
+        types::Type::i32 => Some(Value::new_imm_int32(i as i32)),
+        types::Type::i64 => Some(Value::new_imm_int64(i)),
+        types::Type::f32 => Some(Value::new_imm_f32(i as f32)),
+        types::Type::f64 => Some(Value::new_imm_f64(i as f64)),
+        _ => None,
+    }
+}
+
+/// Returns true if `ast` can be evaluated without branching and has no side effects.
+fn is_cheap_and_pure(ast: &AST) -> bool {
+    match ast.kind {
+        ast::Kind::Int { .. } | ast::Kind::Float(_) | ast::Kind::Char(_) => true,
+        ast::Kind::Load(ref x) => matches!(
+            x.kind,
+            ast::Kind::Variable(ref ty, _)
+                if ty.is_int() || matches!(ty, Type::Float | Type::Double | Type::Pointer(_))
+        ),
+        _ => false,
+    }
+}
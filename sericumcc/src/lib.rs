@@ -3,11 +3,7 @@ extern crate rustc_hash;
 extern crate sericum;
 
 use std::path::PathBuf;
-use std::{
-    fs,
-    io::{BufWriter, Write},
-    process,
-};
+use std::{fs, process};
 use {rand, rand::Rng};
 
 pub mod ast;
@@ -61,7 +57,14 @@ pub fn compile(path: PathBuf, optimization: bool) {
 
     if optimization {
         sericum::ir::mem2reg::Mem2Reg::new().run_on_module(&mut codegen.module);
-        sericum::ir::cse::CommonSubexprElimination::new().run_on_module(&mut codegen.module);
+        sericum::ir::inline::Inliner::new().run_on_module(&mut codegen.module);
+        sericum::ir::sccp::SparseConditionalConstantPropagation::new()
+            .run_on_module(&mut codegen.module);
+        sericum::ir::if_conversion::IfConversion::new().run_on_module(&mut codegen.module);
+        sericum::ir::gvn::GlobalValueNumbering::new().run_on_module(&mut codegen.module);
+        sericum::ir::store_forwarding::StoreToLoadForwarding::new()
+            .run_on_module(&mut codegen.module);
+        sericum::ir::dse::DeadStoreElimination::new().run_on_module(&mut codegen.module);
         sericum::ir::licm::LoopInvariantCodeMotion::new().run_on_module(&mut codegen.module);
     }
     println!("{:?}", codegen.module);
@@ -72,7 +75,12 @@ pub fn compile(path: PathBuf, optimization: bool) {
     printer.run_on_module(&machine_module);
     println!("{}", printer.output);
 
-    assemble_and_run(&printer.output);
+    let obj_name = unique_file_name("o");
+    let mut asmer = sericum::codegen::x64::asm::assembler::Assembler::new(&machine_module);
+    asmer.assemble();
+    asmer.write_to_file(&obj_name);
+
+    link_and_run(&obj_name);
 }
 
 fn unique_file_name(extension: &str) -> String {
@@ -90,22 +98,10 @@ fn unique_file_name(extension: &str) -> String {
     format!("/tmp/{}.{}", name, extension)
 }
 
-fn assemble_and_run(s_target: &str) {
-    let target_name = unique_file_name("s");
-    {
-        let mut target = BufWriter::new(fs::File::create(target_name.as_str()).unwrap());
-        target.write_all(s_target.as_bytes()).unwrap();
-    }
-
+fn link_and_run(obj_name: &str) {
     let output_name = unique_file_name("out");
-    let compilation = process::Command::new("clang")
-        .args(&[
-            target_name.as_str(),
-            "-o",
-            output_name.as_str(),
-            "-lm",
-            "-no-pie",
-        ])
+    let compilation = process::Command::new("cc")
+        .args(&[obj_name, "-o", output_name.as_str(), "-lm", "-no-pie"])
         .status()
         .unwrap();
     assert!(compilation.success());
@@ -121,5 +117,5 @@ fn assemble_and_run(s_target: &str) {
     }
 
     fs::remove_file(output_name).unwrap();
-    fs::remove_file(target_name).unwrap();
+    fs::remove_file(obj_name).unwrap();
 }
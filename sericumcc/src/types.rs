@@ -89,6 +89,17 @@ impl Type {
         matches!(self, Type::Char(_) | Type::Short(_) | Type::Int(_) | Type::Long(_) | Type::LLong(_))
     }
 
+    pub fn is_signed(&self) -> bool {
+        matches!(
+            self,
+            Type::Char(Sign::Signed)
+                | Type::Short(Sign::Signed)
+                | Type::Int(Sign::Signed)
+                | Type::Long(Sign::Signed)
+                | Type::LLong(Sign::Signed)
+        )
+    }
+
     pub fn is_compound(&self) -> bool {
         matches!(self, Type::Pointer(_) | Type::Array(_) | Type::Func(_) | Type::Struct(_) | Type::Union(_))
     }
@@ -245,11 +256,11 @@ impl TypeConversion<types::Type> for Type {
         match self {
             Type::Void => types::Type::Void,
             Type::Char(_) => types::Type::i8,
-            Type::Short(_) => types::Type::i32, // panic!(),
+            Type::Short(_) => types::Type::i16,
             Type::Int(_) => types::Type::i32,
             Type::Long(_) => types::Type::i64,
             Type::LLong(_) => types::Type::i64,
-            Type::Float => panic!(),
+            Type::Float => types::Type::f32,
             Type::Double => types::Type::f64,
             Type::Pointer(id) => {
                 let inner = compound_types[*id].as_pointer();
@@ -1,5 +1,367 @@
-pub use crate::codegen::common::asm::assembler::InstAssembler;
+use crate::codegen::aarch64::common::asm::assembler::{function_symbol, InstructionStream};
+pub use crate::codegen::aarch64::common::asm::assembler::{Assembler, InstAssembler, Offset};
+use crate::codegen::aarch64::common::machine::frame_object::FrameIndexInfo;
+use crate::codegen::aarch64::machine::inst::*;
+use crate::codegen::aarch64::machine::inst_def::Encoding;
+use crate::codegen::aarch64::machine::register::{PhysReg, RegisterClassKind};
+
+/// The target of the object files `Assembler` writes.
+pub const ELF_TRIPLE: &str = "aarch64-unknown-unknown-unknown-elf";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RelocKind {
+    /// 26-bit word displacement of a `bl`, going through the PLT for external functions.
+    Call,
+    /// 32-bit PC-relative address.
+    PcRel32,
+    /// 64-bit absolute address.
+    Abs64,
+}
+
+impl RelocKind {
+    pub fn to_faerie(self, addend: i32) -> faerie::Reloc {
+        // Relocation types of AArch64 ELF
+        let reloc = match self {
+            Self::Call => 283,    // R_AARCH64_CALL26
+            Self::PcRel32 => 261, // R_AARCH64_PREL32
+            Self::Abs64 => 257,   // R_AARCH64_ABS64
+        };
+        faerie::Reloc::Raw { reloc, addend }
+    }
+}
+
+/// Makes the `b`, `bl` or `b.cond` at `at` in `stream` branch to `dst`.
+pub fn patch_branch(stream: &mut InstructionStream, at: usize, dst: usize) {
+    let bytes = &stream.data()[at..at + 4];
+    let word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    let disp = (dst as i32 - at as i32) >> 2;
+    let word = if word & 0x7c000000 == 0x14000000 {
+        assert!((-(1 << 25)..1 << 25).contains(&disp), "branch out of range");
+        word | (disp as u32 & 0x3ffffff)
+    } else {
+        assert!((-(1 << 18)..1 << 18).contains(&disp), "branch out of range");
+        word | (disp as u32 & 0x7ffff) << 5
+    };
+    stream.insert_u32_le(at, word)
+}
 
 impl<'a> InstAssembler<'a> {
-    pub fn assemble(&mut self) {}
+    pub fn assemble(&mut self) {
+        match self.inst.opcode.encoding() {
+            Some(encoding) => self.gen(encoding),
+            None => unimplemented!("{:?}", self.inst.opcode),
+        }
+    }
+
+    fn gen(&mut self, encoding: Encoding) {
+        match encoding {
+            Encoding::Raw(word) => self.emit(word),
+            Encoding::Rr(word) => {
+                let (d, n) = (self.def(0), self.use_(0));
+                self.emit(word | n << 5 | d)
+            }
+            Encoding::Rrr(word) => {
+                let (d, n, m) = (self.def(0), self.use_(0), self.use_(1));
+                self.emit(word | m << 16 | n << 5 | d)
+            }
+            Encoding::Rrrr(word) => {
+                let (d, n, m, a) = (self.def(0), self.use_(0), self.use_(1), self.use_(2));
+                self.emit(word | m << 16 | a << 10 | n << 5 | d)
+            }
+            Encoding::AddSub(sf, sub) => self.gen_add_sub(sf, sub),
+            Encoding::ShiftImm(word, lsl) => self.gen_shift_imm(word, lsl),
+            Encoding::MovImm(sf) => {
+                let imm = const_value(self.inst.operand[0].as_constant());
+                self.gen_mov_imm(sf, self.def(0), imm)
+            }
+            Encoding::Mov => self.gen_mov(),
+            Encoding::Cvt(word) => {
+                let d = self.inst.def[0].id.as_phys_reg();
+                let n = self.inst.operand[0].as_register().id.as_phys_reg();
+                let sf = is_64bit(&d) || is_64bit(&n);
+                let double = d.reg_class() == RegisterClassKind::F64
+                    || n.reg_class() == RegisterClassKind::F64;
+                let word = word | sf_bit(sf) | (double as u32) << 22;
+                self.emit(word | reg_code(&n) << 5 | reg_code(&d))
+            }
+            Encoding::Cmp => self.gen_cmp(),
+            Encoding::Csel(cond) => {
+                let sf = is_64bit(&self.inst.def[0].id.as_phys_reg());
+                let (d, n, m) = (self.def(0), self.use_(0), self.use_(1));
+                self.emit(sf_bit(sf) | 0x1a800000 | m << 16 | cond << 12 | n << 5 | d)
+            }
+            Encoding::BCond(cond) => self.gen_branch(0x54000000 | cond),
+            Encoding::B => self.gen_branch(0x14000000),
+            Encoding::Call => self.gen_call(),
+            Encoding::Ldr(word) => {
+                let t = self.def(0);
+                self.gen_mem(word, t, self.inst.operand[0].as_mem())
+            }
+            Encoding::Str => {
+                let src = self.inst.operand[0].as_register().id.as_phys_reg();
+                let word = match src.reg_class() {
+                    RegisterClassKind::GR16 => 0x79000000,
+                    RegisterClassKind::F32 => 0xbd000000,
+                    RegisterClassKind::F64 => 0xfd000000,
+                    _ if is_64bit(&src) => 0xf9000000,
+                    _ => 0xb9000000,
+                };
+                self.gen_mem(word, reg_code(&src), self.inst.operand[1].as_mem())
+            }
+            Encoding::Pair(load) => self.gen_pair(load),
+        }
+    }
+
+    /// `add`/`sub` with an immediate, or with a register. A negative immediate flips the
+    /// operation.
+    fn gen_add_sub(&mut self, sf: bool, sub: bool) {
+        let d = self.inst.def[0].id.as_phys_reg();
+        let n = self.inst.operand[0].as_register().id.as_phys_reg();
+        let imm = match &self.inst.operand[1] {
+            MachineOperand::Constant(c) => const_value(c),
+            MachineOperand::FrameIndex(fi) => self.frame_offset(fi) as i64,
+            MachineOperand::Register(m) => {
+                let m = reg_code(&m.id.as_phys_reg());
+                let word = if is_sp(&d) || is_sp(&n) {
+                    // Extended register form, where register 31 is sp. uxtx or uxtw
+                    let option = if sf { 0b011 } else { 0b010 };
+                    0x0b200000 | option << 13
+                } else {
+                    // Shifted register form, where register 31 is zr
+                    0x0b000000
+                };
+                let word = word | sf_bit(sf) | (sub as u32) << 30;
+                return self.emit(word | m << 16 | reg_code(&n) << 5 | reg_code(&d));
+            }
+            e => unimplemented!("{:?}", e),
+        };
+        let (sub, imm) = if imm < 0 { (!sub, -imm) } else { (sub, imm) };
+        let (shift, imm) = if imm >= 1 << 12 && imm & 0xfff == 0 {
+            (1, imm >> 12)
+        } else {
+            (0, imm)
+        };
+        assert!(imm < 1 << 12, "immediate out of range");
+        self.emit(
+            sf_bit(sf)
+                | (sub as u32) << 30
+                | 0x11000000
+                | shift << 22
+                | (imm as u32) << 10
+                | reg_code(&n) << 5
+                | reg_code(&d),
+        )
+    }
+
+    fn gen_shift_imm(&mut self, word: u32, lsl: bool) {
+        let size = if word & sf_bit(true) != 0 { 64 } else { 32 };
+        let amount = const_value(self.inst.operand[1].as_constant()) as u32 % size;
+        let (immr, imms) = if lsl {
+            ((size - amount) % size, size - 1 - amount)
+        } else {
+            (amount, size - 1)
+        };
+        let (d, n) = (self.def(0), self.use_(0));
+        self.emit(word | immr << 16 | imms << 10 | n << 5 | d)
+    }
+
+    /// Sets `imm` to `d` with `movz` or `movn`, whichever leaves fewer halfwords to `movk`.
+    fn gen_mov_imm(&mut self, sf: bool, d: u32, imm: i64) {
+        let halfwords = if sf { 4 } else { 2 };
+        let imm = if sf { imm as u64 } else { imm as u32 as u64 };
+        let halfword = |i: u32| (imm >> (i * 16)) as u32 & 0xffff;
+        let ones = (0..halfwords).filter(|&i| halfword(i) == 0xffff).count();
+        let zeros = (0..halfwords).filter(|&i| halfword(i) == 0).count();
+        let (movn, filler) = (ones > zeros, if ones > zeros { 0xffff } else { 0 });
+
+        let mut rest = (0..halfwords).filter(|&i| halfword(i) != filler);
+        let first = rest.next().unwrap_or(0);
+        let (word, first_imm) = if movn {
+            (0x12800000, !halfword(first) & 0xffff)
+        } else {
+            (0x52800000, halfword(first))
+        };
+        self.emit(sf_bit(sf) | word | first << 21 | first_imm << 5 | d);
+        for i in rest {
+            self.emit(sf_bit(sf) | 0x72800000 | i << 21 | halfword(i) << 5 | d) // movk
+        }
+    }
+
+    fn gen_mov(&mut self) {
+        let d = self.inst.def[0].id.as_phys_reg();
+        let n = self.inst.operand[0].as_register().id.as_phys_reg();
+        let sf = sf_bit(is_64bit(&d));
+        if is_sp(&d) || is_sp(&n) {
+            // add d, n, #0
+            self.emit(sf | 0x11000000 | reg_code(&n) << 5 | reg_code(&d))
+        } else {
+            // orr d, zr, n
+            self.emit(sf | 0x2a0003e0 | reg_code(&n) << 16 | reg_code(&d))
+        }
+    }
+
+    /// `cmp` is `subs zr, ...`, and `cmp` with a negative immediate is `cmn`, i.e. `adds`.
+    fn gen_cmp(&mut self) {
+        let n = self.inst.operand[0].as_register().id.as_phys_reg();
+        let sf = sf_bit(is_64bit(&n));
+        let n = reg_code(&n);
+        match &self.inst.operand[1] {
+            MachineOperand::Register(m) => {
+                let m = reg_code(&m.id.as_phys_reg());
+                self.emit(sf | 0x6b00001f | m << 16 | n << 5)
+            }
+            MachineOperand::Constant(c) => {
+                let imm = const_value(c);
+                let (word, imm) = if imm < 0 {
+                    (0x3100001f, -imm)
+                } else {
+                    (0x7100001f, imm)
+                };
+                assert!(imm < 1 << 12, "immediate out of range");
+                self.emit(sf | word | (imm as u32) << 10 | n << 5)
+            }
+            e => unimplemented!("{:?}", e),
+        }
+    }
+
+    /// Emits `word` branching to a basic block, whose displacement is filled in later.
+    fn gen_branch(&mut self, word: u32) {
+        let func = self.function.id.unwrap();
+        let label = self
+            .labels
+            .get_label_for(func, self.inst.operand[0].as_basic_block());
+        let at = Offset(func, self.stream.data().len());
+        self.labels.add_disp32_to_replace(at, label);
+        self.emit(word)
+    }
+
+    fn gen_call(&mut self) {
+        let name = match &self.inst.operand[0] {
+            MachineOperand::Mem(MachineMemOperand::Address(AddressKind::FunctionName(n))) => n,
+            MachineOperand::Register(r) => {
+                let r = reg_code(&r.id.as_phys_reg());
+                return self.emit(0xd63f0000 | r << 5); // blr
+            }
+            e => unimplemented!("{:?}", e),
+        };
+
+        let callee = self
+            .module
+            .function_ref(self.module.find_function_by_name(name).unwrap());
+        let symbol = if callee.is_internal || callee.is_empty() {
+            function_symbol(name)
+        } else {
+            name.to_owned()
+        };
+        let at = Offset(self.function.id.unwrap(), self.stream.data().len());
+        self.labels.add_reloc(at, symbol, RelocKind::Call, 0);
+        self.emit(0x94000000) // bl
+    }
+
+    /// `ldr`/`str` of the register `t`. `word` is the unsigned offset form, scaled by the access
+    /// size, and the unscaled form (`ldur`/`stur`) is used for offsets it can't encode.
+    fn gen_mem(&mut self, word: u32, t: u32, mem: &MachineMemOperand) {
+        let (base, offset) = self.base_offset(mem);
+        let size = 1 << (word >> 30);
+        if offset >= 0 && offset % size == 0 && offset / size < 1 << 12 {
+            self.emit(word | ((offset / size) as u32) << 10 | base << 5 | t)
+        } else {
+            assert!((-256..256).contains(&offset), "offset out of range");
+            let word = word - 0x01000000;
+            self.emit(word | (offset as u32 & 0x1ff) << 12 | base << 5 | t)
+        }
+    }
+
+    /// `ldp`/`stp` of two 64-bit registers, with pre-index, post-index or offset addressing.
+    fn gen_pair(&mut self, load: bool) {
+        let (t1, t2, mem) = if load {
+            (self.def(0), self.def(1), self.inst.operand[0].as_mem())
+        } else {
+            (self.use_(0), self.use_(1), self.inst.operand[2].as_mem())
+        };
+        let (word, base, offset) = match mem {
+            MachineMemOperand::PreIndex(r, off) => {
+                (0xa9800000, reg_code(&r.id.as_phys_reg()), *off)
+            }
+            MachineMemOperand::PostIndex(r, off) => {
+                (0xa8800000, reg_code(&r.id.as_phys_reg()), *off)
+            }
+            mem => {
+                let (base, offset) = self.base_offset(mem);
+                (0xa9000000, base, offset)
+            }
+        };
+        assert!(
+            offset % 8 == 0 && (-512..512).contains(&offset),
+            "offset out of range"
+        );
+        let imm7 = (offset / 8) as u32 & 0x7f;
+        self.emit(word | (load as u32) << 22 | imm7 << 15 | t2 << 10 | base << 5 | t1)
+    }
+
+    fn base_offset(&self, mem: &MachineMemOperand) -> (u32, i32) {
+        match mem {
+            MachineMemOperand::Reg(r) => (reg_code(&r.id.as_phys_reg()), 0),
+            MachineMemOperand::RegFi(r, fi) => {
+                (reg_code(&r.id.as_phys_reg()), self.frame_offset(fi))
+            }
+            e => unimplemented!("{:?}", e),
+        }
+    }
+
+    fn frame_offset(&self, fi: &FrameIndexInfo) -> i32 {
+        let frame_objects = self.function.frame_objects.as_ref().unwrap();
+        frame_objects.offset(fi.idx).unwrap()
+    }
+
+    /// The code of the `i`-th register defined.
+    fn def(&self, i: usize) -> u32 {
+        reg_code(&self.inst.def[i].id.as_phys_reg())
+    }
+
+    /// The code of the `i`-th operand, which is a register.
+    fn use_(&self, i: usize) -> u32 {
+        reg_code(&self.inst.operand[i].as_register().id.as_phys_reg())
+    }
+
+    fn emit(&mut self, word: u32) {
+        self.stream.push_u32_le(word)
+    }
+}
+
+/// The 5-bit register field. `sp` shares 31 with the zero register, which an instruction
+/// tells apart by itself.
+pub fn reg_code(r: &PhysReg) -> u32 {
+    match r.reg_class() {
+        RegisterClassKind::SP | RegisterClassKind::WSP => 31,
+        _ => (r.retrieve() - r.reg_class() as usize) as u32,
+    }
+}
+
+fn is_sp(r: &PhysReg) -> bool {
+    matches!(
+        r.reg_class(),
+        RegisterClassKind::SP | RegisterClassKind::WSP
+    )
+}
+
+fn is_64bit(r: &PhysReg) -> bool {
+    matches!(
+        r.reg_class(),
+        RegisterClassKind::GR64 | RegisterClassKind::SP
+    )
+}
+
+fn sf_bit(sf: bool) -> u32 {
+    (sf as u32) << 31
+}
+
+fn const_value(c: &MachineConstant) -> i64 {
+    match c {
+        MachineConstant::Int8(i) => *i as i64,
+        MachineConstant::Int16(i) => *i as i64,
+        MachineConstant::Int32(i) => *i as i64,
+        MachineConstant::Int64(i) => *i,
+        e => unimplemented!("{:?}", e),
+    }
 }
@@ -1,6 +1,6 @@
 use super::super::frame_object::FrameObjectsInfo;
 use super::super::machine::inst::*;
-use crate::codegen::common::machine::{
+use crate::codegen::aarch64::common::machine::{
     basic_block::MachineBasicBlockId,
     function::{InstIter, MachineFunction},
     module::MachineModule,
@@ -40,7 +40,7 @@ impl<'s> MachineAsmPrinter<'s> {
     }
 
     fn run_on_function(&mut self, f: &MachineFunction) {
-        if f.is_internal {
+        if f.is_internal || f.is_empty() {
             return;
         }
 
@@ -84,6 +84,10 @@ impl<'s> MachineAsmPrinter<'s> {
         inst: &MachineInst,
         fo: &FrameObjectsInfo,
     ) {
+        if matches!(inst.opcode, MachineOpcode::MOVr32i | MachineOpcode::MOVr64i) {
+            return self.mov_imm(inst);
+        }
+
         self.output.push_str("  ");
 
         self.output.push_str(inst.opcode.inst_def().unwrap().name);
@@ -107,9 +111,40 @@ impl<'s> MachineAsmPrinter<'s> {
             }
         }
 
+        if let Some(cond) = csel_cond(inst.opcode) {
+            self.output.push_str(", ");
+            self.output.push_str(cond);
+        }
+
         self.output.push('\n');
     }
 
+    /// `mov` takes only an immediate `movz` or `movn` can set at once, so a wider one is set
+    /// with `movz` and `movk` by halfwords.
+    fn mov_imm(&mut self, inst: &MachineInst) {
+        let d = inst.def[0].id.as_phys_reg();
+        let d = d.name();
+        let (imm, halfwords) = match inst.operand[0].as_constant() {
+            MachineConstant::Int8(i) => (*i as i64, 2),
+            MachineConstant::Int16(i) => (*i as i64, 2),
+            MachineConstant::Int32(i) => (*i as i64, 2),
+            MachineConstant::Int64(i) => (*i, 4),
+            e => unimplemented!("{:?}", e),
+        };
+        if (-(1 << 16)..1 << 16).contains(&imm) {
+            self.output
+                .push_str(format!("  mov {}, {}\n", d, imm).as_str());
+            return;
+        }
+        let halfword = |i: u32| (imm as u64 >> (i * 16)) & 0xffff;
+        self.output
+            .push_str(format!("  movz {}, {}\n", d, halfword(0)).as_str());
+        for i in (1..halfwords).filter(|&i| halfword(i) != 0) {
+            self.output
+                .push_str(format!("  movk {}, {}, lsl {}\n", d, halfword(i), i * 16).as_str());
+        }
+    }
+
     fn bb_id_to_label_id(&self, bb_id: &MachineBasicBlockId) -> String {
         format!(".L{}", bb_id.index() + self.cur_bb_id_base)
     }
@@ -123,6 +158,12 @@ impl<'s> MachineAsmPrinter<'s> {
             MachineOperand::Constant(MachineConstant::Int8(i)) => {
                 self.output.push_str(format!("{}", i).as_str())
             }
+            MachineOperand::Constant(MachineConstant::Int16(i)) => {
+                self.output.push_str(format!("{}", i).as_str())
+            }
+            MachineOperand::Constant(MachineConstant::Int64(i)) => {
+                self.output.push_str(format!("{}", i).as_str())
+            }
             MachineOperand::Register(r) => self.output.push_str(r.id.as_phys_reg().name()),
             MachineOperand::FrameIndex(i) => self
                 .output
@@ -154,3 +195,19 @@ impl<'s> MachineAsmPrinter<'s> {
         };
     }
 }
+
+fn csel_cond(opcode: MachineOpcode) -> Option<&'static str> {
+    match opcode {
+        MachineOpcode::CSEL_EQ => Some("eq"),
+        MachineOpcode::CSEL_NE => Some("ne"),
+        MachineOpcode::CSEL_LT => Some("lt"),
+        MachineOpcode::CSEL_LE => Some("le"),
+        MachineOpcode::CSEL_GT => Some("gt"),
+        MachineOpcode::CSEL_GE => Some("ge"),
+        MachineOpcode::CSEL_LO => Some("lo"),
+        MachineOpcode::CSEL_LS => Some("ls"),
+        MachineOpcode::CSEL_HI => Some("hi"),
+        MachineOpcode::CSEL_HS => Some("hs"),
+        _ => None,
+    }
+}
@@ -1,12 +1,12 @@
 use crate::codegen::{
-    arch::machine::{abi::AAPCS64, register::*},
-    common::{
-        machine::calling_conv::{ArgumentRegisterOrder, CallingConv},
+    aarch64::common::{
         dag::{
             convert::BlockConversionContext,
             node::{IRNode, IROpcode},
         },
+        machine::calling_conv::{ArgumentRegisterOrder, CallingConv},
     },
+    aarch64::machine::{abi::AAPCS64, register::*},
 };
 
 pub fn copy_reg_args<'a>(ctx: &mut BlockConversionContext<'a>) {
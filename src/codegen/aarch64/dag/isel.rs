@@ -1,17 +1,18 @@
-use crate::codegen::arch::machine::register::{RegisterClassKind as RC, GR64};
-use crate::codegen::arch::{dag::node::MemKind, machine::inst::MachineOpcode as MO};
-use crate::codegen::common::{
+use crate::codegen::aarch64::common::{
     dag::{
         function::DAGFunction,
         module::DAGModule,
         node::{IRNode, IROpcode, ImmediateKind, MINode, Node, NodeId, OperandNode},
         pat_match::{
-            any, any_block, any_f64_imm, any_i32_imm, any_i8_imm, any_slot, inst_select, ir,
-            reg_class, slot, CompoundPat, MatchContext, Pat, ReplacedNodeMap,
+            any, any_block, any_f32_imm, any_f64_imm, any_i16_imm, any_i32_imm, any_i64_imm,
+            any_i8_imm, any_reg, any_slot, inst_select, ir, reg_class, slot, CompoundPat,
+            MatchContext, Pat, ReplacedNodeMap,
         },
     },
     types::MVType,
 };
+use crate::codegen::aarch64::machine::register::{rc2ty, ty2rc, RegisterClassKind as RC, GR64};
+use crate::codegen::aarch64::{dag::node::MemKind, machine::inst::MachineOpcode as MO};
 use crate::ir::types::Type;
 
 pub fn run(module: &mut DAGModule) {
@@ -42,6 +43,12 @@ fn run_on_function(func: &mut DAGFunction) {
                                                 let x29 = c.arena.alloc(c.regs.get_phys_reg(GR64::X29).into());
                                                 let mem = c.arena.alloc(OperandNode::Mem(MemKind::RegFi([x29, m["dst"]])).into());
                                                 c.arena.alloc(MINode::new(MO::STR).args(vec![m["src"], mem]).into()) }).into()
+                      | ir(IROpcode::Store).args(vec![ir(IROpcode::FIAddr).args(vec![slot(MVType::i64).named("dst").into()]).into(), any_i64_imm().named("src").into()])
+                                           .generate(|m, c| {
+                                                let x29 = c.arena.alloc(c.regs.get_phys_reg(GR64::X29).into());
+                                                let mem = c.arena.alloc(OperandNode::Mem(MemKind::RegFi([x29, m["dst"]])).into());
+                                                let src = imm_reg(c, m["src"], MVType::i64);
+                                                c.arena.alloc(MINode::new(MO::STR).args(vec![src, mem]).into()) }).into()
                       | ir(IROpcode::Store).args(vec![reg_class(RC::GR64).named("dst").into(), any_i32_imm().named("src").into()])
                                            .generate(|m, c| {
                                                 let mem = c.arena.alloc(OperandNode::Mem(MemKind::Reg(m["dst"])).into());
@@ -51,12 +58,53 @@ fn run_on_function(func: &mut DAGFunction) {
                                            .generate(|m, c| {
                                                 let mem = c.arena.alloc(OperandNode::Mem(MemKind::Reg(m["dst"])).into());
                                                 c.arena.alloc(MINode::new(MO::STR).args(vec![m["src"], mem]).into()) }).into()
+                      | ir(IROpcode::Store).args(vec![reg_class(RC::GR64).named("dst").into(), any_i64_imm().named("src").into()])
+                                           .generate(|m, c| {
+                                                let mem = c.arena.alloc(OperandNode::Mem(MemKind::Reg(m["dst"])).into());
+                                                let src = imm_reg(c, m["src"], MVType::i64);
+                                                c.arena.alloc(MINode::new(MO::STR).args(vec![src, mem]).into()) }).into()
                       | ir(IROpcode::Store).args(vec![reg_class(RC::GR64).named("dst").into(), reg_class(RC::GR64).named("src").into()])
                                            .generate(|m, c| {
                                                 let mem = c.arena.alloc(OperandNode::Mem(MemKind::Reg(m["dst"])).into());
                                                 c.arena.alloc(MINode::new(MO::STR).args(vec![m["src"], mem]).into()) }).into()
                     ).into();
 
+    let fstore: Pat = ir(IROpcode::Store)
+        .args(vec![
+            (ir(IROpcode::FIAddr)
+                .args(vec![any_slot().named("dst")])
+                .into(): CompoundPat
+                | reg_class(RC::GR64).named("dst").into())
+            .into(),
+            ((reg_class(RC::F32) | reg_class(RC::F64)) | (any_f32_imm() | any_f64_imm()))
+                .named("src")
+                .into(),
+        ])
+        .generate(|m, c| {
+            let mem = mem(c, m["dst"]);
+            let src = fp_reg(c, m["src"]);
+            c.arena
+                .alloc(MINode::new(MO::STR).args(vec![src, mem]).into())
+        })
+        .into();
+
+    let store16: Pat = ir(IROpcode::Store)
+        .args(vec![
+            (ir(IROpcode::FIAddr)
+                .args(vec![any_slot().named("dst")])
+                .into(): CompoundPat
+                | reg_class(RC::GR64).named("dst").into())
+            .into(),
+            (reg_class(RC::GR16) | any_i16_imm()).named("src").into(),
+        ])
+        .generate(|m, c| {
+            let mem = mem(c, m["dst"]);
+            let src = imm_reg(c, m["src"], MVType::i16);
+            c.arena
+                .alloc(MINode::new(MO::STRH).args(vec![src, mem]).into())
+        })
+        .into();
+
     // TODO: Support GlobalAddr
     let load: Pat = ir(IROpcode::Load)
         .named("load")
@@ -81,11 +129,11 @@ fn run_on_function(func: &mut DAGFunction) {
             let opcode = match c.arena[m["load"]].as_ir().mvty {
                 MVType::Void | MVType::Invalid => panic!(),
                 MVType::i1 | MVType::i8 => panic!(),
-                MVType::i16 => panic!(),
+                MVType::i16 => MO::LDRSH32,
                 MVType::i32 => MO::LDR32,
                 MVType::i64 => MO::LDR64,
-                MVType::f64 => panic!(),
-                MVType::f32 => panic!(),
+                MVType::f64 => MO::FLDR64,
+                MVType::f32 => MO::FLDR32,
             };
             c.arena.alloc(
                 MINode::new(opcode)
@@ -123,27 +171,15 @@ fn run_on_function(func: &mut DAGFunction) {
     #[rustfmt::skip]
     let addsub: Pat = {
         let add32 = ir(IROpcode::Add).named("bin").ty(Type::i32).args(vec![                 reg_class(RC::GR32) .named("lhs").into(), (reg_class(RC::GR32) | any_i32_imm()).named("rhs").into()]);
-        let add64 = ir(IROpcode::Add).named("bin").ty(Type::i64).args(vec![                 reg_class(RC::GR64) .named("lhs").into(), (reg_class(RC::GR64) | any_i32_imm()).named("rhs").into()]);
+        let add64 = ir(IROpcode::Add).named("bin").ty(Type::i64).args(vec![                 reg_class(RC::GR64) .named("lhs").into(), (reg_class(RC::GR64) | any_i32_imm() | any_i64_imm().into()).named("rhs").into()]);
         let sub32 = ir(IROpcode::Sub).named("bin").ty(Type::i32).args(vec![(any_i32_imm() | reg_class(RC::GR32)).named("lhs").into(), (reg_class(RC::GR32) | any_i32_imm()).named("rhs").into()]);
-        let sub64 = ir(IROpcode::Sub).named("bin").ty(Type::i64).args(vec![                 reg_class(RC::GR64) .named("lhs").into(), (reg_class(RC::GR64) | any_i32_imm()).named("rhs").into()]);
+        let sub64 = ir(IROpcode::Sub).named("bin").ty(Type::i64).args(vec![(any_i32_imm() | any_i64_imm() | reg_class(RC::GR64).into()).named("lhs").into(), (reg_class(RC::GR64) | any_i32_imm() | any_i64_imm().into()).named("rhs").into()]);
         (((add32 | add64)) | (sub32 | sub64)).generate(|m, c| {
             let ty = c.arena[m["bin"]].as_ir().mvty;
-            let lhs = match c.arena[m["lhs"]] {
-                // Node::Operand(OperandNode::Imm(ImmediateKind::Int8(_))) => 
-                //     c.arena.alloc(MINode::new(MO::MOVri8).args(vec![m["lhs"]]).reg_class(RC::GR8).into()),
-                Node::Operand(OperandNode::Imm(ImmediateKind::Int32(_))) if ty == MVType::i32 => 
-                    c.arena.alloc(MINode::new(MO::MOVr32i).args(vec![m["lhs"]]).reg_class(RC::GR32).into()),
-                Node::Operand(OperandNode::Imm(ImmediateKind::Int32(_))) if ty == MVType::i64 => 
-                    c.arena.alloc(MINode::new(MO::MOVr64i).args(vec![m["lhs"]]).reg_class(RC::GR64).into()),
-                _ => m["lhs"]
-            };
+            let lhs = imm_reg(c, m["lhs"], ty);
             let rhs = match c.arena[m["rhs"]] {
                 Node::Operand(OperandNode::Imm(i)) if i.bits_within(12).unwrap() => m["rhs"],
-                Node::Operand(OperandNode::Imm(ImmediateKind::Int32(_))) => 
-                    c.arena.alloc(MINode::new(MO::MOVr32i).args(vec![m["rhs"]]).reg_class(RC::GR32).into()),
-                Node::Operand(OperandNode::Imm(ImmediateKind::Int32(_))) if ty == MVType::i64 => 
-                    c.arena.alloc(MINode::new(MO::MOVr64i).args(vec![m["rhs"]]).reg_class(RC::GR64).into()),
-                _ => m["rhs"]
+                _ => imm_reg(c, m["rhs"], ty),
             };
             let rhs_ = &c.arena[rhs];
             let opcode = match c.arena[m["bin"]].as_ir().opcode {
@@ -153,6 +189,8 @@ fn run_on_function(func: &mut DAGFunction) {
                 IROpcode::Add if matches!(ty, MVType::i64)                                                      => MO::ADDrrr64,
                 IROpcode::Sub if matches!(ty, MVType::i32) && matches!(rhs_, Node::Operand(OperandNode::Imm(i)) if i.bits_within(12).unwrap()) => MO::SUBrr32i,
                 IROpcode::Sub if matches!(ty, MVType::i32)                                                      => MO::SUBrrr32,
+                IROpcode::Sub if matches!(ty, MVType::i64) && matches!(rhs_, Node::Operand(OperandNode::Imm(i)) if i.bits_within(12).unwrap()) => MO::SUBrr64i,
+                IROpcode::Sub if matches!(ty, MVType::i64)                                                      => MO::SUBrrr64,
                 _ => panic!() 
             };
             c.arena.alloc(MINode::new(opcode).args(vec![
@@ -163,57 +201,136 @@ fn run_on_function(func: &mut DAGFunction) {
     #[rustfmt::skip]
     let muldiv: Pat = {
         let mul32 = ir(IROpcode::Mul).named("bin").ty(Type::i32).args(vec![                 reg_class(RC::GR32) .named("lhs").into(), (reg_class(RC::GR32) | any_i32_imm()).named("rhs").into()]);
-        let mul64 = ir(IROpcode::Mul).named("bin").ty(Type::i64).args(vec![                 reg_class(RC::GR64) .named("lhs").into(), (reg_class(RC::GR64) | any_i32_imm()).named("rhs").into()]);
-        let shl32 = ir(IROpcode::Div).named("bin").ty(Type::i32).args(vec![(any_i32_imm() | reg_class(RC::GR32)).named("lhs").into(), (reg_class(RC::GR32) | any_i32_imm()).named("rhs").into()]);
-        let shl64 = ir(IROpcode::Div).named("bin").ty(Type::i64).args(vec![(any_i32_imm() | reg_class(RC::GR64)).named("lhs").into(), (reg_class(RC::GR64) | any_i32_imm()).named("rhs").into()]);
-        ((mul32 | mul64) | (shl32 | shl64)).generate(|m, c| {
+        let mul64 = ir(IROpcode::Mul).named("bin").ty(Type::i64).args(vec![                 reg_class(RC::GR64) .named("lhs").into(), (reg_class(RC::GR64) | any_i32_imm() | any_i64_imm().into()).named("rhs").into()]);
+        let div32  = ir(IROpcode::Div) .named("bin").ty(Type::i32).args(vec![(any_i32_imm() | reg_class(RC::GR32)).named("lhs").into(), (reg_class(RC::GR32) | any_i32_imm()).named("rhs").into()]);
+        let div64  = ir(IROpcode::Div) .named("bin").ty(Type::i64).args(vec![(any_i32_imm() | any_i64_imm() | reg_class(RC::GR64).into()).named("lhs").into(), (reg_class(RC::GR64) | any_i32_imm() | any_i64_imm().into()).named("rhs").into()]);
+        let rem32  = ir(IROpcode::Rem) .named("bin").ty(Type::i32).args(vec![(any_i32_imm() | reg_class(RC::GR32)).named("lhs").into(), (reg_class(RC::GR32) | any_i32_imm()).named("rhs").into()]);
+        let rem64  = ir(IROpcode::Rem) .named("bin").ty(Type::i64).args(vec![(any_i32_imm() | any_i64_imm() | reg_class(RC::GR64).into()).named("lhs").into(), (reg_class(RC::GR64) | any_i32_imm() | any_i64_imm().into()).named("rhs").into()]);
+        let udiv32 = ir(IROpcode::UDiv).named("bin").ty(Type::i32).args(vec![(any_i32_imm() | reg_class(RC::GR32)).named("lhs").into(), (reg_class(RC::GR32) | any_i32_imm()).named("rhs").into()]);
+        let udiv64 = ir(IROpcode::UDiv).named("bin").ty(Type::i64).args(vec![(any_i32_imm() | any_i64_imm() | reg_class(RC::GR64).into()).named("lhs").into(), (reg_class(RC::GR64) | any_i32_imm() | any_i64_imm().into()).named("rhs").into()]);
+        let urem32 = ir(IROpcode::URem).named("bin").ty(Type::i32).args(vec![(any_i32_imm() | reg_class(RC::GR32)).named("lhs").into(), (reg_class(RC::GR32) | any_i32_imm()).named("rhs").into()]);
+        let urem64 = ir(IROpcode::URem).named("bin").ty(Type::i64).args(vec![(any_i32_imm() | any_i64_imm() | reg_class(RC::GR64).into()).named("lhs").into(), (reg_class(RC::GR64) | any_i32_imm() | any_i64_imm().into()).named("rhs").into()]);
+        ((mul32 | mul64) | (div32 | div64) | (rem32 | rem64) | (udiv32 | udiv64) | (urem32 | urem64)).generate(|m, c| {
             let ty = c.arena[m["bin"]].as_ir().mvty;
-            let lhs = match c.arena[m["lhs"]] {
-                Node::Operand(OperandNode::Imm(ImmediateKind::Int32(_))) if ty == MVType::i32 => 
-                    c.arena.alloc(MINode::new(MO::MOVr32i).args(vec![m["lhs"]]).reg_class(RC::GR32).into()),
-                Node::Operand(OperandNode::Imm(ImmediateKind::Int32(_))) if ty == MVType::i64 => 
-                    c.arena.alloc(MINode::new(MO::MOVr64i).args(vec![m["lhs"]]).reg_class(RC::GR64).into()),
-                _ => m["lhs"]
-            };
-            let rhs = match c.arena[m["rhs"]] {
-                Node::Operand(OperandNode::Imm(ImmediateKind::Int32(_))) if ty == MVType::i32 => 
-                    c.arena.alloc(MINode::new(MO::MOVr32i).args(vec![m["rhs"]]).reg_class(RC::GR32).into()),
-                Node::Operand(OperandNode::Imm(ImmediateKind::Int32(_))) if ty == MVType::i64 => 
-                    c.arena.alloc(MINode::new(MO::MOVr64i).args(vec![m["rhs"]]).reg_class(RC::GR64).into()),
-                _ => m["rhs"]
-            };
+            let lhs = imm_reg(c, m["lhs"], ty);
+            let rhs = imm_reg(c, m["rhs"], ty);
             let opcode = match c.arena[m["bin"]].as_ir().opcode {
                 IROpcode::Mul if matches!(ty, MVType::i32) => MO::MULrrr32,
                 IROpcode::Mul if matches!(ty, MVType::i64) => MO::MULrrr64,
-                IROpcode::Div if matches!(ty, MVType::i32) => MO::SDIVrrr32,
+                IROpcode::Div | IROpcode::Rem if matches!(ty, MVType::i32) => MO::SDIVrrr32,
+                IROpcode::Div | IROpcode::Rem if matches!(ty, MVType::i64) => MO::SDIVrrr64,
+                IROpcode::UDiv | IROpcode::URem if matches!(ty, MVType::i32) => MO::UDIVrrr32,
+                IROpcode::UDiv | IROpcode::URem if matches!(ty, MVType::i64) => MO::UDIVrrr64,
                 _ => panic!() 
             };
-            c.arena.alloc(MINode::new(opcode).args(vec![
+            let quo = c.arena.alloc(MINode::new(opcode).args(vec![
                 lhs, rhs
+            ]).reg_class(opcode.inst_def().unwrap().def_reg_class()).into());
+            if !matches!(c.arena[m["bin"]].as_ir().opcode, IROpcode::Rem | IROpcode::URem) {
+                return quo
+            }
+            // There is no remainder instruction: lhs - (lhs / rhs) * rhs
+            let opcode = if ty == MVType::i32 { MO::MSUBrrrr32 } else { MO::MSUBrrrr64 };
+            c.arena.alloc(MINode::new(opcode).args(vec![
+                quo, rhs, lhs
             ]).reg_class(opcode.inst_def().unwrap().def_reg_class()).into())
         })
     }.into();
-    // #[rustfmt::skip]
-    // let fbin: Pat = {
-    //     let add64 = ir(IROpcode::Add).named("bin").ty(Type::f64).args(vec![                 reg_class(RC::XMM) .named("lhs").into(), (reg_class(RC::XMM) | any_f64_imm()).named("rhs").into()]);
-    //     let sub64 = ir(IROpcode::Sub).named("bin").ty(Type::f64).args(vec![(any_f64_imm() | reg_class(RC::XMM)).named("lhs").into(), (reg_class(RC::XMM) | any_f64_imm()).named("rhs").into()]);
-    //     let mul64 = ir(IROpcode::Mul).named("bin").ty(Type::f64).args(vec![                 reg_class(RC::XMM) .named("lhs").into(), (reg_class(RC::XMM) | any_f64_imm()).named("rhs").into()]);
-    //     let div64 = ir(IROpcode::Div).named("bin").ty(Type::f64).args(vec![(any_f64_imm() | reg_class(RC::XMM)).named("lhs").into(), (reg_class(RC::XMM) | any_f64_imm()).named("rhs").into()]);
-    //     (add64 | sub64 | mul64.into() | div64.into()).generate(|m, c| {
-    //         let lhs = if matches!(c.arena[m["lhs"]], Node::Operand(OperandNode::Imm(_))) {
-    //             c.arena.alloc(MINode::new(MO::MOVSDrm64).args(vec![m["lhs"]]).reg_class(RC::XMM).into()) } else { m["lhs"] };
-    //         let rhs = if matches!(c.arena[m["rhs"]], Node::Operand(OperandNode::Imm(_))) {
-    //             c.arena.alloc(MINode::new(MO::MOVSDrm64).args(vec![m["rhs"]]).reg_class(RC::XMM).into()) } else { m["rhs"] };
-    //         let opcode = match c.arena[m["bin"]].as_ir().opcode {
-    //             IROpcode::Add => MO::ADDSDrr,
-    //             IROpcode::Sub => MO::SUBSDrr,
-    //             IROpcode::Mul => MO::MULSDrr,
-    //             IROpcode::Div => MO::DIVSDrr,
-    //             _ => panic!()
-    //         };
-    //         c.arena.alloc(MINode::new(opcode).args(vec![lhs, rhs]).reg_class(opcode.inst_def().unwrap().def_reg_class()).into())
-    //     }).into()
-    // };
+    #[rustfmt::skip]
+    let bitwise: Pat = {
+        let and32 = ir(IROpcode::And) .named("bin").ty(Type::i32).args(vec![reg_class(RC::GR32).named("lhs").into(), (reg_class(RC::GR32) | any_i32_imm()).named("rhs").into()]);
+        let and64 = ir(IROpcode::And) .named("bin").ty(Type::i64).args(vec![reg_class(RC::GR64).named("lhs").into(), (reg_class(RC::GR64) | any_i32_imm() | any_i64_imm().into()).named("rhs").into()]);
+        let or32  = ir(IROpcode::Or)  .named("bin").ty(Type::i32).args(vec![reg_class(RC::GR32).named("lhs").into(), (reg_class(RC::GR32) | any_i32_imm()).named("rhs").into()]);
+        let or64  = ir(IROpcode::Or)  .named("bin").ty(Type::i64).args(vec![reg_class(RC::GR64).named("lhs").into(), (reg_class(RC::GR64) | any_i32_imm() | any_i64_imm().into()).named("rhs").into()]);
+        let xor32 = ir(IROpcode::Xor) .named("bin").ty(Type::i32).args(vec![reg_class(RC::GR32).named("lhs").into(), (reg_class(RC::GR32) | any_i32_imm()).named("rhs").into()]);
+        let xor64 = ir(IROpcode::Xor) .named("bin").ty(Type::i64).args(vec![reg_class(RC::GR64).named("lhs").into(), (reg_class(RC::GR64) | any_i32_imm() | any_i64_imm().into()).named("rhs").into()]);
+        let shl32 = ir(IROpcode::Shl) .named("bin").ty(Type::i32).args(vec![reg_class(RC::GR32).named("lhs").into(), (reg_class(RC::GR32) | any_i8_imm() | any_i32_imm().into()).named("rhs").into()]);
+        let shl64 = ir(IROpcode::Shl) .named("bin").ty(Type::i64).args(vec![reg_class(RC::GR64).named("lhs").into(), (reg_class(RC::GR64) | any_i8_imm() | (any_i32_imm() | any_i64_imm())).named("rhs").into()]);
+        let shr32 = ir(IROpcode::LShr).named("bin").ty(Type::i32).args(vec![reg_class(RC::GR32).named("lhs").into(), (reg_class(RC::GR32) | any_i8_imm() | any_i32_imm().into()).named("rhs").into()]);
+        let shr64 = ir(IROpcode::LShr).named("bin").ty(Type::i64).args(vec![reg_class(RC::GR64).named("lhs").into(), (reg_class(RC::GR64) | any_i8_imm() | (any_i32_imm() | any_i64_imm())).named("rhs").into()]);
+        let sar32 = ir(IROpcode::AShr).named("bin").ty(Type::i32).args(vec![reg_class(RC::GR32).named("lhs").into(), (reg_class(RC::GR32) | any_i8_imm() | any_i32_imm().into()).named("rhs").into()]);
+        let sar64 = ir(IROpcode::AShr).named("bin").ty(Type::i64).args(vec![reg_class(RC::GR64).named("lhs").into(), (reg_class(RC::GR64) | any_i8_imm() | (any_i32_imm() | any_i64_imm())).named("rhs").into()]);
+        ((and32 | and64) | (or32 | or64) | (xor32 | xor64) | (shl32 | shl64) | (shr32 | shr64) | (sar32 | sar64)).generate(|m, c| {
+            let ty = c.arena[m["bin"]].as_ir().mvty;
+            let opcode = c.arena[m["bin"]].as_ir().opcode;
+            let is_shift = matches!(opcode, IROpcode::Shl | IROpcode::LShr | IROpcode::AShr);
+            // Logical immediates use the bitmask encoding, so they are simply materialized.
+            let rhs = match c.arena[m["rhs"]] {
+                Node::Operand(OperandNode::Imm(i)) if is_shift => {
+                    let amount = match i { ImmediateKind::Int8(i) => i as i64, ImmediateKind::Int32(i) => i as i64, ImmediateKind::Int64(i) => i, _ => panic!() };
+                    c.arena.alloc(OperandNode::Imm(ImmediateKind::Int8(amount as i8)).into())
+                }
+                _ => imm_reg(c, m["rhs"], ty)
+            };
+            let imm = matches!(c.arena[rhs], Node::Operand(OperandNode::Imm(_)));
+            let opcode = match opcode {
+                IROpcode::And  if matches!(ty, MVType::i32)        => MO::ANDrrr32,
+                IROpcode::And  if matches!(ty, MVType::i64)        => MO::ANDrrr64,
+                IROpcode::Or   if matches!(ty, MVType::i32)        => MO::ORRrrr32,
+                IROpcode::Or   if matches!(ty, MVType::i64)        => MO::ORRrrr64,
+                IROpcode::Xor  if matches!(ty, MVType::i32)        => MO::EORrrr32,
+                IROpcode::Xor  if matches!(ty, MVType::i64)        => MO::EORrrr64,
+                IROpcode::Shl  if matches!(ty, MVType::i32) && imm => MO::LSLrr32i,
+                IROpcode::Shl  if matches!(ty, MVType::i32)        => MO::LSLrrr32,
+                IROpcode::Shl  if matches!(ty, MVType::i64) && imm => MO::LSLrr64i,
+                IROpcode::Shl  if matches!(ty, MVType::i64)        => MO::LSLrrr64,
+                IROpcode::LShr if matches!(ty, MVType::i32) && imm => MO::LSRrr32i,
+                IROpcode::LShr if matches!(ty, MVType::i32)        => MO::LSRrrr32,
+                IROpcode::LShr if matches!(ty, MVType::i64) && imm => MO::LSRrr64i,
+                IROpcode::LShr if matches!(ty, MVType::i64)        => MO::LSRrrr64,
+                IROpcode::AShr if matches!(ty, MVType::i32) && imm => MO::ASRrr32i,
+                IROpcode::AShr if matches!(ty, MVType::i32)        => MO::ASRrrr32,
+                IROpcode::AShr if matches!(ty, MVType::i64) && imm => MO::ASRrr64i,
+                IROpcode::AShr if matches!(ty, MVType::i64)        => MO::ASRrrr64,
+                _ => panic!() 
+            };
+            c.arena.alloc(MINode::new(opcode).args(vec![
+                m["lhs"], rhs
+            ]).reg_class(opcode.inst_def().unwrap().def_reg_class()).into())
+        })
+    }.into();
+    #[rustfmt::skip]
+    let fbin: Pat = {
+        let bin32 = |opcode| ir(opcode).named("bin").ty(Type::f32).args(vec![(reg_class(RC::F32) | any_f32_imm()).named("lhs").into(), (reg_class(RC::F32) | any_f32_imm()).named("rhs").into()]);
+        let bin64 = |opcode| ir(opcode).named("bin").ty(Type::f64).args(vec![(reg_class(RC::F64) | any_f64_imm()).named("lhs").into(), (reg_class(RC::F64) | any_f64_imm()).named("rhs").into()]);
+        ((bin32(IROpcode::Add) | bin64(IROpcode::Add)) | (bin32(IROpcode::Sub) | bin64(IROpcode::Sub))
+         | (bin32(IROpcode::Mul) | bin64(IROpcode::Mul)) | (bin32(IROpcode::Div) | bin64(IROpcode::Div))).generate(|m, c| {
+            let IRNode { opcode, ty, .. } = *c.arena[m["bin"]].as_ir();
+            let lhs = fp_reg(c, m["lhs"]);
+            let rhs = fp_reg(c, m["rhs"]);
+            let opcode = match opcode {
+                IROpcode::Add if ty == Type::f32 => MO::FADDrrr32,
+                IROpcode::Add                    => MO::FADDrrr64,
+                IROpcode::Sub if ty == Type::f32 => MO::FSUBrrr32,
+                IROpcode::Sub                    => MO::FSUBrrr64,
+                IROpcode::Mul if ty == Type::f32 => MO::FMULrrr32,
+                IROpcode::Mul                    => MO::FMULrrr64,
+                IROpcode::Div if ty == Type::f32 => MO::FDIVrrr32,
+                IROpcode::Div                    => MO::FDIVrrr64,
+                _ => panic!()
+            };
+            c.arena.alloc(MINode::new(opcode).args(vec![lhs, rhs]).reg_class(opcode.inst_def().unwrap().def_reg_class()).into())
+        })
+    }.into();
+
+    #[rustfmt::skip]
+    let bin16: Pat = {
+        let bin = |opcode| ir(opcode).named("bin").ty(Type::i16).args(vec![(reg_class(RC::GR16) | any_i16_imm()).named("lhs").into(), (reg_class(RC::GR16) | any_i16_imm()).named("rhs").into()]);
+        ((bin(IROpcode::Add) | bin(IROpcode::Sub)) | (bin(IROpcode::Mul) | bin(IROpcode::Div)) | (bin(IROpcode::Rem) | bin(IROpcode::UDiv))
+         | (bin(IROpcode::URem) | bin(IROpcode::And)) | (bin(IROpcode::Or) | bin(IROpcode::Xor)) | (bin(IROpcode::Shl) | bin(IROpcode::LShr))
+         | bin(IROpcode::AShr).into()).generate(|m, c| {
+            let opcode = c.arena[m["bin"]].as_ir().opcode;
+            select_bin16(c, opcode, m["lhs"], m["rhs"])
+        })
+    }.into();
+
+    #[rustfmt::skip]
+    let ext: Pat = ((ir(IROpcode::Sext).named("ext").args(vec![any_reg().named("x").into()])
+                     | ir(IROpcode::Zext).named("ext").args(vec![any_reg().named("x").into()]))
+                     | ir(IROpcode::Trunc).named("ext").args(vec![any_reg().named("x").into()]).into())
+                   .generate(|m, c| {
+                       let IRNode { opcode, ty, .. } = *c.arena[m["ext"]].as_ir();
+                       select_ext(c, opcode, ty, m["x"]) }).into();
 
     let br: Pat = ir(IROpcode::Br)
         .args(vec![any_block().named("dst").into()])
@@ -234,17 +351,29 @@ fn run_on_function(func: &mut DAGFunction) {
     //                                       .generate(|m, c| {
     //                                           let mem = c.arena.alloc(OperandNode::Mem(MemKind::Address(m["a"])).into());
     //                                           c.arena.alloc(MINode::new(MO::MOVrm64).args(vec![mem]).reg_class(RC::GR64).into()) }).into();
-    // #[rustfmt::skip]
-    // let fptosi: Pat = ir(IROpcode::FPToSI).ty(Type::i32).args(vec![reg_class(RC::XMM).named("x").into()])
-    //                                       .generate(|m, c| {
-    //                                           c.arena.alloc(MINode::new(MO::CVTTSD2SIr32r).args(vec![m["x"]]).reg_class(RC::GR32).into()) }).into();
-    // #[rustfmt::skip]
-    // let sitofp: Pat = ir(IROpcode::SIToFP).ty(Type::f64).args(vec![reg_class(RC::GR32).named("x").into()])
-    //                                       .generate(|m, c| {
-    //                                           c.arena.alloc(MINode::new(MO::CVTSI2SDrr32).args(vec![m["x"]]).reg_class(RC::XMM).into()) }).into();
+    let cvt = |opcode| ir(opcode).named("cvt").args(vec![any().named("x")]);
+    let cvt: Pat = ((cvt(IROpcode::SIToFP) | cvt(IROpcode::UIToFP))
+        | (cvt(IROpcode::FPToSI) | cvt(IROpcode::FPToUI)))
+    .generate(|m, c| {
+        let IRNode { opcode, ty, .. } = *c.arena[m["cvt"]].as_ir();
+        select_cvt(c, opcode, ty, m["x"])
+    })
+    .into();
+
+    #[rustfmt::skip]
+    let fpext: Pat = ir(IROpcode::FPExt).ty(Type::f64).args(vec![(reg_class(RC::F32) | any_f32_imm()).named("x").into()])
+                                        .generate(|m, c| {
+                                            let x = fp_reg(c, m["x"]);
+                                            c.arena.alloc(MINode::new(MO::FCVTrr64).args(vec![x]).reg_class(RC::F64).into()) }).into();
+    #[rustfmt::skip]
+    let fptrunc: Pat = ir(IROpcode::FPTrunc).ty(Type::f32).args(vec![(reg_class(RC::F64) | any_f64_imm()).named("x").into()])
+                                            .generate(|m, c| {
+                                                let x = fp_reg(c, m["x"]);
+                                                c.arena.alloc(MINode::new(MO::FCVTrr32).args(vec![x]).reg_class(RC::F32).into()) }).into();
 
     let pats = vec![
-        store, load, addsub, muldiv, /*fbin,*/ br, fiaddr, // constaddr, fptosi, sitofp,
+        store, fstore, store16, load, addsub, muldiv, bitwise, bin16, fbin, ext, br, fiaddr, cvt,
+        fpext, fptrunc, // constaddr,
     ];
 
     let mut replaced = ReplacedNodeMap::default();
@@ -263,6 +392,199 @@ fn run_on_function(func: &mut DAGFunction) {
     }
 }
 
+/// i16 is computed with the 32-bit instructions and sign-extended again, since a w-register
+/// holding i16 is kept sign-extended. Unsigned operations zero-extend their operands first.
+fn select_bin16(c: &mut MatchContext, opcode: IROpcode, lhs: NodeId, rhs: NodeId) -> NodeId {
+    let is_shift = matches!(opcode, IROpcode::Shl | IROpcode::LShr | IROpcode::AShr);
+    let lhs = imm_reg(c, lhs, MVType::i16);
+    let rhs = match c.arena[rhs] {
+        Node::Operand(OperandNode::Imm(ImmediateKind::Int16(i))) if is_shift => c
+            .arena
+            .alloc(OperandNode::Imm(ImmediateKind::Int8((i & 31) as i8)).into()),
+        _ => imm_reg(c, rhs, MVType::i16),
+    };
+    let (lhs, rhs) = match opcode {
+        IROpcode::UDiv | IROpcode::URem => (
+            mi(c, MO::UXTHrr, vec![lhs], RC::GR32),
+            mi(c, MO::UXTHrr, vec![rhs], RC::GR32),
+        ),
+        IROpcode::LShr => (mi(c, MO::UXTHrr, vec![lhs], RC::GR32), rhs),
+        _ => (lhs, rhs),
+    };
+    let imm = matches!(c.arena[rhs], Node::Operand(OperandNode::Imm(_)));
+    let op = match opcode {
+        IROpcode::Add => MO::ADDrrr32,
+        IROpcode::Sub => MO::SUBrrr32,
+        IROpcode::Mul => MO::MULrrr32,
+        IROpcode::Div | IROpcode::Rem => MO::SDIVrrr32,
+        IROpcode::UDiv | IROpcode::URem => MO::UDIVrrr32,
+        IROpcode::And => MO::ANDrrr32,
+        IROpcode::Or => MO::ORRrrr32,
+        IROpcode::Xor => MO::EORrrr32,
+        IROpcode::Shl if imm => MO::LSLrr32i,
+        IROpcode::Shl => MO::LSLrrr32,
+        IROpcode::LShr if imm => MO::LSRrr32i,
+        IROpcode::LShr => MO::LSRrrr32,
+        IROpcode::AShr if imm => MO::ASRrr32i,
+        IROpcode::AShr => MO::ASRrrr32,
+        _ => unreachable!(),
+    };
+    let x = mi(c, op, vec![lhs, rhs], RC::GR32);
+    let x = match opcode {
+        // lhs - (lhs / rhs) * rhs
+        IROpcode::Rem | IROpcode::URem => mi(c, MO::MSUBrrrr32, vec![x, rhs, lhs], RC::GR32),
+        _ => x,
+    };
+    match opcode {
+        // The result of these stays within i16 and sign-extended.
+        IROpcode::And
+        | IROpcode::Or
+        | IROpcode::Xor
+        | IROpcode::AShr
+        | IROpcode::Div
+        | IROpcode::Rem => to_reg_class(c, x, RC::GR16),
+        _ => mi(c, MO::SXTHrr, vec![x], RC::GR16),
+    }
+}
+
+fn select_ext(c: &mut MatchContext, opcode: IROpcode, ty: Type, x: NodeId) -> NodeId {
+    let rc = ty2rc(&ty).unwrap();
+    let from = reg_class_of(c, x);
+    match opcode {
+        IROpcode::Sext if rc == RC::GR64 && from != RC::GR64 => mi(c, MO::SXTW64rr, vec![x], rc),
+        IROpcode::Zext => {
+            let x = match from {
+                RC::GR16 => mi(c, MO::UXTHrr, vec![x], RC::GR32),
+                // Writing a w-register clears the upper half.
+                RC::GR32 if rc == RC::GR64 => mi(c, MO::MOVrr, vec![x], RC::GR32),
+                _ => x,
+            };
+            to_reg_class(c, x, rc)
+        }
+        IROpcode::Trunc if rc == RC::GR16 => {
+            let x = to_reg_class(c, x, RC::GR32);
+            mi(c, MO::SXTHrr, vec![x], rc)
+        }
+        _ => to_reg_class(c, x, rc),
+    }
+}
+
+/// Conversion between integers and floating-point values. i16 goes through a w-register, so it's
+/// zero-extended for `ucvtf` and sign-extended again after `fcvtzs`/`fcvtzu`.
+fn select_cvt(c: &mut MatchContext, opcode: IROpcode, ty: Type, x: NodeId) -> NodeId {
+    let rc = ty2rc(&ty).unwrap();
+    let from = reg_class_of(c, x);
+    let x = match from {
+        RC::F32 | RC::F64 => fp_reg(c, x),
+        RC::GR64 => imm_reg(c, x, MVType::i64),
+        _ => imm_reg(c, x, MVType::i32),
+    };
+    match opcode {
+        IROpcode::SIToFP => mi(c, MO::SCVTFrr, vec![x], rc),
+        IROpcode::UIToFP if from == RC::GR16 => {
+            let x = mi(c, MO::UXTHrr, vec![x], RC::GR32);
+            mi(c, MO::UCVTFrr, vec![x], rc)
+        }
+        IROpcode::UIToFP => mi(c, MO::UCVTFrr, vec![x], rc),
+        IROpcode::FPToSI | IROpcode::FPToUI => {
+            let cvt = if opcode == IROpcode::FPToSI {
+                MO::FCVTZSrr
+            } else {
+                MO::FCVTZUrr
+            };
+            if rc == RC::GR16 {
+                let x = mi(c, cvt, vec![x], RC::GR32);
+                mi(c, MO::SXTHrr, vec![x], rc)
+            } else {
+                mi(c, cvt, vec![x], rc)
+            }
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn mi(c: &mut MatchContext, opcode: MO, args: Vec<NodeId>, rc: RC) -> NodeId {
+    c.arena
+        .alloc(MINode::new(opcode).args(args).reg_class(rc).into())
+}
+
+fn to_reg_class(c: &mut MatchContext, x: NodeId, rc: RC) -> NodeId {
+    if reg_class_of(c, x) == rc {
+        return x;
+    }
+    c.arena.alloc(
+        IRNode::new(IROpcode::RegClass)
+            .args(vec![x])
+            .ty(rc2ty(rc))
+            .into(),
+    )
+}
+
+fn reg_class_of(c: &MatchContext, id: NodeId) -> RC {
+    match &c.arena[id] {
+        Node::IR(IRNode { ty, .. }) => ty2rc(ty).unwrap(),
+        Node::MI(MINode { reg_class, .. }) => reg_class.unwrap(),
+        Node::Operand(OperandNode::Reg(r)) => c.regs.arena_ref()[*r].reg_class,
+        Node::Operand(OperandNode::Imm(i)) => match i {
+            ImmediateKind::Int8(_) | ImmediateKind::Int32(_) => RC::GR32,
+            ImmediateKind::Int16(_) => RC::GR16,
+            ImmediateKind::Int64(_) => RC::GR64,
+            ImmediateKind::F32(_) => RC::F32,
+            ImmediateKind::F64(_) => RC::F64,
+        },
+        _ => unreachable!(),
+    }
+}
+
+/// Puts an integer immediate into a register, a w-register unless `ty` is i64.
+fn imm_reg(c: &mut MatchContext, id: NodeId, ty: MVType) -> NodeId {
+    if !matches!(c.arena[id], Node::Operand(OperandNode::Imm(_))) {
+        return id;
+    }
+    if ty == MVType::i64 {
+        mi(c, MO::MOVr64i, vec![id], RC::GR64)
+    } else {
+        mi(c, MO::MOVr32i, vec![id], RC::GR32)
+    }
+}
+
+fn mem(c: &mut MatchContext, dst: NodeId) -> NodeId {
+    let mem = match c.arena[dst] {
+        Node::Operand(OperandNode::Slot(_)) => {
+            let x29 = c.arena.alloc(c.regs.get_phys_reg(GR64::X29).into());
+            MemKind::RegFi([x29, dst])
+        }
+        _ => MemKind::Reg(dst),
+    };
+    c.arena.alloc(OperandNode::Mem(mem).into())
+}
+
+/// Puts a floating-point immediate into a register through a general-purpose register, since
+/// `fmov` encodes only a few immediates.
+fn fp_reg(c: &mut MatchContext, id: NodeId) -> NodeId {
+    let (bits, mov, fmov) = match c.arena[id] {
+        Node::Operand(OperandNode::Imm(ImmediateKind::F32(f))) => (
+            ImmediateKind::Int32(f.to_bits() as i32),
+            MO::MOVr32i,
+            MO::FMOVfr32,
+        ),
+        Node::Operand(OperandNode::Imm(ImmediateKind::F64(f))) => (
+            ImmediateKind::Int64(f.to_bits() as i64),
+            MO::MOVr64i,
+            MO::FMOVfr64,
+        ),
+        _ => return id,
+    };
+    let bits = c.arena.alloc(OperandNode::Imm(bits).into());
+    let rc = mov.inst_def().unwrap().def_reg_class();
+    let src = c
+        .arena
+        .alloc(MINode::new(mov).args(vec![bits]).reg_class(rc).into());
+    let rc = fmov.inst_def().unwrap().def_reg_class();
+    c.arena
+        .alloc(MINode::new(fmov).args(vec![src]).reg_class(rc).into())
+}
+
 fn select_node<'a>(
     ctx: &mut MatchContext<'a>,
     replaced: &mut ReplacedNodeMap,
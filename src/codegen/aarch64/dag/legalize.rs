@@ -1,20 +1,9 @@
-use crate::codegen::arch::machine::register::{RegisterClassKind as RC, GR64};
-use crate::codegen::arch::{dag::node::MemKind, machine::inst::MachineOpcode as MO};
-use crate::codegen::common::{
-    dag::{
-        function::DAGFunction,
-        module::DAGModule,
-        node::{IRNode, IROpcode, ImmediateKind, MINode, Node, NodeId, OperandNode},
-        pat_match::{
-            add, any, any_block, any_cc, any_f64_imm, any_i32_imm, any_imm, any_imm32,
-            any_imm32_power_of_2, any_imm_f64, any_reg, any_slot, fiaddr, gbladdr, inst_select, ir,
-            mul, not, reg_, reg_class, reorder_patterns, store, CompoundPat, MatchContext, Pat,
-            ReplacedNodeMap,
-        },
-    },
-    types::MVType,
+use crate::codegen::aarch64::common::dag::{
+    function::DAGFunction,
+    module::DAGModule,
+    node::{IROpcode, NodeId},
+    pat_match::{any, inst_select, ir, reorder_patterns, MatchContext, Pat, ReplacedNodeMap},
 };
-use crate::ir::types::Type;
 
 pub fn run(module: &mut DAGModule) {
     for (_, func) in &mut module.functions {
@@ -26,23 +15,13 @@ pub fn run(module: &mut DAGModule) {
 }
 
 fn run_on_function(func: &mut DAGFunction) {
-    let sext: Pat = ir(IROpcode::Sext)
-        .named("sext")
-        // .ty(Type::i64)
-        .args(vec![any_reg().named("arg").into()])
-        .generate(|m, c| {
-            let ty = c.arena[m["sext"]].as_ir().ty;
-            c.arena.alloc(
-                IRNode::new(IROpcode::RegClass)
-                    .args(vec![m["arg"]])
-                    .ty(ty)
-                    .into(),
-            )
-        })
+    let bitcast: Pat = ir(IROpcode::Bitcast)
+        .args(vec![any().named("arg")])
+        .generate(|m, _| m["arg"])
         .into();
 
     let pats = vec![
-        sext,
+        bitcast,
         // sext, load4, load5, store, load, load2, load3, store2, brcc, fpbrcc, bitcast, load6, store3,
         // store4,
     ];
@@ -1,18 +1,18 @@
 // use super::{node, node::*};
-use crate::codegen::arch::dag::node::MemKind;
-use crate::codegen::arch::frame_object::FrameIndexInfo;
-use crate::codegen::arch::machine::abi::AAPCS64;
-use crate::codegen::arch::machine::inst::*;
-use crate::codegen::arch::machine::register::*;
-pub use crate::codegen::common::dag::mc_convert::ScheduleContext;
-use crate::codegen::common::dag::{
+pub use crate::codegen::aarch64::common::dag::mc_convert::ScheduleContext;
+use crate::codegen::aarch64::common::dag::{
     node,
     node::{CondKind, IRNode, IROpcode, ImmediateKind, MINode, Node, NodeId, OperandNode},
 };
-use crate::codegen::common::machine::calling_conv::{ArgumentRegisterOrder, CallingConv};
-use crate::codegen::common::machine::inst::*;
-use crate::codegen::common::machine::inst_def::DefOrUseReg;
-use crate::codegen::common::types::MVType;
+use crate::codegen::aarch64::common::machine::calling_conv::{ArgumentRegisterOrder, CallingConv};
+use crate::codegen::aarch64::common::machine::inst::*;
+use crate::codegen::aarch64::common::machine::inst_def::DefOrUseReg;
+use crate::codegen::aarch64::common::types::MVType;
+use crate::codegen::aarch64::dag::node::MemKind;
+use crate::codegen::aarch64::frame_object::FrameIndexInfo;
+use crate::codegen::aarch64::machine::abi::AAPCS64;
+use crate::codegen::aarch64::machine::inst::*;
+use crate::codegen::aarch64::machine::register::*;
 use crate::ir::types::Type;
 
 impl<'a> ScheduleContext<'a> {
@@ -72,13 +72,13 @@ impl<'a> ScheduleContext<'a> {
             }) => {
                 let lhs = self.normal_arg(args[1]);
                 let rhs = self.normal_arg(args[2]);
+                let rhs = self.cmp_rhs(&lhs, rhs);
 
                 self.append_inst(MachineInst::new_simple(
                     if lhs.is_register() && rhs.is_constant() {
                         MachineOpcode::CMPri
                     } else if lhs.is_register() && rhs.is_register() {
-                        todo!()
-                    // MachineOpcode::CMPrr
+                        MachineOpcode::CMPrr
                     } else {
                         unreachable!()
                     },
@@ -94,6 +94,10 @@ impl<'a> ScheduleContext<'a> {
                         CondKind::Lt => MachineOpcode::B_LT,
                         CondKind::Ge => MachineOpcode::B_GE,
                         CondKind::Gt => MachineOpcode::B_GT,
+                        CondKind::ULe => MachineOpcode::B_LS,
+                        CondKind::ULt => MachineOpcode::B_LO,
+                        CondKind::UGe => MachineOpcode::B_HS,
+                        CondKind::UGt => MachineOpcode::B_HI,
                         _ => unreachable!(),
                     },
                     vec![MachineOperand::Branch(
@@ -102,6 +106,12 @@ impl<'a> ScheduleContext<'a> {
                     self.block_id,
                 ))
             }
+            Node::IR(IRNode {
+                opcode: IROpcode::Select,
+                args,
+                ty,
+                ..
+            }) => self.convert_select(*ty, args),
             Node::IR(IRNode {
                 opcode: IROpcode::Call,
                 args,
@@ -140,6 +150,74 @@ impl<'a> ScheduleContext<'a> {
         inst_id
     }
 
+    /// Lowers select into CSEL. The comparison feeding the condition is fused when possible,
+    /// otherwise the condition itself is compared against zero.
+    fn convert_select(&mut self, ty: Type, args: &[NodeId]) -> MachineInstId {
+        let rc = ty2rc(&ty).unwrap();
+        let mut operands = vec![];
+        for &arg in &args[1..] {
+            let op = self.normal_arg(arg);
+            let op = self.fp_const_to_reg(op);
+            operands.push(if op.is_register() {
+                op
+            } else {
+                let mov = MachineInst::new(
+                    &self.func.regs,
+                    mov_rx(rc, &op).unwrap(),
+                    vec![op],
+                    Some(rc),
+                    self.block_id,
+                );
+                let r = mov.def[0];
+                self.append_inst(mov);
+                MachineOperand::Register(r)
+            });
+        }
+
+        let (cond, lhs, rhs) = match &self.func.node_arena[args[0]] {
+            Node::IR(IRNode {
+                opcode: IROpcode::Setcc,
+                args: cmp,
+                ..
+            }) => {
+                let cc = *self.func.node_arena[cmp[0]].as_operand().as_cc();
+                let (lhs, rhs) = (cmp[1], cmp[2]);
+                (cc, self.normal_arg(lhs), self.normal_arg(rhs))
+            }
+            _ => (
+                CondKind::Ne,
+                self.normal_arg(args[0]),
+                MachineOperand::Constant(MachineConstant::Int32(0)),
+            ),
+        };
+        let rhs = self.cmp_rhs(&lhs, rhs);
+        self.append_inst(MachineInst::new_simple(
+            if rhs.is_constant() {
+                MachineOpcode::CMPri
+            } else {
+                MachineOpcode::CMPrr
+            },
+            vec![lhs, rhs],
+            self.block_id,
+        ));
+
+        let opcode = match cond {
+            CondKind::Eq => MachineOpcode::CSEL_EQ,
+            CondKind::Ne => MachineOpcode::CSEL_NE,
+            CondKind::Le => MachineOpcode::CSEL_LE,
+            CondKind::Lt => MachineOpcode::CSEL_LT,
+            CondKind::Ge => MachineOpcode::CSEL_GE,
+            CondKind::Gt => MachineOpcode::CSEL_GT,
+            CondKind::ULe => MachineOpcode::CSEL_LS,
+            CondKind::ULt => MachineOpcode::CSEL_LO,
+            CondKind::UGe => MachineOpcode::CSEL_HS,
+            CondKind::UGt => MachineOpcode::CSEL_HI,
+            _ => unreachable!(),
+        };
+        let inst = MachineInst::new(&self.func.regs, opcode, operands, Some(rc), self.block_id);
+        self.append_inst(inst)
+    }
+
     fn convert_call(&mut self, ret_ty: Type, operands: &[NodeId]) -> MachineInstId {
         let mut arg_regs = vec![RegisterOperand::new(self.func.regs.get_phys_reg(GR64::X30))]; // call uses X30
         let mut off = 0i32;
@@ -174,6 +252,7 @@ impl<'a> ScheduleContext<'a> {
 
         for (i, arg) in args.into_iter().enumerate() {
             let (ty, byval) = func_params[i];
+            let arg = self.fp_const_to_reg(arg);
 
             // if byval {
             //     // TODO
@@ -189,7 +268,14 @@ impl<'a> ScheduleContext<'a> {
 
             if !matches!(
                 ty,
-                Type::i8 | Type::i32 | Type::i64 | Type::f64 | Type::Pointer(_) | Type::Array(_)
+                Type::i8
+                    | Type::i16
+                    | Type::i32
+                    | Type::i64
+                    | Type::f32
+                    | Type::f64
+                    | Type::Pointer(_)
+                    | Type::Array(_)
             ) {
                 unimplemented!()
             }
@@ -384,11 +470,13 @@ impl<'a> ScheduleContext<'a> {
 
         if ret_ty != Type::Void {
             let arg = self.normal_arg(arg);
+            let arg = self.fp_const_to_reg(arg);
             let rc = match arg {
                 MachineOperand::Register(RegisterOperand { id, sub_super }) => {
                     sub_super.unwrap_or_else(|| self.func.regs.arena_ref()[id].reg_class)
                 }
                 // MachineOperand::Constant(MachineConstant::Int8(_)) => RegisterClassKind::GR8,
+                MachineOperand::Constant(MachineConstant::Int16(_)) => RegisterClassKind::GR16,
                 MachineOperand::Constant(MachineConstant::Int32(_)) => RegisterClassKind::GR32,
                 MachineOperand::Constant(MachineConstant::Int64(_)) => RegisterClassKind::GR64,
                 // MachineOperand::Constant(MachineConstant::F64(_)) => RegisterClassKind::XMM,
@@ -408,14 +496,82 @@ impl<'a> ScheduleContext<'a> {
         ))
     }
 
+    /// `cmp` takes an immediate within 12 bits, so others are put into a register as wide as
+    /// `lhs`.
+    fn cmp_rhs(&mut self, lhs: &MachineOperand, rhs: MachineOperand) -> MachineOperand {
+        let imm = match rhs {
+            MachineOperand::Constant(MachineConstant::Int16(i)) => i as i64,
+            MachineOperand::Constant(MachineConstant::Int32(i)) => i as i64,
+            MachineOperand::Constant(MachineConstant::Int64(i)) => i,
+            _ => return rhs,
+        };
+        if (-(1 << 12) + 1..1 << 12).contains(&imm) {
+            return rhs;
+        }
+        let lhs = lhs.as_register();
+        let rc = match lhs
+            .sub_super
+            .unwrap_or_else(|| self.func.regs.arena_ref()[lhs.id].reg_class)
+        {
+            RegisterClassKind::GR64 => RegisterClassKind::GR64,
+            _ => RegisterClassKind::GR32,
+        };
+        let mov = MachineInst::new(
+            &self.func.regs,
+            mov_rx(rc, &rhs).unwrap(),
+            vec![rhs],
+            Some(rc),
+            self.block_id,
+        );
+        let r = mov.def[0];
+        self.append_inst(mov);
+        MachineOperand::Register(r)
+    }
+
+    /// Puts a floating-point constant into a register through a general-purpose register, since
+    /// `fmov` encodes only a few immediates. Other operands are returned as they are.
+    fn fp_const_to_reg(&mut self, op: MachineOperand) -> MachineOperand {
+        let (bits, mov, fmov) = match op {
+            MachineOperand::Constant(MachineConstant::F32(f)) => (
+                MachineConstant::Int32(f.to_bits() as i32),
+                MachineOpcode::MOVr32i,
+                MachineOpcode::FMOVfr32,
+            ),
+            MachineOperand::Constant(MachineConstant::F64(f)) => (
+                MachineConstant::Int64(f.to_bits() as i64),
+                MachineOpcode::MOVr64i,
+                MachineOpcode::FMOVfr64,
+            ),
+            _ => return op,
+        };
+        let mut src = MachineOperand::Constant(bits);
+        for &opcode in &[mov, fmov] {
+            let rc = opcode.inst_def().unwrap().def_reg_class();
+            let inst =
+                MachineInst::new(&self.func.regs, opcode, vec![src], Some(rc), self.block_id);
+            src = MachineOperand::Register(inst.def[0]);
+            self.append_inst(inst);
+        }
+        src
+    }
+
     pub fn normal_arg(&mut self, arg: NodeId) -> MachineOperand {
         match &self.func.node_arena[arg] {
             Node::Operand(OperandNode::Imm(ImmediateKind::Int8(i))) => {
                 MachineOperand::Constant(MachineConstant::Int8(*i))
             }
+            Node::Operand(OperandNode::Imm(ImmediateKind::Int16(i))) => {
+                MachineOperand::Constant(MachineConstant::Int16(*i))
+            }
             Node::Operand(OperandNode::Imm(ImmediateKind::Int32(i))) => {
                 MachineOperand::Constant(MachineConstant::Int32(*i))
             }
+            Node::Operand(OperandNode::Imm(ImmediateKind::Int64(i))) => {
+                MachineOperand::Constant(MachineConstant::Int64(*i))
+            }
+            Node::Operand(OperandNode::Imm(ImmediateKind::F32(f))) => {
+                MachineOperand::Constant(MachineConstant::F32(*f))
+            }
             Node::Operand(OperandNode::Imm(ImmediateKind::F64(f))) => {
                 MachineOperand::Constant(MachineConstant::F64(*f))
             }
@@ -458,7 +614,6 @@ pub fn mov_rx(rc: RegisterClassKind, arg: &MachineOperand) -> Option<MachineOpco
     // let mov8rx = [MachineOpcode::MOVrr8, MachineOpcode::MOVri8];
     let mov32rx = [MachineOpcode::MOVrr, MachineOpcode::MOVr32i];
     let mov64rx = [MachineOpcode::MOVrr, MachineOpcode::MOVr64i];
-    // let movsdrx = [MachineOpcode::MOVSDrr, MachineOpcode::MOVSDrm64];
     let idx = match arg {
         MachineOperand::Register(_) => 0,
         MachineOperand::Constant(_) => 1,
@@ -466,10 +621,13 @@ pub fn mov_rx(rc: RegisterClassKind, arg: &MachineOperand) -> Option<MachineOpco
     };
     match rc {
         // RegisterClassKind::GR8 => Some(mov8rx[idx]),
-        RegisterClassKind::GR32 => Some(mov32rx[idx]),
+        RegisterClassKind::GR16 | RegisterClassKind::GR32 => Some(mov32rx[idx]),
         RegisterClassKind::GR64 => Some(mov64rx[idx]),
+        // Floating-point constants are put into registers by `fp_const_to_reg`.
+        RegisterClassKind::F32 if idx == 0 => Some(MachineOpcode::FMOVrr32),
+        RegisterClassKind::F64 if idx == 0 => Some(MachineOpcode::FMOVrr64),
+        RegisterClassKind::F32 | RegisterClassKind::F64 => None,
         RegisterClassKind::SP | RegisterClassKind::WSP => todo!(),
-        // RegisterClassKind::XMM => Some(movsdrx[idx]),
     }
 }
 
@@ -1,5 +1,5 @@
-use crate::codegen::common::dag::node::NodeId;
-pub use crate::codegen::common::dag::node::*;
+use crate::codegen::aarch64::common::dag::node::NodeId;
+pub use crate::codegen::aarch64::common::dag::node::*;
 use std::fmt;
 
 #[derive(Debug, Clone, PartialEq)]
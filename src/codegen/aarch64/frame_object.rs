@@ -1,8 +1,8 @@
 use super::exec::roundup;
-pub use crate::codegen::common::machine::frame_object::*;
-use crate::codegen::common::machine::function::MachineFunction;
+pub use crate::codegen::aarch64::common::machine::frame_object::*;
+use crate::codegen::aarch64::common::machine::function::MachineFunction;
 use crate::ir::types::*;
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 
 impl FrameObjectsInfo {
     pub fn new(tys: &Types, f: &MachineFunction) -> Self {
@@ -12,6 +12,9 @@ impl FrameObjectsInfo {
                 .appeared_phys_regs()
                 .containing_callee_saved_regs()
                 .to_phys_set()
+                .into_iter()
+                .map(|r| r.superest_reg())
+                .collect::<FxHashSet<_>>()
                 .len()
                 * 8,
             16,
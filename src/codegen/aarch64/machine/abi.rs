@@ -1,20 +1,26 @@
 use crate::{
-    codegen::{arch::machine::register::*, common::machine::calling_conv::CallingConv},
+    codegen::{aarch64::common::machine::calling_conv::CallingConv, aarch64::machine::register::*},
     ir::types::StructType,
 };
 
 #[derive(Clone)]
 pub struct AAPCS64 {
+    gr16: Vec<PhysReg>,
     gr32: Vec<PhysReg>,
     gr64: Vec<PhysReg>,
+    f32: Vec<PhysReg>,
+    f64: Vec<PhysReg>,
 }
 
 #[rustfmt::skip]
 impl AAPCS64 {
     pub fn new() -> Self {
         Self {
+            gr16: to_phys![GR16::W0, GR16::W1, GR16::W2, GR16::W3, GR16::W4, GR16::W5, GR16::W6, GR16::W7],
             gr32: to_phys![GR32::W0, GR32::W1, GR32::W2, GR32::W3, GR32::W4, GR32::W5, GR32::W6, GR32::W7],
             gr64: to_phys![GR64::X0, GR64::X1, GR64::X2, GR64::X3, GR64::X4, GR64::X5, GR64::X6, GR64::X7],
+            f32:  to_phys![F32::S0, F32::S1, F32::S2, F32::S3, F32::S4, F32::S5, F32::S6, F32::S7],
+            f64:  to_phys![F64::D0, F64::D1, F64::D2, F64::D3, F64::D4, F64::D5, F64::D6, F64::D7],
         }
     }
 }
@@ -22,8 +28,11 @@ impl AAPCS64 {
 impl CallingConv for AAPCS64 {
     fn get_nth_arg_reg(&self, rc: RegisterClassKind, nth: usize) -> Option<PhysReg> {
         match rc {
+            RegisterClassKind::GR16 => self.gr16.get(nth),
             RegisterClassKind::GR32 => self.gr32.get(nth),
             RegisterClassKind::GR64 => self.gr64.get(nth),
+            RegisterClassKind::F32 => self.f32.get(nth),
+            RegisterClassKind::F64 => self.f64.get(nth),
             RegisterClassKind::WSP => None,
             RegisterClassKind::SP => None,
         }
@@ -1,7 +1,7 @@
-use crate::codegen::arch::machine::inst::MachineMemOperand;
-use crate::codegen::common::machine::eliminate_fi::EliminateFrameIndexOnFunction;
-use crate::codegen::common::machine::frame_object::{FrameIndexInfo, FrameObjectsInfo};
-use crate::codegen::common::machine::inst::{MachineInst, MachineOperand};
+use crate::codegen::aarch64::common::machine::eliminate_fi::EliminateFrameIndexOnFunction;
+use crate::codegen::aarch64::common::machine::frame_object::{FrameIndexInfo, FrameObjectsInfo};
+use crate::codegen::aarch64::common::machine::inst::{MachineInst, MachineOperand};
+use crate::codegen::aarch64::machine::inst::MachineMemOperand;
 
 impl<'a> EliminateFrameIndexOnFunction<'a> {
     pub fn get_frame_index(inst: &MachineInst) -> Option<&FrameIndexInfo> {
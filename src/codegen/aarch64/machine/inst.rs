@@ -1,6 +1,6 @@
 use super::frame_object::*;
 pub use super::inst_def::TargetOpcode;
-pub use crate::codegen::common::machine::{basic_block::MachineBasicBlockId, inst::*};
+pub use crate::codegen::aarch64::common::machine::{basic_block::MachineBasicBlockId, inst::*};
 use crate::ir::types::Type;
 
 #[derive(Debug, Clone)]
@@ -30,6 +30,10 @@ impl MachineOpcode {
                 | MachineOpcode::B_LT
                 | MachineOpcode::B_GE
                 | MachineOpcode::B_GT
+                | MachineOpcode::B_LO
+                | MachineOpcode::B_LS
+                | MachineOpcode::B_HI
+                | MachineOpcode::B_HS
         )
     }
 
@@ -46,6 +50,10 @@ impl MachineOpcode {
                 | MachineOpcode::B_LT
                 | MachineOpcode::B_GE
                 | MachineOpcode::B_GT
+                | MachineOpcode::B_LO
+                | MachineOpcode::B_LS
+                | MachineOpcode::B_HI
+                | MachineOpcode::B_HS
         )
     }
 
@@ -57,6 +65,10 @@ impl MachineOpcode {
             Self::B_GT => Some(Self::B_LE),
             Self::B_LE => Some(Self::B_GT),
             Self::B_LT => Some(Self::B_GE),
+            Self::B_LO => Some(Self::B_HS),
+            Self::B_LS => Some(Self::B_HI),
+            Self::B_HI => Some(Self::B_LS),
+            Self::B_HS => Some(Self::B_LO),
             _ => None,
         }
     }
@@ -75,7 +87,11 @@ impl MachineInst {
             | MachineOpcode::B_GE
             | MachineOpcode::B_GT
             | MachineOpcode::B_LE
-            | MachineOpcode::B_LT => Some(self.operand[0].as_basic_block()),
+            | MachineOpcode::B_LT
+            | MachineOpcode::B_LO
+            | MachineOpcode::B_LS
+            | MachineOpcode::B_HI
+            | MachineOpcode::B_HS => Some(self.operand[0].as_basic_block()),
             _ => None,
         }
     }
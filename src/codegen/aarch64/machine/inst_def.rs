@@ -1,5 +1,5 @@
-use crate::codegen::arch::machine::register::*;
-use crate::codegen::common::machine::inst_def::*;
+use crate::codegen::aarch64::common::machine::inst_def::*;
+use crate::codegen::aarch64::machine::register::*;
 
 #[allow(non_upper_case_globals)]
 mod inst {
@@ -61,20 +61,222 @@ mod inst {
             .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
                            TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
             .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref SDIVrrr64: TargetInstDef = TargetInstDef::new("sdiv", TargetOpcode::SDIVrrr64)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref UDIVrrr32: TargetInstDef = TargetInstDef::new("udiv", TargetOpcode::UDIVrrr32)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref UDIVrrr64: TargetInstDef = TargetInstDef::new("udiv", TargetOpcode::UDIVrrr64)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref MSUBrrrr32: TargetInstDef = TargetInstDef::new("msub", TargetOpcode::MSUBrrrr32)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref MSUBrrrr64: TargetInstDef = TargetInstDef::new("msub", TargetOpcode::MSUBrrrr64)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref ANDrrr32: TargetInstDef = TargetInstDef::new("and", TargetOpcode::ANDrrr32)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref ANDrrr64: TargetInstDef = TargetInstDef::new("and", TargetOpcode::ANDrrr64)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref ORRrrr32: TargetInstDef = TargetInstDef::new("orr", TargetOpcode::ORRrrr32)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref ORRrrr64: TargetInstDef = TargetInstDef::new("orr", TargetOpcode::ORRrrr64)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref EORrrr32: TargetInstDef = TargetInstDef::new("eor", TargetOpcode::EORrrr32)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref EORrrr64: TargetInstDef = TargetInstDef::new("eor", TargetOpcode::EORrrr64)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref LSLrrr32: TargetInstDef = TargetInstDef::new("lsl", TargetOpcode::LSLrrr32)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref LSLrrr64: TargetInstDef = TargetInstDef::new("lsl", TargetOpcode::LSLrrr64)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref LSRrrr32: TargetInstDef = TargetInstDef::new("lsr", TargetOpcode::LSRrrr32)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref LSRrrr64: TargetInstDef = TargetInstDef::new("lsr", TargetOpcode::LSRrrr64)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref ASRrrr32: TargetInstDef = TargetInstDef::new("asr", TargetOpcode::ASRrrr32)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref ASRrrr64: TargetInstDef = TargetInstDef::new("asr", TargetOpcode::ASRrrr64)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref LSLrr32i: TargetInstDef = TargetInstDef::new("lsl", TargetOpcode::LSLrr32i)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Immediate(TargetImmediate::I8)])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref LSLrr64i: TargetInstDef = TargetInstDef::new("lsl", TargetOpcode::LSLrr64i)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Immediate(TargetImmediate::I8)])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref LSRrr32i: TargetInstDef = TargetInstDef::new("lsr", TargetOpcode::LSRrr32i)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Immediate(TargetImmediate::I8)])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref LSRrr64i: TargetInstDef = TargetInstDef::new("lsr", TargetOpcode::LSRrr64i)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Immediate(TargetImmediate::I8)])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref ASRrr32i: TargetInstDef = TargetInstDef::new("asr", TargetOpcode::ASRrr32i)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Immediate(TargetImmediate::I8)])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref ASRrr64i: TargetInstDef = TargetInstDef::new("asr", TargetOpcode::ASRrr64i)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Immediate(TargetImmediate::I8)])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref FMOVrr32: TargetInstDef = TargetInstDef::new("fmov", TargetOpcode::FMOVrr32)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F32)]);
+        pub static ref FMOVrr64: TargetInstDef = TargetInstDef::new("fmov", TargetOpcode::FMOVrr64)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F64)]);
+        pub static ref FMOVfr32: TargetInstDef = TargetInstDef::new("fmov", TargetOpcode::FMOVfr32)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F32)]);
+        pub static ref FMOVfr64: TargetInstDef = TargetInstDef::new("fmov", TargetOpcode::FMOVfr64)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F64)]);
+        pub static ref FADDrrr32: TargetInstDef = TargetInstDef::new("fadd", TargetOpcode::FADDrrr32)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F32)]);
+        pub static ref FADDrrr64: TargetInstDef = TargetInstDef::new("fadd", TargetOpcode::FADDrrr64)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F64)]);
+        pub static ref FSUBrrr32: TargetInstDef = TargetInstDef::new("fsub", TargetOpcode::FSUBrrr32)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F32)]);
+        pub static ref FSUBrrr64: TargetInstDef = TargetInstDef::new("fsub", TargetOpcode::FSUBrrr64)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F64)]);
+        pub static ref FMULrrr32: TargetInstDef = TargetInstDef::new("fmul", TargetOpcode::FMULrrr32)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F32)]);
+        pub static ref FMULrrr64: TargetInstDef = TargetInstDef::new("fmul", TargetOpcode::FMULrrr64)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F64)]);
+        pub static ref FDIVrrr32: TargetInstDef = TargetInstDef::new("fdiv", TargetOpcode::FDIVrrr32)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F32)]);
+        pub static ref FDIVrrr64: TargetInstDef = TargetInstDef::new("fdiv", TargetOpcode::FDIVrrr64)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F64)]);
+        pub static ref FCVTrr32: TargetInstDef = TargetInstDef::new("fcvt", TargetOpcode::FCVTrr32)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F32)]);
+        pub static ref FCVTrr64: TargetInstDef = TargetInstDef::new("fcvt", TargetOpcode::FCVTrr64)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F64)]);
+        pub static ref SCVTFrr: TargetInstDef = TargetInstDef::new("scvtf", TargetOpcode::SCVTFrr)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any)])
+            .set_defs(vec![TargetRegister::Any]);
+        pub static ref UCVTFrr: TargetInstDef = TargetInstDef::new("ucvtf", TargetOpcode::UCVTFrr)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any)])
+            .set_defs(vec![TargetRegister::Any]);
+        pub static ref FCVTZSrr: TargetInstDef = TargetInstDef::new("fcvtzs", TargetOpcode::FCVTZSrr)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any)])
+            .set_defs(vec![TargetRegister::Any]);
+        pub static ref FCVTZUrr: TargetInstDef = TargetInstDef::new("fcvtzu", TargetOpcode::FCVTZUrr)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any)])
+            .set_defs(vec![TargetRegister::Any]);
         pub static ref CMPri: TargetInstDef = TargetInstDef::new("cmp", TargetOpcode::CMPri)
             .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
                            TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))]);
+        pub static ref CMPrr: TargetInstDef = TargetInstDef::new("cmp", TargetOpcode::CMPrr)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any),
+                           TargetOperand::Register(TargetRegister::Any)]);
+        pub static ref CSEL_EQ: TargetInstDef = TargetInstDef::new("csel", TargetOpcode::CSEL_EQ)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any), TargetOperand::Register(TargetRegister::Any)])
+            .set_defs(vec![TargetRegister::Any]);
+        pub static ref CSEL_NE: TargetInstDef = TargetInstDef::new("csel", TargetOpcode::CSEL_NE)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any), TargetOperand::Register(TargetRegister::Any)])
+            .set_defs(vec![TargetRegister::Any]);
+        pub static ref CSEL_LT: TargetInstDef = TargetInstDef::new("csel", TargetOpcode::CSEL_LT)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any), TargetOperand::Register(TargetRegister::Any)])
+            .set_defs(vec![TargetRegister::Any]);
+        pub static ref CSEL_LE: TargetInstDef = TargetInstDef::new("csel", TargetOpcode::CSEL_LE)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any), TargetOperand::Register(TargetRegister::Any)])
+            .set_defs(vec![TargetRegister::Any]);
+        pub static ref CSEL_GT: TargetInstDef = TargetInstDef::new("csel", TargetOpcode::CSEL_GT)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any), TargetOperand::Register(TargetRegister::Any)])
+            .set_defs(vec![TargetRegister::Any]);
+        pub static ref CSEL_GE: TargetInstDef = TargetInstDef::new("csel", TargetOpcode::CSEL_GE)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any), TargetOperand::Register(TargetRegister::Any)])
+            .set_defs(vec![TargetRegister::Any]);
+        pub static ref CSEL_LO: TargetInstDef = TargetInstDef::new("csel", TargetOpcode::CSEL_LO)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any), TargetOperand::Register(TargetRegister::Any)])
+            .set_defs(vec![TargetRegister::Any]);
+        pub static ref CSEL_LS: TargetInstDef = TargetInstDef::new("csel", TargetOpcode::CSEL_LS)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any), TargetOperand::Register(TargetRegister::Any)])
+            .set_defs(vec![TargetRegister::Any]);
+        pub static ref CSEL_HI: TargetInstDef = TargetInstDef::new("csel", TargetOpcode::CSEL_HI)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any), TargetOperand::Register(TargetRegister::Any)])
+            .set_defs(vec![TargetRegister::Any]);
+        pub static ref CSEL_HS: TargetInstDef = TargetInstDef::new("csel", TargetOpcode::CSEL_HS)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any), TargetOperand::Register(TargetRegister::Any)])
+            .set_defs(vec![TargetRegister::Any]);
         pub static ref B_EQ: TargetInstDef = TargetInstDef::new("b.eq", TargetOpcode::B_EQ).set_uses(vec![TargetOperand::Block]);
         pub static ref B_NE: TargetInstDef = TargetInstDef::new("b.ne", TargetOpcode::B_NE).set_uses(vec![TargetOperand::Block]);
         pub static ref B_LT: TargetInstDef = TargetInstDef::new("b.lt", TargetOpcode::B_LT).set_uses(vec![TargetOperand::Block]);
         pub static ref B_LE: TargetInstDef = TargetInstDef::new("b.le", TargetOpcode::B_LE).set_uses(vec![TargetOperand::Block]);
         pub static ref B_GT: TargetInstDef = TargetInstDef::new("b.gt", TargetOpcode::B_GT).set_uses(vec![TargetOperand::Block]);
         pub static ref B_GE: TargetInstDef = TargetInstDef::new("b.ge", TargetOpcode::B_GE).set_uses(vec![TargetOperand::Block]);
+        pub static ref B_LO: TargetInstDef = TargetInstDef::new("b.lo", TargetOpcode::B_LO).set_uses(vec![TargetOperand::Block]);
+        pub static ref B_LS: TargetInstDef = TargetInstDef::new("b.ls", TargetOpcode::B_LS).set_uses(vec![TargetOperand::Block]);
+        pub static ref B_HI: TargetInstDef = TargetInstDef::new("b.hi", TargetOpcode::B_HI).set_uses(vec![TargetOperand::Block]);
+        pub static ref B_HS: TargetInstDef = TargetInstDef::new("b.hs", TargetOpcode::B_HS).set_uses(vec![TargetOperand::Block]);
         pub static ref B: TargetInstDef = TargetInstDef::new("b", TargetOpcode::B).set_uses(vec![TargetOperand::Block]);
         pub static ref BL: TargetInstDef = TargetInstDef::new("bl", TargetOpcode::CALL).set_uses(vec![TargetOperand::Addr]);
         pub static ref SXTW64rr: TargetInstDef = TargetInstDef::new("sxtw", TargetOpcode::SXTW64rr)
             .set_uses(vec![TargetOperand::Register(TargetRegister::Any)])
             .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref SXTHrr: TargetInstDef = TargetInstDef::new("sxth", TargetOpcode::SXTHrr)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any)])
+            .set_defs(vec![TargetRegister::Any]);
+        pub static ref UXTHrr: TargetInstDef = TargetInstDef::new("uxth", TargetOpcode::UXTHrr)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any)])
+            .set_defs(vec![TargetRegister::Any]);
+        pub static ref LDRSH32: TargetInstDef = TargetInstDef::new("ldrsh", TargetOpcode::LDRSH32)
+            .set_uses(vec![TargetOperand::Mem])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR16)]);
         pub static ref LDR32: TargetInstDef = TargetInstDef::new("ldr", TargetOpcode::LDR32)
             .set_uses(vec![TargetOperand::Mem])
             .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
@@ -84,8 +286,16 @@ mod inst {
         pub static ref LDRSW64: TargetInstDef = TargetInstDef::new("ldrsw", TargetOpcode::LDRSW64)
             .set_uses(vec![TargetOperand::Mem])
             .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref FLDR32: TargetInstDef = TargetInstDef::new("ldr", TargetOpcode::FLDR32)
+            .set_uses(vec![TargetOperand::Mem])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F32)]);
+        pub static ref FLDR64: TargetInstDef = TargetInstDef::new("ldr", TargetOpcode::FLDR64)
+            .set_uses(vec![TargetOperand::Mem])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F64)]);
         pub static ref STR: TargetInstDef = TargetInstDef::new("str", TargetOpcode::STR)
             .set_uses(vec![TargetOperand::Register(TargetRegister::Any), TargetOperand::Mem]);
+        pub static ref STRH: TargetInstDef = TargetInstDef::new("strh", TargetOpcode::STRH)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR16)), TargetOperand::Mem]);
         pub static ref STP: TargetInstDef = TargetInstDef::new("stp", TargetOpcode::STP)
             .set_uses(vec![TargetOperand::Register(TargetRegister::Any),
                            TargetOperand::Register(TargetRegister::Any),
@@ -98,6 +308,47 @@ mod inst {
     }
 }
 
+/// How an instruction is encoded into a 32-bit A64 word. A `u32` is the word with every operand
+/// field zero, and `sf` (bool) selects the 64-bit form.
+#[derive(Debug, Clone, Copy)]
+pub enum Encoding {
+    /// Just the given word.
+    Raw(u32),
+    /// `op Rd, Rn`
+    Rr(u32),
+    /// `op Rd, Rn, Rm`
+    Rrr(u32),
+    /// `op Rd, Rn, Rm, Ra`
+    Rrrr(u32),
+    /// `add`/`sub` of a register and a register, an immediate or a frame index. (sf, is sub)
+    AddSub(bool, bool),
+    /// `lsl`/`lsr`/`asr` by an immediate, which are aliases of `ubfm`/`sbfm`. (word, is lsl)
+    ShiftImm(u32, bool),
+    /// `mov Rd, #imm`, expanded to `movz`/`movn` followed by `movk`s. (sf)
+    MovImm(bool),
+    /// `mov Rd, Rn`, including from and to `sp`.
+    Mov,
+    /// Conversion between a general-purpose and a floating-point register, where the registers
+    /// decide the sf and type bits. (word for w-registers and singles)
+    Cvt(u32),
+    /// `cmp Rn, Rm` or `cmp Rn, #imm`
+    Cmp,
+    /// `csel Rd, Rn, Rm, cond` (cond)
+    Csel(u32),
+    /// `b.cond label` (cond)
+    BCond(u32),
+    /// `b label`
+    B,
+    /// `bl function`
+    Call,
+    /// `ldr Rt, [Xn, #imm]` (word of the unsigned offset form)
+    Ldr(u32),
+    /// `str Rt, [Xn, #imm]`, or `strh` of a w-register holding i16
+    Str,
+    /// `ldp`/`stp` of two 64-bit registers. (is load)
+    Pair(bool),
+}
+
 #[derive(Debug, Clone, PartialEq, Copy)]
 #[allow(non_camel_case_types)]
 pub enum TargetOpcode {
@@ -116,19 +367,81 @@ pub enum TargetOpcode {
     MULrrr32,
     MULrrr64,
     SDIVrrr32,
+    SDIVrrr64,
+    UDIVrrr32,
+    UDIVrrr64,
+    MSUBrrrr32,
+    MSUBrrrr64,
+    ANDrrr32,
+    ANDrrr64,
+    ORRrrr32,
+    ORRrrr64,
+    EORrrr32,
+    EORrrr64,
+    LSLrrr32,
+    LSLrrr64,
+    LSRrrr32,
+    LSRrrr64,
+    ASRrrr32,
+    ASRrrr64,
+    LSLrr32i,
+    LSLrr64i,
+    LSRrr32i,
+    LSRrr64i,
+    ASRrr32i,
+    ASRrr64i,
+    FMOVrr32,
+    FMOVrr64,
+    FMOVfr32,
+    FMOVfr64,
+    FADDrrr32,
+    FADDrrr64,
+    FSUBrrr32,
+    FSUBrrr64,
+    FMULrrr32,
+    FMULrrr64,
+    FDIVrrr32,
+    FDIVrrr64,
+    FCVTrr32,
+    FCVTrr64,
+    SCVTFrr,
+    UCVTFrr,
+    FCVTZSrr,
+    FCVTZUrr,
     CMPri,
+    CMPrr,
+    CSEL_EQ,
+    CSEL_NE,
+    CSEL_LT,
+    CSEL_LE,
+    CSEL_GT,
+    CSEL_GE,
+    CSEL_LO,
+    CSEL_LS,
+    CSEL_HI,
+    CSEL_HS,
     B_EQ,
     B_NE,
     B_LT,
     B_LE,
     B_GT,
     B_GE,
+    B_LO,
+    B_LS,
+    B_HI,
+    B_HS,
     B,
     SXTW64rr,
+    SXTHrr,
+    UXTHrr,
+    LDRSH32,
     LDR32,
     LDR64,
     LDRSW64,
+    FLDR32,
+    FLDR64,
     STR,
+    STRH,
     LDP64,
     STP,
     RET,
@@ -180,24 +493,181 @@ impl TargetOpcode {
             Self::MULrrr32 => Some(&*inst::MULrrr32),
             Self::MULrrr64 => Some(&*inst::MULrrr64),
             Self::SDIVrrr32 => Some(&*inst::SDIVrrr32),
+            Self::SDIVrrr64 => Some(&*inst::SDIVrrr64),
+            Self::UDIVrrr32 => Some(&*inst::UDIVrrr32),
+            Self::UDIVrrr64 => Some(&*inst::UDIVrrr64),
+            Self::MSUBrrrr32 => Some(&*inst::MSUBrrrr32),
+            Self::MSUBrrrr64 => Some(&*inst::MSUBrrrr64),
+            Self::ANDrrr32 => Some(&*inst::ANDrrr32),
+            Self::ANDrrr64 => Some(&*inst::ANDrrr64),
+            Self::ORRrrr32 => Some(&*inst::ORRrrr32),
+            Self::ORRrrr64 => Some(&*inst::ORRrrr64),
+            Self::EORrrr32 => Some(&*inst::EORrrr32),
+            Self::EORrrr64 => Some(&*inst::EORrrr64),
+            Self::LSLrrr32 => Some(&*inst::LSLrrr32),
+            Self::LSLrrr64 => Some(&*inst::LSLrrr64),
+            Self::LSRrrr32 => Some(&*inst::LSRrrr32),
+            Self::LSRrrr64 => Some(&*inst::LSRrrr64),
+            Self::ASRrrr32 => Some(&*inst::ASRrrr32),
+            Self::ASRrrr64 => Some(&*inst::ASRrrr64),
+            Self::LSLrr32i => Some(&*inst::LSLrr32i),
+            Self::LSLrr64i => Some(&*inst::LSLrr64i),
+            Self::LSRrr32i => Some(&*inst::LSRrr32i),
+            Self::LSRrr64i => Some(&*inst::LSRrr64i),
+            Self::ASRrr32i => Some(&*inst::ASRrr32i),
+            Self::ASRrr64i => Some(&*inst::ASRrr64i),
+            Self::FMOVrr32 => Some(&*inst::FMOVrr32),
+            Self::FMOVrr64 => Some(&*inst::FMOVrr64),
+            Self::FMOVfr32 => Some(&*inst::FMOVfr32),
+            Self::FMOVfr64 => Some(&*inst::FMOVfr64),
+            Self::FADDrrr32 => Some(&*inst::FADDrrr32),
+            Self::FADDrrr64 => Some(&*inst::FADDrrr64),
+            Self::FSUBrrr32 => Some(&*inst::FSUBrrr32),
+            Self::FSUBrrr64 => Some(&*inst::FSUBrrr64),
+            Self::FMULrrr32 => Some(&*inst::FMULrrr32),
+            Self::FMULrrr64 => Some(&*inst::FMULrrr64),
+            Self::FDIVrrr32 => Some(&*inst::FDIVrrr32),
+            Self::FDIVrrr64 => Some(&*inst::FDIVrrr64),
+            Self::FCVTrr32 => Some(&*inst::FCVTrr32),
+            Self::FCVTrr64 => Some(&*inst::FCVTrr64),
+            Self::SCVTFrr => Some(&*inst::SCVTFrr),
+            Self::UCVTFrr => Some(&*inst::UCVTFrr),
+            Self::FCVTZSrr => Some(&*inst::FCVTZSrr),
+            Self::FCVTZUrr => Some(&*inst::FCVTZUrr),
             Self::CMPri => Some(&*inst::CMPri),
+            Self::CMPrr => Some(&*inst::CMPrr),
+            Self::CSEL_EQ => Some(&*inst::CSEL_EQ),
+            Self::CSEL_NE => Some(&*inst::CSEL_NE),
+            Self::CSEL_LT => Some(&*inst::CSEL_LT),
+            Self::CSEL_LE => Some(&*inst::CSEL_LE),
+            Self::CSEL_GT => Some(&*inst::CSEL_GT),
+            Self::CSEL_GE => Some(&*inst::CSEL_GE),
+            Self::CSEL_LO => Some(&*inst::CSEL_LO),
+            Self::CSEL_LS => Some(&*inst::CSEL_LS),
+            Self::CSEL_HI => Some(&*inst::CSEL_HI),
+            Self::CSEL_HS => Some(&*inst::CSEL_HS),
             Self::B_EQ => Some(&*inst::B_EQ),
             Self::B_NE => Some(&*inst::B_NE),
             Self::B_LT => Some(&*inst::B_LT),
             Self::B_LE => Some(&*inst::B_LE),
             Self::B_GT => Some(&*inst::B_GT),
             Self::B_GE => Some(&*inst::B_GE),
+            Self::B_LO => Some(&*inst::B_LO),
+            Self::B_LS => Some(&*inst::B_LS),
+            Self::B_HI => Some(&*inst::B_HI),
+            Self::B_HS => Some(&*inst::B_HS),
             Self::B => Some(&*inst::B),
             Self::CALL => Some(&*inst::BL),
             Self::SXTW64rr => Some(&*inst::SXTW64rr),
+            Self::SXTHrr => Some(&*inst::SXTHrr),
+            Self::UXTHrr => Some(&*inst::UXTHrr),
+            Self::LDRSH32 => Some(&*inst::LDRSH32),
             Self::LDR32 => Some(&*inst::LDR32),
             Self::LDR64 => Some(&*inst::LDR64),
             Self::LDRSW64 => Some(&*inst::LDRSW64),
+            Self::FLDR32 => Some(&*inst::FLDR32),
+            Self::FLDR64 => Some(&*inst::FLDR64),
             Self::STR => Some(&*inst::STR),
+            Self::STRH => Some(&*inst::STRH),
             Self::LDP64 => Some(&*inst::LDP64),
             Self::STP => Some(&*inst::STP),
             Self::RET => Some(&*inst::RET),
             _ => None,
         }
     }
+
+    /// How the instruction is encoded. `None` for pseudo instructions.
+    pub fn encoding(&self) -> Option<Encoding> {
+        use Encoding::*;
+        Some(match self {
+            Self::MOVrr => Mov,
+            Self::MOVr32i => MovImm(false),
+            Self::MOVr64i => MovImm(true),
+            Self::ADDrr32i | Self::ADDrrr32 => AddSub(false, false),
+            Self::ADDrr64i | Self::ADDrrr64 => AddSub(true, false),
+            Self::SUBrr32i | Self::SUBrrr32 => AddSub(false, true),
+            Self::SUBrr64i | Self::SUBrrr64 => AddSub(true, true),
+            Self::MULrrr32 => Rrr(0x1b007c00), // madd wd, wn, wm, wzr
+            Self::MULrrr64 => Rrr(0x9b007c00),
+            Self::SDIVrrr32 => Rrr(0x1ac00c00),
+            Self::SDIVrrr64 => Rrr(0x9ac00c00),
+            Self::UDIVrrr32 => Rrr(0x1ac00800),
+            Self::UDIVrrr64 => Rrr(0x9ac00800),
+            Self::MSUBrrrr32 => Rrrr(0x1b008000),
+            Self::MSUBrrrr64 => Rrrr(0x9b008000),
+            Self::ANDrrr32 => Rrr(0x0a000000),
+            Self::ANDrrr64 => Rrr(0x8a000000),
+            Self::ORRrrr32 => Rrr(0x2a000000),
+            Self::ORRrrr64 => Rrr(0xaa000000),
+            Self::EORrrr32 => Rrr(0x4a000000),
+            Self::EORrrr64 => Rrr(0xca000000),
+            Self::LSLrrr32 => Rrr(0x1ac02000), // lslv
+            Self::LSLrrr64 => Rrr(0x9ac02000),
+            Self::LSRrrr32 => Rrr(0x1ac02400), // lsrv
+            Self::LSRrrr64 => Rrr(0x9ac02400),
+            Self::ASRrrr32 => Rrr(0x1ac02800), // asrv
+            Self::ASRrrr64 => Rrr(0x9ac02800),
+            Self::LSLrr32i => ShiftImm(0x53000000, true), // ubfm
+            Self::LSLrr64i => ShiftImm(0xd3400000, true),
+            Self::LSRrr32i => ShiftImm(0x53000000, false),
+            Self::LSRrr64i => ShiftImm(0xd3400000, false),
+            Self::ASRrr32i => ShiftImm(0x13000000, false), // sbfm
+            Self::ASRrr64i => ShiftImm(0x93400000, false),
+            Self::FMOVrr32 => Rr(0x1e204000),
+            Self::FMOVrr64 => Rr(0x1e604000),
+            Self::FMOVfr32 => Rr(0x1e270000), // fmov sd, wn
+            Self::FMOVfr64 => Rr(0x9e670000), // fmov dd, xn
+            Self::FADDrrr32 => Rrr(0x1e202800),
+            Self::FADDrrr64 => Rrr(0x1e602800),
+            Self::FSUBrrr32 => Rrr(0x1e203800),
+            Self::FSUBrrr64 => Rrr(0x1e603800),
+            Self::FMULrrr32 => Rrr(0x1e200800),
+            Self::FMULrrr64 => Rrr(0x1e600800),
+            Self::FDIVrrr32 => Rrr(0x1e201800),
+            Self::FDIVrrr64 => Rrr(0x1e601800),
+            Self::FCVTrr32 => Rr(0x1e624000),  // fcvt sd, dn
+            Self::FCVTrr64 => Rr(0x1e22c000),  // fcvt dd, sn
+            Self::SCVTFrr => Cvt(0x1e220000),  // scvtf sd, wn
+            Self::UCVTFrr => Cvt(0x1e230000),  // ucvtf sd, wn
+            Self::FCVTZSrr => Cvt(0x1e380000), // fcvtzs wd, sn
+            Self::FCVTZUrr => Cvt(0x1e390000), // fcvtzu wd, sn
+            Self::CMPri | Self::CMPrr => Cmp,
+            Self::CSEL_EQ => Csel(0b0000),
+            Self::CSEL_NE => Csel(0b0001),
+            Self::CSEL_HS => Csel(0b0010),
+            Self::CSEL_LO => Csel(0b0011),
+            Self::CSEL_HI => Csel(0b1000),
+            Self::CSEL_LS => Csel(0b1001),
+            Self::CSEL_GE => Csel(0b1010),
+            Self::CSEL_LT => Csel(0b1011),
+            Self::CSEL_GT => Csel(0b1100),
+            Self::CSEL_LE => Csel(0b1101),
+            Self::B_EQ => BCond(0b0000),
+            Self::B_NE => BCond(0b0001),
+            Self::B_HS => BCond(0b0010),
+            Self::B_LO => BCond(0b0011),
+            Self::B_HI => BCond(0b1000),
+            Self::B_LS => BCond(0b1001),
+            Self::B_GE => BCond(0b1010),
+            Self::B_LT => BCond(0b1011),
+            Self::B_GT => BCond(0b1100),
+            Self::B_LE => BCond(0b1101),
+            Self::B => B,
+            Self::CALL => Call,
+            Self::SXTW64rr => Rr(0x93407c00), // sbfm xd, xn, #0, #31
+            Self::SXTHrr => Rr(0x13003c00),   // sbfm wd, wn, #0, #15
+            Self::UXTHrr => Rr(0x53003c00),   // ubfm wd, wn, #0, #15
+            Self::LDRSH32 => Ldr(0x79c00000),
+            Self::LDR32 => Ldr(0xb9400000),
+            Self::LDR64 => Ldr(0xf9400000),
+            Self::LDRSW64 => Ldr(0xb9800000),
+            Self::FLDR32 => Ldr(0xbd400000),
+            Self::FLDR64 => Ldr(0xfd400000),
+            Self::STR | Self::STRH => Str,
+            Self::LDP64 => Pair(true),
+            Self::STP => Pair(false),
+            Self::RET => Raw(0xd65f03c0), // ret x30
+            _ => return None,
+        })
+    }
 }
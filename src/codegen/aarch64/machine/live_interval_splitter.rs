@@ -5,7 +5,9 @@ use super::super::{
 };
 use super::inst::*;
 use crate::analysis::{dom_tree::DominatorTree, loops::Loops};
-use crate::codegen::common::machine::{basic_block::*, function::*, liveness::*, regalloc::*};
+use crate::codegen::aarch64::common::machine::{
+    basic_block::*, function::*, liveness::*, regalloc::*,
+};
 
 pub struct LiveIntervalSplitter<'a> {
     _func: &'a mut MachineFunction,
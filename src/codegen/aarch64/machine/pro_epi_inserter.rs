@@ -1,6 +1,6 @@
 use super::super::{frame_object::*, machine::register::*};
 use super::inst::*;
-use crate::codegen::common::machine::{builder::*, function::*, module::MachineModule};
+use crate::codegen::aarch64::common::machine::{builder::*, function::*, module::MachineModule};
 use crate::{ir::types::*, traits::pass::ModulePassTrait};
 
 pub struct PrologueEpilogueInserter {}
@@ -30,7 +30,7 @@ impl PrologueEpilogueInserter {
 
     pub fn run_on_module(&mut self, module: &mut MachineModule) {
         for (_, func) in &mut module.functions {
-            if func.is_internal {
+            if func.is_internal || func.is_empty() {
                 continue;
             }
             self.run_on_function(&module.types, func);
@@ -1,14 +1,14 @@
-use crate::codegen::arch::{
-    frame_object::FrameIndexInfo,
-    machine::inst::{MachineMemOperand, MachineOpcode},
-    machine::register::*,
-};
-pub use crate::codegen::common::machine::regalloc::*;
-use crate::codegen::common::machine::{
+pub use crate::codegen::aarch64::common::machine::regalloc::*;
+use crate::codegen::aarch64::common::machine::{
     basic_block::MachineBasicBlockId,
     function::MachineFunction,
     inst::{MachineInst, MachineInstId, MachineOperand, RegisterOperand},
 };
+use crate::codegen::aarch64::{
+    frame_object::FrameIndexInfo,
+    machine::inst::{MachineMemOperand, MachineOpcode},
+    machine::register::*,
+};
 
 impl RegisterAllocator {
     pub fn store_and_load_for_reg_preservation(
@@ -20,9 +20,14 @@ impl RegisterAllocator {
     ) -> (MachineInstId, MachineInstId) {
         let src = MachineOperand::Register(RegisterOperand::new(reg));
         let x29 = RegisterOperand::new(f.regs_info.get_phys_reg(GR64::X29));
+        let rc = f.regs_info.arena_ref()[reg].reg_class;
+        let store = match rc {
+            RegisterClassKind::GR16 => MachineOpcode::STRH,
+            _ => MachineOpcode::STR,
+        };
         let store_inst_id = f.alloc_inst(MachineInst::new(
             &f.regs_info,
-            MachineOpcode::STR,
+            store,
             vec![
                 src,
                 MachineOperand::Mem(MachineMemOperand::RegFi(x29, frinfo)),
@@ -31,9 +36,16 @@ impl RegisterAllocator {
             parent,
         ));
 
+        let load = match rc {
+            RegisterClassKind::GR16 => MachineOpcode::LDRSH32,
+            RegisterClassKind::GR64 => MachineOpcode::LDR64,
+            RegisterClassKind::F32 => MachineOpcode::FLDR32,
+            RegisterClassKind::F64 => MachineOpcode::FLDR64,
+            _ => MachineOpcode::LDR32,
+        };
         let load_inst_id = f.alloc_inst(
             MachineInst::new_simple(
-                MachineOpcode::LDR32,
+                load,
                 vec![MachineOperand::Mem(MachineMemOperand::RegFi(x29, frinfo))],
                 parent,
             )
@@ -1,4 +1,4 @@
-pub use crate::codegen::common::machine::register::*;
+pub use crate::codegen::aarch64::common::machine::register::*;
 use crate::ir::types::Type;
 use defs::registers;
 use id_arena::Arena;
@@ -32,10 +32,42 @@ registers! {
         W25, W26, W27, W28, W29, W30
     }
 
+    // i16 lives in a w-register, kept sign-extended to 32 bits.
+    class GR16 (16, i16, [i16], [W0]) < GR32 {
+        W0, W1, W2, W3, W4,
+        W5, W6, W7, W8, W9,
+        W10, W11, W12, W13, W14,
+        W15, W16, W17, W18, W19,
+        W20, W21, W22, W23, W24,
+        W25, W26, W27, W28, W29, W30
+    }
+
+    class F64 (64, f64, [f64], [D0]) {
+        D0, D1, D2, D3, D4, D5, D6, D7,
+        D8, D9, D10, D11, D12, D13, D14, D15,
+        D16, D17, D18, D19, D20, D21, D22, D23,
+        D24, D25, D26, D27, D28, D29, D30, D31
+    }
+
+    class F32 (32, f32, [f32], [S0]) < F64 {
+        S0, S1, S2, S3, S4, S5, S6, S7,
+        S8, S9, S10, S11, S12, S13, S14, S15,
+        S16, S17, S18, S19, S20, S21, S22, S23,
+        S24, S25, S26, S27, S28, S29, S30, S31
+    }
+
+    order arg GR16 { W0, W1, W2, W3, W4, W5, W6, W7 }
     order arg GR32 { W0, W1, W2, W3, W4, W5, W6, W7 }
     order arg GR64 { X0, X1, X2, X3, X4, X5, X6, X7 }
+    order arg F32  { S0, S1, S2, S3, S4, S5, S6, S7 }
+    order arg F64  { D0, D1, D2, D3, D4, D5, D6, D7 }
     order arg SP { SP } order arg WSP { WSP }
 
+    order gp GR16 {
+        W0, W1, W2, W3, W4, W5, W6, W7,
+        W9, W10, W11, W12, W13, W14, W15,
+        W19, W20, W21, W22, W23, W24, W25, W26, W27, W28
+    }
     order gp GR32 {
         W0, W1, W2, W3, W4, W5, W6, W7,
         W9, W10, W11, W12, W13, W14, W15,
@@ -46,6 +78,17 @@ registers! {
         X9, X10, X11, X12, X13, X14, X15,
         X19, X20, X21, X22, X23, X24, X25, X26, X27, X28
     }
+    // d8-d15 are callee-saved, which the prologue doesn't preserve, so they are left unused.
+    order gp F32 {
+        S0, S1, S2, S3, S4, S5, S6, S7,
+        S16, S17, S18, S19, S20, S21, S22, S23,
+        S24, S25, S26, S27, S28, S29, S30, S31
+    }
+    order gp F64 {
+        D0, D1, D2, D3, D4, D5, D6, D7,
+        D16, D17, D18, D19, D20, D21, D22, D23,
+        D24, D25, D26, D27, D28, D29, D30, D31
+    }
     order gp SP { SP } order gp WSP { WSP }
 }
 
@@ -53,6 +96,18 @@ thread_local! {
     pub static CALLEE_SAVED_REGS: PhysRegSet = {
         let mut bits = PhysRegSet::new();
         let regs = to_phys![
+            GR16::W19,
+            GR16::W20,
+            GR16::W21,
+            GR16::W22,
+            GR16::W23,
+            GR16::W24,
+            GR16::W25,
+            GR16::W26,
+            GR16::W27,
+            GR16::W28,
+            GR16::W29,
+            GR16::W30,
             GR32::W19,
             GR32::W20,
             GR32::W21,
@@ -1,6 +1,6 @@
 use super::super::dag::mc_convert::mov_rx;
 use super::inst::MachineOpcode;
-use crate::codegen::common::machine::{function::MachineFunction, module::MachineModule};
+use crate::codegen::aarch64::common::machine::{function::MachineFunction, module::MachineModule};
 use crate::traits::pass::ModulePassTrait;
 
 pub struct ReplaceCopyWithProperMInst {}
@@ -1,6 +1,8 @@
-use crate::codegen::arch::{frame_object::FrameIndexInfo, machine::register::*};
-pub use crate::codegen::common::machine::regalloc::*;
-use crate::codegen::common::machine::{function::MachineFunction, liveness::LiveRegMatrix};
+pub use crate::codegen::aarch64::common::machine::regalloc::*;
+use crate::codegen::aarch64::common::machine::{
+    function::MachineFunction, liveness::LiveRegMatrix,
+};
+use crate::codegen::aarch64::{frame_object::FrameIndexInfo, machine::register::*};
 
 pub struct Spiller<'a> {
     _func: &'a mut MachineFunction,
@@ -1,6 +1,6 @@
 use super::super::{frame_object::*, machine::register::*};
 use super::inst::*;
-use crate::codegen::common::machine::{builder::*, function::*, module::MachineModule};
+use crate::codegen::aarch64::common::machine::{builder::*, function::*, module::MachineModule};
 use crate::{ir::types::*, traits::pass::ModulePassTrait};
 
 pub struct ValidateFrameIndex {}
@@ -1,107 +1,43 @@
+#[path = "../common/mod.rs"]
+#[macro_use]
+pub mod common;
+
 pub mod asm;
 pub mod dag;
 pub mod exec;
 pub mod frame_object;
 pub mod machine;
 
+use crate::codegen::target::Target;
 use crate::{
-    codegen::common::{
+    codegen::aarch64::common::{
         dag::{combine, convert},
         machine::{branch_folding, module::MachineModule, phi_elimination},
     },
     ir,
     ir::module::Module,
-    ir::types::*,
     traits::pass::ModulePassManager,
 };
-
-impl TypeSize for Type {
-    fn size_in_byte(&self, tys: &Types) -> usize {
-        match self {
-            Type::i1 => 1,
-            Type::i8 => 1,
-            Type::i32 => 4,
-            Type::i64 => 8,
-            Type::f64 => 8,
-            Type::Array(id) => tys.compound_ty(*id).as_array().size_in_byte(tys),
-            Type::Struct(id) => tys.compound_ty(*id).as_struct().size_in_byte(tys),
-            Type::Pointer(_) => 8,
-            Type::Function(_) => unimplemented!(),
-            Type::Void => 0,
-        }
-    }
-
-    fn size_in_bits(&self, tys: &Types) -> usize {
-        self.size_in_byte(tys) * 8
-    }
-
-    fn align_in_byte(&self, tys: &Types) -> usize {
-        match self {
-            Type::i1 => 1,
-            Type::i8 => 1,
-            Type::i32 => 4,
-            Type::i64 => 8,
-            Type::f64 => 8,
-            Type::Array(id) => tys.compound_ty(*id).as_array().align_in_byte(tys),
-            Type::Struct(id) => tys.compound_ty(*id).as_struct().align_in_byte(tys),
-            Type::Pointer(_) => 8,
-            Type::Function(_) => unimplemented!(),
-            Type::Void => 0,
-        }
-    }
-}
-
-const MAX_ALIGN: usize = 16;
-
-impl TypeSize for ArrayType {
-    fn size_in_byte(&self, tys: &Types) -> usize {
-        self.elem_ty.size_in_byte(tys) * self.len
-    }
-
-    fn size_in_bits(&self, tys: &Types) -> usize {
-        self.size_in_byte(tys) * 8
-    }
-
-    fn align_in_byte(&self, tys: &Types) -> usize {
-        let size = self.size_in_byte(tys);
-        let align = self.elem_ty.align_in_byte(tys);
-        if size > MAX_ALIGN {
-            MAX_ALIGN
-        } else {
-            align
-        }
-    }
-}
-
-impl TypeSize for StructType {
-    fn size_in_byte(&self, _tys: &Types) -> usize {
-        self.size()
-    }
-
-    fn size_in_bits(&self, tys: &Types) -> usize {
-        self.size_in_byte(tys) * 8
-    }
-
-    fn align_in_byte(&self, _tys: &Types) -> usize {
-        self.align()
-    }
-}
+use target_lexicon::Triple;
 
 pub fn standard_conversion_into_machine_module(mut module: Module) -> MachineModule {
     ir::dce::DeadCodeElimination::new().run_on_module(&mut module);
     ir::merge_ret::MergeReturns::new().run_on_module(&mut module);
     // ir::const_folding::ConstantFolding::new().run_on_module(&mut module);
     // ir::inst_combine::InstructionCombine::new().run_on_module(&mut module);
+    ir::lower_switch::LowerSwitch::new()
+        .without_jump_tables()
+        .run_on_module(&mut module);
     ir::codegen_prepare::CodegenPrepare::new().run_on_module(&mut module);
 
-    let mut module = crate::codegen::common::dag::convert::convert_module_to_dag_module(module);
-    crate::codegen::common::dag::combine::run(&mut module);
+    let mut module = common::dag::convert::convert_module_to_dag_module(module);
+    common::dag::combine::run(&mut module);
     // println!("Initial DAG:\n{:?}", module);
-    crate::codegen::arch::dag::legalize::run(&mut module);
-    crate::codegen::arch::dag::isel::run(&mut module);
+    crate::codegen::aarch64::dag::legalize::run(&mut module);
+    crate::codegen::aarch64::dag::isel::run(&mut module);
     // println!("Selected DAG:\n{:?}", module);
 
-    let mut machine_module = crate::codegen::common::dag::mc_convert::convert_module(module);
+    let mut machine_module = common::dag::mc_convert::convert_module(module);
 
     let mut pass_mgr = ModulePassManager::new();
     pass_mgr.add_pass(phi_elimination::PhiElimination::new());
@@ -116,3 +52,19 @@ pub fn standard_conversion_into_machine_module(mut module: Module) -> MachineMod
 
     machine_module
 }
+
+/// The AArch64 (AAPCS64) target.
+pub struct AArch64;
+
+impl Target for AArch64 {
+    fn triple(&self) -> Triple {
+        "aarch64-unknown-linux-gnu".parse().unwrap()
+    }
+
+    fn compile_to_asm(&self, module: Module) -> String {
+        let machine_module = standard_conversion_into_machine_module(module);
+        let mut printer = asm::print::MachineAsmPrinter::new();
+        printer.run_on_module(&machine_module);
+        printer.output
+    }
+}
@@ -1,12 +1,24 @@
-use crate::codegen::common::machine::{
+pub use super::super::arch::asm::assembler::RelocKind;
+use super::super::arch::{
+    asm::assembler::{patch_branch, ELF_TRIPLE},
+    machine::inst::MachineConstant,
+};
+use super::super::machine::{
     basic_block::{MachineBasicBlock, MachineBasicBlockId},
+    const_data::DataId,
     function::{MachineFunction, MachineFunctionId},
     inst::MachineInst,
     module::MachineModule,
 };
+use crate::ir::{
+    constant_pool::{ConstantArrayElement, ConstantId, ConstantKind},
+    global_val::{GlobalVariableId, Linkage},
+    types::TypeSize,
+    value::ImmediateValue,
+};
 use faerie::*;
 use id_arena::{Arena, Id};
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 use std::str::FromStr;
 use std::{fmt, fs::File, path::Path};
 
@@ -56,6 +68,16 @@ pub struct Labels {
     pub func_label: FxHashMap<MachineFunctionId, LabelId>,
     pub block_label: FxHashMap<MachineBasicBlockId, LabelId>,
     pub replace_disp32: Vec<(Offset, LabelId)>,
+    pub relocs: Vec<Relocation>,
+}
+
+/// A reference from a function to a symbol, resolved by the linker.
+#[derive(Debug, Clone)]
+pub struct Relocation {
+    pub at: Offset,
+    pub symbol: String,
+    pub kind: RelocKind,
+    pub addend: i32,
 }
 
 #[derive(Clone)]
@@ -68,37 +90,46 @@ impl<'a> Assembler<'a> {
         Self {
             module,
             labels: Labels::new(),
-            artifact: ArtifactBuilder::new(triple!("x86_64-unknown-unknown-unknown-elf"))
+            artifact: ArtifactBuilder::new(triple!(ELF_TRIPLE))
                 .name(module.name.to_owned())
                 .finish(),
         }
     }
 
     pub fn assemble(&mut self) {
+        let mut declared = FxHashSet::default();
+
         for (_, func) in &self.module.functions {
+            if func.is_internal || func.is_empty() {
+                continue;
+            }
             self.artifact
                 .declare(&func.name, Decl::function().global())
                 .unwrap();
+            declared.insert(func.name.clone());
         }
 
+        self.assemble_global_vars(&mut declared);
+        self.assemble_constants(&mut declared);
+
         let mut func_streams = FxHashMap::default();
 
         for (id, func) in &self.module.functions {
+            if func.is_internal || func.is_empty() {
+                continue;
+            }
             let mut func_asmer = FunctionAssembler::new(self.module, func, &mut self.labels);
             func_asmer.assemble();
             func_streams.insert(id, func_asmer.stream);
+            self.assemble_const_data(func, &mut declared);
         }
 
         for (off, label) in &self.labels.replace_disp32 {
             let label = self.labels.arena[*label].as_func_offset();
 
             if off.func_id() == label.func_id() {
-                let insert_pt = off.offset();
-                let label = label.offset();
-                func_streams
-                    .get_mut(&off.func_id())
-                    .unwrap()
-                    .insert_u32_le(insert_pt, (label as i32 - (off.offset() as i32 + 4)) as u32);
+                let stream = func_streams.get_mut(&off.func_id()).unwrap();
+                patch_branch(stream, off.offset(), label.offset());
                 continue;
             }
 
@@ -112,11 +143,130 @@ impl<'a> Assembler<'a> {
                 .unwrap();
         }
 
+        for reloc in &self.labels.relocs {
+            // Every data symbol is declared beforehand, so the rest are external functions.
+            if declared.insert(reloc.symbol.clone()) {
+                self.artifact
+                    .declare(&reloc.symbol, Decl::function_import())
+                    .unwrap();
+            }
+            self.artifact
+                .link_with(
+                    Link {
+                        from: self.module.functions[reloc.at.func_id()].name.as_str(),
+                        to: reloc.symbol.as_str(),
+                        at: reloc.at.offset() as u64,
+                    },
+                    reloc.kind.to_faerie(reloc.addend),
+                )
+                .unwrap();
+        }
+
         for (id, func) in &self.module.functions {
-            let stream = func_streams.remove(&id).unwrap();
+            if let Some(stream) = func_streams.remove(&id) {
+                self.artifact
+                    .define(func.name.as_str(), stream.bytes)
+                    .unwrap();
+            }
+        }
+    }
+
+    /// Places common global variables in `.bss`, and imports external ones.
+    fn assemble_global_vars(&mut self, declared: &mut FxHashSet<String>) {
+        for (_, g) in &self.module.global_vars.arena {
+            declared.insert(g.name.clone());
+            match g.linkage {
+                Linkage::Common => {
+                    let size = g.ty.size_in_byte(&self.module.types);
+                    let align = g.ty.align_in_byte(&self.module.types);
+                    self.artifact
+                        .declare(
+                            &g.name,
+                            Decl::data()
+                                .global()
+                                .writable()
+                                .with_align(Some(align as u64)),
+                        )
+                        .unwrap();
+                    self.artifact.define_zero_init(&g.name, size).unwrap();
+                }
+                Linkage::External => self.artifact.declare(&g.name, Decl::data_import()).unwrap(),
+            }
+        }
+    }
+
+    /// Places strings and arrays of the constant pool in `.rodata`.
+    fn assemble_constants(&mut self, declared: &mut FxHashSet<String>) {
+        for (id, _) in &self.module.const_pool.arena {
+            let name = constant_symbol(id);
             self.artifact
-                .define(func.name.as_str(), stream.bytes)
+                .declare(&name, Decl::data().local().read_only().with_align(Some(8)))
                 .unwrap();
+            declared.insert(name);
+        }
+
+        for (id, c) in &self.module.const_pool.arena {
+            let name = constant_symbol(id);
+            let (bytes, strings) = constant_bytes(&c.kind);
+            for (at, id) in strings {
+                self.artifact
+                    .link_with(
+                        Link {
+                            from: name.as_str(),
+                            to: constant_symbol(id).as_str(),
+                            at: at as u64,
+                        },
+                        RelocKind::Abs64.to_faerie(0),
+                    )
+                    .unwrap();
+            }
+            self.artifact.define(&name, bytes).unwrap();
+        }
+    }
+
+    /// Places constants and jump tables of `func` in `.rodata`. Must be called after `func` is
+    /// assembled, since jump tables refer to the offsets of its basic blocks.
+    fn assemble_const_data(&mut self, func: &MachineFunction, declared: &mut FxHashSet<String>) {
+        for (id, data) in func.const_data.id_and_data() {
+            let name = const_data_symbol(&id);
+            let bytes = const_data_bytes(data);
+            self.artifact
+                .declare(
+                    &name,
+                    Decl::data()
+                        .local()
+                        .read_only()
+                        .with_align(Some(bytes.len() as u64)),
+                )
+                .unwrap();
+            self.artifact.define(&name, bytes).unwrap();
+            declared.insert(name);
+        }
+
+        // Each entry holds the offset of a basic block relative to the start of the table.
+        for (id, blocks) in func.const_data.id_and_jump_tables() {
+            let name = const_data_symbol(&id);
+            self.artifact
+                .declare(&name, Decl::data().local().read_only().with_align(Some(4)))
+                .unwrap();
+            for (i, block) in blocks.iter().enumerate() {
+                let label = self.labels.block_label[block];
+                let offset = self.labels.arena[label].as_func_offset().offset();
+                self.artifact
+                    .link_with(
+                        Link {
+                            from: name.as_str(),
+                            to: func.name.as_str(),
+                            at: i as u64 * 4,
+                        },
+                        RelocKind::PcRel32.to_faerie((offset + i * 4) as i32),
+                    )
+                    .unwrap();
+            }
+            self.artifact
+                .define(&name, vec![0; blocks.len() * 4])
+                .unwrap();
+            declared.insert(name);
         }
     }
 
@@ -226,6 +376,7 @@ impl Labels {
             func_label: FxHashMap::default(),
             block_label: FxHashMap::default(),
             replace_disp32: vec![],
+            relocs: vec![],
         }
     }
 
@@ -261,6 +412,93 @@ impl Labels {
     pub fn add_disp32_to_replace(&mut self, off: Offset, dst: LabelId) {
         self.replace_disp32.push((off, dst))
     }
+
+    pub fn add_reloc(&mut self, at: Offset, symbol: String, kind: RelocKind, addend: i32) {
+        self.relocs.push(Relocation {
+            at,
+            symbol,
+            kind,
+            addend,
+        })
+    }
+}
+
+/// The bytes of a string or an array in the constant pool, along with the offsets in them where
+/// the addresses of strings have to be written.
+pub fn constant_bytes(kind: &ConstantKind) -> (Vec<u8>, Vec<(usize, ConstantId)>) {
+    let mut bytes = vec![];
+    let mut strings = vec![];
+    match kind {
+        ConstantKind::String(s) => {
+            bytes.extend(s.as_bytes());
+            bytes.push(0);
+        }
+        ConstantKind::Array(elems) => {
+            for elem in elems {
+                constant_array_element_bytes(&mut bytes, &mut strings, elem)
+            }
+        }
+    }
+    (bytes, strings)
+}
+
+fn constant_array_element_bytes(
+    bytes: &mut Vec<u8>,
+    strings: &mut Vec<(usize, ConstantId)>,
+    elem: &ConstantArrayElement,
+) {
+    match elem {
+        ConstantArrayElement::Array(elems) => {
+            for elem in elems {
+                constant_array_element_bytes(bytes, strings, elem)
+            }
+        }
+        ConstantArrayElement::String(id) => {
+            strings.push((bytes.len(), *id));
+            bytes.extend(&[0; 8]);
+        }
+        ConstantArrayElement::Immediate(imm) => match imm {
+            ImmediateValue::Int1(i) => bytes.push(*i as u8),
+            ImmediateValue::Int8(i) => bytes.extend(&i.to_le_bytes()),
+            ImmediateValue::Int16(i) => bytes.extend(&i.to_le_bytes()),
+            ImmediateValue::Int32(i) => bytes.extend(&i.to_le_bytes()),
+            ImmediateValue::Int64(i) => bytes.extend(&i.to_le_bytes()),
+            ImmediateValue::F32(f) => bytes.extend(&f.to_bits().to_le_bytes()),
+            ImmediateValue::F64(f) => bytes.extend(&f.to_bits().to_le_bytes()),
+        },
+    }
+}
+
+/// The bytes of a constant in a `ConstDataArena`.
+pub fn const_data_bytes(data: &MachineConstant) -> Vec<u8> {
+    match data {
+        MachineConstant::Int8(i) => i.to_le_bytes().to_vec(),
+        MachineConstant::Int16(i) => i.to_le_bytes().to_vec(),
+        MachineConstant::Int32(i) => i.to_le_bytes().to_vec(),
+        MachineConstant::Int64(i) => i.to_le_bytes().to_vec(),
+        MachineConstant::F32(f) => f.to_bits().to_le_bytes().to_vec(),
+        MachineConstant::F64(f) => f.to_bits().to_le_bytes().to_vec(),
+    }
+}
+
+/// The symbol of a global variable.
+pub fn global_var_symbol(module: &MachineModule, id: GlobalVariableId) -> String {
+    module.global_vars.arena[id].name.clone()
+}
+
+/// The symbol of a string or an array in the constant pool.
+pub fn constant_symbol(id: ConstantId) -> String {
+    format!(".L_const_{}", id.index())
+}
+
+/// The symbol of a constant or a jump table in a `ConstDataArena`.
+pub fn const_data_symbol(id: &DataId) -> String {
+    format!(".Lconst{}_{}", id.arena_id(), id.id())
+}
+
+/// The symbol of a function not defined in the module.
+pub fn function_symbol(name: &str) -> String {
+    name.replace('.', "_")
 }
 
 impl InstructionStream {
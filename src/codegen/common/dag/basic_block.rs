@@ -1,4 +1,4 @@
-use crate::codegen::common::dag::node::{Node, NodeId};
+use super::super::dag::node::{Node, NodeId};
 use crate::ir::types::Types;
 use id_arena::*;
 use rustc_hash::{FxHashMap, FxHashSet};
@@ -1,4 +1,4 @@
-use crate::codegen::common::dag::{
+use super::super::dag::{
     function::DAGFunction,
     module::DAGModule,
     node::{IRNode, IROpcode, NodeId},
@@ -104,7 +104,24 @@ fn run_on_function(func: &mut DAGFunction) {
             .into())
     .into();
 
-    let pats = vec![brcond, setcc, add, mul];
+    // (IMM op any) -> (any op IMM) for commutative bitwise ops
+    let bitwise: Pat = (ir(IROpcode::And)
+        .named("n")
+        .args(vec![any_imm().into(), any()])
+        | ir(IROpcode::Or)
+            .named("n")
+            .args(vec![any_imm().into(), any()])
+        | ir(IROpcode::Xor)
+            .named("n")
+            .args(vec![any_imm().into(), any()])
+            .into())
+    .generate(|m, c| {
+        c.arena[m["n"]].as_ir_mut().args.swap(0, 1);
+        m["n"]
+    })
+    .into();
+
+    let pats = vec![brcond, setcc, add, mul, bitwise];
 
     let mut replaced = ReplacedNodeMap::default();
     for &id in &func.dag_basic_blocks {
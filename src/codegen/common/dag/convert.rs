@@ -1,8 +1,8 @@
-use crate::codegen::arch::{
+use super::super::arch::{
     dag::convert::copy_reg_args,
     machine::register::{rc2ty, ty2rc},
 };
-use crate::codegen::common::{
+use super::super::{
     dag::{
         basic_block::{DAGBasicBlock, DAGBasicBlockId},
         function::DAGFunction,
@@ -194,7 +194,19 @@ fn convert_block_to_dag_block<'a>(mut ctx: BlockConversionContext<'a>) -> NodeId
                     IRNode::new(IROpcode::Call).args(args).ty(inst.ty).into(),
                 )
             }
-            Opcode::Add | Opcode::Sub | Opcode::Mul | Opcode::Div | Opcode::Rem | Opcode::Shl => {
+            Opcode::Add
+            | Opcode::Sub
+            | Opcode::Mul
+            | Opcode::Div
+            | Opcode::Rem
+            | Opcode::UDiv
+            | Opcode::URem
+            | Opcode::Shl
+            | Opcode::LShr
+            | Opcode::AShr
+            | Opcode::And
+            | Opcode::Or
+            | Opcode::Xor => {
                 let (lhs, rhs) = (
                     ctx.node_from_value(&inst.operand.args()[0]),
                     ctx.node_from_value(&inst.operand.args()[1]),
@@ -207,7 +219,14 @@ fn convert_block_to_dag_block<'a>(mut ctx: BlockConversionContext<'a>) -> NodeId
                         Opcode::Mul => IROpcode::Mul,
                         Opcode::Div => IROpcode::Div,
                         Opcode::Rem => IROpcode::Rem,
+                        Opcode::UDiv => IROpcode::UDiv,
+                        Opcode::URem => IROpcode::URem,
                         Opcode::Shl => IROpcode::Shl,
+                        Opcode::LShr => IROpcode::LShr,
+                        Opcode::AShr => IROpcode::AShr,
+                        Opcode::And => IROpcode::And,
+                        Opcode::Or => IROpcode::Or,
+                        Opcode::Xor => IROpcode::Xor,
                         _ => unreachable!(),
                     })
                     .args(vec![lhs, rhs])
@@ -215,13 +234,22 @@ fn convert_block_to_dag_block<'a>(mut ctx: BlockConversionContext<'a>) -> NodeId
                     .into(),
                 )
             }
-            Opcode::SIToFP | Opcode::FPToSI => {
+            Opcode::SIToFP
+            | Opcode::FPToSI
+            | Opcode::UIToFP
+            | Opcode::FPToUI
+            | Opcode::FPExt
+            | Opcode::FPTrunc => {
                 let arg = ctx.node_from_value(&inst.operand.args()[0]);
                 ctx.node_(
                     id,
                     IRNode::new(match inst.opcode {
                         Opcode::SIToFP => IROpcode::SIToFP,
                         Opcode::FPToSI => IROpcode::FPToSI,
+                        Opcode::UIToFP => IROpcode::UIToFP,
+                        Opcode::FPToUI => IROpcode::FPToUI,
+                        Opcode::FPExt => IROpcode::FPExt,
+                        Opcode::FPTrunc => IROpcode::FPTrunc,
                         _ => unreachable!(),
                     })
                     .args(vec![arg])
@@ -229,13 +257,14 @@ fn convert_block_to_dag_block<'a>(mut ctx: BlockConversionContext<'a>) -> NodeId
                     .into(),
                 )
             }
-            Opcode::Zext | Opcode::Sext | Opcode::Bitcast => {
+            Opcode::Zext | Opcode::Sext | Opcode::Trunc | Opcode::Bitcast => {
                 let arg = ctx.node_from_value(&inst.operand.args()[0]);
                 ctx.node_(
                     id,
                     IRNode::new(match inst.opcode {
                         Opcode::Sext => IROpcode::Sext,
                         Opcode::Zext => IROpcode::Zext,
+                        Opcode::Trunc => IROpcode::Trunc,
                         Opcode::Bitcast => IROpcode::Bitcast,
                         _ => unreachable!(),
                     })
@@ -244,6 +273,39 @@ fn convert_block_to_dag_block<'a>(mut ctx: BlockConversionContext<'a>) -> NodeId
                     .into(),
                 )
             }
+            Opcode::PtrToInt | Opcode::IntToPtr => {
+                // Pointers are plain integers in the DAG, so these become a
+                // zext, trunc or bitcast depending on the operand sizes
+                let from = ctx.func.get_value_type(&inst.operand.args()[0]);
+                let from_size = from.size_in_byte(&ctx.func.types);
+                let to_size = inst.ty.size_in_byte(&ctx.func.types);
+                let arg = ctx.node_from_value(&inst.operand.args()[0]);
+                ctx.node_(
+                    id,
+                    IRNode::new(if from_size < to_size {
+                        IROpcode::Zext
+                    } else if from_size > to_size {
+                        IROpcode::Trunc
+                    } else {
+                        IROpcode::Bitcast
+                    })
+                    .args(vec![arg])
+                    .ty(inst.ty)
+                    .into(),
+                )
+            }
+            Opcode::Select => {
+                let args = inst
+                    .operand
+                    .args()
+                    .iter()
+                    .map(|a| ctx.node_from_value(a))
+                    .collect();
+                ctx.node_(
+                    id,
+                    IRNode::new(IROpcode::Select).args(args).ty(inst.ty).into(),
+                )
+            }
             Opcode::Br => {
                 let block = ctx.node(ctx.block_map[&inst.operand.blocks()[0]].into());
                 ctx.node(IRNode::new(IROpcode::Br).args(vec![block]).into())
@@ -259,6 +321,17 @@ fn convert_block_to_dag_block<'a>(mut ctx: BlockConversionContext<'a>) -> NodeId
                 let br = ctx.node(IRNode::new(IROpcode::Br).args(vec![else_]).into());
                 br
             }
+            Opcode::Switch => {
+                // (Switch cond default_block case_0 block_0 case_1 block_1 ...)
+                let mut args = vec![ctx.node_from_value(&inst.operand.args()[0])];
+                for (i, block) in inst.operand.blocks().iter().enumerate() {
+                    if i > 0 {
+                        args.push(ctx.node_from_value(&inst.operand.args()[i]));
+                    }
+                    args.push(ctx.node(ctx.block_map[block].into()));
+                }
+                ctx.node(IRNode::new(IROpcode::Switch).args(args).into())
+            }
             Opcode::ICmp | Opcode::FCmp => {
                 let c = if inst.opcode == Opcode::ICmp {
                     ctx.node(inst.operand.int_cmp()[0].into())
@@ -307,11 +380,22 @@ fn convert_block_to_dag_block<'a>(mut ctx: BlockConversionContext<'a>) -> NodeId
 
         let may_live_out = !matches!(
             inst.opcode,
-            Opcode::Alloca | Opcode::Store | Opcode::Br | Opcode::CondBr | Opcode::Ret
+            Opcode::Alloca
+                | Opcode::Store
+                | Opcode::Br
+                | Opcode::CondBr
+                | Opcode::Switch
+                | Opcode::Ret
         );
         let must_make_chain = matches!(
             inst.opcode,
-            Opcode::Load | Opcode::Store | Opcode::Call | Opcode::Br | Opcode::CondBr | Opcode::Ret
+            Opcode::Load
+                | Opcode::Store
+                | Opcode::Call
+                | Opcode::Br
+                | Opcode::CondBr
+                | Opcode::Switch
+                | Opcode::Ret
         );
         let mut chain_made = false;
 
@@ -399,8 +483,10 @@ impl<'a> BlockConversionContext<'a> {
                 let imm = match imm {
                     ImmediateValue::Int1(i) => ImmediateKind::Int8(*i as i8),
                     ImmediateValue::Int8(i) => ImmediateKind::Int8(*i),
+                    ImmediateValue::Int16(i) => ImmediateKind::Int16(*i),
                     ImmediateValue::Int32(i) => ImmediateKind::Int32(*i),
                     ImmediateValue::Int64(i) => ImmediateKind::Int64(*i),
+                    ImmediateValue::F32(f) => ImmediateKind::F32(*f),
                     ImmediateValue::F64(f) => ImmediateKind::F64(*f),
                 };
                 self.node(imm.into())
@@ -547,6 +633,7 @@ impl<'a> BlockConversionContext<'a> {
             Node::IR(IRNode { ty, .. }) => Some(*ty),
             Node::MI(_) => None,
             Node::Operand(OperandNode::Imm(ImmediateKind::Int8(_))) => Some(Type::i8),
+            Node::Operand(OperandNode::Imm(ImmediateKind::Int16(_))) => Some(Type::i16),
             Node::Operand(OperandNode::Imm(ImmediateKind::Int32(_))) => Some(Type::i32),
             Node::Operand(OperandNode::Imm(ImmediateKind::Int64(_))) => Some(Type::i64),
             Node::Operand(OperandNode::Reg(id)) => {
@@ -1,5 +1,5 @@
-use crate::codegen::arch::{frame_object::*, machine::register::*};
-use crate::codegen::common::dag::{basic_block::*, node::*};
+use super::super::arch::{frame_object::*, machine::register::*};
+use super::super::dag::{basic_block::*, node::*};
 use crate::ir::{function::*, types::*};
 use id_arena::*;
 use std::fmt;
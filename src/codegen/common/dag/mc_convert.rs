@@ -1,10 +1,10 @@
-use super::node::*;
-use crate::codegen::arch::machine::register::ty2rc;
-use crate::codegen::common::machine::inst::*;
-use crate::codegen::common::{
+use super::super::arch::machine::register::ty2rc;
+use super::super::machine::inst::*;
+use super::super::{
     dag::{basic_block::*, function::*, module::*},
     machine::{basic_block::*, const_data::ConstDataArena, function::*, module::*},
 };
+use super::node::*;
 use crate::ir::types::*;
 use id_arena::*;
 use rustc_hash::FxHashMap;
@@ -21,6 +21,7 @@ pub struct ScheduleContext<'a> {
     pub iseq: &'a mut Vec<MachineInstId>,
     pub bb_map: &'a FxHashMap<DAGBasicBlockId, MachineBasicBlockId>,
     pub node2inst: &'a mut FxHashMap<NodeId, MachineInstId>,
+    pub const_data: &'a mut ConstDataArena,
 }
 
 pub fn convert_module(module: DAGModule) -> MachineModule {
@@ -62,6 +63,7 @@ pub fn convert_function(func_map: &FuncTyMap, func: DAGFunction) -> MachineFunct
 
     let mut inst_arena = InstructionArena::new();
     let mut node2inst = FxHashMap::default();
+    let mut const_data = ConstDataArena::new();
 
     for dag_bb_id in &func.dag_basic_blocks {
         let node = &func.dag_basic_block_arena[*dag_bb_id];
@@ -82,6 +84,7 @@ pub fn convert_function(func_map: &FuncTyMap, func: DAGFunction) -> MachineFunct
             iseq: &mut iseq,
             bb_map: &bb_map,
             node2inst: &mut node2inst,
+            const_data: &mut const_data,
         }
         .convert(entry);
 
@@ -100,7 +103,7 @@ pub fn convert_function(func_map: &FuncTyMap, func: DAGFunction) -> MachineFunct
         local_mgr: func.local_vars,
         regs_info: func.regs,
         frame_objects: None,
-        const_data: ConstDataArena::new(),
+        const_data,
         types: func.types.clone(),
     }
 }
@@ -1,4 +1,4 @@
-use crate::codegen::common::dag::function::*;
+use super::super::dag::function::*;
 use crate::ir::{constant_pool::ConstantPool, global_val::GlobalVariables, types::Types};
 use id_arena::*;
 use std::fmt;
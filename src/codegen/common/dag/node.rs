@@ -1,11 +1,9 @@
-use crate::codegen::{
-    arch::{
-        dag::node::MemKind,
-        frame_object::FrameIndexInfo,
-        machine::{inst::MachineOpcode, register::*},
-    },
-    common::{dag::basic_block::DAGBasicBlockId, types::MVType},
+use super::super::arch::{
+    dag::node::MemKind,
+    frame_object::FrameIndexInfo,
+    machine::{inst::MachineOpcode, register::*},
 };
+use super::super::{dag::basic_block::DAGBasicBlockId, types::MVType};
 use crate::ir::{constant_pool, global_val::GlobalVariableId, opcode::*, types::*};
 use id_arena::*;
 // use std::fmt;
@@ -57,8 +55,10 @@ pub enum OperandNode {
 #[derive(Debug, Clone, PartialEq, Copy)]
 pub enum ImmediateKind {
     Int8(i8),
+    Int16(i16),
     Int32(i32),
     Int64(i64),
+    F32(f32),
     F64(f64),
 }
 
@@ -165,17 +165,32 @@ impl ImmediateKind {
         }
     }
 
+    /// Returns the sign-extended value of an integer immediate.
+    pub fn to_i64(&self) -> Option<i64> {
+        match self {
+            Self::Int8(x) => Some(*x as i64),
+            Self::Int16(x) => Some(*x as i64),
+            Self::Int32(x) => Some(*x as i64),
+            Self::Int64(x) => Some(*x),
+            Self::F32(_) | Self::F64(_) => None,
+        }
+    }
+
     pub fn is_null(&self) -> bool {
-        matches!(self, Self::Int8(0) | Self::Int32(0) | Self::Int64(0))
+        matches!(
+            self,
+            Self::Int8(0) | Self::Int16(0) | Self::Int32(0) | Self::Int64(0)
+        ) || matches!(self, Self::F32(f) if *f == 0.0)
             || matches!(self, Self::F64(f) if *f == 0.0)
     }
 
     pub fn bits_within(&self, n: u32) -> Option<bool> {
         match self {
             Self::Int8(x) => Some((x << (8 - n)) >> (8 - n) == *x),
+            Self::Int16(x) => Some((x << (16 - n)) >> (16 - n) == *x),
             Self::Int32(x) => Some((x << (32 - n)) >> (32 - n) == *x),
             Self::Int64(x) => Some((x << (64 - n)) >> (64 - n) == *x),
-            Self::F64(_) => None,
+            Self::F32(_) | Self::F64(_) => None,
         }
     }
 }
@@ -199,23 +214,35 @@ pub enum IRNodeKind {
     Shl,
     AShr,
     LShr,
+    And,
+    Or,
+    Xor,
     Add,
     Sub,
     Mul,
     Div,
     Rem,
+    UDiv,
+    URem,
     SIToFP,
     FPToSI,
+    UIToFP,
+    FPToUI,
+    FPExt,
+    FPTrunc,
     Call,
     Phi,
+    Select,
     Setcc,
     BrCond,
     Brcc,
     FPBrcc,
     Br,
+    Switch,
     Ret,
     Sext,
     Zext,
+    Trunc,
     Bitcast,
     FCmp,
 
@@ -265,6 +292,10 @@ impl Into<CondKind> for ICmpKind {
             ICmpKind::Lt => CondKind::Lt,
             ICmpKind::Ge => CondKind::Ge,
             ICmpKind::Gt => CondKind::Gt,
+            ICmpKind::ULe => CondKind::ULe,
+            ICmpKind::ULt => CondKind::ULt,
+            ICmpKind::UGe => CondKind::UGe,
+            ICmpKind::UGt => CondKind::UGt,
         }
     }
 }
@@ -1,6 +1,6 @@
+use super::super::arch::machine::register::{ty2rc, RegisterClassKind as RC};
+use super::super::{machine::register::RegistersInfo, types::MVType};
 use super::node::*;
-use crate::codegen::arch::machine::register::{ty2rc, RegisterClassKind as RC};
-use crate::codegen::common::{machine::register::RegistersInfo, types::MVType};
 use crate::ir::types::Type;
 use id_arena::Arena;
 use rustc_hash::FxHashMap;
@@ -65,8 +65,10 @@ pub enum OperandKind {
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum Immediate {
     AnyInt8,
+    AnyInt16,
     AnyInt32,
     AnyInt64,
+    AnyF32,
     AnyF64,
     AnyInt32PowerOf2,
     Int32(i32),
@@ -285,6 +287,24 @@ pub const fn any_imm8() -> Pat {
     })
 }
 
+pub const fn any_i16_imm() -> OperandPat {
+    OperandPat {
+        name: "",
+        kind: OperandKind::Imm(Immediate::AnyInt16),
+        not: false,
+        generate: None,
+    }
+}
+
+pub const fn any_imm16() -> Pat {
+    Pat::Operand(OperandPat {
+        name: "",
+        kind: OperandKind::Imm(Immediate::AnyInt16),
+        not: false,
+        generate: None,
+    })
+}
+
 pub const fn any_i32_imm() -> OperandPat {
     OperandPat {
         name: "",
@@ -330,6 +350,24 @@ pub const fn any_i64_imm() -> OperandPat {
     }
 }
 
+pub const fn any_imm_f32() -> Pat {
+    Pat::Operand(OperandPat {
+        name: "",
+        kind: OperandKind::Imm(Immediate::AnyF32),
+        not: false,
+        generate: None,
+    })
+}
+
+pub const fn any_f32_imm() -> OperandPat {
+    OperandPat {
+        name: "",
+        kind: OperandKind::Imm(Immediate::AnyF32),
+        not: false,
+        generate: None,
+    }
+}
+
 pub const fn any_imm_f64() -> Pat {
     Pat::Operand(OperandPat {
         name: "",
@@ -817,12 +855,18 @@ fn matches(
                         OperandKind::Imm(Immediate::AnyInt8) => {
                             matches!(n, &OperandNode::Imm(ImmediateKind::Int8(_)))
                         }
+                        OperandKind::Imm(Immediate::AnyInt16) => {
+                            matches!(n, &OperandNode::Imm(ImmediateKind::Int16(_)))
+                        }
                         OperandKind::Imm(Immediate::AnyInt32) => {
                             matches!(n, &OperandNode::Imm(ImmediateKind::Int32(_)))
                         }
                         OperandKind::Imm(Immediate::AnyInt64) => {
                             matches!(n, &OperandNode::Imm(ImmediateKind::Int64(_)))
                         }
+                        OperandKind::Imm(Immediate::AnyF32) => {
+                            matches!(n, &OperandNode::Imm(ImmediateKind::F32(_)))
+                        }
                         OperandKind::Imm(Immediate::AnyF64) => {
                             matches!(n, &OperandNode::Imm(ImmediateKind::F64(_)))
                         }
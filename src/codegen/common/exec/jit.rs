@@ -0,0 +1,804 @@
+use super::arch::{
+    common::{
+        asm::assembler::{
+            const_data_bytes, const_data_symbol, constant_bytes, constant_symbol, function_symbol,
+            FunctionAssembler, Labels, RelocKind,
+        },
+        machine::{function::*, module::MachineModule},
+    },
+    exec::jit::{
+        apply_reloc, flush_icache, lazy_thunk, resolve_labels, stub, trampoline, STUB_SLOT,
+    },
+    standard_conversion_into_machine_module,
+};
+use crate::{
+    codegen::internal_function_names,
+    ir,
+    ir::{basic_block::BasicBlocks, global_val::Linkage, opcode::Instruction, types::*},
+};
+use id_arena::Arena;
+use mmap::{MapOption, MemoryMap};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::{
+    cell::RefCell,
+    ffi::CString,
+    os::raw::{c_char, c_void},
+    rc::Rc,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GenericValue {
+    Address(*mut u8),
+    Int1(bool),
+    Int8(i8),
+    Int16(i16),
+    Int32(i32),
+    Int64(i64),
+    F32(f32),
+    F64(f64),
+    None,
+}
+
+pub struct JITExecutor {
+    // Shared with the thunks of lazily compiled functions, which point to it. It's never
+    // borrowed while compiled code runs, so that a thunk can borrow it mutably.
+    jit: Rc<RefCell<JITCompiler>>,
+    machine_modules: Vec<MachineModule>,
+}
+
+pub struct JITCompiler {
+    /// Executable memory holding the code and data of compiled modules
+    memory: Vec<MemoryMap>,
+    functions: FxHashMap<MachineFunctionId, usize>, // id -> fn address
+    // name -> host fn address, and its type if registered by `JITExecutor::register_symbol`
+    symbols: FxHashMap<String, (u64, Option<FunctionType>)>,
+    stubs: FxHashMap<String, Vec<usize>>, // name -> address slots of its stubs
+    globals: FxHashMap<String, u64>,      // name -> global variable address
+    dlsym: bool,
+    /// Declarations of the IR modules whose functions are compiled on their first call
+    lazy_modules: Vec<ir::module::Module>,
+    lazy_functions: Vec<LazyFunction>, // thunk index -> function
+    pending: FxHashSet<String>,        // functions whose calls still go to their thunks
+}
+
+/// A function compiled on its first call, whose body is taken out of its lazy module.
+struct LazyFunction {
+    module: usize,
+    name: String,
+    body: Option<(BasicBlocks, Arena<Instruction>)>,
+}
+
+/// Code and data of a module laid out in a single buffer, before being mapped.
+#[derive(Default)]
+pub struct Image {
+    bytes: Vec<u8>,
+    symbols: FxHashMap<String, usize>, // symbol -> offset in bytes
+}
+
+impl JITExecutor {
+    pub fn new(module: ir::module::Module) -> Self {
+        let mut executor = Self {
+            jit: Rc::new(RefCell::new(JITCompiler::new())),
+            machine_modules: vec![],
+        };
+        executor.add_module(module);
+        executor
+    }
+
+    /// Like `new`, but compiles each function of `module` only when it's called for the first
+    /// time. See `add_module_lazily`.
+    pub fn new_lazy(module: ir::module::Module) -> Self {
+        let mut executor = Self {
+            jit: Rc::new(RefCell::new(JITCompiler::new())),
+            machine_modules: vec![],
+        };
+        executor.add_module_lazily(module);
+        executor
+    }
+
+    /// Compiles `module` and adds its functions. A function defined by a module added before is
+    /// replaced, and calls to it from the code compiled before go to the new definition. A
+    /// module can call the functions of the modules added before by declaring them. Global
+    /// variables of the same name are shared between modules.
+    pub fn add_module(&mut self, module: ir::module::Module) {
+        let machine_module = standard_conversion_into_machine_module(module);
+
+        for (id, func) in &machine_module.functions {
+            if !func.is_internal {
+                self.check_signature(&func.name, &function_type(&machine_module, id));
+            }
+        }
+
+        self.jit.borrow_mut().compile_module(&machine_module);
+        self.machine_modules.push(machine_module);
+    }
+
+    /// Adds the functions of `module` like `add_module`, but defers compiling each function
+    /// until its first call. Until then, calls to the function go to a thunk that runs the
+    /// code generation pipeline for the function alone, makes the calls go to the generated
+    /// code, and jumps to it. Functions that are never called are never compiled.
+    pub fn add_module_lazily(&mut self, mut module: ir::module::Module) {
+        // Compiling the declarations gives every function a stub and lays out the globals
+        let bodies = take_bodies(&mut module);
+        let machine_module = standard_conversion_into_machine_module(module.clone());
+
+        for (id, func) in &machine_module.functions {
+            if !func.is_internal {
+                self.check_signature(&func.name, &function_type(&machine_module, id));
+            }
+        }
+
+        let jit = Rc::as_ptr(&self.jit);
+        let mut compiler = self.jit.borrow_mut();
+        compiler.compile_module(&machine_module);
+        compiler.add_lazy_module(jit, module, bodies);
+        drop(compiler);
+        self.machine_modules.push(machine_module);
+    }
+
+    /// Whether calls to `name` go to its machine code, that is, `name` is defined or
+    /// registered, and isn't waiting for its first call to be compiled.
+    pub fn is_compiled(&self, name: &str) -> bool {
+        self.jit.borrow().is_compiled(name)
+    }
+
+    /// Returns the latest definition of `name`, or its declaration if there's no definition.
+    pub fn find_function_by_name(&self, name: &str) -> Option<MachineFunctionId> {
+        let mut decl = None;
+        for module in self.machine_modules.iter().rev() {
+            if let Some(id) = module.find_function_by_name(name) {
+                if !module.function_ref(id).is_empty() {
+                    return Some(id);
+                }
+                decl = decl.or(Some(id));
+            }
+        }
+        decl
+    }
+
+    pub fn run(&mut self, id: MachineFunctionId, args: Vec<GenericValue>) -> GenericValue {
+        let now = ::std::time::Instant::now();
+        let module = self.module_of(id);
+        let entry = self.jit.borrow().address_of(module, id);
+        let res = call(entry, &function_type(module, id).ret_ty, &args);
+        debug!(println!(
+            "duration: {:?}",
+            ::std::time::Instant::now().duration_since(now)
+        ));
+        res
+    }
+
+    /// Makes calls to the external function `name` go to `addr`, even from functions already
+    /// compiled. `ty` must agree with the declarations of `name` in the modules, including the
+    /// ones added later.
+    pub fn register_symbol(&mut self, name: &str, addr: *const u8, ty: FunctionType) {
+        self.check_signature(name, &ty);
+        self.jit
+            .borrow_mut()
+            .register_typed_symbol(name, addr as u64, ty)
+    }
+
+    /// Resolves external functions that aren't registered by looking them up in the process
+    /// with `dlsym`, which finds the functions of libc for example.
+    pub fn enable_dlsym(&mut self) {
+        self.jit.borrow_mut().enable_dlsym()
+    }
+
+    /// Returns the function `name` as a function pointer of type `F`, such as
+    /// `extern "C" fn(f64, i32) -> f64`. Returns `None` if there's no such function or its
+    /// signature doesn't agree with `F`.
+    pub fn get_function<F: JITFunction>(&self, name: &str) -> Option<F> {
+        let id = self.find_function_by_name(name)?;
+        self.jit.borrow().get_function(self.module_of(id), id)
+    }
+
+    fn module_of(&self, id: MachineFunctionId) -> &MachineModule {
+        self.machine_modules
+            .iter()
+            .find(|m| m.functions.get(id).is_some())
+            .unwrap()
+    }
+
+    /// Panics if `ty` doesn't agree with what the modules or the registered symbols say `name`
+    /// is, since the code calling `name` is compiled for its type.
+    fn check_signature(&self, name: &str, ty: &FunctionType) {
+        for module in &self.machine_modules {
+            if let Some(id) = module.find_function_by_name(name) {
+                assert!(
+                    same_signature(&function_type(module, id), ty),
+                    "the type of '{}' doesn't agree with its declaration",
+                    name
+                );
+            }
+        }
+        if let Some((_, Some(registered))) = self.jit.borrow().symbols.get(name) {
+            assert!(
+                same_signature(registered, ty),
+                "the type of '{}' doesn't agree with the registered symbol",
+                name
+            );
+        }
+    }
+}
+
+impl JITCompiler {
+    pub fn new() -> Self {
+        Self {
+            memory: vec![],
+            functions: FxHashMap::default(),
+            stubs: FxHashMap::default(),
+            globals: FxHashMap::default(),
+            dlsym: false,
+            lazy_modules: vec![],
+            lazy_functions: vec![],
+            pending: FxHashSet::default(),
+            symbols: {
+                let internal_names = internal_function_names();
+                let internals = vec![
+                    sericum_memset_p0i32_i32_ as u64,
+                    sericum_println_i32_ as _,
+                    sericum_print_i32_ as _,
+                    sericum_printch_i32_ as _,
+                    sericum_println_f64_ as _,
+                    sericum_print_f64_ as _,
+                    sericum_sin_f64_ as _,
+                    sericum_cos_f64_ as _,
+                    sericum_sqrt_f64_ as _,
+                    sericum_floor_f64_ as _,
+                    sericum_fabs_f64_ as _,
+                    sericum_i32_to_f64_i32_ as _,
+                    sericum_f64_to_i32_f64_ as _,
+                    sericum_malloc_i32_ as _,
+                ];
+                assert!(
+                    internal_names.len() == internals.len(),
+                    "unimplemented internal function"
+                );
+                internal_names
+                    .iter()
+                    .map(|n| n.to_string())
+                    .zip(internals.into_iter().map(|addr| (addr, None)))
+                    .into_iter()
+                    .collect::<FxHashMap<_, _>>()
+            },
+        }
+    }
+
+    /// Returns the compiled function `id` as `F` if the signatures agree.
+    pub fn get_function<F: JITFunction>(
+        &self,
+        module: &MachineModule,
+        id: MachineFunctionId,
+    ) -> Option<F> {
+        let base = module.types.base.borrow();
+        let ty = base.as_function_ty(module.function_ref(id).ty).unwrap();
+        let addr = self.address_of(module, id);
+        if F::matches(ty) {
+            Some(unsafe { F::from_addr(addr) })
+        } else {
+            None
+        }
+    }
+
+    /// Encodes `module` with the same assembler as object files, lays out the code and data in
+    /// one buffer, resolves the relocations against the address it gets mapped at, and maps it
+    /// executable.
+    pub fn compile_module(&mut self, module: &MachineModule) {
+        let mut labels = Labels::new();
+        let mut image = Image::default();
+        let mut func_offsets = FxHashMap::default();
+
+        for (id, func) in &module.functions {
+            if func.is_internal || func.is_empty() {
+                continue;
+            }
+            let mut func_asmer = FunctionAssembler::new(module, func, &mut labels);
+            func_asmer.assemble();
+            let offset = image.place_anonymous(func_asmer.stream.data(), 16);
+            func_offsets.insert(id, offset);
+        }
+
+        // Every function is called through a stub that jumps to the address placed in the stub.
+        // The address can be filled in later, so that functions can be replaced and host
+        // functions, which may be too far for a direct call, can be registered later.
+        let mut stubs = vec![];
+        let mut stub_offsets = FxHashMap::default();
+        for (id, func) in &module.functions {
+            let mut stub = stub();
+            stub.extend(&self.resolve_symbol(&func.name).to_le_bytes());
+            let offset = image.place(function_symbol(&func.name), &stub, 16);
+            // Taking the address of a function defined in the module refers to it by its name
+            image.symbols.insert(func.name.clone(), offset);
+            stubs.push((func.name.clone(), offset + STUB_SLOT));
+            stub_offsets.insert(id, offset);
+        }
+
+        resolve_labels(module, &labels, &mut image, &func_offsets, &stub_offsets);
+
+        let mut relocs = labels
+            .relocs
+            .iter()
+            .map(|r| {
+                let at = func_offsets[&r.at.func_id()] + r.at.offset();
+                (at, r.symbol.clone(), r.kind, r.addend)
+            })
+            .collect::<Vec<_>>();
+
+        for (id, c) in &module.const_pool.arena {
+            let (bytes, strings) = constant_bytes(&c.kind);
+            let offset = image.place(constant_symbol(id), &bytes, 8);
+            for (at, id) in strings {
+                relocs.push((offset + at, constant_symbol(id), RelocKind::Abs64, 0));
+            }
+        }
+
+        for (_, func) in &module.functions {
+            if func.is_internal || func.is_empty() {
+                continue;
+            }
+            for (data_id, data) in func.const_data.id_and_data() {
+                let bytes = const_data_bytes(data);
+                image.place(const_data_symbol(&data_id), &bytes, bytes.len());
+            }
+        }
+
+        // A global variable laid out by a module compiled before is shared
+        let mut globals = vec![];
+        for (_, g) in &module.global_vars.arena {
+            if g.linkage == Linkage::Common && !self.globals.contains_key(&g.name) {
+                let size = g.ty.size_in_byte(&module.types);
+                let align = g.ty.align_in_byte(&module.types);
+                globals.push((
+                    g.name.clone(),
+                    image.place(g.name.clone(), &vec![0; size], align),
+                ));
+            }
+        }
+
+        let memory = map_executable(&image.bytes);
+        let base = memory.data() as usize;
+
+        for (at, symbol, kind, addend) in relocs {
+            let s = match image.symbols.get(&symbol) {
+                Some(offset) => (base + offset) as u64,
+                None => *self
+                    .globals
+                    .get(&symbol)
+                    .unwrap_or_else(|| panic!("undefined symbol '{}'", symbol)),
+            };
+            let value = s as i64 + addend as i64;
+            let ptr = unsafe { memory.data().add(at) };
+            apply_reloc(kind, ptr, value, &symbol);
+        }
+        flush_icache(&memory);
+
+        for (name, offset) in stubs {
+            self.stubs.entry(name).or_default().push(base + offset);
+        }
+        for (name, offset) in globals {
+            self.globals.insert(name, (base + offset) as u64);
+        }
+        // Stubs compiled before go to the new definitions from now on
+        for (id, offset) in func_offsets {
+            let name = &module.function_ref(id).name;
+            self.functions.insert(id, base + offset);
+            self.pending.remove(name);
+            self.register_symbol(name, (base + offset) as u64);
+        }
+        self.memory.push(memory);
+    }
+
+    /// Makes calls to the functions of `bodies` go to thunks that compile them with the
+    /// declarations in `module`. `jit` must point to `self`, and stay valid while the thunks
+    /// may be called.
+    pub fn add_lazy_module(
+        &mut self,
+        jit: *const RefCell<JITCompiler>,
+        module: ir::module::Module,
+        bodies: Vec<(String, BasicBlocks, Arena<Instruction>)>,
+    ) {
+        let lazy_module = self.lazy_modules.len();
+        let mut image = Image::default();
+        let mut thunks = vec![];
+        for (name, basic_blocks, inst_table) in bodies {
+            let index = self.lazy_functions.len();
+            let thunk = lazy_thunk(jit, index);
+            thunks.push((name.clone(), image.place_anonymous(thunk.data(), 16)));
+            self.lazy_functions.push(LazyFunction {
+                module: lazy_module,
+                name,
+                body: Some((basic_blocks, inst_table)),
+            });
+        }
+        self.lazy_modules.push(module);
+
+        let memory = map_executable(&image.bytes);
+        let base = memory.data() as usize;
+        for (name, offset) in thunks {
+            self.register_symbol(&name, (base + offset) as u64);
+            self.pending.insert(name);
+        }
+        self.memory.push(memory);
+    }
+
+    /// Compiles the function of the thunk `index`, and returns its address. Only the
+    /// declarations of the lazy module are copied, to hold the body of the function.
+    fn compile_lazily(&mut self, index: usize) -> u64 {
+        let LazyFunction { module, name, body } = &mut self.lazy_functions[index];
+        let name = name.clone();
+        if let (true, Some((basic_blocks, inst_table))) =
+            (self.pending.contains(&name), body.take())
+        {
+            let mut module = self.lazy_modules[*module].clone();
+            let id = module.find_function(name.as_str()).unwrap();
+            let func = module.function_ref_mut(id);
+            func.basic_blocks = basic_blocks;
+            func.inst_table = inst_table;
+            self.compile_module(&standard_conversion_into_machine_module(module));
+        }
+        self.symbols[&name].0
+    }
+
+    pub fn is_compiled(&self, name: &str) -> bool {
+        self.symbols.contains_key(name) && !self.pending.contains(name)
+    }
+
+    /// The address to call the function `id` at. A function that isn't compiled by itself,
+    /// such as a lazily compiled one, is called through its stub.
+    fn address_of(&self, module: &MachineModule, id: MachineFunctionId) -> usize {
+        match self.functions.get(&id) {
+            Some(&addr) => addr,
+            None => {
+                let name = &module.function_ref(id).name;
+                let slot = self.stubs.get(name).and_then(|slots| slots.first());
+                slot.unwrap_or_else(|| panic!("'{}' is never compiled", name)) - STUB_SLOT
+            }
+        }
+    }
+
+    /// Makes calls to `name` go to `addr`. The type `name` was registered with, if any, is kept.
+    pub fn register_symbol(&mut self, name: &str, addr: u64) {
+        self.symbols
+            .entry(name.to_string())
+            .or_insert((addr, None))
+            .0 = addr;
+        for &slot in self.stubs.get(name).into_iter().flatten() {
+            unsafe { (slot as *mut u64).write_unaligned(addr) }
+        }
+    }
+
+    /// Like `register_symbol`, but also records `ty` for checking the modules added later.
+    pub fn register_typed_symbol(&mut self, name: &str, addr: u64, ty: FunctionType) {
+        self.register_symbol(name, addr);
+        self.symbols.get_mut(name).unwrap().1 = Some(ty);
+    }
+
+    pub fn enable_dlsym(&mut self) {
+        self.dlsym = true;
+        for (name, slots) in &self.stubs {
+            if self.symbols.contains_key(name) {
+                continue;
+            }
+            if let Some(addr) = lookup_dlsym(name) {
+                for &slot in slots {
+                    unsafe { (slot as *mut u64).write_unaligned(addr) }
+                }
+            }
+        }
+    }
+
+    fn resolve_symbol(&self, name: &str) -> u64 {
+        if let Some(&(addr, _)) = self.symbols.get(name) {
+            return addr;
+        }
+        if self.dlsym {
+            if let Some(addr) = lookup_dlsym(name) {
+                return addr;
+            }
+        }
+        sericum_unresolved_function_ as extern "C" fn() as usize as u64
+    }
+}
+
+impl Image {
+    /// Appends `bytes` aligned to `align` as `symbol`, and returns its offset.
+    pub fn place(&mut self, symbol: String, bytes: &[u8], align: usize) -> usize {
+        let offset = self.place_anonymous(bytes, align);
+        self.symbols.insert(symbol, offset);
+        offset
+    }
+
+    /// Appends `bytes` aligned to `align` without a symbol, and returns its offset.
+    pub fn place_anonymous(&mut self, bytes: &[u8], align: usize) -> usize {
+        let offset = (self.bytes.len() + align - 1) & !(align - 1);
+        self.bytes.resize(offset, 0);
+        self.bytes.extend(bytes);
+        offset
+    }
+
+    pub fn read_u32(&self, at: usize) -> u32 {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(&self.bytes[at..at + 4]);
+        u32::from_le_bytes(bytes)
+    }
+
+    pub fn write_u32(&mut self, at: usize, x: u32) {
+        self.bytes[at..at + 4].copy_from_slice(&x.to_le_bytes())
+    }
+}
+
+/// Looks up `name` in the objects loaded into the process, such as libc.
+fn lookup_dlsym(name: &str) -> Option<u64> {
+    extern "C" {
+        fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+    }
+    let name = CString::new(name).ok()?;
+    let addr = unsafe {
+        dlsym(
+            ::std::ptr::null_mut(), /* RTLD_DEFAULT */
+            name.as_ptr(),
+        )
+    };
+    if addr.is_null() {
+        None
+    } else {
+        Some(addr as u64)
+    }
+}
+
+fn function_type(module: &MachineModule, id: MachineFunctionId) -> FunctionType {
+    let base = module.types.base.borrow();
+    base.as_function_ty(module.function_ref(id).ty)
+        .unwrap()
+        .clone()
+}
+
+/// Whether the IR signatures `x` and `y` agree. Pointers agree regardless of what they point to.
+fn same_signature(x: &FunctionType, y: &FunctionType) -> bool {
+    let agree = |x: &Type, y: &Type| x == y || x.is_pointer() && y.is_pointer();
+    agree(&x.ret_ty, &y.ret_ty)
+        && x.params_ty.len() == y.params_ty.len()
+        && x.params_ty
+            .iter()
+            .zip(&y.params_ty)
+            .all(|(x, y)| agree(x, y))
+}
+
+/// Copies `bytes` into newly mapped memory that is readable, writable and executable.
+fn map_executable(bytes: &[u8]) -> MemoryMap {
+    let memory = MemoryMap::new(
+        bytes.len().max(1),
+        &[
+            MapOption::MapReadable,
+            MapOption::MapWritable,
+            MapOption::MapExecutable,
+        ],
+    )
+    .unwrap();
+    unsafe { ::std::ptr::copy_nonoverlapping(bytes.as_ptr(), memory.data(), bytes.len()) };
+    flush_icache(&memory);
+    memory
+}
+
+/// Calls the function at `f` with `args`, and returns what it returns as `ret_ty`.
+fn call(f: usize, ret_ty: &Type, args: &[GenericValue]) -> GenericValue {
+    let memory = map_executable(trampoline(f, args).data());
+    let entry = memory.data();
+
+    match ret_ty {
+        Type::i1 => {
+            // Only the lowest bit of a returned i1 is defined
+            let f: extern "C" fn() -> u8 = unsafe { ::std::mem::transmute(entry) };
+            GenericValue::Int1(f() & 1 != 0)
+        }
+        Type::i8 => {
+            let f: extern "C" fn() -> i8 = unsafe { ::std::mem::transmute(entry) };
+            GenericValue::Int8(f())
+        }
+        Type::i16 => {
+            let f: extern "C" fn() -> i16 = unsafe { ::std::mem::transmute(entry) };
+            GenericValue::Int16(f())
+        }
+        Type::i32 => {
+            let f: extern "C" fn() -> i32 = unsafe { ::std::mem::transmute(entry) };
+            GenericValue::Int32(f())
+        }
+        Type::i64 => {
+            let f: extern "C" fn() -> i64 = unsafe { ::std::mem::transmute(entry) };
+            GenericValue::Int64(f())
+        }
+        Type::f32 => {
+            let f: extern "C" fn() -> f32 = unsafe { ::std::mem::transmute(entry) };
+            GenericValue::F32(f())
+        }
+        Type::f64 => {
+            let f: extern "C" fn() -> f64 = unsafe { ::std::mem::transmute(entry) };
+            GenericValue::F64(f())
+        }
+        Type::Pointer(_) => {
+            let f: extern "C" fn() -> *mut u8 = unsafe { ::std::mem::transmute(entry) };
+            GenericValue::Address(f())
+        }
+        Type::Void => {
+            let f: extern "C" fn() = unsafe { ::std::mem::transmute(entry) };
+            f();
+            GenericValue::None
+        }
+        ty => unimplemented!("returning {:?}", ty),
+    }
+}
+
+/// Called by the thunks of lazily compiled functions.
+///
+/// # Safety
+/// `jit` must point to the compiler that made the thunk `index`. Compiled code only runs while
+/// no one borrows the compiler.
+pub unsafe extern "C" fn compile_lazily(jit: *const RefCell<JITCompiler>, index: usize) -> u64 {
+    (*jit).borrow_mut().compile_lazily(index)
+}
+
+/// Takes the bodies of the functions defined in `module` out, leaving their declarations.
+fn take_bodies(module: &mut ir::module::Module) -> Vec<(String, BasicBlocks, Arena<Instruction>)> {
+    let mut bodies = vec![];
+    for (_, func) in &mut module.functions {
+        if func.is_internal || func.is_empty() {
+            continue;
+        }
+        bodies.push((
+            func.name.clone(),
+            ::std::mem::replace(&mut func.basic_blocks, BasicBlocks::new()),
+            ::std::mem::replace(&mut func.inst_table, Arena::new()),
+        ));
+        func.analyses.clear();
+    }
+    bodies
+}
+
+/// A Rust type that is passed and returned in the same way as an IR type.
+pub trait NativeType {
+    fn matches(ty: &Type) -> bool;
+}
+
+macro_rules! impl_native_type {
+    ($($native:ty => $ty:pat),*) => {$(
+        impl NativeType for $native {
+            fn matches(ty: &Type) -> bool {
+                matches!(ty, $ty)
+            }
+        }
+    )*};
+}
+
+impl_native_type!(() => Type::Void, bool => Type::i1, i8 => Type::i8, i16 => Type::i16,
+                  i32 => Type::i32, i64 => Type::i64, f32 => Type::f32, f64 => Type::f64);
+
+impl<T> NativeType for *mut T {
+    fn matches(ty: &Type) -> bool {
+        ty.is_pointer()
+    }
+}
+
+impl<T> NativeType for *const T {
+    fn matches(ty: &Type) -> bool {
+        ty.is_pointer()
+    }
+}
+
+/// A function pointer type through which compiled functions can be called.
+pub trait JITFunction: Copy {
+    /// Whether a function of type `ty` can be called as `Self`.
+    fn matches(ty: &FunctionType) -> bool;
+
+    /// # Safety
+    /// `addr` must be the address of a function of a type that `matches`.
+    unsafe fn from_addr(addr: usize) -> Self;
+}
+
+macro_rules! impl_jit_function {
+    ($($arg:ident),*) => {
+        impl<R: NativeType, $($arg: NativeType),*> JITFunction for extern "C" fn($($arg),*) -> R {
+            fn matches(ty: &FunctionType) -> bool {
+                let mut _params = ty.params_ty.iter();
+                let params_match = true $(&& _params.next().map_or(false, $arg::matches))*;
+                R::matches(&ty.ret_ty)
+                    && params_match
+                    && _params.next().is_none()
+                    && ty.params_attr.values().all(|attr| !attr.byval)
+            }
+
+            unsafe fn from_addr(addr: usize) -> Self {
+                ::std::mem::transmute(addr)
+            }
+        }
+    };
+}
+
+impl_jit_function!();
+impl_jit_function!(A);
+impl_jit_function!(A, B);
+impl_jit_function!(A, B, C);
+impl_jit_function!(A, B, C, D);
+impl_jit_function!(A, B, C, D, E);
+impl_jit_function!(A, B, C, D, E, F);
+impl_jit_function!(A, B, C, D, E, F, G);
+impl_jit_function!(A, B, C, D, E, F, G, H);
+
+// Stubs of external functions that are neither registered nor found jump here
+pub extern "C" fn sericum_unresolved_function_() {
+    eprintln!("call to an unresolved external function");
+    ::std::process::abort()
+}
+
+// Internal function sericum.println.i32
+pub extern "C" fn sericum_println_i32_(i: i32) {
+    println!("{}", i);
+}
+
+// Internal function sericum.println.f64
+pub extern "C" fn sericum_print_i32_(i: i32) {
+    print!("{}", i);
+}
+
+// Internal function sericum.println.f64
+pub extern "C" fn sericum_println_f64_(f: f64) {
+    println!("{}", f);
+}
+
+// Internal function sericum.println.f64
+pub extern "C" fn sericum_print_f64_(f: f64) {
+    print!("{}", f);
+}
+
+// EXPERIMENTAL Internal function sericum.printch.i32
+pub extern "C" fn sericum_printch_i32_(ch: i32) {
+    print!("{}", ch as u8 as char);
+}
+
+// EXPERIMENTAL Internal function sericum.sin.f64
+pub extern "C" fn sericum_sin_f64_(x: f64) -> f64 {
+    x.sin()
+}
+
+// EXPERIMENTAL Internal function sericum.cos.f64
+pub extern "C" fn sericum_cos_f64_(x: f64) -> f64 {
+    x.cos()
+}
+
+// EXPERIMENTAL Internal function sericum.sqrt.f64
+pub extern "C" fn sericum_sqrt_f64_(x: f64) -> f64 {
+    x.sqrt()
+}
+
+// EXPERIMENTAL Internal function sericum.floor.f64
+pub extern "C" fn sericum_floor_f64_(x: f64) -> f64 {
+    x.floor()
+}
+
+// EXPERIMENTAL Internal function sericum.floor.f64
+pub extern "C" fn sericum_fabs_f64_(x: f64) -> f64 {
+    x.abs()
+}
+
+// EXPERIMENTAL Internal function sericum.floor.f64
+pub extern "C" fn sericum_f64_to_i32_f64_(x: f64) -> i32 {
+    x as i32
+}
+
+// EXPERIMENTAL Internal function sericum.floor.f64
+pub extern "C" fn sericum_i32_to_f64_i32_(x: i32) -> f64 {
+    x as f64
+}
+
+// EXPERIMENTAL Internal function sericum.sqrt.f64
+pub extern "C" fn sericum_malloc_i32_(i: i32) -> *mut i64 {
+    let mut vec = Vec::<u8>::with_capacity(i as usize);
+    unsafe {
+        vec.set_len(i as usize);
+    }
+    Box::into_raw(vec.into_boxed_slice()) as *mut i64
+}
+
+// EXPERIMENTAL Internal function sericum.memset.p0i32.i32
+pub extern "C" fn sericum_memset_p0i32_i32_(p: *mut i32, x: i32, count: i32) {
+    unsafe { ::std::ptr::write_bytes(p, x as u8, count as usize) }
+}
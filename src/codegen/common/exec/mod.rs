@@ -0,0 +1,5 @@
+// Shared by the targets with a JIT: the `exec` module of each mounts this tree as its own
+// `common`, so that `arch` below always refers to the arch that includes it.
+use super::super as arch;
+
+pub mod jit;
@@ -1,6 +1,6 @@
 // use super::{module::*, opcode::*, value::*};
-use crate::codegen::arch::machine::inst::*;
-use crate::codegen::arch::machine::register::{
+use super::super::arch::machine::inst::*;
+use super::super::arch::machine::register::{
     PhysRegSet, RegisterId, TargetRegisterTrait, VirtOrPhys, CALLEE_SAVED_REGS,
 };
 use crate::traits::basic_block::*;
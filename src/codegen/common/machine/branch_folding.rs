@@ -1,7 +1,7 @@
-use super::basic_block::MachineBasicBlockId;
-use crate::codegen::common::machine::{
+use super::super::machine::{
     builder::*, function::MachineFunction, inst::*, module::MachineModule,
 };
+use super::basic_block::MachineBasicBlockId;
 use crate::traits::basic_block::BasicBlocksTrait;
 use crate::traits::pass::ModulePassTrait;
 use rustc_hash::{FxHashMap, FxHashSet};
@@ -114,6 +114,7 @@ impl BranchFolding {
                 }
                 // merge succ into block
                 f.body.basic_blocks.merge(&block, &succ);
+                f.const_data.replace_block(succ, block);
                 removed.insert(succ);
             }
             worklist = blocks_to_merge;
@@ -165,6 +166,7 @@ impl BranchFolding {
                     inst.replace_operand_block(block_to_remove, new_dst);
                 }
             }
+            f.const_data.replace_block(block_to_remove, new_dst);
 
             replaced.insert(block_to_remove, new_dst);
         }
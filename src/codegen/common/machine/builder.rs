@@ -1,5 +1,5 @@
-use crate::codegen::arch::machine::inst::*;
-use crate::codegen::common::machine::{
+use super::super::arch::machine::inst::*;
+use super::super::machine::{
     basic_block::*,
     function::*,
     liveness::{LiveRange, LiveRegMatrix, LiveSegment, ProgramPoint},
@@ -1,7 +1,6 @@
-use crate::{
-    codegen::{arch::machine::register::RegisterClassKind, common::machine::register::PhysReg},
-    ir::types::StructType,
-};
+use super::super::arch::machine::register::RegisterClassKind;
+use super::super::machine::register::PhysReg;
+use crate::ir::types::StructType;
 use rustc_hash::FxHashMap;
 
 pub trait CallingConv: Clone {
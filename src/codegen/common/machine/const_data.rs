@@ -1,4 +1,5 @@
-use crate::codegen::arch::machine::inst::MachineConstant;
+use super::super::arch::machine::inst::MachineConstant;
+use super::super::machine::basic_block::MachineBasicBlockId;
 use std::fmt;
 use std::ops::{Index, IndexMut};
 use std::sync::atomic::{self, AtomicUsize};
@@ -11,12 +12,18 @@ pub struct DataId {
 
 pub struct ConstDataArena {
     id: usize,
-    arena: Vec<MachineConstant>,
+    arena: Vec<ConstData>,
+}
+
+pub enum ConstData {
+    Constant(MachineConstant),
+    /// Relative offsets to the basic blocks, indexed by a `switch` condition.
+    JumpTable(Vec<MachineBasicBlockId>),
 }
 
 pub struct ConstDataArenaIter<'a> {
     id: usize,
-    arena: &'a Vec<MachineConstant>,
+    arena: &'a Vec<ConstData>,
     nth: usize,
 }
 
@@ -29,18 +36,51 @@ impl ConstDataArena {
     }
 
     pub fn alloc(&mut self, c: MachineConstant) -> DataId {
+        self.alloc_data(ConstData::Constant(c))
+    }
+
+    pub fn alloc_jump_table(&mut self, blocks: Vec<MachineBasicBlockId>) -> DataId {
+        self.alloc_data(ConstData::JumpTable(blocks))
+    }
+
+    fn alloc_data(&mut self, data: ConstData) -> DataId {
         let id = self.arena.len();
-        self.arena.push(c);
+        self.arena.push(data);
         DataId {
             arena_id: self.id,
             id,
         }
     }
 
+    /// Iterates over constants, skipping jump tables.
     pub fn id_and_data<'a>(&'a self) -> ConstDataArenaIter<'a> {
         ConstDataArenaIter::new(self.id, &self.arena)
     }
 
+    pub fn id_and_jump_tables<'a>(
+        &'a self,
+    ) -> impl Iterator<Item = (DataId, &'a [MachineBasicBlockId])> + 'a {
+        let arena_id = self.id;
+        self.arena
+            .iter()
+            .enumerate()
+            .filter_map(move |(id, data)| match data {
+                ConstData::JumpTable(blocks) => Some((DataId { arena_id, id }, blocks.as_slice())),
+                ConstData::Constant(_) => None,
+            })
+    }
+
+    /// Redirects every jump table entry pointing to `from` to `to`.
+    pub fn replace_block(&mut self, from: MachineBasicBlockId, to: MachineBasicBlockId) {
+        for data in &mut self.arena {
+            if let ConstData::JumpTable(blocks) = data {
+                for block in blocks.iter_mut().filter(|block| **block == from) {
+                    *block = to
+                }
+            }
+        }
+    }
+
     fn new_arena_id() -> usize {
         static ARENA_COUNTER: AtomicUsize = AtomicUsize::new(0);
         ARENA_COUNTER.fetch_add(1, atomic::Ordering::SeqCst)
@@ -58,7 +98,7 @@ impl DataId {
 }
 
 impl<'a> ConstDataArenaIter<'a> {
-    pub fn new(id: usize, arena: &'a Vec<MachineConstant>) -> Self {
+    pub fn new(id: usize, arena: &'a Vec<ConstData>) -> Self {
         Self { id, arena, nth: 0 }
     }
 }
@@ -67,22 +107,27 @@ impl<'a> Iterator for ConstDataArenaIter<'a> {
     type Item = (DataId, &'a MachineConstant);
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.nth += 1;
-        let id = self.nth - 1;
-        self.arena.get(id).and_then(|item| {
-            Some((
-                DataId {
-                    id,
-                    arena_id: self.id,
-                },
-                item,
-            ))
-        })
+        loop {
+            self.nth += 1;
+            let id = self.nth - 1;
+            match self.arena.get(id)? {
+                ConstData::Constant(item) => {
+                    return Some((
+                        DataId {
+                            id,
+                            arena_id: self.id,
+                        },
+                        item,
+                    ))
+                }
+                ConstData::JumpTable(_) => continue,
+            }
+        }
     }
 }
 
 impl Index<DataId> for ConstDataArena {
-    type Output = MachineConstant;
+    type Output = ConstData;
 
     fn index(&self, id: DataId) -> &Self::Output {
         assert_eq!(self.id, id.arena_id);
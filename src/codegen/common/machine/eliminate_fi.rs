@@ -1,4 +1,4 @@
-use crate::codegen::common::machine::{frame_object::*, function::*, module::*};
+use super::super::machine::{frame_object::*, function::*, module::*};
 use crate::traits::pass::ModulePassTrait;
 use rustc_hash::FxHashSet;
 
@@ -1,6 +1,6 @@
-use crate::codegen::arch::exec::roundup;
-use crate::codegen::arch::machine::inst::MachineOpcode;
-use crate::codegen::common::machine::function::MachineFunction;
+use super::super::arch::exec::roundup;
+use super::super::arch::machine::inst::MachineOpcode;
+use super::super::machine::function::MachineFunction;
 use crate::ir::types::*;
 use rustc_hash::FxHashMap;
 use std::cmp;
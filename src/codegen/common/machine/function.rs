@@ -1,8 +1,8 @@
 // use super::super::dag::function::*;
+use super::super::arch::machine::{frame_object::*, inst::*};
+use super::super::machine::const_data::ConstDataArena;
 use super::super::machine::register::*;
-use crate::codegen::arch::machine::{frame_object::*, inst::*};
-use crate::codegen::common::machine::const_data::ConstDataArena;
-use crate::codegen::common::{dag::function::*, machine::basic_block::*};
+use super::super::{dag::function::*, machine::basic_block::*};
 use crate::ir::types::*;
 use crate::traits::function::FunctionTrait;
 use id_arena::*;
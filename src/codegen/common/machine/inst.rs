@@ -1,4 +1,4 @@
-use crate::codegen::arch::machine::{
+use super::super::arch::machine::{
     frame_object::*,
     inst::MachineMemOperand,
     inst_def::TargetOpcode,
@@ -7,7 +7,7 @@ use crate::codegen::arch::machine::{
         VirtOrPhys,
     },
 };
-use crate::codegen::common::machine::{basic_block::*, const_data::DataId};
+use super::super::machine::{basic_block::*, const_data::DataId};
 use crate::ir::{constant_pool::ConstantId, global_val::GlobalVariableId, types::*};
 use id_arena::*;
 use rustc_hash::FxHashMap;
@@ -58,8 +58,10 @@ pub enum AddressKind {
 #[derive(Clone, Copy, PartialEq)]
 pub enum MachineConstant {
     Int8(i8),
+    Int16(i16),
     Int32(i32),
     Int64(i64),
+    F32(f32),
     F64(f64),
 }
 
@@ -459,8 +461,10 @@ impl MachineOperand {
         match self {
             MachineOperand::Branch(_) => None,
             MachineOperand::Constant(MachineConstant::Int8(_)) => Some(Type::i8),
+            MachineOperand::Constant(MachineConstant::Int16(_)) => Some(Type::i16),
             MachineOperand::Constant(MachineConstant::Int32(_)) => Some(Type::i32),
             MachineOperand::Constant(MachineConstant::Int64(_)) => Some(Type::i64),
+            MachineOperand::Constant(MachineConstant::F32(_)) => Some(Type::f32),
             MachineOperand::Constant(MachineConstant::F64(_)) => Some(Type::f64),
             MachineOperand::FrameIndex(fi) => Some(fi.ty),
             MachineOperand::Mem(mem) => mem.get_type(),
@@ -545,8 +549,10 @@ impl MachineConstant {
     pub fn size_in_byte(&self) -> usize {
         match self {
             MachineConstant::Int8(_) => 1,
+            MachineConstant::Int16(_) => 2,
             MachineConstant::Int32(_) => 4,
             MachineConstant::Int64(_) => 8,
+            MachineConstant::F32(_) => 4,
             MachineConstant::F64(_) => 8,
         }
     }
@@ -564,6 +570,13 @@ impl MachineConstant {
         }
     }
 
+    pub fn as_i16(&self) -> i16 {
+        match self {
+            Self::Int16(i) => *i,
+            _ => panic!(),
+        }
+    }
+
     pub fn as_i32(&self) -> i32 {
         match self {
             Self::Int32(i) => *i,
@@ -578,6 +591,13 @@ impl MachineConstant {
         }
     }
 
+    pub fn as_f32(&self) -> f32 {
+        match self {
+            Self::F32(f) => *f,
+            _ => panic!(),
+        }
+    }
+
     pub fn as_f64(&self) -> f64 {
         match self {
             Self::F64(f) => *f,
@@ -727,8 +747,10 @@ impl fmt::Debug for MachineConstant {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Int8(x) => write!(f, "i8 {}", x),
+            Self::Int16(x) => write!(f, "i16 {}", x),
             Self::Int32(x) => write!(f, "i32 {}", x),
             Self::Int64(x) => write!(f, "i64 {}", x),
+            Self::F32(x) => write!(f, "f32 {}", x),
             Self::F64(x) => write!(f, "f64 {}", x),
         }
     }
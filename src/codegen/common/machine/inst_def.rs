@@ -1,5 +1,5 @@
-use crate::codegen::arch::machine::inst::TargetOpcode;
-use crate::codegen::arch::machine::register::{PhysReg, RegisterClassKind};
+use super::super::arch::machine::inst::TargetOpcode;
+use super::super::arch::machine::register::{PhysReg, RegisterClassKind};
 use rustc_hash::FxHashMap;
 
 #[derive(Clone)]
@@ -44,6 +44,7 @@ pub enum TargetImmediate {
     I16,
     I32,
     I64,
+    F32,
     F64,
 }
 
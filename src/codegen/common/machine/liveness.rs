@@ -1,5 +1,5 @@
-use crate::codegen::arch::machine::{inst::*, register::*};
-use crate::codegen::common::machine::{basic_block::*, function::*};
+use super::super::arch::machine::{inst::*, register::*};
+use super::super::machine::{basic_block::*, function::*};
 use crate::util::allocator::{Raw, RawAllocator};
 use rustc_hash::FxHashMap;
 use std::cmp::Ordering;
@@ -216,7 +216,8 @@ impl LiveRegMatrix {
     pub fn merge_regs(&mut self, r1: PhysReg, r2: VirtReg) {
         self.virt_regs.remove(&r2);
         let r2_i = self.virt_reg_interval.remove(&r2).unwrap();
-        let r1_i = self.phys_reg_range.get_mut(r1).unwrap();
+        // r1 has no range yet if it's a callee saved register, whose liveness isn't tracked
+        let r1_i = self.phys_reg_range.get_or_create(r1);
         r1_i.unite_range(r2_i.range);
     }
 
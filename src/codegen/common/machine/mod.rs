@@ -1,7 +1,6 @@
 pub mod basic_block;
 pub mod branch_folding;
 pub mod builder;
-pub mod reg_coalescer;
 pub mod calling_conv;
 pub mod const_data;
 pub mod eliminate_fi;
@@ -12,6 +11,7 @@ pub mod inst_def;
 pub mod liveness;
 pub mod module;
 pub mod phi_elimination;
+pub mod reg_coalescer;
 pub mod regalloc;
 #[macro_use]
 pub mod register;
@@ -1,4 +1,4 @@
-use crate::codegen::common::machine::function::*;
+use super::super::machine::function::*;
 use crate::ir::{constant_pool::ConstantPool, global_val::GlobalVariables, types::*};
 use id_arena::*;
 use std::fmt;
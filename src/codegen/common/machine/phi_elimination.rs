@@ -1,5 +1,5 @@
+use super::super::machine::{builder::*, function::*, module::*};
 use super::inst::*;
-use crate::codegen::common::machine::{builder::*, function::*, module::*};
 use crate::traits::pass::ModulePassTrait;
 
 pub struct PhiElimination {}
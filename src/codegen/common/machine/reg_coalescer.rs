@@ -1,7 +1,5 @@
 use super::super::machine::register::RegisterId;
-use crate::codegen::common::machine::{
-    basic_block::*, function::*, inst::RegisterOperand, liveness::*,
-};
+use super::super::machine::{basic_block::*, function::*, inst::RegisterOperand, liveness::*};
 use rustc_hash::FxHashSet;
 use std::collections::VecDeque;
 
@@ -1,10 +1,10 @@
+use super::super::arch::machine::calc_spill_weight::calc_spill_weight;
+use super::super::arch::machine::live_interval_splitter::LiveIntervalSplitter;
+use super::super::arch::machine::{inst::*, spiller::Spiller};
+use super::super::arch::{frame_object::*, machine::register::*};
+use super::super::machine::reg_coalescer::coalesce_function;
+use super::super::machine::{builder::*, function::*, liveness::*, module::*};
 use crate::analysis::{dom_tree::DominatorTreeConstructor, loops::LoopsConstructor};
-use crate::codegen::arch::machine::calc_spill_weight::calc_spill_weight;
-use crate::codegen::arch::machine::live_interval_splitter::LiveIntervalSplitter;
-use crate::codegen::arch::machine::{inst::*, spiller::Spiller};
-use crate::codegen::arch::{frame_object::*, machine::register::*};
-use crate::codegen::common::machine::reg_coalescer::coalesce_function;
-use crate::codegen::common::machine::{builder::*, function::*, liveness::*, module::*};
 use crate::traits::pass::ModulePassTrait;
 use rustc_hash::FxHashSet;
 use std::collections::VecDeque;
@@ -233,14 +233,18 @@ impl RegisterAllocator {
             let liveness = &cur_func.body.basic_blocks.liveness
                 [&cur_func.body.inst_arena[call_inst_id].parent];
             let mut regs_that_may_interfere = &liveness.def | &liveness.live_in;
-            // remove registers defined by call like rbp, rsp, rax...
+            // remove registers defined by call like rbp, rsp, rax... (and the ones aliasing them)
+            let mut defined_by_call = PhysRegSet::new();
             for def in cur_func.body.inst_arena[call_inst_id]
                 .collect_defined_regs()
                 .iter()
                 .filter(|def| def.id.is_phys_reg())
             {
-                regs_that_may_interfere.remove(&def.id);
+                defined_by_call =
+                    defined_by_call | def.id.as_phys_reg().regs_sharing_same_register_file();
             }
+            regs_that_may_interfere
+                .retain(|r| !(r.is_phys_reg() && defined_by_call.has(r.as_phys_reg())));
             let range = LiveRange::new(vec![LiveSegment::new(call_inst_pp, call_inst_pp)]);
             for r in &regs_that_may_interfere {
                 if (r.is_virt_reg() && matrix.interferes_with_range(r.as_virt_reg(), &range))
@@ -1,4 +1,4 @@
-use crate::codegen::arch::machine::{inst::MachineInstId, register::*};
+use super::super::arch::machine::{inst::MachineInstId, register::*};
 use crate::util::count::Count;
 use id_arena::{Arena, Id};
 use rustc_hash::FxHashSet;
@@ -11,13 +11,6 @@ use std::{
     ops::{Index, IndexMut},
 };
 
-#[macro_export]
-macro_rules! to_phys {
-    ($($r:path),*) => {
-        vec![$(($r.as_phys_reg())),*]
-    };
-}
-
 pub trait TargetRegisterTrait: Copy + Clone {
     fn as_phys_reg(&self) -> PhysReg;
     fn sub_reg(&self) -> Option<PhysReg>;
@@ -26,7 +19,7 @@ pub trait TargetRegisterTrait: Copy + Clone {
 }
 
 #[derive(Debug, Clone)]
-pub struct PhysRegSet(pub [u64; 1]); // 8*8*1 > PHYS_REGISTERS_NUM
+pub struct PhysRegSet(pub [u64; 4]); // 8*8*4 > PHYS_REGISTERS_NUM
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash)]
 pub struct VirtReg(pub usize);
@@ -97,7 +90,7 @@ impl PhysReg {
     }
 
     pub fn is_callee_saved_reg(&self) -> bool {
-        CALLEE_SAVED_REGS.with(|rs| rs.0[0] & (1 << self.retrieve())) != 0
+        CALLEE_SAVED_REGS.with(|rs| rs.has(*self))
     }
 }
 
@@ -229,21 +222,23 @@ impl IndexMut<RegisterId> for RegisterArena {
 
 impl PhysRegSet {
     pub fn new() -> Self {
-        Self([0; 1])
+        Self([0; 4])
     }
 
     pub fn set<T: TargetRegisterTrait>(&mut self, r: T) {
-        self.0[0] |= 1 << r.as_phys_reg().retrieve();
+        let i = r.as_phys_reg().retrieve();
+        self.0[i / 64] |= 1 << (i % 64);
     }
 
     pub fn has<T: TargetRegisterTrait>(&self, r: T) -> bool {
-        (self.0[0] & (1 << r.as_phys_reg().retrieve())) != 0
+        let i = r.as_phys_reg().retrieve();
+        (self.0[i / 64] & (1 << (i % 64))) != 0
     }
 
     pub fn to_phys_set(&self) -> FxHashSet<PhysReg> {
         let mut set = FxHashSet::default();
         for i in 0..self.0.len() * 8 * 8 {
-            if (self.0[0] & (1 << i)) != 0 {
+            if (self.0[i / 64] & (1 << (i % 64))) != 0 {
                 set.insert(PhysReg(i));
             }
         }
@@ -251,27 +246,33 @@ impl PhysRegSet {
     }
 
     pub fn containing_callee_saved_regs(&self) -> PhysRegSet {
-        CALLEE_SAVED_REGS.with(|rs| Self([self.0[0] & rs.0[0]]))
+        CALLEE_SAVED_REGS.with(|rs| self.clone() & rs.clone())
     }
 
     pub fn unite(&mut self, rhs: &Self) {
-        self.0[0] |= rhs.0[0];
+        for (x, y) in self.0.iter_mut().zip(rhs.0.iter()) {
+            *x |= y
+        }
     }
 }
 
 impl BitOr for PhysRegSet {
     type Output = Self;
 
-    fn bitor(self, rhs: Self) -> Self {
-        Self([self.0[0] | rhs.0[0]])
+    fn bitor(mut self, rhs: Self) -> Self {
+        self.unite(&rhs);
+        self
     }
 }
 
 impl BitAnd for PhysRegSet {
     type Output = Self;
 
-    fn bitand(self, rhs: Self) -> Self {
-        Self([self.0[0] & rhs.0[0]])
+    fn bitand(mut self, rhs: Self) -> Self {
+        for (x, y) in self.0.iter_mut().zip(rhs.0.iter()) {
+            *x &= y
+        }
+        self
     }
 }
 
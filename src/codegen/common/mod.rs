@@ -1,3 +1,7 @@
+// Shared by every target: each arch module mounts this tree as its own `common`
+// so that `arch` below always refers to the arch that includes it.
+use super as arch;
+
 pub mod asm;
 pub mod dag;
 #[macro_use]
@@ -21,8 +21,10 @@ impl From<Type> for MVType {
             Type::Void => MVType::Void,
             Type::i1 => MVType::i1,
             Type::i8 => MVType::i8,
+            Type::i16 => MVType::i16,
             Type::i32 => MVType::i32,
             Type::i64 => MVType::i64,
+            Type::f32 => MVType::f32,
             Type::f64 => MVType::f64,
             Type::Pointer(_) => MVType::i64,
             Type::Array(_) | Type::Struct(_) | Type::Function(_) => MVType::Invalid,
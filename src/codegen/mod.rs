@@ -1,17 +1,96 @@
-#[macro_use]
-pub mod common;
+#[macro_export]
+macro_rules! to_phys {
+    ($($r:path),*) => {
+        vec![$(($r.as_phys_reg())),*]
+    };
+}
+
 #[cfg(feature = "aarch64")]
 pub mod aarch64;
 #[cfg(feature = "riscv64")]
 pub mod riscv64;
+pub mod target;
 #[cfg(feature = "x86_64")]
 pub mod x64;
-#[cfg(feature = "aarch64")]
-pub use aarch64 as arch;
-#[cfg(feature = "riscv64")]
-pub use riscv64 as arch;
-#[cfg(feature = "x86_64")]
-pub use x64 as arch;
+
+use crate::ir::types::*;
+
+// All the supported targets are LP64, so they share the same data layout.
+impl TypeSize for Type {
+    fn size_in_byte(&self, tys: &Types) -> usize {
+        match self {
+            Type::i1 => 1,
+            Type::i8 => 1,
+            Type::i16 => 2,
+            Type::i32 => 4,
+            Type::i64 => 8,
+            Type::f32 => 4,
+            Type::f64 => 8,
+            Type::Array(id) => tys.compound_ty(*id).as_array().size_in_byte(tys),
+            Type::Struct(id) => tys.compound_ty(*id).as_struct().size_in_byte(tys),
+            Type::Pointer(_) => 8,
+            Type::Function(_) => unimplemented!(),
+            Type::Void => 0,
+        }
+    }
+
+    fn size_in_bits(&self, tys: &Types) -> usize {
+        self.size_in_byte(tys) * 8
+    }
+
+    fn align_in_byte(&self, tys: &Types) -> usize {
+        match self {
+            Type::i1 => 1,
+            Type::i8 => 1,
+            Type::i16 => 2,
+            Type::i32 => 4,
+            Type::i64 => 8,
+            Type::f32 => 4,
+            Type::f64 => 8,
+            Type::Array(id) => tys.compound_ty(*id).as_array().align_in_byte(tys),
+            Type::Struct(id) => tys.compound_ty(*id).as_struct().align_in_byte(tys),
+            Type::Pointer(_) => 8,
+            Type::Function(_) => unimplemented!(),
+            Type::Void => 0,
+        }
+    }
+}
+
+const MAX_ALIGN: usize = 16;
+
+impl TypeSize for ArrayType {
+    fn size_in_byte(&self, tys: &Types) -> usize {
+        self.elem_ty.size_in_byte(tys) * self.len
+    }
+
+    fn size_in_bits(&self, tys: &Types) -> usize {
+        self.size_in_byte(tys) * 8
+    }
+
+    fn align_in_byte(&self, tys: &Types) -> usize {
+        let size = self.size_in_byte(tys);
+        let align = self.elem_ty.align_in_byte(tys);
+        if size > MAX_ALIGN {
+            MAX_ALIGN
+        } else {
+            align
+        }
+    }
+}
+
+impl TypeSize for StructType {
+    fn size_in_byte(&self, _tys: &Types) -> usize {
+        self.size()
+    }
+
+    fn size_in_bits(&self, tys: &Types) -> usize {
+        self.size_in_byte(tys) * 8
+    }
+
+    fn align_in_byte(&self, _tys: &Types) -> usize {
+        self.align()
+    }
+}
 
 thread_local! {
      pub static INTERNALS: Vec<String> = include!("internals").iter().map(|s| s.to_string()).collect();
@@ -1,5 +1,310 @@
-pub use crate::codegen::common::asm::assembler::InstAssembler;
+use crate::codegen::riscv64::common::asm::assembler::{
+    constant_symbol, function_symbol, global_var_symbol, InstructionStream,
+};
+pub use crate::codegen::riscv64::common::asm::assembler::{Assembler, InstAssembler, Offset};
+use crate::codegen::riscv64::machine::inst::*;
+use crate::codegen::riscv64::machine::inst_def::Encoding;
+use crate::codegen::riscv64::machine::register::{PhysReg, GR64};
+
+/// The target of the object files `Assembler` writes.
+pub const ELF_TRIPLE: &str = "riscv64-unknown-unknown-unknown-elf";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RelocKind {
+    /// `auipc ra` and `jalr ra` of a call, going through the PLT for external functions.
+    Call,
+    /// `auipc rd` and `addi rd` computing the address of a symbol.
+    PcRelHiLo,
+    /// 32-bit PC-relative address.
+    PcRel32,
+    /// 64-bit absolute address.
+    Abs64,
+}
+
+impl RelocKind {
+    pub fn to_faerie(self, addend: i32) -> faerie::Reloc {
+        // Relocation types of RISC-V ELF. R_RISCV_CALL patches any I-type instruction following
+        // the `auipc`, so it serves `addi` as well as `jalr`.
+        let reloc = match self {
+            Self::Call => 19,      // R_RISCV_CALL_PLT
+            Self::PcRelHiLo => 18, // R_RISCV_CALL
+            Self::PcRel32 => 57,   // R_RISCV_32_PCREL
+            Self::Abs64 => 2,      // R_RISCV_64
+        };
+        faerie::Reloc::Raw { reloc, addend }
+    }
+}
+
+/// Makes the `j` at `at` in `stream` jump to `dst`. Conditional branches are assembled as an
+/// inverted branch over a `j`, so only the `j` needs patching.
+pub fn patch_branch(stream: &mut InstructionStream, at: usize, dst: usize) {
+    let bytes = &stream.data()[at..at + 4];
+    let word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    let disp = dst as i32 - at as i32;
+    assert!((-(1 << 20)..1 << 20).contains(&disp), "jump out of range");
+    stream.insert_u32_le(at, word | j_imm(disp))
+}
+
+/// The immediate fields of a J-type instruction: imm[20|10:1|11|19:12].
+pub fn j_imm(disp: i32) -> u32 {
+    let disp = disp as u32;
+    (disp >> 20 & 1) << 31 | (disp >> 1 & 0x3ff) << 21 | (disp >> 11 & 1) << 20 | disp & 0xff000
+}
+
+/// Splits `value` into the immediates of `lui`/`auipc` and a following I-type instruction,
+/// whose immediate is sign-extended.
+pub fn hi_lo(value: i32) -> (u32, u32) {
+    let hi = (value as u32).wrapping_add(0x800) & 0xfffff000;
+    let lo = (value as u32 & 0xfff) << 20;
+    (hi, lo)
+}
 
 impl<'a> InstAssembler<'a> {
-    pub fn assemble(&mut self) {}
+    pub fn assemble(&mut self) {
+        match self.inst.opcode.encoding() {
+            Some(encoding) => self.gen(encoding),
+            None => unimplemented!("{:?}", self.inst.opcode),
+        }
+    }
+
+    fn gen(&mut self, encoding: Encoding) {
+        match encoding {
+            Encoding::Raw(word) => self.emit(word),
+            Encoding::R(word) => {
+                let (d, s1, s2) = (self.def(0), self.use_(0), self.use_(1));
+                self.emit(r_type(word, d, s1, s2))
+            }
+            Encoding::I(word) => {
+                let imm = const_value(self.inst.operand[1].as_constant());
+                assert!(is_imm12(imm), "immediate out of range");
+                let (d, s1) = (self.def(0), self.use_(0));
+                self.emit(i_type(word, d, s1, imm as i32))
+            }
+            Encoding::Shift(word) => {
+                let shamt = const_value(self.inst.operand[1].as_constant()) as u32 & 0x3f;
+                let (d, s1) = (self.def(0), self.use_(0));
+                self.emit(word | shamt << 20 | s1 << 15 | d << 7)
+            }
+            Encoding::Unary(word) => {
+                let (d, s1) = (self.def(0), self.use_(0));
+                self.emit(r_type(word, d, s1, 0))
+            }
+            Encoding::UnaryRs2(word) => {
+                let (d, s2) = (self.def(0), self.use_(0));
+                self.emit(r_type(word, d, 0, s2))
+            }
+            Encoding::FMv(word) => {
+                let (d, s) = (self.def(0), self.use_(0));
+                self.emit(r_type(word, d, s, s))
+            }
+            Encoding::Load(word) => {
+                let d = self.def(0);
+                self.gen_load(word, d, self.inst.operand[0].as_mem())
+            }
+            Encoding::Store(word) => {
+                let s = self.use_(1);
+                self.gen_store(word, s, self.inst.operand[0].as_mem())
+            }
+            Encoding::Branch(word) => {
+                let (s1, s2) = (self.use_(0), self.use_(1));
+                self.gen_branch(word | s2 << 20 | s1 << 15, 2)
+            }
+            Encoding::BranchZ(word) => {
+                let s1 = self.use_(0);
+                self.gen_branch(word | s1 << 15, 1)
+            }
+            Encoding::Jal => self.gen_jump(0),
+            Encoding::Call => self.gen_call(),
+            Encoding::Li => {
+                let imm = const_value(self.inst.operand[0].as_constant());
+                let d = self.def(0);
+                push_li(&mut self.stream, d, imm)
+            }
+            Encoding::La => self.gen_la(),
+            Encoding::AddiMem => {
+                let d = self.def(0);
+                let (base, off) = self.base_offset(self.inst.operand[0].as_mem());
+                if is_imm12(off as i64) {
+                    self.emit(i_type(0x13, d, base, off)) // addi d, base, off
+                } else {
+                    push_li(&mut self.stream, T0, off as i64);
+                    self.emit(r_type(0x33, d, base, T0)) // add d, base, t0
+                }
+            }
+            Encoding::FLi(word) => {
+                let bits = match self.inst.operand[0].as_constant() {
+                    MachineConstant::F32(f) => f.to_bits() as i32 as i64,
+                    c => c.as_f64().to_bits() as i64,
+                };
+                push_li(&mut self.stream, T0, bits);
+                let d = self.def(0);
+                self.emit(r_type(word, d, T0, 0))
+            }
+        }
+    }
+
+    fn gen_load(&mut self, word: u32, d: u32, mem: &MachineMemOperand) {
+        let (base, off) = self.long_offset(mem);
+        self.emit(i_type(word, d, base, off))
+    }
+
+    fn gen_store(&mut self, word: u32, s: u32, mem: &MachineMemOperand) {
+        let (base, off) = self.long_offset(mem);
+        self.emit(s_type(word, s, base, off))
+    }
+
+    /// The base and the 12-bit offset to access `mem` with. An offset that doesn't fit in 12 bits
+    /// is added to the base in t0 beforehand.
+    fn long_offset(&mut self, mem: &MachineMemOperand) -> (u32, i32) {
+        let (base, off) = self.base_offset(mem);
+        if is_imm12(off as i64) {
+            return (base, off);
+        }
+        assert!(base != T0, "t0 is needed to reach the offset");
+        let (hi, lo) = hi_lo(off);
+        self.emit(0x37 | hi | T0 << 7); // lui t0, %hi(off)
+        self.emit(r_type(0x33, T0, T0, base)); // add t0, t0, base
+        (T0, (lo as i32) >> 20)
+    }
+
+    /// Emits the branch `word` with the inverted condition over a `j` to the basic block, so that
+    /// the destination can be as far as a `j` reaches. The block is the operand `dst`.
+    fn gen_branch(&mut self, word: u32, dst: usize) {
+        // Flipping the lowest bit of funct3 inverts the condition. The offset is 8.
+        self.emit((word ^ 0x1000) | 0x400);
+        self.gen_jump(dst)
+    }
+
+    /// Emits `j` to the basic block of the operand `dst`, whose offset is filled in later.
+    fn gen_jump(&mut self, dst: usize) {
+        let func = self.function.id.unwrap();
+        let label = self
+            .labels
+            .get_label_for(func, self.inst.operand[dst].as_basic_block());
+        let at = Offset(func, self.stream.data().len());
+        self.labels.add_disp32_to_replace(at, label);
+        self.emit(0x6f) // jal zero, 0
+    }
+
+    fn gen_call(&mut self) {
+        let name = match &self.inst.operand[0] {
+            MachineOperand::Mem(MachineMemOperand::Address(AddressKind::FunctionName(n))) => n,
+            MachineOperand::Register(r) => {
+                let r = reg_code(&r.id.as_phys_reg());
+                return self.emit(0x000000e7 | r << 15); // jalr ra, 0(r)
+            }
+            e => unimplemented!("{:?}", e),
+        };
+
+        let callee = self
+            .module
+            .function_ref(self.module.find_function_by_name(name).unwrap());
+        let symbol = if callee.is_internal || callee.is_empty() {
+            function_symbol(name)
+        } else {
+            name.to_owned()
+        };
+        let at = Offset(self.function.id.unwrap(), self.stream.data().len());
+        self.labels.add_reloc(at, symbol, RelocKind::Call, 0);
+        self.emit(0x00000097); // auipc ra, 0
+        self.emit(0x000080e7) // jalr ra, 0(ra)
+    }
+
+    fn gen_la(&mut self) {
+        let symbol = match self.inst.operand[0].as_mem() {
+            MachineMemOperand::Address(AddressKind::FunctionName(n)) => n.to_owned(),
+            MachineMemOperand::Address(AddressKind::Global(id)) => {
+                global_var_symbol(self.module, *id)
+            }
+            MachineMemOperand::Address(AddressKind::Constant(id)) => constant_symbol(*id),
+            e => unimplemented!("{:?}", e),
+        };
+        let d = self.def(0);
+        let at = Offset(self.function.id.unwrap(), self.stream.data().len());
+        self.labels.add_reloc(at, symbol, RelocKind::PcRelHiLo, 0);
+        self.emit(0x17 | d << 7); // auipc d, 0
+        self.emit(i_type(0x13, d, d, 0)) // addi d, d, 0
+    }
+
+    fn base_offset(&self, mem: &MachineMemOperand) -> (u32, i32) {
+        match mem {
+            MachineMemOperand::BaseOff(r, off) => (reg_code(&r.id.as_phys_reg()), *off),
+            e => unimplemented!("{:?}", e),
+        }
+    }
+
+    /// The code of the `i`-th register defined.
+    fn def(&self, i: usize) -> u32 {
+        reg_code(&self.inst.def[i].id.as_phys_reg())
+    }
+
+    /// The code of the `i`-th operand, which is a register.
+    fn use_(&self, i: usize) -> u32 {
+        reg_code(&self.inst.operand[i].as_register().id.as_phys_reg())
+    }
+
+    fn emit(&mut self, word: u32) {
+        self.stream.push_u32_le(word)
+    }
+}
+
+/// Sets `imm` to `d` the way `li` expands: `lui` and `addiw` for a 32-bit value, and for a wider
+/// one, the upper bits shifted into place with `slli` followed by `addi` of the lower bits.
+pub fn push_li(stream: &mut InstructionStream, d: u32, imm: i64) {
+    let lo12 = (imm << 52) >> 52;
+    if imm as i32 as i64 == imm {
+        let hi20 = ((imm + 0x800) >> 12) as u32 & 0xfffff;
+        if hi20 == 0 {
+            return stream.push_u32_le(i_type(0x13, d, 0, lo12 as i32)); // addi d, zero, lo12
+        }
+        stream.push_u32_le(0x37 | hi20 << 12 | d << 7); // lui d, hi20
+        if lo12 != 0 {
+            stream.push_u32_le(i_type(0x1b, d, d, lo12 as i32)) // addiw d, d, lo12
+        }
+        return;
+    }
+
+    let hi52 = (imm as u64).wrapping_add(0x800) >> 12;
+    let shift = 12 + hi52.trailing_zeros();
+    let hi = ((hi52 >> (shift - 12)) << shift) as i64 >> shift;
+    push_li(stream, d, hi);
+    stream.push_u32_le(0x1013 | shift << 20 | d << 15 | d << 7); // slli d, d, shift
+    if lo12 != 0 {
+        stream.push_u32_le(i_type(0x13, d, d, lo12 as i32)) // addi d, d, lo12
+    }
+}
+
+/// The scratch register for long offsets and immediates.
+const T0: u32 = GR64::T0 as u32;
+
+/// The 5-bit register field, which is the same for every width of a register.
+pub fn reg_code(r: &PhysReg) -> u32 {
+    (r.retrieve() - r.reg_class() as usize) as u32
+}
+
+pub fn r_type(word: u32, d: u32, s1: u32, s2: u32) -> u32 {
+    word | s2 << 20 | s1 << 15 | d << 7
+}
+
+pub fn i_type(word: u32, d: u32, s1: u32, imm: i32) -> u32 {
+    word | (imm as u32 & 0xfff) << 20 | s1 << 15 | d << 7
+}
+
+pub fn s_type(word: u32, s2: u32, s1: u32, imm: i32) -> u32 {
+    let imm = imm as u32;
+    word | (imm >> 5 & 0x7f) << 25 | s2 << 20 | s1 << 15 | (imm & 0x1f) << 7
+}
+
+fn is_imm12(imm: i64) -> bool {
+    (-2048..2048).contains(&imm)
+}
+
+fn const_value(c: &MachineConstant) -> i64 {
+    match c {
+        MachineConstant::Int8(i) => *i as i64,
+        MachineConstant::Int16(i) => *i as i64,
+        MachineConstant::Int32(i) => *i as i64,
+        MachineConstant::Int64(i) => *i,
+        e => unimplemented!("{:?}", e),
+    }
 }
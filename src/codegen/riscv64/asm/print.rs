@@ -1,29 +1,31 @@
 use super::super::frame_object::FrameObjectsInfo;
 use super::super::machine::inst::*;
-use crate::codegen::common::machine::{
+use crate::codegen::riscv64::common::machine::{
     basic_block::MachineBasicBlockId,
     function::{InstIter, MachineFunction},
     module::MachineModule,
 };
-use crate::ir::{global_val::GlobalVariableId, types::TypeSize};
+use crate::ir::{
+    constant_pool, global_val::GlobalVariableId, types::TypeSize, value::ImmediateValue,
+};
 use rustc_hash::FxHashMap;
 
-pub struct MachineAsmPrinter<'a> {
+pub struct MachineAsmPrinter {
     pub output: String,
     cur_bb_id_base: usize,
-    global_var_name: FxHashMap<GlobalVariableId, &'a str>,
+    id_to_global_name: FxHashMap<GlobalVariableId, String>,
 }
 
-impl<'s> MachineAsmPrinter<'s> {
+impl MachineAsmPrinter {
     pub fn new() -> Self {
         Self {
             output: "".to_string(),
             cur_bb_id_base: 0,
-            global_var_name: FxHashMap::default(),
+            id_to_global_name: FxHashMap::default(),
         }
     }
 
-    pub fn run_on_module(&mut self, m: &'s MachineModule) {
+    pub fn run_on_module(&mut self, m: &MachineModule) {
         self.output.push_str("  .text\n");
 
         for (id, g) in &m.global_vars.arena {
@@ -31,7 +33,42 @@ impl<'s> MachineAsmPrinter<'s> {
             let align = g.ty.align_in_byte(&m.types);
             self.output
                 .push_str(format!("  .comm {},{},{}\n", g.name, size, align).as_str());
-            self.global_var_name.insert(id, g.name.as_str());
+            self.id_to_global_name.insert(id, g.name.clone());
+        }
+
+        fn f(output: &mut String, kind: &constant_pool::ConstantArrayElement) {
+            match kind {
+                constant_pool::ConstantArrayElement::Array(elems) => {
+                    for e in elems {
+                        f(output, e)
+                    }
+                }
+                constant_pool::ConstantArrayElement::String(id) => {
+                    output.push_str(format!("  .quad .L_const_{}\n", id.index()).as_str())
+                }
+                constant_pool::ConstantArrayElement::Immediate(ImmediateValue::Int32(i)) => {
+                    output.push_str(format!("  .word {}\n", i).as_str())
+                }
+                _ => todo!(),
+            }
+        }
+
+        for (id, c) in &m.const_pool.arena {
+            match &c.kind {
+                constant_pool::ConstantKind::Array(elems) => {
+                    self.output
+                        .push_str(format!(".L_const_{}:\n", id.index()).as_str());
+                    for elem in elems {
+                        f(&mut self.output, elem);
+                    }
+                }
+                constant_pool::ConstantKind::String(s) => {
+                    self.output
+                        .push_str(format!(".L_const_{}:\n", id.index()).as_str());
+                    self.output
+                        .push_str(format!("  .string \"{}\"\n", s).as_str())
+                }
+            }
         }
 
         for (_, func) in &m.functions {
@@ -40,7 +77,7 @@ impl<'s> MachineAsmPrinter<'s> {
     }
 
     fn run_on_function(&mut self, f: &MachineFunction) {
-        if f.is_internal {
+        if f.is_internal || f.is_empty() {
             return;
         }
 
@@ -49,64 +86,91 @@ impl<'s> MachineAsmPrinter<'s> {
 
         self.output.push_str(format!("{}:\n", f.name).as_str());
 
-        self.run_on_basic_blocks(f);
+        self.run_on_basic_blocks(f, f.frame_objects.as_ref().unwrap());
     }
 
-    fn run_on_basic_blocks(
-        &mut self,
-        // tys: &Types,
-        f: &MachineFunction,
-    ) {
+    fn run_on_basic_blocks(&mut self, f: &MachineFunction, fo: &FrameObjectsInfo) {
         for (id, _, inst_iter) in f.body.mbb_iter() {
             self.output
                 .push_str(format!("{}:\n", self.bb_id_to_label_id(&id)).as_str());
-            self.run_on_basic_block(inst_iter, &f.frame_objects.as_ref().unwrap());
+            self.run_on_basic_block(inst_iter, fo);
         }
         self.cur_bb_id_base += f.body.basic_blocks.arena.len();
     }
 
-    fn run_on_basic_block<'a>(
-        &mut self,
-        // tys: &Types,
-        // regs_info: &RegistersInfo,
-        inst_iter: InstIter<'a>,
-        fo: &FrameObjectsInfo,
-    ) {
+    fn run_on_basic_block<'a>(&mut self, inst_iter: InstIter<'a>, fo: &FrameObjectsInfo) {
         for (_, inst) in inst_iter {
             self.run_on_inst(inst, fo);
         }
     }
 
-    fn run_on_inst(
-        &mut self,
-        // tys: &Types,
-        // regs_info: &RegistersInfo,
-        inst: &MachineInst,
-        fo: &FrameObjectsInfo,
-    ) {
-        self.output.push_str("  ");
+    fn run_on_inst(&mut self, inst: &MachineInst, fo: &FrameObjectsInfo) {
+        // Floating-point immediates go through t0.
+        let name = match inst.opcode {
+            MachineOpcode::FLI_D | MachineOpcode::FLI_S => {
+                let (bits, fmv) = match inst.operand[0].as_constant() {
+                    MachineConstant::F32(f) => (f.to_bits() as i32 as i64, "fmv.w.x"),
+                    c => (c.as_f64().to_bits() as i64, "fmv.d.x"),
+                };
+                self.output.push_str(
+                    format!(
+                        "  li t0, {}\n  {} {}, t0\n",
+                        bits,
+                        fmv,
+                        inst.def[0].id.as_phys_reg().name()
+                    )
+                    .as_str(),
+                );
+                return;
+            }
+            MachineOpcode::ADDIm => "addi",
+            MachineOpcode::LA => "la",
+            _ => inst.opcode.inst_def().unwrap().name,
+        };
 
-        self.output.push_str(inst.opcode.inst_def().unwrap().name);
+        self.output.push_str("  ");
+        self.output.push_str(name);
         self.output.push(' ');
 
+        // Stores take the source first: `sd rs2, offset(rs1)`
+        let mut operands = inst.operand.iter().collect::<Vec<_>>();
+        if inst.opcode.is_store() {
+            operands.reverse();
+        }
+
         for (i, r) in inst.def.iter().enumerate() {
-            self.output.push_str(r.as_phys_reg().name());
+            self.output.push_str(r.id.as_phys_reg().name());
             if i != inst.def.len() - 1 {
                 self.output.push_str(", ");
             }
         }
 
-        if inst.def.len() > 0 && inst.operand.len() > 0 {
+        if inst.def.len() > 0 && operands.len() > 0 {
             self.output.push_str(", ");
         }
 
-        for (i, o) in inst.operand.iter().enumerate() {
-            self.operand2asm(fo, o);
-            if i != inst.operand.len() - 1 {
+        for (i, o) in operands.iter().enumerate() {
+            self.operand2asm(fo, inst.opcode, o);
+            if i != operands.len() - 1 {
                 self.output.push_str(", ");
             }
         }
 
+        // The default rounding mode is dynamic, while casts to integers truncate.
+        if matches!(
+            inst.opcode,
+            MachineOpcode::FCVT_W_D
+                | MachineOpcode::FCVT_WU_D
+                | MachineOpcode::FCVT_L_D
+                | MachineOpcode::FCVT_LU_D
+                | MachineOpcode::FCVT_W_S
+                | MachineOpcode::FCVT_WU_S
+                | MachineOpcode::FCVT_L_S
+                | MachineOpcode::FCVT_LU_S
+        ) {
+            self.output.push_str(", rtz");
+        }
+
         self.output.push('\n');
     }
 
@@ -114,7 +178,20 @@ impl<'s> MachineAsmPrinter<'s> {
         format!(".L{}", bb_id.index() + self.cur_bb_id_base)
     }
 
-    fn operand2asm(&mut self, fo: &FrameObjectsInfo, operand: &MachineOperand) {
+    fn operand2asm(
+        &mut self,
+        fo: &FrameObjectsInfo,
+        opcode: MachineOpcode,
+        operand: &MachineOperand,
+    ) {
+        // `addi rd, rs1, offset` rather than `addi rd, offset(rs1)`
+        let mem = |base: &RegisterOperand, off: i32| {
+            if opcode == MachineOpcode::ADDIm {
+                format!("{}, {}", base.id.as_phys_reg().name(), off)
+            } else {
+                format!("{}({})", off, base.id.as_phys_reg().name())
+            }
+        };
         match operand {
             MachineOperand::Branch(id) => self.output.push_str(self.bb_id_to_label_id(id).as_str()),
             MachineOperand::Constant(MachineConstant::Int32(i)) => {
@@ -123,22 +200,34 @@ impl<'s> MachineAsmPrinter<'s> {
             MachineOperand::Constant(MachineConstant::Int8(i)) => {
                 self.output.push_str(format!("{}", i).as_str())
             }
-            MachineOperand::Register(r) => self.output.push_str(r.as_phys_reg().name()),
+            MachineOperand::Constant(MachineConstant::Int16(i)) => {
+                self.output.push_str(format!("{}", i).as_str())
+            }
+            MachineOperand::Constant(MachineConstant::Int64(i)) => {
+                self.output.push_str(format!("{}", i).as_str())
+            }
+            MachineOperand::Register(r) => self.output.push_str(r.id.as_phys_reg().name()),
             MachineOperand::FrameIndex(i) => self
                 .output
                 .push_str(format!("{}", fo.offset(i.idx).unwrap()).as_str()),
-            MachineOperand::Mem(MachineMemOperand::FiReg(fi, r)) => self.output.push_str(
-                format!("{}({})", fo.offset(fi.idx).unwrap(), r.as_phys_reg().name()).as_str(),
-            ),
-            MachineOperand::Mem(MachineMemOperand::ImmReg(imm, r)) => self
+            MachineOperand::Mem(MachineMemOperand::BaseFi(r, fi)) => self
+                .output
+                .push_str(mem(r, fo.offset(fi.idx).unwrap()).as_str()),
+            MachineOperand::Mem(MachineMemOperand::BaseFiOff(r, fi, off)) => self
                 .output
-                .push_str(format!("{}({})", imm, r.as_phys_reg().name()).as_str()),
+                .push_str(mem(r, fo.offset(fi.idx).unwrap() + off).as_str()),
+            MachineOperand::Mem(MachineMemOperand::BaseOff(r, off)) => {
+                self.output.push_str(mem(r, *off).as_str())
+            }
             MachineOperand::Mem(MachineMemOperand::Address(AddressKind::FunctionName(name))) => {
                 self.output.push_str(name.replace('.', "_").as_str())
             }
             MachineOperand::Mem(MachineMemOperand::Address(AddressKind::Global(id))) => {
-                self.output.push_str(self.global_var_name[id])
+                self.output.push_str(self.id_to_global_name[id].as_str())
             }
+            MachineOperand::Mem(MachineMemOperand::Address(AddressKind::Constant(id))) => self
+                .output
+                .push_str(format!(".L_const_{}", id.index()).as_str()),
             _ => unimplemented!(),
         };
     }
@@ -1,34 +1,35 @@
-use super::node::*;
-use crate::codegen::arch::machine::register::*;
-use crate::codegen::common::dag::convert::ConvertToDAGNode;
+use crate::codegen::{
+    riscv64::common::dag::{
+        convert::BlockConversionContext,
+        node::{IRNode, IROpcode},
+    },
+    riscv64::machine::{
+        abi::{ArgLocation, LP64D},
+        register::*,
+    },
+};
 
-impl<'a> ConvertToDAGNode<'a> {
-    pub fn copy_reg_args(&mut self) {
-        for i in 0..self.func.get_params_len() {
-            if let Some(ty) = self.func.get_param_type(i) {
-                let arg_reg_class = match ty2rc(&ty) {
-                    Some(rc) => rc,
-                    None => continue,
-                };
-                let arg_reg = match arg_reg_class.get_nth_arg_reg(i) {
-                    Some(reg) => reg,
-                    None => continue,
-                };
-                let arg_reg = self.alloc_node(DAGNode::new_phys_reg(&self.regs_info, arg_reg));
-                let vreg = self.regs_info.new_virt_reg(arg_reg_class);
-                let vreg = self.alloc_node(DAGNode::new(
-                    NodeKind::Operand(OperandNodeKind::Register(vreg)),
-                    vec![],
-                    ty,
-                ));
-                let copy = self.alloc_node(DAGNode::new(
-                    NodeKind::IR(IRNodeKind::CopyToReg),
-                    vec![vreg, arg_reg],
-                    ty,
-                ));
-                self.make_chain(copy);
-                self.arg_regs.insert(i, vreg);
-            }
-        }
+pub fn copy_reg_args<'a>(ctx: &mut BlockConversionContext<'a>) {
+    let (locs, _) = LP64D::new().arg_locations(&ctx.func.types, ctx.func.ty);
+
+    // Arguments other than the ones in registers of their own class are stored into their stack
+    // slots by the prologue.
+    for (i, loc) in locs.into_iter().enumerate() {
+        let arg_reg = match loc {
+            ArgLocation::Reg(r) => r,
+            _ => continue,
+        };
+        let ty = ctx.func.get_param_type(i).unwrap();
+        let arg_reg_class = ty2rc(&ty).unwrap();
+        let arg_reg = ctx.node(ctx.regs.get_phys_reg(arg_reg).into());
+        let vreg = ctx.node(ctx.regs.new_virt_reg(arg_reg_class).into());
+        let copy = ctx.node(
+            IRNode::new(IROpcode::CopyToReg)
+                .args(vec![vreg, arg_reg])
+                .ty(ty)
+                .into(),
+        );
+        ctx.make_chain(copy);
+        ctx.arg_regs.insert(i, vreg);
     }
 }
@@ -1,182 +1,624 @@
-use super::super::machine::register::*;
-use crate::codegen::arch::dag::node::*;
-use crate::codegen::common::dag::{
-    function::{DAGFunction, DAGHeap},
+use crate::codegen::riscv64::common::dag::{
+    function::DAGFunction,
     module::DAGModule,
+    node::{CondKind, IRNode, IROpcode, ImmediateKind, MINode, Node, NodeId, OperandNode},
+    pat_match::{
+        any, any_block, any_cc, any_slot, bitcast, inst_select, ir, MatchContext, Pat,
+        ReplacedNodeMap,
+    },
 };
-use crate::{ir::types::*, traits::pass::ModulePassTrait, util::allocator::*};
-use defs::isel_pat;
-use rustc_hash::FxHashMap;
+use crate::codegen::riscv64::machine::register::{ty2rc, RegisterClassKind as RC, GR64};
+use crate::codegen::riscv64::{dag::node::MemKind, machine::inst::MachineOpcode as MO};
+use crate::ir::types::Type;
 
-impl ModulePassTrait for MISelector {
-    type M = DAGModule;
-
-    fn name(&self) -> &'static str {
-        "MachineInstSelector"
+pub fn run(module: &mut DAGModule) {
+    for (_, func) in &mut module.functions {
+        if func.is_internal {
+            continue;
+        }
+        run_on_function(func);
     }
+}
+
+fn run_on_function(func: &mut DAGFunction) {
+    // Memory operands are made by legalize.
+    let load: Pat = ir(IROpcode::Load)
+        .named("load")
+        .args(vec![any().named("mem")])
+        .generate(|m, c| {
+            let ty = c.arena[m["load"]].as_ir().ty;
+            let rc = ty2rc(&ty).unwrap();
+            let opcode = match rc {
+                RC::GR8 => MO::LB,
+                RC::GR16 => MO::LH,
+                RC::GR32 => MO::LW,
+                RC::GR64 => MO::LD,
+                RC::F32 => MO::FLW,
+                RC::F64 => MO::FLD,
+            };
+            mi(c, opcode, vec![m["mem"]], Some(rc))
+        })
+        .into();
+
+    let store: Pat = ir(IROpcode::Store)
+        .args(vec![any().named("mem"), any().named("src")])
+        .generate(|m, c| {
+            let rc = reg_class_of(c, m["src"]);
+            let src = reg(c, m["src"], rc);
+            let opcode = match rc {
+                RC::GR8 => MO::SB,
+                RC::GR16 => MO::SH,
+                RC::GR32 => MO::SW,
+                RC::GR64 => MO::SD,
+                RC::F32 => MO::FSW,
+                RC::F64 => MO::FSD,
+            };
+            mi(c, opcode, vec![m["mem"], src], None)
+        })
+        .into();
 
-    fn run_on_module(&mut self, module: &mut Self::M) {
-        self.run_on_module(module);
+    #[rustfmt::skip]
+    let bin: Pat = {
+        let bin = |opcode| ir(opcode).named("bin").args(vec![any().named("lhs"), any().named("rhs")]);
+        ((bin(IROpcode::Add) | bin(IROpcode::Sub)) | (bin(IROpcode::Mul) | bin(IROpcode::Div))
+         | (bin(IROpcode::Rem) | bin(IROpcode::UDiv)) | (bin(IROpcode::URem) | bin(IROpcode::And))
+         | (bin(IROpcode::Or) | bin(IROpcode::Xor)) | (bin(IROpcode::Shl) | bin(IROpcode::AShr))
+         | bin(IROpcode::LShr).into())
+        .generate(|m, c| {
+            let IRNode { opcode, ty, .. } = *c.arena[m["bin"]].as_ir();
+            match ty {
+                Type::f32 | Type::f64 => select_fbin(c, opcode, ty, m["lhs"], m["rhs"]),
+                _ => select_bin(c, opcode, ty, m["lhs"], m["rhs"]),
+            }
+        })
+        .into()
+    };
+
+    let cmp = |opcode| {
+        ir(opcode).args(vec![
+            any_cc().named("cc").into(),
+            any().named("lhs"),
+            any().named("rhs"),
+        ])
+    };
+    let setcc: Pat = cmp(IROpcode::Setcc)
+        .generate(|m, c| {
+            let cc = *c.arena[m["cc"]].as_operand().as_cc();
+            select_setcc(c, cc, m["lhs"], m["rhs"])
+        })
+        .into();
+    let fcmp: Pat = cmp(IROpcode::FCmp)
+        .generate(|m, c| {
+            let cc = *c.arena[m["cc"]].as_operand().as_cc();
+            select_fcmp(c, cc, m["lhs"], m["rhs"])
+        })
+        .into();
+
+    let brcc = |opcode| {
+        ir(opcode).args(vec![
+            any_cc().named("cc").into(),
+            any().named("lhs"),
+            any().named("rhs"),
+            any_block().named("dst").into(),
+        ])
+    };
+    let brcc_: Pat = brcc(IROpcode::Brcc)
+        .generate(|m, c| {
+            let cc = *c.arena[m["cc"]].as_operand().as_cc();
+            let (l, r) = (m["lhs"], m["rhs"]);
+            let (opcode, lhs, rhs) = match cc {
+                CondKind::Eq => (MO::BEQ, l, r),
+                CondKind::Ne => (MO::BNE, l, r),
+                CondKind::Lt => (MO::BLT, l, r),
+                CondKind::Ge => (MO::BGE, l, r),
+                CondKind::Gt => (MO::BLT, r, l),
+                CondKind::Le => (MO::BGE, r, l),
+                CondKind::ULt => (MO::BLTU, l, r),
+                CondKind::UGe => (MO::BGEU, l, r),
+                CondKind::UGt => (MO::BLTU, r, l),
+                CondKind::ULe => (MO::BGEU, r, l),
+                _ => unreachable!(),
+            };
+            let lhs = reg(c, lhs, RC::GR64);
+            if is_null(c, rhs) && matches!(opcode, MO::BEQ | MO::BNE) {
+                let opcode = if opcode == MO::BEQ {
+                    MO::BEQZ
+                } else {
+                    MO::BNEZ
+                };
+                return mi(c, opcode, vec![lhs, m["dst"]], None);
+            }
+            let rhs = reg(c, rhs, RC::GR64);
+            mi(c, opcode, vec![lhs, rhs, m["dst"]], None)
+        })
+        .into();
+    let fpbrcc: Pat = brcc(IROpcode::FPBrcc)
+        .generate(|m, c| {
+            let cc = *c.arena[m["cc"]].as_operand().as_cc();
+            // (FPBrcc une a b dst) -> (BEQZ (FEQ a b) dst)
+            if cc == CondKind::UNe {
+                let eq = select_fcmp(c, CondKind::UEq, m["lhs"], m["rhs"]);
+                return mi(c, MO::BEQZ, vec![eq, m["dst"]], None);
+            }
+            let cond = select_fcmp(c, cc, m["lhs"], m["rhs"]);
+            mi(c, MO::BNEZ, vec![cond, m["dst"]], None)
+        })
+        .into();
+    let brcond: Pat = ir(IROpcode::BrCond)
+        .args(vec![any().named("cond"), any_block().named("dst").into()])
+        .generate(|m, c| {
+            let cond = reg(c, m["cond"], RC::GR8);
+            mi(c, MO::BNEZ, vec![cond, m["dst"]], None)
+        })
+        .into();
+    let br: Pat = ir(IROpcode::Br)
+        .args(vec![any_block().named("dst").into()])
+        .generate(|m, c| mi(c, MO::J, vec![m["dst"]], None))
+        .into();
+
+    let fiaddr: Pat = ir(IROpcode::FIAddr)
+        .args(vec![any_slot().named("slot")])
+        .generate(|m, c| {
+            let s0 = c.arena.alloc(c.regs.get_phys_reg(GR64::S0).into());
+            let mem = c.arena.alloc(MemKind::BaseFi([s0, m["slot"]]).into());
+            mi(c, MO::ADDIm, vec![mem], Some(RC::GR64))
+        })
+        .into();
+    let addr: Pat = (ir(IROpcode::GlobalAddr).args(vec![any().named("a")])
+        | ir(IROpcode::ConstAddr).args(vec![any().named("a")]))
+    .generate(|m, c| {
+        let mem = c.arena.alloc(MemKind::Address(m["a"]).into());
+        mi(c, MO::LA, vec![mem], Some(RC::GR64))
+    })
+    .into();
+
+    let cvt = |opcode| ir(opcode).named("cvt").args(vec![any().named("x")]);
+    let cvt: Pat = ((cvt(IROpcode::SIToFP) | cvt(IROpcode::UIToFP))
+        | (cvt(IROpcode::FPToSI) | cvt(IROpcode::FPToUI))
+        | (cvt(IROpcode::FPExt) | cvt(IROpcode::FPTrunc)))
+    .generate(|m, c| {
+        let IRNode { opcode, ty, .. } = *c.arena[m["cvt"]].as_ir();
+        select_cvt(c, opcode, ty, m["x"])
+    })
+    .into();
+
+    let ext: Pat = ((ir(IROpcode::Sext).named("ext").args(vec![any().named("x")])
+        | ir(IROpcode::Zext).named("ext").args(vec![any().named("x")]))
+        | ir(IROpcode::Trunc)
+            .named("ext")
+            .args(vec![any().named("x")])
+            .into())
+    .generate(|m, c| {
+        let IRNode {
+            opcode,
+            ty,
+            ref args,
+            ..
+        } = *c.arena[m["ext"]].as_ir();
+        // The operand before selected tells whether it's i1.
+        let from_i1 = matches!(&c.arena[args[0]], Node::IR(IRNode { ty: Type::i1, .. }));
+        select_ext(c, opcode, ty, m["x"], from_i1)
+    })
+    .into();
+
+    let pats = vec![
+        load,
+        store,
+        bin,
+        setcc,
+        fcmp,
+        brcc_,
+        fpbrcc,
+        brcond,
+        br,
+        fiaddr,
+        addr,
+        cvt,
+        ext,
+        bitcast(any().named("arg")).generate(|m, _| m["arg"]),
+    ];
+
+    let mut replaced = ReplacedNodeMap::default();
+    for &id in &func.dag_basic_blocks {
+        let block = &func.dag_basic_block_arena[id];
+        let a = select_node(
+            &mut MatchContext {
+                arena: &mut func.node_arena,
+                regs: &func.regs,
+            },
+            &mut replaced,
+            &pats,
+            block.entry.unwrap(),
+        );
+        assert_eq!(block.entry.unwrap(), a);
     }
 }
 
-pub struct MISelector {
-    selected: FxHashMap<Raw<DAGNode>, Raw<DAGNode>>,
-}
+/// Integer operations. Values narrower than 64 bits are kept sign-extended in registers, so i32
+/// uses the W instructions, and i8 and i16 are computed in 64 bits and sign-extended again.
+fn select_bin(
+    c: &mut MatchContext,
+    opcode: IROpcode,
+    ty: Type,
+    lhs: NodeId,
+    rhs: NodeId,
+) -> NodeId {
+    let rc = ty2rc(&ty).unwrap();
 
-impl MISelector {
-    pub fn new() -> Self {
-        Self {
-            selected: FxHashMap::default(),
-        }
+    // Bitwise operations keep values sign-extended (and i1 zero or one) as they are.
+    match opcode {
+        IROpcode::And => return rr_or_ri(c, MO::AND, MO::ANDI, lhs, rhs, rc),
+        IROpcode::Or => return rr_or_ri(c, MO::OR, MO::ORI, lhs, rhs, rc),
+        IROpcode::Xor => return rr_or_ri(c, MO::XOR, MO::XORI, lhs, rhs, rc),
+        _ => {}
     }
 
-    pub fn run_on_module(&mut self, module: &mut DAGModule) {
-        for (_, func) in &mut module.functions {
-            if func.is_internal {
-                continue;
-            }
-            self.run_on_function(&module.types, func)
+    if rc == RC::GR32 || rc == RC::GR64 {
+        let w = rc == RC::GR32;
+        #[rustfmt::skip]
+        let (rr, ri) = match opcode {
+            IROpcode::Add  => if w { (MO::ADDW, Some(MO::ADDIW)) } else { (MO::ADD, Some(MO::ADDI)) },
+            IROpcode::Sub  => if w { (MO::SUBW, None) } else { (MO::SUB, None) },
+            IROpcode::Mul  => if w { (MO::MULW, None) } else { (MO::MUL, None) },
+            IROpcode::Div  => if w { (MO::DIVW, None) } else { (MO::DIV, None) },
+            IROpcode::Rem  => if w { (MO::REMW, None) } else { (MO::REM, None) },
+            IROpcode::UDiv => if w { (MO::DIVUW, None) } else { (MO::DIVU, None) },
+            IROpcode::URem => if w { (MO::REMUW, None) } else { (MO::REMU, None) },
+            IROpcode::Shl  => if w { (MO::SLLW, Some(MO::SLLIW)) } else { (MO::SLL, Some(MO::SLLI)) },
+            IROpcode::AShr => if w { (MO::SRAW, Some(MO::SRAIW)) } else { (MO::SRA, Some(MO::SRAI)) },
+            IROpcode::LShr => if w { (MO::SRLW, Some(MO::SRLIW)) } else { (MO::SRL, Some(MO::SRLI)) },
+            _ => unreachable!(),
+        };
+        // (Sub x imm) -> (ADDI x -imm)
+        if let (IROpcode::Sub, Some(i)) = (opcode, imm12(c, rhs).and_then(|i| simm12(-(i as i64))))
+        {
+            let add = if w { MO::ADDIW } else { MO::ADDI };
+            let lhs = reg(c, lhs, rc);
+            let i = c.arena.alloc(i.into());
+            return mi(c, add, vec![lhs, i], Some(rc));
         }
+        return match ri {
+            Some(ri) => rr_or_ri(c, rr, ri, lhs, rhs, rc),
+            None => {
+                let lhs = reg(c, lhs, rc);
+                let rhs = reg(c, rhs, rc);
+                mi(c, rr, vec![lhs, rhs], Some(rc))
+            }
+        };
     }
 
-    fn run_on_function(&mut self, tys: &Types, func: &mut DAGFunction) {
-        for bb_id in &func.dag_basic_blocks {
-            let bb = &func.dag_basic_block_arena[*bb_id];
-            self.run_on_node(tys, &func.regs_info, &mut func.dag_heap, bb.entry.unwrap());
+    let bits = match rc {
+        RC::GR8 => 8,
+        RC::GR16 => 16,
+        _ => unreachable!(),
+    };
+    match opcode {
+        IROpcode::Add | IROpcode::Sub | IROpcode::Mul | IROpcode::Shl | IROpcode::Div => {
+            let x = select_bin(c, opcode, Type::i64, lhs, rhs);
+            sext(c, x, bits, rc)
+        }
+        IROpcode::Rem | IROpcode::AShr => {
+            let x = select_bin(c, opcode, Type::i64, lhs, rhs);
+            reg_class(c, x, rc)
         }
+        IROpcode::UDiv | IROpcode::URem | IROpcode::LShr => {
+            let lhs = reg(c, lhs, rc);
+            let lhs = zext(c, lhs, bits);
+            let rhs = match opcode {
+                IROpcode::LShr => rhs,
+                _ => {
+                    let rhs = reg(c, rhs, rc);
+                    zext(c, rhs, bits)
+                }
+            };
+            let x = select_bin(c, opcode, Type::i64, lhs, rhs);
+            sext(c, x, bits, rc)
+        }
+        _ => unreachable!(),
     }
+}
 
-    fn run_on_node(
-        &mut self,
-        tys: &Types,
-        regs_info: &RegistersInfo,
-        heap: &mut DAGHeap,
-        mut node: Raw<DAGNode>,
-    ) -> Raw<DAGNode> {
-        if !node.may_contain_children() {
-            return node;
+fn select_fbin(
+    c: &mut MatchContext,
+    opcode: IROpcode,
+    ty: Type,
+    lhs: NodeId,
+    rhs: NodeId,
+) -> NodeId {
+    let rc = ty2rc(&ty).unwrap();
+    let d = ty == Type::f64;
+    #[rustfmt::skip]
+    let opcode = match opcode {
+        IROpcode::Add => if d { MO::FADD_D } else { MO::FADD_S },
+        IROpcode::Sub => if d { MO::FSUB_D } else { MO::FSUB_S },
+        IROpcode::Mul => if d { MO::FMUL_D } else { MO::FMUL_S },
+        IROpcode::Div => if d { MO::FDIV_D } else { MO::FDIV_S },
+        e => unimplemented!("{:?} for {:?}", e, ty),
+    };
+    let lhs = reg(c, lhs, rc);
+    let rhs = reg(c, rhs, rc);
+    mi(c, opcode, vec![lhs, rhs], Some(rc))
+}
+
+/// Compares integers into zero or one. Unsigned comparisons work on sign-extended values as they
+/// are since sign extension keeps the unsigned order.
+fn select_setcc(c: &mut MatchContext, cc: CondKind, lhs: NodeId, rhs: NodeId) -> NodeId {
+    let slt = |c: &mut MatchContext, lhs, rhs, unsigned: bool| {
+        let (rr, ri) = if unsigned {
+            (MO::SLTU, MO::SLTIU)
+        } else {
+            (MO::SLT, MO::SLTI)
+        };
+        rr_or_ri(c, rr, ri, lhs, rhs, RC::GR8)
+    };
+    let not = |c: &mut MatchContext, x| {
+        let one = c.arena.alloc(1.into());
+        mi(c, MO::XORI, vec![x, one], Some(RC::GR8))
+    };
+    match cc {
+        CondKind::Eq | CondKind::Ne => {
+            let x = if is_null(c, rhs) {
+                reg(c, lhs, RC::GR64)
+            } else {
+                rr_or_ri(c, MO::XOR, MO::XORI, lhs, rhs, RC::GR64)
+            };
+            let opcode = if cc == CondKind::Eq {
+                MO::SEQZ
+            } else {
+                MO::SNEZ
+            };
+            mi(c, opcode, vec![x], Some(RC::GR8))
+        }
+        CondKind::Lt => slt(c, lhs, rhs, false),
+        CondKind::Gt => slt(c, rhs, lhs, false),
+        CondKind::ULt => slt(c, lhs, rhs, true),
+        CondKind::UGt => slt(c, rhs, lhs, true),
+        CondKind::Ge | CondKind::UGe => {
+            let x = slt(c, lhs, rhs, cc == CondKind::UGe);
+            not(c, x)
         }
+        CondKind::Le | CondKind::ULe => {
+            let x = slt(c, rhs, lhs, cc == CondKind::ULe);
+            not(c, x)
+        }
+        _ => unreachable!(),
+    }
+}
 
-        if let Some(node) = self.selected.get(&node) {
-            return *node;
+fn select_fcmp(c: &mut MatchContext, cc: CondKind, lhs: NodeId, rhs: NodeId) -> NodeId {
+    let rc = match (&c.arena[lhs], &c.arena[rhs]) {
+        (
+            Node::Operand(OperandNode::Imm(_)),
+            Node::Operand(OperandNode::Imm(ImmediateKind::F32(_))),
+        )
+        | (Node::Operand(OperandNode::Imm(ImmediateKind::F32(_))), _) => RC::F32,
+        (Node::Operand(OperandNode::Imm(_)), _) => reg_class_of(c, rhs),
+        _ => reg_class_of(c, lhs),
+    };
+    let d = rc == RC::F64;
+    let lhs = reg(c, lhs, rc);
+    let rhs = reg(c, rhs, rc);
+    #[rustfmt::skip]
+    let (opcode, lhs, rhs) = match cc {
+        CondKind::UEq | CondKind::UNe => (if d { MO::FEQ_D } else { MO::FEQ_S }, lhs, rhs),
+        CondKind::ULt => (if d { MO::FLT_D } else { MO::FLT_S }, lhs, rhs),
+        CondKind::ULe => (if d { MO::FLE_D } else { MO::FLE_S }, lhs, rhs),
+        CondKind::UGt => (if d { MO::FLT_D } else { MO::FLT_S }, rhs, lhs),
+        CondKind::UGe => (if d { MO::FLE_D } else { MO::FLE_S }, rhs, lhs),
+        _ => unreachable!(),
+    };
+    let x = mi(c, opcode, vec![lhs, rhs], Some(RC::GR8));
+    if cc == CondKind::UNe {
+        let one = c.arena.alloc(1.into());
+        return mi(c, MO::XORI, vec![x, one], Some(RC::GR8));
+    }
+    x
+}
+
+fn select_cvt(c: &mut MatchContext, opcode: IROpcode, ty: Type, x: NodeId) -> NodeId {
+    let rc = ty2rc(&ty).unwrap();
+    let from = reg_class_of(c, x);
+    let x = reg(c, x, from);
+    let d = rc == RC::F64;
+    #[rustfmt::skip]
+    let cvt = match (opcode, from) {
+        (IROpcode::SIToFP, RC::GR64) => if d { MO::FCVT_D_L } else { MO::FCVT_S_L },
+        (IROpcode::SIToFP, _)        => if d { MO::FCVT_D_W } else { MO::FCVT_S_W },
+        (IROpcode::UIToFP, RC::GR64) => if d { MO::FCVT_D_LU } else { MO::FCVT_S_LU },
+        (IROpcode::UIToFP, RC::GR32) => if d { MO::FCVT_D_WU } else { MO::FCVT_S_WU },
+        (IROpcode::UIToFP, _) => {
+            let bits = if from == RC::GR8 { 8 } else { 16 };
+            let x = zext(c, x, bits);
+            let cvt = if d { MO::FCVT_D_WU } else { MO::FCVT_S_WU };
+            return mi(c, cvt, vec![x], Some(rc));
+        }
+        (IROpcode::FPToSI, _) | (IROpcode::FPToUI, _) => {
+            let signed = opcode == IROpcode::FPToSI;
+            #[rustfmt::skip]
+            let cvt = match (rc, from == RC::F64, signed) {
+                (RC::GR64, true, true)   => MO::FCVT_L_D,
+                (RC::GR64, true, false)  => MO::FCVT_LU_D,
+                (RC::GR64, false, true)  => MO::FCVT_L_S,
+                (RC::GR64, false, false) => MO::FCVT_LU_S,
+                (_, true, true)          => MO::FCVT_W_D,
+                (_, true, false)         => MO::FCVT_WU_D,
+                (_, false, true)         => MO::FCVT_W_S,
+                (_, false, false)        => MO::FCVT_WU_S,
+            };
+            let x = mi(c, cvt, vec![x], Some(if rc == RC::GR64 { RC::GR64 } else { RC::GR32 }));
+            return match rc {
+                RC::GR8 => sext(c, x, 8, rc),
+                RC::GR16 => sext(c, x, 16, rc),
+                _ => x,
+            };
         }
+        (IROpcode::FPExt, _) => MO::FCVT_D_S,
+        (IROpcode::FPTrunc, _) => MO::FCVT_S_D,
+        _ => unreachable!(),
+    };
+    mi(c, cvt, vec![x], Some(rc))
+}
 
-        let mut selected = isel_pat!(
-            // TODO: Refactoring
-            // (ir.Call _a) => { self.select_call(tys, regs_info, heap, node) }
-            (ir.Add a, b): i32 {
-                GPR a {
-                    imm12 b => (mi.ADDIW a, b)
-                    imm32 b => (mi.ADDW  a, (mi.LI b))
-                    GPR   b => (mi.ADDW  a, b) } }
-            (ir.Add a, b) {
-                GPR a {
-                    imm12 b => (mi.ADDI a, b)
-                    imm32 b => (mi.ADD  a, (mi.LI b))
-                    GPR   b => (mi.ADD  a, b) } }
-            (ir.Mul a, b): i32 {
-                GPR a {
-                    imm32 b => (mi.MULW a, (mi.LI b))
-                    GPR   b => (mi.MULW a, b) } }
-            (ir.Mul a, b) {
-                GPR a {
-                    imm32 b => (mi.MUL a, (mi.LI b))
-                    GPR   b => (mi.MUL a, b) } }
-            (ir.Div a, b): i32 {
-                GPR a {
-                    imm32 b => (mi.DIVW a, (mi.LI b))
-                    GPR   b => (mi.DIVW a, b) } }
-            (ir.Rem a, b): i32 {
-                GPR a {
-                    imm32 b => (mi.REMW a, (mi.LI b))
-                    GPR   b => (mi.REMW a, b) } }
-            (ir.Br a) => (mi.J a)
-            (ir.Shl a, b) {
-                GPR a {
-                    imm6 b => (mi.SLLI a, b)
-                }
-            }
-            (ir.Load a): i32 {
-                (ir.FIAddr b) a { mem32 b => (mi.LW [FiReg b, %s0]) }
-                (ir.GlobalAddr b) a => (mi.LW [Address b])
-                GPR a => (mi.LW [ImmReg $0, a])
-            }
-            // 64bit load
-            (ir.Load a) { GPR a => (mi.LD [ImmReg $0, a]) }
-            (ir.Store a, b) {
-                (ir.FIAddr c) a {
-                    mem32 c {
-                        imm32 b => (mi.SW (mi.LI b), [FiReg c, %s0])
-                        GPR   b => (mi.SW         b, [FiReg c, %s0])
-                    }
-                    mem64 c {
-                        GPR b => (mi.SD b, [FiReg c, %s0])
-                    }
-                }
-                (ir.GlobalAddr c) a {
-                    imm32 b => (mi.SW (mi.LI b), [Address c], %s1)
-                    GPR   b => (mi.SW         b, [Address c], %s1)
-                }
-                GPR a {
-                    imm32 b => (mi.SW (mi.LI b), [ImmReg $0, a])
-                    GPR: i32 b => (mi.SW b, [ImmReg $0, a])
-                    GPR b => (mi.SD b, [ImmReg $0, a])
-                }
+fn select_ext(
+    c: &mut MatchContext,
+    opcode: IROpcode,
+    ty: Type,
+    x: NodeId,
+    from_i1: bool,
+) -> NodeId {
+    let rc = ty2rc(&ty).unwrap();
+    let from = reg_class_of(c, x);
+    let x = reg(c, x, from);
+    match opcode {
+        // Already sign-extended, except for i1 which is zero or one
+        IROpcode::Sext if from_i1 => mi(c, MO::NEG, vec![x], Some(rc)),
+        IROpcode::Sext => reg_class(c, x, rc),
+        IROpcode::Zext => {
+            let x = match from {
+                RC::GR8 => zext(c, x, 8),
+                RC::GR16 => zext(c, x, 16),
+                RC::GR32 => zext(c, x, 32),
+                _ => x,
+            };
+            reg_class(c, x, rc)
+        }
+        IROpcode::Trunc => match ty {
+            Type::i1 => {
+                let one = c.arena.alloc(1.into());
+                mi(c, MO::ANDI, vec![x, one], Some(rc))
             }
-            (ir.FIAddr a) {
-                mem a => (mi.ADDI %s0, a)
+            Type::i8 => sext(c, x, 8, rc),
+            Type::i16 => sext(c, x, 16, rc),
+            Type::i32 => {
+                let zero = c.arena.alloc(0.into());
+                mi(c, MO::ADDIW, vec![x, zero], Some(rc))
             }
-            (ir.GlobalAddr a) => (mi.LA [Address a])
-            // (ir.CopyFromReg a) => (mi.Copy a)
-        );
+            _ => reg_class(c, x, rc),
+        },
+        _ => unreachable!(),
+    }
+}
 
-        self.selected.insert(node, selected);
+/// `rr lhs rhs`, or `ri lhs imm` if `rhs` is an immediate within 12 bits.
+fn rr_or_ri(c: &mut MatchContext, rr: MO, ri: MO, lhs: NodeId, rhs: NodeId, rc: RC) -> NodeId {
+    let lhs = reg(c, lhs, rc);
+    let imm = imm12(c, rhs).map(|i| match ri {
+        MO::SLLI | MO::SRLI | MO::SRAI => i & 63,
+        MO::SLLIW | MO::SRLIW | MO::SRAIW => i & 31,
+        _ => i,
+    });
+    match imm {
+        Some(i) => {
+            let i = c.arena.alloc(i.into());
+            mi(c, ri, vec![lhs, i], Some(rc))
+        }
+        None => {
+            let rhs = reg(c, rhs, rc);
+            mi(c, rr, vec![lhs, rhs], Some(rc))
+        }
+    }
+}
+
+/// Sign-extends the lowest `bits` bits of `x`.
+fn sext(c: &mut MatchContext, x: NodeId, bits: i32, rc: RC) -> NodeId {
+    let sh = c.arena.alloc((64 - bits).into());
+    let x = mi(c, MO::SLLI, vec![x, sh], Some(RC::GR64));
+    mi(c, MO::SRAI, vec![x, sh], Some(rc))
+}
 
-        if let Some(next) = node.next {
-            selected.next = Some(self.run_on_node(tys, regs_info, heap, next));
+/// Zero-extends the lowest `bits` bits of `x`.
+fn zext(c: &mut MatchContext, x: NodeId, bits: i32) -> NodeId {
+    if bits == 8 {
+        let mask = c.arena.alloc(0xff.into());
+        return mi(c, MO::ANDI, vec![x, mask], Some(RC::GR64));
+    }
+    let sh = c.arena.alloc((64 - bits).into());
+    let x = mi(c, MO::SLLI, vec![x, sh], Some(RC::GR64));
+    mi(c, MO::SRLI, vec![x, sh], Some(RC::GR64))
+}
+
+/// Materializes an immediate into a register.
+fn reg(c: &mut MatchContext, id: NodeId, rc: RC) -> NodeId {
+    match c.arena[id] {
+        Node::Operand(OperandNode::Imm(ImmediateKind::F64(_))) => {
+            mi(c, MO::FLI_D, vec![id], Some(RC::F64))
+        }
+        Node::Operand(OperandNode::Imm(ImmediateKind::F32(_))) => {
+            mi(c, MO::FLI_S, vec![id], Some(RC::F32))
         }
+        Node::Operand(OperandNode::Imm(_)) => mi(c, MO::LI, vec![id], Some(rc)),
+        _ => id,
+    }
+}
+
+fn reg_class(c: &mut MatchContext, x: NodeId, rc: RC) -> NodeId {
+    if reg_class_of(c, x) == rc {
+        return x;
+    }
+    let ty = match rc {
+        RC::GR8 => Type::i8,
+        RC::GR16 => Type::i16,
+        RC::GR32 => Type::i32,
+        _ => Type::i64,
+    };
+    c.arena
+        .alloc(IRNode::new(IROpcode::RegClass).args(vec![x]).ty(ty).into())
+}
+
+fn mi(c: &mut MatchContext, opcode: MO, args: Vec<NodeId>, rc: Option<RC>) -> NodeId {
+    let mut node = MINode::new(opcode).args(args);
+    node.reg_class = rc;
+    c.arena.alloc(node.into())
+}
+
+fn imm12(c: &MatchContext, id: NodeId) -> Option<i32> {
+    match c.arena[id] {
+        Node::Operand(OperandNode::Imm(i)) => i.to_i64().and_then(simm12),
+        _ => None,
+    }
+}
+
+fn simm12(i: i64) -> Option<i32> {
+    if -2048 <= i && i < 2048 {
+        Some(i as i32)
+    } else {
+        None
+    }
+}
+
+fn is_null(c: &MatchContext, id: NodeId) -> bool {
+    matches!(c.arena[id], Node::Operand(OperandNode::Imm(i)) if i.is_null())
+}
+
+fn reg_class_of(c: &MatchContext, id: NodeId) -> RC {
+    match &c.arena[id] {
+        Node::IR(IRNode { ty, .. }) => ty2rc(ty).unwrap(),
+        Node::MI(MINode { reg_class, .. }) => reg_class.unwrap(),
+        Node::Operand(OperandNode::Reg(r)) => c.regs.arena_ref()[*r].reg_class,
+        Node::Operand(OperandNode::Imm(i)) => match i {
+            ImmediateKind::Int8(_) => RC::GR8,
+            ImmediateKind::Int16(_) => RC::GR16,
+            ImmediateKind::Int32(_) => RC::GR32,
+            ImmediateKind::Int64(_) => RC::GR64,
+            ImmediateKind::F32(_) => RC::F32,
+            ImmediateKind::F64(_) => RC::F64,
+        },
+        _ => unreachable!(),
+    }
+}
+
+fn select_node<'a>(
+    ctx: &mut MatchContext<'a>,
+    replaced: &mut ReplacedNodeMap,
+    pats: &[Pat],
+    id: NodeId,
+) -> NodeId {
+    let new = inst_select(replaced, ctx, id, pats);
+
+    if let Some(next) = ctx.arena[id].next() {
+        let next = select_node(ctx, replaced, pats, next);
+        *ctx.arena[new].next_mut() = Some(next);
+    }
 
-        selected
-    }
-
-    // fn select_call(
-    //     &mut self,
-    //     tys: &Types,
-    //     regs_info: &RegistersInfo,
-    //     heap: &mut DAGHeap,
-    //     mut node: Raw<DAGNode>,
-    // ) -> Raw<DAGNode> {
-    //     unimplemented!()
-    // const SQRT_F64: &str = "sericum.sqrt.f64";
-    // let supported = [SQRT_F64];
-    //
-    // let name = match &node.operand[0].kind {
-    //     NodeKind::Operand(OperandNodeKind::Address(AddressKind::FunctionName(name)))
-    //         if supported.contains(&name.as_str()) =>
-    //     {
-    //         name.as_str()
-    //     }
-    //     _ => {
-    //         node.operand = node
-    //             .operand
-    //             .iter()
-    //             .map(|op| self.run_on_node(tys, regs_info, heap, *op))
-    //             .collect();
-    //         return node;
-    //     }
-    // };
-    //
-    // match name {
-    //     SQRT_F64 => {
-    //         let x = self.run_on_node(tys, regs_info, heap, node.operand[1]);
-    //         heap.alloc(DAGNode::new(
-    //             NodeKind::MI(MINodeKind::SQRTSDrr),
-    //             vec![x],
-    //             Type::F64,
-    //         ))
-    //     }
-    //     _ => unreachable!(),
-    // }
-    // }
+    new
 }
@@ -1,387 +1,143 @@
-use super::super::machine::register::*;
-use crate::codegen::arch::dag::node::*;
-use crate::codegen::common::dag::{
-    function::{DAGFunction, DAGHeap},
+use crate::codegen::riscv64::common::dag::{
+    function::DAGFunction,
     module::DAGModule,
+    node::{IRNode, IROpcode, Node, NodeId, OperandNode},
+    pat_match::{
+        any, any_block, any_cc, any_imm, any_reg, bitcast, inst_select, ir, reorder_patterns,
+        MatchContext, Pat, ReplacedNodeMap,
+    },
 };
-use crate::{ir::types::*, traits::pass::ModulePassTrait, util::allocator::*};
-// use defs::isel_pat;
-use rustc_hash::FxHashMap;
+use crate::codegen::riscv64::dag::node::MemKind;
+use crate::codegen::riscv64::machine::register::GR64;
 
-impl ModulePassTrait for Legalize {
-    type M = DAGModule;
-
-    fn name(&self) -> &'static str {
-        "Legalize"
+pub fn run(module: &mut DAGModule) {
+    for (_, func) in &mut module.functions {
+        if func.is_internal {
+            continue;
+        }
+        run_on_function(func);
     }
+}
 
-    fn run_on_module(&mut self, module: &mut Self::M) {
-        self.run_on_module(module);
+fn run_on_function(func: &mut DAGFunction) {
+    // (Load addr) -> (Load mem)
+    let load: Pat = ir(IROpcode::Load)
+        .named("load")
+        .args(vec![any_reg().named("addr").into()])
+        .generate(|m, c| {
+            let ty = c.arena[m["load"]].as_ir().ty;
+            let mem = mem(c, m["addr"]);
+            c.arena
+                .alloc(IRNode::new(IROpcode::Load).args(vec![mem]).ty(ty).into())
+        })
+        .into();
+
+    // (Store addr src) -> (Store mem src)
+    let store: Pat = ir(IROpcode::Store)
+        .args(vec![any_reg().named("addr").into(), any().named("src")])
+        .generate(|m, c| {
+            let mem = mem(c, m["addr"]);
+            c.arena.alloc(
+                IRNode::new(IROpcode::Store)
+                    .args(vec![mem, m["src"]])
+                    .into(),
+            )
+        })
+        .into();
+
+    // (Brcc cc imm reg dst) -> (Brcc cc' reg imm dst)
+    let brcc = |opcode| {
+        ir(opcode)
+            .named("brcc")
+            .args(vec![
+                any_cc().named("cc").into(),
+                any_imm().into(),
+                any_reg().into(),
+                any_block().into(),
+            ])
+            .generate(|m, c| {
+                let cc = c.arena[m["cc"]].as_operand_mut().as_cc_mut();
+                *cc = cc.flip();
+                c.arena[m["brcc"]].as_ir_mut().args.swap(1, 2);
+                m["brcc"]
+            })
+    };
+    let brcc: Pat = (brcc(IROpcode::Brcc) | brcc(IROpcode::FPBrcc)).into();
+
+    let pats = vec![
+        load,
+        store,
+        brcc,
+        bitcast(any().named("arg")).generate(|m, _| m["arg"]),
+    ];
+    let pats = reorder_patterns(pats);
+
+    let mut replaced = ReplacedNodeMap::default();
+    for &id in &func.dag_basic_blocks {
+        let block = &func.dag_basic_block_arena[id];
+        let a = select_node(
+            &mut MatchContext {
+                arena: &mut func.node_arena,
+                regs: &func.regs,
+            },
+            &mut replaced,
+            &pats,
+            block.entry.unwrap(),
+        );
+        assert_eq!(block.entry.unwrap(), a);
     }
 }
 
-pub struct Legalize {
-    selected: FxHashMap<Raw<DAGNode>, Raw<DAGNode>>,
+/// Folds the address into a memory operand: a stack slot with or without an offset, or a base
+/// with an offset.
+fn mem(c: &mut MatchContext, addr: NodeId) -> NodeId {
+    let (base, off) = match &c.arena[addr] {
+        Node::IR(IRNode {
+            opcode: IROpcode::Add,
+            args,
+            ..
+        }) => imm_i32(c, args[1]).map_or((addr, 0), |off| (args[0], off)),
+        _ => (addr, 0),
+    };
+    let slot = match &c.arena[base] {
+        Node::IR(IRNode {
+            opcode: IROpcode::FIAddr,
+            args,
+            ..
+        }) => Some(args[0]),
+        _ => None,
+    };
+    let s0 = c.arena.alloc(c.regs.get_phys_reg(GR64::S0).into());
+    let mem = match slot {
+        Some(slot) if off == 0 => MemKind::BaseFi([s0, slot]),
+        Some(slot) => MemKind::BaseFiOff([s0, slot, c.arena.alloc(off.into())]),
+        None => MemKind::BaseOff([base, c.arena.alloc(off.into())]),
+    };
+    c.arena.alloc(mem.into())
 }
 
-impl Legalize {
-    pub fn new() -> Self {
-        Self {
-            selected: FxHashMap::default(),
-        }
-    }
-
-    pub fn run_on_module(&mut self, module: &mut DAGModule) {
-        for (_, func) in &mut module.functions {
-            if func.is_internal {
-                continue;
-            }
-            self.run_on_function(&module.types, func)
-        }
+fn imm_i32(c: &MatchContext, id: NodeId) -> Option<i32> {
+    match c.arena[id] {
+        Node::Operand(OperandNode::Imm(i)) => i
+            .to_i64()
+            .filter(|&i| i as i32 as i64 == i)
+            .map(|i| i as i32),
+        _ => None,
     }
+}
 
-    fn run_on_function(&mut self, tys: &Types, func: &mut DAGFunction) {
-        for bb_id in &func.dag_basic_blocks {
-            let bb = &func.dag_basic_block_arena[*bb_id];
-            self.run_on_node(tys, &func.regs_info, &mut func.dag_heap, bb.entry.unwrap());
-        }
-    }
-
-    fn run_on_node(
-        &mut self,
-        tys: &Types,
-        regs_info: &RegistersInfo,
-        heap: &mut DAGHeap,
-        node: Raw<DAGNode>,
-    ) -> Raw<DAGNode> {
-        if !node.may_contain_children() {
-            return node;
-        }
-
-        if let Some(node) = self.selected.get(&node) {
-            return *node;
-        }
-
-        // TODO: auto-generate the following code by macro
-        let mut selected = match node.kind {
-            // NodeKind::IR(IRNodeKind::Load) => self.run_on_node_load(tys, regs_info, heap, node),
-            // NodeKind::IR(IRNodeKind::Store) => self.run_on_node_store(tys, regs_info, heap, node),
-            // NodeKind::IR(IRNodeKind::Add) => self.run_on_node_add(tys, regs_info, heap, node),
-            // NodeKind::IR(IRNodeKind::Mul) => self.run_on_node_mul(tys, regs_info, heap, node),
-            NodeKind::IR(IRNodeKind::Sext) => self.run_on_node_sext(tys, regs_info, heap, node),
-            // NodeKind::IR(IRNodeKind::Brcc) => self.run_on_node_brcc(tys, regs_info, heap, node),
-            // NodeKind::IR(IRNodeKind::FPBrcc) => self.run_on_node_fpbrcc(tys, regs_info, heap, node),
-            NodeKind::IR(IRNodeKind::Sub) => self.run_on_node_sub(tys, regs_info, heap, node),
-            _ => {
-                self.run_on_node_operand(tys, regs_info, heap, node);
-                node
-            }
-        };
-
-        self.selected.insert(node, selected);
-
-        if let Some(next) = node.next {
-            selected.next = Some(self.run_on_node(tys, regs_info, heap, next));
-        }
-
-        selected
-    }
-
-    fn run_on_node_sub(
-        &mut self,
-        tys: &Types,
-        regs_info: &RegistersInfo,
-        heap: &mut DAGHeap,
-        node: Raw<DAGNode>,
-    ) -> Raw<DAGNode> {
-        if node.kind == NodeKind::IR(IRNodeKind::Sub)
-            && !node.operand[0].is_constant()
-            && node.operand[1].is_constant()
-        {
-            // TODO: Refine code
-            let op0 = self.run_on_node(tys, regs_info, heap, node.operand[0]);
-            let op1 = heap.alloc(DAGNode::new(
-                NodeKind::Operand(OperandNodeKind::Constant(
-                    node.operand[1].as_constant().neg(),
-                )),
-                vec![],
-                node.operand[1].ty,
-            ));
-            return heap.alloc(DAGNode::new(
-                NodeKind::IR(IRNodeKind::Add),
-                vec![op0, op1],
-                node.ty,
-            ));
-        }
-
-        self.run_on_node_operand(tys, regs_info, heap, node);
-        node
-    }
-
-    // fn run_on_node_load(
-    //     &mut self,
-    //     tys: &Types,
-    //     regs_info: &RegistersInfo,
-    //     heap: &mut DAGHeap,
-    //     node: Raw<DAGNode>,
-    // ) -> Raw<DAGNode> {
-    //     if matches!(node.ty, Type::i32) && node.operand[0].kind == NodeKind::IR(IRNodeKind::Add) {
-    //         let add = node.operand[0];
-    //         let op0 = self.run_on_node(tys, regs_info, heap, add.operand[0]);
-    //         let op1 = self.run_on_node(tys, regs_info, heap, add.operand[1]);
-    //         let rbp = heap.alloc_phys_reg(regs_info, GR64::RBP);
-    //
-    //         if op0.kind == NodeKind::IR(IRNodeKind::FIAddr) && op1.is_constant() {
-    //             let mem = heap.alloc(DAGNode::new_mem(
-    //                 MemNodeKind::BaseFiOff,
-    //                 vec![rbp, op0.operand[0], op1],
-    //             ));
-    //             return heap.alloc(DAGNode::new(
-    //                 NodeKind::MI(MINodeKind::MOVrm32),
-    //                 vec![mem],
-    //                 node.ty.clone(),
-    //             ));
-    //         }
-    //
-    //         if op0.kind == NodeKind::IR(IRNodeKind::FIAddr)
-    //             && op1.kind == NodeKind::IR(IRNodeKind::Mul)
-    //             && op1.operand[1].is_constant()
-    //         {
-    //             let op1_op0 = self.run_on_node(tys, regs_info, heap, op1.operand[0]);
-    //             let op1_op1 = op1.operand[1];
-    //             let mem = heap.alloc(DAGNode::new_mem(
-    //                 MemNodeKind::BaseFiAlignOff,
-    //                 vec![rbp, op0.operand[0], op1_op1, op1_op0],
-    //             ));
-    //             return heap.alloc(DAGNode::new(
-    //                 NodeKind::MI(MINodeKind::MOVrm32),
-    //                 vec![mem],
-    //                 node.ty,
-    //             ));
-    //         }
-    //
-    //         if op0.is_maybe_register()
-    //             && op1.kind == NodeKind::IR(IRNodeKind::Mul)
-    //             && op1.operand[1].is_constant()
-    //         {
-    //             let op1_op0 = self.run_on_node(tys, regs_info, heap, op1.operand[0]);
-    //             let op1_op1 = op1.operand[1];
-    //             let mem = heap.alloc(DAGNode::new_mem(
-    //                 MemNodeKind::BaseAlignOff,
-    //                 vec![op0, op1_op1, op1_op0],
-    //             ));
-    //             return heap.alloc(DAGNode::new(
-    //                 NodeKind::MI(MINodeKind::MOVrm32),
-    //                 vec![mem],
-    //                 node.ty.clone(),
-    //             ));
-    //         }
-    //     }
-    //
-    //     self.run_on_node_operand(tys, regs_info, heap, node);
-    //     node
-    // }
-    //
-    // fn run_on_node_store(
-    //     &mut self,
-    //     tys: &Types,
-    //     regs_info: &RegistersInfo,
-    //     heap: &mut DAGHeap,
-    //     mut node: Raw<DAGNode>,
-    // ) -> Raw<DAGNode> {
-    //     isel_pat! {
-    //     (ir.Store dst, src) {
-    //         (ir.Add a1, a2) dst {
-    //             (ir.FIAddr fi) a1 {
-    //                 mem32 fi {
-    //                     imm32 a2 {
-    //                         GR32  src => (mi.MOVmr32 [BaseFiOff %rbp, fi, a2], src)
-    //                         imm32 src => (mi.MOVmi32 [BaseFiOff %rbp, fi, a2], src) }
-    //                     (ir.Mul m1, m2) a2 {
-    //                         imm32 m2 {
-    //                             GR32  src => (mi.MOVmr32 [BaseFiAlignOff %rbp, fi, m2, m1], src)
-    //                             imm32 src => (mi.MOVmi32 [BaseFiAlignOff %rbp, fi, m2, m1], src) } } } }
-    //             GR64 a1 {
-    //                 (ir.Mul m1, m2) a2 {
-    //                     imm32 m2 {
-    //                         GR32  src => (mi.MOVmr32 [BaseAlignOff a1, m2, m1], src)
-    //                         imm32 src => (mi.MOVmi32 [BaseAlignOff a1, m2, m1], src) } } } } } }
-    // }
-    //
-    // fn run_on_node_add(
-    //     &mut self,
-    //     tys: &Types,
-    //     regs_info: &RegistersInfo,
-    //     heap: &mut DAGHeap,
-    //     node: Raw<DAGNode>,
-    // ) -> Raw<DAGNode> {
-    //     if node.operand[0].kind == NodeKind::IR(IRNodeKind::FIAddr) {
-    //         let op0 = node.operand[0].operand[0];
-    //         let rbp = heap.alloc_phys_reg(regs_info, GR64::RBP);
-    //         let one = heap.alloc(DAGNode::new(
-    //             NodeKind::Operand(OperandNodeKind::Constant(ConstantKind::i32(1))),
-    //             vec![],
-    //             Type::i32,
-    //         ));
-    //
-    //         if node.operand[1].is_maybe_register() {
-    //             let op1 = self.run_on_node(tys, regs_info, heap, node.operand[1]);
-    //             let mem = heap.alloc(DAGNode::new_mem(
-    //                 MemNodeKind::BaseFiAlignOff,
-    //                 vec![rbp, op0, one, op1],
-    //             ));
-    //             return heap.alloc(DAGNode::new(
-    //                 NodeKind::MI(MINodeKind::LEAr64m),
-    //                 vec![mem],
-    //                 node.ty,
-    //             ));
-    //         } else if node.operand[1].is_constant() {
-    //             let op1 = node.operand[1];
-    //             let mem = heap.alloc(DAGNode::new_mem(
-    //                 MemNodeKind::BaseFiOff,
-    //                 vec![rbp, op0, op1],
-    //             ));
-    //             return heap.alloc(DAGNode::new(
-    //                 NodeKind::MI(MINodeKind::LEAr64m),
-    //                 vec![mem],
-    //                 node.ty,
-    //             ));
-    //         }
-    //     }
-    //
-    //     self.run_on_node_operand(tys, regs_info, heap, node);
-    //     node
-    // }
-    //
-    // fn run_on_node_mul(
-    //     &mut self,
-    //     tys: &Types,
-    //     regs_info: &RegistersInfo,
-    //     heap: &mut DAGHeap,
-    //     mut node: Raw<DAGNode>,
-    // ) -> Raw<DAGNode> {
-    //     let lhs = node.operand[0];
-    //     let rhs = node.operand[1];
-    //
-    //     if lhs.is_constant() && rhs.is_maybe_register() {
-    //         let tmp = node.operand[0];
-    //         node.operand[0] = node.operand[1];
-    //         node.operand[0] = tmp;
-    //         return self.run_on_node_mul(tys, regs_info, heap, node);
-    //     }
-    //
-    //     self.run_on_node_operand(tys, regs_info, heap, node);
-    //     node
-    // }
-    //
-    fn run_on_node_sext(
-        &mut self,
-        tys: &Types,
-        regs_info: &RegistersInfo,
-        heap: &mut DAGHeap,
-        node: Raw<DAGNode>,
-    ) -> Raw<DAGNode> {
-        // if node.ty == Type::i64
-        //     && node.operand[0].kind == NodeKind::IR(IRNodeKind::Load)
-        //     && node.operand[0].operand[0].kind == NodeKind::IR(IRNodeKind::FIAddr)
-        // {
-        //     return heap.alloc(DAGNode::new(
-        //         NodeKind::MI(MINodeKind::MOVSXDr64m32),
-        //         vec![node.operand[0].operand[0].operand[0]],
-        //         node.ty.clone(),
-        //     ));
-        // }
-
-        if node.ty == Type::i64 && !node.operand[0].is_constant() && node.operand[0].ty == Type::i32
-        {
-            let op = self.run_on_node(tys, regs_info, heap, node.operand[0]);
-            return heap.alloc(DAGNode::new(
-                NodeKind::MI(MINodeKind::SEXT_W),
-                vec![op],
-                node.ty,
-            ));
-        }
-
-        self.run_on_node_operand(tys, regs_info, heap, node);
-        node
+fn select_node<'a>(
+    ctx: &mut MatchContext<'a>,
+    replaced: &mut ReplacedNodeMap,
+    pats: &[Pat],
+    id: NodeId,
+) -> NodeId {
+    let new = inst_select(replaced, ctx, id, pats);
+
+    if let Some(next) = ctx.arena[id].next() {
+        let next = select_node(ctx, replaced, pats, next);
+        *ctx.arena[new].next_mut() = Some(next);
     }
-    //
-    // fn run_on_node_brcc(
-    //     &mut self,
-    //     tys: &Types,
-    //     regs_info: &RegistersInfo,
-    //     heap: &mut DAGHeap,
-    //     mut node: Raw<DAGNode>,
-    // ) -> Raw<DAGNode> {
-    //     let mut cond = node.operand[0];
-    //     let lhs = node.operand[1];
-    //     let rhs = node.operand[2];
-    //     // lhs must be register
-    //     if lhs.is_constant() && rhs.is_maybe_register() {
-    //         let tmp = node.operand[1];
-    //         node.operand[1] = node.operand[2];
-    //         node.operand[2] = tmp;
-    //         if let NodeKind::Operand(OperandNodeKind::CondKind(kind)) = &mut cond.kind {
-    //             *kind = kind.flip();
-    //         }
-    //         return self.run_on_node_brcc(tys, regs_info, heap, node);
-    //     }
-    //     self.run_on_node_operand(tys, regs_info, heap, node);
-    //     node
-    // }
-    //
-    // fn run_on_node_fpbrcc(
-    //     &mut self,
-    //     tys: &Types,
-    //     regs_info: &RegistersInfo,
-    //     heap: &mut DAGHeap,
-    //     mut node: Raw<DAGNode>,
-    // ) -> Raw<DAGNode> {
-    //     let mut cond = node.operand[0];
-    //     let lhs = node.operand[1];
-    //     let rhs = node.operand[2];
-    //
-    //     // lhs must be register
-    //     if lhs.is_constant() && rhs.is_maybe_register() {
-    //         let tmp = lhs;
-    //         node.operand[1] = node.operand[2];
-    //         node.operand[2] = tmp;
-    //         if let NodeKind::Operand(OperandNodeKind::CondKind(kind)) = &mut cond.kind {
-    //             *kind = kind.flip();
-    //         }
-    //         return self.run_on_node_fpbrcc(tys, regs_info, heap, node);
-    //     }
-    //
-    //     // now: lhs=register, rhs=const|register
-    //     if lhs.is_maybe_register() && rhs.is_constant() {
-    //         let lhs = self.run_on_node(tys, regs_info, heap, lhs);
-    //         let rhs = heap.alloc(DAGNode::new(
-    //             NodeKind::MI(MINodeKind::MOVSDrm64),
-    //             vec![rhs],
-    //             Type::F64,
-    //         ));
-    //         return heap.alloc(DAGNode::new(
-    //             NodeKind::IR(IRNodeKind::FPBrcc),
-    //             vec![cond, lhs, rhs, node.operand[3]],
-    //             node.ty,
-    //         ));
-    //     }
-    //
-    //     self.run_on_node_operand(tys, regs_info, heap, node);
-    //     node
-    // }
 
-    fn run_on_node_operand(
-        &mut self,
-        tys: &Types,
-        regs_info: &RegistersInfo,
-        heap: &mut DAGHeap,
-        mut node: Raw<DAGNode>,
-    ) {
-        node.operand = node
-            .operand
-            .iter()
-            .map(|op| self.run_on_node(tys, regs_info, heap, *op))
-            .collect()
-    }
+    new
 }
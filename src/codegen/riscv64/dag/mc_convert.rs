@@ -1,596 +1,464 @@
-// TODO: dirty code
-
-use super::super::machine::register::*;
-use super::super::machine::{inst, inst::*};
-use crate::codegen::arch::dag::{node, node::*};
-use crate::codegen::common::dag::{basic_block::DAGBasicBlockId, function::*, module::*};
-use crate::codegen::common::machine::{
-    basic_block::*, function::*, inst_def::DefOrUseReg, module::*,
+pub use crate::codegen::riscv64::common::dag::mc_convert::ScheduleContext;
+use crate::codegen::riscv64::common::dag::{
+    node,
+    node::{IRNode, IROpcode, ImmediateKind, MINode, Node, NodeId, OperandNode},
 };
-use crate::ir::types::Type;
-use crate::util::allocator::*;
-use id_arena::*;
-use rustc_hash::FxHashMap;
-use std::cell::RefCell;
-
-pub struct ScheduleByBlock<'a> {
-    // types: &'a Types,
-    cur_func: &'a DAGFunction,
-    machine_inst_arena: &'a mut InstructionArena,
-    node_to_reg: FxHashMap<Raw<DAGNode>, Option<RegisterId>>,
-    cur_bb: MachineBasicBlockId,
-    iseq: &'a mut Vec<MachineInstId>,
-    bb_map: &'a FxHashMap<DAGBasicBlockId, MachineBasicBlockId>,
-    node2minst: &'a mut FxHashMap<Raw<DAGNode>, MachineInstId>,
-}
-
-pub fn convert_module(module: DAGModule) -> MachineModule {
-    let mut functions = Arena::new();
-    for (_, func) in module.functions {
-        functions.alloc(convert_function(/*&module.types,*/ func));
-    }
-    MachineModule::new(module.name, functions, module.types, module.global_vars)
-}
-
-pub fn convert_function(/*types: &Types,*/ dag_func: DAGFunction) -> MachineFunction {
-    let mut bb_map = FxHashMap::default();
-    let mut mbbs = MachineBasicBlocks::new();
-
-    for dag_bb_id in &dag_func.dag_basic_blocks {
-        let mbb_id = mbbs.arena.alloc(MachineBasicBlock::new());
-        bb_map.insert(*dag_bb_id, mbb_id);
-        mbbs.order.push(mbb_id);
-    }
-
-    for (dag, machine) in &bb_map {
-        let dbb = &dag_func.dag_basic_block_arena[*dag];
-        let mbb = &mut mbbs.arena[*machine];
-        mbb.pred = dbb.pred.iter().map(|bb| *bb_map.get(bb).unwrap()).collect();
-        mbb.succ = dbb.succ.iter().map(|bb| *bb_map.get(bb).unwrap()).collect();
-    }
-
-    let mut machine_inst_arena = InstructionArena::new();
-    let mut node2minst = FxHashMap::default();
-
-    for dag_bb_id in &dag_func.dag_basic_blocks {
-        let node = &dag_func.dag_basic_block_arena[*dag_bb_id];
-        let bb_id = *bb_map.get(dag_bb_id).unwrap();
-        let mut iseq = vec![];
-
-        let entry = match node.entry {
-            Some(entry) => entry,
-            None => continue,
-        };
-
-        ScheduleByBlock {
-            // types,
-            cur_func: &dag_func,
-            machine_inst_arena: &mut machine_inst_arena,
-            node_to_reg: FxHashMap::default(),
-            cur_bb: bb_id,
-            iseq: &mut iseq,
-            bb_map: &bb_map,
-            node2minst: &mut node2minst,
-        }
-        .convert(entry);
-
-        mbbs.arena[bb_id].iseq = RefCell::new(iseq);
-    }
-
-    MachineFunction::new(dag_func, mbbs, machine_inst_arena)
-}
-
-impl<'a> ScheduleByBlock<'a> {
-    pub fn convert(&mut self, node: Raw<DAGNode>) -> Option<RegisterId> {
-        if let Some(reg_id) = self.node_to_reg.get(&node) {
-            return *reg_id;
+use crate::codegen::riscv64::common::machine::inst_def::DefOrUseReg;
+use crate::codegen::riscv64::dag::node::MemKind;
+use crate::codegen::riscv64::machine::abi::{ArgLocation, LP64D};
+use crate::codegen::riscv64::machine::inst::*;
+use crate::codegen::riscv64::machine::register::*;
+use crate::ir::types::{Type, TypeSize};
+
+impl<'a> ScheduleContext<'a> {
+    pub fn convert_node(&mut self, id: NodeId) -> MachineInstId {
+        if let Some(inst_id) = self.node2inst.get(&id) {
+            return *inst_id;
         }
 
-        let reg_id = match self.convert_node_to_inst(node) {
-            Some(id) => {
-                let inst = &self.machine_inst_arena[id];
-                inst.get_def_reg()
-            }
-            None => None,
-        };
-        self.node_to_reg.insert(node, reg_id);
-
-        some_then!(next, node.next, {
-            self.convert(next);
-        });
-
-        reg_id
-    }
-
-    fn convert_node_to_inst(&mut self, node: Raw<DAGNode>) -> Option<MachineInstId> {
-        if let Some(machine_inst_id) = self.node2minst.get(&node) {
-            return Some(*machine_inst_id);
-        }
-
-        #[rustfmt::skip]
-        macro_rules! cond_kind {($id:expr)=>{ $id.as_cond_kind() };}
-
-        // there should be no NodeKind::IRs here
-        let machine_inst_id = match &node.kind {
-            NodeKind::MI(_) => {
-                fn reg(inst: &MachineInst, x: &DefOrUseReg) -> RegisterId {
+        let inst_id = match &self.func.node_arena[id] {
+            Node::MI(MINode {
+                opcode,
+                args,
+                reg_class,
+                ..
+            }) => {
+                fn reg(inst: &MachineInst, x: &DefOrUseReg) -> RegisterOperand {
                     match x {
                         DefOrUseReg::Def(i) => inst.def[*i],
                         DefOrUseReg::Use(i) => *inst.operand[*i].as_register(),
                     }
                 }
-                let mi = node.kind.as_mi();
-                let inst_def = mi.inst_def().unwrap();
-                let operands = node
-                    .operand
-                    .iter()
-                    .map(|op| self.normal_operand(*op))
-                    .collect();
+                let inst_def = opcode.inst_def().unwrap();
+                let operands = args.iter().map(|op| self.normal_arg(*op)).collect();
                 let mut inst = MachineInst::new(
-                    &self.cur_func.regs_info,
-                    mi,
+                    &self.func.regs,
+                    *opcode,
                     operands,
-                    ty2rc(&node.ty),
-                    self.cur_bb,
+                    *reg_class,
+                    self.block_id,
                 );
                 for (def_, use_) in &inst_def.tie {
                     inst.tie_regs(reg(&inst, def_), reg(&inst, use_));
                 }
-                Some(self.push_inst(inst))
+                self.append_inst(inst)
             }
-            NodeKind::IR(IRNodeKind::Entry) => None,
-            NodeKind::IR(IRNodeKind::CopyToReg) => {
-                let val = self.normal_operand(node.operand[1]);
-                let dst = match &node.operand[0].kind {
-                    NodeKind::Operand(OperandNodeKind::Register(r)) => *r,
+            Node::IR(IRNode {
+                opcode: IROpcode::CopyToReg,
+                args,
+                ..
+            }) => {
+                let src = self.normal_arg(args[1]);
+                let dst = match &self.func.node_arena[args[0]] {
+                    Node::Operand(OperandNode::Reg(r)) => RegisterOperand::new(*r),
                     _ => unreachable!(),
                 };
-                Some(self.push_inst(MachineInst::new_with_def_reg(
+                self.append_inst(MachineInst::new_with_def_reg(
                     MachineOpcode::Copy,
-                    vec![val],
+                    vec![src],
                     vec![dst],
-                    self.cur_bb,
-                )))
+                    self.block_id,
+                ))
             }
-            NodeKind::IR(IRNodeKind::Call) => Some(self.convert_call_dag(&*node)),
-            NodeKind::IR(IRNodeKind::Phi) => {
+            Node::IR(IRNode {
+                opcode: IROpcode::Call,
+                args,
+                ty,
+                ..
+            }) => self.convert_call(*ty, args),
+            Node::IR(IRNode {
+                opcode: IROpcode::Phi,
+                args,
+                ty,
+                ..
+            }) => {
                 let mut operands = vec![];
-                let mut i = 0;
-                while i < node.operand.len() {
-                    operands.push(self.normal_operand(node.operand[i]));
-                    operands.push(MachineOperand::Branch(
-                        self.get_machine_bb(node.operand[i + 1].as_basic_block()),
-                    ));
-                    i += 2;
+                for i in (0..args.len()).step_by(2) {
+                    let val = args[i];
+                    let block = args[i + 1];
+                    operands.push(self.normal_arg(val));
+                    operands.push(self.normal_arg(block));
                 }
                 let phi_inst = MachineInst::new(
-                    &self.cur_func.regs_info,
+                    &self.func.regs,
                     MachineOpcode::Phi,
                     operands,
-                    ty2rc(&node.ty),
-                    self.cur_bb,
+                    ty2rc(ty),
+                    self.block_id,
                 );
-                Some(self.push_inst(phi_inst))
-            }
-            // NodeKind::IR(IRNodeKind::Setcc) => {
-            //     let new_op1 = self.normal_operand(node.operand[1]);
-            //     let new_op2 = self.normal_operand(node.operand[2]);
-            //     let inst = MachineInst::new(
-            //         &self.cur_func.regs_info,
-            //         match cond_kind!(node.operand[0]) {
-            //             CondKind::Eq => MachineOpcode::Seteq,
-            //             CondKind::Le => MachineOpcode::Setle,
-            //             CondKind::Lt => MachineOpcode::Setlt,
-            //             _ => unimplemented!(),
-            //         },
-            //         vec![new_op1, new_op2],
-            //         ty2rc(&node.ty),
-            //         self.cur_bb,
-            //     );
-            //     Some(self.push_inst(inst))
-            // }
-            // NodeKind::IR(IRNodeKind::Br) => {
-            //     let inst = MachineInst::new(
-            //         &self.cur_func.regs_info,
-            //         MachineOpcode::JMP,
-            //         vec![MachineOperand::Branch(
-            //             self.get_machine_bb(bb!(node.operand[0])),
-            //         )],
-            //         None,
-            //         self.cur_bb,
-            //     );
-            //     Some(self.push_inst(inst))
-            // }
-            // NodeKind::IR(IRNodeKind::BrCond) => {
-            //     let new_cond = self.normal_operand(node.operand[0]);
-            //     let inst = MachineInst::new(
-            //         &self.cur_func.regs_info,
-            //         MachineOpcode::BrCond,
-            //         vec![
-            //             new_cond,
-            //             MachineOperand::Branch(self.get_machine_bb(bb!(node.operand[1]))),
-            //         ],
-            //         None,
-            //         self.cur_bb,
-            //     );
-            //     Some(self.push_inst(inst))
-            // }
-            NodeKind::IR(IRNodeKind::Brcc) => {
-                let mut operands = node.operand[1..3]
-                    .iter()
-                    .map(|&op| {
-                        let op = self.normal_operand(op);
-                        match self.move2reg_if_necessary(op) {
-                            Ok((r, inst)) => {
-                                self.push_inst(inst);
-                                MachineOperand::Register(r)
-                            }
-                            Err(op) => op,
-                        }
-                    })
-                    .collect::<Vec<_>>();
-                operands.push(MachineOperand::Branch(
-                    self.get_machine_bb(node.operand[3].as_basic_block()),
-                ));
-                Some(self.push_inst(MachineInst::new_simple(
-                    match cond_kind!(node.operand[0]) {
-                        CondKind::Eq => MachineOpcode::BEQ,
-                        CondKind::Le => MachineOpcode::BLE,
-                        CondKind::Lt => MachineOpcode::BLT,
-                        // CondKind::Lt => MachineOpcode::JL,
-                        // CondKind::Ge => MachineOpcode::JGE,
-                        // CondKind::Gt => MachineOpcode::JG,
-                        _ => unreachable!(),
-                    },
-                    operands,
-                    self.cur_bb,
-                )))
-            }
-            // NodeKind::IR(IRNodeKind::FPBrcc) => {
-            //     let op0 = self.normal_operand(node.operand[1]);
-            //     let op1 = self.normal_operand(node.operand[2]);
-            //
-            //     self.push_inst(MachineInst::new_simple(
-            //         MachineOpcode::UCOMISDrr,
-            //         vec![op0, op1],
-            //         self.cur_bb,
-            //     ));
-            //
-            //     Some(self.push_inst(MachineInst::new_simple(
-            //         match cond_kind!(node.operand[0]) {
-            //             CondKind::UEq => MachineOpcode::JE,
-            //             CondKind::ULe => MachineOpcode::JBE,
-            //             CondKind::ULt => MachineOpcode::JB,
-            //             CondKind::UGe => MachineOpcode::JAE,
-            //             CondKind::UGt => MachineOpcode::JA,
-            //             _ => unreachable!(),
-            //         },
-            //         vec![MachineOperand::Branch(
-            //             self.get_machine_bb(bb!(node.operand[3])),
-            //         )],
-            //         self.cur_bb,
-            //     )))
-            // }
-            NodeKind::IR(IRNodeKind::Ret) => Some(self.convert_ret(&*node)),
-            NodeKind::IR(IRNodeKind::CopyToLiveOut) => self.convert_node_to_inst(node.operand[0]),
-            e => {
-                dbg!(e);
-                // println!("{:?}, {:?}", *node.operand[0], *node.operand[1]);
-                None
+                self.append_inst(phi_inst)
             }
+            Node::IR(IRNode {
+                opcode: IROpcode::Ret,
+                args,
+                ..
+            }) => self.convert_ret(args[0]),
+            e => todo!("{:?}", e),
         };
-
-        if machine_inst_id.is_some() {
-            self.node2minst.insert(node, machine_inst_id.unwrap());
-        }
-
-        machine_inst_id
-    }
-
-    fn convert_ret(&mut self, node: &DAGNode) -> MachineInstId {
-        let val = self.normal_operand(node.operand[0]);
-        let ra = self.cur_func.regs_info.get_phys_reg(GPR::RA);
-
-        if let Some(ty) = val.get_type(&self.cur_func.regs_info) {
-            assert!(ty.is_integer());
-            let ret_reg = ty2rc(&ty).unwrap().return_value_register();
-            let set_ret_val = self.move2reg(self.cur_func.regs_info.get_phys_reg(ret_reg), val);
-            self.push_inst(set_ret_val);
-        }
-
-        self.push_inst(MachineInst::new_simple(
-            MachineOpcode::JR, // TODO
-            vec![MachineOperand::Register(ra)],
-            self.cur_bb,
-        ))
-    }
-
-    fn move2reg(&self, r: RegisterId, src: MachineOperand) -> MachineInst {
-        MachineInst::new_simple(opcode_copy2reg(&src), vec![src], self.cur_bb).with_def(vec![r])
-    }
-
-    fn move2reg_if_necessary(
-        &self,
-        src: MachineOperand,
-    ) -> Result<(RegisterId, MachineInst), MachineOperand> {
-        match src {
-            MachineOperand::Register(_) => Err(src),
-            _ => {
-                let r = self
-                    .cur_func
-                    .regs_info
-                    .new_virt_reg(ty2rc(&src.get_type(&self.cur_func.regs_info).unwrap()).unwrap());
-                Ok((
-                    r,
-                    MachineInst::new_simple(opcode_copy2reg(&src), vec![src], self.cur_bb)
-                        .with_def(vec![r]),
-                ))
-            }
-        }
+        inst_id
     }
 
-    fn convert_call_dag(&mut self, node: &DAGNode) -> MachineInstId {
-        let mut arg_regs = vec![];
-        // let mut off = 0;
-
-        let mut args = vec![];
-        for operand in &node.operand[1..] {
-            args.push(self.normal_operand(*operand));
-        }
-
-        for (i, arg) in args.into_iter().enumerate() {
-            let ty = arg.get_type(&self.cur_func.regs_info).unwrap();
-
-            if !matches!(
-                ty,
-                Type::i8 | Type::i32 | Type::i64 | Type::f64 | Type::Pointer(_) | Type::Array(_)
-            ) {
-                unimplemented!()
+    fn convert_call(&mut self, ret_ty: Type, operands: &[NodeId]) -> MachineInstId {
+        let sp = RegisterOperand::new(self.func.regs.get_phys_reg(GR64::SP));
+        let mut arg_regs = vec![sp]; // call uses SP
+
+        let func_name = self.func.node_arena[operands[0]]
+            .as_operand()
+            .as_addr()
+            .as_func_name();
+        let func_ty = self.func_map[func_name];
+        let params_ty = self
+            .func
+            .types
+            .compound_ty(func_ty)
+            .as_function()
+            .params_ty
+            .clone();
+        let (locs, stack_size) = LP64D::new().arg_locations(&self.func.types, func_ty);
+
+        let args: Vec<MachineOperand> = operands[1..]
+            .iter()
+            .map(|arg| self.normal_arg(*arg))
+            .collect();
+
+        // Copies of structs passed by reference are placed above the arguments on the stack.
+        let mut off = stack_size;
+
+        for ((arg, loc), ty) in args.into_iter().zip(locs.iter()).zip(params_ty) {
+            let struct_size = || {
+                let struct_ty = self.func.types.get_element_ty(ty, None).unwrap();
+                struct_ty.size_in_byte(&self.func.types) as i32
             };
-
-            let reg_class = ty2rc(&ty).unwrap();
-            let inst = match reg_class.get_nth_arg_reg(i) {
-                Some(arg_reg) => {
-                    let r = self.cur_func.regs_info.get_phys_reg(arg_reg);
-                    arg_regs.push(r);
-                    self.move2reg(r, arg)
+            match loc {
+                ArgLocation::StructInRegs(parts) => {
+                    let addr = *arg.as_register();
+                    for &(r, part_off) in parts {
+                        let r = RegisterOperand::new(self.func.regs.get_phys_reg(r));
+                        let src = MachineOperand::Mem(MachineMemOperand::BaseOff(addr, part_off));
+                        let rc = self.func.regs.arena_ref()[r.id].reg_class;
+                        let load = MachineInst::new_simple(
+                            mov_rx(rc, &src).unwrap(),
+                            vec![src],
+                            self.block_id,
+                        )
+                        .with_def(vec![r]);
+                        self.append_inst(load);
+                        arg_regs.push(r);
+                    }
                 }
-                None => {
-                    // Put the exceeded value onto the stack
-                    // let inst = MachineInst::new_simple(
-                    //     mov_mx(&self.cur_func.regs_info, &arg).unwrap(),
-                    //     vec![
-                    //         MachineOperand::Mem(MachineMemOperand::BaseOff(
-                    //             self.cur_func.regs_info.get_phys_reg(GR64::RSP),
-                    //             off,
-                    //         )),
-                    //         arg,
-                    //     ],
-                    //     self.cur_bb,
-                    // );
-                    // off += 8;
-                    // inst
-                    unimplemented!()
+                ArgLocation::StructOnStack(dst_off) => {
+                    let size = struct_size();
+                    self.copy_mem(*arg.as_register(), sp, *dst_off, size);
                 }
-            };
-
-            self.push_inst(inst);
+                ArgLocation::StructRef(loc) => {
+                    let size = struct_size();
+                    self.copy_mem(*arg.as_register(), sp, off, size);
+                    let addr = MachineInst::new(
+                        &self.func.regs,
+                        MachineOpcode::ADDI,
+                        vec![MachineOperand::Register(sp), MachineOperand::imm_i32(off)],
+                        Some(RegisterClassKind::GR64),
+                        self.block_id,
+                    );
+                    let r = addr.def[0];
+                    self.append_inst(addr);
+                    let addr = MachineOperand::Register(r);
+                    off += size + (8 - size % 8) % 8;
+                    self.pass_arg(addr, Type::i64, loc, &mut arg_regs);
+                }
+                loc => self.pass_arg(arg, ty, loc, &mut arg_regs),
+            }
         }
 
-        // self.push_inst(
-        //     MachineInst::new_simple(
-        //         MachineOpcode::AdjStackDown,
-        //         vec![MachineOperand::imm_i32(off)],
-        //         self.cur_bb,
-        //     )
-        //     .with_imp_def(self.cur_func.regs_info.get_phys_reg(GR64::RSP))
-        //     .with_imp_use(self.cur_func.regs_info.get_phys_reg(GR64::RSP)),
-        // );
+        let adj = off + (16 - off % 16) % 16;
+        self.append_inst(
+            MachineInst::new_simple(
+                MachineOpcode::AdjStackDown,
+                vec![MachineOperand::imm_i32(adj)],
+                self.block_id,
+            )
+            .with_imp_def(sp)
+            .with_imp_use(sp),
+        );
 
-        let callee = self.normal_operand(node.operand[0]);
-        let ret_reg = self.cur_func.regs_info.get_phys_reg(
-            ty2rc(&node.ty)
-                .unwrap_or(RegisterClassKind::GPR)
+        let callee = self.normal_arg(operands[0]);
+        let ret_reg = self.func.regs.get_phys_reg(
+            ty2rc(&ret_ty)
+                .unwrap_or(RegisterClassKind::GR32)
                 .return_value_register(),
         );
-        let call_inst = self.push_inst(
-            MachineInst::new_simple(MachineOpcode::CALL, vec![callee], self.cur_bb)
+        let call_inst = self.append_inst(
+            MachineInst::new_simple(MachineOpcode::CALL, vec![callee], self.block_id)
                 .with_imp_uses(arg_regs)
                 .with_imp_defs({
-                    let mut imp_defs = match node.ty {
-                        Type::Void => vec![],
-                        _ => vec![ret_reg],
-                    };
-                    imp_defs.push(self.cur_func.regs_info.get_phys_reg(GPR::RA));
-                    imp_defs
+                    let mut defs = vec![sp];
+                    if ret_ty != Type::Void {
+                        defs.push(RegisterOperand::new(ret_reg));
+                    }
+                    defs
                 }),
         );
 
-        // self.push_inst(
-        //     MachineInst::new_simple(
-        //         MachineOpcode::AdjStackUp,
-        //         vec![MachineOperand::imm_i32(off)],
-        //         self.cur_bb,
-        //     )
-        //     .with_imp_def(self.cur_func.regs_info.get_phys_reg(GR64::RSP))
-        //     .with_imp_use(self.cur_func.regs_info.get_phys_reg(GR64::RSP)),
-        // );
+        self.append_inst(
+            MachineInst::new_simple(
+                MachineOpcode::AdjStackUp,
+                vec![MachineOperand::imm_i32(adj)],
+                self.block_id,
+            )
+            .with_imp_def(sp)
+            .with_imp_use(sp),
+        );
 
-        if node.ty == Type::Void {
+        if ret_ty == Type::Void {
             return call_inst;
         }
 
-        let reg_class = self.cur_func.regs_info.arena_ref()[ret_reg].reg_class;
+        let ret_reg_class = self.func.regs.arena_ref()[ret_reg].reg_class;
         let copy = MachineInst::new(
-            &self.cur_func.regs_info,
+            &self.func.regs,
             MachineOpcode::Copy,
-            vec![MachineOperand::Register(ret_reg)],
-            Some(reg_class),
-            self.cur_bb,
+            vec![MachineOperand::Register(RegisterOperand::new(ret_reg))],
+            Some(ret_reg_class),
+            self.block_id,
         );
-        self.push_inst(copy)
+        self.append_inst(copy)
     }
 
-    fn normal_operand(&mut self, node: Raw<DAGNode>) -> MachineOperand {
-        match node.kind {
-            NodeKind::Operand(OperandNodeKind::Constant(c)) => match c {
-                ConstantKind::Int8(i) => MachineOperand::Constant(MachineConstant::Int8(i)),
-                ConstantKind::Int32(i) => MachineOperand::Constant(MachineConstant::Int32(i)),
-                ConstantKind::Int64(i) => MachineOperand::Constant(MachineConstant::Int64(i)),
-                ConstantKind::F64(f) => MachineOperand::Constant(MachineConstant::F64(f)),
-            },
-            NodeKind::Operand(OperandNodeKind::FrameIndex(ref kind)) => {
-                MachineOperand::FrameIndex(kind.clone())
+    /// Passes a scalar argument in the register or on the stack.
+    fn pass_arg(
+        &mut self,
+        arg: MachineOperand,
+        ty: Type,
+        loc: &ArgLocation,
+        arg_regs: &mut Vec<RegisterOperand>,
+    ) {
+        match loc {
+            ArgLocation::Reg(r) => {
+                let r = RegisterOperand::new(self.func.regs.get_phys_reg(*r));
+                arg_regs.push(r);
+                let mov = MachineInst::new_simple(
+                    mov_rx(r.id.as_phys_reg().reg_class(), &arg).unwrap(),
+                    vec![arg],
+                    self.block_id,
+                )
+                .with_def(vec![r]);
+                self.append_inst(mov);
+            }
+            ArgLocation::FloatInGPR(r) => {
+                let r = RegisterOperand::new(self.func.regs.get_phys_reg(*r));
+                arg_regs.push(r);
+                let rc = ty2rc(&ty).unwrap();
+                let arg = self.to_register(rc, arg);
+                let opcode = if rc == RegisterClassKind::F64 {
+                    MachineOpcode::FMV_X_D
+                } else {
+                    MachineOpcode::FMV_X_W
+                };
+                let mov =
+                    MachineInst::new_simple(opcode, vec![arg], self.block_id).with_def(vec![r]);
+                self.append_inst(mov);
             }
-            NodeKind::Operand(OperandNodeKind::Address(ref g)) => match g {
-                node::AddressKind::FunctionName(n) => MachineOperand::Mem(
-                    MachineMemOperand::Address(inst::AddressKind::FunctionName(n.clone())),
-                ),
-                _ => unimplemented!(),
-            },
-            NodeKind::Operand(OperandNodeKind::BasicBlock(bb)) => {
-                MachineOperand::Branch(self.get_machine_bb(bb))
+            ArgLocation::Stack(off) => {
+                let arg = self.to_register(ty2rc(&ty).unwrap(), arg);
+                let sp = RegisterOperand::new(self.func.regs.get_phys_reg(GR64::SP));
+                let store = MachineInst::new_simple(
+                    mov_mx(&self.func.regs, &arg).unwrap(),
+                    vec![
+                        MachineOperand::Mem(MachineMemOperand::BaseOff(sp, *off)),
+                        arg,
+                    ],
+                    self.block_id,
+                );
+                self.append_inst(store);
             }
-            NodeKind::Operand(OperandNodeKind::Register(ref r)) => MachineOperand::Register(*r),
-            NodeKind::Operand(OperandNodeKind::Mem(ref mem)) => match mem {
-                MemNodeKind::FiReg => MachineOperand::Mem(MachineMemOperand::FiReg(
-                    *self.normal_operand(node.operand[0]).as_frame_index(),
-                    *self.normal_operand(node.operand[1]).as_register(),
-                )),
-                MemNodeKind::ImmReg => MachineOperand::Mem(MachineMemOperand::ImmReg(
-                    self.normal_operand(node.operand[0]).as_constant().as_i32(),
-                    *self.normal_operand(node.operand[1]).as_register(),
-                )),
-                MemNodeKind::Address => MachineOperand::Mem(MachineMemOperand::Address(
-                    inst::AddressKind::Global(*node.operand[0].as_address().as_global()),
-                )),
-                // MemNodeKind::Base => MachineOperand::Mem(MachineMemOperand::Base(
-                //     *self.normal_operand(node.operand[0]).as_register(),
-                // )),
-                // MemNodeKind::BaseAlignOff => MachineOperand::Mem(MachineMemOperand::BaseAlignOff(
-                //     *self.normal_operand(node.operand[0]).as_register(),
-                //     self.normal_operand(node.operand[1]).as_constant().as_i32(),
-                //     *self.normal_operand(node.operand[2]).as_register(),
-                // )),
-                // MemNodeKind::BaseFi => MachineOperand::Mem(MachineMemOperand::BaseFi(
-                //     *self.normal_operand(node.operand[0]).as_register(),
-                //     *self.normal_operand(node.operand[1]).as_frame_index(),
-                // )),
-                // MemNodeKind::BaseFiAlignOff => {
-                //     MachineOperand::Mem(MachineMemOperand::BaseFiAlignOff(
-                //         *self.normal_operand(node.operand[0]).as_register(),
-                //         *self.normal_operand(node.operand[1]).as_frame_index(),
-                //         self.normal_operand(node.operand[2]).as_constant().as_i32(),
-                //         *self.normal_operand(node.operand[3]).as_register(),
-                //     ))
-                // }
-                // MemNodeKind::BaseFiOff => MachineOperand::Mem(MachineMemOperand::BaseFiOff(
-                //     *self.normal_operand(node.operand[0]).as_register(),
-                //     *self.normal_operand(node.operand[1]).as_frame_index(),
-                //     self.normal_operand(node.operand[2]).as_constant().as_i32(),
-                // )),
-            },
-            NodeKind::None => MachineOperand::None,
-            _ => MachineOperand::Register(self.convert(node).unwrap()),
+            _ => unreachable!(),
         }
     }
 
-    fn get_machine_bb(&self, dag_bb_id: DAGBasicBlockId) -> MachineBasicBlockId {
-        *self.bb_map.get(&dag_bb_id).unwrap()
+    /// Copies `size` bytes from `src` to `dst + dst_off`.
+    fn copy_mem(&mut self, src: RegisterOperand, dst: RegisterOperand, dst_off: i32, size: i32) {
+        let mut off = 0;
+        for &(n, rc) in &[
+            (8, RegisterClassKind::GR64),
+            (4, RegisterClassKind::GR32),
+            (2, RegisterClassKind::GR16),
+            (1, RegisterClassKind::GR8),
+        ] {
+            while size - off >= n {
+                let mem = MachineOperand::Mem(MachineMemOperand::BaseOff(src, off));
+                let load = MachineInst::new(
+                    &self.func.regs,
+                    mov_rx(rc, &mem).unwrap(),
+                    vec![mem],
+                    Some(rc),
+                    self.block_id,
+                );
+                let r = MachineOperand::Register(load.def[0]);
+                self.append_inst(load);
+                let store = MachineInst::new_simple(
+                    mov_mx(&self.func.regs, &r).unwrap(),
+                    vec![
+                        MachineOperand::Mem(MachineMemOperand::BaseOff(dst, dst_off + off)),
+                        r,
+                    ],
+                    self.block_id,
+                );
+                self.append_inst(store);
+                off += n;
+            }
+        }
     }
 
-    pub fn push_inst(&mut self, inst: MachineInst) -> MachineInstId {
-        let inst_id = self
-            .machine_inst_arena
-            .alloc(&self.cur_func.regs_info, inst);
-        self.iseq.push(inst_id);
-        inst_id
+    /// Puts `op` in a new register unless it is already in a register.
+    fn to_register(&mut self, rc: RegisterClassKind, op: MachineOperand) -> MachineOperand {
+        if op.is_register() {
+            return op;
+        }
+        let mov = MachineInst::new(
+            &self.func.regs,
+            mov_rx(rc, &op).unwrap(),
+            vec![op],
+            Some(rc),
+            self.block_id,
+        );
+        let r = mov.def[0];
+        self.append_inst(mov);
+        MachineOperand::Register(r)
     }
-}
 
-// pub fn mov_n_rx(bit: usize, x: &MachineOperand) -> Option<MachineOpcode> {
-//     // TODO: refine code
-//     assert!(bit > 0 && ((bit & (bit - 1)) == 0));
-//
-//     let mov32rx = [MachineOpcode::MOVrr32, MachineOpcode::MOVri32];
-//     let xidx = match x {
-//         MachineOperand::Register(_) => 0,
-//         MachineOperand::Constant(_) => 1,
-//         _ => return None, // TODO: Support Address?
-//     };
-//     match bit {
-//         32 => Some(mov32rx[xidx]),
-//         _ => None,
-//     }
-// }
+    fn convert_ret(&mut self, arg: NodeId) -> MachineInstId {
+        let ret_ty = self
+            .func
+            .types
+            .compound_ty(self.func.ty)
+            .as_function()
+            .ret_ty;
+
+        if ret_ty != Type::Void {
+            let arg = self.normal_arg(arg);
+            let rc = match arg {
+                MachineOperand::Register(RegisterOperand { id, sub_super }) => {
+                    sub_super.unwrap_or_else(|| self.func.regs.arena_ref()[id].reg_class)
+                }
+                MachineOperand::Constant(MachineConstant::Int8(_)) => RegisterClassKind::GR8,
+                MachineOperand::Constant(MachineConstant::Int16(_)) => RegisterClassKind::GR16,
+                MachineOperand::Constant(MachineConstant::Int32(_)) => RegisterClassKind::GR32,
+                MachineOperand::Constant(MachineConstant::Int64(_)) => RegisterClassKind::GR64,
+                MachineOperand::Constant(MachineConstant::F32(_)) => RegisterClassKind::F32,
+                MachineOperand::Constant(MachineConstant::F64(_)) => RegisterClassKind::F64,
+                _ => panic!(),
+            };
+            let opcode = mov_rx(rc, &arg).unwrap();
+            let mov = MachineInst::new_simple(opcode, vec![arg], self.block_id).with_def(vec![
+                RegisterOperand::new(self.func.regs.get_phys_reg(rc.return_value_register())),
+            ]);
+            self.append_inst(mov);
+        }
+
+        self.append_inst(MachineInst::new_simple(
+            MachineOpcode::RET,
+            vec![],
+            self.block_id,
+        ))
+    }
 
-// pub fn mov_rx(tys: &Types, regs_info: &RegistersInfo, x: &MachineOperand) -> Option<MachineOpcode> {
-//     // TODO: special handling for float
-//     if x.get_type(regs_info).unwrap() == Type::f64 {
-//         return match x {
-//             MachineOperand::Constant(_) => Some(MachineOpcode::MOVSDrm64),
-//             MachineOperand::FrameIndex(_) | MachineOperand::Mem(_) => Some(MachineOpcode::MOVSDrm),
-//             MachineOperand::Register(_) => Some(MachineOpcode::MOVSDrr),
-//             _ => None,
-//         };
-//     }
-//
-//     let mov32rx = [
-//         MachineOpcode::MOVrr32,
-//         MachineOpcode::MOVri32,
-//         MachineOpcode::MOVrm32,
-//     ];
-//     let mov64rx = [
-//         MachineOpcode::MOVrr64,
-//         MachineOpcode::MOVri64,
-//         MachineOpcode::MOVrm64,
-//     ];
-//     let (bit, xidx) = match x {
-//         MachineOperand::Register(r) => (regs_info.arena_ref()[*r].reg_class.size_in_bits(), 0),
-//         MachineOperand::Constant(c) => (c.size_in_bits(), 1),
-//         MachineOperand::FrameIndex(f) => (f.ty.size_in_bits(tys), 2),
-//         _ => return None, // TODO: Support Address?
-//     };
-//     match bit {
-//         32 => Some(mov32rx[xidx]),
-//         64 => Some(mov64rx[xidx]),
-//         _ => None,
-//     }
-// }
-//
-// pub fn mov_mx(regs_info: &RegistersInfo, x: &MachineOperand) -> Option<MachineOpcode> {
-//     if x.get_type(regs_info).unwrap() == Type::f64 {
-//         return match x {
-//             MachineOperand::Register(_) => Some(MachineOpcode::MOVSDmr),
-//             _ => None,
-//         };
-//     }
-//
-//     let mov32mx = [MachineOpcode::MOVmr32, MachineOpcode::MOVmi32];
-//     let mov64mx = [MachineOpcode::MOVmr64, MachineOpcode::MOVmi64];
-//     // let mov64rx = [
-//     //     MachineOpcode::MOVrr64,
-//     //     MachineOpcode::MOVri64,
-//     //     MachineOpcode::MOVrm64,
-//     // ];
-//     let (bit, n) = match x {
-//         MachineOperand::Register(r) => (regs_info.arena_ref()[*r].reg_class.size_in_bits(), 0),
-//         MachineOperand::Constant(c) => (c.size_in_bits(), 1),
-//         _ => return None, // TODO: Support Address?
-//     };
-//     match bit {
-//         32 => Some(mov32mx[n]),
-//         64 => Some(mov64mx[n]),
-//         _ => None,
-//     }
-// }
+    pub fn normal_arg(&mut self, arg: NodeId) -> MachineOperand {
+        match &self.func.node_arena[arg] {
+            Node::Operand(OperandNode::Imm(ImmediateKind::Int8(i))) => {
+                MachineOperand::Constant(MachineConstant::Int8(*i))
+            }
+            Node::Operand(OperandNode::Imm(ImmediateKind::Int16(i))) => {
+                MachineOperand::Constant(MachineConstant::Int16(*i))
+            }
+            Node::Operand(OperandNode::Imm(ImmediateKind::Int32(i))) => {
+                MachineOperand::Constant(MachineConstant::Int32(*i))
+            }
+            Node::Operand(OperandNode::Imm(ImmediateKind::Int64(i))) => {
+                MachineOperand::Constant(MachineConstant::Int64(*i))
+            }
+            Node::Operand(OperandNode::Imm(ImmediateKind::F32(f))) => {
+                MachineOperand::Constant(MachineConstant::F32(*f))
+            }
+            Node::Operand(OperandNode::Imm(ImmediateKind::F64(f))) => {
+                MachineOperand::Constant(MachineConstant::F64(*f))
+            }
+            Node::Operand(OperandNode::Slot(slot)) => MachineOperand::FrameIndex(*slot),
+            Node::Operand(OperandNode::Mem(MemKind::BaseFi(args))) => {
+                MachineOperand::Mem(MachineMemOperand::BaseFi(
+                    *self.normal_arg(args[0]).as_register(),
+                    *self.normal_arg(args[1]).as_frame_index(),
+                ))
+            }
+            Node::Operand(OperandNode::Mem(MemKind::BaseFiOff(args))) => {
+                MachineOperand::Mem(MachineMemOperand::BaseFiOff(
+                    *self.normal_arg(args[0]).as_register(),
+                    *self.normal_arg(args[1]).as_frame_index(),
+                    self.normal_arg(args[2]).as_constant().as_i32(),
+                ))
+            }
+            Node::Operand(OperandNode::Mem(MemKind::BaseOff(args))) => {
+                MachineOperand::Mem(MachineMemOperand::BaseOff(
+                    *self.normal_arg(args[0]).as_register(),
+                    self.normal_arg(args[1]).as_constant().as_i32(),
+                ))
+            }
+            Node::Operand(OperandNode::Mem(MemKind::Address(arg))) => MachineOperand::Mem(
+                MachineMemOperand::Address(self.normal_arg(*arg).as_mem().as_address().clone()),
+            ),
+            Node::Operand(OperandNode::Addr(node::AddressKind::Const(id))) => {
+                MachineOperand::Mem(MachineMemOperand::Address(AddressKind::Constant(*id)))
+            }
+            Node::Operand(OperandNode::Reg(r)) => {
+                MachineOperand::Register(RegisterOperand::new(*r))
+            }
+            Node::Operand(OperandNode::Addr(node::AddressKind::FunctionName(name))) => {
+                MachineOperand::Mem(MachineMemOperand::Address(AddressKind::FunctionName(
+                    name.clone(),
+                )))
+            }
+            Node::Operand(OperandNode::Addr(node::AddressKind::Global(id))) => {
+                MachineOperand::Mem(MachineMemOperand::Address(AddressKind::Global(*id)))
+            }
+            Node::Operand(OperandNode::Block(id)) => MachineOperand::Branch(self.bb_map[id]),
+            Node::IR(_) | Node::MI(_) => MachineOperand::Register(self.convert(arg).unwrap()),
+            Node::None => MachineOperand::None,
+            e => todo!("{:?}", e),
+        }
+    }
+}
 
-pub fn opcode_copy2reg(src: &MachineOperand) -> MachineOpcode {
-    match src {
-        MachineOperand::Constant(MachineConstant::Int32(_))
-        | MachineOperand::Constant(MachineConstant::Int64(_))
-        | MachineOperand::Constant(MachineConstant::Int8(_)) => MachineOpcode::LI,
-        MachineOperand::Register(_) => MachineOpcode::MV,
-        _ => unimplemented!(),
+pub fn mov_rx(rc: RegisterClassKind, arg: &MachineOperand) -> Option<MachineOpcode> {
+    #[rustfmt::skip]
+    let (rr, ri, rm) = match rc {
+        RegisterClassKind::GR8  => (MachineOpcode::MV, MachineOpcode::LI, MachineOpcode::LB),
+        RegisterClassKind::GR16 => (MachineOpcode::MV, MachineOpcode::LI, MachineOpcode::LH),
+        RegisterClassKind::GR32 => (MachineOpcode::MV, MachineOpcode::LI, MachineOpcode::LW),
+        RegisterClassKind::GR64 => (MachineOpcode::MV, MachineOpcode::LI, MachineOpcode::LD),
+        RegisterClassKind::F32  => (MachineOpcode::FMV_S, MachineOpcode::FLI_S, MachineOpcode::FLW),
+        RegisterClassKind::F64  => (MachineOpcode::FMV_D, MachineOpcode::FLI_D, MachineOpcode::FLD),
+    };
+    match arg {
+        MachineOperand::Register(_) => Some(rr),
+        MachineOperand::Constant(_) => Some(ri),
+        MachineOperand::Mem(_) => Some(rm),
+        _ => None,
     }
 }
+
+/// Stores have no immediate forms, so only a register can be stored.
+pub fn mov_mx(regs: &RegistersInfo, arg: &MachineOperand) -> Option<MachineOpcode> {
+    let rc = match arg {
+        MachineOperand::Register(r) => r
+            .sub_super
+            .unwrap_or_else(|| regs.arena_ref()[r.id].reg_class),
+        _ => return None,
+    };
+    Some(match rc {
+        RegisterClassKind::GR8 => MachineOpcode::SB,
+        RegisterClassKind::GR16 => MachineOpcode::SH,
+        RegisterClassKind::GR32 => MachineOpcode::SW,
+        RegisterClassKind::GR64 => MachineOpcode::SD,
+        RegisterClassKind::F32 => MachineOpcode::FSW,
+        RegisterClassKind::F64 => MachineOpcode::FSD,
+    })
+}
@@ -1,8 +1,43 @@
-pub use crate::codegen::common::dag::node::*;
+pub use crate::codegen::riscv64::common::dag::node::*;
+use std::fmt;
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum MemNodeKind {
-    FiReg,
-    ImmReg,
-    Address,
+#[derive(Clone, PartialEq)]
+pub enum MemKind {
+    BaseFi([NodeId; 2]),
+    BaseFiOff([NodeId; 3]),
+    BaseOff([NodeId; 2]),
+    Address(NodeId),
+}
+
+impl MemKind {
+    pub fn args(&self) -> &[NodeId] {
+        match self {
+            Self::BaseFiOff(args) => args,
+            Self::BaseFi(args) | Self::BaseOff(args) => args,
+            Self::Address(arg) => ::core::slice::from_ref(arg),
+        }
+    }
+
+    pub fn args_mut(&mut self) -> &mut [NodeId] {
+        match self {
+            Self::BaseFiOff(args) => args,
+            Self::BaseFi(args) | Self::BaseOff(args) => args,
+            Self::Address(arg) => ::core::slice::from_mut(arg),
+        }
+    }
+}
+
+impl fmt::Debug for MemKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::BaseFi(_) => "BaseFi",
+                Self::BaseFiOff(_) => "BaseFiOff",
+                Self::BaseOff(_) => "BaseOff",
+                Self::Address(_) => "Address",
+            }
+        )
+    }
 }
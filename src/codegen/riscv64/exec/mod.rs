@@ -1,4 +1,6 @@
-// pub mod jit;
+#[path = "../../common/exec/mod.rs"]
+pub mod common;
+pub mod jit;
 
 pub fn roundup(n: i32, align: i32) -> i32 {
     (n + align - 1) & !(align - 1)
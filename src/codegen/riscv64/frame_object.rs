@@ -1,65 +1,88 @@
 use super::exec::roundup;
-pub use crate::codegen::common::machine::frame_object::*;
-use crate::codegen::common::machine::function::MachineFunction;
+use super::machine::abi::{ArgLocation, LP64D};
+use super::machine::register::{PhysReg, GR64};
+pub use crate::codegen::riscv64::common::machine::frame_object::*;
+use crate::codegen::riscv64::common::machine::function::MachineFunction;
+use crate::codegen::riscv64::common::machine::register::TargetRegisterTrait;
 use crate::ir::types::*;
 use rustc_hash::FxHashMap;
 
+// The frame looks like the following, where s0 is the stack pointer on entry. Every offset in
+// `offset_map` is from s0.
+//
+//   s0 + n    : arguments passed on the stack
+//   s0 - 8    : ra
+//   s0 - 16   : old s0
+//   s0 - 24 - : callee-saved registers
+//             : arguments passed in registers that need a stack slot
+//             : local variables
+//   sp        : outgoing arguments
 impl FrameObjectsInfo {
     pub fn new(tys: &Types, f: &MachineFunction) -> Self {
         let mut offset_map = FxHashMap::default();
-        let callee_saved_regs_byte: usize = f
-            .body
-            .appeared_phys_regs()
-            .containing_callee_saved_regs()
-            .to_phys_set()
-            .len()
-            * 8
-            + f.body.has_call() as usize * 8/*=ra*/;
-        let mut total_size = 0i32;
-
+        let saved_regs_byte = Self::saved_regs(f).len() * 8;
+        let mut offset = 16 + saved_regs_byte as i32;
         let padding = |off, align| -> i32 { (align - off % align) % align };
 
-        // TODO: Implement
-        // for (i, param_ty) in tys
-        //     .base
-        //     .borrow()
-        //     .as_function_ty(f.ty)
-        //     .unwrap()
-        //     .params_ty
-        //     .iter()
-        //     .enumerate()
-        // {
-        // let rc = ty2rc(param_ty).unwrap();
-        // if rc.get_nth_arg_reg(i).is_none() {
-        //     offset += param_ty.size_in_byte(tys);
-        //     offset_map.insert(FrameIndexKind::Arg(i), offset);
-        // }
-        // }
+        let (locs, _) = LP64D::new().arg_locations(tys, f.ty);
+        for (i, loc) in locs.into_iter().enumerate() {
+            let param_ty = tys.base.borrow().as_function_ty(f.ty).unwrap().params_ty[i];
+            match loc {
+                ArgLocation::Reg(_) => {}
+                ArgLocation::Stack(off) | ArgLocation::StructOnStack(off) => {
+                    offset_map.insert(FrameIndexKind::Arg(i), off);
+                }
+                ArgLocation::FloatInGPR(_) => {
+                    offset += 8 + padding(offset, 8);
+                    offset_map.insert(FrameIndexKind::Arg(i), -offset);
+                }
+                ArgLocation::StructInRegs(_) | ArgLocation::StructRef(_) => {
+                    let struct_ty = tys.get_element_ty(param_ty, None).unwrap();
+                    let size = struct_ty.size_in_byte(tys) as i32;
+                    offset += size + padding(offset, 8);
+                    offset_map.insert(FrameIndexKind::Arg(i), -offset);
+                }
+            }
+        }
 
         for FrameIndexInfo { idx, ty } in &f.local_mgr.locals {
             let size = ty.size_in_byte(tys) as i32;
             let align = ty.align_in_byte(tys) as i32;
-            total_size += size + padding(total_size, align);
-            offset_map.insert(*idx, -total_size);
+            offset += size + padding(offset, align);
+            offset_map.insert(*idx, -offset);
         }
 
-        let stack_down = Self::calc_max_adjust_stack_down(f) as i32;
-        total_size = roundup(total_size + stack_down, ALIGN);
+        let stack_down = Self::calc_max_adjust_stack_down(f);
 
         Self {
             offset_map,
-            total_size,
-            callee_saved_regs_byte,
+            total_size: roundup(offset + stack_down, ALIGN),
+            callee_saved_regs_byte: saved_regs_byte,
         }
     }
 
+    /// The callee-saved registers the function has to save other than s0, which is always saved
+    /// along with ra.
+    pub fn saved_regs(f: &MachineFunction) -> Vec<PhysReg> {
+        let mut regs = f
+            .body
+            .appeared_phys_regs()
+            .containing_callee_saved_regs()
+            .to_phys_set()
+            .into_iter()
+            .map(|r| r.superest_reg())
+            .filter(|&r| r != GR64::S0.as_phys_reg())
+            .collect::<Vec<_>>();
+        regs.sort_by_key(|r| r.retrieve());
+        regs.dedup();
+        regs
+    }
+
     pub fn offset(&self, kind: FrameIndexKind) -> Option<i32> {
-        self.offset_map
-            .get(&kind)
-            .map(|x| *x as i32 - roundup(self.callee_saved_regs_byte as i32, ALIGN))
+        self.offset_map.get(&kind).map(|x| *x as i32)
     }
 
     pub fn total_size(&self) -> i32 {
-        self.total_size as i32 + roundup(self.callee_saved_regs_byte as i32, ALIGN)
+        self.total_size
     }
 }
@@ -0,0 +1,210 @@
+use crate::{
+    codegen::{
+        riscv64::common::machine::calling_conv::{ArgumentRegisterOrder, CallingConv},
+        riscv64::exec::roundup,
+        riscv64::machine::register::*,
+    },
+    ir::types::{StructType, Type, Types},
+};
+
+#[derive(Clone)]
+pub struct LP64D {
+    gr8: Vec<PhysReg>,
+    gr16: Vec<PhysReg>,
+    gr32: Vec<PhysReg>,
+    gr64: Vec<PhysReg>,
+    f32: Vec<PhysReg>,
+    f64: Vec<PhysReg>,
+}
+
+/// Where an argument is passed. Stack offsets are from the stack pointer at the call, that is,
+/// from `s0` of the callee.
+#[derive(Debug, Clone)]
+pub enum ArgLocation {
+    /// In the register.
+    Reg(PhysReg),
+    /// A float in the general-purpose register as its bits, once the floating-point registers run
+    /// out.
+    FloatInGPR(PhysReg),
+    /// On the stack.
+    Stack(i32),
+    /// A struct in registers, each holding the part of the struct at the offset.
+    StructInRegs(Vec<(PhysReg, i32)>),
+    /// A struct on the stack, as it is.
+    StructOnStack(i32),
+    /// A struct as the address of a copy made by the caller, which is passed as the inner location
+    /// says.
+    StructRef(Box<ArgLocation>),
+}
+
+#[rustfmt::skip]
+impl LP64D {
+    pub fn new() -> Self {
+        Self {
+            gr8:  to_phys![GR8::A0,  GR8::A1,  GR8::A2,  GR8::A3,  GR8::A4,  GR8::A5,  GR8::A6,  GR8::A7],
+            gr16: to_phys![GR16::A0, GR16::A1, GR16::A2, GR16::A3, GR16::A4, GR16::A5, GR16::A6, GR16::A7],
+            gr32: to_phys![GR32::A0, GR32::A1, GR32::A2, GR32::A3, GR32::A4, GR32::A5, GR32::A6, GR32::A7],
+            gr64: to_phys![GR64::A0, GR64::A1, GR64::A2, GR64::A3, GR64::A4, GR64::A5, GR64::A6, GR64::A7],
+            f32:  to_phys![F32::FA0, F32::FA1, F32::FA2, F32::FA3, F32::FA4, F32::FA5, F32::FA6, F32::FA7],
+            f64:  to_phys![F64::FA0, F64::FA1, F64::FA2, F64::FA3, F64::FA4, F64::FA5, F64::FA6, F64::FA7],
+        }
+    }
+}
+
+impl LP64D {
+    /// Decides where each of the parameters of the function type goes. Also returns the bytes of
+    /// the stack used.
+    ///
+    /// A struct of one or two fields including a float goes in floating-point registers (and a
+    /// general-purpose one for the integer field) while they last. Otherwise a struct up to 16
+    /// bytes goes in general-purpose registers by 8 bytes, and a larger one by reference. Unlike
+    /// the ABI, a struct is never split between the last register and the stack.
+    pub fn arg_locations(&self, tys: &Types, func_ty: Type) -> (Vec<ArgLocation>, i32) {
+        let params: Vec<(Type, bool)> = {
+            let base = &tys.base.borrow();
+            let func_ty = base.as_function_ty(func_ty).unwrap();
+            func_ty
+                .params_ty
+                .iter()
+                .enumerate()
+                .map(|(i, ty)| (*ty, func_ty.params_attr.get(&i).map_or(false, |a| a.byval)))
+                .collect()
+        };
+        let mut order = ArgumentRegisterOrder::new(self);
+        let mut stack = 0;
+        let mut alloc_stack = |size: i32| {
+            let off = stack;
+            stack += roundup(size, 8);
+            off
+        };
+        let mut locs = vec![];
+
+        for (ty, byval) in params {
+            if byval {
+                let base = &tys.base.borrow();
+                let struct_ty = base
+                    .as_struct_ty(base.get_element_ty(ty, None).unwrap())
+                    .unwrap();
+                locs.push(Self::struct_location(
+                    &mut order,
+                    struct_ty,
+                    &mut alloc_stack,
+                ));
+                continue;
+            }
+
+            let rc = ty2rc(&ty).unwrap();
+            locs.push(match order.next(rc) {
+                Some(r) => ArgLocation::Reg(r),
+                None if matches!(rc, RegisterClassKind::F32 | RegisterClassKind::F64) => {
+                    match order.next(RegisterClassKind::GR64) {
+                        Some(r) => ArgLocation::FloatInGPR(r),
+                        None => ArgLocation::Stack(alloc_stack(8)),
+                    }
+                }
+                None => ArgLocation::Stack(alloc_stack(8)),
+            });
+        }
+
+        (locs, stack)
+    }
+
+    fn struct_location(
+        order: &mut ArgumentRegisterOrder<Self>,
+        struct_ty: &StructType,
+        alloc_stack: &mut impl FnMut(i32) -> i32,
+    ) -> ArgLocation {
+        if struct_ty.size() > 16 {
+            return ArgLocation::StructRef(Box::new(match order.next(RegisterClassKind::GR64) {
+                Some(r) => ArgLocation::Reg(r),
+                None => ArgLocation::Stack(alloc_stack(8)),
+            }));
+        }
+
+        for parts in float_parts(struct_ty)
+            .into_iter()
+            .chain(Some(int_parts(struct_ty)))
+        {
+            let rcs = parts.iter().map(|&(rc, _)| rc).collect::<Vec<_>>();
+            if order.regs_available_for(&rcs) {
+                return ArgLocation::StructInRegs(
+                    parts
+                        .into_iter()
+                        .map(|(rc, off)| (order.next(rc).unwrap(), off))
+                        .collect(),
+                );
+            }
+        }
+
+        ArgLocation::StructOnStack(alloc_stack(struct_ty.size() as i32))
+    }
+}
+
+impl CallingConv for LP64D {
+    fn get_nth_arg_reg(&self, rc: RegisterClassKind, nth: usize) -> Option<PhysReg> {
+        match rc {
+            RegisterClassKind::GR8 => self.gr8.get(nth),
+            RegisterClassKind::GR16 => self.gr16.get(nth),
+            RegisterClassKind::GR32 => self.gr32.get(nth),
+            RegisterClassKind::GR64 => self.gr64.get(nth),
+            RegisterClassKind::F32 => self.f32.get(nth),
+            RegisterClassKind::F64 => self.f64.get(nth),
+        }
+        .map_or(None, |r| Some(*r))
+    }
+
+    fn reg_classes_used_for_passing_byval(struct_ty: &StructType) -> Vec<RegisterClassKind> {
+        if struct_ty.size() > 16 {
+            return vec![];
+        }
+        float_parts(struct_ty)
+            .unwrap_or_else(|| int_parts(struct_ty))
+            .into_iter()
+            .map(|(rc, _)| rc)
+            .collect()
+    }
+}
+
+/// The fields of a struct of one or two scalar fields, at least one of which is a float.
+fn float_parts(struct_ty: &StructType) -> Option<Vec<(RegisterClassKind, i32)>> {
+    let mut parts = vec![];
+    let mut i = 0;
+    while let Some(&off) = struct_ty.get_elem_offset(i) {
+        let rc = match struct_ty.get_type_at(off)? {
+            Type::f64 => RegisterClassKind::F64,
+            Type::f32 => RegisterClassKind::F32,
+            Type::i8 => RegisterClassKind::GR8,
+            Type::i16 => RegisterClassKind::GR16,
+            Type::i32 => RegisterClassKind::GR32,
+            Type::i64 | Type::Pointer(_) => RegisterClassKind::GR64,
+            _ => return None,
+        };
+        parts.push((rc, off as i32));
+        i += 1;
+    }
+    let floats = parts
+        .iter()
+        .filter(|(rc, _)| matches!(rc, RegisterClassKind::F32 | RegisterClassKind::F64))
+        .count();
+    if parts.len() > 2 || floats == 0 {
+        return None;
+    }
+    Some(parts)
+}
+
+/// The 8-byte pieces of a struct, the last of which may be 4 bytes.
+fn int_parts(struct_ty: &StructType) -> Vec<(RegisterClassKind, i32)> {
+    let sz = struct_ty.size() as i32;
+    assert!(sz % 4 == 0);
+    (0..sz)
+        .step_by(8)
+        .map(|off| {
+            let rc = if sz - off >= 8 {
+                RegisterClassKind::GR64
+            } else {
+                RegisterClassKind::GR32
+            };
+            (rc, off)
+        })
+        .collect()
+}
@@ -3,7 +3,7 @@ use crate::{
         dom_tree::DominatorTreeConstructor,
         loops::{Loops, LoopsConstructor},
     },
-    codegen::common::machine::{
+    codegen::riscv64::common::machine::{
         basic_block::MachineBasicBlock, function::MachineFunction, liveness::LiveRegMatrix,
     },
     traits::function::FunctionTrait,
@@ -0,0 +1,37 @@
+use crate::codegen::riscv64::common::machine::eliminate_fi::EliminateFrameIndexOnFunction;
+use crate::codegen::riscv64::common::machine::frame_object::{FrameIndexInfo, FrameObjectsInfo};
+use crate::codegen::riscv64::common::machine::inst::{MachineInst, MachineOperand};
+use crate::codegen::riscv64::machine::inst::MachineMemOperand;
+
+impl<'a> EliminateFrameIndexOnFunction<'a> {
+    pub fn get_frame_index(inst: &MachineInst) -> Option<&FrameIndexInfo> {
+        for op in &inst.operand {
+            match op {
+                MachineOperand::Mem(MachineMemOperand::BaseFi(_, fi))
+                | MachineOperand::Mem(MachineMemOperand::BaseFiOff(_, fi, _)) => return Some(fi),
+                _ => {}
+            }
+        }
+        None
+    }
+
+    pub fn replace_frame_index(frame_objects: &FrameObjectsInfo, inst: &mut MachineInst) {
+        for op in &mut inst.operand {
+            match op {
+                MachineOperand::Mem(MachineMemOperand::BaseFi(base, fi)) => {
+                    *op = MachineOperand::Mem(MachineMemOperand::BaseOff(
+                        *base,
+                        frame_objects.offset(fi.idx).unwrap(),
+                    ))
+                }
+                MachineOperand::Mem(MachineMemOperand::BaseFiOff(base, fi, off)) => {
+                    *op = MachineOperand::Mem(MachineMemOperand::BaseOff(
+                        *base,
+                        frame_objects.offset(fi.idx).unwrap() + *off,
+                    ))
+                }
+                _ => {}
+            }
+        }
+    }
+}
@@ -1,36 +1,58 @@
-use super::super::machine::register::RegisterId;
 use super::frame_object::*;
 pub use super::inst_def::TargetOpcode;
-pub use crate::codegen::common::machine::{basic_block::MachineBasicBlockId, inst::*};
+pub use crate::codegen::riscv64::common::machine::{basic_block::MachineBasicBlockId, inst::*};
 use crate::ir::types::Type;
 
 #[derive(Debug, Clone)]
 pub enum MachineMemOperand {
-    FiReg(FrameIndexInfo, RegisterId),
-    ImmReg(i32, RegisterId),
+    BaseFi(RegisterOperand, FrameIndexInfo),
+    BaseFiOff(RegisterOperand, FrameIndexInfo, i32),
+    BaseOff(RegisterOperand, i32),
     Address(AddressKind),
 }
 
 impl MachineOpcode {
     pub fn is_copy_like(&self) -> bool {
-        matches!(self, MachineOpcode::MV | MachineOpcode::Copy)
+        matches!(
+            self,
+            MachineOpcode::Copy | MachineOpcode::MV | MachineOpcode::FMV_D | MachineOpcode::FMV_S
+        )
     }
 
     pub fn is_terminator(&self) -> bool {
         matches!(
             self,
-            MachineOpcode::Ret | MachineOpcode::JR | MachineOpcode::J | MachineOpcode::BEQ
+            MachineOpcode::RET | MachineOpcode::Ret | MachineOpcode::J
+        ) || self.is_conditional_jmp()
+    }
+
+    pub fn is_store(&self) -> bool {
+        matches!(
+            self,
+            MachineOpcode::SB
+                | MachineOpcode::SH
+                | MachineOpcode::SW
+                | MachineOpcode::SD
+                | MachineOpcode::FSW
+                | MachineOpcode::FSD
         )
     }
 
     pub fn is_unconditional_jmp(&self) -> bool {
-        matches!(self, MachineOpcode::JR | MachineOpcode::J)
+        matches!(self, MachineOpcode::J)
     }
 
     pub fn is_conditional_jmp(&self) -> bool {
         matches!(
             self,
-            MachineOpcode::BEQ | MachineOpcode::BLT | MachineOpcode::BLE
+            MachineOpcode::BEQ
+                | MachineOpcode::BNE
+                | MachineOpcode::BLT
+                | MachineOpcode::BGE
+                | MachineOpcode::BLTU
+                | MachineOpcode::BGEU
+                | MachineOpcode::BEQZ
+                | MachineOpcode::BNEZ
         )
     }
 
@@ -38,10 +60,12 @@ impl MachineOpcode {
         match self {
             Self::BEQ => Some(Self::BNE),
             Self::BNE => Some(Self::BEQ),
-            Self::BGE => Some(Self::BLT),
-            Self::BGT => Some(Self::BLE),
-            Self::BLE => Some(Self::BGT),
             Self::BLT => Some(Self::BGE),
+            Self::BGE => Some(Self::BLT),
+            Self::BLTU => Some(Self::BGEU),
+            Self::BGEU => Some(Self::BLTU),
+            Self::BEQZ => Some(Self::BNEZ),
+            Self::BNEZ => Some(Self::BEQZ),
             _ => None,
         }
     }
@@ -53,38 +77,30 @@ impl MachineInst {
             return None;
         }
 
-        match self.opcode {
-            MachineOpcode::J => Some(self.operand[0].as_basic_block()),
-            MachineOpcode::BEQ
-            | MachineOpcode::BNE
-            | MachineOpcode::BGE
-            | MachineOpcode::BGT
-            | MachineOpcode::BLE
-            | MachineOpcode::BLT => Some(self.operand[2].as_basic_block()),
-            _ => None,
-        }
+        // The destination is always the last operand.
+        self.operand.last().map(|op| op.as_basic_block())
     }
 }
 
 impl MachineMemOperand {
-    pub fn registers(&self) -> Vec<&RegisterId> {
+    pub fn registers(&self) -> Vec<&RegisterOperand> {
         match self {
-            MachineMemOperand::ImmReg(_, r) | MachineMemOperand::FiReg(_, r) => vec![r],
-            MachineMemOperand::Address(_) => vec![],
+            Self::BaseFi(r, _) | Self::BaseFiOff(r, _, _) | Self::BaseOff(r, _) => vec![r],
+            Self::Address(_) => vec![],
         }
     }
 
-    pub fn registers_mut(&mut self) -> Vec<&mut RegisterId> {
+    pub fn registers_mut(&mut self) -> Vec<&mut RegisterOperand> {
         match self {
-            MachineMemOperand::ImmReg(_, r) | MachineMemOperand::FiReg(_, r) => vec![r],
-            MachineMemOperand::Address(_) => vec![],
+            Self::BaseFi(r, _) | Self::BaseFiOff(r, _, _) | Self::BaseOff(r, _) => vec![r],
+            Self::Address(_) => vec![],
         }
     }
 
     pub fn get_type(&self) -> Option<Type> {
         match self {
-            Self::FiReg(fi, _) => Some(fi.ty),
-            Self::ImmReg(_, _) | Self::Address(_) => None,
+            Self::BaseFi(_, fi) | Self::BaseFiOff(_, fi, _) => Some(fi.ty),
+            Self::BaseOff(_, _) | Self::Address(_) => None,
         }
     }
 }
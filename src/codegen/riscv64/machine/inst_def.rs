@@ -1,5 +1,5 @@
-use crate::codegen::arch::machine::register::*;
-use crate::codegen::common::machine::inst_def::*;
+use crate::codegen::riscv64::common::machine::inst_def::*;
+use crate::codegen::riscv64::machine::register::*;
 
 #[allow(non_upper_case_globals)]
 mod inst {
@@ -7,175 +7,548 @@ mod inst {
 
     // TODO: need macro to describe the followings
     lazy_static! {
-        pub static ref ADDI: TargetInstDef = TargetInstDef::new("addi", TargetOpcode::ADDI)
-            .set_uses(vec![
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-                TargetOperand::Immediate(TargetImmediate::I32),
-            ])
-            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GPR)]);
-        pub static ref ADDIW: TargetInstDef = TargetInstDef::new("addiw", TargetOpcode::ADDIW)
-            .set_uses(vec![
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-                TargetOperand::Immediate(TargetImmediate::I32),
-            ])
-            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GPR)]);
-        pub static ref ADDW: TargetInstDef = TargetInstDef::new("addw", TargetOpcode::ADDW)
-            .set_uses(vec![
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-            ])
-            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GPR)]);
+        // integer arithmetic
         pub static ref ADD: TargetInstDef = TargetInstDef::new("add", TargetOpcode::ADD)
-            .set_uses(vec![
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-            ])
-            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GPR)]);
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref SUB: TargetInstDef = TargetInstDef::new("sub", TargetOpcode::SUB)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
         pub static ref MUL: TargetInstDef = TargetInstDef::new("mul", TargetOpcode::MUL)
-            .set_uses(vec![
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-            ])
-            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GPR)]);
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref DIV: TargetInstDef = TargetInstDef::new("div", TargetOpcode::DIV)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref DIVU: TargetInstDef = TargetInstDef::new("divu", TargetOpcode::DIVU)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref REM: TargetInstDef = TargetInstDef::new("rem", TargetOpcode::REM)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref REMU: TargetInstDef = TargetInstDef::new("remu", TargetOpcode::REMU)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref AND: TargetInstDef = TargetInstDef::new("and", TargetOpcode::AND)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref OR: TargetInstDef = TargetInstDef::new("or", TargetOpcode::OR)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref XOR: TargetInstDef = TargetInstDef::new("xor", TargetOpcode::XOR)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref SLL: TargetInstDef = TargetInstDef::new("sll", TargetOpcode::SLL)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref SRL: TargetInstDef = TargetInstDef::new("srl", TargetOpcode::SRL)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref SRA: TargetInstDef = TargetInstDef::new("sra", TargetOpcode::SRA)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref SLT: TargetInstDef = TargetInstDef::new("slt", TargetOpcode::SLT)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref SLTU: TargetInstDef = TargetInstDef::new("sltu", TargetOpcode::SLTU)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref ADDI: TargetInstDef = TargetInstDef::new("addi", TargetOpcode::ADDI)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Immediate(TargetImmediate::I32)])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref ANDI: TargetInstDef = TargetInstDef::new("andi", TargetOpcode::ANDI)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Immediate(TargetImmediate::I32)])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref ORI: TargetInstDef = TargetInstDef::new("ori", TargetOpcode::ORI)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Immediate(TargetImmediate::I32)])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref XORI: TargetInstDef = TargetInstDef::new("xori", TargetOpcode::XORI)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Immediate(TargetImmediate::I32)])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref SLTI: TargetInstDef = TargetInstDef::new("slti", TargetOpcode::SLTI)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Immediate(TargetImmediate::I32)])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref SLTIU: TargetInstDef = TargetInstDef::new("sltiu", TargetOpcode::SLTIU)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Immediate(TargetImmediate::I32)])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref SLLI: TargetInstDef = TargetInstDef::new("slli", TargetOpcode::SLLI)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Immediate(TargetImmediate::I32)])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref SRLI: TargetInstDef = TargetInstDef::new("srli", TargetOpcode::SRLI)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Immediate(TargetImmediate::I32)])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref SRAI: TargetInstDef = TargetInstDef::new("srai", TargetOpcode::SRAI)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
+                           TargetOperand::Immediate(TargetImmediate::I32)])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        // 32-bit integer arithmetic. the results are sign-extended to 64 bits
+        pub static ref ADDW: TargetInstDef = TargetInstDef::new("addw", TargetOpcode::ADDW)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref SUBW: TargetInstDef = TargetInstDef::new("subw", TargetOpcode::SUBW)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
         pub static ref MULW: TargetInstDef = TargetInstDef::new("mulw", TargetOpcode::MULW)
-            .set_uses(vec![
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-            ])
-            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GPR)]);
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
         pub static ref DIVW: TargetInstDef = TargetInstDef::new("divw", TargetOpcode::DIVW)
-            .set_uses(vec![
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-            ])
-            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GPR)]);
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref DIVUW: TargetInstDef = TargetInstDef::new("divuw", TargetOpcode::DIVUW)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
         pub static ref REMW: TargetInstDef = TargetInstDef::new("remw", TargetOpcode::REMW)
-            .set_uses(vec![
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-            ])
-            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GPR)]);
-        pub static ref SLLI: TargetInstDef = TargetInstDef::new("slli", TargetOpcode::SLLI)
-            .set_uses(vec![
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-                TargetOperand::Immediate(TargetImmediate::I8)
-            ])
-            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GPR)]);
-        pub static ref MV: TargetInstDef = TargetInstDef::new("mv", TargetOpcode::LI)
-            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(
-                RegisterClassKind::GPR
-            ))])
-            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GPR)]);
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref REMUW: TargetInstDef = TargetInstDef::new("remuw", TargetOpcode::REMUW)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref SLLW: TargetInstDef = TargetInstDef::new("sllw", TargetOpcode::SLLW)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref SRLW: TargetInstDef = TargetInstDef::new("srlw", TargetOpcode::SRLW)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref SRAW: TargetInstDef = TargetInstDef::new("sraw", TargetOpcode::SRAW)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref ADDIW: TargetInstDef = TargetInstDef::new("addiw", TargetOpcode::ADDIW)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Immediate(TargetImmediate::I32)])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref SLLIW: TargetInstDef = TargetInstDef::new("slliw", TargetOpcode::SLLIW)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Immediate(TargetImmediate::I32)])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref SRLIW: TargetInstDef = TargetInstDef::new("srliw", TargetOpcode::SRLIW)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Immediate(TargetImmediate::I32)])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref SRAIW: TargetInstDef = TargetInstDef::new("sraiw", TargetOpcode::SRAIW)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
+                           TargetOperand::Immediate(TargetImmediate::I32)])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        // pseudo instructions for integers
+        pub static ref SEQZ: TargetInstDef = TargetInstDef::new("seqz", TargetOpcode::SEQZ)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any)])
+            .set_defs(vec![TargetRegister::Any]);
+        pub static ref SNEZ: TargetInstDef = TargetInstDef::new("snez", TargetOpcode::SNEZ)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any)])
+            .set_defs(vec![TargetRegister::Any]);
+        pub static ref NEG: TargetInstDef = TargetInstDef::new("neg", TargetOpcode::NEG)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any)])
+            .set_defs(vec![TargetRegister::Any]);
+        pub static ref MV: TargetInstDef = TargetInstDef::new("mv", TargetOpcode::MV)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any)])
+            .set_defs(vec![TargetRegister::Any]);
+        pub static ref LI: TargetInstDef = TargetInstDef::new("li", TargetOpcode::LI)
+            .set_uses(vec![TargetOperand::Immediate(TargetImmediate::I32)])
+            .set_defs(vec![TargetRegister::Any]);
         pub static ref LA: TargetInstDef = TargetInstDef::new("la", TargetOpcode::LA)
             .set_uses(vec![TargetOperand::Mem])
-            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GPR)]);
-        pub static ref LI: TargetInstDef = TargetInstDef::new("li", TargetOpcode::LI)
-            .set_uses(vec![TargetOperand::Any])
-            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GPR)]);
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref ADDIm: TargetInstDef = TargetInstDef::new("addi", TargetOpcode::ADDIm)
+            .set_uses(vec![TargetOperand::Mem])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        // loads and stores. the memory operand comes first
+        pub static ref LB: TargetInstDef = TargetInstDef::new("lb", TargetOpcode::LB)
+            .set_uses(vec![TargetOperand::Mem])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR8)]);
+        pub static ref LH: TargetInstDef = TargetInstDef::new("lh", TargetOpcode::LH)
+            .set_uses(vec![TargetOperand::Mem])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR16)]);
         pub static ref LW: TargetInstDef = TargetInstDef::new("lw", TargetOpcode::LW)
             .set_uses(vec![TargetOperand::Mem])
-            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GPR)]);
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
         pub static ref LD: TargetInstDef = TargetInstDef::new("ld", TargetOpcode::LD)
             .set_uses(vec![TargetOperand::Mem])
-            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GPR)]);
-        pub static ref SW: TargetInstDef =
-            TargetInstDef::new("sw", TargetOpcode::SW).set_uses(vec![
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-                TargetOperand::Mem
-            ]);
-        pub static ref SD: TargetInstDef =
-            TargetInstDef::new("sd", TargetOpcode::SD).set_uses(vec![
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-                TargetOperand::Mem
-            ]);
-        pub static ref SEXT_W: TargetInstDef = TargetInstDef::new("sext.w", TargetOpcode::SEXT_W)
-            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(
-                RegisterClassKind::GPR
-            )),])
-            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GPR)]);
-        pub static ref CALL: TargetInstDef = TargetInstDef::new("call", TargetOpcode::CALL);
-        pub static ref BEQ: TargetInstDef =
-            TargetInstDef::new("beq", TargetOpcode::BEQ).set_uses(vec![
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-                TargetOperand::Block
-            ]);
-        pub static ref BNE: TargetInstDef =
-            TargetInstDef::new("bne", TargetOpcode::BNE).set_uses(vec![
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-                TargetOperand::Block
-            ]);
-        pub static ref BLE: TargetInstDef =
-            TargetInstDef::new("ble", TargetOpcode::BLE).set_uses(vec![
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-                TargetOperand::Block
-            ]);
-        pub static ref BLT: TargetInstDef =
-            TargetInstDef::new("blt", TargetOpcode::BLT).set_uses(vec![
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-                TargetOperand::Block
-            ]);
-        pub static ref BGE: TargetInstDef =
-            TargetInstDef::new("bge", TargetOpcode::BGE).set_uses(vec![
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-                TargetOperand::Block
-            ]);
-        pub static ref BGT: TargetInstDef =
-            TargetInstDef::new("bgt", TargetOpcode::BGT).set_uses(vec![
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GPR)),
-                TargetOperand::Block
-            ]);
-        pub static ref J: TargetInstDef =
-            TargetInstDef::new("j", TargetOpcode::J).set_uses(vec![TargetOperand::Block]);
-        pub static ref JR: TargetInstDef =
-            TargetInstDef::new("jr", TargetOpcode::JR).set_uses(vec![TargetOperand::Register(
-                TargetRegister::RegClass(RegisterClassKind::GPR)
-            )]);
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref FLW: TargetInstDef = TargetInstDef::new("flw", TargetOpcode::FLW)
+            .set_uses(vec![TargetOperand::Mem])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F32)]);
+        pub static ref FLD: TargetInstDef = TargetInstDef::new("fld", TargetOpcode::FLD)
+            .set_uses(vec![TargetOperand::Mem])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F64)]);
+        pub static ref SB: TargetInstDef = TargetInstDef::new("sb", TargetOpcode::SB)
+            .set_uses(vec![TargetOperand::Mem,
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR8))]);
+        pub static ref SH: TargetInstDef = TargetInstDef::new("sh", TargetOpcode::SH)
+            .set_uses(vec![TargetOperand::Mem,
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR16))]);
+        pub static ref SW: TargetInstDef = TargetInstDef::new("sw", TargetOpcode::SW)
+            .set_uses(vec![TargetOperand::Mem,
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))]);
+        pub static ref SD: TargetInstDef = TargetInstDef::new("sd", TargetOpcode::SD)
+            .set_uses(vec![TargetOperand::Mem,
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))]);
+        pub static ref FSW: TargetInstDef = TargetInstDef::new("fsw", TargetOpcode::FSW)
+            .set_uses(vec![TargetOperand::Mem,
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32))]);
+        pub static ref FSD: TargetInstDef = TargetInstDef::new("fsd", TargetOpcode::FSD)
+            .set_uses(vec![TargetOperand::Mem,
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64))]);
+        // floating-point arithmetic
+        pub static ref FADD_D: TargetInstDef = TargetInstDef::new("fadd.d", TargetOpcode::FADD_D)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F64)]);
+        pub static ref FSUB_D: TargetInstDef = TargetInstDef::new("fsub.d", TargetOpcode::FSUB_D)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F64)]);
+        pub static ref FMUL_D: TargetInstDef = TargetInstDef::new("fmul.d", TargetOpcode::FMUL_D)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F64)]);
+        pub static ref FDIV_D: TargetInstDef = TargetInstDef::new("fdiv.d", TargetOpcode::FDIV_D)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F64)]);
+        pub static ref FADD_S: TargetInstDef = TargetInstDef::new("fadd.s", TargetOpcode::FADD_S)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F32)]);
+        pub static ref FSUB_S: TargetInstDef = TargetInstDef::new("fsub.s", TargetOpcode::FSUB_S)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F32)]);
+        pub static ref FMUL_S: TargetInstDef = TargetInstDef::new("fmul.s", TargetOpcode::FMUL_S)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F32)]);
+        pub static ref FDIV_S: TargetInstDef = TargetInstDef::new("fdiv.s", TargetOpcode::FDIV_S)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F32)]);
+        pub static ref FEQ_D: TargetInstDef = TargetInstDef::new("feq.d", TargetOpcode::FEQ_D)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref FLT_D: TargetInstDef = TargetInstDef::new("flt.d", TargetOpcode::FLT_D)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref FLE_D: TargetInstDef = TargetInstDef::new("fle.d", TargetOpcode::FLE_D)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref FEQ_S: TargetInstDef = TargetInstDef::new("feq.s", TargetOpcode::FEQ_S)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref FLT_S: TargetInstDef = TargetInstDef::new("flt.s", TargetOpcode::FLT_S)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref FLE_S: TargetInstDef = TargetInstDef::new("fle.s", TargetOpcode::FLE_S)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32)),
+                           TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        // conversions between integers and floating-point values
+        pub static ref FCVT_W_D: TargetInstDef = TargetInstDef::new("fcvt.w.d", TargetOpcode::FCVT_W_D)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref FCVT_WU_D: TargetInstDef = TargetInstDef::new("fcvt.wu.d", TargetOpcode::FCVT_WU_D)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref FCVT_L_D: TargetInstDef = TargetInstDef::new("fcvt.l.d", TargetOpcode::FCVT_L_D)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref FCVT_LU_D: TargetInstDef = TargetInstDef::new("fcvt.lu.d", TargetOpcode::FCVT_LU_D)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref FCVT_W_S: TargetInstDef = TargetInstDef::new("fcvt.w.s", TargetOpcode::FCVT_W_S)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref FCVT_WU_S: TargetInstDef = TargetInstDef::new("fcvt.wu.s", TargetOpcode::FCVT_WU_S)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref FCVT_L_S: TargetInstDef = TargetInstDef::new("fcvt.l.s", TargetOpcode::FCVT_L_S)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref FCVT_LU_S: TargetInstDef = TargetInstDef::new("fcvt.lu.s", TargetOpcode::FCVT_LU_S)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref FCVT_D_W: TargetInstDef = TargetInstDef::new("fcvt.d.w", TargetOpcode::FCVT_D_W)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F64)]);
+        pub static ref FCVT_D_WU: TargetInstDef = TargetInstDef::new("fcvt.d.wu", TargetOpcode::FCVT_D_WU)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F64)]);
+        pub static ref FCVT_D_L: TargetInstDef = TargetInstDef::new("fcvt.d.l", TargetOpcode::FCVT_D_L)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F64)]);
+        pub static ref FCVT_D_LU: TargetInstDef = TargetInstDef::new("fcvt.d.lu", TargetOpcode::FCVT_D_LU)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F64)]);
+        pub static ref FCVT_S_W: TargetInstDef = TargetInstDef::new("fcvt.s.w", TargetOpcode::FCVT_S_W)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F32)]);
+        pub static ref FCVT_S_WU: TargetInstDef = TargetInstDef::new("fcvt.s.wu", TargetOpcode::FCVT_S_WU)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F32)]);
+        pub static ref FCVT_S_L: TargetInstDef = TargetInstDef::new("fcvt.s.l", TargetOpcode::FCVT_S_L)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F32)]);
+        pub static ref FCVT_S_LU: TargetInstDef = TargetInstDef::new("fcvt.s.lu", TargetOpcode::FCVT_S_LU)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F32)]);
+        pub static ref FCVT_D_S: TargetInstDef = TargetInstDef::new("fcvt.d.s", TargetOpcode::FCVT_D_S)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F64)]);
+        pub static ref FCVT_S_D: TargetInstDef = TargetInstDef::new("fcvt.s.d", TargetOpcode::FCVT_S_D)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F32)]);
+        // floating-point moves
+        pub static ref FMV_D: TargetInstDef = TargetInstDef::new("fmv.d", TargetOpcode::FMV_D)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F64)]);
+        pub static ref FMV_S: TargetInstDef = TargetInstDef::new("fmv.s", TargetOpcode::FMV_S)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F32)]);
+        pub static ref FMV_D_X: TargetInstDef = TargetInstDef::new("fmv.d.x", TargetOpcode::FMV_D_X)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F64)]);
+        pub static ref FMV_X_D: TargetInstDef = TargetInstDef::new("fmv.x.d", TargetOpcode::FMV_X_D)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F64))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)]);
+        pub static ref FMV_W_X: TargetInstDef = TargetInstDef::new("fmv.w.x", TargetOpcode::FMV_W_X)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F32)]);
+        pub static ref FMV_X_W: TargetInstDef = TargetInstDef::new("fmv.x.w", TargetOpcode::FMV_X_W)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::F32))])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)]);
+        pub static ref FLI_D: TargetInstDef = TargetInstDef::new("fli.d", TargetOpcode::FLI_D)
+            .set_uses(vec![TargetOperand::Immediate(TargetImmediate::F64)])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F64)]);
+        pub static ref FLI_S: TargetInstDef = TargetInstDef::new("fli.s", TargetOpcode::FLI_S)
+            .set_uses(vec![TargetOperand::Immediate(TargetImmediate::F32)])
+            .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::F32)]);
+        // control flow
+        pub static ref BEQ: TargetInstDef = TargetInstDef::new("beq", TargetOpcode::BEQ)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any),
+                           TargetOperand::Register(TargetRegister::Any),
+                           TargetOperand::Block]);
+        pub static ref BNE: TargetInstDef = TargetInstDef::new("bne", TargetOpcode::BNE)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any),
+                           TargetOperand::Register(TargetRegister::Any),
+                           TargetOperand::Block]);
+        pub static ref BLT: TargetInstDef = TargetInstDef::new("blt", TargetOpcode::BLT)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any),
+                           TargetOperand::Register(TargetRegister::Any),
+                           TargetOperand::Block]);
+        pub static ref BGE: TargetInstDef = TargetInstDef::new("bge", TargetOpcode::BGE)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any),
+                           TargetOperand::Register(TargetRegister::Any),
+                           TargetOperand::Block]);
+        pub static ref BLTU: TargetInstDef = TargetInstDef::new("bltu", TargetOpcode::BLTU)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any),
+                           TargetOperand::Register(TargetRegister::Any),
+                           TargetOperand::Block]);
+        pub static ref BGEU: TargetInstDef = TargetInstDef::new("bgeu", TargetOpcode::BGEU)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any),
+                           TargetOperand::Register(TargetRegister::Any),
+                           TargetOperand::Block]);
+        pub static ref BEQZ: TargetInstDef = TargetInstDef::new("beqz", TargetOpcode::BEQZ)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any),
+                           TargetOperand::Block]);
+        pub static ref BNEZ: TargetInstDef = TargetInstDef::new("bnez", TargetOpcode::BNEZ)
+            .set_uses(vec![TargetOperand::Register(TargetRegister::Any),
+                           TargetOperand::Block]);
+        pub static ref J: TargetInstDef = TargetInstDef::new("j", TargetOpcode::J)
+            .set_uses(vec![TargetOperand::Block]);
+        pub static ref CALL: TargetInstDef = TargetInstDef::new("call", TargetOpcode::CALL)
+            .set_uses(vec![TargetOperand::Addr]);
+        pub static ref RET: TargetInstDef = TargetInstDef::new("ret", TargetOpcode::RET);
     }
 }
 
+/// How an instruction is encoded into a 32-bit RV64 word. A `u32` is the word with every register
+/// and immediate field zero.
+#[derive(Debug, Clone, Copy)]
+pub enum Encoding {
+    /// Just the given word.
+    Raw(u32),
+    /// `op rd, rs1, rs2`
+    R(u32),
+    /// `op rd, rs1, imm12`
+    I(u32),
+    /// `op rd, rs1, shamt`
+    Shift(u32),
+    /// `op rd, rs1`
+    Unary(u32),
+    /// `op rd, rs2`, which is `rs1` being `zero`.
+    UnaryRs2(u32),
+    /// `fmv rd, rs`, which is `fsgnj rd, rs, rs`.
+    FMv(u32),
+    /// `op rd, offset(rs1)`
+    Load(u32),
+    /// `op rs2, offset(rs1)`
+    Store(u32),
+    /// `op rs1, rs2, label`
+    Branch(u32),
+    /// `op rs1, label`, which is `rs2` being `zero`.
+    BranchZ(u32),
+    /// `j label`
+    Jal,
+    /// `call function`, which is `auipc ra` followed by `jalr ra`.
+    Call,
+    /// `li rd, imm`, expanded to `lui`/`addiw`/`slli`/`addi`.
+    Li,
+    /// `la rd, symbol`, which is `auipc rd` followed by `addi rd`.
+    La,
+    /// `addi rd, s0, offset` of a stack slot, which may need `lui` for a large offset.
+    AddiMem,
+    /// `li t0, imm` followed by the given `fmv.{d,w}.x rd, t0`.
+    FLi(u32),
+}
+
 #[derive(Debug, Clone, PartialEq, Copy)]
 #[allow(non_camel_case_types)]
 pub enum TargetOpcode {
-    // CALL,
-
-    // Comparison
-    Seteq,
-    Setle,
-    Setlt,
-
-    ADDI,  // Add Integer
-    ADDIW, // Add Integer Word
-    ADDW,  // Add Word
-    ADD,   // Add
-    MUL,   // Mul
-    MULW,  // Mul Word
-    DIVW,  // Div Word
-    REMW,  // Rem Word
-    SLLI,  // Shift Left Logical Immediate
-    MV,    // Move
+    ADD,
+    SUB,
+    MUL,
+    DIV,
+    DIVU,
+    REM,
+    REMU,
+    AND,
+    OR,
+    XOR,
+    SLL,
+    SRL,
+    SRA,
+    SLT,
+    SLTU,
+    ADDI,
+    ANDI,
+    ORI,
+    XORI,
+    SLTI,
+    SLTIU,
+    SLLI,
+    SRLI,
+    SRAI,
+    ADDW,
+    SUBW,
+    MULW,
+    DIVW,
+    DIVUW,
+    REMW,
+    REMUW,
+    SLLW,
+    SRLW,
+    SRAW,
+    ADDIW,
+    SLLIW,
+    SRLIW,
+    SRAIW,
+    SEQZ,
+    SNEZ,
+    NEG,
+    MV,
+    LI,
     LA,
-    LI,     // Load Immediate
-    LW,     // Load Word
-    LD,     // Load Double
-    SW,     // Store Word
-    SD,     // Store Double
-    SEXT_W, // Sign-extend Word
-    CALL,
+    ADDIm,
+    LB,
+    LH,
+    LW,
+    LD,
+    FLW,
+    FLD,
+    SB,
+    SH,
+    SW,
+    SD,
+    FSW,
+    FSD,
+    FADD_D,
+    FSUB_D,
+    FMUL_D,
+    FDIV_D,
+    FADD_S,
+    FSUB_S,
+    FMUL_S,
+    FDIV_S,
+    FEQ_D,
+    FLT_D,
+    FLE_D,
+    FEQ_S,
+    FLT_S,
+    FLE_S,
+    FCVT_W_D,
+    FCVT_WU_D,
+    FCVT_L_D,
+    FCVT_LU_D,
+    FCVT_W_S,
+    FCVT_WU_S,
+    FCVT_L_S,
+    FCVT_LU_S,
+    FCVT_D_W,
+    FCVT_D_WU,
+    FCVT_D_L,
+    FCVT_D_LU,
+    FCVT_S_W,
+    FCVT_S_WU,
+    FCVT_S_L,
+    FCVT_S_LU,
+    FCVT_D_S,
+    FCVT_S_D,
+    FMV_D,
+    FMV_S,
+    FMV_D_X,
+    FMV_X_D,
+    FMV_W_X,
+    FMV_X_W,
+    FLI_D,
+    FLI_S,
     BEQ,
     BNE,
-    BLE,
     BLT,
     BGE,
-    BGT,
+    BLTU,
+    BGEU,
+    BEQZ,
+    BNEZ,
     J,
-    JR,
+    CALL,
+    RET,
 
+    // Pseudo instructions
     Phi,
     Ret,
     Copy,
@@ -187,33 +560,231 @@ pub enum TargetOpcode {
 impl TargetOpcode {
     pub fn inst_def(&self) -> Option<&TargetInstDef> {
         match self {
+            Self::ADD => Some(&*inst::ADD),
+            Self::SUB => Some(&*inst::SUB),
+            Self::MUL => Some(&*inst::MUL),
+            Self::DIV => Some(&*inst::DIV),
+            Self::DIVU => Some(&*inst::DIVU),
+            Self::REM => Some(&*inst::REM),
+            Self::REMU => Some(&*inst::REMU),
+            Self::AND => Some(&*inst::AND),
+            Self::OR => Some(&*inst::OR),
+            Self::XOR => Some(&*inst::XOR),
+            Self::SLL => Some(&*inst::SLL),
+            Self::SRL => Some(&*inst::SRL),
+            Self::SRA => Some(&*inst::SRA),
+            Self::SLT => Some(&*inst::SLT),
+            Self::SLTU => Some(&*inst::SLTU),
             Self::ADDI => Some(&*inst::ADDI),
-            Self::ADDIW => Some(&*inst::ADDIW),
+            Self::ANDI => Some(&*inst::ANDI),
+            Self::ORI => Some(&*inst::ORI),
+            Self::XORI => Some(&*inst::XORI),
+            Self::SLTI => Some(&*inst::SLTI),
+            Self::SLTIU => Some(&*inst::SLTIU),
+            Self::SLLI => Some(&*inst::SLLI),
+            Self::SRLI => Some(&*inst::SRLI),
+            Self::SRAI => Some(&*inst::SRAI),
             Self::ADDW => Some(&*inst::ADDW),
-            Self::ADD => Some(&*inst::ADD),
+            Self::SUBW => Some(&*inst::SUBW),
             Self::MULW => Some(&*inst::MULW),
-            Self::MUL => Some(&*inst::MUL),
             Self::DIVW => Some(&*inst::DIVW),
+            Self::DIVUW => Some(&*inst::DIVUW),
             Self::REMW => Some(&*inst::REMW),
-            Self::SLLI => Some(&*inst::SLLI),
+            Self::REMUW => Some(&*inst::REMUW),
+            Self::SLLW => Some(&*inst::SLLW),
+            Self::SRLW => Some(&*inst::SRLW),
+            Self::SRAW => Some(&*inst::SRAW),
+            Self::ADDIW => Some(&*inst::ADDIW),
+            Self::SLLIW => Some(&*inst::SLLIW),
+            Self::SRLIW => Some(&*inst::SRLIW),
+            Self::SRAIW => Some(&*inst::SRAIW),
+            Self::SEQZ => Some(&*inst::SEQZ),
+            Self::SNEZ => Some(&*inst::SNEZ),
+            Self::NEG => Some(&*inst::NEG),
             Self::MV => Some(&*inst::MV),
-            Self::LA => Some(&*inst::LA),
             Self::LI => Some(&*inst::LI),
+            Self::LA => Some(&*inst::LA),
+            Self::ADDIm => Some(&*inst::ADDIm),
+            Self::LB => Some(&*inst::LB),
+            Self::LH => Some(&*inst::LH),
             Self::LW => Some(&*inst::LW),
             Self::LD => Some(&*inst::LD),
+            Self::FLW => Some(&*inst::FLW),
+            Self::FLD => Some(&*inst::FLD),
+            Self::SB => Some(&*inst::SB),
+            Self::SH => Some(&*inst::SH),
             Self::SW => Some(&*inst::SW),
             Self::SD => Some(&*inst::SD),
-            Self::SEXT_W => Some(&*inst::SEXT_W),
-            Self::CALL => Some(&*inst::CALL),
+            Self::FSW => Some(&*inst::FSW),
+            Self::FSD => Some(&*inst::FSD),
+            Self::FADD_D => Some(&*inst::FADD_D),
+            Self::FSUB_D => Some(&*inst::FSUB_D),
+            Self::FMUL_D => Some(&*inst::FMUL_D),
+            Self::FDIV_D => Some(&*inst::FDIV_D),
+            Self::FADD_S => Some(&*inst::FADD_S),
+            Self::FSUB_S => Some(&*inst::FSUB_S),
+            Self::FMUL_S => Some(&*inst::FMUL_S),
+            Self::FDIV_S => Some(&*inst::FDIV_S),
+            Self::FEQ_D => Some(&*inst::FEQ_D),
+            Self::FLT_D => Some(&*inst::FLT_D),
+            Self::FLE_D => Some(&*inst::FLE_D),
+            Self::FEQ_S => Some(&*inst::FEQ_S),
+            Self::FLT_S => Some(&*inst::FLT_S),
+            Self::FLE_S => Some(&*inst::FLE_S),
+            Self::FCVT_W_D => Some(&*inst::FCVT_W_D),
+            Self::FCVT_WU_D => Some(&*inst::FCVT_WU_D),
+            Self::FCVT_L_D => Some(&*inst::FCVT_L_D),
+            Self::FCVT_LU_D => Some(&*inst::FCVT_LU_D),
+            Self::FCVT_W_S => Some(&*inst::FCVT_W_S),
+            Self::FCVT_WU_S => Some(&*inst::FCVT_WU_S),
+            Self::FCVT_L_S => Some(&*inst::FCVT_L_S),
+            Self::FCVT_LU_S => Some(&*inst::FCVT_LU_S),
+            Self::FCVT_D_W => Some(&*inst::FCVT_D_W),
+            Self::FCVT_D_WU => Some(&*inst::FCVT_D_WU),
+            Self::FCVT_D_L => Some(&*inst::FCVT_D_L),
+            Self::FCVT_D_LU => Some(&*inst::FCVT_D_LU),
+            Self::FCVT_S_W => Some(&*inst::FCVT_S_W),
+            Self::FCVT_S_WU => Some(&*inst::FCVT_S_WU),
+            Self::FCVT_S_L => Some(&*inst::FCVT_S_L),
+            Self::FCVT_S_LU => Some(&*inst::FCVT_S_LU),
+            Self::FCVT_D_S => Some(&*inst::FCVT_D_S),
+            Self::FCVT_S_D => Some(&*inst::FCVT_S_D),
+            Self::FMV_D => Some(&*inst::FMV_D),
+            Self::FMV_S => Some(&*inst::FMV_S),
+            Self::FMV_D_X => Some(&*inst::FMV_D_X),
+            Self::FMV_X_D => Some(&*inst::FMV_X_D),
+            Self::FMV_W_X => Some(&*inst::FMV_W_X),
+            Self::FMV_X_W => Some(&*inst::FMV_X_W),
+            Self::FLI_D => Some(&*inst::FLI_D),
+            Self::FLI_S => Some(&*inst::FLI_S),
             Self::BEQ => Some(&*inst::BEQ),
             Self::BNE => Some(&*inst::BNE),
-            Self::BLE => Some(&*inst::BLE),
             Self::BLT => Some(&*inst::BLT),
             Self::BGE => Some(&*inst::BGE),
-            Self::BGT => Some(&*inst::BGT),
+            Self::BLTU => Some(&*inst::BLTU),
+            Self::BGEU => Some(&*inst::BGEU),
+            Self::BEQZ => Some(&*inst::BEQZ),
+            Self::BNEZ => Some(&*inst::BNEZ),
             Self::J => Some(&*inst::J),
-            Self::JR => Some(&*inst::JR),
+            Self::CALL => Some(&*inst::CALL),
+            Self::RET => Some(&*inst::RET),
             _ => None,
         }
     }
+
+    /// How the instruction is encoded. `None` for pseudo instructions.
+    pub fn encoding(&self) -> Option<Encoding> {
+        use Encoding::*;
+        Some(match self {
+            Self::ADD => R(0x00000033),
+            Self::SUB => R(0x40000033),
+            Self::MUL => R(0x02000033),
+            Self::DIV => R(0x02004033),
+            Self::DIVU => R(0x02005033),
+            Self::REM => R(0x02006033),
+            Self::REMU => R(0x02007033),
+            Self::AND => R(0x00007033),
+            Self::OR => R(0x00006033),
+            Self::XOR => R(0x00004033),
+            Self::SLL => R(0x00001033),
+            Self::SRL => R(0x00005033),
+            Self::SRA => R(0x40005033),
+            Self::SLT => R(0x00002033),
+            Self::SLTU => R(0x00003033),
+            Self::ADDI => I(0x00000013),
+            Self::ANDI => I(0x00007013),
+            Self::ORI => I(0x00006013),
+            Self::XORI => I(0x00004013),
+            Self::SLTI => I(0x00002013),
+            Self::SLTIU => I(0x00003013),
+            Self::SLLI => Shift(0x00001013),
+            Self::SRLI => Shift(0x00005013),
+            Self::SRAI => Shift(0x40005013),
+            Self::ADDW => R(0x0000003b),
+            Self::SUBW => R(0x4000003b),
+            Self::MULW => R(0x0200003b),
+            Self::DIVW => R(0x0200403b),
+            Self::DIVUW => R(0x0200503b),
+            Self::REMW => R(0x0200603b),
+            Self::REMUW => R(0x0200703b),
+            Self::SLLW => R(0x0000103b),
+            Self::SRLW => R(0x0000503b),
+            Self::SRAW => R(0x4000503b),
+            Self::ADDIW => I(0x0000001b),
+            Self::SLLIW => Shift(0x0000101b),
+            Self::SRLIW => Shift(0x0000501b),
+            Self::SRAIW => Shift(0x4000501b),
+            Self::SEQZ => Unary(0x00103013),
+            Self::SNEZ => UnaryRs2(0x00003033),
+            Self::NEG => UnaryRs2(0x40000033),
+            Self::MV => Unary(0x00000013),
+            Self::LI => Li,
+            Self::LA => La,
+            Self::ADDIm => AddiMem,
+            Self::LB => Load(0x00000003),
+            Self::LH => Load(0x00001003),
+            Self::LW => Load(0x00002003),
+            Self::LD => Load(0x00003003),
+            Self::FLW => Load(0x00002007),
+            Self::FLD => Load(0x00003007),
+            Self::SB => Store(0x00000023),
+            Self::SH => Store(0x00001023),
+            Self::SW => Store(0x00002023),
+            Self::SD => Store(0x00003023),
+            Self::FSW => Store(0x00002027),
+            Self::FSD => Store(0x00003027),
+            Self::FADD_D => R(0x02007053),
+            Self::FSUB_D => R(0x0a007053),
+            Self::FMUL_D => R(0x12007053),
+            Self::FDIV_D => R(0x1a007053),
+            Self::FADD_S => R(0x00007053),
+            Self::FSUB_S => R(0x08007053),
+            Self::FMUL_S => R(0x10007053),
+            Self::FDIV_S => R(0x18007053),
+            Self::FEQ_D => R(0xa2002053),
+            Self::FLT_D => R(0xa2001053),
+            Self::FLE_D => R(0xa2000053),
+            Self::FEQ_S => R(0xa0002053),
+            Self::FLT_S => R(0xa0001053),
+            Self::FLE_S => R(0xa0000053),
+            Self::FCVT_W_D => Unary(0xc2001053),
+            Self::FCVT_WU_D => Unary(0xc2101053),
+            Self::FCVT_L_D => Unary(0xc2201053),
+            Self::FCVT_LU_D => Unary(0xc2301053),
+            Self::FCVT_W_S => Unary(0xc0001053),
+            Self::FCVT_WU_S => Unary(0xc0101053),
+            Self::FCVT_L_S => Unary(0xc0201053),
+            Self::FCVT_LU_S => Unary(0xc0301053),
+            Self::FCVT_D_W => Unary(0xd2000053),
+            Self::FCVT_D_WU => Unary(0xd2100053),
+            Self::FCVT_D_L => Unary(0xd2207053),
+            Self::FCVT_D_LU => Unary(0xd2307053),
+            Self::FCVT_S_W => Unary(0xd0007053),
+            Self::FCVT_S_WU => Unary(0xd0107053),
+            Self::FCVT_S_L => Unary(0xd0207053),
+            Self::FCVT_S_LU => Unary(0xd0307053),
+            Self::FCVT_D_S => Unary(0x42000053),
+            Self::FCVT_S_D => Unary(0x40107053),
+            Self::FMV_D => FMv(0x22000053),
+            Self::FMV_S => FMv(0x20000053),
+            Self::FMV_D_X => Unary(0xf2000053),
+            Self::FMV_X_D => Unary(0xe2000053),
+            Self::FMV_W_X => Unary(0xf0000053),
+            Self::FMV_X_W => Unary(0xe0000053),
+            Self::FLI_D => FLi(0xf2000053),
+            Self::FLI_S => FLi(0xf0000053),
+            Self::BEQ => Branch(0x00000063),
+            Self::BNE => Branch(0x00001063),
+            Self::BLT => Branch(0x00004063),
+            Self::BGE => Branch(0x00005063),
+            Self::BLTU => Branch(0x00006063),
+            Self::BGEU => Branch(0x00007063),
+            Self::BEQZ => BranchZ(0x00000063),
+            Self::BNEZ => BranchZ(0x00001063),
+            Self::J => Jal,
+            Self::CALL => Call,
+            Self::RET => Raw(0x00008067),
+            _ => return None,
+        })
+    }
 }
@@ -1,6 +1,13 @@
-use super::super::{frame_object::*, machine::register::*};
+use super::super::{
+    dag::mc_convert::{mov_mx, mov_rx},
+    frame_object::*,
+    machine::{
+        abi::{ArgLocation, LP64D},
+        register::*,
+    },
+};
 use super::inst::*;
-use crate::codegen::common::machine::{builder::*, function::*, module::MachineModule};
+use crate::codegen::riscv64::common::machine::{builder::*, function::*, module::MachineModule};
 use crate::{ir::types::*, traits::pass::ModulePassTrait};
 
 pub struct PrologueEpilogueInserter {}
@@ -17,12 +24,6 @@ impl ModulePassTrait for PrologueEpilogueInserter {
     }
 }
 
-// struct CopyArgs<'a> {
-//     offset: i32,
-//     builder: &'a mut Builder<'a>,
-//     params_ty: &'a Vec<Type>,
-// }
-
 impl PrologueEpilogueInserter {
     pub fn new() -> Self {
         Self {}
@@ -30,7 +31,7 @@ impl PrologueEpilogueInserter {
 
     pub fn run_on_module(&mut self, module: &mut MachineModule) {
         for (_, func) in &mut module.functions {
-            if func.is_internal {
+            if func.is_internal || func.is_empty() {
                 continue;
             }
             self.run_on_function(&module.types, func);
@@ -39,19 +40,10 @@ impl PrologueEpilogueInserter {
 
     pub fn run_on_function(&mut self, tys: &Types, cur_func: &mut MachineFunction) {
         let frame_objects = FrameObjectsInfo::new(tys, cur_func);
-        let mut saved_regs = cur_func
-            .body
-            .appeared_phys_regs()
-            .containing_callee_saved_regs()
-            .to_phys_set()
-            .into_iter()
-            .collect::<Vec<_>>();
-        if cur_func.body.has_call() {
-            saved_regs.push(GPR::RA.as_phys_reg())
-        }
+        let saved_regs = FrameObjectsInfo::saved_regs(cur_func);
         Self::remove_adjust_stack_inst(cur_func);
-        self.insert_prologue(cur_func, &saved_regs, &frame_objects);
-        self.insert_epilogue(cur_func, &saved_regs, &frame_objects);
+        self.insert_prologue(tys, cur_func, &saved_regs, &frame_objects);
+        self.insert_epilogue(cur_func, &saved_regs);
         cur_func.frame_objects = Some(frame_objects);
     }
 
@@ -74,183 +66,177 @@ impl PrologueEpilogueInserter {
 
     fn insert_prologue(
         &mut self,
-        /* tys: &Types, */ cur_func: &mut MachineFunction,
+        tys: &Types,
+        cur_func: &mut MachineFunction,
         saved_regs: &[PhysReg],
         frame_objects: &FrameObjectsInfo,
     ) {
+        let (locs, _) = LP64D::new().arg_locations(tys, cur_func.ty);
+        let params_ty = tys
+            .base
+            .borrow()
+            .as_function_ty(cur_func.ty)
+            .unwrap()
+            .params_ty
+            .clone();
         let adjust = frame_objects.total_size();
-        let callee_saved_regs_adjust = frame_objects.aligned_callee_saved_regs_byte();
-        let big_stack_down = !bits_within(adjust, 12);
+
         let mut builder = Builder::new(cur_func);
         builder.set_insert_point_at_entry_block();
+        let bb = builder.get_cur_bb().unwrap();
+        let regs = &builder.function.regs_info;
+        let sp = RegisterOperand::new(regs.get_phys_reg(GR64::SP));
+        let s0 = RegisterOperand::new(regs.get_phys_reg(GR64::S0));
+        let ra = RegisterOperand::new(regs.get_phys_reg(GR64::RA));
+        let t0 = RegisterOperand::new(regs.get_phys_reg(GR64::T0));
+        let t1 = RegisterOperand::new(regs.get_phys_reg(GR64::T1));
+
+        let mut iseq = vec![];
 
-        //addi sp,sp,-X
-        let sp = builder.function.regs_info.get_phys_reg(GPR::SP);
-        let addi = MachineInst::new_simple(
-            MachineOpcode::ADDI,
-            vec![
-                MachineOperand::Register(sp),
-                MachineOperand::Constant(MachineConstant::Int32(if big_stack_down {
-                    -callee_saved_regs_adjust
-                } else {
-                    -adjust
-                })),
-            ],
-            builder.get_cur_bb().unwrap(),
-        )
-        .with_def(vec![sp]);
-        builder.insert(addi);
+        // addi sp, sp, -16
+        // sd ra, 8(sp)
+        // sd s0, 0(sp)
+        // addi s0, sp, 16
+        iseq.push(addi(sp, sp, -16, bb));
+        iseq.push(store(MachineOpcode::SD, ra, sp, 8, bb));
+        iseq.push(store(MachineOpcode::SD, s0, sp, 0, bb));
+        iseq.push(addi(s0, sp, 16, bb));
 
-        let mut s0_used = false;
         for (i, r) in saved_regs.iter().enumerate() {
-            s0_used |= r == &GPR::S0.as_phys_reg();
-            let r = builder.function.regs_info.get_phys_reg(*r);
-            let sd = MachineInst::new_simple(
-                MachineOpcode::SD,
+            let r = MachineOperand::phys_reg(regs, *r);
+            iseq.push(MachineInst::new_simple(
+                mov_mx(regs, &r).unwrap(),
                 vec![
-                    MachineOperand::Register(r),
-                    MachineOperand::Mem(MachineMemOperand::ImmReg(
-                        if big_stack_down {
-                            callee_saved_regs_adjust - 8 * (i as i32 + 1)
-                        } else {
-                            adjust - 8 * (i as i32 + 1)
-                        },
-                        sp,
-                    )),
+                    MachineOperand::Mem(MachineMemOperand::BaseOff(s0, -24 - 8 * i as i32)),
+                    r,
                 ],
-                builder.get_cur_bb().unwrap(),
-            );
-            builder.insert(sd);
+                bb,
+            ));
         }
 
-        if s0_used {
-            let addi = MachineInst::new_simple(
-                MachineOpcode::ADDI,
-                vec![
-                    MachineOperand::Register(sp),
-                    MachineOperand::Constant(MachineConstant::Int32(if big_stack_down {
-                        callee_saved_regs_adjust
-                    } else {
-                        adjust
-                    })),
-                ],
-                builder.get_cur_bb().unwrap(),
-            )
-            .with_def(vec![builder.function.regs_info.get_phys_reg(GPR::S0)]);
-            builder.insert(addi);
+        if -2048 <= -adjust {
+            // addi sp, s0, -adjust
+            iseq.push(addi(sp, s0, -adjust, bb));
+        } else {
+            // li t0, adjust
+            // sub sp, s0, t0
+            iseq.push(
+                MachineInst::new_simple(
+                    MachineOpcode::LI,
+                    vec![MachineOperand::imm_i32(adjust)],
+                    bb,
+                )
+                .with_def(vec![t0]),
+            );
+            iseq.push(
+                MachineInst::new_simple(
+                    MachineOpcode::SUB,
+                    vec![MachineOperand::Register(s0), MachineOperand::Register(t0)],
+                    bb,
+                )
+                .with_def(vec![sp]),
+            );
         }
 
-        if big_stack_down {
-            let s1 = builder.function.regs_info.get_phys_reg(GPR::S1);
-            let li = MachineInst::new_simple(
-                MachineOpcode::LI,
-                vec![MachineOperand::imm_i32(
-                    -(adjust - callee_saved_regs_adjust),
-                )],
-                builder.get_cur_bb().unwrap(),
-            )
-            .with_def(vec![s1]);
-            let add = MachineInst::new_simple(
-                MachineOpcode::ADD,
-                vec![MachineOperand::Register(sp), MachineOperand::Register(s1)],
-                builder.get_cur_bb().unwrap(),
-            )
-            .with_def(vec![builder.function.regs_info.get_phys_reg(GPR::SP)]);
-            builder.insert(li);
-            builder.insert(add);
+        // Give the arguments that can't stay in registers their stack slots.
+        for (i, (loc, ty)) in locs.iter().zip(params_ty).enumerate() {
+            let slot = FrameIndexInfo::new(ty, FrameIndexKind::Arg(i));
+            let slot_off =
+                |off| MachineOperand::Mem(MachineMemOperand::BaseFiOff(s0, slot.clone(), off));
+            match loc {
+                ArgLocation::FloatInGPR(r) => {
+                    let r = MachineOperand::phys_reg(regs, *r);
+                    iseq.push(MachineInst::new_simple(
+                        MachineOpcode::SD,
+                        vec![slot_off(0), r],
+                        bb,
+                    ));
+                }
+                ArgLocation::StructInRegs(parts) => {
+                    for &(r, off) in parts {
+                        let r = MachineOperand::phys_reg(regs, r);
+                        iseq.push(MachineInst::new_simple(
+                            mov_mx(regs, &r).unwrap(),
+                            vec![slot_off(off), r],
+                            bb,
+                        ));
+                    }
+                }
+                ArgLocation::StructRef(ptr) => {
+                    let ptr = match **ptr {
+                        ArgLocation::Reg(r) => RegisterOperand::new(regs.get_phys_reg(r)),
+                        ArgLocation::Stack(off) => {
+                            let mem = MachineOperand::Mem(MachineMemOperand::BaseOff(s0, off));
+                            iseq.push(
+                                MachineInst::new_simple(MachineOpcode::LD, vec![mem], bb)
+                                    .with_def(vec![t0]),
+                            );
+                            t0
+                        }
+                        _ => unreachable!(),
+                    };
+                    let struct_ty = tys.get_element_ty(ty, None).unwrap();
+                    let size = struct_ty.size_in_byte(tys) as i32;
+                    let mut off = 0;
+                    for &(n, ld, sd) in &[
+                        (8, MachineOpcode::LD, MachineOpcode::SD),
+                        (4, MachineOpcode::LW, MachineOpcode::SW),
+                        (2, MachineOpcode::LH, MachineOpcode::SH),
+                        (1, MachineOpcode::LB, MachineOpcode::SB),
+                    ] {
+                        while size - off >= n {
+                            iseq.push(load(ld, t1, ptr, off, bb));
+                            iseq.push(MachineInst::new_simple(
+                                sd,
+                                vec![slot_off(off), MachineOperand::Register(t1)],
+                                bb,
+                            ));
+                            off += n;
+                        }
+                    }
+                }
+                ArgLocation::Reg(_) | ArgLocation::Stack(_) | ArgLocation::StructOnStack(_) => {}
+            }
         }
 
-        // self.insert_arg_copy(&tys.base.borrow(), &mut builder);
+        for inst in iseq {
+            builder.insert(inst);
+        }
     }
-    //
-    // fn insert_arg_copy<'a>(&mut self, tys: &'a TypesBase, builder: &'a mut Builder<'a>) {
-    //     CopyArgs::new(
-    //         builder,
-    //         &tys.as_function_ty(builder.function.ty).unwrap().params_ty,
-    //     )
-    //     .copy();
-    // }
-    //
-    fn insert_epilogue(
-        &mut self,
-        cur_func: &mut MachineFunction,
-        saved_regs: &[PhysReg],
-        frame_objects: &FrameObjectsInfo,
-    ) {
-        let adjust = frame_objects.total_size();
-        let callee_saved_regs_adjust = frame_objects.aligned_callee_saved_regs_byte();
-        let big_stack_down = !bits_within(adjust, 12);
+
+    fn insert_epilogue(&mut self, cur_func: &mut MachineFunction, saved_regs: &[PhysReg]) {
         let mut bb_iseq = vec![];
-        // let has_call = cur_func.body.has_call();
+        let regs = &cur_func.regs_info;
+        let sp = RegisterOperand::new(regs.get_phys_reg(GR64::SP));
+        let s0 = RegisterOperand::new(regs.get_phys_reg(GR64::S0));
+        let ra = RegisterOperand::new(regs.get_phys_reg(GR64::RA));
 
         for (bb_id, bb) in cur_func.body.basic_blocks.id_and_block() {
             let last_inst_id = *bb.iseq_ref().last().unwrap();
             let last_inst = &cur_func.body.inst_arena[last_inst_id];
-            let is_return =
-                last_inst.opcode == MachineOpcode::JR && last_inst.operand[0].is_register() && {
-                    let r = last_inst.operand[0].as_register();
-                    r.is_phys_reg() && r.as_phys_reg() == GPR::RA.as_phys_reg()
-                };
 
-            if !is_return {
+            if last_inst.opcode != MachineOpcode::RET {
                 continue;
             }
 
             let mut iseq = vec![];
 
-            let sp = cur_func.regs_info.get_phys_reg(GPR::SP);
-
-            if big_stack_down {
-                let s1 = cur_func.regs_info.get_phys_reg(GPR::S1);
-                let li = MachineInst::new_simple(
-                    MachineOpcode::LI,
-                    vec![MachineOperand::imm_i32(adjust - callee_saved_regs_adjust)],
-                    bb_id,
-                )
-                .with_def(vec![s1]);
-                let add = MachineInst::new_simple(
-                    MachineOpcode::ADD,
-                    vec![MachineOperand::Register(sp), MachineOperand::Register(s1)],
-                    bb_id,
-                )
-                .with_def(vec![cur_func.regs_info.get_phys_reg(GPR::SP)]);
-                iseq.push(cur_func.body.inst_arena.alloc(&cur_func.regs_info, li));
-                iseq.push(cur_func.body.inst_arena.alloc(&cur_func.regs_info, add));
-            }
-
-            // let s0 = cur_func.regs_info.get_phys_reg(GPR::S0);
-            for (i, r) in saved_regs.iter().enumerate().rev() {
-                let r = cur_func.regs_info.get_phys_reg(*r);
-                let ld = MachineInst::new_simple(
-                    MachineOpcode::LD,
-                    vec![MachineOperand::Mem(MachineMemOperand::ImmReg(
-                        if big_stack_down {
-                            callee_saved_regs_adjust - 8 * (i as i32 + 1)
-                        } else {
-                            adjust - 8 * (i as i32 + 1)
-                        },
-                        sp,
-                    ))],
-                    bb_id,
-                )
-                .with_def(vec![r]);
-                iseq.push(cur_func.body.inst_arena.alloc(&cur_func.regs_info, ld));
+            for (i, r) in saved_regs.iter().enumerate() {
+                let mem = MachineOperand::Mem(MachineMemOperand::BaseOff(s0, -24 - 8 * i as i32));
+                iseq.push(
+                    MachineInst::new_simple(mov_rx(r.reg_class(), &mem).unwrap(), vec![mem], bb_id)
+                        .with_def(vec![RegisterOperand::new(regs.get_phys_reg(*r))]),
+                );
             }
 
-            let addi = MachineInst::new_simple(
-                MachineOpcode::ADDI,
-                vec![
-                    MachineOperand::Register(sp),
-                    MachineOperand::Constant(MachineConstant::Int32(if big_stack_down {
-                        callee_saved_regs_adjust
-                    } else {
-                        adjust
-                    })),
-                ],
-                bb_id,
-            )
-            .with_def(vec![sp]);
-            iseq.push(cur_func.body.inst_arena.alloc(&cur_func.regs_info, addi));
+            // addi sp, s0, -16
+            // ld ra, 8(sp)
+            // ld s0, 0(sp)
+            // addi sp, sp, 16
+            iseq.push(addi(sp, s0, -16, bb_id));
+            iseq.push(load(MachineOpcode::LD, ra, sp, 8, bb_id));
+            iseq.push(load(MachineOpcode::LD, s0, sp, 0, bb_id));
+            iseq.push(addi(sp, sp, 16, bb_id));
 
             bb_iseq.push((last_inst_id, iseq));
         }
@@ -265,109 +251,48 @@ impl PrologueEpilogueInserter {
     }
 }
 
-// impl<'a> CopyArgs<'a> {
-//     pub fn new(builder: &'a mut Builder<'a>, params_ty: &'a Vec<Type>) -> Self {
-//         Self {
-//             builder,
-//             params_ty,
-//             offset: 16, // call + push rbp. TODO: this may vary if there're more pushes
-//         }
-//     }
-//
-//     pub fn copy(mut self) {
-//         for (i, &ty) in self.params_ty.iter().enumerate() {
-//             match ty {
-//                 Type::Int32 => self.copy_int(ty, i, 32),
-//                 Type::Int64 | Type::Pointer(_) => self.copy_int(ty, i, 64),
-//                 Type::F64 => self.copy_f64(i),
-//                 _ => unimplemented!(),
-//             }
-//         }
-//     }
-//
-//     fn copy_f64(&mut self, i: usize) {
-//         let ret_reg = XMM::XMM0.as_phys_reg();
-//         let dst = FrameIndexInfo::new(Type::F64, FrameIndexKind::Arg(i));
-//         let src = match RegisterClassKind::XMM.get_nth_arg_reg(i) {
-//             Some(_arg_reg) => return, // MachineOperand::phys_reg(&self.builder.function.regs_info, arg_reg),
-//             None => {
-//                 let ax = self.builder.function.regs_info.get_phys_reg(ret_reg);
-//                 let inst = MachineInst::new_simple(
-//                     MachineOpcode::MOVSDrm,
-//                     vec![MachineOperand::Mem(MachineMemOperand::BaseOff(
-//                         self.builder.function.regs_info.get_phys_reg(GR64::RBP),
-//                         self.offset,
-//                     ))],
-//                     self.builder.get_cur_bb().unwrap(),
-//                 )
-//                 .with_def(vec![ax.clone()]);
-//                 self.builder.insert(inst);
-//                 self.offset += 8;
-//                 MachineOperand::Register(ax)
-//             }
-//         };
-//         let inst = MachineInst::new_simple(
-//             MachineOpcode::MOVSDmr,
-//             vec![
-//                 MachineOperand::Mem(MachineMemOperand::BaseFi(
-//                     self.builder.function.regs_info.get_phys_reg(GR64::RBP),
-//                     dst,
-//                 )),
-//                 src,
-//             ],
-//             self.builder.get_cur_bb().unwrap(),
-//         );
-//         self.builder.insert(inst)
-//     }
-//
-//     fn copy_int(&mut self, ty: Type, i: usize, bit: usize) {
-//         let (ax, rc, movrm) = match bit {
-//             32 => (
-//                 GR32::EAX.as_phys_reg(),
-//                 RegisterClassKind::GR32,
-//                 MachineOpcode::MOVrm32,
-//             ),
-//             64 => (
-//                 GR64::RAX.as_phys_reg(),
-//                 RegisterClassKind::GR64,
-//                 MachineOpcode::MOVrm64,
-//             ),
-//             _ => unimplemented!(),
-//         };
-//         let dst = FrameIndexInfo::new(ty, FrameIndexKind::Arg(i));
-//         let src = match rc.get_nth_arg_reg(i) {
-//             Some(_arg_reg) => return, // MachineOperand::phys_reg(&self.builder.function.regs_info, arg_reg),
-//             None => {
-//                 let ax = self.builder.function.regs_info.get_phys_reg(ax);
-//                 let inst = MachineInst::new_simple(
-//                     movrm,
-//                     vec![MachineOperand::Mem(MachineMemOperand::BaseOff(
-//                         self.builder.function.regs_info.get_phys_reg(GR64::RBP),
-//                         self.offset,
-//                     ))],
-//                     self.builder.get_cur_bb().unwrap(),
-//                 )
-//                 .with_def(vec![ax]);
-//                 self.builder.insert(inst);
-//                 self.offset += 8;
-//                 MachineOperand::Register(ax)
-//             }
-//         };
-//         let inst = MachineInst::new_simple(
-//             mov_mx(&self.builder.function.regs_info, &src).unwrap(),
-//             vec![
-//                 MachineOperand::Mem(MachineMemOperand::BaseFi(
-//                     self.builder.function.regs_info.get_phys_reg(GR64::RBP),
-//                     dst,
-//                 )),
-//                 src,
-//             ],
-//             self.builder.get_cur_bb().unwrap(),
-//         );
-//         self.builder.insert(inst)
-//     }
-// }
+fn addi(
+    dst: RegisterOperand,
+    src: RegisterOperand,
+    imm: i32,
+    bb: MachineBasicBlockId,
+) -> MachineInst {
+    MachineInst::new_simple(
+        MachineOpcode::ADDI,
+        vec![MachineOperand::Register(src), MachineOperand::imm_i32(imm)],
+        bb,
+    )
+    .with_def(vec![dst])
+}
+
+fn store(
+    opcode: MachineOpcode,
+    src: RegisterOperand,
+    base: RegisterOperand,
+    off: i32,
+    bb: MachineBasicBlockId,
+) -> MachineInst {
+    MachineInst::new_simple(
+        opcode,
+        vec![
+            MachineOperand::Mem(MachineMemOperand::BaseOff(base, off)),
+            MachineOperand::Register(src),
+        ],
+        bb,
+    )
+}
 
-fn bits_within(x: i32, y: u32) -> bool {
-    (x << (32 - y)) >> (32 - y) == x
+fn load(
+    opcode: MachineOpcode,
+    dst: RegisterOperand,
+    base: RegisterOperand,
+    off: i32,
+    bb: MachineBasicBlockId,
+) -> MachineInst {
+    MachineInst::new_simple(
+        opcode,
+        vec![MachineOperand::Mem(MachineMemOperand::BaseOff(base, off))],
+        bb,
+    )
+    .with_def(vec![dst])
 }
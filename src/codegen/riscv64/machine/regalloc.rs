@@ -1,13 +1,14 @@
-use crate::codegen::arch::{
-    frame_object::FrameIndexInfo,
-    machine::inst::{MachineMemOperand, MachineOpcode},
-    machine::register::*,
-};
-pub use crate::codegen::common::machine::regalloc::*;
-use crate::codegen::common::machine::{
+pub use crate::codegen::riscv64::common::machine::regalloc::*;
+use crate::codegen::riscv64::common::machine::{
     basic_block::MachineBasicBlockId,
     function::MachineFunction,
-    inst::{MachineInst, MachineInstId, MachineOperand},
+    inst::{MachineInst, MachineInstId, MachineOperand, RegisterOperand},
+};
+use crate::codegen::riscv64::{
+    dag::mc_convert::{mov_mx, mov_rx},
+    frame_object::FrameIndexInfo,
+    machine::inst::MachineMemOperand,
+    machine::register::*,
 };
 
 impl RegisterAllocator {
@@ -18,28 +19,42 @@ impl RegisterAllocator {
         frinfo: FrameIndexInfo,
         parent: MachineBasicBlockId,
     ) -> (MachineInstId, MachineInstId) {
-        let src = MachineOperand::Register(reg);
-        let s0 = f.regs_info.get_phys_reg(GPR::S0);
+        let dst = MachineOperand::FrameIndex(frinfo.clone());
+        let src = MachineOperand::Register(RegisterOperand::new(reg));
+        let s0 = RegisterOperand::new(f.regs_info.get_phys_reg(GR64::S0));
         let store_inst_id = f.alloc_inst(MachineInst::new(
             &f.regs_info,
-            MachineOpcode::SD,
+            mov_mx(&f.regs_info, &src).unwrap(),
             vec![
+                MachineOperand::Mem(MachineMemOperand::BaseFi(s0, *dst.as_frame_index())),
                 src,
-                MachineOperand::Mem(MachineMemOperand::FiReg(frinfo, s0)),
             ],
             None,
             parent,
         ));
 
+        let src = MachineOperand::Mem(MachineMemOperand::BaseFi(s0, frinfo));
+        let opcode = mov_rx(f.regs_info.arena_ref()[reg].reg_class, &src).unwrap();
         let load_inst_id = f.alloc_inst(
-            MachineInst::new_simple(
-                MachineOpcode::LD,
-                vec![MachineOperand::Mem(MachineMemOperand::FiReg(frinfo, s0))],
-                parent,
-            )
-            .with_def(vec![reg]),
+            MachineInst::new_simple(opcode, vec![src], parent)
+                .with_def(vec![RegisterOperand::new(reg)]),
         );
 
         (store_inst_id, load_inst_id)
     }
+
+    pub fn get_regs_used_to_preserve(
+        &mut self,
+        func: &mut MachineFunction,
+        store_id: MachineInstId,
+        load_id: MachineInstId,
+    ) -> Vec<(RegisterId, RegisterId)> {
+        let store = &func.body.inst_arena[store_id];
+        let r1 = store.operand[1].as_register().id;
+
+        let load = &func.body.inst_arena[load_id];
+        let r2 = load.def[0].id;
+
+        vec![(r1, r2)]
+    }
 }
@@ -1,4 +1,4 @@
-pub use crate::codegen::common::machine::register::*;
+pub use crate::codegen::riscv64::common::machine::register::*;
 use crate::ir::types::Type;
 use defs::registers;
 use id_arena::Arena;
@@ -6,42 +6,102 @@ use rustc_hash::FxHashMap;
 use std::cell::RefCell;
 
 registers! {
-    class GPR (64, i64, [i32, i64, Pointer!], [A0, A1]) {
-        ZERO, RA, SP, GP, TP, T0, T1, T2, S0, S1, A0,
-        A1, A2, A3, A4, A5, A6, A7, S2, S3, S4, S5,
-        S6, S7, S8, S9, S10, S11, T3, T4, T5, T6
+    // Every width of an integer register has the same name, and narrower values are kept
+    // sign-extended to 64 bits in it.
+    class GR64 (64, i64, [i64, Pointer!], [A0]) {
+        ZERO, RA, SP, GP, TP, T0, T1, T2,
+        S0, S1, A0, A1, A2, A3, A4, A5,
+        A6, A7, S2, S3, S4, S5, S6, S7,
+        S8, S9, S10, S11, T3, T4, T5, T6
     }
 
-    order arg GPR { A0, A1, A2, A3, A4, A5, A6, A7 }
+    class GR32 (32, i32, [i32], [A0]) < GR64 {
+        ZERO, RA, SP, GP, TP, T0, T1, T2,
+        S0, S1, A0, A1, A2, A3, A4, A5,
+        A6, A7, S2, S3, S4, S5, S6, S7,
+        S8, S9, S10, S11, T3, T4, T5, T6
+    }
 
-    order gp GPR { T0, T1, T2, A0, A1, A2, A3, A4,
-                    A5, A6, A7, T3, T4, T5, T6, S2,
-                    S3, S4, S5, S6, S7, S8, S9, S10, S11 } // S1 is reserved
-}
+    class GR16 (16, i16, [i16], [A0]) < GR32 {
+        ZERO, RA, SP, GP, TP, T0, T1, T2,
+        S0, S1, A0, A1, A2, A3, A4, A5,
+        A6, A7, S2, S3, S4, S5, S6, S7,
+        S8, S9, S10, S11, T3, T4, T5, T6
+    }
 
-macro_rules! to_phys {
-    ($($r:path),*) => {
-        vec![$(($r.as_phys_reg())),*]
-    };
+    class GR8 (8, i8, [i1, i8], [A0]) < GR16 {
+        ZERO, RA, SP, GP, TP, T0, T1, T2,
+        S0, S1, A0, A1, A2, A3, A4, A5,
+        A6, A7, S2, S3, S4, S5, S6, S7,
+        S8, S9, S10, S11, T3, T4, T5, T6
+    }
+
+    class F64 (64, f64, [f64], [FA0]) {
+        FT0, FT1, FT2, FT3, FT4, FT5, FT6, FT7,
+        FS0, FS1, FA0, FA1, FA2, FA3, FA4, FA5,
+        FA6, FA7, FS2, FS3, FS4, FS5, FS6, FS7,
+        FS8, FS9, FS10, FS11, FT8, FT9, FT10, FT11
+    }
+
+    class F32 (32, f32, [f32], [FA0]) < F64 {
+        FT0, FT1, FT2, FT3, FT4, FT5, FT6, FT7,
+        FS0, FS1, FA0, FA1, FA2, FA3, FA4, FA5,
+        FA6, FA7, FS2, FS3, FS4, FS5, FS6, FS7,
+        FS8, FS9, FS10, FS11, FT8, FT9, FT10, FT11
+    }
+
+    // Normal order of registers used to pass arguments (LP64D)
+    order arg GR8  { A0, A1, A2, A3, A4, A5, A6, A7 }
+    order arg GR16 { A0, A1, A2, A3, A4, A5, A6, A7 }
+    order arg GR32 { A0, A1, A2, A3, A4, A5, A6, A7 }
+    order arg GR64 { A0, A1, A2, A3, A4, A5, A6, A7 }
+    order arg F32  { FA0, FA1, FA2, FA3, FA4, FA5, FA6, FA7 }
+    order arg F64  { FA0, FA1, FA2, FA3, FA4, FA5, FA6, FA7 }
+
+    // Normal order of general-purpose registers. s0 is the frame pointer, and t0, t1, ft0 and
+    // ft1 are left as scratch registers for the prologue, the epilogue and immediates.
+    order gp GR8 {
+        A0, A1, A2, A3, A4, A5, A6, A7, T2, T3, T4, T5, T6,
+        S1, S2, S3, S4, S5, S6, S7, S8, S9, S10, S11
+    }
+    order gp GR16 {
+        A0, A1, A2, A3, A4, A5, A6, A7, T2, T3, T4, T5, T6,
+        S1, S2, S3, S4, S5, S6, S7, S8, S9, S10, S11
+    }
+    order gp GR32 {
+        A0, A1, A2, A3, A4, A5, A6, A7, T2, T3, T4, T5, T6,
+        S1, S2, S3, S4, S5, S6, S7, S8, S9, S10, S11
+    }
+    order gp GR64 {
+        A0, A1, A2, A3, A4, A5, A6, A7, T2, T3, T4, T5, T6,
+        S1, S2, S3, S4, S5, S6, S7, S8, S9, S10, S11
+    }
+    order gp F32 {
+        FA0, FA1, FA2, FA3, FA4, FA5, FA6, FA7, FT2, FT3, FT4, FT5, FT6, FT7,
+        FT8, FT9, FT10, FT11, FS0, FS1, FS2, FS3, FS4, FS5, FS6, FS7, FS8, FS9, FS10, FS11
+    }
+    order gp F64 {
+        FA0, FA1, FA2, FA3, FA4, FA5, FA6, FA7, FT2, FT3, FT4, FT5, FT6, FT7,
+        FT8, FT9, FT10, FT11, FS0, FS1, FS2, FS3, FS4, FS5, FS6, FS7, FS8, FS9, FS10, FS11
+    }
 }
 
 thread_local! {
     pub static CALLEE_SAVED_REGS: PhysRegSet = {
         let mut bits = PhysRegSet::new();
         let regs = to_phys![
-            GPR::SP,
-            GPR::S0,
-            GPR::S1,
-            GPR::S2,
-            GPR::S3,
-            GPR::S4,
-            GPR::S5,
-            GPR::S6,
-            GPR::S7,
-            GPR::S8,
-            GPR::S9,
-            GPR::S10,
-            GPR::S11
+            GR8::S0, GR8::S1, GR8::S2, GR8::S3, GR8::S4, GR8::S5,
+            GR8::S6, GR8::S7, GR8::S8, GR8::S9, GR8::S10, GR8::S11,
+            GR16::S0, GR16::S1, GR16::S2, GR16::S3, GR16::S4, GR16::S5,
+            GR16::S6, GR16::S7, GR16::S8, GR16::S9, GR16::S10, GR16::S11,
+            GR32::S0, GR32::S1, GR32::S2, GR32::S3, GR32::S4, GR32::S5,
+            GR32::S6, GR32::S7, GR32::S8, GR32::S9, GR32::S10, GR32::S11,
+            GR64::S0, GR64::S1, GR64::S2, GR64::S3, GR64::S4, GR64::S5,
+            GR64::S6, GR64::S7, GR64::S8, GR64::S9, GR64::S10, GR64::S11,
+            F32::FS0, F32::FS1, F32::FS2, F32::FS3, F32::FS4, F32::FS5,
+            F32::FS6, F32::FS7, F32::FS8, F32::FS9, F32::FS10, F32::FS11,
+            F64::FS0, F64::FS1, F64::FS2, F64::FS3, F64::FS4, F64::FS5,
+            F64::FS6, F64::FS7, F64::FS8, F64::FS9, F64::FS10, F64::FS11
         ];
         for reg in regs {
             bits.set(reg)
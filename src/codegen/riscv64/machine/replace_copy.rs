@@ -1,6 +1,6 @@
-use super::super::dag::mc_convert::opcode_copy2reg;
+use super::super::dag::mc_convert::mov_rx;
 use super::inst::MachineOpcode;
-use crate::codegen::common::machine::{function::MachineFunction, module::MachineModule};
+use crate::codegen::riscv64::common::machine::{function::MachineFunction, module::MachineModule};
 use crate::traits::pass::ModulePassTrait;
 
 pub struct ReplaceCopyWithProperMInst {}
@@ -24,14 +24,15 @@ impl ReplaceCopyWithProperMInst {
 
     pub fn run_on_module(&mut self, module: &mut MachineModule) {
         for (_, f) in &mut module.functions {
-            if f.is_internal {
-                continue;
-            }
-            self.run_on_function(/*&module.types,*/ f);
+            self.run_on_function(f);
         }
     }
 
-    pub fn run_on_function(&mut self, /*tys: &Types,*/ f: &mut MachineFunction) {
+    pub fn run_on_function(&mut self, f: &mut MachineFunction) {
+        if f.is_internal {
+            return;
+        }
+
         for (_, bb) in f.body.basic_blocks.id_and_block() {
             for inst_id in &*bb.iseq_ref() {
                 let inst = &mut f.body.inst_arena[*inst_id];
@@ -40,8 +41,9 @@ impl ReplaceCopyWithProperMInst {
                     continue;
                 }
 
-                let opcode = opcode_copy2reg(&inst.operand[0]);
-                inst.opcode = opcode;
+                let mov =
+                    mov_rx(inst.def[0].id.as_phys_reg().reg_class(), &inst.operand[0]).unwrap();
+                inst.opcode = mov;
             }
         }
     }
@@ -1,124 +1,77 @@
+#[path = "../common/mod.rs"]
+#[macro_use]
+pub mod common;
+
 pub mod asm;
 pub mod dag;
 pub mod exec;
 pub mod frame_object;
 pub mod machine;
 
+use crate::codegen::target::Target;
 use crate::{
-    codegen::common::{
-        dag::{combine, convert},
-        machine::{branch_folding, module::MachineModule, phi_elimination},
+    codegen::riscv64::common::machine::{
+        branch_folding, eliminate_fi, module::MachineModule, phi_elimination,
     },
     ir,
     ir::module::Module,
-    ir::types::*,
     traits::pass::ModulePassManager,
 };
+use target_lexicon::{Architecture, Triple};
+
+pub fn standard_conversion_into_machine_module(mut module: Module) -> MachineModule {
+    ir::dce::DeadCodeElimination::new().run_on_module(&mut module);
+    ir::merge_ret::MergeReturns::new().run_on_module(&mut module);
+    ir::const_folding::ConstantFolding::new().run_on_module(&mut module);
+    ir::inst_combine::InstructionCombine::new().run_on_module(&mut module);
+    // RV64IMD has no conditional move.
+    ir::expand_select::ExpandSelect::new().run_on_module(&mut module);
+    ir::lower_switch::LowerSwitch::new()
+        .without_jump_tables()
+        .run_on_module(&mut module);
+    ir::codegen_prepare::CodegenPrepare::new().run_on_module(&mut module);
+
+    let mut module = common::dag::convert::convert_module_to_dag_module(module);
+    common::dag::combine::run(&mut module);
+    // println!("Initial DAG:\n{:?}", module);
+    crate::codegen::riscv64::dag::legalize::run(&mut module);
+    crate::codegen::riscv64::dag::isel::run(&mut module);
+    // println!("Selected DAG:\n{:?}", module);
+
+    let mut module = common::dag::mc_convert::convert_module(module);
 
-impl TypeSize for Type {
-    fn size_in_byte(&self, tys: &Types) -> usize {
-        match self {
-            Type::i1 => 1,
-            Type::i8 => 1,
-            Type::i32 => 4,
-            Type::i64 => 8,
-            Type::f64 => 8,
-            Type::Array(id) => tys.base.borrow().non_primitive_types[*id]
-                .as_array()
-                .size_in_byte(tys),
-            Type::Struct(id) => tys.base.borrow().non_primitive_types[*id]
-                .as_struct()
-                .size_in_byte(tys),
-            Type::Pointer(_) => 8,
-            Type::Function(_) => unimplemented!(),
-            Type::Void => 0,
-        }
-    }
-
-    fn size_in_bits(&self, tys: &Types) -> usize {
-        self.size_in_byte(tys) * 8
-    }
+    let mut pass_mgr = ModulePassManager::new();
+    pass_mgr.add_pass(branch_folding::BranchFolding::new().only_removing_unreachable_block());
+    pass_mgr.add_pass(phi_elimination::PhiElimination::new());
+    pass_mgr.add_pass(machine::regalloc::RegisterAllocator::new());
+    pass_mgr.add_pass(branch_folding::BranchFolding::new()); // apply after regalloc
+    pass_mgr.add_pass(machine::pro_epi_inserter::PrologueEpilogueInserter::new());
+    pass_mgr.add_pass(machine::replace_copy::ReplaceCopyWithProperMInst::new());
+    pass_mgr.add_pass(eliminate_fi::EliminateFrameIndex::new());
+    pass_mgr.run_on_module(&mut module);
 
-    fn align_in_byte(&self, tys: &Types) -> usize {
-        match self {
-            Type::i1 => 1,
-            Type::i8 => 1,
-            Type::i32 => 4,
-            Type::i64 => 8,
-            Type::f64 => 8,
-            Type::Array(id) => tys.base.borrow().non_primitive_types[*id]
-                .as_array()
-                .align_in_byte(tys),
-            Type::Struct(id) => tys.base.borrow().non_primitive_types[*id]
-                .as_struct()
-                .align_in_byte(tys),
-            Type::Pointer(_) => 8,
-            Type::Function(_) => unimplemented!(),
-            Type::Void => 0,
-        }
-    }
+    module
 }
 
-const MAX_ALIGN: usize = 16;
-
-impl TypeSize for ArrayType {
-    fn size_in_byte(&self, tys: &Types) -> usize {
-        self.elem_ty.size_in_byte(tys) * self.len
-    }
-
-    fn size_in_bits(&self, tys: &Types) -> usize {
-        self.size_in_byte(tys) * 8
-    }
-
-    fn align_in_byte(&self, tys: &Types) -> usize {
-        let size = self.size_in_byte(tys);
-        let align = self.elem_ty.align_in_byte(tys);
-        if size > MAX_ALIGN {
-            MAX_ALIGN
-        } else {
-            align
-        }
-    }
-}
+/// The RV64GC (LP64D ABI) target.
+pub struct RiscV64;
 
-impl TypeSize for StructType {
-    fn size_in_byte(&self, _tys: &Types) -> usize {
-        self.size()
+impl Target for RiscV64 {
+    fn triple(&self) -> Triple {
+        "riscv64gc-unknown-linux-gnu".parse().unwrap()
     }
 
-    fn size_in_bits(&self, tys: &Types) -> usize {
-        self.size_in_byte(tys) * 8
+    fn supports(&self, triple: &Triple) -> bool {
+        matches!(
+            triple.architecture,
+            Architecture::Riscv64 | Architecture::Riscv64gc | Architecture::Riscv64imac
+        )
     }
 
-    fn align_in_byte(&self, _tys: &Types) -> usize {
-        self.align()
+    fn compile_to_asm(&self, module: Module) -> String {
+        let machine_module = standard_conversion_into_machine_module(module);
+        let mut printer = asm::print::MachineAsmPrinter::new();
+        printer.run_on_module(&machine_module);
+        printer.output
     }
 }
-
-pub fn standard_conversion_into_machine_module(module: &mut Module) -> MachineModule {
-    ir::merge_ret::MergeReturns::new().run_on_module(module);
-
-    let mut dag_module = convert::ConvertToDAGModule::new(module).run();
-
-    let mut pass_mgr = ModulePassManager::new();
-    pass_mgr.add_pass(combine::Combine::new());
-    pass_mgr.add_pass(dag::legalize::Legalize::new());
-    pass_mgr.add_pass(dag::isel::MISelector::new());
-    pass_mgr.run_on_module(&mut dag_module);
-    // println!("{:?}", dag_module);
-
-    let mut machine_module = dag::mc_convert::convert_module(dag_module);
-
-    let mut pass_mgr = ModulePassManager::new();
-    pass_mgr.add_pass(phi_elimination::PhiElimination::new());
-    // pass_mgr.add_pass(machine::two_addr::TwoAddressConverter::new());
-    pass_mgr.add_pass(machine::regalloc::RegisterAllocator::new());
-    pass_mgr.add_pass(branch_folding::BranchFolding::new());
-    pass_mgr.add_pass(machine::validate_frame_index::ValidateFrameIndex::new());
-    pass_mgr.add_pass(machine::pro_epi_inserter::PrologueEpilogueInserter::new());
-    pass_mgr.add_pass(machine::replace_copy::ReplaceCopyWithProperMInst::new());
-    // pass_mgr.add_pass(machine::replace_data::ReplaceConstFPWithMemoryRef::new());
-    pass_mgr.run_on_module(&mut machine_module);
-
-    machine_module
-}
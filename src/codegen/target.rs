@@ -0,0 +1,67 @@
+use crate::ir::module::Module;
+use target_lexicon::Triple;
+
+/// A backend that can lower an `ir::Module` all the way down to assembly.
+/// Every architecture module compiled into the crate provides one, so the
+/// target can be picked at runtime instead of at build time.
+pub trait Target {
+    /// The triple this target emits code for.
+    fn triple(&self) -> Triple;
+
+    /// Whether this target can generate code for `triple`.
+    fn supports(&self, triple: &Triple) -> bool {
+        self.triple().architecture == triple.architecture
+    }
+
+    /// Runs the target's whole pipeline (IR passes, instruction selection,
+    /// register allocation, ...) and returns the textual assembly.
+    fn compile_to_asm(&self, module: Module) -> String;
+}
+
+/// Targets available at runtime, looked up by triple.
+pub struct TargetRegistry {
+    targets: Vec<Box<dyn Target>>,
+}
+
+impl TargetRegistry {
+    /// Creates a registry holding every target enabled by cargo features.
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        #[cfg(feature = "x86_64")]
+        registry.register(Box::new(super::x64::X86_64));
+        #[cfg(feature = "aarch64")]
+        registry.register(Box::new(super::aarch64::AArch64));
+        #[cfg(feature = "riscv64")]
+        registry.register(Box::new(super::riscv64::RiscV64));
+        registry
+    }
+
+    pub fn empty() -> Self {
+        Self { targets: vec![] }
+    }
+
+    pub fn register(&mut self, target: Box<dyn Target>) {
+        self.targets.push(target)
+    }
+
+    pub fn lookup(&self, triple: &Triple) -> Option<&dyn Target> {
+        self.targets
+            .iter()
+            .find(|t| t.supports(triple))
+            .map(|t| t.as_ref())
+    }
+
+    pub fn lookup_str(&self, triple: &str) -> Option<&dyn Target> {
+        self.lookup(&triple.parse().ok()?)
+    }
+
+    pub fn triples(&self) -> Vec<Triple> {
+        self.targets.iter().map(|t| t.triple()).collect()
+    }
+}
+
+impl Default for TargetRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
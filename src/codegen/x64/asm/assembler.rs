@@ -1,183 +1,308 @@
-pub use crate::codegen::common::asm::assembler::{Assembler, InstAssembler, Offset};
+use crate::codegen::x64::common::asm::assembler::{
+    const_data_symbol, constant_symbol, function_symbol, global_var_symbol, InstructionStream,
+};
+pub use crate::codegen::x64::common::asm::assembler::{Assembler, InstAssembler, Offset};
+use crate::codegen::x64::common::machine::frame_object::FrameIndexInfo;
 use crate::codegen::x64::machine::inst::*;
-use crate::codegen::x64::machine::register::RegisterId;
+use crate::codegen::x64::machine::inst_def::Encoding;
+use crate::codegen::x64::machine::register::{PhysReg, RegisterClassKind};
+
+/// The target of the object files `Assembler` writes.
+pub const ELF_TRIPLE: &str = "x86_64-unknown-unknown-unknown-elf";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RelocKind {
+    /// 32-bit displacement of a `call`, going through the PLT for external functions.
+    Call,
+    /// 32-bit PC-relative address.
+    PcRel32,
+    /// 32-bit sign-extended absolute address.
+    Abs32S,
+    /// 64-bit absolute address.
+    Abs64,
+}
 
-impl<'a> InstAssembler<'a> {
-    pub fn assemble(&mut self) {
-        match self.inst.opcode {
-            MachineOpcode::PUSH64 => self.gen_push64(),
-            MachineOpcode::POP64 => self.gen_pop64(),
+impl RelocKind {
+    pub fn to_faerie(self, addend: i32) -> faerie::Reloc {
+        // Relocation types of x86_64 ELF
+        let reloc = match self {
+            Self::Call => 4,    // R_X86_64_PLT32
+            Self::PcRel32 => 2, // R_X86_64_PC32
+            Self::Abs32S => 11, // R_X86_64_32S
+            Self::Abs64 => 1,   // R_X86_64_64
+        };
+        faerie::Reloc::Raw { reloc, addend }
+    }
+}
 
-            MachineOpcode::MOVri32 => self.gen_movri32(),
-            MachineOpcode::MOVrm32 => self.gen_movrm32(),
-            MachineOpcode::MOVrr32 => self.gen_mov_rr32(),
-            MachineOpcode::MOVrr64 => self.gen_movrr64(),
-            MachineOpcode::MOVmi32 => self.gen_movmi32(),
+/// Makes the rel32 at `at` in `stream` point to `dst`.
+pub fn patch_branch(stream: &mut InstructionStream, at: usize, dst: usize) {
+    stream.insert_u32_le(at, (dst as i32 - (at as i32 + 4)) as u32)
+}
 
-            MachineOpcode::ADDri32 => self.gen_add_ri32(),
+/// The `reg` field of ModR/M: either a register or an opcode extension.
+#[derive(Clone, Copy)]
+enum Reg {
+    R(PhysReg),
+    Ext(u8),
+}
 
-            MachineOpcode::SUBri32 => self.gen_sub_ri32(),
+/// The `r/m` field of ModR/M.
+#[derive(Clone, Copy)]
+enum RM<'a> {
+    R(PhysReg),
+    M(&'a MachineMemOperand),
+}
 
-            MachineOpcode::IMULrri32 => self.gen_imul_rri32(),
+/// Memory operand resolved into its addressing form.
+enum Addr {
+    Base {
+        base: PhysReg,
+        index: Option<(PhysReg, i32)>,
+        disp: i32,
+    },
+    /// `[rip + symbol + disp]`
+    RipRel(String, i32),
+    /// `[symbol + disp + index * scale]`
+    Abs(String, i32, Option<(PhysReg, i32)>),
+}
 
-            MachineOpcode::IDIV => self.gen_idiv(),
+impl<'a> InstAssembler<'a> {
+    pub fn assemble(&mut self) {
+        if let Some(encoding) = self.inst.opcode.encoding() {
+            return self.gen(encoding);
+        }
+        match self.inst.opcode {
+            MachineOpcode::MOVri64 => self.gen_mov_ri64(),
+            MachineOpcode::MOVrm64 => self.gen_mov_rm64(),
+            MachineOpcode::CMPrr => self.gen_cmp_rr(),
+            MachineOpcode::CMPri => self.gen_cmp_ri(),
+            MachineOpcode::CALL => self.gen_call(),
+            op => unimplemented!("{:?}", op),
+        }
+    }
 
-            MachineOpcode::CDQ => self.gen_cdq(),
+    fn gen(&mut self, encoding: Encoding) {
+        match encoding {
+            Encoding::Raw(bytes) => {
+                for &byte in bytes {
+                    self.stream.push_u8(byte)
+                }
+            }
+            Encoding::OpReg(opcode) => self.gen_push_pop(opcode),
+            Encoding::Rr(prefix, opcode) => self.gen_rr(prefix, opcode),
+            Encoding::RrTied(prefix, opcode) => self.gen_rr_tied(prefix, opcode),
+            Encoding::RrDef(prefix, opcode, w) => self.gen_rr_def(prefix, opcode, w),
+            Encoding::Rm(prefix, opcode) => self.gen_rm(prefix, opcode),
+            Encoding::RmTied(prefix, opcode) => self.gen_rm_tied(prefix, opcode),
+            Encoding::Mr(prefix, opcode, w) => self.gen_mr(prefix, opcode, w),
+            Encoding::Mi(prefix, opcode, ext, imm_size, w) => {
+                self.gen_mi(prefix, opcode, ext, imm_size, w)
+            }
+            Encoding::Ri(opcode, ext, imm_size) => self.gen_ri(opcode, ext, imm_size),
+            Encoding::MovRi(prefix, opcode, imm_size) => self.gen_mov_ri(prefix, opcode, imm_size),
+            Encoding::ImulRri(opcode, imm_size) => self.gen_imul_rri(opcode, imm_size),
+            Encoding::Unary(opcode, ext) => self.gen_unary(opcode, ext),
+            Encoding::ShiftCl(ext) => self.gen_shift_rcl(ext),
+            Encoding::Ucomis(prefix) => self.gen_ucomis(prefix),
+            Encoding::Setcc => self.gen_setcc(),
+            Encoding::Cmov => self.gen_cmov(),
+            Encoding::Jcc => self.gen_jmp(&[0x0f, 0x80 + cond_code(self.inst.opcode)]),
+            Encoding::Jmp(opcode) => self.gen_jmp(opcode),
+        }
+    }
 
-            MachineOpcode::CALL => self.gen_call(),
+    fn gen_push_pop(&mut self, opcode: u8) {
+        let r = self.inst.operand[0].as_register().id.as_phys_reg();
+        if reg_code(&r) >= 8 {
+            self.stream.push_u8(0x41); // REX.B
+        }
+        self.stream.push_u8(opcode + (reg_code(&r) & 7));
+    }
 
-            MachineOpcode::CMPri => self.gen_cmp_ri(),
+    /// `op r/m, r` where r/m is the destination tied to the first operand.
+    fn gen_rr(&mut self, prefix: &[u8], opcode: &[u8]) {
+        let dst = self.def(0);
+        let src = self.reg(self.inst.operand.len() - 1);
+        let w = is_64(&dst);
+        self.encode(prefix, w, opcode, Reg::R(src), RM::R(dst), 0);
+    }
 
-            MachineOpcode::JMP => self.gen_jmp(),
-            MachineOpcode::JNE => self.gen_jne(),
+    /// `op r, r/m` where r is the destination tied to the first operand.
+    fn gen_rr_tied(&mut self, prefix: &[u8], opcode: &[u8]) {
+        let dst = self.def(0);
+        let src = self.reg(1);
+        let w = is_64(&dst);
+        self.encode(prefix, w, opcode, Reg::R(dst), RM::R(src), 0);
+    }
 
-            MachineOpcode::RET => self.gen_ret(),
-            op => unimplemented!("{:?}", op),
-        };
+    /// `op r, r/m` where r is the destination and r/m the only operand.
+    fn gen_rr_def(&mut self, prefix: &[u8], opcode: &[u8], w: bool) {
+        let dst = self.def(0);
+        let src = self.reg(0);
+        self.encode(prefix, w, opcode, Reg::R(dst), RM::R(src), 0);
     }
 
-    fn gen_push64(&mut self) {
-        let rd = reg_code(&self.inst.operand[0].as_register().id);
-        self.stream.push_u8(0x50 + rd);
+    /// `op r, m` where r is the destination.
+    fn gen_rm(&mut self, prefix: &[u8], opcode: &[u8]) {
+        let dst = self.def(0);
+        let w = is_64(&dst);
+        let mem = self.inst.operand[0].as_mem();
+        self.encode(prefix, w, opcode, Reg::R(dst), RM::M(mem), 0);
     }
 
-    fn gen_pop64(&mut self) {
-        let rd = reg_code(&self.inst.operand[0].as_register().id);
-        self.stream.push_u8(0x58 + rd);
+    /// `op r, m` where r is the destination tied to the first operand.
+    fn gen_rm_tied(&mut self, prefix: &[u8], opcode: &[u8]) {
+        let dst = self.def(0);
+        let mem = self.inst.operand[1].as_mem();
+        self.encode(prefix, false, opcode, Reg::R(dst), RM::M(mem), 0);
     }
 
-    fn gen_movri32(&mut self) {
-        self.stream.push_u8(0xc7);
-        self.stream
-            .push_u8(mod_rm(Mod::Reg, 0, reg_code(&self.inst.def[0].id)));
-        self.stream
-            .push_u32_le(self.inst.operand[0].as_constant().as_i32() as u32);
+    /// `op m, r`
+    fn gen_mr(&mut self, prefix: &[u8], opcode: &[u8], w: bool) {
+        let mem = self.inst.operand[0].as_mem();
+        let src = self.reg(1);
+        self.encode(prefix, w, opcode, Reg::R(src), RM::M(mem), 0);
     }
 
-    fn gen_movrm32(&mut self) {
-        self.stream.push_u8(0x8b); // TODO
-        let reg = reg_code(&self.inst.def[0].id);
-        let reg = reg; // eax
-        match self.inst.operand[0].as_mem() {
-            MachineMemOperand::BaseOff(base, off) => {
-                self.stream
-                    .push_u8(mod_rm(Mod::BaseDisp8, reg, reg_code(&base.id)));
-                self.stream.push_u8(*off as u32 as u8);
-            }
-            _ => unimplemented!(),
-        }
+    /// `op m, imm`
+    fn gen_mi(&mut self, prefix: &[u8], opcode: u8, ext: u8, imm_size: usize, w: bool) {
+        let mem = self.inst.operand[0].as_mem();
+        let imm = const_to_i64(self.inst.operand[1].as_constant());
+        self.encode(prefix, w, &[opcode], Reg::Ext(ext), RM::M(mem), imm_size);
+        self.push_imm(imm, imm_size);
     }
 
-    fn gen_mov_rr32(&mut self) {
-        self.stream.push_u8(0x89);
-        self.stream.push_u8(mod_rm(
-            Mod::Reg,
-            reg_code(&self.inst.operand[0].as_register().id),
-            reg_code(&self.inst.def[0].id),
-        ));
+    /// `op r/m, imm` where r/m is the destination tied to the first operand.
+    fn gen_ri(&mut self, opcode: u8, ext: u8, imm_size: usize) {
+        let dst = self.def(0);
+        let imm = const_to_i64(self.inst.operand[1].as_constant());
+        self.encode(&[], is_64(&dst), &[opcode], Reg::Ext(ext), RM::R(dst), 0);
+        self.push_imm(imm, imm_size);
     }
 
-    fn gen_movrr64(&mut self) {
-        self.stream.push_u8(0b01001000); // REX.W
-        self.stream.push_u8(0x89);
-        self.stream.push_u8(mod_rm(
-            Mod::Reg,
-            reg_code(&self.inst.operand[0].as_register().id),
-            reg_code(&self.inst.def[0].id),
-        ));
+    fn gen_mov_ri(&mut self, prefix: &[u8], opcode: u8, imm_size: usize) {
+        let dst = self.def(0);
+        let imm = const_to_i64(self.inst.operand[0].as_constant());
+        self.encode(prefix, false, &[opcode], Reg::Ext(0), RM::R(dst), 0);
+        self.push_imm(imm, imm_size);
     }
 
-    fn gen_movmi32(&mut self) {
-        self.stream.push_u8(0xc7);
+    fn gen_mov_ri64(&mut self) {
+        let dst = self.def(0);
+        let imm = const_to_i64(self.inst.operand[0].as_constant());
+        if imm as i32 as i64 == imm {
+            self.encode(&[], true, &[0xc7], Reg::Ext(0), RM::R(dst), 0);
+            self.push_imm(imm, 4);
+            return;
+        }
+        // movabs
+        self.stream.push_u8(0x48 | (reg_code(&dst) >> 3)); // REX.W(B)
+        self.stream.push_u8(0xb8 + (reg_code(&dst) & 7));
+        self.push_imm(imm, 8);
+    }
 
+    fn gen_mov_rm64(&mut self) {
         match self.inst.operand[0].as_mem() {
-            MachineMemOperand::BaseOff(base, off) => {
-                self.stream
-                    .push_u8(mod_rm(Mod::BaseDisp8, 0, reg_code(&base.id)));
-                self.stream.push_u8(*off as u32 as u8);
-            }
-            _ => unimplemented!(),
+            // Loads the address of the constant, as `mov r, offset .L_const` does.
+            MachineMemOperand::Address(AddressKind::Constant(_)) => self.gen_rm(&[], &[0x8d]),
+            _ => self.gen_rm(&[], &[0x8b]),
         }
+    }
 
-        self.stream
-            .push_u32_le(self.inst.operand[1].as_constant().as_i32() as u32);
+    fn gen_imul_rri(&mut self, opcode: u8, imm_size: usize) {
+        let dst = self.def(0);
+        let src = self.reg(0);
+        let imm = const_to_i64(self.inst.operand[1].as_constant());
+        self.encode(&[], is_64(&dst), &[opcode], Reg::R(dst), RM::R(src), 0);
+        self.push_imm(imm, imm_size);
     }
 
-    fn gen_add_ri32(&mut self) {
-        self.stream.push_u8(0x81);
-        self.stream
-            .push_u8(mod_rm(Mod::Reg, 0, reg_code(&self.inst.def[0].id)));
-        self.stream
-            .push_u32_le(self.inst.operand[1].as_constant().as_i32() as u32)
+    /// `op r/m` taking the first operand.
+    fn gen_unary(&mut self, opcode: u8, ext: u8) {
+        let r = self.reg(0);
+        let w = is_64(&r) && opcode != 0xff;
+        self.encode(&[], w, &[opcode], Reg::Ext(ext), RM::R(r), 0);
     }
 
-    fn gen_sub_ri32(&mut self) {
-        self.stream.push_u8(0x81);
-        self.stream
-            .push_u8(mod_rm(Mod::Reg, 5, reg_code(&self.inst.def[0].id)));
-        self.stream
-            .push_u32_le(self.inst.operand[1].as_constant().as_i32() as u32)
+    fn gen_shift_rcl(&mut self, ext: u8) {
+        let dst = self.def(0);
+        self.encode(&[], is_64(&dst), &[0xd3], Reg::Ext(ext), RM::R(dst), 0);
     }
 
-    fn gen_imul_rri32(&mut self) {
-        self.stream.push_u8(0x69);
-        self.stream.push_u8(mod_rm(
-            Mod::Reg,
-            reg_code(&self.inst.def[0].id),
-            reg_code(&self.inst.operand[0].as_register().id),
-        ));
-        self.stream
-            .push_u32_le(self.inst.operand[1].as_constant().as_i32() as u32);
+    fn gen_cmp_rr(&mut self) {
+        let lhs = self.reg(0);
+        let rhs = self.reg(1);
+        let (prefix, opcode): (&[u8], u8) = match lhs.reg_class() {
+            RegisterClassKind::GR8 => (&[], 0x38),
+            RegisterClassKind::GR16 => (&[0x66], 0x39),
+            _ => (&[], 0x39),
+        };
+        self.encode(prefix, is_64(&lhs), &[opcode], Reg::R(rhs), RM::R(lhs), 0);
     }
 
-    fn gen_idiv(&mut self) {
-        self.stream.push_u8(0xf7);
-        self.stream.push_u8(mod_rm(
-            Mod::Reg,
-            7,
-            reg_code(&self.inst.operand[0].as_register().id),
-        ));
+    fn gen_cmp_ri(&mut self) {
+        let lhs = self.reg(0);
+        let imm = const_to_i64(self.inst.operand[1].as_constant());
+        let (prefix, opcode, imm_size): (&[u8], u8, usize) = match lhs.reg_class() {
+            RegisterClassKind::GR8 => (&[], 0x80, 1),
+            RegisterClassKind::GR16 => (&[0x66], 0x81, 2),
+            _ => (&[], 0x81, 4),
+        };
+        self.encode(prefix, is_64(&lhs), &[opcode], Reg::Ext(7), RM::R(lhs), 0);
+        self.push_imm(imm, imm_size);
+    }
+
+    fn gen_ucomis(&mut self, prefix: &[u8]) {
+        let lhs = self.reg(0);
+        let rhs = self.reg(1);
+        self.encode(prefix, false, &[0x0f, 0x2e], Reg::R(lhs), RM::R(rhs), 0);
     }
 
-    fn gen_cdq(&mut self) {
-        self.stream.push_u8(0x99);
+    fn gen_setcc(&mut self) {
+        let dst = self.def(0);
+        let opcode = [0x0f, 0x90 + cond_code(self.inst.opcode)];
+        self.encode(&[], false, &opcode, Reg::Ext(0), RM::R(dst), 0);
+    }
+
+    fn gen_cmov(&mut self) {
+        let dst = self.def(0);
+        let src = self.reg(1);
+        let opcode = [0x0f, 0x40 + cond_code(self.inst.opcode)];
+        self.encode(&[], is_64(&dst), &opcode, Reg::R(dst), RM::R(src), 0);
     }
 
     fn gen_call(&mut self) {
-        self.stream.push_u8(0xe8);
+        let name = match &self.inst.operand[0] {
+            MachineOperand::Mem(MachineMemOperand::Address(AddressKind::FunctionName(n))) => n,
+            MachineOperand::Register(r) => {
+                let r = r.id.as_phys_reg();
+                self.encode(&[], false, &[0xff], Reg::Ext(2), RM::R(r), 0);
+                return;
+            }
+            e => unimplemented!("{:?}", e),
+        };
 
-        let callee_id = self
-            .module
-            .find_function_by_name(match &self.inst.operand[0] {
-                MachineOperand::Mem(MachineMemOperand::Address(AddressKind::FunctionName(n))) => {
-                    n.as_str()
-                }
-                _ => unimplemented!(),
-            })
-            .unwrap();
+        self.stream.push_u8(0xe8);
 
-        let label = self.labels.get_func_label(callee_id);
-        self.labels.add_disp32_to_replace(
-            Offset(self.function.id.unwrap(), self.stream.data().len()),
-            label,
-        );
+        let callee_id = self.module.find_function_by_name(name).unwrap();
+        let callee = self.module.function_ref(callee_id);
+        let at = Offset(self.function.id.unwrap(), self.stream.data().len());
+        if callee.is_internal || callee.is_empty() {
+            self.labels
+                .add_reloc(at, function_symbol(name), RelocKind::Call, -4);
+        } else {
+            let label = self.labels.get_func_label(callee_id);
+            self.labels.add_disp32_to_replace(at, label);
+        }
 
         self.stream.push_u32_le(0);
     }
 
-    fn gen_cmp_ri(&mut self) {
-        self.stream.push_u8(0x81);
-        self.stream.push_u8(mod_rm(
-            Mod::Reg,
-            7,
-            reg_code(&self.inst.operand[0].as_register().id),
-        ));
-        self.stream
-            .push_u32_le(self.inst.operand[1].as_constant().as_i32() as u32);
-    }
-
-    fn gen_jmp(&mut self) {
-        self.stream.push_u8(0xe9);
+    fn gen_jmp(&mut self, opcode: &[u8]) {
+        for &byte in opcode {
+            self.stream.push_u8(byte);
+        }
 
         match &self.inst.operand[0] {
             MachineOperand::Branch(b) => {
@@ -193,32 +318,220 @@ impl<'a> InstAssembler<'a> {
         self.stream.push_u32_le(0);
     }
 
-    fn gen_jne(&mut self) {
-        self.stream.push_u8(0x0f);
-        self.stream.push_u8(0x85);
+    /// Emits the prefixes, REX, opcode, ModR/M, SIB and displacement of an instruction.
+    /// `imm_size` is the size of the immediate following the displacement, which a PC-relative
+    /// displacement needs to take into account.
+    fn encode(&mut self, prefix: &[u8], w: bool, opcode: &[u8], reg: Reg, rm: RM, imm_size: usize) {
+        let (reg_code_, reg_byte) = match reg {
+            Reg::R(r) => (reg_code(&r), needs_rex_for_byte(&r)),
+            Reg::Ext(ext) => (ext, false),
+        };
+        let addr = match rm {
+            RM::R(_) => None,
+            RM::M(mem) => Some(self.resolve_mem(mem)),
+        };
+        let (x, b, rm_byte) = match (&rm, &addr) {
+            (RM::R(r), _) => (0, reg_code(r) >> 3, needs_rex_for_byte(r)),
+            (_, Some(Addr::Base { base, index, .. })) => (
+                index.as_ref().map_or(0, |(i, _)| reg_code(i) >> 3),
+                reg_code(base) >> 3,
+                false,
+            ),
+            (_, Some(Addr::Abs(_, _, index))) => (
+                index.as_ref().map_or(0, |(i, _)| reg_code(i) >> 3),
+                0,
+                false,
+            ),
+            _ => (0, 0, false),
+        };
+        let rex = (w as u8) << 3 | (reg_code_ >> 3) << 2 | x << 1 | b;
 
-        match &self.inst.operand[0] {
-            MachineOperand::Branch(b) => {
-                let label = self.labels.get_label_for(self.function.id.unwrap(), *b);
-                self.labels.add_disp32_to_replace(
-                    Offset(self.function.id.unwrap(), self.stream.data().len()),
-                    label,
-                );
+        for &byte in prefix {
+            self.stream.push_u8(byte);
+        }
+        if rex != 0 || reg_byte || rm_byte {
+            self.stream.push_u8(0x40 | rex);
+        }
+        for &byte in opcode {
+            self.stream.push_u8(byte);
+        }
+
+        let reg_code_ = reg_code_ & 7;
+        let addr = match (rm, addr) {
+            (RM::R(r), _) => {
+                self.stream
+                    .push_u8(mod_rm(Mod::Reg, reg_code_, reg_code(&r) & 7));
+                return;
+            }
+            (_, addr) => addr.unwrap(),
+        };
+
+        match addr {
+            Addr::Base { base, index, disp } => {
+                let base = reg_code(&base) & 7;
+                // [rbp] and [r13] can't be encoded without displacement.
+                let mod_ = if disp == 0 && base != 5 {
+                    Mod::Base
+                } else if disp as i8 as i32 == disp {
+                    Mod::BaseDisp8
+                } else {
+                    Mod::BaseDisp32
+                };
+                let is_disp8 = matches!(mod_, Mod::BaseDisp8);
+                let is_disp32 = matches!(mod_, Mod::BaseDisp32);
+                match index {
+                    Some((index, scale)) => {
+                        self.stream.push_u8(mod_rm(mod_, reg_code_, 0b100));
+                        self.stream.push_u8(sib(scale, reg_code(&index) & 7, base));
+                    }
+                    // [rsp] and [r12] need SIB.
+                    None if base == 4 => {
+                        self.stream.push_u8(mod_rm(mod_, reg_code_, 0b100));
+                        self.stream.push_u8(sib(1, 0b100, base));
+                    }
+                    None => self.stream.push_u8(mod_rm(mod_, reg_code_, base)),
+                }
+                if is_disp8 {
+                    self.stream.push_u8(disp as i8 as u8);
+                } else if is_disp32 {
+                    self.stream.push_u32_le(disp as u32);
+                }
+            }
+            Addr::RipRel(symbol, disp) => {
+                self.stream.push_u8(mod_rm(Mod::Base, reg_code_, 0b101));
+                let at = Offset(self.function.id.unwrap(), self.stream.data().len());
+                let addend = disp - 4 - imm_size as i32;
+                self.labels
+                    .add_reloc(at, symbol, RelocKind::PcRel32, addend);
+                self.stream.push_u32_le(0);
+            }
+            Addr::Abs(symbol, disp, index) => {
+                self.stream.push_u8(mod_rm(Mod::Base, reg_code_, 0b100));
+                match index {
+                    Some((index, scale)) => {
+                        self.stream.push_u8(sib(scale, reg_code(&index) & 7, 0b101))
+                    }
+                    None => self.stream.push_u8(sib(1, 0b100, 0b101)),
+                }
+                let at = Offset(self.function.id.unwrap(), self.stream.data().len());
+                self.labels.add_reloc(at, symbol, RelocKind::Abs32S, disp);
+                self.stream.push_u32_le(0);
             }
-            _ => unimplemented!(),
         }
+    }
 
-        self.stream.push_u32_le(0);
+    fn resolve_mem(&self, mem: &MachineMemOperand) -> Addr {
+        let fo = |fi: &FrameIndexInfo| {
+            self.function
+                .frame_objects
+                .as_ref()
+                .unwrap()
+                .offset(fi.idx)
+                .unwrap()
+        };
+        let base =
+            |base: &RegisterOperand, index: Option<(&RegisterOperand, i32)>, disp| Addr::Base {
+                base: base.id.as_phys_reg(),
+                index: index.map(|(r, scale)| (r.id.as_phys_reg(), scale)),
+                disp,
+            };
+        match mem {
+            MachineMemOperand::BaseFi(b, fi) => base(b, None, fo(fi)),
+            MachineMemOperand::BaseFiOff(b, fi, off) => base(b, None, fo(fi) + off),
+            MachineMemOperand::BaseFiAlignOff(b, fi, align, idx) => {
+                base(b, Some((idx, *align)), fo(fi))
+            }
+            MachineMemOperand::BaseFiAlignOffOff(b, fi, align, idx, off) => {
+                base(b, Some((idx, *align)), fo(fi) + off)
+            }
+            MachineMemOperand::BaseAlignOff(b, align, idx) => base(b, Some((idx, *align)), 0),
+            MachineMemOperand::BaseOffAlignOff(b, off, align, idx) => {
+                base(b, Some((idx, *align)), *off)
+            }
+            MachineMemOperand::BaseOff(b, off) => base(b, None, *off),
+            MachineMemOperand::Base(b) => base(b, None, 0),
+            MachineMemOperand::Address(kind) => Addr::RipRel(self.symbol(kind), 0),
+            MachineMemOperand::AddressOff(kind, off) => Addr::RipRel(self.symbol(kind), *off),
+            MachineMemOperand::AddressAlignOff(kind, align, idx) => {
+                Addr::Abs(self.symbol(kind), 0, Some((idx.id.as_phys_reg(), *align)))
+            }
+        }
     }
 
-    fn gen_ret(&mut self) {
-        // TODO: What's the difference between Near return and Far return?
-        self.stream.push_u8(0xc3);
+    fn symbol(&self, kind: &AddressKind) -> String {
+        match kind {
+            AddressKind::FunctionName(name) => match self.module.find_function_by_name(name) {
+                Some(id) if !self.module.function_ref(id).is_empty() => name.clone(),
+                _ => function_symbol(name),
+            },
+            AddressKind::Global(id) => global_var_symbol(self.module, *id),
+            AddressKind::Constant(id) => constant_symbol(*id),
+            AddressKind::Label(id) => const_data_symbol(id),
+        }
+    }
+
+    fn push_imm(&mut self, imm: i64, size: usize) {
+        for i in 0..size {
+            self.stream.push_u8((imm >> (i * 8)) as u8);
+        }
+    }
+
+    fn def(&self, i: usize) -> PhysReg {
+        reg_of(&self.inst.def[i])
+    }
+
+    fn reg(&self, i: usize) -> PhysReg {
+        reg_of(self.inst.operand[i].as_register())
     }
 }
 
-pub fn reg_code(r: &RegisterId) -> u8 {
-    let r = r.as_phys_reg();
+/// The physical register of `r`, seen as the register class it's used as.
+fn reg_of(r: &RegisterOperand) -> PhysReg {
+    let reg = r.id.as_phys_reg();
+    match r.sub_super {
+        Some(rc) => reg.reg_class_as(rc),
+        None => reg,
+    }
+}
+
+fn is_64(r: &PhysReg) -> bool {
+    r.reg_class() == RegisterClassKind::GR64
+}
+
+/// `spl`, `bpl`, `sil` and `dil` are only accessible with REX.
+fn needs_rex_for_byte(r: &PhysReg) -> bool {
+    r.reg_class() == RegisterClassKind::GR8 && (4..8).contains(&reg_code(r))
+}
+
+fn const_to_i64(c: &MachineConstant) -> i64 {
+    match c {
+        MachineConstant::Int8(i) => *i as i64,
+        MachineConstant::Int16(i) => *i as i64,
+        MachineConstant::Int32(i) => *i as i64,
+        MachineConstant::Int64(i) => *i,
+        MachineConstant::F32(f) => f.to_bits() as i64,
+        MachineConstant::F64(f) => f.to_bits() as i64,
+    }
+}
+
+/// The condition code shared by `jcc`, `setcc` and `cmovcc`.
+fn cond_code(opcode: MachineOpcode) -> u8 {
+    match opcode {
+        MachineOpcode::JB | MachineOpcode::SETB | MachineOpcode::CMOVBrr => 0x2,
+        MachineOpcode::JAE | MachineOpcode::SETAE | MachineOpcode::CMOVAErr => 0x3,
+        MachineOpcode::JE | MachineOpcode::SETE | MachineOpcode::CMOVErr => 0x4,
+        MachineOpcode::JNE | MachineOpcode::SETNE | MachineOpcode::CMOVNErr => 0x5,
+        MachineOpcode::JBE | MachineOpcode::SETBE | MachineOpcode::CMOVBErr => 0x6,
+        MachineOpcode::JA | MachineOpcode::SETA | MachineOpcode::CMOVArr => 0x7,
+        MachineOpcode::JL | MachineOpcode::SETL | MachineOpcode::CMOVLrr => 0xc,
+        MachineOpcode::JGE | MachineOpcode::SETGE | MachineOpcode::CMOVGErr => 0xd,
+        MachineOpcode::JLE | MachineOpcode::SETLE | MachineOpcode::CMOVLErr => 0xe,
+        MachineOpcode::JG | MachineOpcode::SETG | MachineOpcode::CMOVGrr => 0xf,
+        _ => unreachable!(),
+    }
+}
+
+pub fn reg_code(r: &PhysReg) -> u8 {
     (r.retrieve() - r.reg_class() as usize) as u8
 }
 
@@ -239,3 +552,14 @@ pub fn mod_rm(mod_: Mod, reg: u8, rm: u8) -> u8 {
     };
     (mod_ << 6) + (reg << 3) + rm
 }
+
+fn sib(scale: i32, index: u8, base: u8) -> u8 {
+    let scale = match scale {
+        1 => 0b00,
+        2 => 0b01,
+        4 => 0b10,
+        8 => 0b11,
+        _ => unreachable!(),
+    };
+    (scale << 6) + (index << 3) + base
+}
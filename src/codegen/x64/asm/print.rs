@@ -1,6 +1,6 @@
 use super::super::frame_object::FrameObjectsInfo;
 use super::super::machine::inst::*;
-use crate::codegen::common::machine::{
+use crate::codegen::x64::common::machine::{
     basic_block::MachineBasicBlockId,
     const_data::DataId,
     function::{InstIter, MachineFunction},
@@ -86,12 +86,28 @@ impl MachineAsmPrinter {
         for (id, data) in f.const_data.id_and_data() {
             self.output
                 .push_str(format!(".Lconst{}{}:\n", id.arena_id(), id.id()).as_str());
-            self.output.push_str(
-                format!("  .quad {}\n", unsafe {
-                    ::std::mem::transmute::<f64, u64>(data.as_f64())
-                })
-                .as_str(),
-            )
+            match data {
+                MachineConstant::F32(f) => self
+                    .output
+                    .push_str(format!("  .long {}\n", f.to_bits()).as_str()),
+                _ => self.output.push_str(
+                    format!("  .quad {}\n", unsafe {
+                        ::std::mem::transmute::<f64, u64>(data.as_f64())
+                    })
+                    .as_str(),
+                ),
+            }
+        }
+
+        for (id, blocks) in f.const_data.id_and_jump_tables() {
+            let label = format!(".Lconst{}{}", id.arena_id(), id.id());
+            self.output
+                .push_str(format!("  .p2align 2\n{}:\n", label).as_str());
+            for block in blocks {
+                self.output.push_str(
+                    format!("  .long {}-{}\n", self.bb_id_to_label_id(block), label).as_str(),
+                );
+            }
         }
 
         self.output
@@ -167,6 +183,12 @@ impl MachineAsmPrinter {
             MachineOperand::Constant(MachineConstant::Int8(i)) => {
                 self.output.push_str(format!("{}", i).as_str())
             }
+            MachineOperand::Constant(MachineConstant::Int16(i)) => {
+                self.output.push_str(format!("{}", i).as_str())
+            }
+            MachineOperand::Constant(MachineConstant::Int64(i)) => {
+                self.output.push_str(format!("{}", i).as_str())
+            }
             MachineOperand::Register(r) => self.output.push_str(r.id.as_phys_reg().name()),
             MachineOperand::FrameIndex(i) => self
                 .output
@@ -175,7 +197,7 @@ impl MachineAsmPrinter {
                 self.output.push_str(name.replace('.', "_").as_str())
             }
             MachineOperand::Mem(_) => {
-                let word = opcode2word(opcode);
+                let word = opcode.mem_word().unwrap_or("");
                 self.run_on_mem_operand(operand, fo, word)
             }
             e => unimplemented!("{:?}", e),
@@ -184,9 +206,9 @@ impl MachineAsmPrinter {
 
     fn run_on_mem_operand(&mut self, op: &MachineOperand, fo: &FrameObjectsInfo, word: &str) {
         match op {
-            MachineOperand::Mem(MachineMemOperand::Address(AddressKind::Label(id))) => {
-                self.output.push_str(self.data_id_to_label_id(id).as_str())
-            }
+            MachineOperand::Mem(MachineMemOperand::Address(AddressKind::Label(id))) => self
+                .output
+                .push_str(self.data_id_to_label_id(id, word).as_str()),
             MachineOperand::Mem(MachineMemOperand::Address(AddressKind::Global(id))) => self
                 .output
                 .push_str(format!("{} ptr [{}]", word, self.global_var_name(id)).as_str()),
@@ -282,9 +304,10 @@ impl MachineAsmPrinter {
         format!(".L{}", bb_id.index() + self.cur_bb_id_base)
     }
 
-    fn data_id_to_label_id(&self, data_id: &DataId) -> String {
+    fn data_id_to_label_id(&self, data_id: &DataId, word: &str) -> String {
         format!(
-            "qword ptr [rip + .Lconst{}{}]",
+            "{} ptr [rip + .Lconst{}{}]",
+            word,
             data_id.arena_id(),
             data_id.id()
         )
@@ -302,32 +325,3 @@ fn off_s(off: i32) -> String {
         format!("+{}", off)
     }
 }
-
-fn opcode2word(opcode: &MachineOpcode) -> &'static str {
-    let byte = match opcode {
-        MachineOpcode::LEAr64m
-        | MachineOpcode::MOVSDrm64
-        | MachineOpcode::MOVSDmr
-        | MachineOpcode::MOVmr64
-        | MachineOpcode::DIVSDrm
-        | MachineOpcode::MOVrm64
-        | MachineOpcode::MOVmi64
-        | MachineOpcode::MOVSDrm => 8,
-        MachineOpcode::MOVmr32
-        | MachineOpcode::MOVrm32
-        | MachineOpcode::MOVmi32
-        | MachineOpcode::MOVSXDr64m32
-        | MachineOpcode::ADDSDrm
-        | MachineOpcode::SUBSDrm
-        | MachineOpcode::MULSDrm
-        | MachineOpcode::DIVSDrr => 4,
-        MachineOpcode::MOVrm8 | MachineOpcode::MOVmr8 | MachineOpcode::MOVmi8 => 1,
-        _ => 0,
-    };
-    match byte {
-        1 => "byte",
-        4 => "dword",
-        8 => "qword",
-        _ => "",
-    }
-}
@@ -1,12 +1,12 @@
 use crate::codegen::{
-    arch::machine::{abi::SystemV, register::*},
-    common::{
-        machine::calling_conv::{ArgumentRegisterOrder, CallingConv},
+    x64::common::{
         dag::{
             convert::BlockConversionContext,
             node::{IRNode, IROpcode},
         },
+        machine::calling_conv::{ArgumentRegisterOrder, CallingConv},
     },
+    x64::machine::{abi::SystemV, register::*},
 };
 
 pub fn copy_reg_args<'a>(ctx: &mut BlockConversionContext<'a>) {
@@ -1,17 +1,20 @@
-use crate::codegen::arch::machine::register::{str2reg, RegisterClassKind as RC, GR64};
-use crate::codegen::arch::{dag::node::MemKind, machine::inst::MachineOpcode as MO};
-use crate::codegen::common::{
+use crate::codegen::x64::common::machine::inst_def::TargetImmediate;
+use crate::codegen::x64::common::{
     dag::{
         function::DAGFunction,
         module::DAGModule,
         node::{IRNode, IROpcode, ImmediateKind, MINode, Node, NodeId, OperandNode},
         pat_match::{
-            any, any_block, any_f64_imm, any_i32_imm, any_i8_imm, any_slot, inst_select, ir,
-            reg_class, CompoundPat, MatchContext, Pat, ReplacedNodeMap,
+            any, any_block, any_f32_imm, any_f64_imm, any_i16_imm, any_i32_imm, any_i64_imm,
+            any_i8_imm, any_imm, any_reg, any_slot, inst_select, ir, reg_class, CompoundPat,
+            MatchContext, Pat, ReplacedNodeMap,
         },
     },
     types::MVType,
 };
+use crate::codegen::x64::machine::inst_def::SelectOperand;
+use crate::codegen::x64::machine::register::{str2reg, ty2rc, RegisterClassKind as RC, GR64};
+use crate::codegen::x64::{dag::node::MemKind, machine::inst::MachineOpcode as MO};
 use crate::ir::types::Type;
 use defs::node_gen;
 
@@ -25,53 +28,42 @@ pub fn run(module: &mut DAGModule) {
 }
 
 fn run_on_function(func: &mut DAGFunction) {
-    #[rustfmt::skip]
-    let store: Pat = (
-                        ir(IROpcode::Store).args(vec![ir(IROpcode::FIAddr).args(vec![any_slot().named("dst").into()]).into(), any_i32_imm().named("src").into()])
-                                           .generate(|m, c| node_gen!((MI.MOVmi32 [BaseFi %rbp, m["dst"]], m["src"])))
-                      | ir(IROpcode::Store).args(vec![ir(IROpcode::FIAddr).args(vec![any_slot().named("dst").into()]).into(), reg_class(RC::GR32).named("src").into()])
-                                           .generate(|m, c| node_gen!((MI.MOVmr32 [BaseFi %rbp, m["dst"]], m["src"])))
-                      | ir(IROpcode::Store).args(vec![ir(IROpcode::FIAddr).args(vec![any_slot().named("dst").into()]).into(), any_i8_imm().named("src").into()])
-                                           .generate(|m, c| node_gen!((MI.MOVmr8  [BaseFi %rbp, m["dst"]], m["src"]))).into()
-                      | ir(IROpcode::Store).args(vec![ir(IROpcode::FIAddr).args(vec![any_slot().named("dst").into()]).into(), reg_class(RC::GR8).named("src").into()])
-                                           .generate(|m, c| {
-                                                let rbp = c.arena.alloc(c.regs.get_phys_reg(GR64::RBP).into());
-                                                let mem = c.arena.alloc(OperandNode::Mem(MemKind::BaseFi([rbp, m["dst"]])).into());
-                                                c.arena.alloc(MINode::new(MO::MOVmr8).args(vec![mem, m["src"]]).into()) }).into()
-                      | ir(IROpcode::Store).args(vec![ir(IROpcode::FIAddr).args(vec![any_slot().named("dst").into()]).into(), reg_class(RC::GR64).named("src").into()])
-                                           .generate(|m, c| {
-                                                let rbp = c.arena.alloc(c.regs.get_phys_reg(GR64::RBP).into());
-                                                let mem = c.arena.alloc(OperandNode::Mem(MemKind::BaseFi([rbp, m["dst"]])).into());
-                                                c.arena.alloc(MINode::new(MO::MOVmr64).args(vec![mem, m["src"]]).into()) }).into()
-                      | ir(IROpcode::Store).args(vec![ir(IROpcode::FIAddr).args(vec![any_slot().named("dst")]).into(), any_f64_imm().named("src").into()])
-                                           .generate(|m, c| {
-                                                let rbp = c.arena.alloc(c.regs.get_phys_reg(GR64::RBP).into());
-                                                let mem = c.arena.alloc(OperandNode::Mem(MemKind::BaseFi([rbp, m["dst"]])).into());
-                                                let src = c.arena.alloc(MINode::new(MO::MOVSDrm64).args(vec![m["src"]]).reg_class(RC::XMM).into());
-                                                c.arena.alloc(MINode::new(MO::MOVSDmr).args(vec![mem, src]).into()) }).into()
-                       // (Store (FIAddr slot:f64) src:XMM) -> (MOVSDmr BaseFi(rbp, slot) src)
-                      | ir(IROpcode::Store).args(vec![ir(IROpcode::FIAddr).args(vec![any_slot().named("dst")]).into(), reg_class(RC::XMM).named("src").into()])
-                                           .generate(|m, c| {
-                                                let rbp = c.arena.alloc(c.regs.get_phys_reg(GR64::RBP).into());
-                                                let mem = c.arena.alloc(OperandNode::Mem(MemKind::BaseFi([rbp, m["dst"]])).into());
-                                                c.arena.alloc(MINode::new(MO::MOVSDmr).args(vec![mem, m["src"]]).into()) }).into()
-                      | ir(IROpcode::Store).args(vec![reg_class(RC::GR64).named("dst").into(), any_i32_imm().named("src").into()])
-                                           .generate(|m, c| {
-                                                let mem = c.arena.alloc(OperandNode::Mem(MemKind::Base(m["dst"])).into());
-                                                c.arena.alloc(MINode::new(MO::MOVmi32).args(vec![mem, m["src"]]).into()) }).into()
-                      | ir(IROpcode::Store).args(vec![reg_class(RC::GR64).named("dst").into(), reg_class(RC::GR32).named("src").into()])
-                                           .generate(|m, c| {
-                                                let mem = c.arena.alloc(OperandNode::Mem(MemKind::Base(m["dst"])).into());
-                                                c.arena.alloc(MINode::new(MO::MOVmr32).args(vec![mem, m["src"]]).into()) }).into()
-                      | ir(IROpcode::Store).args(vec![reg_class(RC::GR64).named("dst").into(), reg_class(RC::XMM).named("src").into()])
-                                           .generate(|m, c| {
-                                                let mem = c.arena.alloc(OperandNode::Mem(MemKind::Base(m["dst"])).into());
-                                                c.arena.alloc(MINode::new(MO::MOVSDmr).args(vec![mem, m["src"]]).into()) }).into()
-                      | ir(IROpcode::Store).args(vec![reg_class(RC::GR64).named("dst").into(), reg_class(RC::GR64).named("src").into()])
-                                           .generate(|m, c| {
-                                                let mem = c.arena.alloc(OperandNode::Mem(MemKind::Base(m["dst"])).into());
-                                                c.arena.alloc(MINode::new(MO::MOVmr64).args(vec![mem, m["src"]]).into()) }).into()
-                    ).into();
+    let store: Pat = ir(IROpcode::Store)
+        .args(vec![
+            (ir(IROpcode::FIAddr)
+                .args(vec![any_slot().named("dst")])
+                .into(): CompoundPat
+                | reg_class(RC::GR64).named("dst").into())
+            .into(),
+            (any_reg() | any_imm()).named("src").into(),
+        ])
+        .generate(|m, c| {
+            let mem = mem(c, m["dst"]);
+            let src = match c.arena[m["src"]] {
+                Node::Operand(OperandNode::Imm(ImmediateKind::Int64(_))) => {
+                    return store_i64_imm(c, mem, m["src"])
+                }
+                // x64 has no floating-point immediate operand
+                Node::Operand(OperandNode::Imm(ImmediateKind::F32(_))) => c.arena.alloc(
+                    MINode::new(MO::MOVSSrm32)
+                        .args(vec![m["src"]])
+                        .reg_class(RC::FR32)
+                        .into(),
+                ),
+                Node::Operand(OperandNode::Imm(ImmediateKind::F64(_))) => c.arena.alloc(
+                    MINode::new(MO::MOVSDrm64)
+                        .args(vec![m["src"]])
+                        .reg_class(RC::XMM)
+                        .into(),
+                ),
+                _ => m["src"],
+            };
+            let ops = [SelectOperand::Mem, select_operand(c, src)];
+            let opcode = MO::select(IROpcode::Store, MVType::Void, &ops).unwrap();
+            c.arena
+                .alloc(MINode::new(opcode).args(vec![mem, src]).into())
+        })
+        .into();
 
     // TODO: Support GlobalAddr
     let load: Pat = ir(IROpcode::Load)
@@ -82,27 +74,9 @@ fn run_on_function(func: &mut DAGFunction) {
             | reg_class(RC::GR64).named("src").into())
         .into()])
         .generate(|m, c| {
-            let rbp = c.arena.alloc(c.regs.get_phys_reg(GR64::RBP).into());
-            let src = m["src"];
-            let mem = c.arena.alloc(
-                match c.arena[src] {
-                    Node::IR(_) | Node::MI(_) | Node::Operand(OperandNode::Reg(_)) => {
-                        MemKind::Base(src)
-                    }
-                    Node::Operand(OperandNode::Slot(_)) => MemKind::BaseFi([rbp, src]),
-                    _ => unreachable!(),
-                }
-                .into(),
-            );
-            let opcode = match c.arena[m["load"]].as_ir().mvty {
-                MVType::Void | MVType::Invalid => panic!(),
-                MVType::i1 | MVType::i8 => MO::MOVrm8,
-                MVType::i16 => panic!(),
-                MVType::i32 => MO::MOVrm32,
-                MVType::i64 => MO::MOVrm64,
-                MVType::f64 => MO::MOVSDrm,
-                MVType::f32 => panic!(),
-            };
+            let mem = mem(c, m["src"]);
+            let ty = c.arena[m["load"]].as_ir().mvty;
+            let opcode = MO::select(IROpcode::Load, ty, &[SelectOperand::Mem]).unwrap();
             c.arena.alloc(
                 MINode::new(opcode)
                     .args(vec![mem])
@@ -112,7 +86,7 @@ fn run_on_function(func: &mut DAGFunction) {
         })
         .into();
 
-    use crate::codegen::common::dag::pat_match::{any_imm8, mul, reg_};
+    use crate::codegen::x64::common::dag::pat_match::{any_imm8, mul, reg_};
 
     let mul8: Pat = mul(
         reg_(RC::GR8).named("lhs"),
@@ -129,54 +103,104 @@ fn run_on_function(func: &mut DAGFunction) {
         _ => panic!(),
     });
 
+    // i16 operations are done in 32-bit registers
+    #[rustfmt::skip]
+    let bin16: Pat = {
+        let lhs = || (reg_class(RC::GR16) | any_i16_imm()).named("lhs").into();
+        let rhs = || (reg_class(RC::GR16) | any_i16_imm()).named("rhs").into();
+        let add16 = ir(IROpcode::Add).named("bin").ty(Type::i16).args(vec![lhs(), rhs()]);
+        let sub16 = ir(IROpcode::Sub).named("bin").ty(Type::i16).args(vec![lhs(), rhs()]);
+        let mul16 = ir(IROpcode::Mul).named("bin").ty(Type::i16).args(vec![lhs(), rhs()]);
+        let and16 = ir(IROpcode::And).named("bin").ty(Type::i16).args(vec![lhs(), rhs()]);
+        let or16  = ir(IROpcode::Or) .named("bin").ty(Type::i16).args(vec![lhs(), rhs()]);
+        let xor16 = ir(IROpcode::Xor).named("bin").ty(Type::i16).args(vec![lhs(), rhs()]);
+        ((add16 | sub16) | (mul16 | and16) | (or16 | xor16)).generate(|m, c| {
+            let lhs = match c.arena[m["lhs"]] {
+                Node::Operand(OperandNode::Imm(ImmediateKind::Int16(i))) => {
+                    let i = c.arena.alloc((i as i32).into());
+                    c.arena.alloc(MINode::new(MO::MOVri32).args(vec![i]).reg_class(RC::GR32).into())
+                }
+                _ => node_gen!((IR.RegClass.(Type::i32) m["lhs"])),
+            };
+            let (rhs, imm) = match c.arena[m["rhs"]] {
+                Node::Operand(OperandNode::Imm(ImmediateKind::Int16(i))) => (c.arena.alloc((i as i32).into()), true),
+                _ => (node_gen!((IR.RegClass.(Type::i32) m["rhs"])), false),
+            };
+            let opcode = match c.arena[m["bin"]].as_ir().opcode {
+                IROpcode::Add if imm => MO::ADDri32,
+                IROpcode::Add        => MO::ADDrr32,
+                IROpcode::Sub if imm => MO::SUBri32,
+                IROpcode::Sub        => MO::SUBrr32,
+                IROpcode::Mul if imm => MO::IMULrri32,
+                IROpcode::Mul        => MO::IMULrr32,
+                IROpcode::And if imm => MO::ANDri32,
+                IROpcode::And        => MO::ANDrr32,
+                IROpcode::Or  if imm => MO::ORri32,
+                IROpcode::Or         => MO::ORrr32,
+                IROpcode::Xor if imm => MO::XORri32,
+                IROpcode::Xor        => MO::XORrr32,
+                _ => panic!()
+            };
+            let bin = c.arena.alloc(MINode::new(opcode).args(vec![lhs, rhs]).reg_class(RC::GR32).into());
+            node_gen!((IR.RegClass.(Type::i16) bin))
+        }).into()
+    };
+
     #[rustfmt::skip]
     let bin: Pat = {
         let add8  = ir(IROpcode::Add).named("bin").ty(Type::i8) .args(vec![                 reg_class(RC::GR8)  .named("lhs").into(), (reg_class(RC::GR8)  | any_i8_imm() ).named("rhs").into()]);
         let add32 = ir(IROpcode::Add).named("bin").ty(Type::i32).args(vec![                 reg_class(RC::GR32) .named("lhs").into(), (reg_class(RC::GR32) | any_i32_imm()).named("rhs").into()]);
-        let add64 = ir(IROpcode::Add).named("bin").ty(Type::i64).args(vec![                 reg_class(RC::GR64) .named("lhs").into(), (reg_class(RC::GR64) | any_i32_imm()).named("rhs").into()]);
+        let add64 = ir(IROpcode::Add).named("bin").ty(Type::i64).args(vec![                 reg_class(RC::GR64) .named("lhs").into(), (reg_class(RC::GR64) | any_i32_imm() | any_i64_imm().into()).named("rhs").into()]);
         let sub8  = ir(IROpcode::Sub).named("bin").ty(Type::i8) .args(vec![(any_i8_imm()  | reg_class(RC::GR8)) .named("lhs").into(), (reg_class(RC::GR8)  | any_i8_imm()) .named("rhs").into()]);
         let sub32 = ir(IROpcode::Sub).named("bin").ty(Type::i32).args(vec![(any_i32_imm() | reg_class(RC::GR32)).named("lhs").into(), (reg_class(RC::GR32) | any_i32_imm()).named("rhs").into()]);
-        let sub64 = ir(IROpcode::Sub).named("bin").ty(Type::i64).args(vec![                 reg_class(RC::GR64) .named("lhs").into(), (reg_class(RC::GR64) | any_i32_imm()).named("rhs").into()]);
+        let sub64 = ir(IROpcode::Sub).named("bin").ty(Type::i64).args(vec![(any_i64_imm() | reg_class(RC::GR64)).named("lhs").into(), (reg_class(RC::GR64) | any_i32_imm() | any_i64_imm().into()).named("rhs").into()]);
         let mul32 = ir(IROpcode::Mul).named("bin").ty(Type::i32).args(vec![                 reg_class(RC::GR32) .named("lhs").into(), (reg_class(RC::GR32) | any_i32_imm()).named("rhs").into()]);
-        let mul64 = ir(IROpcode::Mul).named("bin").ty(Type::i64).args(vec![                 reg_class(RC::GR64) .named("lhs").into(),                        any_i32_imm() .named("rhs").into()]);
+        let mul64 = ir(IROpcode::Mul).named("bin").ty(Type::i64).args(vec![                 reg_class(RC::GR64) .named("lhs").into(), (reg_class(RC::GR64) | any_i32_imm() | any_i64_imm().into()).named("rhs").into()]);
         let shl32 = ir(IROpcode::Shl).named("bin").ty(Type::i32).args(vec![                 reg_class(RC::GR32) .named("lhs").into(),                        any_i8_imm()  .named("rhs").into()]);
         let shl64 = ir(IROpcode::Shl).named("bin").ty(Type::i64).args(vec![                 reg_class(RC::GR64) .named("lhs").into(),                        any_i8_imm()  .named("rhs").into()]);
+        let shr32 = ir(IROpcode::LShr).named("bin").ty(Type::i32).args(vec![                reg_class(RC::GR32) .named("lhs").into(),                        any_i8_imm()  .named("rhs").into()]);
+        let shr64 = ir(IROpcode::LShr).named("bin").ty(Type::i64).args(vec![                reg_class(RC::GR64) .named("lhs").into(),                        any_i8_imm()  .named("rhs").into()]);
+        let sar32 = ir(IROpcode::AShr).named("bin").ty(Type::i32).args(vec![                reg_class(RC::GR32) .named("lhs").into(),                        any_i8_imm()  .named("rhs").into()]);
+        let sar64 = ir(IROpcode::AShr).named("bin").ty(Type::i64).args(vec![                reg_class(RC::GR64) .named("lhs").into(),                        any_i8_imm()  .named("rhs").into()]);
+        let and8  = ir(IROpcode::And).named("bin").ty(Type::i8) .args(vec![                 reg_class(RC::GR8)  .named("lhs").into(), (reg_class(RC::GR8)  | any_i8_imm() ).named("rhs").into()]);
+        let and32 = ir(IROpcode::And).named("bin").ty(Type::i32).args(vec![                 reg_class(RC::GR32) .named("lhs").into(), (reg_class(RC::GR32) | any_i32_imm()).named("rhs").into()]);
+        let and64 = ir(IROpcode::And).named("bin").ty(Type::i64).args(vec![                 reg_class(RC::GR64) .named("lhs").into(), (reg_class(RC::GR64) | any_i32_imm() | any_i64_imm().into()).named("rhs").into()]);
+        let or8   = ir(IROpcode::Or) .named("bin").ty(Type::i8) .args(vec![                 reg_class(RC::GR8)  .named("lhs").into(), (reg_class(RC::GR8)  | any_i8_imm() ).named("rhs").into()]);
+        let or32  = ir(IROpcode::Or) .named("bin").ty(Type::i32).args(vec![                 reg_class(RC::GR32) .named("lhs").into(), (reg_class(RC::GR32) | any_i32_imm()).named("rhs").into()]);
+        let or64  = ir(IROpcode::Or) .named("bin").ty(Type::i64).args(vec![                 reg_class(RC::GR64) .named("lhs").into(), (reg_class(RC::GR64) | any_i32_imm() | any_i64_imm().into()).named("rhs").into()]);
+        let xor8  = ir(IROpcode::Xor).named("bin").ty(Type::i8) .args(vec![                 reg_class(RC::GR8)  .named("lhs").into(), (reg_class(RC::GR8)  | any_i8_imm() ).named("rhs").into()]);
+        let xor32 = ir(IROpcode::Xor).named("bin").ty(Type::i32).args(vec![                 reg_class(RC::GR32) .named("lhs").into(), (reg_class(RC::GR32) | any_i32_imm()).named("rhs").into()]);
+        let xor64 = ir(IROpcode::Xor).named("bin").ty(Type::i64).args(vec![                 reg_class(RC::GR64) .named("lhs").into(), (reg_class(RC::GR64) | any_i32_imm() | any_i64_imm().into()).named("rhs").into()]);
         // GR32 a {
         //     GR32  b => (mi.IMULrr32  a, b)
         //     imm32 b => (mi.IMULrri32 a, b) }
         // GR64 a {
         //     imm32 b => (mi.IMULrr64i32 a, b) }
-        (((add8 | add32 | add64.into())) | (sub8 | sub32 | sub64.into()) | (mul32 | mul64) | (shl32 | shl64)).generate(|m, c| {
+        (((add8 | add32 | add64.into())) | (sub8 | sub32 | sub64.into()) | (mul32 | mul64)
+         | (shl32 | shl64) | (shr32 | shr64) | (sar32 | sar64)
+         | (and8 | and32 | and64.into()) | (or8 | or32 | or64.into()) | (xor8 | xor32 | xor64.into())).generate(|m, c| {
             let ty = c.arena[m["bin"]].as_ir().mvty;
             let lhs = match c.arena[m["lhs"]] {
                 Node::Operand(OperandNode::Imm(ImmediateKind::Int8(_))) => 
                     c.arena.alloc(MINode::new(MO::MOVri8).args(vec![m["lhs"]]).reg_class(RC::GR8).into()),
                 Node::Operand(OperandNode::Imm(ImmediateKind::Int32(_))) => 
                     c.arena.alloc(MINode::new(MO::MOVri32).args(vec![m["lhs"]]).reg_class(RC::GR32).into()),
+                Node::Operand(OperandNode::Imm(ImmediateKind::Int64(_))) => 
+                    c.arena.alloc(MINode::new(MO::MOVri64).args(vec![m["lhs"]]).reg_class(RC::GR64).into()),
                 _ => m["lhs"]
             };
-            let rhs = &c.arena[m["rhs"]];
-            let opcode = match c.arena[m["bin"]].as_ir().opcode {
-                IROpcode::Add if matches!(ty, MVType::i8)  && matches!(rhs, Node::Operand(OperandNode::Imm(_))) => MO::ADDri8,
-                IROpcode::Add if matches!(ty, MVType::i8)                                                       => MO::ADDrr8,
-                IROpcode::Add if matches!(ty, MVType::i32) && matches!(rhs, Node::Operand(OperandNode::Imm(_))) => MO::ADDri32,
-                IROpcode::Add if matches!(ty, MVType::i32)                                                      => MO::ADDrr32,
-                IROpcode::Add if matches!(ty, MVType::i64) && matches!(rhs, Node::Operand(OperandNode::Imm(_))) => MO::ADDr64i32,
-                IROpcode::Add if matches!(ty, MVType::i64)                                                      => MO::ADDrr64,
-                IROpcode::Sub if matches!(ty, MVType::i8)  && matches!(rhs, Node::Operand(OperandNode::Imm(_))) => MO::SUBri8,
-                IROpcode::Sub if matches!(ty, MVType::i8)                                                       => MO::SUBrr8,
-                IROpcode::Sub if matches!(ty, MVType::i32) && matches!(rhs, Node::Operand(OperandNode::Imm(_))) => MO::SUBri32,
-                IROpcode::Sub if matches!(ty, MVType::i32)                                                      => MO::SUBrr32,
-                IROpcode::Sub if matches!(ty, MVType::i64) && matches!(rhs, Node::Operand(OperandNode::Imm(_))) => MO::SUBr64i32,
-                IROpcode::Mul if matches!(ty, MVType::i32) && matches!(rhs, Node::Operand(OperandNode::Imm(_))) => MO::IMULrri32,
-                IROpcode::Mul if matches!(ty, MVType::i32)                                                      => MO::IMULrr32,
-                IROpcode::Mul if matches!(ty, MVType::i64) && matches!(rhs, Node::Operand(OperandNode::Imm(_))) => MO::IMULrr64i32,
-                IROpcode::Shl if matches!(ty, MVType::i32)                                                      => MO::SHLr32i8,
-                IROpcode::Shl if matches!(ty, MVType::i64)                                                      => MO::SHLr64i8,
-                _ => panic!() 
+            // x64 has no 64-bit immediate operand except for mov
+            let rhs_id = match c.arena[m["rhs"]] {
+                Node::Operand(OperandNode::Imm(ImmediateKind::Int64(i))) if i as i32 as i64 == i =>
+                    c.arena.alloc((i as i32).into()),
+                Node::Operand(OperandNode::Imm(ImmediateKind::Int64(_))) =>
+                    c.arena.alloc(MINode::new(MO::MOVri64).args(vec![m["rhs"]]).reg_class(RC::GR64).into()),
+                _ => m["rhs"]
             };
+            let ops = [select_operand(c, lhs), select_operand(c, rhs_id)];
+            let opcode = MO::select(c.arena[m["bin"]].as_ir().opcode, ty, &ops).unwrap();
             c.arena.alloc(MINode::new(opcode).args(vec![
-                lhs, m["rhs"]
+                lhs, rhs_id
             ]).reg_class(opcode.inst_def().unwrap().def_reg_class()).into())
         })
     }.into();
@@ -186,18 +210,19 @@ fn run_on_function(func: &mut DAGFunction) {
         let sub64 = ir(IROpcode::Sub).named("bin").ty(Type::f64).args(vec![(any_f64_imm() | reg_class(RC::XMM)).named("lhs").into(), (reg_class(RC::XMM) | any_f64_imm()).named("rhs").into()]);
         let mul64 = ir(IROpcode::Mul).named("bin").ty(Type::f64).args(vec![                 reg_class(RC::XMM) .named("lhs").into(), (reg_class(RC::XMM) | any_f64_imm()).named("rhs").into()]);
         let div64 = ir(IROpcode::Div).named("bin").ty(Type::f64).args(vec![(any_f64_imm() | reg_class(RC::XMM)).named("lhs").into(), (reg_class(RC::XMM) | any_f64_imm()).named("rhs").into()]);
-        (add64 | sub64 | mul64.into() | div64.into()).generate(|m, c| {
+        let add32 = ir(IROpcode::Add).named("bin").ty(Type::f32).args(vec![                 reg_class(RC::FR32) .named("lhs").into(), (reg_class(RC::FR32) | any_f32_imm()).named("rhs").into()]);
+        let sub32 = ir(IROpcode::Sub).named("bin").ty(Type::f32).args(vec![(any_f32_imm() | reg_class(RC::FR32)).named("lhs").into(), (reg_class(RC::FR32) | any_f32_imm()).named("rhs").into()]);
+        let mul32 = ir(IROpcode::Mul).named("bin").ty(Type::f32).args(vec![                 reg_class(RC::FR32) .named("lhs").into(), (reg_class(RC::FR32) | any_f32_imm()).named("rhs").into()]);
+        let div32 = ir(IROpcode::Div).named("bin").ty(Type::f32).args(vec![(any_f32_imm() | reg_class(RC::FR32)).named("lhs").into(), (reg_class(RC::FR32) | any_f32_imm()).named("rhs").into()]);
+        (add64 | sub64 | mul64.into() | div64.into() | add32.into() | sub32.into() | mul32.into() | div32.into()).generate(|m, c| {
+            let single = c.arena[m["bin"]].as_ir().mvty == MVType::f32;
+            let (mov, rc) = if single { (MO::MOVSSrm32, RC::FR32) } else { (MO::MOVSDrm64, RC::XMM) };
             let lhs = if matches!(c.arena[m["lhs"]], Node::Operand(OperandNode::Imm(_))) {
-                c.arena.alloc(MINode::new(MO::MOVSDrm64).args(vec![m["lhs"]]).reg_class(RC::XMM).into()) } else { m["lhs"] };
+                c.arena.alloc(MINode::new(mov).args(vec![m["lhs"]]).reg_class(rc).into()) } else { m["lhs"] };
             let rhs = if matches!(c.arena[m["rhs"]], Node::Operand(OperandNode::Imm(_))) {
-                c.arena.alloc(MINode::new(MO::MOVSDrm64).args(vec![m["rhs"]]).reg_class(RC::XMM).into()) } else { m["rhs"] };
-            let opcode = match c.arena[m["bin"]].as_ir().opcode {
-                IROpcode::Add => MO::ADDSDrr,
-                IROpcode::Sub => MO::SUBSDrr,
-                IROpcode::Mul => MO::MULSDrr,
-                IROpcode::Div => MO::DIVSDrr,
-                _ => panic!() 
-            };
+                c.arena.alloc(MINode::new(mov).args(vec![m["rhs"]]).reg_class(rc).into()) } else { m["rhs"] };
+            let ops = [select_operand(c, lhs), select_operand(c, rhs)];
+            let opcode = MO::select(c.arena[m["bin"]].as_ir().opcode, c.arena[m["bin"]].as_ir().mvty, &ops).unwrap();
             c.arena.alloc(MINode::new(opcode).args(vec![lhs, rhs]).reg_class(opcode.inst_def().unwrap().def_reg_class()).into())
         }).into()
     };
@@ -215,24 +240,101 @@ fn run_on_function(func: &mut DAGFunction) {
         .args(vec![any().named("a")])
         .generate(|m, c| node_gen!((MI.MOVrm64 [Address m["a"]])))
         .into();
-    let fptosi: Pat = ir(IROpcode::FPToSI)
-        .ty(Type::i32)
-        .args(vec![reg_class(RC::XMM).named("x").into()])
-        .generate(|m, c| node_gen!((MI.CVTTSD2SIr32r m["x"])))
+    let gbladdr: Pat = ir(IROpcode::GlobalAddr)
+        .args(vec![any().named("g")])
+        .generate(|m, c| node_gen!((MI.LEAr64m [Address m["g"]])))
+        .into();
+    let cvt: Pat = {
+        let cvt = |opcode| {
+            ir(opcode)
+                .named("cvt")
+                .args(vec![any_reg().named("x").into()])
+        };
+        (cvt(IROpcode::FPToSI)
+            | cvt(IROpcode::SIToFP)
+            | cvt(IROpcode::FPExt).into()
+            | cvt(IROpcode::FPTrunc).into())
+        .generate(|m, c| {
+            let (opcode, mvty) = (
+                c.arena[m["cvt"]].as_ir().opcode,
+                c.arena[m["cvt"]].as_ir().mvty,
+            );
+            unary(c, opcode, mvty, m["x"])
+        })
+        .into()
+    };
+    // Unsigned conversions go through the signed 64-bit forms, which cover every value of
+    // an unsigned integer narrower than 64 bits. i64 ones need a fix-up for the top bit.
+    let uitofp: Pat = ir(IROpcode::UIToFP)
+        .named("cvt")
+        .args(vec![any_reg().named("r").into()])
+        .generate(|m, c| {
+            let mvty = c.arena[m["cvt"]].as_ir().mvty;
+            let r = match reg_class_of(c, m["r"]) {
+                RC::GR32 => node_gen!((MI.MOVrr32 m["r"])),
+                RC::GR64 => return u64_to_fp(c, m["r"], mvty),
+                _ => unary(c, IROpcode::Zext, MVType::i32, m["r"]),
+            };
+            let r = node_gen!((IR.RegClass.(Type::i64) r));
+            unary(c, IROpcode::SIToFP, mvty, r)
+        })
         .into();
-    let sitofp: Pat = ir(IROpcode::SIToFP)
-        .ty(Type::f64)
-        .args(vec![reg_class(RC::GR32).named("x").into()])
-        .generate(|m, c| node_gen!((MI.CVTSI2SDrr32 m["x"])))
+    let fptoui: Pat = ir(IROpcode::FPToUI)
+        .named("cvt")
+        .args(vec![any_reg().named("x").into()])
+        .generate(|m, c| {
+            let ty = c.arena[m["cvt"]].as_ir().ty;
+            if ty == Type::i64 {
+                return fp_to_u64(c, m["x"]);
+            }
+            let r = unary(c, IROpcode::FPToSI, MVType::i64, m["x"]);
+            node_gen!((IR.RegClass.(ty) r))
+        })
+        .into();
+    // Extensions go through 32 bits since writing a 32-bit register clears its upper half
+    let sext_: Pat = ir(IROpcode::Sext)
+        .named("ext")
+        .args(vec![any_reg().named("r").into()])
+        .generate(|m, c| {
+            let ty = c.arena[m["ext"]].as_ir().ty;
+            let r = match reg_class_of(c, m["r"]) {
+                RC::GR8 | RC::GR16 => unary(c, IROpcode::Sext, MVType::i32, m["r"]),
+                _ => m["r"],
+            };
+            match ty2rc(&ty).unwrap() {
+                RC::GR64 => unary(c, IROpcode::Sext, MVType::i64, r),
+                RC::GR32 => r,
+                _ => node_gen!((IR.RegClass.(ty) r)),
+            }
+        })
         .into();
     let zext_: Pat = ir(IROpcode::Zext)
-        .ty(Type::i32)
-        .args(vec![reg_(RC::GR8).named("r")])
-        .generate(|m, c| node_gen!((MI.MOVZXr32r8 m["r"])))
+        .named("ext")
+        .args(vec![any_reg().named("r").into()])
+        .generate(|m, c| {
+            let ty = c.arena[m["ext"]].as_ir().ty;
+            let r = match reg_class_of(c, m["r"]) {
+                RC::GR8 | RC::GR16 => unary(c, IROpcode::Zext, MVType::i32, m["r"]),
+                _ => node_gen!((MI.MOVrr32 m["r"])),
+            };
+            match ty2rc(&ty).unwrap() {
+                RC::GR32 => r,
+                _ => node_gen!((IR.RegClass.(ty) r)),
+            }
+        })
+        .into();
+    let trunc: Pat = ir(IROpcode::Trunc)
+        .named("trunc")
+        .args(vec![any_reg().named("r").into()])
+        .generate(|m, c| {
+            let ty = c.arena[m["trunc"]].as_ir().ty;
+            node_gen!((IR.RegClass.(ty) m["r"]))
+        })
         .into();
 
     let pats = vec![
-        store, load, mul8, bin, fbin, br, fiaddr, constaddr, fptosi, sitofp, zext_,
+        store, load, mul8, bin16, bin, fbin, br, fiaddr, constaddr, gbladdr, cvt, uitofp, fptoui,
+        sext_, zext_, trunc,
     ];
 
     let mut replaced = ReplacedNodeMap::default();
@@ -266,3 +368,137 @@ fn select_node<'a>(
 
     new
 }
+
+fn select_operand(c: &MatchContext, id: NodeId) -> SelectOperand {
+    match c.arena[id] {
+        Node::Operand(OperandNode::Imm(i)) => SelectOperand::Imm(match i {
+            ImmediateKind::Int8(_) => TargetImmediate::I8,
+            ImmediateKind::Int16(_) => TargetImmediate::I16,
+            ImmediateKind::Int32(_) => TargetImmediate::I32,
+            ImmediateKind::Int64(_) => TargetImmediate::I64,
+            ImmediateKind::F32(_) => TargetImmediate::F32,
+            ImmediateKind::F64(_) => TargetImmediate::F64,
+        }),
+        _ => SelectOperand::Reg(reg_class_of(c, id)),
+    }
+}
+
+/// Selects the single-register instruction `opcode` producing `ty` from `r`.
+fn unary(c: &mut MatchContext, opcode: IROpcode, ty: MVType, r: NodeId) -> NodeId {
+    let ops = [select_operand(c, r)];
+    let opcode = MO::select(opcode, ty, &ops).unwrap();
+    c.arena.alloc(
+        MINode::new(opcode)
+            .args(vec![r])
+            .reg_class(opcode.inst_def().unwrap().def_reg_class())
+            .into(),
+    )
+}
+
+/// Converts the unsigned i64 `x` to `ty`. A value with the sign bit set is halved (keeping
+/// the low bit so that it still rounds correctly), converted and then doubled. The halved
+/// value and the doubling are picked with the sign mask rather than a branch.
+fn u64_to_fp(c: &mut MatchContext, x: NodeId, ty: MVType) -> NodeId {
+    let (add, mul, rc) = match ty {
+        MVType::f32 => (MO::ADDSSrr, MO::MULSSrr, RC::FR32),
+        _ => (MO::ADDSDrr, MO::MULSDrr, RC::XMM),
+    };
+    let i63 = imm(c, ImmediateKind::Int8(63));
+    let sign = mi(c, MO::SARr64i8, vec![x, i63], RC::GR64);
+    let i1 = imm(c, ImmediateKind::Int8(1));
+    let half = mi(c, MO::SHRr64i8, vec![x, i1], RC::GR64);
+    let i1 = imm(c, ImmediateKind::Int32(1));
+    let low = mi(c, MO::ANDr64i32, vec![x, i1], RC::GR64);
+    let half = mi(c, MO::ORrr64, vec![half, low], RC::GR64);
+    // x ^ ((x ^ half) & sign) is half if the sign bit is set and x otherwise
+    let diff = mi(c, MO::XORrr64, vec![x, half], RC::GR64);
+    let diff = mi(c, MO::ANDrr64, vec![diff, sign], RC::GR64);
+    let src = mi(c, MO::XORrr64, vec![x, diff], RC::GR64);
+    let f = unary(c, IROpcode::SIToFP, ty, src);
+    let i1 = imm(c, ImmediateKind::Int32(1));
+    let halved = mi(c, MO::ANDr64i32, vec![sign, i1], RC::GR64);
+    let halved = unary(c, IROpcode::SIToFP, ty, halved);
+    let extra = mi(c, mul, vec![f, halved], rc);
+    mi(c, add, vec![f, extra], rc)
+}
+
+/// Converts `x` to an unsigned i64. cvtts[sd]2si gives 1 << 63 for a value of at least 2^63,
+/// in which case x - 2^63 is converted instead and the top bit is put back.
+fn fp_to_u64(c: &mut MatchContext, x: NodeId) -> NodeId {
+    let (mov, sub, rc, two63) = match reg_class_of(c, x) {
+        RC::FR32 => (
+            MO::MOVSSrm32,
+            MO::SUBSSrr,
+            RC::FR32,
+            ImmediateKind::F32(9223372036854775808.0),
+        ),
+        _ => (
+            MO::MOVSDrm64,
+            MO::SUBSDrr,
+            RC::XMM,
+            ImmediateKind::F64(9223372036854775808.0),
+        ),
+    };
+    let lo = unary(c, IROpcode::FPToSI, MVType::i64, x);
+    let two63 = imm(c, two63);
+    let two63 = mi(c, mov, vec![two63], rc);
+    let y = mi(c, sub, vec![x, two63], rc);
+    let hi = unary(c, IROpcode::FPToSI, MVType::i64, y);
+    // All ones if x >= 2^63
+    let i63 = imm(c, ImmediateKind::Int8(63));
+    let big = mi(c, MO::SARr64i8, vec![lo, i63], RC::GR64);
+    let hi = mi(c, MO::ANDrr64, vec![hi, big], RC::GR64);
+    mi(c, MO::ORrr64, vec![lo, hi], RC::GR64)
+}
+
+fn mi(c: &mut MatchContext, opcode: MO, args: Vec<NodeId>, rc: RC) -> NodeId {
+    c.arena
+        .alloc(MINode::new(opcode).args(args).reg_class(rc).into())
+}
+
+fn imm(c: &mut MatchContext, i: ImmediateKind) -> NodeId {
+    c.arena.alloc(OperandNode::Imm(i).into())
+}
+
+/// The memory operand for a frame slot (addressed off rbp) or an address held in a register.
+fn mem(c: &mut MatchContext, addr: NodeId) -> NodeId {
+    let mem = match c.arena[addr] {
+        Node::Operand(OperandNode::Slot(_)) => {
+            let rbp = c.arena.alloc(c.regs.get_phys_reg(GR64::RBP).into());
+            MemKind::BaseFi([rbp, addr])
+        }
+        _ => MemKind::Base(addr),
+    };
+    c.arena.alloc(OperandNode::Mem(mem).into())
+}
+
+fn reg_class_of(c: &MatchContext, id: NodeId) -> RC {
+    match &c.arena[id] {
+        Node::IR(IRNode { ty, .. }) => ty2rc(ty).unwrap(),
+        Node::MI(MINode { reg_class, .. }) => reg_class.unwrap(),
+        Node::Operand(OperandNode::Reg(r)) => c.regs.arena_ref()[*r].reg_class,
+        _ => unreachable!(),
+    }
+}
+
+/// Stores an i64 immediate into `mem`, materializing it first when it doesn't fit in 32 bits.
+fn store_i64_imm(c: &mut MatchContext, mem: NodeId, src: NodeId) -> NodeId {
+    let i = match c.arena[src] {
+        Node::Operand(OperandNode::Imm(ImmediateKind::Int64(i))) => i,
+        _ => unreachable!(),
+    };
+    if i as i32 as i64 == i {
+        let src = c.arena.alloc((i as i32).into());
+        return c
+            .arena
+            .alloc(MINode::new(MO::MOVmi64).args(vec![mem, src]).into());
+    }
+    let src = c.arena.alloc(
+        MINode::new(MO::MOVri64)
+            .args(vec![src])
+            .reg_class(RC::GR64)
+            .into(),
+    );
+    c.arena
+        .alloc(MINode::new(MO::MOVmr64).args(vec![mem, src]).into())
+}
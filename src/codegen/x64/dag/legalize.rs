@@ -1,19 +1,19 @@
-use crate::codegen::arch::machine::register::{str2reg, RegisterClassKind as RC, GR64};
-use crate::codegen::arch::{dag::node::MemKind, machine::inst::MachineOpcode as MO};
-use crate::codegen::common::{
+use crate::codegen::x64::common::{
     dag::{
         function::DAGFunction,
         module::DAGModule,
         node::{IRNode, IROpcode, ImmediateKind, MINode, Node, NodeId, OperandNode},
         pat_match::{
             add, any, any_block, any_cc, any_f64_imm, any_i32_imm, any_imm, any_imm32,
-            any_imm32_power_of_2, any_imm_f64, any_reg, any_slot, bitcast, fiaddr, gbladdr,
-            inst_select, ir, load, mul, not, reg_, reg_class, reorder_patterns, sext, store,
+            any_imm32_power_of_2, any_imm_f32, any_imm_f64, any_reg, any_slot, bitcast, fiaddr,
+            gbladdr, inst_select, ir, load, mul, not, reg_, reg_class, reorder_patterns, store,
             CompoundPat, MatchContext, Pat, ReplacedNodeMap,
         },
     },
     types::MVType,
 };
+use crate::codegen::x64::machine::register::{str2reg, RegisterClassKind as RC, GR64};
+use crate::codegen::x64::{dag::node::MemKind, machine::inst::MachineOpcode as MO};
 use crate::ir::types::Type;
 use defs::node_gen;
 
@@ -47,7 +47,9 @@ fn run_on_function(func: &mut DAGFunction) {
     .generate(|m, c| {
         let opcode = match c.arena[m["load"]].as_ir().mvty {
             MVType::i8 => MO::MOVrm8,
+            MVType::i16 => MO::MOVrm16,
             MVType::i32 => MO::MOVrm32,
+            MVType::i64 => MO::MOVrm64,
             _ => todo!(),
         };
         node_gen!((MI.(opcode) [BaseAlignOff m["base"], m["align"], (IR.RegClass.(Type::i64) m["off"])]))
@@ -60,7 +62,9 @@ fn run_on_function(func: &mut DAGFunction) {
     .generate(|m, c| {
         let opcode = match c.arena[m["load"]].as_ir().mvty {
             MVType::i8 => MO::MOVrm8,
+            MVType::i16 => MO::MOVrm16,
             MVType::i32 => MO::MOVrm32,
+            MVType::i64 => MO::MOVrm64,
             _ => todo!(),
         };
         node_gen!((MI.(opcode) [AddressAlignOff m["g"], m["align"], (IR.RegClass.(Type::i64) m["off"])]))
@@ -71,7 +75,9 @@ fn run_on_function(func: &mut DAGFunction) {
         .generate(|m, c| {
             node_gen!((MI.(match c.arena[m["load"]].as_ir().mvty {
                 MVType::i8 => MO::MOVrm8,
+                MVType::i16 => MO::MOVrm16,
                 MVType::i32 => MO::MOVrm32,
+                MVType::i64 => MO::MOVrm64,
                 _ => todo!(),
             }) [AddressOff m["g"], m["off"]]))
         });
@@ -83,8 +89,10 @@ fn run_on_function(func: &mut DAGFunction) {
     .generate(|m, c| {
         node_gen!((MI.(match c.arena[m["load"]].as_ir().mvty {
             MVType::i8 => MO::MOVrm8,
+            MVType::i16 => MO::MOVrm16,
             MVType::i32 => MO::MOVrm32,
             MVType::i64 => MO::MOVrm64,
+            MVType::f32 => MO::MOVSSrm,
             MVType::f64 => MO::MOVSDrm,
             _ => todo!(),
         }) [BaseOff m["base"], m["off"]]))
@@ -95,10 +103,13 @@ fn run_on_function(func: &mut DAGFunction) {
     ))
     .ty(Type::f64)
     .generate(|m, c| node_gen!((MI.MOVSDrm [BaseFiOff %rbp, m["slot"], m["off"]])));
+    let load7 = load(add(
+        fiaddr(any_slot().named("slot")),
+        any_imm32().named("off"),
+    ))
+    .ty(Type::f32)
+    .generate(|m, c| node_gen!((MI.MOVSSrm [BaseFiOff %rbp, m["slot"], m["off"]])));
 
-    let sext1 = sext(any_reg().named("arg").into())
-        .named("sext")
-        .generate(|m, c| node_gen!((IR.RegClass.(c.arena[m["sext"]].as_ir().ty) m["arg"])));
     let brcc: Pat = ir(IROpcode::Brcc)
         .named("brcc")
         .args(vec![
@@ -135,9 +146,13 @@ fn run_on_function(func: &mut DAGFunction) {
                 any_imm().named("r").into(),
                 any_block().named("dst").into(),
             ])
-            .generate(
-                |m, c| node_gen!((IR.FPBrcc m["cc"], m["l"], (MI.MOVSDrm64 m["r"]), m["dst"])),
-            ))
+            .generate(|m, c| {
+                let opcode = match c.arena[m["r"]].as_operand().as_imm() {
+                    ImmediateKind::F32(_) => MO::MOVSSrm32,
+                    _ => MO::MOVSDrm64,
+                };
+                node_gen!((IR.FPBrcc m["cc"], m["l"], (MI.(opcode) m["r"]), m["dst"]))
+            }))
     .into();
 
     #[rustfmt::skip]
@@ -146,7 +161,7 @@ fn run_on_function(func: &mut DAGFunction) {
             (fiaddr(any_slot().named("slot")) | gbladdr(any().named("g")) | reg_(RC::GR64).named("base")).named("lhs"),
             (mul(any().named("off"), any_imm32_power_of_2().named("align")) | any_imm32().named("off")).named("rhs"),
         ),
-        (any_imm32().named("imm") | any_imm_f64().named("imm") | reg_(RC::GR32).named("reg")).named("src"),
+        (any_imm32().named("imm") | any_imm_f32().named("imm") | any_imm_f64().named("imm") | reg_(RC::GR32).named("reg")).named("src"),
     ).generate(|m, c| {
         let mem = match c.arena[m["lhs"]] {
             Node::IR(IRNode { opcode: IROpcode::FIAddr, ..}) => {
@@ -177,6 +192,10 @@ fn run_on_function(func: &mut DAGFunction) {
                 let src = c.arena.alloc(MINode::new(MO::MOVSDrm64).args(vec![m["imm"]]).reg_class(RC::XMM).into());
                 c.arena.alloc(MINode::new(MO::MOVSDmr).args(vec![mem, src]).into())
             }
+            Node::Operand(OperandNode::Imm(ImmediateKind::F32(_))) => {
+                let src = c.arena.alloc(MINode::new(MO::MOVSSrm32).args(vec![m["imm"]]).reg_class(RC::FR32).into());
+                c.arena.alloc(MINode::new(MO::MOVSSmr).args(vec![mem, src]).into())
+            }
             _ => unreachable!()
         }
     }).into();
@@ -255,6 +274,7 @@ fn run_on_function(func: &mut DAGFunction) {
             | any_imm_f64().named("imm")
             | reg_(RC::GR32).named("reg")
             | reg_(RC::GR64).named("reg")
+            | reg_(RC::FR32).named("reg")
             | reg_(RC::XMM).named("reg"))
         .named("src"),
     )
@@ -274,6 +294,16 @@ fn run_on_function(func: &mut DAGFunction) {
             Node::Operand(OperandNode::Reg(r)) if c.regs.arena_ref()[r].reg_class == RC::GR64 => {
                 MINode::new(MO::MOVmr64)
             }
+            Node::IR(IRNode {
+                mvty: MVType::f32, ..
+            })
+            | Node::MI(MINode {
+                reg_class: Some(RC::FR32),
+                ..
+            }) => MINode::new(MO::MOVSSmr),
+            Node::Operand(OperandNode::Reg(r)) if c.regs.arena_ref()[r].reg_class == RC::FR32 => {
+                MINode::new(MO::MOVSSmr)
+            }
             Node::IR(IRNode {
                 mvty: MVType::f64, ..
             })
@@ -305,9 +335,9 @@ fn run_on_function(func: &mut DAGFunction) {
         load1,
         load2,
         load3,
+        load7,
         store2,
         store1,
-        sext1,
         brcc,
         fpbrcc,
         store3,
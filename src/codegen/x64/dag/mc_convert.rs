@@ -1,18 +1,19 @@
 // use super::{node, node::*};
-use crate::codegen::arch::dag::node::MemKind;
-use crate::codegen::arch::frame_object::FrameIndexInfo;
-use crate::codegen::arch::machine::abi::SystemV;
-use crate::codegen::arch::machine::inst::*;
-use crate::codegen::arch::machine::register::*;
-pub use crate::codegen::common::dag::mc_convert::ScheduleContext;
-use crate::codegen::common::dag::{
+pub use crate::codegen::x64::common::dag::mc_convert::ScheduleContext;
+use crate::codegen::x64::common::dag::{
     node,
     node::{CondKind, IRNode, IROpcode, ImmediateKind, MINode, Node, NodeId, OperandNode},
 };
-use crate::codegen::common::machine::calling_conv::{ArgumentRegisterOrder, CallingConv};
-use crate::codegen::common::machine::inst::*;
-use crate::codegen::common::machine::inst_def::DefOrUseReg;
-use crate::codegen::common::types::MVType;
+use crate::codegen::x64::common::machine::calling_conv::{ArgumentRegisterOrder, CallingConv};
+use crate::codegen::x64::common::machine::inst::*;
+use crate::codegen::x64::common::machine::inst_def::{DefOrUseReg, TargetImmediate};
+use crate::codegen::x64::common::types::MVType;
+use crate::codegen::x64::dag::node::MemKind;
+use crate::codegen::x64::frame_object::FrameIndexInfo;
+use crate::codegen::x64::machine::abi::SystemV;
+use crate::codegen::x64::machine::inst::*;
+use crate::codegen::x64::machine::inst_def::SelectOperand;
+use crate::codegen::x64::machine::register::*;
 use crate::ir::types::Type;
 
 impl<'a> ScheduleContext<'a> {
@@ -70,20 +71,7 @@ impl<'a> ScheduleContext<'a> {
                 args,
                 ..
             }) => {
-                let lhs = self.normal_arg(args[1]);
-                let rhs = self.normal_arg(args[2]);
-
-                self.append_inst(MachineInst::new_simple(
-                    if lhs.is_register() && rhs.is_constant() {
-                        MachineOpcode::CMPri
-                    } else if lhs.is_register() && rhs.is_register() {
-                        MachineOpcode::CMPrr
-                    } else {
-                        unreachable!()
-                    },
-                    vec![lhs, rhs],
-                    self.block_id,
-                ));
+                self.append_cmp(args[1], args[2]);
 
                 let r = RegisterOperand::new(self.func.regs.new_virt_reg(RegisterClassKind::GR8));
                 self.append_inst(
@@ -95,6 +83,10 @@ impl<'a> ScheduleContext<'a> {
                             CondKind::Lt => MachineOpcode::SETL,
                             CondKind::Ge => MachineOpcode::SETGE,
                             CondKind::Gt => MachineOpcode::SETG,
+                            CondKind::ULe => MachineOpcode::SETBE,
+                            CondKind::ULt => MachineOpcode::SETB,
+                            CondKind::UGe => MachineOpcode::SETAE,
+                            CondKind::UGt => MachineOpcode::SETA,
                             _ => todo!(),
                         },
                         vec![],
@@ -117,20 +109,7 @@ impl<'a> ScheduleContext<'a> {
                 args,
                 ..
             }) => {
-                let lhs = self.normal_arg(args[1]);
-                let rhs = self.normal_arg(args[2]);
-
-                self.append_inst(MachineInst::new_simple(
-                    if lhs.is_register() && rhs.is_constant() {
-                        MachineOpcode::CMPri
-                    } else if lhs.is_register() && rhs.is_register() {
-                        MachineOpcode::CMPrr
-                    } else {
-                        unreachable!()
-                    },
-                    vec![lhs, rhs],
-                    self.block_id,
-                ));
+                self.append_cmp(args[1], args[2]);
 
                 self.append_inst(MachineInst::new_simple(
                     match self.func.node_arena[args[0]].as_operand().as_cc() {
@@ -140,6 +119,10 @@ impl<'a> ScheduleContext<'a> {
                         CondKind::Lt => MachineOpcode::JL,
                         CondKind::Ge => MachineOpcode::JGE,
                         CondKind::Gt => MachineOpcode::JG,
+                        CondKind::ULe => MachineOpcode::JBE,
+                        CondKind::ULt => MachineOpcode::JB,
+                        CondKind::UGe => MachineOpcode::JAE,
+                        CondKind::UGt => MachineOpcode::JA,
                         _ => unreachable!(),
                     },
                     vec![MachineOperand::Branch(
@@ -157,7 +140,10 @@ impl<'a> ScheduleContext<'a> {
                 let rhs = self.normal_arg(args[2]);
 
                 self.append_inst(MachineInst::new_simple(
-                    MachineOpcode::UCOMISDrr,
+                    match lhs.get_type(&self.func.regs) {
+                        Some(Type::f32) => MachineOpcode::UCOMISSrr,
+                        _ => MachineOpcode::UCOMISDrr,
+                    },
                     vec![lhs, rhs],
                     self.block_id,
                 ));
@@ -179,17 +165,58 @@ impl<'a> ScheduleContext<'a> {
                 ))
             }
             Node::IR(IRNode {
-                opcode: IROpcode::Div,
+                opcode: opcode @ IROpcode::Div,
                 args,
                 mvty,
                 ..
-            }) => self.convert_div(*mvty, args, false),
-            Node::IR(IRNode {
-                opcode: IROpcode::Rem,
+            })
+            | Node::IR(IRNode {
+                opcode: opcode @ IROpcode::Rem,
                 args,
                 mvty,
                 ..
-            }) => self.convert_div(*mvty, args, true),
+            })
+            | Node::IR(IRNode {
+                opcode: opcode @ IROpcode::UDiv,
+                args,
+                mvty,
+                ..
+            })
+            | Node::IR(IRNode {
+                opcode: opcode @ IROpcode::URem,
+                args,
+                mvty,
+                ..
+            }) => self.convert_div(*opcode, *mvty, args),
+            Node::IR(IRNode {
+                opcode: opcode @ IROpcode::Shl,
+                args,
+                ty,
+                ..
+            })
+            | Node::IR(IRNode {
+                opcode: opcode @ IROpcode::LShr,
+                args,
+                ty,
+                ..
+            })
+            | Node::IR(IRNode {
+                opcode: opcode @ IROpcode::AShr,
+                args,
+                ty,
+                ..
+            }) => self.convert_shift(*opcode, *ty, args),
+            Node::IR(IRNode {
+                opcode: IROpcode::Select,
+                args,
+                ty,
+                ..
+            }) => self.convert_select(*ty, args),
+            Node::IR(IRNode {
+                opcode: IROpcode::Switch,
+                args,
+                ..
+            }) => self.convert_switch(args),
             Node::IR(IRNode {
                 opcode: IROpcode::Call,
                 args,
@@ -228,57 +255,538 @@ impl<'a> ScheduleContext<'a> {
         inst_id
     }
 
-    fn convert_div(&mut self, mvty: MVType, args: &[NodeId], is_rem: bool) -> MachineInstId {
+    /// x64 has no 64-bit immediate operand except for `mov`, so an i64 right-hand side that
+    /// doesn't fit in 32 bits is materialized into a register.
+    fn append_cmp(&mut self, lhs: NodeId, rhs: NodeId) -> MachineInstId {
+        let lhs = self.normal_arg(lhs);
+        let mut rhs = self.normal_arg(rhs);
+        if let MachineOperand::Constant(MachineConstant::Int64(i)) = rhs {
+            if i as i32 as i64 == i {
+                rhs = MachineOperand::Constant(MachineConstant::Int32(i as i32));
+            } else {
+                let mov = MachineInst::new(
+                    &self.func.regs,
+                    MachineOpcode::MOVri64,
+                    vec![rhs],
+                    Some(RegisterClassKind::GR64),
+                    self.block_id,
+                );
+                rhs = MachineOperand::Register(mov.def[0]);
+                self.append_inst(mov);
+            }
+        }
+        self.append_inst(MachineInst::new_simple(
+            if lhs.is_register() && rhs.is_constant() {
+                MachineOpcode::CMPri
+            } else if lhs.is_register() && rhs.is_register() {
+                MachineOpcode::CMPrr
+            } else {
+                unreachable!()
+            },
+            vec![lhs, rhs],
+            self.block_id,
+        ))
+    }
+
+    /// Signed division sign-extends RAX/EAX into RDX/EDX while unsigned one clears it.
+    /// i8 and i16 operands are extended to 32 bits first.
+    fn convert_div(&mut self, opcode: IROpcode, mvty: MVType, args: &[NodeId]) -> MachineInstId {
+        let is_rem = matches!(opcode, IROpcode::Rem | IROpcode::URem);
+        let is_unsigned = matches!(opcode, IROpcode::UDiv | IROpcode::URem);
         let regs = match mvty {
-            MVType::i8 => to_phys!(GR32::EAX, GR32::EDX),
-            MVType::i32 => to_phys!(GR32::EAX, GR32::EDX),
+            MVType::i8 | MVType::i16 | MVType::i32 => to_phys!(GR32::EAX, GR32::EDX),
+            MVType::i64 => to_phys!(GR64::RAX, GR64::RDX),
             _ => todo!(),
         };
+        let rc = regs[0].reg_class();
         let (eax, edx) = (
             RegisterOperand::new(self.func.regs.get_phys_reg(regs[0])),
             RegisterOperand::new(self.func.regs.get_phys_reg(regs[1])),
         );
         let (mut lhs, mut rhs) = (self.normal_arg(args[0]), self.normal_arg(args[1]));
-        if mvty == MVType::i8 {
-            if let MachineOperand::Register(r) = &mut lhs {
-                *r = r.sub_super(Some(RegisterClassKind::GR32))
-            }
-            if let MachineOperand::Register(r) = &mut rhs {
-                *r = r.sub_super(Some(RegisterClassKind::GR32))
-            }
+        if matches!(mvty, MVType::i8 | MVType::i16) {
+            lhs = self.extend_to_32(lhs, is_unsigned);
+            rhs = self.extend_to_32(rhs, is_unsigned);
         }
         self.append_inst(
-            MachineInst::new_simple(
-                mov_rx(regs[0].reg_class(), &lhs).unwrap(),
-                vec![lhs],
-                self.block_id,
-            )
-            .with_def(vec![eax]),
+            MachineInst::new_simple(mov_rx(rc, &lhs).unwrap(), vec![lhs], self.block_id)
+                .with_def(vec![eax]),
         );
-        self.append_inst(
-            MachineInst::new_simple(MachineOpcode::CDQ, vec![], self.block_id)
+        if is_unsigned {
+            self.append_inst(
+                MachineInst::new_simple(
+                    MachineOpcode::MOVri32,
+                    vec![MachineOperand::Constant(MachineConstant::Int32(0))],
+                    self.block_id,
+                )
+                .with_def(vec![edx]),
+            );
+        } else {
+            self.append_inst(
+                MachineInst::new_simple(
+                    if mvty == MVType::i64 {
+                        MachineOpcode::CQO
+                    } else {
+                        MachineOpcode::CDQ
+                    },
+                    vec![],
+                    self.block_id,
+                )
                 .with_imp_defs(vec![eax, edx])
                 .with_imp_use(eax),
-        );
+            );
+        }
         let mov = MachineInst::new(
             &self.func.regs,
-            mov_rx(regs[0].reg_class(), &rhs).unwrap(),
+            mov_rx(rc, &rhs).unwrap(),
             vec![rhs],
-            Some(regs[0].reg_class()),
+            Some(rc),
             self.block_id,
         );
         let rhs = MachineOperand::Register(mov.def[0]);
         self.append_inst(mov);
         self.append_inst(
-            MachineInst::new_simple(MachineOpcode::IDIV, vec![rhs], self.block_id)
-                .with_imp_defs(vec![eax, edx])
-                .with_imp_uses(vec![eax, edx]),
+            MachineInst::new_simple(
+                if is_unsigned {
+                    MachineOpcode::DIV
+                } else {
+                    MachineOpcode::IDIV
+                },
+                vec![rhs],
+                self.block_id,
+            )
+            .with_imp_defs(vec![eax, edx])
+            .with_imp_uses(vec![eax, edx]),
         );
         let copy = MachineInst::new(
             &self.func.regs,
             MachineOpcode::Copy,
             vec![MachineOperand::Register(if is_rem { edx } else { eax })],
-            Some(regs[0].reg_class()),
+            Some(rc),
+            self.block_id,
+        );
+        let res = copy.def[0];
+        let copy = self.append_inst(copy);
+        match mvty {
+            MVType::i8 => self.truncate_from_32(res, RegisterClassKind::GR8),
+            MVType::i16 => self.truncate_from_32(res, RegisterClassKind::GR16),
+            _ => copy,
+        }
+    }
+
+    /// Shifts by a register are only encodable with the amount in CL, so the amount is moved
+    /// into ECX first. Immediate left-hand sides are materialized as well, and i8/i16 ones are
+    /// shifted as 32-bit values.
+    fn convert_shift(&mut self, opcode: IROpcode, ty: Type, args: &[NodeId]) -> MachineInstId {
+        let rc = ty2rc(&ty).unwrap();
+        let (mut lhs, rhs) = (self.normal_arg(args[0]), self.normal_arg(args[1]));
+        if !lhs.is_register() {
+            let mov = MachineInst::new(
+                &self.func.regs,
+                mov_rx(rc, &lhs).unwrap(),
+                vec![lhs],
+                Some(rc),
+                self.block_id,
+            );
+            lhs = MachineOperand::Register(mov.def[0]);
+            self.append_inst(mov);
+        }
+
+        let narrow = matches!(rc, RegisterClassKind::GR8 | RegisterClassKind::GR16);
+        let shift_rc = if narrow { RegisterClassKind::GR32 } else { rc };
+        let shift_ty = if shift_rc == RegisterClassKind::GR64 {
+            MVType::i64
+        } else {
+            MVType::i32
+        };
+        if narrow {
+            lhs = match opcode {
+                IROpcode::Shl => MachineOperand::Register(
+                    lhs.as_register().sub_super(Some(RegisterClassKind::GR32)),
+                ),
+                _ => self.extend_to_32(lhs, opcode == IROpcode::LShr),
+            };
+        }
+
+        let (opcode, rhs) = match rhs {
+            MachineOperand::Constant(c) => {
+                let amount = match c {
+                    MachineConstant::Int8(i) => i as i64,
+                    MachineConstant::Int16(i) => i as i64,
+                    MachineConstant::Int32(i) => i as i64,
+                    MachineConstant::Int64(i) => i,
+                    MachineConstant::F32(_) | MachineConstant::F64(_) => unreachable!(),
+                };
+                let ops = [
+                    SelectOperand::Reg(shift_rc),
+                    SelectOperand::Imm(TargetImmediate::I8),
+                ];
+                (
+                    MachineOpcode::select(opcode, shift_ty, &ops).unwrap(),
+                    MachineOperand::Constant(MachineConstant::Int8(amount as i8)),
+                )
+            }
+            MachineOperand::Register(r) => {
+                let ecx = RegisterOperand::new(self.func.regs.get_phys_reg(GR32::ECX));
+                let rhs = MachineOperand::Register(r.sub_super(Some(RegisterClassKind::GR32)));
+                self.append_inst(
+                    MachineInst::new_simple(MachineOpcode::MOVrr32, vec![rhs], self.block_id)
+                        .with_def(vec![ecx]),
+                );
+                let ops = [
+                    SelectOperand::Reg(shift_rc),
+                    SelectOperand::Reg(RegisterClassKind::GR8),
+                ];
+                (
+                    MachineOpcode::select(opcode, shift_ty, &ops).unwrap(),
+                    MachineOperand::Register(RegisterOperand::new(
+                        self.func.regs.get_phys_reg(GR8::CL),
+                    )),
+                )
+            }
+            _ => unreachable!(),
+        };
+
+        let mut inst = MachineInst::new(
+            &self.func.regs,
+            opcode,
+            vec![lhs, rhs],
+            Some(shift_rc),
+            self.block_id,
+        );
+        inst.tie_regs(inst.def[0], *inst.operand[0].as_register());
+        let res = inst.def[0];
+        let inst = self.append_inst(inst);
+        if narrow {
+            self.truncate_from_32(res, rc)
+        } else {
+            inst
+        }
+    }
+
+    /// Lowers select into CMOVcc. The comparison feeding the condition is fused when possible,
+    /// otherwise the i1 condition itself is tested. There is no 8-bit CMOVcc, so i1/i8/i16
+    /// selects are done on 32-bit registers. Floating-point selects are expanded into branches
+    /// before instruction selection (see `ExpandSelect`).
+    fn convert_select(&mut self, ty: Type, args: &[NodeId]) -> MachineInstId {
+        let rc = ty2rc(&ty).unwrap();
+        let narrow = matches!(rc, RegisterClassKind::GR8 | RegisterClassKind::GR16);
+        let cmov_rc = if narrow { RegisterClassKind::GR32 } else { rc };
+
+        let in_reg = |ctx: &mut Self, arg: NodeId| -> MachineOperand {
+            let mut op = ctx.normal_arg(arg);
+            if narrow {
+                op = ctx.extend_to_32(op, true);
+            }
+            if op.is_register() {
+                return op;
+            }
+            let mov = MachineInst::new(
+                &ctx.func.regs,
+                mov_rx(cmov_rc, &op).unwrap(),
+                vec![op],
+                Some(cmov_rc),
+                ctx.block_id,
+            );
+            let r = mov.def[0];
+            ctx.append_inst(mov);
+            MachineOperand::Register(r)
+        };
+        let then_ = in_reg(self, args[1]);
+        let else_ = in_reg(self, args[2]);
+
+        let cond = match &self.func.node_arena[args[0]] {
+            Node::IR(IRNode {
+                opcode: IROpcode::Setcc,
+                args: cmp,
+                ..
+            }) => {
+                let (cc, lhs, rhs) = (cmp[0], cmp[1], cmp[2]);
+                self.append_cmp(lhs, rhs);
+                *self.func.node_arena[cc].as_operand().as_cc()
+            }
+            Node::IR(IRNode {
+                opcode: IROpcode::FCmp,
+                args: cmp,
+                ..
+            }) => {
+                let (cc, lhs, rhs) = (cmp[0], cmp[1], cmp[2]);
+                let mut ops = vec![];
+                for arg in &[lhs, rhs] {
+                    let op = self.normal_arg(*arg);
+                    ops.push(if op.is_register() {
+                        op
+                    } else {
+                        let rc = ty2rc(&op.get_type(&self.func.regs).unwrap()).unwrap();
+                        let mov = MachineInst::new(
+                            &self.func.regs,
+                            mov_rx(rc, &op).unwrap(),
+                            vec![op],
+                            Some(rc),
+                            self.block_id,
+                        );
+                        let r = mov.def[0];
+                        self.append_inst(mov);
+                        MachineOperand::Register(r)
+                    });
+                }
+                self.append_inst(MachineInst::new_simple(
+                    match ops[0].get_type(&self.func.regs) {
+                        Some(Type::f32) => MachineOpcode::UCOMISSrr,
+                        _ => MachineOpcode::UCOMISDrr,
+                    },
+                    ops,
+                    self.block_id,
+                ));
+                match self.func.node_arena[cc].as_operand().as_cc() {
+                    CondKind::UEq => CondKind::Eq,
+                    CondKind::UNe => CondKind::Ne,
+                    cc => *cc,
+                }
+            }
+            _ => {
+                let c = self.normal_arg(args[0]);
+                self.append_inst(MachineInst::new_simple(
+                    MachineOpcode::CMPri,
+                    vec![c, MachineOperand::Constant(MachineConstant::Int8(0))],
+                    self.block_id,
+                ));
+                CondKind::Ne
+            }
+        };
+
+        let opcode = match cond {
+            CondKind::Eq => MachineOpcode::CMOVErr,
+            CondKind::Ne => MachineOpcode::CMOVNErr,
+            CondKind::Le => MachineOpcode::CMOVLErr,
+            CondKind::Lt => MachineOpcode::CMOVLrr,
+            CondKind::Ge => MachineOpcode::CMOVGErr,
+            CondKind::Gt => MachineOpcode::CMOVGrr,
+            CondKind::ULe => MachineOpcode::CMOVBErr,
+            CondKind::ULt => MachineOpcode::CMOVBrr,
+            CondKind::UGe => MachineOpcode::CMOVAErr,
+            CondKind::UGt => MachineOpcode::CMOVArr,
+            _ => unreachable!(),
+        };
+        let mut inst = MachineInst::new(
+            &self.func.regs,
+            opcode,
+            vec![else_, then_],
+            Some(cmov_rc),
+            self.block_id,
+        );
+        inst.tie_regs(inst.def[0], *inst.operand[0].as_register());
+        let res = inst.def[0];
+        let inst = self.append_inst(inst);
+        if narrow {
+            self.truncate_from_32(res, rc)
+        } else {
+            inst
+        }
+    }
+
+    /// Lowers a switch left by `LowerSwitch` into an indirect jump through a table of 32-bit
+    /// offsets relative to the table itself. The default destination is placed right after the
+    /// cases and an out-of-range index is clamped to it with CMOVA, so the block still ends with
+    /// a single terminator.
+    fn convert_switch(&mut self, args: &[NodeId]) -> MachineInstId {
+        let default = self.bb_map[self.func.node_arena[args[1]].as_operand().as_block()];
+        let mut cases = vec![];
+        for pair in args[2..].chunks(2) {
+            let val = self.func.node_arena[pair[0]].as_operand().as_imm().to_i64();
+            let dst = self.bb_map[self.func.node_arena[pair[1]].as_operand().as_block()];
+            cases.push((val.unwrap(), dst));
+        }
+        let low = cases.iter().map(|(val, _)| *val).min().unwrap();
+        let high = cases.iter().map(|(val, _)| *val).max().unwrap();
+        let num = (high - low + 1) as usize;
+        let mut table = vec![default; num + 1];
+        for (val, dst) in cases {
+            table[(val - low) as usize] = dst;
+        }
+        let table = self.const_data.alloc_jump_table(table);
+
+        let mut cond = self.normal_arg(args[0]);
+        let is_64 = matches!(cond.get_type(&self.func.regs), Some(Type::i64));
+        let rc = if is_64 {
+            RegisterClassKind::GR64
+        } else {
+            RegisterClassKind::GR32
+        };
+        cond = self.extend_to_32(cond, false);
+        if let MachineOperand::Constant(c) = cond {
+            let mov = MachineInst::new(
+                &self.func.regs,
+                if is_64 {
+                    MachineOpcode::MOVri64
+                } else {
+                    MachineOpcode::MOVri32
+                },
+                vec![MachineOperand::Constant(c)],
+                Some(rc),
+                self.block_id,
+            );
+            cond = MachineOperand::Register(mov.def[0]);
+            self.append_inst(mov);
+        }
+
+        let mut idx = *cond.as_register();
+        if low != 0 {
+            let (opcode, low) = if is_64 && low as i32 as i64 != low {
+                let mov = MachineInst::new(
+                    &self.func.regs,
+                    MachineOpcode::MOVri64,
+                    vec![MachineOperand::Constant(MachineConstant::Int64(low))],
+                    Some(rc),
+                    self.block_id,
+                );
+                let low = MachineOperand::Register(mov.def[0]);
+                self.append_inst(mov);
+                (MachineOpcode::SUBrr64, low)
+            } else if is_64 {
+                let low = MachineOperand::Constant(MachineConstant::Int32(low as i32));
+                (MachineOpcode::SUBr64i32, low)
+            } else {
+                let low = MachineOperand::Constant(MachineConstant::Int32(low as i32));
+                (MachineOpcode::SUBri32, low)
+            };
+            let mut sub = MachineInst::new(
+                &self.func.regs,
+                opcode,
+                vec![MachineOperand::Register(idx), low],
+                Some(rc),
+                self.block_id,
+            );
+            sub.tie_regs(sub.def[0], idx);
+            idx = sub.def[0];
+            self.append_inst(sub);
+        }
+
+        self.append_inst(MachineInst::new_simple(
+            MachineOpcode::CMPri,
+            vec![
+                MachineOperand::Register(idx),
+                MachineOperand::Constant(MachineConstant::Int32(num as i32)),
+            ],
+            self.block_id,
+        ));
+        let mov = MachineInst::new(
+            &self.func.regs,
+            if is_64 {
+                MachineOpcode::MOVri64
+            } else {
+                MachineOpcode::MOVri32
+            },
+            vec![MachineOperand::Constant(if is_64 {
+                MachineConstant::Int64(num as i64)
+            } else {
+                MachineConstant::Int32(num as i32)
+            })],
+            Some(rc),
+            self.block_id,
+        );
+        let num = mov.def[0];
+        self.append_inst(mov);
+        let mut cmov = MachineInst::new(
+            &self.func.regs,
+            MachineOpcode::CMOVArr,
+            vec![MachineOperand::Register(idx), MachineOperand::Register(num)],
+            Some(rc),
+            self.block_id,
+        );
+        cmov.tie_regs(cmov.def[0], idx);
+        // A 32-bit CMOV clears the upper half of the register even if no move occurs.
+        let idx = cmov.def[0].sub_super(Some(RegisterClassKind::GR64));
+        self.append_inst(cmov);
+
+        let lea = MachineInst::new(
+            &self.func.regs,
+            MachineOpcode::LEAr64m,
+            vec![MachineOperand::Mem(MachineMemOperand::Address(
+                AddressKind::Label(table),
+            ))],
+            Some(RegisterClassKind::GR64),
+            self.block_id,
+        );
+        let base = lea.def[0];
+        self.append_inst(lea);
+        let movsxd = MachineInst::new(
+            &self.func.regs,
+            MachineOpcode::MOVSXDr64m32,
+            vec![MachineOperand::Mem(MachineMemOperand::BaseAlignOff(
+                base, 4, idx,
+            ))],
+            Some(RegisterClassKind::GR64),
+            self.block_id,
+        );
+        let offset = movsxd.def[0];
+        self.append_inst(movsxd);
+        let mut add = MachineInst::new(
+            &self.func.regs,
+            MachineOpcode::ADDrr64,
+            vec![
+                MachineOperand::Register(offset),
+                MachineOperand::Register(base),
+            ],
+            Some(RegisterClassKind::GR64),
+            self.block_id,
+        );
+        add.tie_regs(add.def[0], offset);
+        let dst = add.def[0];
+        self.append_inst(add);
+
+        self.append_inst(MachineInst::new_simple(
+            MachineOpcode::JMPr,
+            vec![MachineOperand::Register(dst)],
+            self.block_id,
+        ))
+    }
+
+    /// Sign- or zero-extends an i8/i16 operand to 32 bits.
+    fn extend_to_32(&mut self, op: MachineOperand, unsigned: bool) -> MachineOperand {
+        let r = match op {
+            MachineOperand::Constant(MachineConstant::Int8(i)) if unsigned => i as u8 as i32,
+            MachineOperand::Constant(MachineConstant::Int8(i)) => i as i32,
+            MachineOperand::Constant(MachineConstant::Int16(i)) if unsigned => i as u16 as i32,
+            MachineOperand::Constant(MachineConstant::Int16(i)) => i as i32,
+            MachineOperand::Register(r) => {
+                let rc = r
+                    .sub_super
+                    .unwrap_or_else(|| self.func.regs.arena_ref()[r.id].reg_class);
+                let ext = if unsigned {
+                    IROpcode::Zext
+                } else {
+                    IROpcode::Sext
+                };
+                let opcode = match rc {
+                    RegisterClassKind::GR8 | RegisterClassKind::GR16 => {
+                        MachineOpcode::select(ext, MVType::i32, &[SelectOperand::Reg(rc)]).unwrap()
+                    }
+                    _ => return op,
+                };
+                let mov = MachineInst::new(
+                    &self.func.regs,
+                    opcode,
+                    vec![op],
+                    Some(RegisterClassKind::GR32),
+                    self.block_id,
+                );
+                let r = mov.def[0];
+                self.append_inst(mov);
+                return MachineOperand::Register(r);
+            }
+            _ => return op,
+        };
+        MachineOperand::Constant(MachineConstant::Int32(r))
+    }
+
+    /// Copies the low `rc` part of a 32-bit register into a register of class `rc`.
+    fn truncate_from_32(&mut self, r: RegisterOperand, rc: RegisterClassKind) -> MachineInstId {
+        let copy = MachineInst::new(
+            &self.func.regs,
+            MachineOpcode::Copy,
+            vec![MachineOperand::Register(r.sub_super(Some(rc)))],
+            Some(rc),
             self.block_id,
         );
         self.append_inst(copy)
@@ -333,7 +841,14 @@ impl<'a> ScheduleContext<'a> {
 
             if !matches!(
                 ty,
-                Type::i8 | Type::i32 | Type::i64 | Type::f64 | Type::Pointer(_) | Type::Array(_)
+                Type::i8
+                    | Type::i16
+                    | Type::i32
+                    | Type::i64
+                    | Type::f32
+                    | Type::f64
+                    | Type::Pointer(_)
+                    | Type::Array(_)
             ) {
                 unimplemented!()
             }
@@ -467,7 +982,9 @@ impl<'a> ScheduleContext<'a> {
                         RegisterClassKind::GR32 => MachineOpcode::MOVrm32,
                         RegisterClassKind::GR64 => MachineOpcode::MOVrm64,
                         RegisterClassKind::XMM => MachineOpcode::MOVSDrm,
-                        RegisterClassKind::GR8 => unimplemented!(),
+                        RegisterClassKind::GR8
+                        | RegisterClassKind::GR16
+                        | RegisterClassKind::FR32 => unimplemented!(),
                     },
                     vec![mem],
                     self.block_id,
@@ -533,8 +1050,10 @@ impl<'a> ScheduleContext<'a> {
                     sub_super.unwrap_or_else(|| self.func.regs.arena_ref()[id].reg_class)
                 }
                 MachineOperand::Constant(MachineConstant::Int8(_)) => RegisterClassKind::GR8,
+                MachineOperand::Constant(MachineConstant::Int16(_)) => RegisterClassKind::GR16,
                 MachineOperand::Constant(MachineConstant::Int32(_)) => RegisterClassKind::GR32,
                 MachineOperand::Constant(MachineConstant::Int64(_)) => RegisterClassKind::GR64,
+                MachineOperand::Constant(MachineConstant::F32(_)) => RegisterClassKind::FR32,
                 MachineOperand::Constant(MachineConstant::F64(_)) => RegisterClassKind::XMM,
                 _ => panic!(),
             };
@@ -557,9 +1076,18 @@ impl<'a> ScheduleContext<'a> {
             Node::Operand(OperandNode::Imm(ImmediateKind::Int8(i))) => {
                 MachineOperand::Constant(MachineConstant::Int8(*i))
             }
+            Node::Operand(OperandNode::Imm(ImmediateKind::Int16(i))) => {
+                MachineOperand::Constant(MachineConstant::Int16(*i))
+            }
             Node::Operand(OperandNode::Imm(ImmediateKind::Int32(i))) => {
                 MachineOperand::Constant(MachineConstant::Int32(*i))
             }
+            Node::Operand(OperandNode::Imm(ImmediateKind::Int64(i))) => {
+                MachineOperand::Constant(MachineConstant::Int64(*i))
+            }
+            Node::Operand(OperandNode::Imm(ImmediateKind::F32(f))) => {
+                MachineOperand::Constant(MachineConstant::F32(*f))
+            }
             Node::Operand(OperandNode::Imm(ImmediateKind::F64(f))) => {
                 MachineOperand::Constant(MachineConstant::F64(*f))
             }
@@ -627,11 +1155,9 @@ impl<'a> ScheduleContext<'a> {
                     self.normal_arg(args[1]).as_constant().as_i32(),
                 ))
             }
-            Node::Operand(OperandNode::Mem(MemKind::Address(arg))) => {
-                MachineOperand::Mem(MachineMemOperand::Address(AddressKind::Constant(
-                    *self.normal_arg(*arg).as_mem().as_address().as_const(),
-                )))
-            }
+            Node::Operand(OperandNode::Mem(MemKind::Address(arg))) => MachineOperand::Mem(
+                MachineMemOperand::Address(self.normal_arg(*arg).as_mem().as_address().clone()),
+            ),
             Node::Operand(OperandNode::Addr(node::AddressKind::Const(id))) => {
                 MachineOperand::Mem(MachineMemOperand::Address(AddressKind::Constant(*id)))
             }
@@ -664,6 +1190,11 @@ pub fn mov_rx(rc: RegisterClassKind, arg: &MachineOperand) -> Option<MachineOpco
         MachineOpcode::MOVri8,
         MachineOpcode::MOVrm8,
     ];
+    let mov16rx = [
+        MachineOpcode::MOVrr16,
+        MachineOpcode::MOVri16,
+        MachineOpcode::MOVrm16,
+    ];
     let mov32rx = [
         MachineOpcode::MOVrr32,
         MachineOpcode::MOVri32,
@@ -674,6 +1205,11 @@ pub fn mov_rx(rc: RegisterClassKind, arg: &MachineOperand) -> Option<MachineOpco
         MachineOpcode::MOVri64,
         MachineOpcode::MOVrm64,
     ];
+    let movssrx = [
+        MachineOpcode::MOVSSrr,
+        MachineOpcode::MOVSSrm32,
+        MachineOpcode::MOVSSrm,
+    ];
     let movsdrx = [
         MachineOpcode::MOVSDrr,
         MachineOpcode::MOVSDrm64,
@@ -688,42 +1224,29 @@ pub fn mov_rx(rc: RegisterClassKind, arg: &MachineOperand) -> Option<MachineOpco
     };
     match rc {
         RegisterClassKind::GR8 => Some(mov8rx[idx]),
+        RegisterClassKind::GR16 => Some(mov16rx[idx]),
         RegisterClassKind::GR32 => Some(mov32rx[idx]),
         RegisterClassKind::GR64 => Some(mov64rx[idx]),
+        RegisterClassKind::FR32 => Some(movssrx[idx]),
         RegisterClassKind::XMM => Some(movsdrx[idx]),
     }
 }
 
 pub fn mov_mx(regs: &RegistersInfo, arg: &MachineOperand) -> Option<MachineOpcode> {
-    // TODO: We'd better use another way to determine if arg is floating-point value or not
-    if arg.get_type(regs).unwrap() == Type::f64 {
-        return match arg {
-            MachineOperand::Register(_) => Some(MachineOpcode::MOVSDmr),
-            _ => None,
-        };
-    }
-
-    let mov32mx = [MachineOpcode::MOVmr32, MachineOpcode::MOVmi32];
-    let mov64mx = [MachineOpcode::MOVmr64, MachineOpcode::MOVmi64];
-    // let mov64rx = [
-    //     MachineOpcode::MOVrr64,
-    //     MachineOpcode::MOVri64,
-    //     MachineOpcode::MOVrm64,
-    // ];
-    let (bit, n) = match arg {
-        MachineOperand::Register(r) => {
-            if let Some(sub_super) = r.sub_super {
-                (sub_super.size_in_bits(), 0)
-            } else {
-                (regs.arena_ref()[r.id].reg_class.size_in_bits(), 0)
-            }
-        }
-        MachineOperand::Constant(c) => (c.size_in_bits(), 1),
+    let src = match arg {
+        MachineOperand::Register(r) => SelectOperand::Reg(
+            r.sub_super
+                .unwrap_or_else(|| regs.arena_ref()[r.id].reg_class),
+        ),
+        MachineOperand::Constant(c) => SelectOperand::Imm(match c {
+            MachineConstant::Int8(_) => TargetImmediate::I8,
+            MachineConstant::Int16(_) => TargetImmediate::I16,
+            MachineConstant::Int32(_) => TargetImmediate::I32,
+            MachineConstant::Int64(_) => TargetImmediate::I64,
+            MachineConstant::F32(_) => TargetImmediate::F32,
+            MachineConstant::F64(_) => TargetImmediate::F64,
+        }),
         _ => return None, // TODO: Support Address?
     };
-    match bit {
-        32 => Some(mov32mx[n]),
-        64 => Some(mov64mx[n]),
-        _ => None,
-    }
+    MachineOpcode::select(IROpcode::Store, MVType::Void, &[SelectOperand::Mem, src])
 }
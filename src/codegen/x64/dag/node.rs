@@ -1,5 +1,5 @@
-pub use crate::codegen::common::dag::node::*;
-use crate::codegen::common::dag::node::NodeId;
+use crate::codegen::x64::common::dag::node::NodeId;
+pub use crate::codegen::x64::common::dag::node::*;
 use std::fmt;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -1,9 +1,9 @@
-use crate::codegen::arch::asm::assembler::{mod_rm, Mod};
-use crate::codegen::arch::machine::register::RegisterClassKind;
-use crate::codegen::common::asm::assembler::{FunctionAssembler, InstructionStream, Labels};
-use crate::codegen::common::machine::{function::MachineFunctionId, module::MachineModule};
+use crate::codegen::x64::asm::assembler::{mod_rm, Mod};
+use crate::codegen::x64::common::asm::assembler::{FunctionAssembler, InstructionStream, Labels};
+use crate::codegen::x64::common::machine::{function::MachineFunctionId, module::MachineModule};
+use crate::codegen::x64::machine::register::RegisterClassKind;
 use crate::codegen::{
-    arch::machine::abi::SystemV, common::machine::calling_conv::ArgumentRegisterOrder,
+    x64::common::machine::calling_conv::ArgumentRegisterOrder, x64::machine::abi::SystemV,
 };
 use crate::ir::types::Type;
 use mmap::{MapOption, MemoryMap};
@@ -1,3 +1,5 @@
+#[path = "../../common/exec/mod.rs"]
+pub mod common;
 pub mod executor;
 pub mod jit;
 
@@ -1,10 +1,12 @@
 use super::exec::roundup;
 use super::machine::register::{ty2rc, GR64};
-use crate::codegen::arch::machine::abi::SystemV;
-use crate::codegen::common::machine::calling_conv::CallingConv;
-use crate::codegen::common::machine::function::MachineFunction;
-use crate::codegen::common::machine::register::TargetRegisterTrait;
-pub use crate::codegen::common::machine::{calling_conv::ArgumentRegisterOrder, frame_object::*};
+use crate::codegen::x64::common::machine::calling_conv::CallingConv;
+use crate::codegen::x64::common::machine::function::MachineFunction;
+use crate::codegen::x64::common::machine::register::TargetRegisterTrait;
+pub use crate::codegen::x64::common::machine::{
+    calling_conv::ArgumentRegisterOrder, frame_object::*,
+};
+use crate::codegen::x64::machine::abi::SystemV;
 use crate::ir::types::*;
 use rustc_hash::{FxHashMap, FxHashSet};
 
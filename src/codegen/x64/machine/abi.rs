@@ -1,13 +1,15 @@
 use crate::{
-    codegen::{arch::machine::register::*, common::machine::calling_conv::CallingConv},
+    codegen::{x64::common::machine::calling_conv::CallingConv, x64::machine::register::*},
     ir::types::{StructType, Type},
 };
 
 #[derive(Clone)]
 pub struct SystemV {
     gr8: Vec<PhysReg>,
+    gr16: Vec<PhysReg>,
     gr32: Vec<PhysReg>,
     gr64: Vec<PhysReg>,
+    fr32: Vec<PhysReg>,
     xmm: Vec<PhysReg>,
 }
 
@@ -16,8 +18,10 @@ impl SystemV {
     pub fn new() -> Self {
         Self {
             gr8:  to_phys![GR8::DIL,  GR8::SIL,  GR8::DL,   GR8::CL,   GR8::R8B,  GR8::R9B ],
+            gr16: to_phys![GR16::DI,  GR16::SI,  GR16::DX,  GR16::CX,  GR16::R8W, GR16::R9W],
             gr32: to_phys![GR32::EDI, GR32::ESI, GR32::EDX, GR32::ECX, GR32::R8D, GR32::R9D],
             gr64: to_phys![GR64::RDI, GR64::RSI, GR64::RDX, GR64::RCX, GR64::R8,  GR64::R9 ],
+            fr32: to_phys![FR32::XMM0, FR32::XMM1, FR32::XMM2, FR32::XMM3, FR32::XMM4, FR32::XMM5, FR32::XMM6, FR32::XMM7],
             xmm:  to_phys![XMM::XMM0, XMM::XMM1, XMM::XMM2, XMM::XMM3, XMM::XMM4, XMM::XMM5, XMM::XMM6, XMM::XMM7],
         }
     }
@@ -27,8 +31,10 @@ impl CallingConv for SystemV {
     fn get_nth_arg_reg(&self, rc: RegisterClassKind, nth: usize) -> Option<PhysReg> {
         match rc {
             RegisterClassKind::GR8 => self.gr8.get(nth),
+            RegisterClassKind::GR16 => self.gr16.get(nth),
             RegisterClassKind::GR32 => self.gr32.get(nth),
             RegisterClassKind::GR64 => self.gr64.get(nth),
+            RegisterClassKind::FR32 => self.fr32.get(nth),
             RegisterClassKind::XMM => self.xmm.get(nth),
         }
         .map_or(None, |r| Some(*r))
@@ -1,7 +1,7 @@
 // use super::super::machine::register::RegisterId;
 use super::frame_object::*;
 pub use super::inst_def::TargetOpcode;
-pub use crate::codegen::common::machine::{basic_block::MachineBasicBlockId, inst::*};
+pub use crate::codegen::x64::common::machine::{basic_block::MachineBasicBlockId, inst::*};
 use crate::ir::types::Type;
 
 #[derive(Debug, Clone)]
@@ -27,7 +27,9 @@ impl MachineOpcode {
                 | MachineOpcode::MOVrr64
                 | MachineOpcode::Copy
                 | MachineOpcode::MOVSDrr
+                | MachineOpcode::MOVSSrr
                 | MachineOpcode::MOVrr8
+                | MachineOpcode::MOVrr16
         )
     }
 
@@ -37,6 +39,7 @@ impl MachineOpcode {
             MachineOpcode::Ret
                 | MachineOpcode::RET
                 | MachineOpcode::JMP
+                | MachineOpcode::JMPr
                 | MachineOpcode::BrCond
                 | MachineOpcode::JE
                 | MachineOpcode::JNE
@@ -1,731 +1,1182 @@
-use crate::codegen::arch::machine::register::*;
-use crate::codegen::common::machine::inst_def::*;
+use crate::codegen::x64::common::{dag::node::IROpcode, machine::inst_def::*, types::MVType};
+use crate::codegen::x64::machine::register::*;
+use defs::instructions;
 
-#[allow(non_upper_case_globals)]
-mod inst {
-    use super::*;
-
-    // TODO: need macro to describe the followings
-    lazy_static! {
-        pub static ref MOVSDrm64: TargetInstDef = {
-            TargetInstDef::new("movsd", TargetOpcode::MOVSDrm64)
-                .set_uses(vec![TargetOperand::Mem])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::XMM)])
-        };
-        pub static ref MOVSDrr: TargetInstDef = {
-            TargetInstDef::new("movsd", TargetOpcode::MOVSDrr)
-                .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(
-                    RegisterClassKind::XMM,
-                ))])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::XMM)])
-        };
-        pub static ref MOVSDrm: TargetInstDef = {
-            TargetInstDef::new("movsd",TargetOpcode::MOVSDrm)
-                .set_uses(vec![TargetOperand::Mem])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::XMM)])
-        };
-        pub static ref MOVSDmr: TargetInstDef = {
-            TargetInstDef::new("movsd",TargetOpcode::MOVSDmr).set_uses(vec![
-                TargetOperand::Mem,
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::XMM)),
-            ])
-        };
-        pub static ref MOVSXr32r8: TargetInstDef = {
-            TargetInstDef::new("movsx", TargetOpcode::MOVSXr32r8)
-                .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR8))])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)])
-        };
-        pub static ref MOVSXDr64m32: TargetInstDef = {
-            TargetInstDef::new("movsxd", TargetOpcode::MOVSXDr64m32)
-                .set_uses(vec![TargetOperand::FrameIndex])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)])
-        };
-        pub static ref MOVSXDr64r32: TargetInstDef = {
-            TargetInstDef::new("movsxd", TargetOpcode::MOVSXDr64r32)
-                .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)])
-        };
-        pub static ref MOVZXr32r8: TargetInstDef = {
-            TargetInstDef::new("movzx", TargetOpcode::MOVZXr32r8)
-                .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR8))])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)])
-        };
-        pub static ref LEAr64m: TargetInstDef = {
-            TargetInstDef::new("lea", TargetOpcode::LEAr64m)
-                .set_uses(vec![TargetOperand::Mem])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)])
-        };
-        pub static ref ADDrr8: TargetInstDef = {
-            TargetInstDef::new("add", TargetOpcode::ADDrr8)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR8)),
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR8)),
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR8)])
-                .add_tie(DefOrUseReg::Def(0), DefOrUseReg::Use(0))
-        };
-        pub static ref ADDri8: TargetInstDef = {
-            TargetInstDef::new("add", TargetOpcode::ADDri8)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR8)),
-                    TargetOperand::Immediate(TargetImmediate::I8)
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR8)])
-                .add_tie(DefOrUseReg::Def(0), DefOrUseReg::Use(0))
-        };
-        pub static ref ADDrr32: TargetInstDef = {
-            TargetInstDef::new("add", TargetOpcode::ADDrr32)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)])
-                .add_tie(DefOrUseReg::Def(0), DefOrUseReg::Use(0))
-        };
-        pub static ref ADDmr32: TargetInstDef = {
-            TargetInstDef::new("add", TargetOpcode::ADDmr32)
-                .set_uses(vec![
-                    TargetOperand::Mem,
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)])
-        };
-        pub static ref ADDmi32: TargetInstDef = {
-            TargetInstDef::new("add", TargetOpcode::ADDmi32)
-                .set_uses(vec![
-                    TargetOperand::Mem,
-                    TargetOperand::Immediate(TargetImmediate::I32)
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)])
-        };
-        pub static ref ADDrr64: TargetInstDef = {
-            TargetInstDef::new("add", TargetOpcode::ADDrr64)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)])
-                .add_tie(DefOrUseReg::Def(0), DefOrUseReg::Use(0))
-        };
-        pub static ref ADDri32: TargetInstDef = {
-            TargetInstDef::new("add", TargetOpcode::ADDri32)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
-                    TargetOperand::Immediate(TargetImmediate::I32),
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)])
-                .add_tie(DefOrUseReg::Def(0), DefOrUseReg::Use(0))
-        };
-        pub static ref ADDr64i32: TargetInstDef = {
-            TargetInstDef::new("add", TargetOpcode::ADDr64i32)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
-                    TargetOperand::Immediate(TargetImmediate::I32),
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)])
-                .add_tie(DefOrUseReg::Def(0), DefOrUseReg::Use(0))
-        };
-        pub static ref ADDSDrr: TargetInstDef = {
-            TargetInstDef::new("addsd", TargetOpcode::ADDSDrr)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::XMM)),
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::XMM)),
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::XMM)])
-                .add_tie(DefOrUseReg::Def(0), DefOrUseReg::Use(0))
-        };
-        pub static ref ADDSDrm: TargetInstDef = {
-            TargetInstDef::new("addsd", TargetOpcode::ADDSDrm)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::XMM)),
-                    TargetOperand::Mem,
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::XMM)])
-                .add_tie(DefOrUseReg::Def(0), DefOrUseReg::Use(0))
-        };
-        pub static ref SUBrr8: TargetInstDef = {
-            TargetInstDef::new("sub", TargetOpcode::SUBrr8)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR8)),
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR8)),
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR8)])
-                .add_tie(DefOrUseReg::Def(0), DefOrUseReg::Use(0))
-        };
-        pub static ref SUBri8: TargetInstDef = {
-            TargetInstDef::new("sub", TargetOpcode::SUBri8)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR8)),
-                    TargetOperand::Immediate(TargetImmediate::I8),
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR8)])
-                .add_tie(DefOrUseReg::Def(0), DefOrUseReg::Use(0))
-        };
-        pub static ref SUBrr32: TargetInstDef = {
-            TargetInstDef::new("sub", TargetOpcode::SUBrr32)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)])
-                .add_tie(DefOrUseReg::Def(0), DefOrUseReg::Use(0))
-        };
-        pub static ref SUBri32: TargetInstDef = {
-            TargetInstDef::new("sub", TargetOpcode::SUBri32)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
-                    TargetOperand::Immediate(TargetImmediate::I32),
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)])
-                .add_tie(DefOrUseReg::Def(0), DefOrUseReg::Use(0))
-        };
-        pub static ref SUBr64i32: TargetInstDef = {
-            TargetInstDef::new("sub", TargetOpcode::SUBr64i32)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
-                    TargetOperand::Immediate(TargetImmediate::I32),
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)])
-                .add_tie(DefOrUseReg::Def(0), DefOrUseReg::Use(0))
-        };
-        pub static ref SUBmr32: TargetInstDef = {
-            TargetInstDef::new("sub", TargetOpcode::SUBmr32)
-                .set_uses(vec![
-                    TargetOperand::Mem,
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)])
-        };
-        pub static ref SUBmi32: TargetInstDef = {
-            TargetInstDef::new("sub", TargetOpcode::SUBmi32)
-                .set_uses(vec![
-                    TargetOperand::Mem,
-                    TargetOperand::Immediate(TargetImmediate::I32)
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)])
-        };
-        pub static ref SUBSDrr: TargetInstDef = {
-            TargetInstDef::new("subsd", TargetOpcode::SUBSDrr)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::XMM)),
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::XMM)),
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::XMM)])
-                .add_tie(DefOrUseReg::Def(0), DefOrUseReg::Use(0))
-        };
-        pub static ref SUBSDrm: TargetInstDef = {
-            TargetInstDef::new("subsd", TargetOpcode::SUBSDrm)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::XMM)),
-                    TargetOperand::Mem,
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::XMM)])
-                .add_tie(DefOrUseReg::Def(0), DefOrUseReg::Use(0))
-        };
-        // pub static ref IMULrr8: TargetInstDef = {
-        //     TargetInstDef::new("imul", TargetOpcode::IMULrr8)
-        //         .set_uses(vec![
-        //             TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR8)),
-        //             TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR8)),
-        //         ])
-        //         .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR8)])
-        //         .add_tie(DefOrUseReg::Def(0), DefOrUseReg::Use(0))
-        // };
-        pub static ref IMULrri8: TargetInstDef = {
-            TargetInstDef::new("imul", TargetOpcode::IMULrri8)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR8)),
-                    TargetOperand::Immediate(TargetImmediate::I8),
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR8)])
-        };
-        pub static ref IMULrr32: TargetInstDef = {
-            TargetInstDef::new("imul", TargetOpcode::IMULrr32)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)])
-                .add_tie(DefOrUseReg::Def(0), DefOrUseReg::Use(0))
-        };
-        pub static ref IMULrri32: TargetInstDef = {
-            TargetInstDef::new("imul", TargetOpcode::IMULrri32)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
-                    TargetOperand::Immediate(TargetImmediate::I32),
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)])
-        };
-        pub static ref IMULrr64i32: TargetInstDef = {
-            TargetInstDef::new("imul", TargetOpcode::IMULrr64i32)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
-                    TargetOperand::Immediate(TargetImmediate::I32),
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)])
-                .add_tie(DefOrUseReg::Def(0), DefOrUseReg::Use(0))
-        };
-        pub static ref MULSDrr: TargetInstDef = {
-            TargetInstDef::new("mulsd", TargetOpcode::MULSDrr)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::XMM)),
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::XMM)),
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::XMM)])
-                .add_tie(DefOrUseReg::Def(0), DefOrUseReg::Use(0))
-        };
-        pub static ref MULSDrm: TargetInstDef = {
-            TargetInstDef::new("mulsd", TargetOpcode::MULSDrm)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::XMM)),
-                    TargetOperand::Mem,
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::XMM)])
-                .add_tie(DefOrUseReg::Def(0), DefOrUseReg::Use(0))
-        };
-        pub static ref DIVSDrr: TargetInstDef = {
-            TargetInstDef::new("divsd", TargetOpcode::DIVSDrr)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::XMM)),
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::XMM)),
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::XMM)])
-                .add_tie(DefOrUseReg::Def(0), DefOrUseReg::Use(0))
-        };
-        pub static ref DIVSDrm: TargetInstDef = {
-            TargetInstDef::new("divsd", TargetOpcode::DIVSDrm)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::XMM)),
-                    TargetOperand::Mem,
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::XMM)])
-                .add_tie(DefOrUseReg::Def(0), DefOrUseReg::Use(0))
-        };
-        pub static ref SHLr32i8: TargetInstDef = {
-            TargetInstDef::new("shl", TargetOpcode::SHLr32i8)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
-                    TargetOperand::Immediate(TargetImmediate::I8),
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)])
-                .add_tie(DefOrUseReg::Def(0), DefOrUseReg::Use(0))
-        };
-        pub static ref SHLr64i8: TargetInstDef = {
-            TargetInstDef::new("shl", TargetOpcode::SHLr64i8)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
-                    TargetOperand::Immediate(TargetImmediate::I8),
-                ])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)])
-                .add_tie(DefOrUseReg::Def(0), DefOrUseReg::Use(0))
-        };
-        pub static ref SQRTSDrr: TargetInstDef = {
-            TargetInstDef::new("sqrtsd", TargetOpcode::SQRTSDrr)
-                .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(
-                    RegisterClassKind::XMM,
-                ))])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::XMM)])
-                // .add_tie(DefOrUseReg::Def(0), DefOrUseReg::Use(0))
-        };
-        pub static ref CDQ: TargetInstDef = {
-            TargetInstDef::new("cdq", TargetOpcode::CDQ)
-                .set_imp_def(vec![TargetRegister::Specific(GR32::EDX.as_phys_reg())])
-                .set_imp_use(vec![TargetRegister::Specific(GR32::EAX.as_phys_reg())])
-        };
-        pub static ref CVTSI2SDrr32: TargetInstDef = {
-            TargetInstDef::new("cvtsi2sd", TargetOpcode::CVTSI2SDrr32)
-                .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32))])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::XMM)])
-        };
-        pub static ref CVTTSD2SIr32r: TargetInstDef = {
-            TargetInstDef::new("cvttsd2si", TargetOpcode::CVTTSD2SIr32r)
-                .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::XMM))])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)])
-        };
-        pub static ref MOVrr8: TargetInstDef = {
-            TargetInstDef::new("mov", TargetOpcode::MOVrr8)
-                .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR8))])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR8)])
-        };
-        pub static ref MOVrr32: TargetInstDef = {
-            TargetInstDef::new("mov", TargetOpcode::MOVrr32)
-                .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(
-                    RegisterClassKind::GR32,
-                ))])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)])
-        };
-        pub static ref MOVri32: TargetInstDef = {
-            TargetInstDef::new("mov", TargetOpcode::MOVri32)
-                .set_uses(vec![TargetOperand::Immediate(TargetImmediate::I32)])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)])
-        };
-        pub static ref MOVri8: TargetInstDef = {
-            TargetInstDef::new("mov", TargetOpcode::MOVri8)
-                .set_uses(vec![TargetOperand::Immediate(TargetImmediate::I8)])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR8)])
-        };
-        pub static ref MOVrm8: TargetInstDef = {
-            TargetInstDef::new("mov", TargetOpcode::MOVrm8)
-                .set_uses(vec![TargetOperand::Mem])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR8)])
-        };
-        pub static ref MOVmi8: TargetInstDef = {
-            TargetInstDef::new("mov", TargetOpcode::MOVmi8).set_uses(vec![
-                TargetOperand::Mem,
-                TargetOperand::Immediate(TargetImmediate::I8),
-            ])
-        };
-        pub static ref MOVmr8: TargetInstDef = {
-            TargetInstDef::new("mov", TargetOpcode::MOVmr8).set_uses(vec![
-                TargetOperand::Mem,
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR8)),
-            ])
-        };
-        pub static ref MOVrm32: TargetInstDef = {
-            TargetInstDef::new("mov", TargetOpcode::MOVrm32)
-                .set_uses(vec![TargetOperand::Mem])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR32)])
-        };
-        pub static ref MOVmr32: TargetInstDef = {
-            TargetInstDef::new("mov", TargetOpcode::MOVmr32).set_uses(vec![
-                TargetOperand::Mem,
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR32)),
-            ])
-        };
-        pub static ref MOVmi32: TargetInstDef = {
-            TargetInstDef::new("mov", TargetOpcode::MOVmi32).set_uses(vec![
-                TargetOperand::Mem,
-                TargetOperand::Immediate(TargetImmediate::I32),
-            ])
-        };
-        pub static ref MOVmr64: TargetInstDef = {
-            TargetInstDef::new("mov", TargetOpcode::MOVmr64).set_uses(vec![
-                TargetOperand::Mem,
-                TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::GR64)),
-            ])
-        };
-        pub static ref MOVmi64: TargetInstDef = {
-            TargetInstDef::new("mov", TargetOpcode::MOVmi64).set_uses(vec![
-                TargetOperand::Mem,
-                TargetOperand::Immediate(TargetImmediate::I64),
-            ])
-        };
-        pub static ref MOVrr64: TargetInstDef = {
-            TargetInstDef::new("mov", TargetOpcode::MOVrr64)
-                .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(
-                    RegisterClassKind::GR64,
-                ))])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)])
-        };
-        pub static ref MOVri64: TargetInstDef = {
-            TargetInstDef::new("mov", TargetOpcode::MOVri64)
-                .set_uses(vec![TargetOperand::Immediate(TargetImmediate::I64)])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)])
-        };
-        pub static ref MOVrm64: TargetInstDef = {
-            TargetInstDef::new("mov", TargetOpcode::MOVrm64)
-                .set_uses(vec![TargetOperand::Mem])
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR64)])
-        };
-        pub static ref IDIV: TargetInstDef = {
-            TargetInstDef::new("idiv", TargetOpcode::IDIV)
-                .set_uses(vec![TargetOperand::Register(TargetRegister::RegClass(
-                    RegisterClassKind::GR32,
-                ))])
-                .set_imp_def(vec![
-                    TargetRegister::Specific(GR32::EAX.as_phys_reg()),
-                    TargetRegister::Specific(GR32::EDX.as_phys_reg()),
-                ])
-                .set_imp_use(vec![
-                    TargetRegister::Specific(GR32::EAX.as_phys_reg()),
-                    TargetRegister::Specific(GR32::EDX.as_phys_reg()),
-                ])
-        };
-        pub static ref PUSH64: TargetInstDef = {
-            TargetInstDef::new("push", TargetOpcode::PUSH64).set_uses(vec![TargetOperand::Register(
-                TargetRegister::RegClass(RegisterClassKind::GR64),
-            )])
-        };
-        pub static ref POP64: TargetInstDef = {
-            TargetInstDef::new("pop", TargetOpcode::POP64).set_uses(vec![TargetOperand::Register(
-                TargetRegister::RegClass(RegisterClassKind::GR64),
-            )])
-        };
-        pub static ref SETE: TargetInstDef = {
-            TargetInstDef::new("sete", TargetOpcode::SETE)
-                .set_defs(vec![TargetRegister::RegClass(RegisterClassKind::GR8)])
-        };
-        pub static ref SETG: TargetInstDef = {
-            TargetInstDef::new("setg", TargetOpcode::SETG).set_uses(vec![TargetOperand::Block])
-        };
-        pub static ref SETGE: TargetInstDef = {
-            TargetInstDef::new("setge", TargetOpcode::SETGE).set_uses(vec![TargetOperand::Block])
-        };
-        pub static ref SETNE: TargetInstDef = {
-            TargetInstDef::new("setne", TargetOpcode::SETNE).set_uses(vec![TargetOperand::Block])
-        };
-        pub static ref SETLE: TargetInstDef = {
-            TargetInstDef::new("setle", TargetOpcode::SETLE).set_uses(vec![TargetOperand::Block])
-        };
-        pub static ref SETL: TargetInstDef = {
-            TargetInstDef::new("setl", TargetOpcode::SETL).set_uses(vec![TargetOperand::Block])
-        };
+/// How an instruction is laid out in machine code. `prefix` is the list of legacy prefixes,
+/// `w` forces REX.W and `ext` is the opcode extension put in ModR/M's reg field.
+#[derive(Debug, Clone, Copy)]
+pub enum Encoding {
+    /// Just the given bytes.
+    Raw(&'static [u8]),
+    /// `opcode + reg` of the first operand.
+    OpReg(u8),
+    /// `op r/m, r` with r/m the destination. (prefix, opcode)
+    Rr(&'static [u8], &'static [u8]),
+    /// `op r, r/m` with r the tied destination. (prefix, opcode)
+    RrTied(&'static [u8], &'static [u8]),
+    /// `op r, r/m` with r the destination. (prefix, opcode, w)
+    RrDef(&'static [u8], &'static [u8], bool),
+    /// `op r, m` with r the destination. (prefix, opcode)
+    Rm(&'static [u8], &'static [u8]),
+    /// `op r, m` with r the tied destination. (prefix, opcode)
+    RmTied(&'static [u8], &'static [u8]),
+    /// `op m, r` (prefix, opcode, w)
+    Mr(&'static [u8], &'static [u8], bool),
+    /// `op m, imm` (prefix, opcode, ext, imm size, w)
+    Mi(&'static [u8], u8, u8, usize, bool),
+    /// `op r/m, imm` with r/m the tied destination. (opcode, ext, imm size)
+    Ri(u8, u8, usize),
+    /// `mov r/m, imm` (prefix, opcode, imm size)
+    MovRi(&'static [u8], u8, usize),
+    /// `imul r, r/m, imm` (opcode, imm size)
+    ImulRri(u8, usize),
+    /// `op r/m` (opcode, ext)
+    Unary(u8, u8),
+    /// `op r/m, cl` (ext)
+    ShiftCl(u8),
+    /// `ucomis[sd] r, r/m` (prefix)
+    Ucomis(&'static [u8]),
+    Setcc,
+    Cmov,
+    Jcc,
+    /// `op rel32` (opcode)
+    Jmp(&'static [u8]),
+}
 
-        pub static ref JG: TargetInstDef = {
-            TargetInstDef::new("jg", TargetOpcode::JG).set_uses(vec![TargetOperand::Block])
-        };
-        pub static ref JGE: TargetInstDef = {
-            TargetInstDef::new("jge", TargetOpcode::JGE).set_uses(vec![TargetOperand::Block])
-        };
-        pub static ref JE: TargetInstDef = {
-            TargetInstDef::new("je", TargetOpcode::JE).set_uses(vec![TargetOperand::Block])
-        };
-        pub static ref JNE: TargetInstDef = {
-            TargetInstDef::new("jne", TargetOpcode::JNE).set_uses(vec![TargetOperand::Block])
-        };
-        pub static ref JLE: TargetInstDef = {
-            TargetInstDef::new("jle", TargetOpcode::JLE).set_uses(vec![TargetOperand::Block])
-        };
-        pub static ref JL: TargetInstDef = {
-            TargetInstDef::new("jl", TargetOpcode::JL).set_uses(vec![TargetOperand::Block])
-        };
-        pub static ref JB: TargetInstDef = {
-            TargetInstDef::new("jb", TargetOpcode::JB).set_uses(vec![TargetOperand::Block])
-        };
-        pub static ref JBE: TargetInstDef = {
-            TargetInstDef::new("jbe", TargetOpcode::JB).set_uses(vec![TargetOperand::Block])
-        };
-        pub static ref JA: TargetInstDef = {
-            TargetInstDef::new("ja", TargetOpcode::JA).set_uses(vec![TargetOperand::Block])
-        };
-        pub static ref JAE: TargetInstDef = {
-            TargetInstDef::new("jae", TargetOpcode::JAE).set_uses(vec![TargetOperand::Block])
-        };
-        pub static ref JMP: TargetInstDef = {
-            TargetInstDef::new("jmp", TargetOpcode::JMP).set_uses(vec![TargetOperand::Block])
-        };
-        pub static ref CMPri: TargetInstDef = {
-            TargetInstDef::new("cmp", TargetOpcode::CMPri)
-                .set_uses(vec![TargetOperand::Any, TargetOperand::Any])
-        };
-        pub static ref CMPrr: TargetInstDef = {
-            TargetInstDef::new("cmp", TargetOpcode::CMPrr)
-                .set_uses(vec![TargetOperand::Any, TargetOperand::Any])
-        };
-        pub static ref UCOMISDrr: TargetInstDef = {
-            TargetInstDef::new("ucomisd", TargetOpcode::UCOMISDrr)
-                .set_uses(vec![
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::XMM)),
-                    TargetOperand::Register(TargetRegister::RegClass(RegisterClassKind::XMM))
-                ])
-        };
-        pub static ref CALL: TargetInstDef = {
-            TargetInstDef::new("call", TargetOpcode::CALL) // TODO: defs and uses info
-                // .set_uses(vec![TargetOperand::Any, TargetOperand::Any])
-                // .set_imp_use(vec![]
-        };
-        pub static ref RET: TargetInstDef = TargetInstDef::new("ret", TargetOpcode::RET);
-    }
+/// The shape of an operand an instruction pattern can match.
+#[derive(Clone, Copy)]
+pub enum SelectOperand {
+    Reg(RegisterClassKind),
+    Imm(TargetImmediate),
+    Mem,
 }
 
-#[derive(Debug, Clone, PartialEq, Copy)]
-pub enum TargetOpcode {
-    MOVSDrm64, // out(xmm) = movsd [memory64] TODO
-    MOVSDmr,   // movsd MEM, r
-    MOVSDrm,   // movsd r, MEM
-    MOVSDrr,
+// Every x64 instruction is described once here. The macro generates `TargetOpcode`, the
+// `TargetInstDef`s used by the register allocator and the asm printer, the encodings used by
+// the assembler, the memory operand sizes used by the asm printer and the patterns used by
+// instruction selection.
+//
+// Instructions without a `pattern` are still picked by hand: compares, setcc, cmov and jumps
+// (chosen by condition code), division (a cdq/cqo + idiv/div sequence), calls, push/pop,
+// lea, register copies, immediate and constant-pool moves, sqrtsd and pseudo instructions.
+instructions! {
+    // out(xmm) = movsd [memory64] TODO
+    MOVSDrm64 {
+        asm: "movsd",
+        defs: [XMM],
+        uses: [mem],
+        encoding: Rm(&[0xf2], &[0x0f, 0x10]),
+        mem: qword,
+    }
+    // out(xmm) = movss [memory32]
+    MOVSSrm32 {
+        asm: "movss",
+        defs: [FR32],
+        uses: [mem],
+        encoding: Rm(&[0xf3], &[0x0f, 0x10]),
+        mem: dword,
+    }
+    // movsd MEM, r
+    MOVSDmr {
+        asm: "movsd",
+        uses: [mem, XMM],
+        encoding: Mr(&[0xf2], &[0x0f, 0x11], false),
+        mem: qword,
+        pattern: Store Void (mem, XMM),
+    }
+    // movss MEM, r
+    MOVSSmr {
+        asm: "movss",
+        uses: [mem, FR32],
+        encoding: Mr(&[0xf3], &[0x0f, 0x11], false),
+        mem: dword,
+        pattern: Store Void (mem, FR32),
+    }
+    // movsd r, MEM
+    MOVSDrm {
+        asm: "movsd",
+        defs: [XMM],
+        uses: [mem],
+        encoding: Rm(&[0xf2], &[0x0f, 0x10]),
+        mem: qword,
+        pattern: Load f64 (mem),
+    }
+    // movss r, MEM
+    MOVSSrm {
+        asm: "movss",
+        defs: [FR32],
+        uses: [mem],
+        encoding: Rm(&[0xf3], &[0x0f, 0x10]),
+        mem: dword,
+        pattern: Load f32 (mem),
+    }
+    MOVSDrr {
+        asm: "movsd",
+        defs: [XMM],
+        uses: [XMM],
+        encoding: RrDef(&[0xf2], &[0x0f, 0x10], false),
+    }
+    MOVSSrr {
+        asm: "movss",
+        defs: [FR32],
+        uses: [FR32],
+        encoding: RrDef(&[0xf3], &[0x0f, 0x10], false),
+    }
 
-    MOVrm32,
-    MOVrm8,
-    MOVri8,
+    MOVrm32 {
+        asm: "mov",
+        defs: [GR32],
+        uses: [mem],
+        encoding: Rm(&[], &[0x8b]),
+        mem: dword,
+        pattern: Load i32 (mem),
+    }
+    MOVrm16 {
+        asm: "mov",
+        defs: [GR16],
+        uses: [mem],
+        encoding: Rm(&[0x66], &[0x8b]),
+        mem: word,
+        pattern: Load i16 (mem),
+    }
+    MOVrm8 {
+        asm: "mov",
+        defs: [GR8],
+        uses: [mem],
+        encoding: Rm(&[], &[0x8a]),
+        mem: byte,
+        pattern: Load i8 (mem),
+        pattern: Load i1 (mem),
+    }
+    MOVri8 {
+        asm: "mov",
+        defs: [GR8],
+        uses: [i8],
+        encoding: MovRi(&[], 0xc6, 1),
+    }
+    MOVri16 {
+        asm: "mov",
+        defs: [GR16],
+        uses: [i16],
+        encoding: MovRi(&[0x66], 0xc7, 2),
+    }
 
-    MOVmr8,
-    MOVmi8,
-    MOVmr32,
-    MOVmi32,
-    MOVmr64,
-    MOVmi64,
+    MOVmr8 {
+        asm: "mov",
+        uses: [mem, GR8],
+        encoding: Mr(&[], &[0x88], false),
+        mem: byte,
+        pattern: Store Void (mem, GR8),
+    }
+    MOVmi8 {
+        asm: "mov",
+        uses: [mem, i8],
+        encoding: Mi(&[], 0xc6, 0, 1, false),
+        mem: byte,
+        pattern: Store Void (mem, i8),
+    }
+    MOVmr16 {
+        asm: "mov",
+        uses: [mem, GR16],
+        encoding: Mr(&[0x66], &[0x89], false),
+        mem: word,
+        pattern: Store Void (mem, GR16),
+    }
+    MOVmi16 {
+        asm: "mov",
+        uses: [mem, i16],
+        encoding: Mi(&[0x66], 0xc7, 0, 2, false),
+        mem: word,
+        pattern: Store Void (mem, i16),
+    }
+    MOVmr32 {
+        asm: "mov",
+        uses: [mem, GR32],
+        encoding: Mr(&[], &[0x89], false),
+        mem: dword,
+        pattern: Store Void (mem, GR32),
+    }
+    MOVmi32 {
+        asm: "mov",
+        uses: [mem, i32],
+        encoding: Mi(&[], 0xc7, 0, 4, false),
+        mem: dword,
+        pattern: Store Void (mem, i32),
+    }
+    MOVmr64 {
+        asm: "mov",
+        uses: [mem, GR64],
+        encoding: Mr(&[], &[0x89], true),
+        mem: qword,
+        pattern: Store Void (mem, GR64),
+    }
+    MOVmi64 {
+        asm: "mov",
+        uses: [mem, i64],
+        encoding: Mi(&[], 0xc7, 0, 4, true),
+        mem: qword,
+        pattern: Store Void (mem, i64),
+    }
 
-    MOVSXr32r8,
-    MOVSXDr64m32, // out = movsxd [rbp - fi.off]
-    MOVSXDr64r32, // r64 = movsxd r32
+    MOVSXr32r8 {
+        asm: "movsx",
+        defs: [GR32],
+        uses: [GR8],
+        encoding: RrDef(&[], &[0x0f, 0xbe], false),
+        pattern: Sext i32 (GR8),
+    }
+    MOVSXr32r16 {
+        asm: "movsx",
+        defs: [GR32],
+        uses: [GR16],
+        encoding: RrDef(&[], &[0x0f, 0xbf], false),
+        pattern: Sext i32 (GR16),
+    }
+    // out = movsxd [rbp - fi.off]
+    MOVSXDr64m32 {
+        asm: "movsxd",
+        defs: [GR64],
+        uses: [mem],
+        encoding: Rm(&[], &[0x63]),
+        mem: dword,
+    }
+    // r64 = movsxd r32
+    MOVSXDr64r32 {
+        asm: "movsxd",
+        defs: [GR64],
+        uses: [GR32],
+        encoding: RrDef(&[], &[0x63], true),
+        pattern: Sext i64 (GR32),
+    }
 
-    MOVZXr32r8,
+    MOVZXr32r8 {
+        asm: "movzx",
+        defs: [GR32],
+        uses: [GR8],
+        encoding: RrDef(&[], &[0x0f, 0xb6], false),
+        pattern: Zext i32 (GR8),
+    }
+    MOVZXr32r16 {
+        asm: "movzx",
+        defs: [GR32],
+        uses: [GR16],
+        encoding: RrDef(&[], &[0x0f, 0xb7], false),
+        pattern: Zext i32 (GR16),
+    }
 
-    LEAr64m,
+    LEAr64m {
+        asm: "lea",
+        defs: [GR64],
+        uses: [mem],
+        encoding: Rm(&[], &[0x8d]),
+        mem: qword,
+    }
 
-    ADDrr8,
-    ADDri8,
-    ADDrr32,
-    ADDmr32,
-    ADDmi32,
-    ADDrr64,
-    ADDri32,
-    ADDr64i32,
-    ADDSDrr,
-    ADDSDrm,
-    SUBrr8,
-    SUBri8,
-    SUBrr32,
-    SUBmr32,
-    SUBmi32,
-    SUBri32,
-    SUBr64i32,
-    SUBSDrr,
-    SUBSDrm,
+    ADDrr8 {
+        asm: "add",
+        defs: [GR8],
+        uses: [GR8, GR8],
+        tie: (0, 0),
+        encoding: Rr(&[], &[0x00]),
+        pattern: Add i8 (GR8, GR8),
+    }
+    ADDri8 {
+        asm: "add",
+        defs: [GR8],
+        uses: [GR8, i8],
+        tie: (0, 0),
+        encoding: Ri(0x80, 0, 1),
+        pattern: Add i8 (GR8, i8),
+    }
+    ADDrr32 {
+        asm: "add",
+        defs: [GR32],
+        uses: [GR32, GR32],
+        tie: (0, 0),
+        encoding: Rr(&[], &[0x01]),
+        pattern: Add i32 (GR32, GR32),
+    }
+    ADDmr32 {
+        asm: "add",
+        defs: [GR32],
+        uses: [mem, GR32],
+        encoding: Mr(&[], &[0x01], false),
+        mem: dword,
+    }
+    ADDmi32 {
+        asm: "add",
+        defs: [GR32],
+        uses: [mem, i32],
+        encoding: Mi(&[], 0x81, 0, 4, false),
+        mem: dword,
+    }
+    ADDrr64 {
+        asm: "add",
+        defs: [GR64],
+        uses: [GR64, GR64],
+        tie: (0, 0),
+        encoding: Rr(&[], &[0x01]),
+        pattern: Add i64 (GR64, GR64),
+    }
+    ADDri32 {
+        asm: "add",
+        defs: [GR32],
+        uses: [GR32, i32],
+        tie: (0, 0),
+        encoding: Ri(0x81, 0, 4),
+        pattern: Add i32 (GR32, i32),
+    }
+    ADDr64i32 {
+        asm: "add",
+        defs: [GR64],
+        uses: [GR64, i32],
+        tie: (0, 0),
+        encoding: Ri(0x81, 0, 4),
+        pattern: Add i64 (GR64, i32),
+    }
+    ADDSDrr {
+        asm: "addsd",
+        defs: [XMM],
+        uses: [XMM, XMM],
+        tie: (0, 0),
+        encoding: RrTied(&[0xf2], &[0x0f, 0x58]),
+        pattern: Add f64 (XMM, XMM),
+    }
+    ADDSSrr {
+        asm: "addss",
+        defs: [FR32],
+        uses: [FR32, FR32],
+        tie: (0, 0),
+        encoding: RrTied(&[0xf3], &[0x0f, 0x58]),
+        pattern: Add f32 (FR32, FR32),
+    }
+    ADDSDrm {
+        asm: "addsd",
+        defs: [XMM],
+        uses: [XMM, mem],
+        tie: (0, 0),
+        encoding: RmTied(&[0xf2], &[0x0f, 0x58]),
+        mem: qword,
+    }
+    ADDSSrm {
+        asm: "addss",
+        defs: [FR32],
+        uses: [FR32, mem],
+        tie: (0, 0),
+        encoding: RmTied(&[0xf3], &[0x0f, 0x58]),
+        mem: dword,
+    }
+    SUBrr8 {
+        asm: "sub",
+        defs: [GR8],
+        uses: [GR8, GR8],
+        tie: (0, 0),
+        encoding: Rr(&[], &[0x28]),
+        pattern: Sub i8 (GR8, GR8),
+    }
+    SUBri8 {
+        asm: "sub",
+        defs: [GR8],
+        uses: [GR8, i8],
+        tie: (0, 0),
+        encoding: Ri(0x80, 5, 1),
+        pattern: Sub i8 (GR8, i8),
+    }
+    SUBrr32 {
+        asm: "sub",
+        defs: [GR32],
+        uses: [GR32, GR32],
+        tie: (0, 0),
+        encoding: Rr(&[], &[0x29]),
+        pattern: Sub i32 (GR32, GR32),
+    }
+    SUBmr32 {
+        asm: "sub",
+        defs: [GR32],
+        uses: [mem, GR32],
+        encoding: Mr(&[], &[0x29], false),
+        mem: dword,
+    }
+    SUBmi32 {
+        asm: "sub",
+        defs: [GR32],
+        uses: [mem, i32],
+        encoding: Mi(&[], 0x81, 5, 4, false),
+        mem: dword,
+    }
+    SUBri32 {
+        asm: "sub",
+        defs: [GR32],
+        uses: [GR32, i32],
+        tie: (0, 0),
+        encoding: Ri(0x81, 5, 4),
+        pattern: Sub i32 (GR32, i32),
+    }
+    SUBrr64 {
+        asm: "sub",
+        defs: [GR64],
+        uses: [GR64, GR64],
+        tie: (0, 0),
+        encoding: Rr(&[], &[0x29]),
+        pattern: Sub i64 (GR64, GR64),
+    }
+    SUBr64i32 {
+        asm: "sub",
+        defs: [GR64],
+        uses: [GR64, i32],
+        tie: (0, 0),
+        encoding: Ri(0x81, 5, 4),
+        pattern: Sub i64 (GR64, i32),
+    }
+    SUBSDrr {
+        asm: "subsd",
+        defs: [XMM],
+        uses: [XMM, XMM],
+        tie: (0, 0),
+        encoding: RrTied(&[0xf2], &[0x0f, 0x5c]),
+        pattern: Sub f64 (XMM, XMM),
+    }
+    SUBSSrr {
+        asm: "subss",
+        defs: [FR32],
+        uses: [FR32, FR32],
+        tie: (0, 0),
+        encoding: RrTied(&[0xf3], &[0x0f, 0x5c]),
+        pattern: Sub f32 (FR32, FR32),
+    }
+    SUBSDrm {
+        asm: "subsd",
+        defs: [XMM],
+        uses: [XMM, mem],
+        tie: (0, 0),
+        encoding: RmTied(&[0xf2], &[0x0f, 0x5c]),
+        mem: qword,
+    }
+    SUBSSrm {
+        asm: "subss",
+        defs: [FR32],
+        uses: [FR32, mem],
+        tie: (0, 0),
+        encoding: RmTied(&[0xf3], &[0x0f, 0x5c]),
+        mem: dword,
+    }
     // IMULrr8,
-    IMULrri8,
-    IMULrr32,
-    IMULrri32,
-    IMULrr64i32,
-    MULSDrr,
-    MULSDrm,
-    CDQ,
-    IDIV,
-    DIVSDrr,
-    DIVSDrm,
-    SHLr64i8,
-    SHLr32i8,
-    CVTTSD2SIr32r,
-    CVTSI2SDrr32,
-    SQRTSDrr,
-    MOVrr8,
-    MOVrr32,
-    MOVri32,
-    MOVrr64,
-    MOVri64,
-    MOVrm64,
-    PUSH64,
-    POP64,
-    RET,
+    IMULrri8 {
+        asm: "imul",
+        defs: [GR8],
+        uses: [GR8, i8],
+        encoding: ImulRri(0x6b, 1),
+    }
+    IMULrr32 {
+        asm: "imul",
+        defs: [GR32],
+        uses: [GR32, GR32],
+        tie: (0, 0),
+        encoding: RrTied(&[], &[0x0f, 0xaf]),
+        pattern: Mul i32 (GR32, GR32),
+    }
+    IMULrri32 {
+        asm: "imul",
+        defs: [GR32],
+        uses: [GR32, i32],
+        encoding: ImulRri(0x69, 4),
+        pattern: Mul i32 (GR32, i32),
+    }
+    IMULrr64 {
+        asm: "imul",
+        defs: [GR64],
+        uses: [GR64, GR64],
+        tie: (0, 0),
+        encoding: RrTied(&[], &[0x0f, 0xaf]),
+        pattern: Mul i64 (GR64, GR64),
+    }
+    IMULrr64i32 {
+        asm: "imul",
+        defs: [GR64],
+        uses: [GR64, i32],
+        tie: (0, 0),
+        encoding: ImulRri(0x69, 4),
+        pattern: Mul i64 (GR64, i32),
+    }
+    MULSDrr {
+        asm: "mulsd",
+        defs: [XMM],
+        uses: [XMM, XMM],
+        tie: (0, 0),
+        encoding: RrTied(&[0xf2], &[0x0f, 0x59]),
+        pattern: Mul f64 (XMM, XMM),
+    }
+    MULSSrr {
+        asm: "mulss",
+        defs: [FR32],
+        uses: [FR32, FR32],
+        tie: (0, 0),
+        encoding: RrTied(&[0xf3], &[0x0f, 0x59]),
+        pattern: Mul f32 (FR32, FR32),
+    }
+    MULSDrm {
+        asm: "mulsd",
+        defs: [XMM],
+        uses: [XMM, mem],
+        tie: (0, 0),
+        encoding: RmTied(&[0xf2], &[0x0f, 0x59]),
+        mem: qword,
+    }
+    MULSSrm {
+        asm: "mulss",
+        defs: [FR32],
+        uses: [FR32, mem],
+        tie: (0, 0),
+        encoding: RmTied(&[0xf3], &[0x0f, 0x59]),
+        mem: dword,
+    }
+    CDQ {
+        asm: "cdq",
+        imp_defs: [GR32::EDX],
+        imp_uses: [GR32::EAX],
+        encoding: Raw(&[0x99]),
+    }
+    CQO {
+        asm: "cqo",
+        imp_defs: [GR64::RDX],
+        imp_uses: [GR64::RAX],
+        encoding: Raw(&[0x48, 0x99]),
+    }
+    IDIV {
+        asm: "idiv",
+        uses: [GR32],
+        imp_defs: [GR32::EAX, GR32::EDX],
+        imp_uses: [GR32::EAX, GR32::EDX],
+        encoding: Unary(0xf7, 7),
+    }
+    DIV {
+        asm: "div",
+        uses: [GR32],
+        imp_defs: [GR32::EAX, GR32::EDX],
+        imp_uses: [GR32::EAX, GR32::EDX],
+        encoding: Unary(0xf7, 6),
+    }
+    DIVSDrr {
+        asm: "divsd",
+        defs: [XMM],
+        uses: [XMM, XMM],
+        tie: (0, 0),
+        encoding: RrTied(&[0xf2], &[0x0f, 0x5e]),
+        pattern: Div f64 (XMM, XMM),
+    }
+    DIVSSrr {
+        asm: "divss",
+        defs: [FR32],
+        uses: [FR32, FR32],
+        tie: (0, 0),
+        encoding: RrTied(&[0xf3], &[0x0f, 0x5e]),
+        pattern: Div f32 (FR32, FR32),
+    }
+    DIVSDrm {
+        asm: "divsd",
+        defs: [XMM],
+        uses: [XMM, mem],
+        tie: (0, 0),
+        encoding: RmTied(&[0xf2], &[0x0f, 0x5e]),
+        mem: qword,
+    }
+    DIVSSrm {
+        asm: "divss",
+        defs: [FR32],
+        uses: [FR32, mem],
+        tie: (0, 0),
+        encoding: RmTied(&[0xf3], &[0x0f, 0x5e]),
+        mem: dword,
+    }
+    SHLr64i8 {
+        asm: "shl",
+        defs: [GR64],
+        uses: [GR64, i8],
+        tie: (0, 0),
+        encoding: Ri(0xc1, 4, 1),
+        pattern: Shl i64 (GR64, i8),
+    }
+    SHLr32i8 {
+        asm: "shl",
+        defs: [GR32],
+        uses: [GR32, i8],
+        tie: (0, 0),
+        encoding: Ri(0xc1, 4, 1),
+        pattern: Shl i32 (GR32, i8),
+    }
+    ANDrr8 {
+        asm: "and",
+        defs: [GR8],
+        uses: [GR8, GR8],
+        tie: (0, 0),
+        encoding: Rr(&[], &[0x20]),
+        pattern: And i8 (GR8, GR8),
+    }
+    ANDri8 {
+        asm: "and",
+        defs: [GR8],
+        uses: [GR8, i8],
+        tie: (0, 0),
+        encoding: Ri(0x80, 4, 1),
+        pattern: And i8 (GR8, i8),
+    }
+    ANDrr32 {
+        asm: "and",
+        defs: [GR32],
+        uses: [GR32, GR32],
+        tie: (0, 0),
+        encoding: Rr(&[], &[0x21]),
+        pattern: And i32 (GR32, GR32),
+    }
+    ANDri32 {
+        asm: "and",
+        defs: [GR32],
+        uses: [GR32, i32],
+        tie: (0, 0),
+        encoding: Ri(0x81, 4, 4),
+        pattern: And i32 (GR32, i32),
+    }
+    ANDrr64 {
+        asm: "and",
+        defs: [GR64],
+        uses: [GR64, GR64],
+        tie: (0, 0),
+        encoding: Rr(&[], &[0x21]),
+        pattern: And i64 (GR64, GR64),
+    }
+    ANDr64i32 {
+        asm: "and",
+        defs: [GR64],
+        uses: [GR64, i32],
+        tie: (0, 0),
+        encoding: Ri(0x81, 4, 4),
+        pattern: And i64 (GR64, i32),
+    }
+    ORrr8 {
+        asm: "or",
+        defs: [GR8],
+        uses: [GR8, GR8],
+        tie: (0, 0),
+        encoding: Rr(&[], &[0x08]),
+        pattern: Or i8 (GR8, GR8),
+    }
+    ORri8 {
+        asm: "or",
+        defs: [GR8],
+        uses: [GR8, i8],
+        tie: (0, 0),
+        encoding: Ri(0x80, 1, 1),
+        pattern: Or i8 (GR8, i8),
+    }
+    ORrr32 {
+        asm: "or",
+        defs: [GR32],
+        uses: [GR32, GR32],
+        tie: (0, 0),
+        encoding: Rr(&[], &[0x09]),
+        pattern: Or i32 (GR32, GR32),
+    }
+    ORri32 {
+        asm: "or",
+        defs: [GR32],
+        uses: [GR32, i32],
+        tie: (0, 0),
+        encoding: Ri(0x81, 1, 4),
+        pattern: Or i32 (GR32, i32),
+    }
+    ORrr64 {
+        asm: "or",
+        defs: [GR64],
+        uses: [GR64, GR64],
+        tie: (0, 0),
+        encoding: Rr(&[], &[0x09]),
+        pattern: Or i64 (GR64, GR64),
+    }
+    ORr64i32 {
+        asm: "or",
+        defs: [GR64],
+        uses: [GR64, i32],
+        tie: (0, 0),
+        encoding: Ri(0x81, 1, 4),
+        pattern: Or i64 (GR64, i32),
+    }
+    XORrr8 {
+        asm: "xor",
+        defs: [GR8],
+        uses: [GR8, GR8],
+        tie: (0, 0),
+        encoding: Rr(&[], &[0x30]),
+        pattern: Xor i8 (GR8, GR8),
+    }
+    XORri8 {
+        asm: "xor",
+        defs: [GR8],
+        uses: [GR8, i8],
+        tie: (0, 0),
+        encoding: Ri(0x80, 6, 1),
+        pattern: Xor i8 (GR8, i8),
+    }
+    XORrr32 {
+        asm: "xor",
+        defs: [GR32],
+        uses: [GR32, GR32],
+        tie: (0, 0),
+        encoding: Rr(&[], &[0x31]),
+        pattern: Xor i32 (GR32, GR32),
+    }
+    XORri32 {
+        asm: "xor",
+        defs: [GR32],
+        uses: [GR32, i32],
+        tie: (0, 0),
+        encoding: Ri(0x81, 6, 4),
+        pattern: Xor i32 (GR32, i32),
+    }
+    XORrr64 {
+        asm: "xor",
+        defs: [GR64],
+        uses: [GR64, GR64],
+        tie: (0, 0),
+        encoding: Rr(&[], &[0x31]),
+        pattern: Xor i64 (GR64, GR64),
+    }
+    XORr64i32 {
+        asm: "xor",
+        defs: [GR64],
+        uses: [GR64, i32],
+        tie: (0, 0),
+        encoding: Ri(0x81, 6, 4),
+        pattern: Xor i64 (GR64, i32),
+    }
+    SHLr32CL {
+        asm: "shl",
+        defs: [GR32],
+        uses: [GR32, GR8::CL],
+        tie: (0, 0),
+        encoding: ShiftCl(4),
+        pattern: Shl i32 (GR32, GR8),
+    }
+    SHLr64CL {
+        asm: "shl",
+        defs: [GR64],
+        uses: [GR64, GR8::CL],
+        tie: (0, 0),
+        encoding: ShiftCl(4),
+        pattern: Shl i64 (GR64, GR8),
+    }
+    SHRr32i8 {
+        asm: "shr",
+        defs: [GR32],
+        uses: [GR32, i8],
+        tie: (0, 0),
+        encoding: Ri(0xc1, 5, 1),
+        pattern: LShr i32 (GR32, i8),
+    }
+    SHRr64i8 {
+        asm: "shr",
+        defs: [GR64],
+        uses: [GR64, i8],
+        tie: (0, 0),
+        encoding: Ri(0xc1, 5, 1),
+        pattern: LShr i64 (GR64, i8),
+    }
+    SHRr32CL {
+        asm: "shr",
+        defs: [GR32],
+        uses: [GR32, GR8::CL],
+        tie: (0, 0),
+        encoding: ShiftCl(5),
+        pattern: LShr i32 (GR32, GR8),
+    }
+    SHRr64CL {
+        asm: "shr",
+        defs: [GR64],
+        uses: [GR64, GR8::CL],
+        tie: (0, 0),
+        encoding: ShiftCl(5),
+        pattern: LShr i64 (GR64, GR8),
+    }
+    SARr32i8 {
+        asm: "sar",
+        defs: [GR32],
+        uses: [GR32, i8],
+        tie: (0, 0),
+        encoding: Ri(0xc1, 7, 1),
+        pattern: AShr i32 (GR32, i8),
+    }
+    SARr64i8 {
+        asm: "sar",
+        defs: [GR64],
+        uses: [GR64, i8],
+        tie: (0, 0),
+        encoding: Ri(0xc1, 7, 1),
+        pattern: AShr i64 (GR64, i8),
+    }
+    SARr32CL {
+        asm: "sar",
+        defs: [GR32],
+        uses: [GR32, GR8::CL],
+        tie: (0, 0),
+        encoding: ShiftCl(7),
+        pattern: AShr i32 (GR32, GR8),
+    }
+    SARr64CL {
+        asm: "sar",
+        defs: [GR64],
+        uses: [GR64, GR8::CL],
+        tie: (0, 0),
+        encoding: ShiftCl(7),
+        pattern: AShr i64 (GR64, GR8),
+    }
+    CVTTSD2SIr32r {
+        asm: "cvttsd2si",
+        defs: [GR32],
+        uses: [XMM],
+        encoding: RrDef(&[0xf2], &[0x0f, 0x2c], false),
+        pattern: FPToSI i32 (XMM),
+    }
+    CVTTSS2SIr32r {
+        asm: "cvttss2si",
+        defs: [GR32],
+        uses: [FR32],
+        encoding: RrDef(&[0xf3], &[0x0f, 0x2c], false),
+        pattern: FPToSI i32 (FR32),
+    }
+    CVTTSD2SIr64r {
+        asm: "cvttsd2si",
+        defs: [GR64],
+        uses: [XMM],
+        encoding: RrDef(&[0xf2], &[0x0f, 0x2c], true),
+        pattern: FPToSI i64 (XMM),
+    }
+    CVTTSS2SIr64r {
+        asm: "cvttss2si",
+        defs: [GR64],
+        uses: [FR32],
+        encoding: RrDef(&[0xf3], &[0x0f, 0x2c], true),
+        pattern: FPToSI i64 (FR32),
+    }
+    CVTSS2SDrr {
+        asm: "cvtss2sd",
+        defs: [XMM],
+        uses: [FR32],
+        encoding: RrDef(&[0xf3], &[0x0f, 0x5a], false),
+        pattern: FPExt f64 (FR32),
+    }
+    CVTSD2SSrr {
+        asm: "cvtsd2ss",
+        defs: [FR32],
+        uses: [XMM],
+        encoding: RrDef(&[0xf2], &[0x0f, 0x5a], false),
+        pattern: FPTrunc f32 (XMM),
+    }
+    CVTSI2SDrr32 {
+        asm: "cvtsi2sd",
+        defs: [XMM],
+        uses: [GR32],
+        encoding: RrDef(&[0xf2], &[0x0f, 0x2a], false),
+        pattern: SIToFP f64 (GR32),
+    }
+    CVTSI2SSrr32 {
+        asm: "cvtsi2ss",
+        defs: [FR32],
+        uses: [GR32],
+        encoding: RrDef(&[0xf3], &[0x0f, 0x2a], false),
+        pattern: SIToFP f32 (GR32),
+    }
+    CVTSI2SDrr64 {
+        asm: "cvtsi2sd",
+        defs: [XMM],
+        uses: [GR64],
+        encoding: RrDef(&[0xf2], &[0x0f, 0x2a], true),
+        pattern: SIToFP f64 (GR64),
+    }
+    CVTSI2SSrr64 {
+        asm: "cvtsi2ss",
+        defs: [FR32],
+        uses: [GR64],
+        encoding: RrDef(&[0xf3], &[0x0f, 0x2a], true),
+        pattern: SIToFP f32 (GR64),
+    }
+    SQRTSDrr {
+        asm: "sqrtsd",
+        defs: [XMM],
+        uses: [XMM],
+        encoding: RrDef(&[0xf2], &[0x0f, 0x51], false),
+    }
+    MOVrr8 {
+        asm: "mov",
+        defs: [GR8],
+        uses: [GR8],
+        encoding: Rr(&[], &[0x88]),
+    }
+    MOVrr16 {
+        asm: "mov",
+        defs: [GR16],
+        uses: [GR16],
+        encoding: Rr(&[0x66], &[0x89]),
+    }
+    MOVrr32 {
+        asm: "mov",
+        defs: [GR32],
+        uses: [GR32],
+        encoding: Rr(&[], &[0x89]),
+    }
+    MOVri32 {
+        asm: "mov",
+        defs: [GR32],
+        uses: [i32],
+        encoding: MovRi(&[], 0xc7, 4),
+    }
+    MOVrr64 {
+        asm: "mov",
+        defs: [GR64],
+        uses: [GR64],
+        encoding: Rr(&[], &[0x89]),
+    }
+    MOVri64 {
+        asm: "mov",
+        defs: [GR64],
+        uses: [i64],
+    }
+    MOVrm64 {
+        asm: "mov",
+        defs: [GR64],
+        uses: [mem],
+        mem: qword,
+        pattern: Load i64 (mem),
+    }
+    PUSH64 {
+        asm: "push",
+        uses: [GR64],
+        encoding: OpReg(0x50),
+    }
+    POP64 {
+        asm: "pop",
+        uses: [GR64],
+        encoding: OpReg(0x58),
+    }
+    RET {
+        asm: "ret",
+        encoding: Raw(&[0xc3]),
+    }
 
-    CALL,
+    CALL {
+        asm: "call",
+    }
 
     // Comparison
-    Seteq,
-    Setle,
-    Setlt,
+    Seteq {}
+    Setle {}
+    Setlt {}
 
     // BrccEq,
     // BrccLe,
     // BrccLt,
-    CMPrr,
-    CMPri,
-    UCOMISDrr,
-    SETE,
-    SETNE,
-    SETGE,
-    SETG,
-    SETLE,
-    SETL,
-    JE,
-    JNE,
-    JBE,
-    JB,
-    JLE,
-    JL,
-    JA,
-    JAE,
-    JG,
-    JGE,
-    JMP,
-
-    Phi,
-    Ret,
-    Copy,
-    ExtractSubreg,
-    BrCond,
-    AdjStackDown,
-    AdjStackUp,
-}
-
-impl TargetOpcode {
-    pub fn inst_def(&self) -> Option<&TargetInstDef> {
-        match self {
-            Self::MOVSDrm64 => Some(&*inst::MOVSDrm64),
-            Self::MOVSDmr => Some(&*inst::MOVSDmr),
-            Self::MOVSDrm => Some(&*inst::MOVSDrm),
-            Self::MOVSDrr => Some(&*inst::MOVSDrr),
-            Self::MOVSXr32r8 => Some(&*inst::MOVSXr32r8),
-            Self::MOVSXDr64m32 => Some(&*inst::MOVSXDr64m32),
-            Self::MOVSXDr64r32 => Some(&*inst::MOVSXDr64r32),
-            Self::MOVZXr32r8 => Some(&*inst::MOVZXr32r8),
-            Self::LEAr64m => Some(&*inst::LEAr64m),
-            Self::ADDrr8 => Some(&*inst::ADDrr8),
-            Self::ADDri8 => Some(&*inst::ADDri8),
-            Self::ADDrr32 => Some(&*inst::ADDrr32),
-            Self::ADDmr32 => Some(&*inst::ADDmr32),
-            Self::ADDmi32 => Some(&*inst::ADDmi32),
-            Self::ADDrr64 => Some(&*inst::ADDrr64),
-            Self::ADDri32 => Some(&*inst::ADDri32),
-            Self::ADDr64i32 => Some(&*inst::ADDr64i32),
-            Self::ADDSDrr => Some(&*inst::ADDSDrr),
-            Self::ADDSDrm => Some(&*inst::ADDSDrm),
-            Self::SUBrr8 => Some(&*inst::SUBrr8),
-            Self::SUBri8 => Some(&*inst::SUBri8),
-            Self::SUBrr32 => Some(&*inst::SUBrr32),
-            Self::SUBri32 => Some(&*inst::SUBri32),
-            Self::SUBmr32 => Some(&*inst::SUBmr32),
-            Self::SUBmi32 => Some(&*inst::SUBmi32),
-            Self::SUBr64i32 => Some(&*inst::SUBr64i32),
-            Self::SUBSDrr => Some(&*inst::SUBSDrr),
-            Self::SUBSDrm => Some(&*inst::SUBSDrm),
-            // Self::IMULrr8 => Some(&*inst::IMULrr8),
-            Self::IMULrri8 => Some(&*inst::IMULrri8),
-            Self::IMULrr32 => Some(&*inst::IMULrr32),
-            Self::IMULrri32 => Some(&*inst::IMULrri32),
-            Self::IMULrr64i32 => Some(&*inst::IMULrr64i32),
-            Self::MULSDrr => Some(&*inst::MULSDrr),
-            Self::MULSDrm => Some(&*inst::MULSDrm),
-            Self::CDQ => Some(&*inst::CDQ),
-            Self::DIVSDrr => Some(&*inst::DIVSDrr),
-            Self::DIVSDrm => Some(&*inst::DIVSDrm),
-            Self::SHLr64i8 => Some(&*inst::SHLr64i8),
-            Self::SHLr32i8 => Some(&*inst::SHLr32i8),
-            Self::CVTSI2SDrr32 => Some(&*inst::CVTSI2SDrr32),
-            Self::CVTTSD2SIr32r => Some(&*inst::CVTTSD2SIr32r),
-            Self::SQRTSDrr => Some(&*inst::SQRTSDrr),
-            Self::MOVrr8 => Some(&*inst::MOVrr8),
-            Self::MOVri8 => Some(&*inst::MOVri8),
-            Self::MOVrm8 => Some(&*inst::MOVrm8),
-            Self::MOVmr8 => Some(&*inst::MOVmr8),
-            Self::MOVmi8 => Some(&*inst::MOVmi8),
-            Self::MOVrr32 => Some(&*inst::MOVrr32),
-            Self::MOVri32 => Some(&*inst::MOVri32),
-            Self::MOVrm32 => Some(&*inst::MOVrm32),
-            Self::MOVmr32 => Some(&*inst::MOVmr32),
-            Self::MOVmi32 => Some(&*inst::MOVmi32),
-            Self::MOVmr64 => Some(&*inst::MOVmr64),
-            Self::MOVmi64 => Some(&*inst::MOVmi64),
-            Self::MOVrr64 => Some(&*inst::MOVrr64),
-            Self::MOVri64 => Some(&*inst::MOVri64),
-            Self::MOVrm64 => Some(&*inst::MOVrm64),
-            Self::IDIV => Some(&*inst::IDIV),
-            Self::PUSH64 => Some(&*inst::PUSH64),
-            Self::POP64 => Some(&*inst::POP64),
-            Self::SETE => Some(&*inst::SETE),
-            Self::SETG => Some(&*inst::SETG),
-            Self::SETGE => Some(&*inst::SETGE),
-            Self::SETNE => Some(&*inst::SETNE),
-            Self::SETLE => Some(&*inst::SETLE),
-            Self::SETL => Some(&*inst::SETL),
-            Self::JMP => Some(&*inst::JMP),
-            Self::JG => Some(&*inst::JG),
-            Self::JGE => Some(&*inst::JGE),
-            Self::JE => Some(&*inst::JE),
-            Self::JNE => Some(&*inst::JNE),
-            Self::JLE => Some(&*inst::JLE),
-            Self::JL => Some(&*inst::JL),
-            Self::JB => Some(&*inst::JB),
-            Self::JBE => Some(&*inst::JBE),
-            Self::JA => Some(&*inst::JA),
-            Self::JAE => Some(&*inst::JAE),
-            Self::CMPri => Some(&*inst::CMPri),
-            Self::CMPrr => Some(&*inst::CMPrr),
-            Self::CALL => Some(&*inst::CALL),
-            Self::RET => Some(&*inst::RET),
-            Self::UCOMISDrr => Some(&*inst::UCOMISDrr),
-            _ => None,
-        }
+    CMPrr {
+        asm: "cmp",
+        uses: [any, any],
+    }
+    CMPri {
+        asm: "cmp",
+        uses: [any, any],
+    }
+    UCOMISDrr {
+        asm: "ucomisd",
+        uses: [XMM, XMM],
+        encoding: Ucomis(&[0x66]),
+    }
+    UCOMISSrr {
+        asm: "ucomiss",
+        uses: [FR32, FR32],
+        encoding: Ucomis(&[]),
+    }
+    SETE {
+        asm: "sete",
+        defs: [GR8],
+        encoding: Setcc,
+    }
+    SETNE {
+        asm: "setne",
+        uses: [block],
+        encoding: Setcc,
+    }
+    SETGE {
+        asm: "setge",
+        uses: [block],
+        encoding: Setcc,
+    }
+    SETG {
+        asm: "setg",
+        uses: [block],
+        encoding: Setcc,
+    }
+    SETLE {
+        asm: "setle",
+        uses: [block],
+        encoding: Setcc,
+    }
+    SETL {
+        asm: "setl",
+        uses: [block],
+        encoding: Setcc,
+    }
+    SETBE {
+        asm: "setbe",
+        defs: [GR8],
+        encoding: Setcc,
+    }
+    SETB {
+        asm: "setb",
+        defs: [GR8],
+        encoding: Setcc,
+    }
+    SETAE {
+        asm: "setae",
+        defs: [GR8],
+        encoding: Setcc,
+    }
+    SETA {
+        asm: "seta",
+        defs: [GR8],
+        encoding: Setcc,
+    }
+    CMOVErr {
+        asm: "cmove",
+        defs: [any_reg],
+        uses: [any_reg, any_reg],
+        tie: (0, 0),
+        encoding: Cmov,
+    }
+    CMOVNErr {
+        asm: "cmovne",
+        defs: [any_reg],
+        uses: [any_reg, any_reg],
+        tie: (0, 0),
+        encoding: Cmov,
+    }
+    CMOVGErr {
+        asm: "cmovge",
+        defs: [any_reg],
+        uses: [any_reg, any_reg],
+        tie: (0, 0),
+        encoding: Cmov,
     }
+    CMOVGrr {
+        asm: "cmovg",
+        defs: [any_reg],
+        uses: [any_reg, any_reg],
+        tie: (0, 0),
+        encoding: Cmov,
+    }
+    CMOVLErr {
+        asm: "cmovle",
+        defs: [any_reg],
+        uses: [any_reg, any_reg],
+        tie: (0, 0),
+        encoding: Cmov,
+    }
+    CMOVLrr {
+        asm: "cmovl",
+        defs: [any_reg],
+        uses: [any_reg, any_reg],
+        tie: (0, 0),
+        encoding: Cmov,
+    }
+    CMOVBErr {
+        asm: "cmovbe",
+        defs: [any_reg],
+        uses: [any_reg, any_reg],
+        tie: (0, 0),
+        encoding: Cmov,
+    }
+    CMOVBrr {
+        asm: "cmovb",
+        defs: [any_reg],
+        uses: [any_reg, any_reg],
+        tie: (0, 0),
+        encoding: Cmov,
+    }
+    CMOVAErr {
+        asm: "cmovae",
+        defs: [any_reg],
+        uses: [any_reg, any_reg],
+        tie: (0, 0),
+        encoding: Cmov,
+    }
+    CMOVArr {
+        asm: "cmova",
+        defs: [any_reg],
+        uses: [any_reg, any_reg],
+        tie: (0, 0),
+        encoding: Cmov,
+    }
+    JE {
+        asm: "je",
+        uses: [block],
+        encoding: Jcc,
+    }
+    JNE {
+        asm: "jne",
+        uses: [block],
+        encoding: Jcc,
+    }
+    JBE {
+        asm: "jbe",
+        uses: [block],
+        encoding: Jcc,
+    }
+    JB {
+        asm: "jb",
+        uses: [block],
+        encoding: Jcc,
+    }
+    JLE {
+        asm: "jle",
+        uses: [block],
+        encoding: Jcc,
+    }
+    JL {
+        asm: "jl",
+        uses: [block],
+        encoding: Jcc,
+    }
+    JA {
+        asm: "ja",
+        uses: [block],
+        encoding: Jcc,
+    }
+    JAE {
+        asm: "jae",
+        uses: [block],
+        encoding: Jcc,
+    }
+    JG {
+        asm: "jg",
+        uses: [block],
+        encoding: Jcc,
+    }
+    JGE {
+        asm: "jge",
+        uses: [block],
+        encoding: Jcc,
+    }
+    JMP {
+        asm: "jmp",
+        uses: [block],
+        encoding: Jmp(&[0xe9]),
+    }
+    JMPr {
+        asm: "jmp",
+        uses: [GR64],
+        encoding: Unary(0xff, 4),
+    }
+
+    Phi {}
+    Ret {}
+    Copy {}
+    ExtractSubreg {}
+    BrCond {}
+    AdjStackDown {}
+    AdjStackUp {}
 }
@@ -5,7 +5,7 @@ use super::super::{
 };
 use super::inst::*;
 use crate::analysis::{dom_tree::DominatorTree, loops::Loops};
-use crate::codegen::common::machine::{
+use crate::codegen::x64::common::machine::{
     basic_block::*, builder::*, function::*, liveness::*, regalloc::*,
 };
 use rustc_hash::FxHashSet;
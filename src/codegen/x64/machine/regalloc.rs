@@ -1,15 +1,15 @@
-use crate::codegen::arch::{
+pub use crate::codegen::x64::common::machine::regalloc::*;
+use crate::codegen::x64::common::machine::{
+    basic_block::MachineBasicBlockId,
+    function::MachineFunction,
+    inst::{MachineInst, MachineInstId, MachineOperand, RegisterOperand},
+};
+use crate::codegen::x64::{
     dag::mc_convert::{mov_mx, mov_rx},
     frame_object::FrameIndexInfo,
     machine::inst::MachineMemOperand,
     machine::register::*,
 };
-pub use crate::codegen::common::machine::regalloc::*;
-use crate::codegen::common::machine::{
-    basic_block::MachineBasicBlockId,
-    function::MachineFunction,
-    inst::{MachineInst, MachineInstId, MachineOperand, RegisterOperand},
-};
 
 impl RegisterAllocator {
     pub fn store_and_load_for_reg_preservation(
@@ -1,4 +1,4 @@
-pub use crate::codegen::common::machine::register::*;
+pub use crate::codegen::x64::common::machine::register::*;
 use crate::ir::types::Type;
 use defs::registers;
 use id_arena::Arena;
@@ -7,11 +7,16 @@ use std::cell::RefCell;
 
 registers! {
     // register nubmering: https://corsix.github.io/dynasm-doc/instructions.html#registers
-    class GR8  (8, i8, [i1, i8], [AL]) < GR32 {
+    class GR8  (8, i8, [i1, i8], [AL]) < GR16 {
         AL, CL, DL, BL, SPL, BPL, SIL, DIL,
         R8B, R9B, R10B, R11B, R12B, R13B, R14B, R15B
     }
 
+    class GR16 (16, i16, [i16], [AX]) < GR32 {
+        AX, CX, DX, BX, SP, BP, SI, DI,
+        R8W, R9W, R10W, R11W, R12W, R13W, R14W, R15W
+    }
+
     class GR32 (32, i32, [i32], [EAX]) < GR64 {
         EAX, ECX, EDX, EBX, ESP, EBP, ESI, EDI,
         R8D, R9D, R10D, R11D, R12D, R13D, R14D, R15D
@@ -22,6 +27,11 @@ registers! {
         R8, R9, R10, R11, R12, R13, R14, R15
     }
 
+    class FR32 (32, f32, [f32], [XMM0]) < XMM {
+        XMM0, XMM1, XMM2, XMM3, XMM4, XMM5, XMM6, XMM7,
+        XMM8, XMM9, XMM10, XMM11, XMM12, XMM13, XMM14, XMM15
+    }
+
     class XMM (128, f64, [f64], [XMM0]) {
         XMM0, XMM1, XMM2, XMM3, XMM4, XMM5, XMM6, XMM7,
         XMM8, XMM9, XMM10, XMM11, XMM12, XMM13, XMM14, XMM15
@@ -31,14 +41,18 @@ registers! {
     // TODO: This is System V AMD64 ABI.
     // https://en.wikipedia.org/wiki/X86_calling_conventions#System_V_AMD64_ABI
     order arg GR8  { DIL, SIL, DL, CL, R8B, R9B }
+    order arg GR16 { DI,  SI,  DX,  CX,  R8W, R9W }
     order arg GR32 { EDI, ESI, EDX, ECX, R8D, R9D }
     order arg GR64 { RDI, RSI, RDX, RCX, R8,  R9 }
+    order arg FR32 { XMM0, XMM1, XMM2, XMM3, XMM4, XMM5, XMM6, XMM7 }
     order arg XMM  { XMM0, XMM1, XMM2, XMM3, XMM4, XMM5, XMM6, XMM7 }
 
     // Normal order of general-purpose registers
     order gp GR8  { AL,  CL,  DL,  R8B, R9B, R10B, R11B, BL, R12B, R13B, R14B, R15B }
+    order gp GR16 { AX,  CX,  DX,  R8W, R9W, R10W, R11W, BX, R12W, R13W, R14W, R15W }
     order gp GR32 { EAX, ECX, EDX, R8D, R9D, R10D, R11D, EBX,R12D, R13D, R14D, R15D }
     order gp GR64 { RAX, RCX, RDX, R8,  R9,  R10,  R11,  RBX,R12,  R13,  R14,  R15  }
+    order gp FR32 { XMM0, XMM1, XMM2, XMM3, XMM4, XMM5, XMM6, XMM7, XMM8, XMM15 }
     order gp XMM { XMM0, XMM1, XMM2, XMM3, XMM4, XMM5, XMM6, XMM7, XMM8, XMM15 }
 }
 
@@ -52,6 +66,12 @@ thread_local! {
             GR8::R13B,
             GR8::R14B,
             GR8::R15B,
+            GR16::BX,
+            GR16::BP,
+            GR16::R12W,
+            GR16::R13W,
+            GR16::R14W,
+            GR16::R15W,
             GR32::EBX,
             GR32::EBP,
             GR32::R12D,
@@ -64,6 +84,10 @@ thread_local! {
             GR64::R13,
             GR64::R14,
             GR64::R15,
+            FR32::XMM6,
+            FR32::XMM7,
+            FR32::XMM8,
+            FR32::XMM15,
             XMM::XMM6,
             XMM::XMM7,
             XMM::XMM8,
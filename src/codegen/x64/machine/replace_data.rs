@@ -1,5 +1,5 @@
 use super::inst::*;
-use crate::codegen::common::machine::{function::*, module::*};
+use crate::codegen::x64::common::machine::{function::*, module::*};
 use crate::traits::pass::ModulePassTrait;
 
 pub struct ReplaceConstFPWithMemoryRef {}
@@ -34,14 +34,18 @@ impl ReplaceConstFPWithMemoryRef {
         for (_, bb) in cur_func.body.basic_blocks.id_and_block() {
             for inst_id in &*bb.iseq_ref() {
                 let inst = &mut cur_func.body.inst_arena[*inst_id];
-                let replace = matches!(inst.opcode, MachineOpcode::MOVSDrm64);
+                let replace = matches!(
+                    inst.opcode,
+                    MachineOpcode::MOVSDrm64 | MachineOpcode::MOVSSrm32
+                );
                 if !replace {
                     continue;
                 }
                 for operand in &mut inst.operand {
                     match operand {
-                        MachineOperand::Constant(MachineConstant::F64(f)) => {
-                            let id = cur_func.const_data.alloc(MachineConstant::F64(*f));
+                        MachineOperand::Constant(c @ MachineConstant::F32(_))
+                        | MachineOperand::Constant(c @ MachineConstant::F64(_)) => {
+                            let id = cur_func.const_data.alloc(*c);
                             *operand = MachineOperand::Mem(MachineMemOperand::Address(
                                 AddressKind::Label(id),
                             ));
@@ -4,7 +4,7 @@ use super::super::{
     machine::register::{rc2ty, RegisterId, VirtReg, GR64},
 };
 use super::inst::{MachineInst, MachineMemOperand, MachineOperand, RegisterOperand};
-use crate::codegen::common::machine::{
+use crate::codegen::x64::common::machine::{
     builder::{BuilderTrait, BuilderWithLiveInfoEdit},
     function::MachineFunction,
     liveness::{LiveRange, LiveRegMatrix, LiveSegment},
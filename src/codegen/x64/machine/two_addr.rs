@@ -1,5 +1,5 @@
 use super::inst::*;
-use crate::codegen::common::machine::{builder::*, function::*, module::*};
+use crate::codegen::x64::common::machine::{builder::*, function::*, module::*};
 use crate::traits::pass::ModulePassTrait;
 use rustc_hash::FxHashMap;
 use std::mem;
@@ -1,96 +1,34 @@
+#[path = "../common/mod.rs"]
+#[macro_use]
+pub mod common;
+
 pub mod asm;
 pub mod dag;
 pub mod exec;
 pub mod frame_object;
 pub mod machine;
 
+use crate::codegen::target::Target;
 use crate::{
-    codegen::common::machine::{
+    codegen::x64::common::machine::{
         branch_folding, eliminate_fi, module::MachineModule, phi_elimination,
     },
     ir,
     ir::module::Module,
-    ir::types::*,
     traits::pass::ModulePassManager,
 };
-
-impl TypeSize for Type {
-    fn size_in_byte(&self, tys: &Types) -> usize {
-        match self {
-            Type::i1 => 1,
-            Type::i8 => 1,
-            Type::i32 => 4,
-            Type::i64 => 8,
-            Type::f64 => 8,
-            Type::Array(id) => tys.compound_ty(*id).as_array().size_in_byte(tys),
-            Type::Struct(id) => tys.compound_ty(*id).as_struct().size_in_byte(tys),
-            Type::Pointer(_) => 8,
-            Type::Function(_) => unimplemented!(),
-            Type::Void => 0,
-        }
-    }
-
-    fn size_in_bits(&self, tys: &Types) -> usize {
-        self.size_in_byte(tys) * 8
-    }
-
-    fn align_in_byte(&self, tys: &Types) -> usize {
-        match self {
-            Type::i1 => 1,
-            Type::i8 => 1,
-            Type::i32 => 4,
-            Type::i64 => 8,
-            Type::f64 => 8,
-            Type::Array(id) => tys.compound_ty(*id).as_array().align_in_byte(tys),
-            Type::Struct(id) => tys.compound_ty(*id).as_struct().align_in_byte(tys),
-            Type::Pointer(_) => 8,
-            Type::Function(_) => unimplemented!(),
-            Type::Void => 0,
-        }
-    }
-}
-
-const MAX_ALIGN: usize = 16;
-
-impl TypeSize for ArrayType {
-    fn size_in_byte(&self, tys: &Types) -> usize {
-        self.elem_ty.size_in_byte(tys) * self.len
-    }
-
-    fn size_in_bits(&self, tys: &Types) -> usize {
-        self.size_in_byte(tys) * 8
-    }
-
-    fn align_in_byte(&self, tys: &Types) -> usize {
-        let size = self.size_in_byte(tys);
-        let align = self.elem_ty.align_in_byte(tys);
-        if size > MAX_ALIGN {
-            MAX_ALIGN
-        } else {
-            align
-        }
-    }
-}
-
-impl TypeSize for StructType {
-    fn size_in_byte(&self, _tys: &Types) -> usize {
-        self.size()
-    }
-
-    fn size_in_bits(&self, tys: &Types) -> usize {
-        self.size_in_byte(tys) * 8
-    }
-
-    fn align_in_byte(&self, _tys: &Types) -> usize {
-        self.align()
-    }
-}
+use target_lexicon::Triple;
 
 pub fn standard_conversion_into_machine_module(mut module: Module) -> MachineModule {
     ir::dce::DeadCodeElimination::new().run_on_module(&mut module);
     ir::merge_ret::MergeReturns::new().run_on_module(&mut module);
     ir::const_folding::ConstantFolding::new().run_on_module(&mut module);
     ir::inst_combine::InstructionCombine::new().run_on_module(&mut module);
+    // x64 has no conditional move between XMM registers.
+    ir::expand_select::ExpandSelect::new()
+        .only_floating_point()
+        .run_on_module(&mut module);
+    ir::lower_switch::LowerSwitch::new().run_on_module(&mut module);
     ir::codegen_prepare::CodegenPrepare::new().run_on_module(&mut module);
 
     ir::verify::verify_module(&module).unwrap();
@@ -98,18 +36,18 @@ pub fn standard_conversion_into_machine_module(mut module: Module) -> MachineMod
     // println!("{:?}", module);
 
     // let now = ::std::time::Instant::now();
-    let mut module = crate::codegen::common::dag::convert::convert_module_to_dag_module(module);
-    crate::codegen::common::dag::combine::run(&mut module);
+    let mut module = common::dag::convert::convert_module_to_dag_module(module);
+    common::dag::combine::run(&mut module);
     // println!("Initial DAG:\n{:?}", module);
-    crate::codegen::arch::dag::legalize::run(&mut module);
-    crate::codegen::arch::dag::isel::run(&mut module);
+    crate::codegen::x64::dag::legalize::run(&mut module);
+    crate::codegen::x64::dag::isel::run(&mut module);
     // debug!(println!(
     //     "after pass {:?}",
     //     ::std::time::Instant::now().duration_since(now)
     // ));
     // println!("Selected DAG:\n{:?}", module);
 
-    let mut module = crate::codegen::common::dag::mc_convert::convert_module(module);
+    let mut module = common::dag::mc_convert::convert_module(module);
 
     // println!("{:?}", module);
 
@@ -137,3 +75,19 @@ pub fn standard_conversion_into_machine_module(mut module: Module) -> MachineMod
 
     module
 }
+
+/// The x86_64 (System V ABI) target.
+pub struct X86_64;
+
+impl Target for X86_64 {
+    fn triple(&self) -> Triple {
+        "x86_64-unknown-linux-gnu".parse().unwrap()
+    }
+
+    fn compile_to_asm(&self, module: Module) -> String {
+        let machine_module = standard_conversion_into_machine_module(module);
+        let mut printer = asm::print::MachineAsmPrinter::new();
+        printer.run_on_module(&machine_module);
+        printer.output
+    }
+}
@@ -1,304 +1,774 @@
-// TODO: CAUTION: this code is no longer usable
-
-// use crate::ir::{function::*, module::*, opcode::*, types::*, value::*};
-use crate::ir::{function::*, module::*, types::*};
+use super::memory::{InterpError, Memory};
+use crate::ir::{
+    basic_block::BasicBlockId,
+    constant_pool::{ConstantArrayElement, ConstantId, ConstantKind},
+    function::*,
+    global_val::GlobalVariableId,
+    module::*,
+    opcode::*,
+    types::*,
+    value::*,
+};
 use rustc_hash::FxHashMap;
+use std::cmp::Ordering;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ConcreteValue {
     Void,
     Int1(bool),
+    Int8(i8),
+    Int16(i16),
     Int32(i32),
-    Mem(*mut u8, Type),
+    Int64(i64),
+    F32(f32),
+    F64(f64),
+    /// An address in the interpreter's `Memory`.
+    Mem(u64, Type),
 }
 
+type InternalFunc = fn(&mut Memory, &[ConcreteValue]) -> Result<ConcreteValue, InterpError>;
+
 pub struct Interpreter<'a> {
-    _module: &'a Module,
-    _internal_func: FxHashMap<String, fn(&[ConcreteValue]) -> ConcreteValue>,
+    module: &'a Module,
+    internal_func: FxHashMap<String, InternalFunc>,
+    globals: FxHashMap<GlobalVariableId, u64>,
+    consts: FxHashMap<ConstantId, u64>,
+    memory: Memory,
+}
+
+struct Frame {
+    args: Vec<ConcreteValue>,
+    vals: FxHashMap<InstructionId, ConcreteValue>,
+    // Allocas (and copies of byval arguments). Released when the function returns
+    stack: Vec<u64>,
+}
+
+enum Flow {
+    Next,
+    Jump(BasicBlockId),
+    Return(ConcreteValue),
 }
 
 impl<'a> Interpreter<'a> {
     pub fn new(module: &'a Module) -> Self {
-        Self {
-            _module: module,
-            _internal_func: {
-                vec![("sericum.println.i32".to_string(), sericum_println_i32 as _)]
-                    .into_iter()
-                    .collect::<FxHashMap<_, _>>()
+        let mut interp = Self {
+            module,
+            internal_func: {
+                vec![
+                    ("sericum.memset.p0i32.i32", sericum_memset as InternalFunc),
+                    ("sericum.println.i32", sericum_println_i32),
+                    ("sericum.print.i32", sericum_print_i32),
+                    ("sericum.printch.i32", sericum_printch_i32),
+                    ("sericum.println.f64", sericum_println_f64),
+                    ("sericum.print.f64", sericum_print_f64),
+                    ("sericum.sin.f64", sericum_sin_f64),
+                    ("sericum.cos.f64", sericum_cos_f64),
+                    ("sericum.sqrt.f64", sericum_sqrt_f64),
+                    ("sericum.floor.f64", sericum_floor_f64),
+                    ("sericum.fabs.f64", sericum_fabs_f64),
+                    ("sericum.i32_to_f64.i32", sericum_i32_to_f64),
+                    ("sericum.f64_to_i32.f64", sericum_f64_to_i32),
+                    ("sericum.malloc.i32", sericum_malloc),
+                ]
+                .into_iter()
+                .map(|(name, f)| (name.to_string(), f))
+                .collect::<FxHashMap<_, _>>()
             },
+            globals: FxHashMap::default(),
+            consts: FxHashMap::default(),
+            memory: Memory::new(),
+        };
+        interp.alloc_globals();
+        interp
+    }
+
+    /// Runs function `id`. A load, store or GEP through a bad address is an error.
+    pub fn run_function(
+        &mut self,
+        id: FunctionId,
+        args: Vec<ConcreteValue>,
+    ) -> Result<ConcreteValue, InterpError> {
+        let module = self.module;
+        let func = module.function_ref(id);
+
+        if let Some(f) = self.internal_func.get(&func.name) {
+            return f(&mut self.memory, &args);
+        }
+
+        if func.is_empty() {
+            panic!("interp: function '{}' has no body", func.name)
         }
+
+        let mut frame = Frame {
+            args,
+            vals: FxHashMap::default(),
+            stack: vec![],
+        };
+        let ret = self.run_frame(func, &mut frame);
+        for addr in frame.stack {
+            self.memory.free(addr)
+        }
+        ret
     }
 
-    // TODO: Refactor
-    pub fn run_function(&mut self, _id: FunctionId, _args: Vec<ConcreteValue>) -> ConcreteValue {
-        panic!()
-        // let f = self.module.function_ref(id);
-        //
-        // if let Some(f) = self.internal_func.get(&f.name) {
-        //     return f(&args);
-        // }
-        //
-        // let mut mem = FxHashMap::default();
-        //
-        // fn get_value(
-        //     val: &Value,
-        //     args: &[ConcreteValue],
-        //     mem: &mut FxHashMap<InstructionId, ConcreteValue>,
-        // ) -> ConcreteValue {
-        //     match val {
-        //         Value::Argument(ArgumentValue { index, .. }) => args[*index].clone(),
-        //         Value::Instruction(InstructionValue { id, .. }) => mem.get(&id).unwrap().clone(),
-        //         Value::Immediate(im) => match im {
-        //             ImmediateValue::Int32(i) => ConcreteValue::Int32(*i),
-        //             _ => unimplemented!(),
-        //         },
-        //         Value::Function(_id) => unimplemented!(),
-        //         Value::None => ConcreteValue::Void,
-        //     }
-        // }
-        //
-        // let (mut cur_bb_id, mut bb) = f.basic_block_arena.iter().next().unwrap();
-        // let mut last_bb_id = cur_bb_id;
-        //
-        // let ret = 'main: loop {
-        //     for val in &*bb.iseq.borrow() {
-        //         let inst_id = val.get_inst_id().unwrap();
-        //         let inst = &f.inst_table[inst_id];
-        //         match &inst.opcode {
-        //             Opcode::Add(v1, v2) => {
-        //                 let val =
-        //                     get_value(&v1, &args, &mut mem).add(get_value(&v2, &args, &mut mem));
-        //                 mem.insert(inst_id, val);
-        //             }
-        //             Opcode::Sub(v1, v2) => {
-        //                 let val =
-        //                     get_value(&v1, &args, &mut mem).sub(get_value(&v2, &args, &mut mem));
-        //                 mem.insert(inst_id, val);
-        //             }
-        //             Opcode::Mul(v1, v2) => {
-        //                 let val =
-        //                     get_value(&v1, &args, &mut mem).mul(get_value(&v2, &args, &mut mem));
-        //                 mem.insert(inst_id, val);
-        //             }
-        //             Opcode::Rem(v1, v2) => {
-        //                 let val =
-        //                     get_value(&v1, &args, &mut mem).rem(get_value(&v2, &args, &mut mem));
-        //                 mem.insert(inst_id, val);
-        //             }
-        //             Opcode::Alloca(ty) => {
-        //                 mem.insert(
-        //                     inst_id,
-        //                     ConcreteValue::Mem(
-        //                         match ty {
-        //                             Type::Int1 => Box::into_raw(Box::new(0u8)) as *mut u8,
-        //                             Type::Int32 => Box::into_raw(Box::new(0u32)) as *mut u8,
-        //                             Type::Int64 => Box::into_raw(Box::new(0u64)) as *mut u8,
-        //                             Type::F64 => unimplemented!(),
-        //                             Type::Pointer(_) => unimplemented!(),
-        //                             Type::Void => unreachable!(),
-        //                             Type::Function(_) => unimplemented!(),
-        //                             Type::Array(a) => unsafe {
-        //                                 let mut vec = match a.elem_ty {
-        //                                     Type::Int32 => {
-        //                                         let mut v = Vec::<*mut i32>::with_capacity(a.len);
-        //                                         for _ in 0..a.len {
-        //                                             v.push(
-        //                                                 Box::into_raw(Box::new(0i32)) as *mut i32
-        //                                             );
-        //                                         }
-        //                                         v
-        //                                     }
-        //                                     _ => unimplemented!(),
-        //                                 };
-        //                                 vec.set_len(a.len);
-        //                                 Box::into_raw(vec.into_boxed_slice()) as *mut u8
-        //                             },
-        //                             Type::Struct(_) => unimplemented!(),
-        //                         },
-        //                         ty.get_pointer_ty(),
-        //                     ),
-        //                 );
-        //             }
-        //             Opcode::ICmp(kind, v1, v2) => {
-        //                 let val =
-        //                     match kind {
-        //                         ICmpKind::Eq => get_value(&v1, &args, &mut mem)
-        //                             .eq(get_value(&v2, &args, &mut mem)),
-        //                         ICmpKind::Le => get_value(&v1, &args, &mut mem)
-        //                             .le(get_value(&v2, &args, &mut mem)),
-        //                         ICmpKind::Lt => get_value(&v1, &args, &mut mem)
-        //                             .lt(get_value(&v2, &args, &mut mem)),
-        //                     };
-        //                 mem.insert(inst_id, val);
-        //             }
-        //             Opcode::Br(id) => {
-        //                 last_bb_id = cur_bb_id;
-        //                 cur_bb_id = *id;
-        //                 bb = f.basic_block_ref(*id);
-        //                 break;
-        //             }
-        //             Opcode::CondBr(cond, bb1, bb2) => {
-        //                 let cond = get_value(&cond, &args, &mut mem).i1_as_bool().unwrap();
-        //                 bb = f.basic_block_ref({
-        //                     last_bb_id = cur_bb_id;
-        //                     cur_bb_id = if cond { *bb1 } else { *bb2 };
-        //                     cur_bb_id
-        //                 });
-        //                 break;
-        //             }
-        //             Opcode::Phi(pairs) => {
-        //                 let val = get_value(
-        //                     &pairs.iter().find(|&(_, bb)| bb == &last_bb_id).unwrap().0,
-        //                     &args,
-        //                     &mut mem,
-        //                 );
-        //                 mem.insert(inst_id, val);
-        //             }
-        //             Opcode::Call(f, f_args) => match f {
-        //                 Value::Function(FunctionValue { func_id, .. }) => {
-        //                     let val = self.run_function(
-        //                         *func_id,
-        //                         f_args
-        //                             .iter()
-        //                             .map(|arg| get_value(&arg, &args, &mut mem))
-        //                             .collect(),
-        //                     );
-        //                     if val != ConcreteValue::Void {
-        //                         mem.insert(inst_id, val);
-        //                     }
-        //                 }
-        //                 _ => unimplemented!(),
-        //             },
-        //             Opcode::Load(v) => {
-        //                 let ptr = match get_value(&v, &args, &mut mem) {
-        //                     ConcreteValue::Mem(ptr, _) => ptr,
-        //                     _ => unreachable!(),
-        //                 };
-        //                 let val = match v.get_type(self.module).get_element_ty(None).unwrap() {
-        //                     Type::Int1 => {
-        //                         ConcreteValue::Int1(if unsafe { *(ptr as *mut u8) } == 0 {
-        //                             false
-        //                         } else {
-        //                             true
-        //                         })
-        //                     }
-        //                     Type::Int32 => ConcreteValue::Int32(unsafe { *(ptr as *mut i32) }),
-        //                     _ => unimplemented!(),
-        //                 };
-        //                 mem.insert(inst_id, val);
-        //             }
-        //             Opcode::Store(src, dst) => {
-        //                 let dst_ptr = match get_value(&dst, &args, &mut mem) {
-        //                     ConcreteValue::Mem(ptr, _) => ptr,
-        //                     _ => unreachable!(),
-        //                 };
-        //                 match get_value(&src, &args, &mut mem) {
-        //                     ConcreteValue::Int32(i) => {
-        //                         unsafe { *(dst_ptr as *mut i32) = i };
-        //                     }
-        //                     _ => unimplemented!(),
-        //                 }
-        //             }
-        //             Opcode::GetElementPtr(ptrval, indices) => {
-        //                 let ptr = match get_value(&ptrval, &args, &mut mem) {
-        //                     ConcreteValue::Mem(ptr, ref ty) => {
-        //                         let mut v = ptr;
-        //                         let mut t = ty;
-        //                         for idx in indices {
-        //                             match t {
-        //                                 Type::Pointer(_) => {}
-        //                                 Type::Array(_a) => unsafe {
-        //                                     // TODO: assume _a.elem_ty is Int32 for now
-        //                                     v = *((v as *mut *mut i32).add(
-        //                                         match get_value(&idx, &args, &mut mem) {
-        //                                             ConcreteValue::Int32(i) => i as usize,
-        //                                             _ => unimplemented!(),
-        //                                         },
-        //                                     ))
-        //                                         as *mut u8
-        //                                 },
-        //                                 _ => unimplemented!(),
-        //                             }
-        //                             t = t.get_element_ty_with_indices(&vec![*idx]).unwrap();
-        //                         }
-        //                         v
-        //                     }
-        //                     _ => unreachable!(),
-        //                 };
-        //                 mem.insert(inst_id, ConcreteValue::Mem(ptr, inst.ty.clone()));
-        //             }
-        //             Opcode::Ret(v) => break 'main get_value(&v, &args, &mut mem),
-        //         }
-        //     }
-        // };
-        //
-        // ret
+    fn run_frame(
+        &mut self,
+        func: &Function,
+        frame: &mut Frame,
+    ) -> Result<ConcreteValue, InterpError> {
+        let types = &self.module.types;
+
+        // A byval argument is a copy of the pointed object
+        for i in 0..frame.args.len() {
+            match (func.get_param_attr(i), &frame.args[i]) {
+                (Some(ParamAttribute { byval: true }), &ConcreteValue::Mem(src, ty)) => {
+                    let size = pointee_ty(types, ty).size_in_byte(types);
+                    let bytes = self.memory.read(src, size)?.to_vec();
+                    let dst = self.memory.alloc(size);
+                    frame.stack.push(dst);
+                    self.memory.write(dst, &bytes)?;
+                    frame.args[i] = ConcreteValue::Mem(dst, ty)
+                }
+                _ => {}
+            }
+        }
+
+        let mut cur = func.get_entry_block().unwrap();
+        let mut prev = cur;
+
+        loop {
+            let iseq = func.basic_block_ref(cur).iseq_ref().clone();
+
+            // Phis are evaluated at once so that they see the values of the previous block
+            let num_phis = iseq
+                .iter()
+                .take_while(|&&id| func.inst_table[id].opcode == Opcode::Phi)
+                .count();
+            let phis = iseq[..num_phis]
+                .iter()
+                .map(|&id| {
+                    let phi = &func.inst_table[id];
+                    let blocks = phi.operand.blocks();
+                    let i = blocks.iter().position(|&b| b == prev).unwrap();
+                    (id, self.get_value(func, frame, &phi.operand.args()[i]))
+                })
+                .collect::<Vec<_>>();
+            frame.vals.extend(phis);
+
+            let mut next = None;
+            for &inst_id in &iseq[num_phis..] {
+                match self.run_inst(func, frame, inst_id)? {
+                    Flow::Next => {}
+                    Flow::Jump(dst) => {
+                        next = Some(dst);
+                        break;
+                    }
+                    Flow::Return(val) => return Ok(val),
+                }
+            }
+
+            prev = cur;
+            cur = next.expect("interp: basic block has no terminator");
+        }
+    }
+
+    fn run_inst(
+        &mut self,
+        func: &Function,
+        frame: &mut Frame,
+        inst_id: InstructionId,
+    ) -> Result<Flow, InterpError> {
+        let module = self.module;
+        let types = &module.types;
+        let inst = &func.inst_table[inst_id];
+        let args = inst.operand.args();
+
+        if inst.opcode == Opcode::Alloca {
+            let size = inst.operand.types()[0].size_in_byte(types);
+            let addr = self.memory.alloc(size);
+            frame.stack.push(addr);
+            frame
+                .vals
+                .insert(inst_id, ConcreteValue::Mem(addr, inst.ty));
+            return Ok(Flow::Next);
+        }
+
+        let val = |i: usize| self.get_value(func, frame, &args[i]);
+        let result = match inst.opcode {
+            Opcode::Load => load(&self.memory, val(0).as_addr(), inst.ty)?,
+            Opcode::Store => {
+                let ty = pointee_ty(types, func.get_value_type(&args[1]));
+                let (addr, v) = (val(1).as_addr(), val(0).coerce(ty));
+                store(&mut self.memory, addr, v)?;
+                return Ok(Flow::Next);
+            }
+            Opcode::GetElementPtr => {
+                let mut offset = 0i64;
+                let mut ty = func.get_value_type(&args[0]);
+                for idx in &args[1..] {
+                    match ty {
+                        Type::Struct(id) => {
+                            let i = idx.as_imm().as_int32() as usize;
+                            offset += *types
+                                .compound_ty(id)
+                                .as_struct()
+                                .get_elem_offset(i)
+                                .unwrap() as i64;
+                            ty = types.get_element_ty(ty, Some(idx)).unwrap();
+                        }
+                        _ => {
+                            ty = types.get_element_ty(ty, None).unwrap();
+                            let i = self.get_value(func, frame, idx).as_i64().unwrap();
+                            let size = ty.size_in_byte(types) as i64;
+                            offset = offset.wrapping_add(i.wrapping_mul(size));
+                        }
+                    }
+                }
+                ConcreteValue::Mem(self.memory.offset(val(0).as_addr(), offset)?, inst.ty)
+            }
+            Opcode::Add | Opcode::Sub | Opcode::Mul | Opcode::Div | Opcode::Rem => {
+                let (x, y) = (val(0).coerce(inst.ty), val(1).coerce(inst.ty));
+                if matches!(inst.opcode, Opcode::Div | Opcode::Rem) && y.as_i64() == Some(0) {
+                    return Err(InterpError::DivisionByZero);
+                }
+                match inst.opcode {
+                    Opcode::Add => x.add(y),
+                    Opcode::Sub => x.sub(y),
+                    Opcode::Mul => x.mul(y),
+                    Opcode::Div => x.div(y),
+                    Opcode::Rem => x.rem(y),
+                    _ => unreachable!(),
+                }
+            }
+            Opcode::UDiv | Opcode::URem => {
+                let (x, y) = (val(0).coerce(inst.ty), val(1).coerce(inst.ty));
+                if y.as_i64() == Some(0) {
+                    return Err(InterpError::DivisionByZero);
+                }
+                match inst.opcode {
+                    Opcode::UDiv => x.udiv(y),
+                    _ => x.urem(y),
+                }
+            }
+            Opcode::And | Opcode::Or | Opcode::Xor => {
+                let (x, y) = (val(0).coerce(inst.ty), val(1).coerce(inst.ty));
+                match inst.opcode {
+                    Opcode::And => x.and(y),
+                    Opcode::Or => x.or(y),
+                    _ => x.xor(y),
+                }
+            }
+            Opcode::Shl => val(0).shl(val(1)),
+            Opcode::LShr => val(0).lshr(val(1)),
+            Opcode::AShr => val(0).ashr(val(1)),
+            Opcode::SIToFP => ConcreteValue::from_f64(inst.ty, val(0).as_i64().unwrap() as f64),
+            Opcode::FPToSI => ConcreteValue::from_i64(inst.ty, val(0).as_f64().unwrap() as i64),
+            Opcode::UIToFP => ConcreteValue::from_f64(inst.ty, val(0).as_u64().unwrap() as f64),
+            Opcode::FPToUI => {
+                ConcreteValue::from_i64(inst.ty, val(0).as_f64().unwrap() as u64 as i64)
+            }
+            Opcode::FPExt | Opcode::FPTrunc => {
+                ConcreteValue::from_f64(inst.ty, val(0).as_f64().unwrap())
+            }
+            Opcode::Sext => ConcreteValue::from_i64(inst.ty, val(0).as_i64().unwrap()),
+            Opcode::Zext => ConcreteValue::from_i64(inst.ty, val(0).as_u64().unwrap() as i64),
+            Opcode::Trunc => ConcreteValue::from_i64(inst.ty, val(0).as_i64().unwrap()),
+            Opcode::PtrToInt => ConcreteValue::from_i64(inst.ty, val(0).as_addr() as i64),
+            Opcode::IntToPtr => ConcreteValue::Mem(val(0).as_u64().unwrap(), inst.ty),
+            Opcode::Bitcast => match val(0) {
+                ConcreteValue::Mem(addr, _) => ConcreteValue::Mem(addr, inst.ty),
+                v => v,
+            },
+            Opcode::Select => match val(0) {
+                ConcreteValue::Int1(true) => val(1),
+                _ => val(2),
+            },
+            Opcode::ICmp => {
+                // An immediate operand takes the type of the other operand
+                let ty = match args[0] {
+                    Value::Immediate(_) => func.get_value_type(&args[1]),
+                    _ => func.get_value_type(&args[0]),
+                };
+                let (x, y) = (val(0).coerce(ty), val(1).coerce(ty));
+                let kind = match inst.operand {
+                    InstOperand::IntCmp { cond, .. } => cond,
+                    _ => unreachable!(),
+                };
+                match kind {
+                    ICmpKind::Eq => x.eq(y),
+                    ICmpKind::Ne => x.ne(y),
+                    ICmpKind::Le => x.le(y),
+                    ICmpKind::Lt => x.lt(y),
+                    ICmpKind::Ge => x.ge(y),
+                    ICmpKind::Gt => x.gt(y),
+                    ICmpKind::ULe => x.ule(y),
+                    ICmpKind::ULt => x.ult(y),
+                    ICmpKind::UGe => x.uge(y),
+                    ICmpKind::UGt => x.ugt(y),
+                }
+            }
+            Opcode::FCmp => {
+                let kind = match inst.operand {
+                    InstOperand::FloatCmp { cond, .. } => cond,
+                    _ => unreachable!(),
+                };
+                // Unordered comparisons are true if either operand is NaN
+                ConcreteValue::Int1(match val(0).compare(&val(1)) {
+                    None => true,
+                    Some(ord) => match kind {
+                        FCmpKind::UEq => ord == Ordering::Equal,
+                        FCmpKind::UNe => ord != Ordering::Equal,
+                        FCmpKind::ULe => ord != Ordering::Greater,
+                        FCmpKind::ULt => ord == Ordering::Less,
+                        FCmpKind::UGe => ord != Ordering::Less,
+                        FCmpKind::UGt => ord == Ordering::Greater,
+                    },
+                })
+            }
+            Opcode::Br => return Ok(Flow::Jump(inst.operand.blocks()[0])),
+            Opcode::CondBr => {
+                let dsts = inst.operand.blocks();
+                let cond = val(0).i1_as_bool().unwrap();
+                return Ok(Flow::Jump(if cond { dsts[0] } else { dsts[1] }));
+            }
+            Opcode::Switch => {
+                let dsts = inst.operand.blocks();
+                let cond = val(0).as_i64().unwrap();
+                let case = args[1..]
+                    .iter()
+                    .position(|case| case.as_imm().to_i64() == Some(cond));
+                return Ok(Flow::Jump(case.map_or(dsts[0], |i| dsts[i + 1])));
+            }
+            Opcode::Ret => return Ok(Flow::Return(val(0).coerce(func.get_return_type()))),
+            Opcode::Alloca | Opcode::Phi => unreachable!(),
+            Opcode::Call => {
+                let callee = match args[0] {
+                    Value::Function(id) => id,
+                    _ => unimplemented!("interp: indirect call"),
+                };
+                let params_ty = types
+                    .compound_ty(module.function_ref(callee).ty)
+                    .as_function()
+                    .params_ty
+                    .clone();
+                let call_args = args[1..]
+                    .iter()
+                    .zip(params_ty)
+                    .map(|(arg, ty)| self.get_value(func, frame, arg).coerce(ty))
+                    .collect();
+                match self.run_function(callee, call_args)? {
+                    ConcreteValue::Void => return Ok(Flow::Next),
+                    ConcreteValue::Mem(addr, _) => ConcreteValue::Mem(addr, inst.ty),
+                    ret => ret,
+                }
+            }
+        };
+
+        frame.vals.insert(inst_id, result);
+        Ok(Flow::Next)
     }
+
+    fn get_value(&self, func: &Function, frame: &Frame, val: &Value) -> ConcreteValue {
+        match val {
+            Value::Argument(ArgumentValue { index, .. }) => frame.args[*index].clone(),
+            Value::Instruction(InstructionValue { id, .. }) => frame.vals[id].clone(),
+            Value::Immediate(imm) => ConcreteValue::from_imm(imm),
+            Value::Global(GlobalValue { id }) => {
+                ConcreteValue::Mem(self.globals[id], func.get_value_type(val))
+            }
+            Value::Constant(ConstantValue { id }) => {
+                ConcreteValue::Mem(self.consts[id], func.get_value_type(val))
+            }
+            Value::Function(_) => unimplemented!("interp: function pointer"),
+            Value::None => ConcreteValue::Void,
+        }
+    }
+
+    fn alloc_globals(&mut self) {
+        let types = &self.module.types;
+
+        for (id, g) in &self.module.global_vars.arena {
+            let addr = self.memory.alloc(g.ty.size_in_byte(types));
+            self.globals.insert(id, addr);
+        }
+
+        // Allocate every constant first since a constant may refer to another
+        for (id, c) in &self.module.const_pool.arena {
+            let addr = self.memory.alloc(c.ty.size_in_byte(types));
+            self.consts.insert(id, addr);
+        }
+
+        for (id, c) in &self.module.const_pool.arena {
+            let addr = self.consts[&id];
+            match &c.kind {
+                ConstantKind::String(s) => self.memory.write(addr, s.as_bytes()),
+                ConstantKind::Array(elems) => self.init_const_array(addr, c.ty, elems),
+            }
+            .expect("interp: constant does not fit in its allocation")
+        }
+    }
+
+    fn init_const_array(
+        &mut self,
+        addr: u64,
+        ty: Type,
+        elems: &[ConstantArrayElement],
+    ) -> Result<(), InterpError> {
+        let types = &self.module.types;
+        let elem_ty = types.get_element_ty(ty, None).unwrap();
+        let size = elem_ty.size_in_byte(types);
+
+        for (i, elem) in elems.iter().enumerate() {
+            let addr = self.memory.offset(addr, (i * size) as i64)?;
+            match elem {
+                ConstantArrayElement::Immediate(imm) => store(
+                    &mut self.memory,
+                    addr,
+                    ConcreteValue::from_imm(imm).coerce(elem_ty),
+                )?,
+                ConstantArrayElement::String(id) => store(
+                    &mut self.memory,
+                    addr,
+                    ConcreteValue::Mem(self.consts[id], elem_ty),
+                )?,
+                ConstantArrayElement::Array(elems) => {
+                    self.init_const_array(addr, elem_ty, elems)?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn pointee_ty(types: &Types, ty: Type) -> Type {
+    types.get_element_ty(ty, None).unwrap()
+}
+
+fn load(memory: &Memory, addr: u64, ty: Type) -> Result<ConcreteValue, InterpError> {
+    macro_rules! read {
+        ($t:ty) => {{
+            let mut buf = [0u8; ::std::mem::size_of::<$t>()];
+            buf.copy_from_slice(memory.read(addr, ::std::mem::size_of::<$t>())?);
+            <$t>::from_le_bytes(buf)
+        }};
+    }
+    Ok(match ty {
+        Type::i1 => ConcreteValue::Int1(read!(u8) != 0),
+        Type::i8 => ConcreteValue::Int8(read!(i8)),
+        Type::i16 => ConcreteValue::Int16(read!(i16)),
+        Type::i32 => ConcreteValue::Int32(read!(i32)),
+        Type::i64 => ConcreteValue::Int64(read!(i64)),
+        Type::f32 => ConcreteValue::F32(read!(f32)),
+        Type::f64 => ConcreteValue::F64(read!(f64)),
+        Type::Pointer(_) => ConcreteValue::Mem(read!(u64), ty),
+        _ => unimplemented!("interp: load of aggregate"),
+    })
+}
+
+fn store(memory: &mut Memory, addr: u64, val: ConcreteValue) -> Result<(), InterpError> {
+    match val {
+        ConcreteValue::Int1(b) => memory.write(addr, &[b as u8]),
+        ConcreteValue::Int8(i) => memory.write(addr, &i.to_le_bytes()),
+        ConcreteValue::Int16(i) => memory.write(addr, &i.to_le_bytes()),
+        ConcreteValue::Int32(i) => memory.write(addr, &i.to_le_bytes()),
+        ConcreteValue::Int64(i) => memory.write(addr, &i.to_le_bytes()),
+        ConcreteValue::F32(f) => memory.write(addr, &f.to_le_bytes()),
+        ConcreteValue::F64(f) => memory.write(addr, &f.to_le_bytes()),
+        ConcreteValue::Mem(a, _) => memory.write(addr, &a.to_le_bytes()),
+        ConcreteValue::Void => unreachable!(),
+    }
+}
+
+macro_rules! arith_op {
+    ($name:ident, $wrapping:ident, $op:tt) => {
+        pub fn $name(self, v: ConcreteValue) -> Self {
+            use ConcreteValue::*;
+            match (self, v) {
+                (Int8(x), Int8(y)) => Int8(x.$wrapping(y)),
+                (Int16(x), Int16(y)) => Int16(x.$wrapping(y)),
+                (Int32(x), Int32(y)) => Int32(x.$wrapping(y)),
+                (Int64(x), Int64(y)) => Int64(x.$wrapping(y)),
+                (F32(x), F32(y)) => F32(x $op y),
+                (F64(x), F64(y)) => F64(x $op y),
+                _ => unimplemented!(),
+            }
+        }
+    };
+}
+
+macro_rules! bit_op {
+    ($name:ident, $op:tt) => {
+        pub fn $name(self, v: ConcreteValue) -> Self {
+            use ConcreteValue::*;
+            match (self, v) {
+                (Int1(x), Int1(y)) => Int1(x $op y),
+                (Int8(x), Int8(y)) => Int8(x $op y),
+                (Int16(x), Int16(y)) => Int16(x $op y),
+                (Int32(x), Int32(y)) => Int32(x $op y),
+                (Int64(x), Int64(y)) => Int64(x $op y),
+                _ => unimplemented!(),
+            }
+        }
+    };
+}
+
+/// Shifts are performed on `$i8`/`$i16`/`$i32`/`$i64`, so unsigned types give logical shifts.
+macro_rules! shift_op {
+    ($name:ident, $wrapping:ident, $i8:ty, $i16:ty, $i32:ty, $i64:ty) => {
+        pub fn $name(self, v: ConcreteValue) -> Self {
+            use ConcreteValue::*;
+            let shift = v.as_i64().unwrap() as u32;
+            match self {
+                Int8(x) => Int8((x as $i8).$wrapping(shift) as i8),
+                Int16(x) => Int16((x as $i16).$wrapping(shift) as i16),
+                Int32(x) => Int32((x as $i32).$wrapping(shift) as i32),
+                Int64(x) => Int64((x as $i64).$wrapping(shift) as i64),
+                _ => unimplemented!(),
+            }
+        }
+    };
+}
+
+/// Like `arith_op!`, but the operands are reinterpreted as unsigned integers.
+macro_rules! unsigned_op {
+    ($name:ident, $wrapping:ident) => {
+        pub fn $name(self, v: ConcreteValue) -> Self {
+            use ConcreteValue::*;
+            match (self, v) {
+                (Int8(x), Int8(y)) => Int8((x as u8).$wrapping(y as u8) as i8),
+                (Int16(x), Int16(y)) => Int16((x as u16).$wrapping(y as u16) as i16),
+                (Int32(x), Int32(y)) => Int32((x as u32).$wrapping(y as u32) as i32),
+                (Int64(x), Int64(y)) => Int64((x as u64).$wrapping(y as u64) as i64),
+                _ => unimplemented!(),
+            }
+        }
+    };
+}
+
+macro_rules! cmp_op {
+    ($name:ident, $compare:ident, $($ord:pat)|+) => {
+        pub fn $name(self, v: ConcreteValue) -> Self {
+            ConcreteValue::Int1(matches!(self.$compare(&v).unwrap(), $($ord)|+))
+        }
+    };
 }
 
 impl ConcreteValue {
-    pub fn add(self, v: ConcreteValue) -> Self {
-        match (self, v) {
-            (ConcreteValue::Int32(i1), ConcreteValue::Int32(i2)) => ConcreteValue::Int32(i1 + i2),
-            _ => unimplemented!(),
+    pub fn from_imm(imm: &ImmediateValue) -> Self {
+        match *imm {
+            ImmediateValue::Int1(b) => ConcreteValue::Int1(b),
+            ImmediateValue::Int8(i) => ConcreteValue::Int8(i),
+            ImmediateValue::Int16(i) => ConcreteValue::Int16(i),
+            ImmediateValue::Int32(i) => ConcreteValue::Int32(i),
+            ImmediateValue::Int64(i) => ConcreteValue::Int64(i),
+            ImmediateValue::F32(f) => ConcreteValue::F32(f),
+            ImmediateValue::F64(f) => ConcreteValue::F64(f),
         }
     }
 
-    pub fn sub(self, v: ConcreteValue) -> Self {
-        match (self, v) {
-            (ConcreteValue::Int32(i1), ConcreteValue::Int32(i2)) => ConcreteValue::Int32(i1 - i2),
+    /// Rounds `f` to the floating-point type `ty`.
+    pub fn from_f64(ty: Type, f: f64) -> Self {
+        match ty {
+            Type::f32 => ConcreteValue::F32(f as f32),
+            Type::f64 => ConcreteValue::F64(f),
             _ => unimplemented!(),
         }
     }
 
-    pub fn mul(self, v: ConcreteValue) -> Self {
-        match (self, v) {
-            (ConcreteValue::Int32(i1), ConcreteValue::Int32(i2)) => ConcreteValue::Int32(i1 * i2),
+    /// Truncates `i` to the integer type `ty`.
+    pub fn from_i64(ty: Type, i: i64) -> Self {
+        match ty {
+            Type::i1 => ConcreteValue::Int1(i & 1 == 1),
+            Type::i8 => ConcreteValue::Int8(i as i8),
+            Type::i16 => ConcreteValue::Int16(i as i16),
+            Type::i32 => ConcreteValue::Int32(i as i32),
+            Type::i64 => ConcreteValue::Int64(i),
             _ => unimplemented!(),
         }
     }
 
-    pub fn rem(self, v: ConcreteValue) -> Self {
-        match (self, v) {
-            (ConcreteValue::Int32(i1), ConcreteValue::Int32(i2)) => ConcreteValue::Int32(i1 % i2),
-            _ => unimplemented!(),
+    /// Sign-extends an integer value to i64.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            ConcreteValue::Int1(b) => Some(b as i64),
+            ConcreteValue::Int8(i) => Some(i as i64),
+            ConcreteValue::Int16(i) => Some(i as i64),
+            ConcreteValue::Int32(i) => Some(i as i64),
+            ConcreteValue::Int64(i) => Some(i),
+            _ => None,
         }
     }
 
-    pub fn eq(self, v: ConcreteValue) -> Self {
-        match (self, v) {
-            (ConcreteValue::Int32(i1), ConcreteValue::Int32(i2)) => ConcreteValue::Int1(i1 == i2),
-            (ConcreteValue::Int1(i1), ConcreteValue::Int1(i2)) => ConcreteValue::Int1(i1 == i2),
-            _ => unimplemented!(),
+    /// Zero-extends an integer value to u64.
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            ConcreteValue::Int1(b) => Some(b as u64),
+            ConcreteValue::Int8(i) => Some(i as u8 as u64),
+            ConcreteValue::Int16(i) => Some(i as u16 as u64),
+            ConcreteValue::Int32(i) => Some(i as u32 as u64),
+            ConcreteValue::Int64(i) => Some(i as u64),
+            _ => None,
         }
     }
 
-    pub fn le(self, v: ConcreteValue) -> Self {
-        match (self, v) {
-            (ConcreteValue::Int32(i1), ConcreteValue::Int32(i2)) => ConcreteValue::Int1(i1 <= i2),
-            _ => unimplemented!(),
+    /// Widens a floating-point value to f64.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            ConcreteValue::F32(f) => Some(f as f64),
+            ConcreteValue::F64(f) => Some(f),
+            _ => None,
         }
     }
 
-    pub fn lt(self, v: ConcreteValue) -> Self {
-        match (self, v) {
-            (ConcreteValue::Int32(i1), ConcreteValue::Int32(i2)) => ConcreteValue::Int1(i1 < i2),
-            _ => unimplemented!(),
+    pub fn as_addr(&self) -> u64 {
+        match *self {
+            ConcreteValue::Mem(addr, _) => addr,
+            _ => panic!("interp: not a pointer: {:?}", self),
         }
     }
 
+    /// Converts an integer to the integer type `ty`. Other values are returned as they are.
+    pub fn coerce(self, ty: Type) -> Self {
+        match self.as_i64() {
+            Some(i) if ty.is_integer() => Self::from_i64(ty, i),
+            _ => self,
+        }
+    }
+
+    arith_op!(add, wrapping_add, +);
+    arith_op!(sub, wrapping_sub, -);
+    arith_op!(mul, wrapping_mul, *);
+    arith_op!(div, wrapping_div, /);
+    arith_op!(rem, wrapping_rem, %);
+    unsigned_op!(udiv, wrapping_div);
+    unsigned_op!(urem, wrapping_rem);
+
+    bit_op!(and, &);
+    bit_op!(or, |);
+    bit_op!(xor, ^);
+    shift_op!(shl, wrapping_shl, i8, i16, i32, i64);
+    shift_op!(lshr, wrapping_shr, u8, u16, u32, u64);
+    shift_op!(ashr, wrapping_shr, i8, i16, i32, i64);
+
+    cmp_op!(eq, compare, Ordering::Equal);
+    cmp_op!(ne, compare, Ordering::Less | Ordering::Greater);
+    cmp_op!(le, compare, Ordering::Less | Ordering::Equal);
+    cmp_op!(lt, compare, Ordering::Less);
+    cmp_op!(ge, compare, Ordering::Greater | Ordering::Equal);
+    cmp_op!(gt, compare, Ordering::Greater);
+    cmp_op!(ule, ucompare, Ordering::Less | Ordering::Equal);
+    cmp_op!(ult, ucompare, Ordering::Less);
+    cmp_op!(uge, ucompare, Ordering::Greater | Ordering::Equal);
+    cmp_op!(ugt, ucompare, Ordering::Greater);
+
     pub fn i1_as_bool(self) -> Option<bool> {
         match self {
             ConcreteValue::Int1(b) => Some(b),
             _ => None,
         }
     }
-}
 
-fn sericum_println_i32(args: &[ConcreteValue]) -> ConcreteValue {
-    match args[0] {
-        ConcreteValue::Int32(i) => println!("{}", i),
-        _ => unimplemented!(),
+    fn compare(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (ConcreteValue::F32(x), ConcreteValue::F32(y)) => x.partial_cmp(y),
+            (ConcreteValue::F64(x), ConcreteValue::F64(y)) => x.partial_cmp(y),
+            (ConcreteValue::Mem(x, _), ConcreteValue::Mem(y, _)) => x.partial_cmp(y),
+            (x, y) => x.as_i64()?.partial_cmp(&y.as_i64()?),
+        }
+    }
+
+    fn ucompare(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (ConcreteValue::Mem(x, _), ConcreteValue::Mem(y, _)) => x.partial_cmp(y),
+            (x, y) => x.as_u64()?.partial_cmp(&y.as_u64()?),
+        }
     }
-    ConcreteValue::Void
+}
+
+/// Like the JIT's, `count` is the number of `i32` elements to fill.
+fn sericum_memset(
+    memory: &mut Memory,
+    args: &[ConcreteValue],
+) -> Result<ConcreteValue, InterpError> {
+    let x = args[1].as_i64().unwrap() as u8;
+    let count = args[2].as_i64().unwrap() as usize;
+    memory.write(args[0].as_addr(), &vec![x; count * 4])?;
+    Ok(ConcreteValue::Void)
+}
+
+fn sericum_println_i32(
+    _: &mut Memory,
+    args: &[ConcreteValue],
+) -> Result<ConcreteValue, InterpError> {
+    println!("{}", args[0].as_i64().unwrap());
+    Ok(ConcreteValue::Void)
+}
+
+fn sericum_print_i32(_: &mut Memory, args: &[ConcreteValue]) -> Result<ConcreteValue, InterpError> {
+    print!("{}", args[0].as_i64().unwrap());
+    Ok(ConcreteValue::Void)
+}
+
+fn sericum_printch_i32(
+    _: &mut Memory,
+    args: &[ConcreteValue],
+) -> Result<ConcreteValue, InterpError> {
+    print!("{}", args[0].as_i64().unwrap() as u8 as char);
+    Ok(ConcreteValue::Void)
+}
+
+fn sericum_println_f64(
+    _: &mut Memory,
+    args: &[ConcreteValue],
+) -> Result<ConcreteValue, InterpError> {
+    println!("{}", args[0].as_f64().unwrap());
+    Ok(ConcreteValue::Void)
+}
+
+fn sericum_print_f64(_: &mut Memory, args: &[ConcreteValue]) -> Result<ConcreteValue, InterpError> {
+    print!("{}", args[0].as_f64().unwrap());
+    Ok(ConcreteValue::Void)
+}
+
+fn sericum_sin_f64(_: &mut Memory, args: &[ConcreteValue]) -> Result<ConcreteValue, InterpError> {
+    Ok(ConcreteValue::F64(args[0].as_f64().unwrap().sin()))
+}
+
+fn sericum_cos_f64(_: &mut Memory, args: &[ConcreteValue]) -> Result<ConcreteValue, InterpError> {
+    Ok(ConcreteValue::F64(args[0].as_f64().unwrap().cos()))
+}
+
+fn sericum_sqrt_f64(_: &mut Memory, args: &[ConcreteValue]) -> Result<ConcreteValue, InterpError> {
+    Ok(ConcreteValue::F64(args[0].as_f64().unwrap().sqrt()))
+}
+
+fn sericum_floor_f64(_: &mut Memory, args: &[ConcreteValue]) -> Result<ConcreteValue, InterpError> {
+    Ok(ConcreteValue::F64(args[0].as_f64().unwrap().floor()))
+}
+
+fn sericum_fabs_f64(_: &mut Memory, args: &[ConcreteValue]) -> Result<ConcreteValue, InterpError> {
+    Ok(ConcreteValue::F64(args[0].as_f64().unwrap().abs()))
+}
+
+fn sericum_i32_to_f64(
+    _: &mut Memory,
+    args: &[ConcreteValue],
+) -> Result<ConcreteValue, InterpError> {
+    Ok(ConcreteValue::F64(args[0].as_i64().unwrap() as f64))
+}
+
+fn sericum_f64_to_i32(
+    _: &mut Memory,
+    args: &[ConcreteValue],
+) -> Result<ConcreteValue, InterpError> {
+    Ok(ConcreteValue::Int32(args[0].as_f64().unwrap() as i32))
+}
+
+// Like the JIT's, memory returned by malloc is never freed
+fn sericum_malloc(
+    memory: &mut Memory,
+    args: &[ConcreteValue],
+) -> Result<ConcreteValue, InterpError> {
+    let size = args[0].as_i64().unwrap() as usize;
+    Ok(ConcreteValue::Mem(memory.alloc(size), Type::Void))
 }
@@ -0,0 +1,99 @@
+/// Address space of the interpreted program.
+///
+/// An address holds the index of an allocation (starting from 1, so that 0 is null) in the upper
+/// 32 bits and an offset into it in the lower 32 bits, so that every access is checked against
+/// the bounds and the lifetime of the allocation it points into.
+pub struct Memory {
+    allocations: Vec<Allocation>,
+}
+
+struct Allocation {
+    bytes: Vec<u8>,
+    live: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterpError {
+    /// The address points into no allocation, e.g. null.
+    InvalidAddress(u64),
+    /// The access of `size` bytes at `addr` goes out of the allocation `addr` points into.
+    OutOfBounds { addr: u64, size: usize },
+    /// The allocation has been released, e.g. an alloca of a function that has returned.
+    UseAfterFree(u64),
+    /// The divisor of an integer division or remainder is zero.
+    DivisionByZero,
+}
+
+const OFFSET_BITS: u32 = 32;
+const OFFSET_MASK: u64 = (1 << OFFSET_BITS) - 1;
+
+impl Memory {
+    pub fn new() -> Self {
+        Self {
+            allocations: vec![],
+        }
+    }
+
+    /// Allocates `size` bytes filled with zero and returns the address.
+    pub fn alloc(&mut self, size: usize) -> u64 {
+        assert!((size as u64) <= OFFSET_MASK, "interp: too large allocation");
+        self.allocations.push(Allocation {
+            bytes: vec![0; size],
+            live: true,
+        });
+        (self.allocations.len() as u64) << OFFSET_BITS
+    }
+
+    /// Releases the allocation starting at `addr`. Accessing it later is an error.
+    pub fn free(&mut self, addr: u64) {
+        let allocation = &mut self.allocations[(addr >> OFFSET_BITS) as usize - 1];
+        allocation.live = false;
+        allocation.bytes = vec![];
+    }
+
+    pub fn read(&self, addr: u64, size: usize) -> Result<&[u8], InterpError> {
+        let (allocation, offset) = self.allocation(addr)?;
+        allocation
+            .bytes
+            .get(offset..offset + size)
+            .ok_or(InterpError::OutOfBounds { addr, size })
+    }
+
+    pub fn write(&mut self, addr: u64, bytes: &[u8]) -> Result<(), InterpError> {
+        let (_, offset) = self.allocation(addr)?;
+        let size = bytes.len();
+        let i = (addr >> OFFSET_BITS) as usize - 1;
+        self.allocations[i]
+            .bytes
+            .get_mut(offset..offset + size)
+            .ok_or(InterpError::OutOfBounds { addr, size })?
+            .copy_from_slice(bytes);
+        Ok(())
+    }
+
+    /// Returns `addr` moved by `offset` bytes. The result must point into the same allocation or
+    /// just past its end.
+    pub fn offset(&self, addr: u64, offset: i64) -> Result<u64, InterpError> {
+        let (allocation, base) = self.allocation(addr)?;
+        let new = base as i64 + offset;
+        if new < 0 || new as usize > allocation.bytes.len() {
+            return Err(InterpError::OutOfBounds {
+                addr: (addr & !OFFSET_MASK).wrapping_add(new as u64),
+                size: 0,
+            });
+        }
+        Ok((addr & !OFFSET_MASK) | new as u64)
+    }
+
+    fn allocation(&self, addr: u64) -> Result<(&Allocation, usize), InterpError> {
+        let i = (addr >> OFFSET_BITS) as usize;
+        let allocation = match i.checked_sub(1).and_then(|i| self.allocations.get(i)) {
+            Some(allocation) => allocation,
+            None => return Err(InterpError::InvalidAddress(addr)),
+        };
+        if !allocation.live {
+            return Err(InterpError::UseAfterFree(addr));
+        }
+        Ok((allocation, (addr & OFFSET_MASK) as usize))
+    }
+}
@@ -1,15 +1,33 @@
+use crate::analysis::Analysis;
 use crate::ir::{
     function::Function,
     module::Module,
-    opcode::Opcode,
+    opcode::{InstructionId, Opcode},
+    types::{Type, TypeSize},
     value::{InstructionValue, Value},
 };
-use rustc_hash::{FxHashMap, FxHashSet};
+use rustc_hash::FxHashSet;
+use std::any::Any;
 
+/// Computes `AliasInfo` for functions and stores it in them.
+///
+/// Passes that transform a function may leave the stored info stale (e.g. allocas created later
+/// are unknown to it and treated conservatively), so those consulting it should run this first.
 pub struct AliasAnalysis {}
 
-struct AliasAnalysisOnFunction<'a> {
-    func: &'a Function,
+/// Alias information of a function, queried with `alias`.
+#[derive(Debug, Clone)]
+pub struct AliasInfo {
+    /// Allocas whose address is only used to load from and store to, so that no pointer other
+    /// than those derived from them and no callee can access them.
+    non_escaping: FxHashSet<InstructionId>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AliasResult {
+    NoAlias,
+    MayAlias,
+    MustAlias,
 }
 
 impl AliasAnalysis {
@@ -19,48 +37,205 @@ impl AliasAnalysis {
 
     pub fn run_on_module(&mut self, module: &mut Module) {
         for (_, func) in &mut module.functions {
-            AliasAnalysisOnFunction { func }.run()
+            if func.is_internal || func.is_empty() {
+                continue;
+            }
+            self.run_on_function(func)
         }
     }
-}
 
-impl<'a> AliasAnalysisOnFunction<'a> {
-    pub fn run(&mut self) {
-        let mut alloca = FxHashSet::default();
-        let mut points_to = FxHashMap::default();
+    pub fn run_on_function(&mut self, func: &mut Function) {
+        let mut non_escaping = FxHashSet::default();
+        for &block in &func.basic_blocks.order {
+            for &id in &*func.basic_blocks.arena[block].iseq_ref() {
+                if func.inst_table[id].opcode == Opcode::Alloca && !escapes(func, id) {
+                    non_escaping.insert(id);
+                }
+            }
+        }
+        func.add_analysis(AliasInfo { non_escaping })
+    }
+}
 
-        for &block_id in &self.func.basic_blocks.order {
-            let block = &self.func.basic_blocks.arena[block_id];
-            for &inst_id in &*block.iseq_ref() {
-                let inst = &self.func.inst_table[inst_id];
+impl AliasInfo {
+    /// Returns whether the memory accessed through pointers `a` and `b` may overlap.
+    ///
+    /// `MustAlias` means they point to the same address, regardless of the size of the accesses.
+    pub fn alias(&self, func: &Function, a: Value, b: Value) -> AliasResult {
+        let (base_a, offset_a) = decompose(func, a);
+        let (base_b, offset_b) = decompose(func, b);
 
-                match inst.opcode {
-                    Opcode::Alloca => {
-                        alloca.insert(inst_id);
-                    }
-                    Opcode::Load => {
-                        let mem = inst.operand.args()[0];
-                        match mem {
-                            Value::Instruction(InstructionValue { id, .. })
-                                if alloca.contains(&id) =>
-                            {
-                                points_to.insert(inst_id, id);
-                            }
-                            Value::Instruction(InstructionValue { id, .. })
-                                if points_to.contains_key(&id) =>
-                            {
-                                points_to.insert(inst_id, id);
-                            }
-                            _ => {
-                                todo!("???")
-                            }
-                        }
+        if base_a == base_b {
+            return match (offset_a, offset_b) {
+                (Some(x), Some(y)) if x == y => AliasResult::MustAlias,
+                (Some(x), Some(y)) => match (access_size(func, a), access_size(func, b)) {
+                    (Some(size_a), Some(size_b)) if x + size_a <= y || y + size_b <= x => {
+                        AliasResult::NoAlias
                     }
-                    _ => {
-                        panic!()
-                    } // Opcode
+                    _ => AliasResult::MayAlias,
+                },
+                _ => AliasResult::MayAlias,
+            };
+        }
+
+        if is_identified_object(func, base_a) && is_identified_object(func, base_b) {
+            return AliasResult::NoAlias;
+        }
+
+        // No other pointer can be derived from a non-escaping alloca, and the arguments are
+        // passed before any alloca of the function is created.
+        for &(x, y) in &[(base_a, base_b), (base_b, base_a)] {
+            if self.is_non_escaping_alloca(x) || is_alloca(func, x) && is_argument(y) {
+                return AliasResult::NoAlias;
+            }
+        }
+
+        AliasResult::MayAlias
+    }
+
+    /// Returns true if `ptr` points into an alloca no callee can access.
+    pub fn points_to_local(&self, func: &Function, ptr: Value) -> bool {
+        self.is_non_escaping_alloca(decompose(func, ptr).0)
+    }
+
+    /// Returns true if loading from `ptr` can never trap, i.e. it points into an alloca or a
+    /// global at a constant offset within its bounds.
+    pub fn is_dereferenceable(&self, func: &Function, ptr: Value) -> bool {
+        let (base, offset) = decompose(func, ptr);
+        let object_ty = match base {
+            Value::Instruction(InstructionValue { id, .. })
+                if func.inst_table[id].opcode == Opcode::Alloca =>
+            {
+                func.inst_table[id].operand.types()[0]
+            }
+            Value::Global(_) => match func.types.get_element_ty(func.get_value_type(&base), None) {
+                Some(ty) => ty,
+                None => return false,
+            },
+            _ => return false,
+        };
+        match (offset, access_size(func, ptr), sized(object_ty)) {
+            (Some(offset), Some(size), true) => {
+                0 <= offset && offset + size <= object_ty.size_in_byte(&func.types) as i64
+            }
+            _ => false,
+        }
+    }
+
+    fn is_non_escaping_alloca(&self, val: Value) -> bool {
+        matches!(val, Value::Instruction(InstructionValue { id, .. }) if self.non_escaping.contains(&id))
+    }
+}
+
+impl Analysis for AliasInfo {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Returns the value `ptr` is based on, looking through GEPs and bitcasts, and the offset in
+/// bytes of `ptr` from it if constant.
+fn decompose(func: &Function, mut ptr: Value) -> (Value, Option<i64>) {
+    let mut offset = Some(0);
+    while let Value::Instruction(InstructionValue { id, .. }) = ptr {
+        let inst = &func.inst_table[id];
+        match inst.opcode {
+            Opcode::GetElementPtr => {
+                let gep_offset = gep_offset(func, inst.operand.args());
+                offset = offset.and_then(|offset| Some(offset + gep_offset?))
+            }
+            Opcode::Bitcast => {}
+            _ => break,
+        }
+        ptr = inst.operand.args()[0];
+    }
+    (ptr, offset)
+}
+
+fn gep_offset(func: &Function, args: &[Value]) -> Option<i64> {
+    let types = &func.types;
+    let mut ty = func.get_value_type(&args[0]);
+    let mut offset = 0;
+    for idx in &args[1..] {
+        let i = match idx {
+            Value::Immediate(imm) => imm.to_i64()?,
+            _ => return None,
+        };
+        match ty {
+            Type::Struct(id) => {
+                offset += *types
+                    .compound_ty(id)
+                    .as_struct()
+                    .get_elem_offset(i as usize)? as i64;
+                ty = types.get_element_ty(ty, Some(idx))?
+            }
+            _ => {
+                ty = types.get_element_ty(ty, None)?;
+                if !sized(ty) {
+                    return None;
+                }
+                offset += i * ty.size_in_byte(types) as i64
+            }
+        }
+    }
+    Some(offset)
+}
+
+/// Returns the size in bytes of the value `ptr` points to.
+fn access_size(func: &Function, ptr: Value) -> Option<i64> {
+    match func.types.get_element_ty(func.get_value_type(&ptr), None) {
+        Some(ty) if sized(ty) => Some(ty.size_in_byte(&func.types) as i64),
+        _ => None,
+    }
+}
+
+fn sized(ty: Type) -> bool {
+    !matches!(ty, Type::Void | Type::Function(_))
+}
+
+/// Returns true if the address of alloca `id` may be used other than to load from or store to.
+fn escapes(func: &Function, id: InstructionId) -> bool {
+    let mut worklist = vec![id];
+    while let Some(ptr) = worklist.pop() {
+        let ptr_val = Value::Instruction(InstructionValue {
+            func_id: func.id.unwrap(),
+            id: ptr,
+        });
+        for &user in &*func.inst_table[ptr].users.borrow() {
+            let inst = &func.inst_table[user];
+            let args = inst.operand.args();
+            match inst.opcode {
+                Opcode::Load | Opcode::ICmp => {}
+                Opcode::Store if args[0] != ptr_val => {}
+                Opcode::GetElementPtr | Opcode::Bitcast
+                    if args[1..].iter().all(|&a| a != ptr_val) =>
+                {
+                    worklist.push(user)
                 }
+                _ => return true,
             }
         }
     }
+    false
+}
+
+fn is_alloca(func: &Function, val: Value) -> bool {
+    matches!(val, Value::Instruction(InstructionValue { id, .. }) if func.inst_table[id].opcode == Opcode::Alloca)
+}
+
+fn is_argument(val: Value) -> bool {
+    matches!(val, Value::Argument(_))
+}
+
+/// Returns true if `val` is the start of an object distinct from any other identified object.
+fn is_identified_object(func: &Function, val: Value) -> bool {
+    is_alloca(func, val)
+        || matches!(
+            val,
+            Value::Global(_) | Value::Constant(_) | Value::Function(_)
+        )
 }
@@ -279,10 +279,32 @@ pub trait IRBuilder {
         inst
     }
 
+    fn build_udiv(&mut self, v1: Value, v2: Value) -> Value {
+        if let Some(konst) = v1.const_udiv(&v2) {
+            return konst;
+        }
+
+        let ty = self.func_ref().get_value_type(&v1);
+        let inst = self.create_inst_value(Opcode::UDiv, InstOperand::Binary { args: [v1, v2] }, ty);
+        self.append_inst_to_current_block(inst.as_instruction().id);
+        inst
+    }
+
+    fn build_urem(&mut self, v1: Value, v2: Value) -> Value {
+        if let Some(konst) = v1.const_urem(&v2) {
+            return konst;
+        }
+
+        let ty = self.func_ref().get_value_type(&v1);
+        let inst = self.create_inst_value(Opcode::URem, InstOperand::Binary { args: [v1, v2] }, ty);
+        self.append_inst_to_current_block(inst.as_instruction().id);
+        inst
+    }
+
     fn build_shl(&mut self, v1: Value, v2: Value) -> Value {
-        // if let Some(konst) = v1.const_shl(&v2) {
-        //     return konst;
-        // }
+        if let Some(konst) = v1.const_shl(&v2) {
+            return konst;
+        }
 
         let ty = self.func_ref().get_value_type(&v1);
         let inst = self.create_inst_value(Opcode::Shl, InstOperand::Binary { args: [v1, v2] }, ty);
@@ -290,6 +312,61 @@ pub trait IRBuilder {
         inst
     }
 
+    fn build_lshr(&mut self, v1: Value, v2: Value) -> Value {
+        if let Some(konst) = v1.const_lshr(&v2) {
+            return konst;
+        }
+
+        let ty = self.func_ref().get_value_type(&v1);
+        let inst = self.create_inst_value(Opcode::LShr, InstOperand::Binary { args: [v1, v2] }, ty);
+        self.append_inst_to_current_block(inst.as_instruction().id);
+        inst
+    }
+
+    fn build_ashr(&mut self, v1: Value, v2: Value) -> Value {
+        if let Some(konst) = v1.const_ashr(&v2) {
+            return konst;
+        }
+
+        let ty = self.func_ref().get_value_type(&v1);
+        let inst = self.create_inst_value(Opcode::AShr, InstOperand::Binary { args: [v1, v2] }, ty);
+        self.append_inst_to_current_block(inst.as_instruction().id);
+        inst
+    }
+
+    fn build_and(&mut self, v1: Value, v2: Value) -> Value {
+        if let Some(konst) = v1.const_and(&v2) {
+            return konst;
+        }
+
+        let ty = self.func_ref().get_value_type(&v1);
+        let inst = self.create_inst_value(Opcode::And, InstOperand::Binary { args: [v1, v2] }, ty);
+        self.append_inst_to_current_block(inst.as_instruction().id);
+        inst
+    }
+
+    fn build_or(&mut self, v1: Value, v2: Value) -> Value {
+        if let Some(konst) = v1.const_or(&v2) {
+            return konst;
+        }
+
+        let ty = self.func_ref().get_value_type(&v1);
+        let inst = self.create_inst_value(Opcode::Or, InstOperand::Binary { args: [v1, v2] }, ty);
+        self.append_inst_to_current_block(inst.as_instruction().id);
+        inst
+    }
+
+    fn build_xor(&mut self, v1: Value, v2: Value) -> Value {
+        if let Some(konst) = v1.const_xor(&v2) {
+            return konst;
+        }
+
+        let ty = self.func_ref().get_value_type(&v1);
+        let inst = self.create_inst_value(Opcode::Xor, InstOperand::Binary { args: [v1, v2] }, ty);
+        self.append_inst_to_current_block(inst.as_instruction().id);
+        inst
+    }
+
     fn build_sitofp(&mut self, v: Value, ty: Type) -> Value {
         let inst = self.create_inst_value(Opcode::SIToFP, InstOperand::Cast { arg: v }, ty);
         self.append_inst_to_current_block(inst.as_instruction().id);
@@ -302,18 +379,90 @@ pub trait IRBuilder {
         inst
     }
 
+    fn build_uitofp(&mut self, v: Value, ty: Type) -> Value {
+        let inst = self.create_inst_value(Opcode::UIToFP, InstOperand::Cast { arg: v }, ty);
+        self.append_inst_to_current_block(inst.as_instruction().id);
+        inst
+    }
+
+    fn build_fptoui(&mut self, v: Value, ty: Type) -> Value {
+        let inst = self.create_inst_value(Opcode::FPToUI, InstOperand::Cast { arg: v }, ty);
+        self.append_inst_to_current_block(inst.as_instruction().id);
+        inst
+    }
+
+    fn build_fpext(&mut self, v: Value, ty: Type) -> Value {
+        if let Value::Immediate(imm) = v {
+            if let Some(konst) = imm.fp_convert_to(ty) {
+                return konst;
+            }
+        }
+
+        let inst = self.create_inst_value(Opcode::FPExt, InstOperand::Cast { arg: v }, ty);
+        self.append_inst_to_current_block(inst.as_instruction().id);
+        inst
+    }
+
+    fn build_fptrunc(&mut self, v: Value, ty: Type) -> Value {
+        if let Value::Immediate(imm) = v {
+            if let Some(konst) = imm.fp_convert_to(ty) {
+                return konst;
+            }
+        }
+
+        let inst = self.create_inst_value(Opcode::FPTrunc, InstOperand::Cast { arg: v }, ty);
+        self.append_inst_to_current_block(inst.as_instruction().id);
+        inst
+    }
+
     fn build_sext(&mut self, v: Value, ty: Type) -> Value {
+        if let Value::Immediate(imm) = v {
+            if let Some(konst) = imm.extend_to(ty, false) {
+                return konst;
+            }
+        }
+
         let inst = self.create_inst_value(Opcode::Sext, InstOperand::Cast { arg: v }, ty);
         self.append_inst_to_current_block(inst.as_instruction().id);
         inst
     }
 
     fn build_zext(&mut self, v: Value, ty: Type) -> Value {
+        if let Value::Immediate(imm) = v {
+            if let Some(konst) = imm.extend_to(ty, true) {
+                return konst;
+            }
+        }
+
         let inst = self.create_inst_value(Opcode::Zext, InstOperand::Cast { arg: v }, ty);
         self.append_inst_to_current_block(inst.as_instruction().id);
         inst
     }
 
+    fn build_trunc(&mut self, v: Value, ty: Type) -> Value {
+        if let Value::Immediate(imm) = v {
+            if let Some(konst) = imm.truncate_to(ty) {
+                return konst;
+            }
+        }
+
+        let inst = self.create_inst_value(Opcode::Trunc, InstOperand::Cast { arg: v }, ty);
+        self.append_inst_to_current_block(inst.as_instruction().id);
+        inst
+    }
+
+    fn build_ptrtoint(&mut self, v: Value, ty: Type) -> Value {
+        let inst = self.create_inst_value(Opcode::PtrToInt, InstOperand::Cast { arg: v }, ty);
+        self.append_inst_to_current_block(inst.as_instruction().id);
+        inst
+    }
+
+    fn build_inttoptr(&mut self, v: Value, ty: Type) -> Value {
+        let inst = self.create_inst_value(Opcode::IntToPtr, InstOperand::Cast { arg: v }, ty);
+        self.append_inst_to_current_block(inst.as_instruction().id);
+        inst
+    }
+
     fn build_bitcast(&mut self, v: Value, ty: Type) -> Value {
         let inst = self.create_inst_value(Opcode::Bitcast, InstOperand::Cast { arg: v }, ty);
         self.append_inst_to_current_block(inst.as_instruction().id);
@@ -388,6 +537,38 @@ pub trait IRBuilder {
         inst
     }
 
+    fn build_switch(
+        &mut self,
+        cond: Value,
+        default: BasicBlockId,
+        cases: Vec<(Value, BasicBlockId)>,
+    ) -> Value {
+        let cur_bb_id = self.block().unwrap();
+        let mut args = vec![cond];
+        let mut blocks = vec![default];
+        for (v, bb) in cases {
+            args.push(v);
+            blocks.push(bb);
+        }
+        let inst = self.create_inst_value(
+            Opcode::Switch,
+            InstOperand::Switch {
+                args,
+                blocks: blocks.clone(),
+            },
+            Type::Void,
+        );
+        self.append_inst_to_current_block(inst.as_instruction().id);
+
+        self.with_function(|f| {
+            for bb in blocks {
+                f.basic_blocks.make_edge(cur_bb_id, bb);
+            }
+        });
+
+        inst
+    }
+
     fn build_phi(&mut self, pairs: Vec<(Value, BasicBlockId)>) -> Value {
         let ty = self.func_ref().get_value_type(&pairs.get(0).unwrap().0);
         let mut blocks = vec![];
@@ -401,6 +582,26 @@ pub trait IRBuilder {
         inst
     }
 
+    fn build_select(&mut self, cond: Value, v1: Value, v2: Value) -> Value {
+        match cond {
+            Value::Immediate(ImmediateValue::Int1(true)) => return v1,
+            Value::Immediate(ImmediateValue::Int1(false)) => return v2,
+            _ if v1 == v2 => return v1,
+            _ => {}
+        }
+
+        let ty = self.func_ref().get_value_type(&v1);
+        let inst = self.create_inst_value(
+            Opcode::Select,
+            InstOperand::Select {
+                args: [cond, v1, v2],
+            },
+            ty,
+        );
+        self.append_inst_to_current_block(inst.as_instruction().id);
+        inst
+    }
+
     fn build_call(&mut self, f: Value, args: Vec<Value>) -> Value {
         let f_ptr_ty = self.func_ref().get_value_type(&f);
         let f_ty = *self.func_ref().types.compound_ty(f_ptr_ty).as_pointer();
@@ -137,7 +137,24 @@ impl<'a> ConstantFoldingOnFunction<'a> {
     fn is_foldable(inst: &Instruction) -> bool {
         matches!(
             inst.opcode,
-            Opcode::Add | Opcode::Sub | Opcode::Mul | Opcode::Div | Opcode::ICmp | Opcode::Zext
+            Opcode::Add
+                | Opcode::Sub
+                | Opcode::Mul
+                | Opcode::Div
+                | Opcode::UDiv
+                | Opcode::URem
+                | Opcode::ICmp
+                | Opcode::FCmp
+                | Opcode::Sext
+                | Opcode::Zext
+                | Opcode::Trunc
+                | Opcode::Select
+                | Opcode::SIToFP
+                | Opcode::UIToFP
+                | Opcode::FPToSI
+                | Opcode::FPToUI
+                | Opcode::FPExt
+                | Opcode::FPTrunc
         ) && inst
             .operand
             .args()
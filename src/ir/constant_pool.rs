@@ -68,7 +68,7 @@ impl fmt::Debug for ConstantPool {
 impl fmt::Debug for ConstantKind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Self::String(s) => write!(f, "\"{}\"", s),
+            Self::String(s) => write!(f, "{:?}", s),
             Self::Array(es) => {
                 write!(f, "{{")?;
                 for e in es {
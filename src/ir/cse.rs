@@ -88,10 +88,22 @@ impl<'a> GlobalCommonSubexprEliminationOnFunction<'a> {
                     | Opcode::Mul
                     | Opcode::Div
                     | Opcode::Rem
+                    | Opcode::UDiv
+                    | Opcode::URem
                     | Opcode::Shl
+                    | Opcode::LShr
+                    | Opcode::AShr
+                    | Opcode::And
+                    | Opcode::Or
+                    | Opcode::Xor
                     | Opcode::SIToFP
                     | Opcode::FPToSI
+                    | Opcode::UIToFP
+                    | Opcode::FPToUI
                     | Opcode::Sext
+                    | Opcode::Zext
+                    | Opcode::Trunc
+                    | Opcode::Select
             ) {
                 commons
                     .entry(inst.opcode)
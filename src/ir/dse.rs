@@ -0,0 +1,113 @@
+use crate::ir::{
+    alias_analysis::{AliasAnalysis, AliasInfo, AliasResult},
+    function::Function,
+    module::Module,
+    opcode::{InstructionId, Opcode},
+};
+use rustc_hash::FxHashSet;
+
+/// Dead store elimination.
+///
+/// A store is removed if the same address is overwritten, or the function returns and the
+/// memory is a local no callee can access, before anything may read the memory. Following a
+/// store, the instructions of its block are scanned, and then those of the only successor as
+/// long as the successor is only reachable from the block. Loads that may alias and calls that
+/// may access the memory (according to the `AliasInfo` of the function) read it.
+pub struct DeadStoreElimination {}
+
+struct DeadStoreEliminationOnFunction<'a> {
+    func: &'a mut Function,
+}
+
+impl DeadStoreElimination {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn run_on_module(&mut self, module: &mut Module) {
+        for (_, func) in &mut module.functions {
+            if func.is_internal || func.is_empty() {
+                continue;
+            }
+
+            DeadStoreEliminationOnFunction { func }.run()
+        }
+    }
+}
+
+impl<'a> DeadStoreEliminationOnFunction<'a> {
+    fn run(&mut self) {
+        AliasAnalysis::new().run_on_function(self.func);
+
+        let mut dead_stores = vec![];
+        for &block in &self.func.basic_blocks.order {
+            for &id in &*self.func.basic_blocks.arena[block].iseq_ref() {
+                if self.func.inst_table[id].opcode == Opcode::Store && self.is_dead(id) {
+                    dead_stores.push(id)
+                }
+            }
+        }
+
+        debug!(println!(
+            "function '{}': {} stores removed",
+            self.func.name,
+            dead_stores.len()
+        ));
+
+        for id in dead_stores {
+            self.func.remove_inst(id)
+        }
+    }
+
+    fn is_dead(&self, store: InstructionId) -> bool {
+        let func = &*self.func;
+        let info = func.get_analysis::<AliasInfo>().unwrap();
+        let (val, dst) = (
+            func.inst_table[store].operand.args()[0],
+            func.inst_table[store].operand.args()[1],
+        );
+        let ty = func.get_value_type(&val);
+
+        let (mut block, pos) = func.find_inst_pos(store).unwrap();
+        let mut insts = func.basic_blocks.arena[block].iseq_ref()[pos + 1..].to_vec();
+        let mut visited = FxHashSet::default();
+
+        loop {
+            for id in insts {
+                let inst = &func.inst_table[id];
+                match inst.opcode {
+                    Opcode::Load => {
+                        if info.alias(func, inst.operand.args()[0], dst) != AliasResult::NoAlias {
+                            return false;
+                        }
+                    }
+                    Opcode::Store => {
+                        if info.alias(func, inst.operand.args()[1], dst) == AliasResult::MustAlias
+                            && func.get_value_type(&inst.operand.args()[0]) == ty
+                        {
+                            return true;
+                        }
+                    }
+                    Opcode::Call => {
+                        if !info.points_to_local(func, dst) {
+                            return false;
+                        }
+                    }
+                    Opcode::Ret => return info.points_to_local(func, dst),
+                    _ => {}
+                }
+            }
+
+            visited.insert(block);
+            let succ = &func.basic_blocks.arena[block].succ;
+            if succ.len() != 1 {
+                return false;
+            }
+            block = *succ.iter().next().unwrap();
+            if func.basic_blocks.arena[block].pred.len() != 1 || visited.contains(&block) {
+                return false;
+            }
+            insts = func.basic_blocks.arena[block].iseq_ref().clone();
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use crate::ir::{
+    basic_block::BasicBlockId,
+    builder::IRBuilder,
+    function::Function,
+    module::Module,
+    opcode::{InstOperand, Instruction, InstructionId, Opcode},
+};
+
+/// Expands `select` into explicit control flow for targets that have no (or only a partial)
+/// conditional move instruction.
+///
+/// ```text
+/// B:  ...                            B:  ...
+///     %x = select %c, %a, %b   =>        condbr %c, T, Tail
+///     ...                            T:  br Tail
+///                                    Tail:
+///                                        %x = phi [%a, T], [%b, B]
+///                                        ...
+/// ```
+pub struct ExpandSelect {
+    only_floating_point: bool,
+}
+
+struct ExpandSelectOnFunction<'a> {
+    func: &'a mut Function,
+    only_floating_point: bool,
+}
+
+impl ExpandSelect {
+    pub fn new() -> Self {
+        Self {
+            only_floating_point: false,
+        }
+    }
+
+    /// Expands only selects producing a floating-point value.
+    pub fn only_floating_point(mut self) -> Self {
+        self.only_floating_point = true;
+        self
+    }
+
+    pub fn run_on_module(&mut self, module: &mut Module) {
+        for (_, func) in &mut module.functions {
+            if func.is_internal || func.is_empty() {
+                continue;
+            }
+
+            ExpandSelectOnFunction {
+                func,
+                only_floating_point: self.only_floating_point,
+            }
+            .run();
+        }
+    }
+}
+
+impl<'a> ExpandSelectOnFunction<'a> {
+    pub fn run(&mut self) {
+        let mut selects = vec![];
+        for &block_id in &self.func.basic_blocks.order {
+            let block = &self.func.basic_blocks.arena[block_id];
+            for &id in &*block.iseq_ref() {
+                let inst = &self.func.inst_table[id];
+                if inst.opcode == Opcode::Select
+                    && (!self.only_floating_point || inst.ty.is_float())
+                {
+                    selects.push(id);
+                }
+            }
+        }
+
+        // A select may have been moved into a new block by the expansion of a preceding one,
+        // so its parent is looked up every time.
+        for select in selects {
+            self.expand(select)
+        }
+    }
+
+    fn expand(&mut self, select: InstructionId) {
+        let (block, pos) = self.func.find_inst_pos(select).unwrap();
+        let tail = self.append_basic_block_after(block);
+        let then_ = self.func.append_basic_block_before(tail);
+
+        // Move the instructions following the select into the tail block.
+        let rest = self.func.basic_blocks.arena[block]
+            .iseq_ref_mut()
+            .split_off(pos + 1);
+        for &id in &rest {
+            self.func.inst_table[id].parent = tail;
+        }
+        *self.func.basic_blocks.arena[tail].iseq_ref_mut() = rest;
+
+        let succs = self.func.basic_blocks.arena[block].succ.clone();
+        for succ in succs {
+            self.func.basic_blocks.delete_edge(block, succ);
+            self.func.basic_blocks.make_edge(tail, succ);
+            for &id in &*self.func.basic_blocks.arena[succ].iseq_ref() {
+                if self.func.inst_table[id].opcode != Opcode::Phi {
+                    break;
+                }
+                Instruction::replace_block_operand(&mut self.func.inst_table, id, &block, tail);
+            }
+        }
+
+        let [cond, then_val, else_val] = match &self.func.inst_table[select].operand {
+            InstOperand::Select { args } => *args,
+            _ => unreachable!(),
+        };
+        self.func.remove_inst(select);
+
+        let mut builder = self.func.ir_builder();
+        builder.set_insert_point(block);
+        builder.build_cond_br(cond, then_, tail);
+        builder.set_insert_point(then_);
+        builder.build_br(tail);
+        builder.set_insert_point_at(0, tail);
+        let phi = builder.build_phi(vec![(then_val, then_), (else_val, block)]);
+
+        Instruction::replace_all_uses(&mut self.func.inst_table, select, phi);
+    }
+
+    fn append_basic_block_after(&mut self, block: BasicBlockId) -> BasicBlockId {
+        let order = &self.func.basic_blocks.order;
+        match order.iter().position(|&b| b == block) {
+            Some(pos) if pos + 1 < order.len() => {
+                let next = order[pos + 1];
+                self.func.append_basic_block_before(next)
+            }
+            _ => self.func.append_basic_block(),
+        }
+    }
+}
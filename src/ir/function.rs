@@ -5,6 +5,7 @@ use crate::analysis::Analysis;
 use crate::codegen::is_internal_function;
 use crate::traits::function::FunctionTrait;
 use id_arena::*;
+use rustc_hash::FxHashSet;
 
 pub type FunctionId = Id<Function>;
 
@@ -295,13 +296,13 @@ impl BasicBlocks {
                 "{}label.{}:\t// pred({}), succ({})\n{}\n",
                 s,
                 id.index(),
-                &b.pred
+                &sorted_indices(&b.pred)
                     .iter()
-                    .fold("".to_string(), |s, x| format!("{}{},", s, x.index()))
+                    .fold("".to_string(), |s, x| format!("{}{},", s, x))
                     .trim_matches(','),
-                &b.succ
+                &sorted_indices(&b.succ)
                     .iter()
-                    .fold("".to_string(), |s, x| format!("{}{},", s, x.index()))
+                    .fold("".to_string(), |s, x| format!("{}{},", s, x))
                     .trim_matches(','),
                 // &liveness
                 //     .def
@@ -324,6 +325,12 @@ impl BasicBlocks {
     }
 }
 
+fn sorted_indices(blocks: &FxHashSet<BasicBlockId>) -> Vec<usize> {
+    let mut indices: Vec<usize> = blocks.iter().map(|b| b.index()).collect();
+    indices.sort();
+    indices
+}
+
 impl PartialEq for Function {
     fn eq(&self, other: &Self) -> bool {
         self.name == other.name
@@ -0,0 +1,325 @@
+use crate::analysis::dom_tree::{DominatorTree, DominatorTreeConstructor};
+use crate::ir::{
+    alias_analysis::{AliasAnalysis, AliasInfo, AliasResult},
+    basic_block::{BasicBlock, BasicBlockId},
+    function::Function,
+    module::Module,
+    opcode::{FCmpKind, ICmpKind, InstOperand, Instruction, InstructionId, Opcode},
+    types::Type,
+    value::{InstructionValue, Value},
+};
+use rustc_hash::FxHashMap;
+
+/// Dominator-based global value numbering.
+///
+/// Blocks are visited in preorder of the dominator tree with a scoped table of the expressions
+/// computed by dominating instructions, so that an instruction computing an available expression
+/// is replaced with the dominating one. Operands of commutative instructions (and comparisons,
+/// with the predicate swapped) are put in a canonical order, phis with the same incoming values
+/// in the same block are unified, and a phi whose incoming values are all the same is replaced
+/// with the value.
+///
+/// A load is replaced with a previous load of the same address in the same block or in the
+/// dominator of the block if it is the only predecessor, as long as no store that may alias
+/// (according to `AliasInfo`) and no call that may access the memory is in between.
+pub struct GlobalValueNumbering {}
+
+struct GlobalValueNumberingOnFunction<'a> {
+    func: &'a mut Function,
+    exprs: FxHashMap<Expression, Value>,
+    /// Expressions added to `exprs` by the blocks currently visited, to undo on leaving them.
+    scopes: Vec<Vec<Expression>>,
+    removal_list: Vec<InstructionId>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Expression {
+    opcode: Opcode,
+    ty: Type,
+    operand: InstOperand,
+    /// The parent of a phi, which is equivalent only to phis in the same block.
+    block: Option<BasicBlockId>,
+}
+
+/// Values of loads available at a point, with their addresses.
+type AvailableLoads = Vec<(Value, Value)>;
+
+impl GlobalValueNumbering {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn run_on_module(&mut self, module: &mut Module) {
+        for (_, func) in &mut module.functions {
+            if func.is_internal || func.is_empty() {
+                continue;
+            }
+
+            GlobalValueNumberingOnFunction {
+                func,
+                exprs: FxHashMap::default(),
+                scopes: vec![],
+                removal_list: vec![],
+            }
+            .run()
+        }
+    }
+}
+
+impl<'a> GlobalValueNumberingOnFunction<'a> {
+    fn run(mut self) {
+        AliasAnalysis::new().run_on_function(self.func);
+        let dom_tree = DominatorTreeConstructor::new(&self.func.basic_blocks).construct();
+        let entry = self.func.get_entry_block().unwrap();
+        self.run_on_block(&dom_tree, entry, vec![]);
+
+        debug!(println!(
+            "function '{}': {} insts removed",
+            self.func.name,
+            self.removal_list.len()
+        ));
+
+        for id in self.removal_list {
+            self.func.remove_inst(id)
+        }
+    }
+
+    fn run_on_block(
+        &mut self,
+        dom_tree: &DominatorTree<BasicBlock>,
+        block: BasicBlockId,
+        mut loads: AvailableLoads,
+    ) {
+        self.scopes.push(vec![]);
+
+        let iseq = self.func.basic_blocks.arena[block].iseq_ref().clone();
+        for id in iseq {
+            let opcode = self.func.inst_table[id].opcode;
+            match opcode {
+                Opcode::Load => self.number_load(id, &mut loads),
+                Opcode::Store => {
+                    let dst = self.func.inst_table[id].operand.args()[1];
+                    let func = &*self.func;
+                    let info = func.get_analysis::<AliasInfo>().unwrap();
+                    loads.retain(|&(addr, _)| info.alias(func, addr, dst) == AliasResult::NoAlias)
+                }
+                Opcode::Call => {
+                    let func = &*self.func;
+                    let info = func.get_analysis::<AliasInfo>().unwrap();
+                    loads.retain(|&(addr, _)| info.points_to_local(func, addr))
+                }
+                Opcode::Phi => {
+                    if !self.remove_trivial_phi(id) {
+                        self.number(id)
+                    }
+                }
+                _ if Self::is_pure(opcode) => self.number(id),
+                _ => {}
+            }
+        }
+
+        let mut children = dom_tree
+            .children_of(block)
+            .map_or(vec![], |children| children.iter().copied().collect());
+        children.sort_by_key(|child| child.index());
+        for child in children {
+            // Loads available at the end of the block stay so in a child only reachable from it.
+            let pred = &self.func.basic_blocks.arena[child].pred;
+            let loads = if pred.len() == 1 && pred.contains(&block) {
+                loads.clone()
+            } else {
+                vec![]
+            };
+            self.run_on_block(dom_tree, child, loads)
+        }
+
+        for expr in self.scopes.pop().unwrap() {
+            self.exprs.remove(&expr);
+        }
+    }
+
+    fn number(&mut self, id: InstructionId) {
+        let expr = self.expression(id);
+        if let Some(&leader) = self.exprs.get(&expr) {
+            self.replace(id, leader);
+            return;
+        }
+        self.exprs.insert(expr.clone(), self.inst_value(id));
+        self.scopes.last_mut().unwrap().push(expr);
+    }
+
+    fn number_load(&mut self, id: InstructionId, loads: &mut AvailableLoads) {
+        let addr = self.func.inst_table[id].operand.args()[0];
+        let ty = self.func.inst_table[id].ty;
+        let func = &*self.func;
+        let info = func.get_analysis::<AliasInfo>().unwrap();
+        if let Some(&(_, val)) = loads.iter().find(|&&(addr_, val)| {
+            info.alias(func, addr_, addr) == AliasResult::MustAlias
+                && func.get_value_type(&val) == ty
+        }) {
+            self.replace(id, val);
+            return;
+        }
+        loads.push((addr, self.inst_value(id)))
+    }
+
+    /// Replaces a phi whose incoming values are all the same (or the phi itself).
+    fn remove_trivial_phi(&mut self, id: InstructionId) -> bool {
+        let this = self.inst_value(id);
+        let mut unique = None;
+        for &arg in self.func.inst_table[id].operand.args() {
+            if arg == this || Some(arg) == unique {
+                continue;
+            }
+            if unique.is_some() {
+                return false;
+            }
+            unique = Some(arg)
+        }
+        match unique {
+            Some(val) => {
+                self.replace(id, val);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn replace(&mut self, id: InstructionId, val: Value) {
+        Instruction::replace_all_uses(&mut self.func.inst_table, id, val);
+        self.removal_list.push(id);
+    }
+
+    fn expression(&self, id: InstructionId) -> Expression {
+        let inst = &self.func.inst_table[id];
+        let mut operand = inst.operand.clone();
+        let mut block = None;
+
+        match &mut operand {
+            InstOperand::Binary { args } if Self::is_commutative(inst.opcode) => {
+                if rank(&args[1]) < rank(&args[0]) {
+                    args.swap(0, 1)
+                }
+            }
+            InstOperand::IntCmp { cond, args } => {
+                if rank(&args[1]) < rank(&args[0]) {
+                    args.swap(0, 1);
+                    *cond = swap_icmp(*cond)
+                }
+            }
+            InstOperand::FloatCmp { cond, args } => {
+                if rank(&args[1]) < rank(&args[0]) {
+                    args.swap(0, 1);
+                    *cond = swap_fcmp(*cond)
+                }
+            }
+            InstOperand::Phi { blocks, args } => {
+                let mut incomings = blocks
+                    .iter()
+                    .copied()
+                    .zip(args.iter().copied())
+                    .collect::<Vec<_>>();
+                incomings.sort_by_key(|(block, _)| block.index());
+                *blocks = incomings.iter().map(|&(block, _)| block).collect();
+                *args = incomings.iter().map(|&(_, arg)| arg).collect();
+                block = Some(inst.parent);
+            }
+            _ => {}
+        }
+
+        Expression {
+            opcode: inst.opcode,
+            ty: inst.ty,
+            operand,
+            block,
+        }
+    }
+
+    fn inst_value(&self, id: InstructionId) -> Value {
+        Value::Instruction(InstructionValue {
+            func_id: self.func.id.unwrap(),
+            id,
+        })
+    }
+
+    fn is_pure(opcode: Opcode) -> bool {
+        matches!(
+            opcode,
+            Opcode::GetElementPtr
+                | Opcode::Add
+                | Opcode::Sub
+                | Opcode::Mul
+                | Opcode::Div
+                | Opcode::Rem
+                | Opcode::UDiv
+                | Opcode::URem
+                | Opcode::Shl
+                | Opcode::LShr
+                | Opcode::AShr
+                | Opcode::And
+                | Opcode::Or
+                | Opcode::Xor
+                | Opcode::SIToFP
+                | Opcode::FPToSI
+                | Opcode::UIToFP
+                | Opcode::FPToUI
+                | Opcode::FPExt
+                | Opcode::FPTrunc
+                | Opcode::Bitcast
+                | Opcode::Sext
+                | Opcode::Zext
+                | Opcode::Trunc
+                | Opcode::PtrToInt
+                | Opcode::IntToPtr
+                | Opcode::ICmp
+                | Opcode::FCmp
+                | Opcode::Select
+        )
+    }
+
+    fn is_commutative(opcode: Opcode) -> bool {
+        matches!(
+            opcode,
+            Opcode::Add | Opcode::Mul | Opcode::And | Opcode::Or | Opcode::Xor
+        )
+    }
+}
+
+/// Orders operands so that arguments come first, then instructions and finally immediates.
+fn rank(val: &Value) -> (u8, usize) {
+    match val {
+        Value::Argument(arg) => (0, arg.index),
+        Value::Instruction(InstructionValue { id, .. }) => (1, id.index()),
+        Value::Global(g) => (2, g.id.index()),
+        Value::Constant(c) => (3, c.id.index()),
+        Value::Function(f) => (4, f.index()),
+        Value::Immediate(_) => (5, 0),
+        Value::None => (6, 0),
+    }
+}
+
+fn swap_icmp(cond: ICmpKind) -> ICmpKind {
+    match cond {
+        ICmpKind::Eq => ICmpKind::Eq,
+        ICmpKind::Ne => ICmpKind::Ne,
+        ICmpKind::Lt => ICmpKind::Gt,
+        ICmpKind::Le => ICmpKind::Ge,
+        ICmpKind::Gt => ICmpKind::Lt,
+        ICmpKind::Ge => ICmpKind::Le,
+        ICmpKind::ULt => ICmpKind::UGt,
+        ICmpKind::ULe => ICmpKind::UGe,
+        ICmpKind::UGt => ICmpKind::ULt,
+        ICmpKind::UGe => ICmpKind::ULe,
+    }
+}
+
+fn swap_fcmp(cond: FCmpKind) -> FCmpKind {
+    match cond {
+        FCmpKind::UEq => FCmpKind::UEq,
+        FCmpKind::UNe => FCmpKind::UNe,
+        FCmpKind::ULt => FCmpKind::UGt,
+        FCmpKind::ULe => FCmpKind::UGe,
+        FCmpKind::UGt => FCmpKind::ULt,
+        FCmpKind::UGe => FCmpKind::ULe,
+    }
+}
@@ -0,0 +1,232 @@
+use crate::ir::{
+    basic_block::BasicBlockId,
+    builder::IRBuilder,
+    function::Function,
+    module::Module,
+    opcode::{InstOperand, Instruction, InstructionId, Opcode},
+    value::Value,
+};
+
+/// The maximum number of instructions (except the terminator) a side block may have to be
+/// speculatively executed.
+const MAX_SPECULATED_INSTS: usize = 4;
+
+/// Turns small triangle and diamond shaped CFGs into straight-line code using `select`.
+///
+/// ```text
+///      H                   H
+///     / \                 / \
+///    T   |               T   F
+///     \ /                 \ /
+///      M                   M
+/// ```
+///
+/// The instructions in T (and F) are hoisted into H and phis in M are replaced with selects.
+pub struct IfConversion {}
+
+struct IfConversionOnFunction<'a> {
+    func: &'a mut Function,
+}
+
+impl IfConversion {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn run_on_module(&mut self, module: &mut Module) {
+        for (_, func) in &mut module.functions {
+            if func.is_internal || func.is_empty() {
+                continue;
+            }
+
+            IfConversionOnFunction { func }.run();
+        }
+    }
+}
+
+impl<'a> IfConversionOnFunction<'a> {
+    pub fn run(&mut self) {
+        // Converting an inner diamond may make an outer one convertible.
+        while self.run_once() {}
+    }
+
+    fn run_once(&mut self) -> bool {
+        let mut changed = false;
+        for head in self.func.basic_blocks.order.clone() {
+            if !self.func.basic_blocks.order.contains(&head) {
+                continue;
+            }
+            changed |= self.try_convert(head);
+        }
+        changed
+    }
+
+    fn try_convert(&mut self, head: BasicBlockId) -> bool {
+        let [then_, else_] = match self.cond_br(head) {
+            Some((_, _, dsts)) => dsts,
+            None => return false,
+        };
+        if then_ == else_ || then_ == head || else_ == head {
+            return false;
+        }
+
+        let then_merge = self.single_succ(then_);
+        let else_merge = self.single_succ(else_);
+
+        // Diamond
+        if let Some(merge) = then_merge {
+            if then_merge == else_merge
+                && merge != head
+                && self.is_speculatable(then_, head)
+                && self.is_speculatable(else_, head)
+            {
+                self.convert(head, then_, else_, merge, &[then_, else_]);
+                return true;
+            }
+        }
+
+        // Triangle (head -> then -> else)
+        if then_merge == Some(else_) && self.is_speculatable(then_, head) {
+            self.convert(head, then_, head, else_, &[then_]);
+            return true;
+        }
+
+        // Triangle (head -> else -> then)
+        if else_merge == Some(then_) && self.is_speculatable(else_, head) {
+            self.convert(head, head, else_, then_, &[else_]);
+            return true;
+        }
+
+        false
+    }
+
+    /// `then_` and `else_` are the predecessors of `merge` whose incoming values are selected.
+    /// `head` itself is one of them in the case of a triangle.
+    fn convert(
+        &mut self,
+        head: BasicBlockId,
+        then_: BasicBlockId,
+        else_: BasicBlockId,
+        merge: BasicBlockId,
+        sides: &[BasicBlockId],
+    ) {
+        let (condbr, cond, _) = self.cond_br(head).unwrap();
+        for &side in sides {
+            self.hoist(side, head);
+        }
+
+        let phis: Vec<InstructionId> = self.func.basic_blocks.arena[merge]
+            .iseq_ref()
+            .iter()
+            .copied()
+            .take_while(|&id| self.func.inst_table[id].opcode == Opcode::Phi)
+            .collect();
+        for phi in phis {
+            let incoming = |func: &Function, block: BasicBlockId| {
+                let inst = &func.inst_table[phi];
+                let pos = inst.operand.blocks().iter().position(|&b| b == block);
+                pos.map(|pos| inst.operand.args()[pos])
+            };
+            let (then_val, else_val) =
+                match (incoming(self.func, then_), incoming(self.func, else_)) {
+                    (Some(t), Some(e)) => (t, e),
+                    _ => continue,
+                };
+
+            let mut builder = self.func.ir_builder();
+            builder.set_insert_point_before_terminator(head);
+            let selected = builder.build_select(cond, then_val, else_val);
+
+            // Replace the incomings from the arms with the single incoming from the head.
+            self.func.inst_table[phi].remove(&self.func.inst_table);
+            let (blocks, args) = match &mut self.func.inst_table[phi].operand {
+                InstOperand::Phi { blocks, args } => (blocks, args),
+                _ => unreachable!(),
+            };
+            let mut pos = 0;
+            while pos < blocks.len() {
+                if blocks[pos] == then_ || blocks[pos] == else_ {
+                    blocks.remove(pos);
+                    args.remove(pos);
+                } else {
+                    pos += 1;
+                }
+            }
+            blocks.push(head);
+            args.push(selected);
+            self.func.inst_table[phi].set_users(&self.func.inst_table);
+
+            if self.func.inst_table[phi].operand.args().len() == 1 {
+                Instruction::replace_all_uses(&mut self.func.inst_table, phi, selected);
+                self.func.remove_inst(phi);
+            }
+        }
+
+        self.func.remove_inst(condbr);
+        for &side in sides {
+            self.func.basic_blocks.remove_block(side);
+        }
+        let mut builder = self.func.ir_builder();
+        builder.set_insert_point(head);
+        builder.build_br(merge);
+    }
+
+    /// Moves the instructions of `side` except the terminator to the end of `head`.
+    fn hoist(&mut self, side: BasicBlockId, head: BasicBlockId) {
+        let insts = self.func.basic_blocks.arena[side].iseq_ref().clone();
+        let (br, body) = insts.split_last().unwrap();
+        self.func.remove_inst(*br);
+        for &id in body {
+            self.func.remove_inst_from_block(id);
+            self.func.inst_table[id].parent = head;
+            let mut builder = self.func.ir_builder();
+            builder.set_insert_point_before_terminator(head);
+            builder.insert(id);
+        }
+    }
+
+    fn cond_br(&self, block: BasicBlockId) -> Option<(InstructionId, Value, [BasicBlockId; 2])> {
+        let id = *self.func.basic_blocks.arena[block].iseq_ref().last()?;
+        match &self.func.inst_table[id].operand {
+            InstOperand::CondBranch { arg, dsts } => Some((id, *arg, *dsts)),
+            _ => None,
+        }
+    }
+
+    fn single_succ(&self, block: BasicBlockId) -> Option<BasicBlockId> {
+        let block = &self.func.basic_blocks.arena[block];
+        if block.succ.len() != 1 {
+            return None;
+        }
+        block.succ.iter().next().copied()
+    }
+
+    /// Returns true if `block` is only reached from `head` and its instructions are cheap and
+    /// safe to execute unconditionally.
+    fn is_speculatable(&self, block: BasicBlockId, head: BasicBlockId) -> bool {
+        let block = &self.func.basic_blocks.arena[block];
+        if block.pred.len() != 1 || !block.pred.contains(&head) {
+            return false;
+        }
+        let iseq = block.iseq_ref();
+        match iseq.last() {
+            Some(&id) if self.func.inst_table[id].opcode == Opcode::Br => {}
+            _ => return false,
+        }
+        iseq.len() - 1 <= MAX_SPECULATED_INSTS
+            && iseq[..iseq.len() - 1].iter().all(|&id| {
+                let opcode = self.func.inst_table[id].opcode;
+                !opcode.access_memory()
+                    && !matches!(
+                        opcode,
+                        Opcode::Alloca
+                            | Opcode::Call
+                            | Opcode::Phi
+                            | Opcode::Div
+                            | Opcode::Rem
+                            | Opcode::UDiv
+                            | Opcode::URem
+                    )
+            })
+    }
+}
@@ -0,0 +1,425 @@
+use crate::ir::{
+    basic_block::BasicBlockId,
+    builder::IRBuilder,
+    function::{Function, FunctionId},
+    merge_ret::MergeReturns,
+    module::Module,
+    opcode::{InstOperand, Instruction, InstructionId, Opcode},
+    value::{ArgumentValue, InstructionValue, Value},
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// Default upper bound on the cost of a callee to be inlined.
+const DEFAULT_THRESHOLD: usize = 32;
+
+/// Cost of a call instruction in a callee, which is much more than a simple arithmetic.
+const CALL_COST: usize = 5;
+
+/// Inlines calls to small functions.
+///
+/// Functions are visited in post order of the call graph, so that a callee has already got its
+/// own calls inlined when deciding whether to inline it. A callee is inlined only if its cost,
+/// roughly the number of instructions it executes, is at most the threshold. Functions that may
+/// call themselves (directly or not), internal functions and functions taking `byval` parameters
+/// are never inlined.
+///
+/// ```text
+/// B:  ...                            B:    ...
+///     %x = call f, %a          =>          br F.entry
+///     ...                        F.entry:  ; the body of f with %arg.0 replaced by %a
+///                                          ...
+///                                F.ret:    br Tail
+///                                Tail:     ; %x replaced by the value f returns
+///                                          ...
+/// ```
+pub struct Inliner {
+    threshold: usize,
+}
+
+struct InlineCallSite<'a> {
+    caller: &'a mut Function,
+    callee: &'a Function,
+    call: InstructionId,
+    blocks: FxHashMap<BasicBlockId, BasicBlockId>,
+    insts: FxHashMap<InstructionId, InstructionId>,
+}
+
+impl Inliner {
+    pub fn new() -> Self {
+        Self {
+            threshold: DEFAULT_THRESHOLD,
+        }
+    }
+
+    /// Sets the upper bound on the cost of a callee to be inlined.
+    pub fn with_threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn run_on_module(&mut self, module: &mut Module) {
+        let call_graph = CallGraph::new(module);
+
+        for caller_id in call_graph.post_order() {
+            let call_sites = self.collect_call_sites(module, &call_graph, caller_id);
+
+            for (call, callee_id) in call_sites {
+                let mut callee = module.function_ref(callee_id).clone();
+                MergeReturns::new().run_on_function(&mut callee);
+                InlineCallSite {
+                    caller: module.function_ref_mut(caller_id),
+                    callee: &callee,
+                    call,
+                    blocks: FxHashMap::default(),
+                    insts: FxHashMap::default(),
+                }
+                .run();
+            }
+        }
+    }
+
+    fn collect_call_sites(
+        &self,
+        module: &Module,
+        call_graph: &CallGraph,
+        caller_id: FunctionId,
+    ) -> Vec<(InstructionId, FunctionId)> {
+        let caller = module.function_ref(caller_id);
+        let mut call_sites = vec![];
+        let mut inlinable = FxHashMap::default();
+
+        for &block_id in &caller.basic_blocks.order {
+            for &id in &*caller.basic_blocks.arena[block_id].iseq_ref() {
+                let inst = &caller.inst_table[id];
+                if inst.opcode != Opcode::Call {
+                    continue;
+                }
+                let callee_id = match inst.operand.args()[0] {
+                    Value::Function(id) => id,
+                    _ => continue,
+                };
+                if *inlinable.entry(callee_id).or_insert_with(|| {
+                    !call_graph.is_recursive(callee_id)
+                        && self.is_inlinable(module.function_ref(callee_id))
+                }) {
+                    call_sites.push((id, callee_id))
+                }
+            }
+        }
+
+        call_sites
+    }
+
+    fn is_inlinable(&self, callee: &Function) -> bool {
+        if callee.is_internal || callee.is_empty() {
+            return false;
+        }
+
+        let entry = callee.get_entry_block().unwrap();
+        if !callee.basic_blocks.arena[entry].pred.is_empty() {
+            return false;
+        }
+
+        if (0..callee.get_params_len())
+            .any(|i| callee.get_param_attr(i).map_or(false, |attr| attr.byval))
+        {
+            return false;
+        }
+
+        let mut has_ret = false;
+        let mut cost = 0;
+        for block_id in reachable_blocks(callee) {
+            for &id in &*callee.basic_blocks.arena[block_id].iseq_ref() {
+                cost += match callee.inst_table[id].opcode {
+                    Opcode::Alloca | Opcode::Phi | Opcode::Bitcast | Opcode::Br => 0,
+                    Opcode::Call => CALL_COST,
+                    Opcode::Ret => {
+                        has_ret = true;
+                        0
+                    }
+                    _ => 1,
+                };
+            }
+        }
+
+        has_ret && cost <= self.threshold
+    }
+}
+
+impl<'a> InlineCallSite<'a> {
+    fn run(&mut self) {
+        let (block, pos) = self.caller.find_inst_pos(self.call).unwrap();
+        let tail = self.split_block_after(block, pos);
+
+        // Clone the blocks and instructions of the callee. Instructions are allocated first so
+        // that every operand, even one defined later (e.g. by a phi in a loop), can be mapped.
+        let callee_blocks = reachable_blocks(self.callee);
+        for &callee_block in &callee_blocks {
+            let new_block = self.caller.append_basic_block_before(tail);
+            self.blocks.insert(callee_block, new_block);
+        }
+
+        let mut ret = None;
+        for &callee_block in &callee_blocks {
+            let new_block = self.blocks[&callee_block];
+            for &id in &*self.callee.basic_blocks.arena[callee_block].iseq_ref() {
+                let inst = &self.callee.inst_table[id];
+                if inst.opcode == Opcode::Ret {
+                    ret = Some((inst.operand.args()[0], new_block));
+                    continue;
+                }
+                let new_id = self.caller.alloc_inst(Instruction::new(
+                    inst.opcode,
+                    InstOperand::None,
+                    inst.ty,
+                    new_block,
+                ));
+                self.insts.insert(id, new_id);
+                self.caller.basic_blocks.arena[new_block]
+                    .iseq_ref_mut()
+                    .push(new_id);
+            }
+        }
+
+        for (&id, &new_id) in &self.insts {
+            let mut operand = self.callee.inst_table[id].operand.clone();
+            self.map_operand(&mut operand);
+            self.caller.inst_table[new_id].operand = operand;
+            self.caller.inst_table[new_id].set_users(&self.caller.inst_table);
+        }
+
+        for &callee_block in &callee_blocks {
+            for succ in &self.callee.basic_blocks.arena[callee_block].succ {
+                self.caller
+                    .basic_blocks
+                    .make_edge(self.blocks[&callee_block], self.blocks[succ]);
+            }
+        }
+
+        self.hoist_allocas(&callee_blocks);
+
+        // Replace the call with a branch to the cloned body, and the return with a branch to
+        // the tail.
+        let (ret_val, ret_block) = ret.unwrap();
+        let ret_val = self.map_value(ret_val);
+        if ret_val != Value::None {
+            Instruction::replace_all_uses(&mut self.caller.inst_table, self.call, ret_val);
+        }
+        self.caller.remove_inst(self.call);
+
+        let entry = self.blocks[&self.callee.get_entry_block().unwrap()];
+        let mut builder = self.caller.ir_builder();
+        builder.set_insert_point(block);
+        builder.build_br(entry);
+        builder.set_insert_point(ret_block);
+        builder.build_br(tail);
+    }
+
+    /// Moves the instructions following `pos` in `block` into a new block, which takes over the
+    /// successors of `block`.
+    fn split_block_after(&mut self, block: BasicBlockId, pos: usize) -> BasicBlockId {
+        let tail = {
+            let order = &self.caller.basic_blocks.order;
+            match order.iter().position(|&b| b == block) {
+                Some(pos) if pos + 1 < order.len() => {
+                    let next = order[pos + 1];
+                    self.caller.append_basic_block_before(next)
+                }
+                _ => self.caller.append_basic_block(),
+            }
+        };
+
+        let rest = self.caller.basic_blocks.arena[block]
+            .iseq_ref_mut()
+            .split_off(pos + 1);
+        for &id in &rest {
+            self.caller.inst_table[id].parent = tail;
+        }
+        *self.caller.basic_blocks.arena[tail].iseq_ref_mut() = rest;
+
+        let succs = self.caller.basic_blocks.arena[block].succ.clone();
+        for succ in succs {
+            self.caller.basic_blocks.delete_edge(block, succ);
+            self.caller.basic_blocks.make_edge(tail, succ);
+            for &id in &*self.caller.basic_blocks.arena[succ].iseq_ref() {
+                if self.caller.inst_table[id].opcode != Opcode::Phi {
+                    break;
+                }
+                Instruction::replace_block_operand(&mut self.caller.inst_table, id, &block, tail);
+            }
+        }
+
+        tail
+    }
+
+    /// Moves the cloned allocas into the entry block of the caller, so that a call in a loop
+    /// doesn't make them look like allocated every iteration.
+    fn hoist_allocas(&mut self, callee_blocks: &[BasicBlockId]) {
+        let entry = self.caller.get_entry_block().unwrap();
+        let mut allocas = vec![];
+        for callee_block in callee_blocks {
+            let block = self.blocks[callee_block];
+            let iseq = &mut *self.caller.basic_blocks.arena[block].iseq_ref_mut();
+            let inst_table = &self.caller.inst_table;
+            iseq.retain(|&id| {
+                if inst_table[id].opcode == Opcode::Alloca {
+                    allocas.push(id);
+                    return false;
+                }
+                true
+            });
+        }
+        for &id in &allocas {
+            self.caller.inst_table[id].parent = entry;
+        }
+        self.caller.basic_blocks.arena[entry]
+            .iseq_ref_mut()
+            .splice(0..0, allocas);
+    }
+
+    fn map_operand(&self, operand: &mut InstOperand) {
+        if let InstOperand::Phi { blocks, args } = operand {
+            // Incoming values from unreachable blocks of the callee are dropped with the blocks.
+            let mut i = 0;
+            while i < blocks.len() {
+                if self.blocks.contains_key(&blocks[i]) {
+                    i += 1;
+                } else {
+                    blocks.remove(i);
+                    args.remove(i);
+                }
+            }
+        }
+        for block in operand.blocks_mut() {
+            *block = self.blocks[block];
+        }
+        for arg in operand.args_mut() {
+            *arg = self.map_value(*arg);
+        }
+    }
+
+    fn map_value(&self, val: Value) -> Value {
+        match val {
+            Value::Argument(ArgumentValue { index, .. }) => {
+                self.caller.inst_table[self.call].operand.args()[index + 1]
+            }
+            Value::Instruction(InstructionValue { id, .. }) => {
+                Value::Instruction(InstructionValue {
+                    func_id: self.caller.id.unwrap(),
+                    id: self.insts[&id],
+                })
+            }
+            val => val,
+        }
+    }
+}
+
+/// Direct calls between the functions of a module.
+struct CallGraph {
+    callees: FxHashMap<FunctionId, Vec<FunctionId>>,
+    recursive: FxHashSet<FunctionId>,
+}
+
+impl CallGraph {
+    fn new(module: &Module) -> Self {
+        let mut callees = FxHashMap::default();
+        for (id, func) in &module.functions {
+            let mut list = vec![];
+            if !func.is_internal {
+                for &block_id in &func.basic_blocks.order {
+                    for &inst_id in &*func.basic_blocks.arena[block_id].iseq_ref() {
+                        let inst = &func.inst_table[inst_id];
+                        if inst.opcode != Opcode::Call {
+                            continue;
+                        }
+                        if let Value::Function(callee) = inst.operand.args()[0] {
+                            if !list.contains(&callee) {
+                                list.push(callee)
+                            }
+                        }
+                    }
+                }
+            }
+            callees.insert(id, list);
+        }
+
+        let mut graph = Self {
+            callees,
+            recursive: FxHashSet::default(),
+        };
+        graph.recursive = module
+            .functions
+            .iter()
+            .map(|(id, _)| id)
+            .filter(|&id| graph.reaches(id, id))
+            .collect();
+        graph
+    }
+
+    fn is_recursive(&self, id: FunctionId) -> bool {
+        self.recursive.contains(&id)
+    }
+
+    /// Returns true if `to` is called, directly or not, by `from`.
+    fn reaches(&self, from: FunctionId, to: FunctionId) -> bool {
+        let mut visited = FxHashSet::default();
+        let mut worklist = self.callees[&from].clone();
+        while let Some(id) = worklist.pop() {
+            if id == to {
+                return true;
+            }
+            if visited.insert(id) {
+                worklist.extend(self.callees[&id].iter().copied())
+            }
+        }
+        false
+    }
+
+    /// Returns the functions so that every callee comes before its callers, except in cycles.
+    fn post_order(&self) -> Vec<FunctionId> {
+        fn visit(
+            graph: &CallGraph,
+            id: FunctionId,
+            visited: &mut FxHashSet<FunctionId>,
+            order: &mut Vec<FunctionId>,
+        ) {
+            if !visited.insert(id) {
+                return;
+            }
+            for &callee in &graph.callees[&id] {
+                visit(graph, callee, visited, order)
+            }
+            order.push(id)
+        }
+
+        let mut ids = self.callees.keys().copied().collect::<Vec<_>>();
+        ids.sort_by_key(|id| id.index());
+        let mut visited = FxHashSet::default();
+        let mut order = vec![];
+        for id in ids {
+            visit(self, id, &mut visited, &mut order)
+        }
+        order
+    }
+}
+
+/// Returns the blocks of `func` reachable from its entry, in the order of the function.
+fn reachable_blocks(func: &Function) -> Vec<BasicBlockId> {
+    let entry = match func.get_entry_block() {
+        Some(entry) => entry,
+        None => return vec![],
+    };
+    let mut reachable = FxHashSet::default();
+    let mut worklist = vec![entry];
+    while let Some(block) = worklist.pop() {
+        if reachable.insert(block) {
+            worklist.extend(func.basic_blocks.arena[block].succ.iter().copied())
+        }
+    }
+    func.basic_blocks
+        .order
+        .iter()
+        .copied()
+        .filter(|block| reachable.contains(block))
+        .collect()
+}
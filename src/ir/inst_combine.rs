@@ -54,15 +54,18 @@ impl<'a> InstructionCombineOnFunction<'a> {
                 self.func.remove_inst(inst_id);
             }
 
-            let inst2 = &mut self.func.inst_table[inst2_id];
+            Instruction::replace_inst_operand(&mut self.func.inst_table, inst2_id, inst_id, op0);
 
-            inst2.operand.args_mut()[0] = op0;
+            let inst2 = &mut self.func.inst_table[inst2_id];
             inst2.operand.args_mut()[1] = match inst2.opcode {
                 Opcode::Add => op1.const_add(&inst2.operand.args()[1]).unwrap(),
                 Opcode::Sub => op1.const_add(&inst2.operand.args()[1]).unwrap(),
                 Opcode::Mul => op1.const_mul(&inst2.operand.args()[1]).unwrap(),
                 Opcode::Div => op1.const_mul(&inst2.operand.args()[1]).unwrap(),
                 Opcode::Rem => op1.const_rem(&inst2.operand.args()[1]).unwrap(),
+                Opcode::And => op1.const_and(&inst2.operand.args()[1]).unwrap(),
+                Opcode::Or => op1.const_or(&inst2.operand.args()[1]).unwrap(),
+                Opcode::Xor => op1.const_xor(&inst2.operand.args()[1]).unwrap(),
                 _ => unreachable!(),
             };
         }
@@ -81,7 +84,14 @@ impl<'a> InstructionCombineOnFunction<'a> {
     pub fn is_combinable_sub(inst: &Instruction) -> bool {
         matches!(
             inst.opcode,
-            Opcode::Add | Opcode::Sub | Opcode::Mul | Opcode::Div | Opcode::Rem
+            Opcode::Add
+                | Opcode::Sub
+                | Opcode::Mul
+                | Opcode::Div
+                | Opcode::Rem
+                | Opcode::And
+                | Opcode::Or
+                | Opcode::Xor
         ) && matches!(inst.operand.args()[0], Value::Instruction(_))
             && matches!(inst.operand.args()[1], Value::Immediate(_))
     }
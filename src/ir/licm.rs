@@ -4,6 +4,7 @@ use crate::{
         loops::{Loop, Loops, LoopsConstructor},
     },
     ir::{
+        alias_analysis::{AliasAnalysis, AliasInfo, AliasResult},
         basic_block::{BasicBlock, BasicBlockId},
         builder::IRBuilder,
         function::Function,
@@ -46,6 +47,7 @@ impl<'a> LoopInvariantCodeMotionOnFunction<'a> {
     }
 
     pub fn run(&mut self) {
+        AliasAnalysis::new().run_on_function(self.func);
         let dom_tree = DominatorTreeConstructor::new(&self.func.basic_blocks).construct();
         let mut loops = LoopsConstructor::new(&dom_tree, &self.func.basic_blocks).analyze();
 
@@ -151,7 +153,10 @@ impl<'a> LoopInvariantCodeMotionOnFunction<'a> {
                 let mut insts_to_hoist = vec![];
                 while let Some(inst_id) = worklist.pop_front() {
                     let inst = &self.func.inst_table[inst_id];
-                    if inst.opcode.access_memory() || inst.opcode == Opcode::Call {
+                    if matches!(inst.opcode, Opcode::Store | Opcode::Call)
+                        || inst.opcode == Opcode::Load
+                            && !self.is_hoistable_load(loop_, inst.operand.args()[0])
+                    {
                         continue;
                     }
                     let invariant = inst.operand.args().iter().all(|val| match val {
@@ -188,4 +193,26 @@ impl<'a> LoopInvariantCodeMotionOnFunction<'a> {
 
         debug!(println!("LICM: {} invariants hoisted", count));
     }
+
+    /// Returns true if a load from `ptr` can be executed speculatively, and no store or call in
+    /// `loop_` may modify the memory.
+    fn is_hoistable_load(&self, loop_: &Loop<BasicBlock>, ptr: Value) -> bool {
+        let func = &*self.func;
+        let info = func.get_analysis::<AliasInfo>().unwrap();
+        if !info.is_dereferenceable(func, ptr) {
+            return false;
+        }
+        loop_.set.iter().all(|&bb| {
+            func.basic_blocks.arena[bb].iseq_ref().iter().all(|&id| {
+                let inst = &func.inst_table[id];
+                match inst.opcode {
+                    Opcode::Store => {
+                        info.alias(func, inst.operand.args()[1], ptr) == AliasResult::NoAlias
+                    }
+                    Opcode::Call => info.points_to_local(func, ptr),
+                    _ => true,
+                }
+            })
+        })
+    }
 }
@@ -0,0 +1,264 @@
+use crate::ir::{
+    basic_block::BasicBlockId,
+    builder::IRBuilder,
+    function::Function,
+    module::Module,
+    opcode::{ICmpKind, InstOperand, InstructionId, Opcode},
+    value::Value,
+};
+use rustc_hash::FxHashMap;
+use std::ops::Range;
+
+/// Minimum number of cases a jump table must cover.
+const MIN_JUMP_TABLE_CASES: usize = 4;
+
+/// Minimum ratio (in percent) of cases to the range of values a jump table covers.
+const MIN_JUMP_TABLE_DENSITY: i128 = 40;
+
+/// Lowers `switch` into a binary search tree of comparisons.
+///
+/// Sorted cases are first grouped into clusters. A run of at least four cases that fills at
+/// least 40% of its value range becomes a single jump table cluster, left as a smaller `switch`
+/// for the backend to lower into a table; any other case forms a cluster of its own. A switch
+/// made of exactly one jump table cluster is left untouched.
+pub struct LowerSwitch {
+    jump_tables: bool,
+}
+
+struct LowerSwitchOnFunction<'a> {
+    func: &'a mut Function,
+    jump_tables: bool,
+}
+
+struct Cluster {
+    cases: Range<usize>,
+    is_table: bool,
+}
+
+struct Case {
+    val: i64,
+    imm: Value,
+    dst: BasicBlockId,
+}
+
+struct SwitchTree<'a> {
+    cond: Value,
+    default: BasicBlockId,
+    cases: &'a [Case],
+    /// The last block of the tree in the block order.
+    last: BasicBlockId,
+    /// Blocks branching to each destination of the switch.
+    preds: FxHashMap<BasicBlockId, Vec<BasicBlockId>>,
+}
+
+impl LowerSwitch {
+    pub fn new() -> Self {
+        Self { jump_tables: true }
+    }
+
+    /// Lowers every case into comparisons, for targets not supporting jump tables.
+    pub fn without_jump_tables(mut self) -> Self {
+        self.jump_tables = false;
+        self
+    }
+
+    pub fn run_on_module(&mut self, module: &mut Module) {
+        for (_, func) in &mut module.functions {
+            if func.is_internal || func.is_empty() {
+                continue;
+            }
+
+            LowerSwitchOnFunction {
+                func,
+                jump_tables: self.jump_tables,
+            }
+            .run();
+        }
+    }
+}
+
+impl<'a> LowerSwitchOnFunction<'a> {
+    pub fn run(&mut self) {
+        let mut switches = vec![];
+        for &block_id in &self.func.basic_blocks.order {
+            let block = &self.func.basic_blocks.arena[block_id];
+            if let Some(&id) = block.iseq_ref().last() {
+                if self.func.inst_table[id].opcode == Opcode::Switch {
+                    switches.push(id);
+                }
+            }
+        }
+
+        for switch in switches {
+            self.lower(switch)
+        }
+    }
+
+    fn lower(&mut self, switch: InstructionId) {
+        let block = self.func.inst_table[switch].parent;
+        let (cond, default, mut cases) = match &self.func.inst_table[switch].operand {
+            InstOperand::Switch { args, blocks } => {
+                let cases = args[1..]
+                    .iter()
+                    .zip(blocks[1..].iter())
+                    .map(|(&imm, &dst)| Case {
+                        val: imm.as_imm().to_i64().unwrap(),
+                        imm,
+                        dst,
+                    })
+                    .collect::<Vec<_>>();
+                (args[0], blocks[0], cases)
+            }
+            _ => unreachable!(),
+        };
+        cases.sort_by_key(|case| case.val);
+
+        let clusters = self.clusterize(&cases);
+        if clusters.len() == 1 && clusters[0].is_table {
+            return;
+        }
+
+        let succs = self.func.basic_blocks.arena[block].succ.clone();
+        for &succ in &succs {
+            self.func.basic_blocks.delete_edge(block, succ);
+        }
+        self.func.remove_inst(switch);
+
+        let mut tree = SwitchTree {
+            cond,
+            default,
+            cases: &cases,
+            last: block,
+            preds: FxHashMap::default(),
+        };
+        tree.build(self.func, block, &clusters);
+
+        // Every block of the tree branching to a successor now takes over the incoming value
+        // that the successor's phis had for the original block.
+        for succ in succs {
+            let preds = &tree.preds[&succ];
+            for &id in &*self.func.basic_blocks.arena[succ].iseq_ref() {
+                if self.func.inst_table[id].opcode != Opcode::Phi {
+                    break;
+                }
+                self.func.inst_table[id].remove(&self.func.inst_table);
+                match &mut self.func.inst_table[id].operand {
+                    InstOperand::Phi { blocks, args } => {
+                        let pos = blocks.iter().position(|&b| b == block).unwrap();
+                        let arg = args[pos];
+                        blocks.remove(pos);
+                        args.remove(pos);
+                        for &pred in preds {
+                            blocks.push(pred);
+                            args.push(arg);
+                        }
+                    }
+                    _ => unreachable!(),
+                }
+                self.func.inst_table[id].set_users(&self.func.inst_table);
+            }
+        }
+    }
+
+    fn clusterize(&self, cases: &[Case]) -> Vec<Cluster> {
+        let mut clusters = vec![];
+        let mut i = 0;
+        while i < cases.len() {
+            let table_end = if self.jump_tables {
+                (i + MIN_JUMP_TABLE_CASES - 1..cases.len())
+                    .rev()
+                    .find(|&j| {
+                        let num = (j - i + 1) as i128;
+                        let range = cases[j].val as i128 - cases[i].val as i128 + 1;
+                        num * 100 >= range * MIN_JUMP_TABLE_DENSITY
+                    })
+            } else {
+                None
+            };
+            match table_end {
+                Some(j) => {
+                    clusters.push(Cluster {
+                        cases: i..j + 1,
+                        is_table: true,
+                    });
+                    i = j + 1;
+                }
+                None => {
+                    clusters.push(Cluster {
+                        cases: i..i + 1,
+                        is_table: false,
+                    });
+                    i += 1;
+                }
+            }
+        }
+        clusters
+    }
+}
+
+impl<'a> SwitchTree<'a> {
+    fn build(&mut self, func: &mut Function, block: BasicBlockId, clusters: &[Cluster]) {
+        if clusters.len() > 1 {
+            let (lhs, rhs) = clusters.split_at(clusters.len() / 2);
+            let pivot = self.cases[rhs[0].cases.start].imm;
+            let lhs_block = self.append_basic_block(func);
+            let rhs_block = self.append_basic_block(func);
+            let mut builder = func.ir_builder();
+            builder.set_insert_point(block);
+            let lt = builder.build_icmp(ICmpKind::Lt, self.cond, pivot);
+            builder.build_cond_br(lt, lhs_block, rhs_block);
+            self.build(func, lhs_block, lhs);
+            self.build(func, rhs_block, rhs);
+            return;
+        }
+
+        let mut builder = func.ir_builder();
+        builder.set_insert_point(block);
+
+        match clusters {
+            [] => {
+                builder.build_br(self.default);
+                self.add_pred(self.default, block);
+            }
+            [cluster] if cluster.is_table => {
+                let cases = self.cases[cluster.cases.clone()]
+                    .iter()
+                    .map(|case| (case.imm, case.dst))
+                    .collect::<Vec<_>>();
+                for &(_, dst) in &cases {
+                    self.add_pred(dst, block);
+                }
+                builder.build_switch(self.cond, self.default, cases);
+                self.add_pred(self.default, block);
+            }
+            [cluster] => {
+                let case = &self.cases[cluster.cases.start];
+                let eq = builder.build_icmp(ICmpKind::Eq, self.cond, case.imm);
+                builder.build_cond_br(eq, case.dst, self.default);
+                self.add_pred(case.dst, block);
+                self.add_pred(self.default, block);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn append_basic_block(&mut self, func: &mut Function) -> BasicBlockId {
+        let order = &func.basic_blocks.order;
+        let block = match order.iter().position(|&b| b == self.last) {
+            Some(pos) if pos + 1 < order.len() => {
+                let next = order[pos + 1];
+                func.append_basic_block_before(next)
+            }
+            _ => func.append_basic_block(),
+        };
+        self.last = block;
+        block
+    }
+
+    fn add_pred(&mut self, dst: BasicBlockId, pred: BasicBlockId) {
+        let preds = self.preds.entry(dst).or_default();
+        if !preds.contains(&pred) {
+            preds.push(pred)
+        }
+    }
+}
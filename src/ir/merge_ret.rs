@@ -20,9 +20,13 @@ impl MergeReturns {
                 continue;
             }
 
-            MergeReturnsOnFunction { func }.run();
+            self.run_on_function(func);
         }
     }
+
+    pub fn run_on_function(&mut self, func: &mut Function) {
+        MergeReturnsOnFunction { func }.run();
+    }
 }
 
 impl<'a> MergeReturnsOnFunction<'a> {
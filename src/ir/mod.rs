@@ -1,3 +1,4 @@
+pub mod alias_analysis;
 pub mod basic_block;
 pub mod builder;
 pub mod codegen_prepare;
@@ -5,18 +6,27 @@ pub mod const_folding;
 pub mod constant_pool;
 pub mod cse;
 pub mod dce;
+pub mod dse;
+pub mod expand_select;
 pub mod function;
 pub mod global_val;
+pub mod gvn;
+pub mod if_conversion;
+pub mod inline;
 pub mod inst_combine;
 pub mod licm;
 pub mod liveness;
+pub mod lower_switch;
 pub mod mem2reg;
 pub mod merge_ret;
 pub mod module;
 pub mod opcode;
+pub mod parser;
 pub mod prelude;
 pub mod remove_unreachable_block;
+pub mod sccp;
 pub mod simplify_loop;
+pub mod store_forwarding;
 pub mod types;
 pub mod value;
 pub mod verify;
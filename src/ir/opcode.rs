@@ -26,17 +26,33 @@ pub enum Opcode {
     Mul,    //
     Div,    //
     Rem,    //
+    UDiv,   //
+    URem,   //
     Shl,    //
+    LShr,   //
+    AShr,   //
+    And,    //
+    Or,     //
+    Xor,    //
     SIToFP, //
     FPToSI, //
+    UIToFP,
+    FPToUI,
+    FPExt,
+    FPTrunc,
     Bitcast,
     Sext, //
     Zext,
+    Trunc,
+    PtrToInt,
+    IntToPtr,
     ICmp,   //
     FCmp,   //
     Br,     //
     CondBr, //
+    Switch, //
     Phi,    //
+    Select, //
     Call,   //
     Ret,    //
 }
@@ -73,6 +89,15 @@ pub enum InstOperand {
         arg: Value,
         dsts: [BasicBlockId; 2],
     },
+    /// `args[0]` is the condition and `blocks[0]` the default destination. The other
+    /// elements are the case values and their destinations.
+    Switch {
+        args: Vec<Value>,
+        blocks: Vec<BasicBlockId>,
+    },
+    Select {
+        args: [Value; 3],
+    },
     Call {
         args: Vec<Value>,
     },
@@ -97,7 +122,10 @@ pub enum ICmpKind {
     Lt,
     Ge,
     Gt,
-    // Ne,
+    ULe,
+    ULt,
+    UGe,
+    UGt,
 }
 
 #[derive(Clone, Debug, Copy, PartialEq, Hash, Eq)]
@@ -141,7 +169,10 @@ impl Instruction {
     }
 
     pub fn set_user(&self, inst_arena: &Arena<Instruction>, new: InstructionId) {
-        inst_arena[self.id.unwrap()].users.borrow_mut().push(new);
+        let mut users = inst_arena[self.id.unwrap()].users.borrow_mut();
+        if !users.contains(&new) {
+            users.push(new);
+        }
     }
 
     pub fn add_value_operand(arena: &mut Arena<Instruction>, self_id: InstructionId, value: Value) {
@@ -238,15 +269,59 @@ impl Instruction {
             Opcode::Mul => self.operand.args()[0].const_mul(&self.operand.args()[1]),
             Opcode::Div => self.operand.args()[0].const_div(&self.operand.args()[1]),
             Opcode::Rem => self.operand.args()[0].const_rem(&self.operand.args()[1]),
+            Opcode::UDiv => self.operand.args()[0].const_udiv(&self.operand.args()[1]),
+            Opcode::URem => self.operand.args()[0].const_urem(&self.operand.args()[1]),
+            Opcode::Shl => self.operand.args()[0].const_shl(&self.operand.args()[1]),
+            Opcode::LShr => self.operand.args()[0].const_lshr(&self.operand.args()[1]),
+            Opcode::AShr => self.operand.args()[0].const_ashr(&self.operand.args()[1]),
+            Opcode::And => self.operand.args()[0].const_and(&self.operand.args()[1]),
+            Opcode::Or => self.operand.args()[0].const_or(&self.operand.args()[1]),
+            Opcode::Xor => self.operand.args()[0].const_xor(&self.operand.args()[1]),
             Opcode::ICmp => match self.operand.int_cmp()[0] {
                 ICmpKind::Eq => self.operand.args()[0].const_eq(&self.operand.args()[1]),
+                ICmpKind::Ne => self.operand.args()[0].const_ne(&self.operand.args()[1]),
                 ICmpKind::Lt => self.operand.args()[0].const_lt(&self.operand.args()[1]),
-                _ => None,
+                ICmpKind::Le => self.operand.args()[0].const_le(&self.operand.args()[1]),
+                ICmpKind::Gt => self.operand.args()[0].const_gt(&self.operand.args()[1]),
+                ICmpKind::Ge => self.operand.args()[0].const_ge(&self.operand.args()[1]),
+                ICmpKind::ULe => self.operand.args()[0].const_ule(&self.operand.args()[1]),
+                ICmpKind::ULt => self.operand.args()[0].const_ult(&self.operand.args()[1]),
+                ICmpKind::UGe => self.operand.args()[0].const_uge(&self.operand.args()[1]),
+                ICmpKind::UGt => self.operand.args()[0].const_ugt(&self.operand.args()[1]),
             },
-            Opcode::Zext => match self.ty {
-                Type::i32 => Some(Value::new_imm_int32(
-                    self.operand.args()[0].as_imm().to_i32()?,
-                )),
+            Opcode::FCmp => {
+                use ImmediateValue::{F32, F64};
+                let (x, y) = match self.operand.args() {
+                    [Value::Immediate(F64(x)), Value::Immediate(F64(y))] => (*x, *y),
+                    [Value::Immediate(F32(x)), Value::Immediate(F32(y))] => (*x as f64, *y as f64),
+                    _ => return None,
+                };
+                let unordered = x.is_nan() || y.is_nan();
+                Some(Value::Immediate(ImmediateValue::Int1(
+                    unordered
+                        || match self.operand.float_cmp()[0] {
+                            FCmpKind::UEq => x == y,
+                            FCmpKind::UNe => x != y,
+                            FCmpKind::ULe => x <= y,
+                            FCmpKind::ULt => x < y,
+                            FCmpKind::UGe => x >= y,
+                            FCmpKind::UGt => x > y,
+                        },
+                )))
+            }
+            Opcode::Sext => self.operand.args()[0].as_imm().extend_to(self.ty, false),
+            Opcode::Zext => self.operand.args()[0].as_imm().extend_to(self.ty, true),
+            Opcode::Trunc => self.operand.args()[0].as_imm().truncate_to(self.ty),
+            Opcode::SIToFP => self.operand.args()[0].as_imm().int_to_fp(self.ty, false),
+            Opcode::UIToFP => self.operand.args()[0].as_imm().int_to_fp(self.ty, true),
+            Opcode::FPToSI => self.operand.args()[0].as_imm().fp_to_int(self.ty, false),
+            Opcode::FPToUI => self.operand.args()[0].as_imm().fp_to_int(self.ty, true),
+            Opcode::FPExt | Opcode::FPTrunc => {
+                self.operand.args()[0].as_imm().fp_convert_to(self.ty)
+            }
+            Opcode::Select => match self.operand.args()[0].as_imm() {
+                ImmediateValue::Int1(true) => Some(self.operand.args()[1]),
+                ImmediateValue::Int1(false) => Some(self.operand.args()[2]),
                 _ => None,
             },
             _ => None,
@@ -268,9 +343,16 @@ impl Instruction {
                 args[0].to_string(parent, false),
                 args[1].to_string(parent, false)
             ),
-            InstOperand::Ret { arg } | InstOperand::Load { arg } | InstOperand::Cast { arg } => {
+            InstOperand::Ret { arg: Value::None } => format!("{} void", output),
+            InstOperand::Ret { arg } | InstOperand::Load { arg } => {
                 format!("{} {}", output, arg.to_string(parent, false))
             }
+            InstOperand::Cast { arg } => format!(
+                "{} {} to {}",
+                output,
+                arg.to_string(parent, false),
+                ty2str(&self.ty)
+            ),
             InstOperand::IntCmp { cond, args } => format!(
                 "{} {} {}, {}",
                 output,
@@ -293,6 +375,29 @@ impl Instruction {
                 dsts[0].index(),
                 dsts[1].index(),
             ),
+            InstOperand::Switch { args, blocks } => args[1..].iter().zip(blocks[1..].iter()).fold(
+                format!(
+                    "{} {}, %label.{}",
+                    output,
+                    args[0].to_string(parent, false),
+                    blocks[0].index()
+                ),
+                |acc, (v, b)| {
+                    format!(
+                        "{}, ({}, %label.{})",
+                        acc,
+                        v.to_string(parent, false),
+                        b.index()
+                    )
+                },
+            ),
+            InstOperand::Select { args } => format!(
+                "{} {}, {}, {}",
+                output,
+                args[0].to_string(parent, false),
+                args[1].to_string(parent, false),
+                args[2].to_string(parent, false),
+            ),
             InstOperand::Call { args } | InstOperand::Gep { args } => {
                 args.iter().enumerate().fold(output, |acc, (i, v)| {
                     format!(
@@ -326,14 +431,17 @@ impl Instruction {
 impl Opcode {
     pub fn returns_value(&self) -> bool {
         match self {
-            Opcode::Br | Opcode::CondBr | Opcode::Ret | Opcode::Store | Opcode::Call |
+            Opcode::Br | Opcode::CondBr | Opcode::Switch | Opcode::Ret | Opcode::Store | Opcode::Call |
                 /* alloca doesn't return value = */ Opcode::Alloca => false,
             _ => true,
         }
     }
 
     pub fn is_terminator(&self) -> bool {
-        matches!(self, Opcode::Br | Opcode::CondBr | Opcode::Ret)
+        matches!(
+            self,
+            Opcode::Br | Opcode::CondBr | Opcode::Switch | Opcode::Ret
+        )
     }
 
     pub fn access_memory(&self) -> bool {
@@ -351,17 +459,33 @@ impl Opcode {
             Opcode::Mul => "mul",
             Opcode::Div => "div",
             Opcode::Rem => "rem",
+            Opcode::UDiv => "udiv",
+            Opcode::URem => "urem",
             Opcode::Shl => "shl",
+            Opcode::LShr => "lshr",
+            Opcode::AShr => "ashr",
+            Opcode::And => "and",
+            Opcode::Or => "or",
+            Opcode::Xor => "xor",
             Opcode::SIToFP => "sitofp",
             Opcode::FPToSI => "fptosi",
+            Opcode::UIToFP => "uitofp",
+            Opcode::FPToUI => "fptoui",
+            Opcode::FPExt => "fpext",
+            Opcode::FPTrunc => "fptrunc",
             Opcode::Sext => "sext",
             Opcode::Zext => "zext",
             Opcode::Bitcast => "bitcast",
+            Opcode::Trunc => "trunc",
+            Opcode::PtrToInt => "ptrtoint",
+            Opcode::IntToPtr => "inttoptr",
             Opcode::ICmp => "icmp",
             Opcode::FCmp => "fcmp",
             Opcode::Br => "br",
             Opcode::CondBr => "br",
+            Opcode::Switch => "switch",
             Opcode::Phi => "phi",
+            Opcode::Select => "select",
             Opcode::Call => "call",
             Opcode::Ret => "ret",
         }
@@ -401,7 +525,7 @@ impl InstOperand {
         match self {
             Self::Branch { dst } => ::core::slice::from_ref(dst),
             Self::CondBranch { dsts, .. } => dsts,
-            Self::Phi { blocks, .. } => blocks.as_ref(),
+            Self::Phi { blocks, .. } | Self::Switch { blocks, .. } => blocks.as_ref(),
             _ => &[],
         }
     }
@@ -410,7 +534,7 @@ impl InstOperand {
         match self {
             Self::Branch { dst } => ::core::slice::from_mut(dst),
             Self::CondBranch { dsts, .. } => dsts,
-            Self::Phi { blocks, .. } => blocks.as_mut(),
+            Self::Phi { blocks, .. } | Self::Switch { blocks, .. } => blocks.as_mut(),
             _ => &mut [],
         }
     }
@@ -425,7 +549,11 @@ impl InstOperand {
             Self::Binary { args } | Self::IntCmp { args, .. } | Self::FloatCmp { args, .. } => {
                 args.as_ref()
             }
-            Self::Gep { args } | Self::Phi { args, .. } | Self::Call { args } => args.as_ref(),
+            Self::Select { args } => args,
+            Self::Gep { args }
+            | Self::Phi { args, .. }
+            | Self::Switch { args, .. }
+            | Self::Call { args } => args.as_ref(),
             _ => &[],
         }
     }
@@ -440,7 +568,11 @@ impl InstOperand {
             Self::Binary { args } | Self::IntCmp { args, .. } | Self::FloatCmp { args, .. } => {
                 args.as_mut()
             }
-            Self::Gep { args } | Self::Phi { args, .. } | Self::Call { args } => args.as_mut(),
+            Self::Select { args } => args,
+            Self::Gep { args }
+            | Self::Phi { args, .. }
+            | Self::Switch { args, .. }
+            | Self::Call { args } => args.as_mut(),
             _ => &mut [],
         }
     }
@@ -539,6 +671,10 @@ impl ICmpKind {
             ICmpKind::Le => "le",
             ICmpKind::Gt => "gt",
             ICmpKind::Ge => "ge",
+            ICmpKind::ULt => "ult",
+            ICmpKind::ULe => "ule",
+            ICmpKind::UGt => "ugt",
+            ICmpKind::UGe => "uge",
         }
     }
 }
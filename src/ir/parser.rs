@@ -0,0 +1,1156 @@
+use super::{
+    basic_block::{BasicBlock, BasicBlockId},
+    constant_pool::{Constant, ConstantArrayElement, ConstantId, ConstantKind},
+    function::FunctionId,
+    global_val::{GlobalVariableId, Linkage},
+    module::Module,
+    opcode::*,
+    types::*,
+    value::*,
+};
+use rustc_hash::FxHashMap;
+use std::{error::Error, fmt};
+
+/// An error found while parsing textual IR.
+/// `line` and `column` are 1-origin and point at the offending token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+type Result<T> = ::std::result::Result<T, ParseError>;
+
+/// Parses the textual IR printed by `impl fmt::Debug for Module` into a `Module`.
+///
+/// # Examples
+///
+/// ```
+/// use sericum::ir::parser::parse_module;
+/// let m = parse_module(
+///     "define i32 f(i32) {
+///     label.0:
+///         %0 = add i32 %arg.0, i32 1
+///         ret i32 %0
+///     }",
+/// )
+/// .unwrap();
+/// assert!(m.find_function("f").is_some());
+/// ```
+pub fn parse_module(src: &str) -> Result<Module> {
+    let tokens = Lexer::new(src).tokenize()?;
+    Parser::new(tokens).parse()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Ident(String),
+    Number(String),
+    String(String),
+    Punct(char),
+    Eof,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    line: usize,
+    column: usize,
+}
+
+struct Lexer {
+    src: Vec<char>,
+    pos: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Lexer {
+    pub fn new(src: &str) -> Self {
+        Self {
+            src: src.chars().collect(),
+            pos: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    pub fn tokenize(mut self) -> Result<Vec<Token>> {
+        let mut tokens = vec![];
+        loop {
+            self.skip_whitespace_and_comments();
+            let (line, column) = (self.line, self.column);
+            let kind = match self.peek_char(0) {
+                None => {
+                    tokens.push(Token {
+                        kind: TokenKind::Eof,
+                        line,
+                        column,
+                    });
+                    return Ok(tokens);
+                }
+                Some(c) if c.is_ascii_digit() => self.read_number(),
+                Some('-') if self.peek_char(1).map_or(false, |c| c.is_ascii_digit()) => {
+                    self.read_number()
+                }
+                Some('"') => self.read_string()?,
+                Some(c) if is_ident_char(c) => self.read_ident(),
+                Some(c) if "%@=,()[]{}*:;-".contains(c) => {
+                    self.next_char();
+                    TokenKind::Punct(c)
+                }
+                Some(c) => {
+                    return Err(ParseError::new(
+                        line,
+                        column,
+                        format!("unexpected character '{}'", c),
+                    ))
+                }
+            };
+            tokens.push(Token { kind, line, column })
+        }
+    }
+
+    fn peek_char(&self, n: usize) -> Option<char> {
+        self.src.get(self.pos + n).copied()
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.src.get(self.pos).copied()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        loop {
+            match (self.peek_char(0), self.peek_char(1)) {
+                (Some(c), _) if c.is_whitespace() => {
+                    self.next_char();
+                }
+                (Some('/'), Some('/')) => {
+                    while !matches!(self.peek_char(0), Some('\n') | None) {
+                        self.next_char();
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn read_number(&mut self) -> TokenKind {
+        let mut s = String::new();
+        if self.peek_char(0) == Some('-') {
+            s.push(self.next_char().unwrap());
+        }
+        self.read_digits(&mut s);
+        if self.peek_char(0) == Some('.') && self.peek_char(1).map_or(false, |c| c.is_ascii_digit())
+        {
+            s.push(self.next_char().unwrap());
+            self.read_digits(&mut s);
+        }
+        if matches!(self.peek_char(0), Some('e') | Some('E')) {
+            let sign = matches!(self.peek_char(1), Some('+') | Some('-')) as usize;
+            if self
+                .peek_char(1 + sign)
+                .map_or(false, |c| c.is_ascii_digit())
+            {
+                for _ in 0..1 + sign {
+                    s.push(self.next_char().unwrap());
+                }
+                self.read_digits(&mut s);
+            }
+        }
+        TokenKind::Number(s)
+    }
+
+    fn read_digits(&mut self, s: &mut String) {
+        while let Some(c) = self.peek_char(0) {
+            if !c.is_ascii_digit() {
+                break;
+            }
+            s.push(c);
+            self.next_char();
+        }
+    }
+
+    fn read_ident(&mut self) -> TokenKind {
+        let mut s = String::new();
+        while let Some(c) = self.peek_char(0) {
+            if !is_ident_char(c) {
+                break;
+            }
+            s.push(c);
+            self.next_char();
+        }
+        TokenKind::Ident(s)
+    }
+
+    fn read_string(&mut self) -> Result<TokenKind> {
+        let (line, column) = (self.line, self.column);
+        let err = |msg: &str| ParseError::new(line, column, msg.to_string());
+        self.next_char(); // "
+        let mut s = String::new();
+        loop {
+            match self.next_char() {
+                None => return Err(err("unterminated string literal")),
+                Some('"') => return Ok(TokenKind::String(s)),
+                Some('\\') => match self.next_char() {
+                    Some('n') => s.push('\n'),
+                    Some('t') => s.push('\t'),
+                    Some('r') => s.push('\r'),
+                    Some('0') => s.push('\0'),
+                    Some('\\') => s.push('\\'),
+                    Some('"') => s.push('"'),
+                    Some('\'') => s.push('\''),
+                    Some('u') => {
+                        if self.next_char() != Some('{') {
+                            return Err(err("malformed unicode escape"));
+                        }
+                        let mut hex = String::new();
+                        loop {
+                            match self.next_char() {
+                                Some('}') => break,
+                                Some(c) if c.is_ascii_hexdigit() => hex.push(c),
+                                _ => return Err(err("malformed unicode escape")),
+                            }
+                        }
+                        match u32::from_str_radix(hex.as_str(), 16)
+                            .ok()
+                            .and_then(std::char::from_u32)
+                        {
+                            Some(c) => s.push(c),
+                            None => return Err(err("malformed unicode escape")),
+                        }
+                    }
+                    _ => return Err(err("unknown escape sequence")),
+                },
+                Some(c) => s.push(c),
+            }
+        }
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.' || c == '$'
+}
+
+/// Parses instruction operands for one function.
+/// Labels and value names are resolved before the body is read so that phi nodes and
+/// branches may refer to blocks and values defined later in the text.
+struct FunctionContext {
+    func_id: FunctionId,
+    blocks: FxHashMap<String, BasicBlockId>,
+    values: FxHashMap<String, InstructionId>,
+    cur_block: Option<BasicBlockId>,
+    placed: Vec<InstructionId>,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    module: Module,
+    globals: FxHashMap<String, GlobalVariableId>,
+    consts: FxHashMap<String, ConstantId>,
+}
+
+impl Parser {
+    pub fn new(tokens: Vec<Token>) -> Self {
+        Self {
+            tokens,
+            pos: 0,
+            module: Module::new("sericum"),
+            globals: FxHashMap::default(),
+            consts: FxHashMap::default(),
+        }
+    }
+
+    pub fn parse(mut self) -> Result<Module> {
+        self.parse_module_header()?;
+
+        // Function bodies are parsed after every declaration is known,
+        // so that calls may refer to functions defined later.
+        let mut bodies = vec![];
+        loop {
+            match self.peek().kind.clone() {
+                TokenKind::Eof => break,
+                TokenKind::Punct('@') => self.parse_global_or_constant()?,
+                TokenKind::Ident(ref s) if s == "define" => {
+                    if let Some(body) = self.parse_function_header()? {
+                        bodies.push(body)
+                    }
+                }
+                _ => return Err(self.error_here("expected 'define' or global definition")),
+            }
+        }
+
+        for (func_id, pos) in bodies {
+            self.pos = pos;
+            self.parse_function_body(func_id)?;
+        }
+
+        Ok(self.module)
+    }
+
+    fn parse_module_header(&mut self) -> Result<()> {
+        if !self.is_ident("Module") {
+            return Ok(());
+        }
+        self.next();
+        self.expect_punct('(')?;
+        self.expect_ident("name")?;
+        self.expect_punct(':')?;
+        self.module.name = self.parse_name()?;
+        self.expect_punct(')')
+    }
+
+    fn parse_global_or_constant(&mut self) -> Result<()> {
+        self.expect_punct('@')?;
+        let name_tok = self.peek().clone();
+        let name = self.parse_name()?;
+        self.expect_punct('=')?;
+
+        if self.is_ident("constant") {
+            self.next();
+            let ty = self.parse_type()?;
+            let kind = match self.next().kind {
+                TokenKind::String(s) => ConstantKind::String(s),
+                TokenKind::Punct('{') => ConstantKind::Array(self.parse_const_array_elems()?),
+                _ => return Err(self.error_prev("expected string or '{'")),
+            };
+            let id = self.module.const_pool.add(Constant { ty, kind });
+            if self.consts.insert(name.clone(), id).is_some() {
+                return Err(error_at(&name_tok, format!("redefinition of '@{}'", name)));
+            }
+            return Ok(());
+        }
+
+        let linkage = match self.parse_name()?.as_str() {
+            "common" => Linkage::Common,
+            "external" => Linkage::External,
+            l => return Err(self.error_prev(format!("unknown linkage '{}'", l))),
+        };
+        self.expect_ident("global")?;
+        let ty = self.parse_type()?;
+        let id = self
+            .module
+            .global_vars
+            .new_global_var_with_name(ty, linkage, name.as_str());
+        if self.globals.insert(name.clone(), id).is_some() {
+            return Err(error_at(&name_tok, format!("redefinition of '@{}'", name)));
+        }
+        Ok(())
+    }
+
+    /// Parses elements after the opening '{'.
+    fn parse_const_array_elems(&mut self) -> Result<Vec<ConstantArrayElement>> {
+        let mut elems = vec![];
+        loop {
+            let tok = self.next();
+            let elem = match tok.kind.clone() {
+                TokenKind::Punct('}') => return Ok(elems),
+                TokenKind::Punct('{') => {
+                    ConstantArrayElement::Array(self.parse_const_array_elems()?)
+                }
+                TokenKind::Punct('@') => {
+                    let name = self.parse_name()?;
+                    match self.consts.get(&name) {
+                        Some(id) => ConstantArrayElement::String(*id),
+                        None => {
+                            return Err(self.error_prev(format!("undefined constant '@{}'", name)))
+                        }
+                    }
+                }
+                TokenKind::Ident(kind) => {
+                    let ty = match kind.as_str() {
+                        "Int1" => Type::i1,
+                        "Int8" => Type::i8,
+                        "Int16" => Type::i16,
+                        "Int32" => Type::i32,
+                        "Int64" => Type::i64,
+                        "F32" => Type::f32,
+                        "F64" => Type::f64,
+                        _ => return Err(error_at(&tok, format!("unknown constant '{}'", kind))),
+                    };
+                    self.expect_punct('(')?;
+                    let imm = self.parse_immediate(ty)?;
+                    self.expect_punct(')')?;
+                    ConstantArrayElement::Immediate(imm)
+                }
+                _ => return Err(error_at(&tok, "expected constant array element")),
+            };
+            elems.push(elem);
+            if self.is_punct(',') {
+                self.next();
+            }
+        }
+    }
+
+    /// Parses a function header and returns the position of its body, if it has one.
+    fn parse_function_header(&mut self) -> Result<Option<(FunctionId, usize)>> {
+        self.expect_ident("define")?;
+        let ret_ty = self.parse_type()?;
+        let name_tok = self.peek().clone();
+        let name = self.parse_name()?;
+        if self.module.find_function(name.as_str()).is_some() {
+            return Err(error_at(&name_tok, format!("redefinition of '{}'", name)));
+        }
+
+        self.expect_punct('(')?;
+        let mut params_ty = vec![];
+        while !self.is_punct(')') {
+            let ty = self.parse_type()?;
+            if self.is_ident("byval") {
+                self.next();
+                match ty {
+                    // `Types::new_function_ty` turns a struct parameter into a byval pointer
+                    Type::Pointer(id) => {
+                        params_ty.push(*self.module.types.compound_ty(id).as_pointer())
+                    }
+                    _ => return Err(self.error_prev("byval parameter must be a pointer")),
+                }
+            } else {
+                params_ty.push(ty)
+            }
+            if !self.is_punct(')') {
+                self.expect_punct(',')?;
+            }
+        }
+        self.expect_punct(')')?;
+
+        let func_id = self
+            .module
+            .create_function(name.as_str(), ret_ty, params_ty);
+
+        if self.is_ident("internal") {
+            self.next();
+            self.expect_punct(';')?;
+            self.module.function_ref_mut(func_id).is_internal = true;
+            return Ok(None);
+        }
+
+        let body = self.pos;
+        self.expect_punct('{')?;
+        let mut depth = 1;
+        while depth > 0 {
+            match self.next().kind {
+                TokenKind::Punct('{') => depth += 1,
+                TokenKind::Punct('}') => depth -= 1,
+                TokenKind::Eof => return Err(self.error_prev("unexpected end of input")),
+                _ => {}
+            }
+        }
+        Ok(Some((func_id, body)))
+    }
+
+    fn parse_function_body(&mut self, func_id: FunctionId) -> Result<()> {
+        let body = self.pos;
+        self.expect_punct('{')?;
+
+        // Collect labels and value names. Numbered ones keep their numbers as arena indices
+        // so that printing a parsed module reproduces the original text.
+        let mut labels = vec![];
+        let mut defs = vec![];
+        let mut depth = 1;
+        let mut i = self.pos;
+        while depth > 0 {
+            match &self.tokens[i].kind {
+                TokenKind::Punct('{') => depth += 1,
+                TokenKind::Punct('}') => depth -= 1,
+                TokenKind::Ident(name) if self.tokens[i + 1].kind == TokenKind::Punct(':') => {
+                    labels.push((name.clone(), i))
+                }
+                TokenKind::Punct('%') if self.tokens[i + 2].kind == TokenKind::Punct('=') => {
+                    match &self.tokens[i + 1].kind {
+                        TokenKind::Ident(name) | TokenKind::Number(name) => {
+                            defs.push((name.clone(), i + 1))
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+
+        let mut ctx = FunctionContext {
+            func_id,
+            blocks: FxHashMap::default(),
+            values: FxHashMap::default(),
+            cur_block: None,
+            placed: vec![],
+        };
+
+        {
+            let func = self.module.function_ref_mut(func_id);
+            let label_num = |name: &str| -> Option<usize> {
+                name.strip_prefix("label.").and_then(|n| n.parse().ok())
+            };
+            let max = labels.iter().filter_map(|(l, _)| label_num(l)).max();
+            let numbered = (0..max.map_or(0, |m| m + 1))
+                .map(|_| func.basic_blocks.arena.alloc(BasicBlock::new()))
+                .collect::<Vec<_>>();
+            for (name, pos) in &labels {
+                let id = match label_num(name) {
+                    Some(n) => numbered[n],
+                    None => func.basic_blocks.arena.alloc(BasicBlock::new()),
+                };
+                if ctx.blocks.insert(name.clone(), id).is_some() {
+                    return Err(error_at(
+                        &self.tokens[*pos],
+                        format!("redefinition of label '{}'", name),
+                    ));
+                }
+                func.basic_blocks.order.push(id);
+            }
+
+            let entry = match func.basic_blocks.order.get(0) {
+                Some(entry) => *entry,
+                None if defs.is_empty() => {
+                    self.skip_block(body)?;
+                    return Ok(());
+                }
+                None => return Err(error_at(&self.tokens[defs[0].1], "value outside of block")),
+            };
+            let max = defs
+                .iter()
+                .filter_map(|(d, _)| d.parse::<usize>().ok())
+                .max();
+            let numbered = (0..max.map_or(0, |m| m + 1))
+                .map(|_| Self::alloc_placeholder(&mut func.inst_table, entry))
+                .collect::<Vec<_>>();
+            for (name, pos) in &defs {
+                let id = match name.parse::<usize>() {
+                    Ok(n) => numbered[n],
+                    Err(_) => Self::alloc_placeholder(&mut func.inst_table, entry),
+                };
+                if ctx.values.insert(name.clone(), id).is_some() {
+                    return Err(error_at(
+                        &self.tokens[*pos],
+                        format!("redefinition of '%{}'", name),
+                    ));
+                }
+            }
+        }
+
+        loop {
+            let tok = self.peek().clone();
+            match tok.kind {
+                TokenKind::Punct('}') => {
+                    self.next();
+                    break;
+                }
+                TokenKind::Ident(name) if self.peek_nth(1).kind == TokenKind::Punct(':') => {
+                    self.next();
+                    self.next();
+                    ctx.cur_block = Some(ctx.blocks[&name]);
+                }
+                TokenKind::Punct('%') => {
+                    self.next();
+                    let name = self.parse_name()?;
+                    self.expect_punct('=')?;
+                    let id = ctx.values[&name];
+                    self.parse_inst(&mut ctx, Some(id))?;
+                    if self.module.function_ref(func_id).inst_table[id].ty == Type::Void {
+                        return Err(error_at(
+                            &tok,
+                            format!("'%{}' is defined by an instruction without a value", name),
+                        ));
+                    }
+                }
+                TokenKind::Ident(_) => self.parse_inst(&mut ctx, None)?,
+                _ => return Err(error_at(&tok, "expected instruction or label")),
+            }
+        }
+
+        let func = self.module.function_ref(func_id);
+        for id in ctx.placed {
+            func.inst_table[id].set_users(&func.inst_table);
+        }
+
+        Ok(())
+    }
+
+    fn parse_inst(&mut self, ctx: &mut FunctionContext, def: Option<InstructionId>) -> Result<()> {
+        let opcode_tok = self.next();
+        let parent = match ctx.cur_block {
+            Some(block) => block,
+            None => return Err(error_at(&opcode_tok, "instruction outside of block")),
+        };
+        let opcode_name = match opcode_tok.kind {
+            TokenKind::Ident(ref name) => name.clone(),
+            _ => return Err(error_at(&opcode_tok, "expected opcode")),
+        };
+
+        let (opcode, operand, ty) = match opcode_name.as_str() {
+            "alloca" => {
+                let ty = self.parse_type()?;
+                let ptr_ty = self.module.types.new_pointer_ty(ty);
+                (Opcode::Alloca, InstOperand::Type { ty }, ptr_ty)
+            }
+            "load" => {
+                let (arg, ptr_ty) = self.parse_value(ctx)?;
+                let ty = self.pointee_ty(ptr_ty)?;
+                (Opcode::Load, InstOperand::Load { arg }, ty)
+            }
+            "store" => {
+                let (src, _) = self.parse_value(ctx)?;
+                self.expect_punct(',')?;
+                let (dst, _) = self.parse_value(ctx)?;
+                (
+                    Opcode::Store,
+                    InstOperand::Store { args: [src, dst] },
+                    Type::Void,
+                )
+            }
+            "getelementptr" => {
+                let (base, base_ty) = self.parse_value(ctx)?;
+                let mut args = vec![base];
+                while self.is_punct(',') {
+                    self.next();
+                    args.push(self.parse_value(ctx)?.0);
+                }
+                let elem_ty = self
+                    .module
+                    .types
+                    .get_element_ty_with_indices(base_ty, &args[1..])
+                    .ok_or_else(|| self.error_prev("invalid getelementptr indices"))?;
+                let ty = self.module.types.new_pointer_ty(elem_ty);
+                (Opcode::GetElementPtr, InstOperand::Gep { args }, ty)
+            }
+            "add" | "sub" | "mul" | "div" | "rem" | "udiv" | "urem" | "shl" | "lshr" | "ashr"
+            | "and" | "or" | "xor" => {
+                let opcode = match opcode_name.as_str() {
+                    "add" => Opcode::Add,
+                    "sub" => Opcode::Sub,
+                    "mul" => Opcode::Mul,
+                    "div" => Opcode::Div,
+                    "rem" => Opcode::Rem,
+                    "udiv" => Opcode::UDiv,
+                    "urem" => Opcode::URem,
+                    "shl" => Opcode::Shl,
+                    "lshr" => Opcode::LShr,
+                    "ashr" => Opcode::AShr,
+                    "and" => Opcode::And,
+                    "or" => Opcode::Or,
+                    _ => Opcode::Xor,
+                };
+                let (lhs, ty) = self.parse_value(ctx)?;
+                self.expect_punct(',')?;
+                let (rhs, _) = self.parse_value(ctx)?;
+                (opcode, InstOperand::Binary { args: [lhs, rhs] }, ty)
+            }
+            "sitofp" | "fptosi" | "uitofp" | "fptoui" | "fpext" | "fptrunc" | "sext" | "zext"
+            | "trunc" | "ptrtoint" | "inttoptr" | "bitcast" => {
+                let opcode = match opcode_name.as_str() {
+                    "sitofp" => Opcode::SIToFP,
+                    "fptosi" => Opcode::FPToSI,
+                    "uitofp" => Opcode::UIToFP,
+                    "fptoui" => Opcode::FPToUI,
+                    "fpext" => Opcode::FPExt,
+                    "fptrunc" => Opcode::FPTrunc,
+                    "sext" => Opcode::Sext,
+                    "zext" => Opcode::Zext,
+                    "trunc" => Opcode::Trunc,
+                    "ptrtoint" => Opcode::PtrToInt,
+                    "inttoptr" => Opcode::IntToPtr,
+                    _ => Opcode::Bitcast,
+                };
+                let (arg, _) = self.parse_value(ctx)?;
+                self.expect_ident("to")?;
+                let ty = self.parse_type()?;
+                (opcode, InstOperand::Cast { arg }, ty)
+            }
+            "icmp" => {
+                let cond = match self.parse_name()?.as_str() {
+                    "eq" => ICmpKind::Eq,
+                    "ne" => ICmpKind::Ne,
+                    "lt" => ICmpKind::Lt,
+                    "le" => ICmpKind::Le,
+                    "gt" => ICmpKind::Gt,
+                    "ge" => ICmpKind::Ge,
+                    "ult" => ICmpKind::ULt,
+                    "ule" => ICmpKind::ULe,
+                    "ugt" => ICmpKind::UGt,
+                    "uge" => ICmpKind::UGe,
+                    c => return Err(self.error_prev(format!("unknown icmp kind '{}'", c))),
+                };
+                let (lhs, _) = self.parse_value(ctx)?;
+                self.expect_punct(',')?;
+                let (rhs, _) = self.parse_value(ctx)?;
+                (
+                    Opcode::ICmp,
+                    InstOperand::IntCmp {
+                        cond,
+                        args: [lhs, rhs],
+                    },
+                    Type::i1,
+                )
+            }
+            "fcmp" => {
+                let cond = match self.parse_name()?.as_str() {
+                    "ueq" => FCmpKind::UEq,
+                    "une" => FCmpKind::UNe,
+                    "ult" => FCmpKind::ULt,
+                    "ule" => FCmpKind::ULe,
+                    "ugt" => FCmpKind::UGt,
+                    "uge" => FCmpKind::UGe,
+                    c => return Err(self.error_prev(format!("unknown fcmp kind '{}'", c))),
+                };
+                let (lhs, _) = self.parse_value(ctx)?;
+                self.expect_punct(',')?;
+                let (rhs, _) = self.parse_value(ctx)?;
+                (
+                    Opcode::FCmp,
+                    InstOperand::FloatCmp {
+                        cond,
+                        args: [lhs, rhs],
+                    },
+                    Type::i1,
+                )
+            }
+            "br" if self.is_punct('%') => {
+                let dst = self.parse_label(ctx)?;
+                self.module
+                    .function_ref_mut(ctx.func_id)
+                    .basic_blocks
+                    .make_edge(parent, dst);
+                (Opcode::Br, InstOperand::Branch { dst }, Type::Void)
+            }
+            "br" => {
+                let (arg, _) = self.parse_value(ctx)?;
+                self.expect_punct(',')?;
+                let bb1 = self.parse_label(ctx)?;
+                self.expect_punct(',')?;
+                let bb2 = self.parse_label(ctx)?;
+                let blocks = &mut self.module.function_ref_mut(ctx.func_id).basic_blocks;
+                blocks.make_edge(parent, bb1);
+                blocks.make_edge(parent, bb2);
+                (
+                    Opcode::CondBr,
+                    InstOperand::CondBranch {
+                        arg,
+                        dsts: [bb1, bb2],
+                    },
+                    Type::Void,
+                )
+            }
+            "switch" => {
+                let (cond, _) = self.parse_value(ctx)?;
+                self.expect_punct(',')?;
+                let mut args = vec![cond];
+                let mut blocks = vec![self.parse_label(ctx)?];
+                while self.is_punct(',') {
+                    self.next();
+                    self.expect_punct('(')?;
+                    args.push(self.parse_value(ctx)?.0);
+                    self.expect_punct(',')?;
+                    blocks.push(self.parse_label(ctx)?);
+                    self.expect_punct(')')?;
+                }
+                let func = self.module.function_ref_mut(ctx.func_id);
+                for &bb in &blocks {
+                    func.basic_blocks.make_edge(parent, bb);
+                }
+                (
+                    Opcode::Switch,
+                    InstOperand::Switch { args, blocks },
+                    Type::Void,
+                )
+            }
+            "select" => {
+                let (cond, _) = self.parse_value(ctx)?;
+                self.expect_punct(',')?;
+                let (then_, ty) = self.parse_value(ctx)?;
+                self.expect_punct(',')?;
+                let (else_, _) = self.parse_value(ctx)?;
+                (
+                    Opcode::Select,
+                    InstOperand::Select {
+                        args: [cond, then_, else_],
+                    },
+                    ty,
+                )
+            }
+            "phi" => {
+                let mut blocks = vec![];
+                let mut args = vec![];
+                let mut ty;
+                loop {
+                    self.expect_punct('(')?;
+                    blocks.push(self.parse_label(ctx)?);
+                    self.expect_punct(',')?;
+                    let (arg, arg_ty) = self.parse_value(ctx)?;
+                    args.push(arg);
+                    ty = arg_ty;
+                    self.expect_punct(')')?;
+                    if !self.is_punct(',') {
+                        break;
+                    }
+                    self.next();
+                }
+                (Opcode::Phi, InstOperand::Phi { blocks, args }, ty)
+            }
+            "call" => {
+                let (callee, callee_ty) = self.parse_value(ctx)?;
+                let ty = match callee {
+                    Value::Function(id) => {
+                        let f_ty = self.module.function_ref(id).ty;
+                        let ret_ty = self.module.types.compound_ty(f_ty).as_function().ret_ty;
+                        ret_ty
+                    }
+                    _ => {
+                        let f_ty = self.pointee_ty(callee_ty)?;
+                        match self.module.types.base.borrow().as_function_ty(f_ty) {
+                            Some(f) => f.ret_ty,
+                            None => return Err(self.error_prev("callee is not a function")),
+                        }
+                    }
+                };
+                let mut args = vec![callee];
+                while self.is_punct(',') {
+                    self.next();
+                    args.push(self.parse_value(ctx)?.0);
+                }
+                (Opcode::Call, InstOperand::Call { args }, ty)
+            }
+            "ret" => {
+                let (arg, _) = self.parse_value(ctx)?;
+                (Opcode::Ret, InstOperand::Ret { arg }, Type::Void)
+            }
+            _ => {
+                return Err(error_at(
+                    &opcode_tok,
+                    format!("unknown opcode '{}'", opcode_name),
+                ))
+            }
+        };
+
+        let func = self.module.function_ref_mut(ctx.func_id);
+        let mut inst = Instruction::new(opcode, operand, ty, parent);
+        let id = match def {
+            Some(id) => id,
+            None => Self::alloc_placeholder(&mut func.inst_table, parent),
+        };
+        inst.set_id(id);
+        func.inst_table[id] = inst;
+        func.basic_block_ref(parent).iseq_ref_mut().push(id);
+        ctx.placed.push(id);
+
+        Ok(())
+    }
+
+    /// Parses a value preceded by its type and returns both.
+    fn parse_value(&mut self, ctx: &FunctionContext) -> Result<(Value, Type)> {
+        let ty = self.parse_type()?;
+        if ty == Type::Void {
+            return Ok((Value::None, ty));
+        }
+
+        let tok = self.peek().clone();
+        let val = match tok.kind {
+            TokenKind::Punct('%') => {
+                self.next();
+                let name = self.parse_name()?;
+                if let Some(index) = name.strip_prefix("arg.").and_then(|n| n.parse().ok()) {
+                    if index >= self.module.function_ref(ctx.func_id).get_params_len() {
+                        return Err(error_at(&tok, format!("no such argument '%{}'", name)));
+                    }
+                    Value::Argument(ArgumentValue {
+                        func_id: ctx.func_id,
+                        index,
+                    })
+                } else {
+                    match ctx.values.get(&name) {
+                        Some(&id) => Value::new_inst(ctx.func_id, id),
+                        None => return Err(error_at(&tok, format!("undefined value '%{}'", name))),
+                    }
+                }
+            }
+            TokenKind::Punct('@') => {
+                self.next();
+                let name = self.parse_name()?;
+                // Globals and constants are printed with the type they hold,
+                // while the value itself is a pointer to it.
+                let base = self.module.types.base.borrow();
+                if let Some(&id) = self.consts.get(&name) {
+                    return Ok((
+                        Value::Constant(ConstantValue { id }),
+                        base.const_ptr_types[&id],
+                    ));
+                } else if let Some(&id) = self.globals.get(&name) {
+                    return Ok((
+                        Value::Global(GlobalValue { id }),
+                        base.gblvar_ptr_types[&id],
+                    ));
+                } else {
+                    return Err(error_at(&tok, format!("undefined global '@{}'", name)));
+                }
+            }
+            TokenKind::Ident(ref name)
+                if !matches!(name.as_str(), "true" | "false" | "inf" | "NaN") =>
+            {
+                self.next();
+                match self.module.find_function(name.as_str()) {
+                    Some(id) => Value::Function(id),
+                    None => return Err(error_at(&tok, format!("undefined function '{}'", name))),
+                }
+            }
+            _ => Value::Immediate(self.parse_immediate(ty)?),
+        };
+
+        Ok((val, ty))
+    }
+
+    fn parse_immediate(&mut self, ty: Type) -> Result<ImmediateValue> {
+        let tok = self.next();
+        let err = || error_at(&tok, format!("invalid immediate of type {:?}", ty));
+        let s = match &tok.kind {
+            TokenKind::Number(s) | TokenKind::Ident(s) => s.clone(),
+            TokenKind::Punct('-') => match self.next().kind {
+                TokenKind::Ident(ref s) if s == "inf" => "-inf".to_string(),
+                _ => return Err(err()),
+            },
+            _ => return Err(err()),
+        };
+        Ok(match ty {
+            Type::i1 => ImmediateValue::Int1(match s.as_str() {
+                "true" | "1" => true,
+                "false" | "0" => false,
+                _ => return Err(err()),
+            }),
+            Type::i8 => ImmediateValue::Int8(s.parse().map_err(|_| err())?),
+            Type::i16 => ImmediateValue::Int16(s.parse().map_err(|_| err())?),
+            Type::i32 => ImmediateValue::Int32(s.parse().map_err(|_| err())?),
+            Type::i64 => ImmediateValue::Int64(s.parse().map_err(|_| err())?),
+            Type::f32 => ImmediateValue::F32(s.parse().map_err(|_| err())?),
+            Type::f64 => ImmediateValue::F64(s.parse().map_err(|_| err())?),
+            _ => return Err(err()),
+        })
+    }
+
+    fn parse_label(&mut self, ctx: &FunctionContext) -> Result<BasicBlockId> {
+        self.expect_punct('%')?;
+        let name = self.parse_name()?;
+        match ctx.blocks.get(&name) {
+            Some(&id) => Ok(id),
+            None => Err(self.error_prev(format!("undefined label '{}'", name))),
+        }
+    }
+
+    fn parse_type(&mut self) -> Result<Type> {
+        let tok = self.next();
+        let mut ty = match &tok.kind {
+            TokenKind::Ident(name) => match name.as_str() {
+                "void" => Type::Void,
+                "i1" => Type::i1,
+                "i8" => Type::i8,
+                "i16" => Type::i16,
+                "i32" => Type::i32,
+                "i64" => Type::i64,
+                "f32" => Type::f32,
+                "f64" => Type::f64,
+                "struct" => {
+                    let fields = self.parse_struct_fields()?;
+                    self.module.types.new_struct_ty(fields)
+                }
+                name if name.starts_with("struct.") => {
+                    self.parse_named_struct(&name["struct.".len()..])?
+                }
+                _ => return Err(error_at(&tok, format!("unknown type '{}'", name))),
+            },
+            TokenKind::Punct('[') => {
+                let len = match self.next().kind {
+                    TokenKind::Number(n) => n
+                        .parse()
+                        .map_err(|_| self.error_prev("invalid array length"))?,
+                    _ => return Err(self.error_prev("expected array length")),
+                };
+                self.expect_ident("x")?;
+                let elem_ty = self.parse_type()?;
+                self.expect_punct(']')?;
+                self.module.types.new_array_ty(elem_ty, len)
+            }
+            _ => return Err(error_at(&tok, "expected type")),
+        };
+
+        loop {
+            if self.is_punct('*') {
+                self.next();
+                ty = self.module.types.new_pointer_ty(ty);
+            } else if self.is_punct('(') {
+                self.next();
+                let mut params_ty = vec![];
+                while !self.is_punct(')') {
+                    let param_ty = self.parse_type()?;
+                    if self.is_ident("byval") {
+                        self.next();
+                        params_ty.push(self.pointee_ty(param_ty)?);
+                    } else {
+                        params_ty.push(param_ty);
+                    }
+                    if !self.is_punct(')') {
+                        self.expect_punct(',')?;
+                    }
+                }
+                self.expect_punct(')')?;
+                ty = self.module.types.new_function_ty(ty, params_ty);
+            } else {
+                return Ok(ty);
+            }
+        }
+    }
+
+    fn parse_named_struct(&mut self, name: &str) -> Result<Type> {
+        let ty = match self.module.types.find_named_struct(name) {
+            Some(ty) => ty,
+            None => self
+                .module
+                .types
+                .new_named_struct_ty(name.to_string(), vec![]),
+        };
+        if self.is_punct('{') {
+            self.next();
+            let fields = self.parse_struct_fields_after_brace()?;
+            let types = &self.module.types;
+            let mut strct = types.compound_ty(ty).as_struct().clone();
+            strct.set_fields(types, fields);
+            *types.compound_ty_mut(ty).as_struct_mut() = strct;
+        }
+        Ok(ty)
+    }
+
+    fn parse_struct_fields(&mut self) -> Result<Vec<Type>> {
+        self.expect_punct('{')?;
+        self.parse_struct_fields_after_brace()
+    }
+
+    fn parse_struct_fields_after_brace(&mut self) -> Result<Vec<Type>> {
+        let mut fields = vec![];
+        while !self.is_punct('}') {
+            fields.push(self.parse_type()?);
+            if !self.is_punct('}') {
+                self.expect_punct(',')?;
+            }
+        }
+        self.expect_punct('}')?;
+        Ok(fields)
+    }
+
+    fn pointee_ty(&self, ty: Type) -> Result<Type> {
+        match ty {
+            Type::Pointer(id) => Ok(*self.module.types.compound_ty(id).as_pointer()),
+            _ => Err(self.error_prev(format!(
+                "expected pointer type but found {}",
+                self.module.types.to_string(ty)
+            ))),
+        }
+    }
+
+    fn parse_name(&mut self) -> Result<String> {
+        match self.next().kind {
+            TokenKind::Ident(name) | TokenKind::Number(name) => Ok(name),
+            _ => Err(self.error_prev("expected name")),
+        }
+    }
+
+    fn skip_block(&mut self, pos: usize) -> Result<()> {
+        self.pos = pos;
+        self.expect_punct('{')?;
+        self.expect_punct('}')
+    }
+
+    fn alloc_placeholder(
+        inst_table: &mut id_arena::Arena<Instruction>,
+        parent: BasicBlockId,
+    ) -> InstructionId {
+        let id = inst_table.alloc(Instruction::new(
+            Opcode::Alloca,
+            InstOperand::None,
+            Type::Void,
+            parent,
+        ));
+        inst_table[id].set_id(id);
+        id
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos]
+    }
+
+    fn peek_nth(&self, n: usize) -> &Token {
+        &self.tokens[::std::cmp::min(self.pos + n, self.tokens.len() - 1)]
+    }
+
+    fn next(&mut self) -> Token {
+        let tok = self.tokens[self.pos].clone();
+        if tok.kind != TokenKind::Eof {
+            self.pos += 1;
+        }
+        tok
+    }
+
+    fn is_punct(&self, c: char) -> bool {
+        self.peek().kind == TokenKind::Punct(c)
+    }
+
+    fn is_ident(&self, s: &str) -> bool {
+        matches!(&self.peek().kind, TokenKind::Ident(name) if name == s)
+    }
+
+    fn expect_punct(&mut self, c: char) -> Result<()> {
+        if self.is_punct(c) {
+            self.next();
+            return Ok(());
+        }
+        Err(self.error_here(format!("expected '{}'", c)))
+    }
+
+    fn expect_ident(&mut self, s: &str) -> Result<()> {
+        if self.is_ident(s) {
+            self.next();
+            return Ok(());
+        }
+        Err(self.error_here(format!("expected '{}'", s)))
+    }
+
+    fn error_here<S: Into<String>>(&self, msg: S) -> ParseError {
+        error_at(self.peek(), msg)
+    }
+
+    fn error_prev<S: Into<String>>(&self, msg: S) -> ParseError {
+        error_at(&self.tokens[self.pos.saturating_sub(1)], msg)
+    }
+}
+
+fn error_at<S: Into<String>>(tok: &Token, msg: S) -> ParseError {
+    ParseError::new(tok.line, tok.column, msg.into())
+}
+
+impl ParseError {
+    pub fn new(line: usize, column: usize, message: String) -> Self {
+        Self {
+            line,
+            column,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ParseError: {}:{}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl Error for ParseError {}
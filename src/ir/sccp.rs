@@ -0,0 +1,356 @@
+use crate::ir::{
+    basic_block::BasicBlockId,
+    builder::IRBuilder,
+    function::Function,
+    module::Module,
+    opcode::{InstOperand, Instruction, InstructionId, Opcode},
+    remove_unreachable_block::RemoveUnreachableBlockOnFunction,
+    value::{ImmediateValue, InstructionValue, Value},
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// Sparse conditional constant propagation (Wegman and Zadeck).
+///
+/// Values are assumed to be undefined until proven otherwise, and blocks unreachable until a
+/// branch proven executable reaches them, so that constants flowing around loops and through
+/// phis whose other incoming values come from dead paths are found. Afterwards constant values
+/// replace their instructions, branches on constant conditions become `br`, and the blocks never
+/// found executable are removed.
+pub struct SparseConditionalConstantPropagation {}
+
+struct SCCPOnFunction<'a> {
+    func: &'a mut Function,
+    lattice: FxHashMap<InstructionId, LatticeValue>,
+    executable_blocks: FxHashSet<BasicBlockId>,
+    executable_edges: FxHashSet<(BasicBlockId, BasicBlockId)>,
+    flow_worklist: Vec<(BasicBlockId, BasicBlockId)>,
+    ssa_worklist: Vec<InstructionId>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LatticeValue {
+    Undef,
+    Const(Value),
+    Overdefined,
+}
+
+impl SparseConditionalConstantPropagation {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn run_on_module(&mut self, module: &mut Module) {
+        for (_, func) in &mut module.functions {
+            if func.is_internal || func.is_empty() {
+                continue;
+            }
+
+            SCCPOnFunction {
+                func,
+                lattice: FxHashMap::default(),
+                executable_blocks: FxHashSet::default(),
+                executable_edges: FxHashSet::default(),
+                flow_worklist: vec![],
+                ssa_worklist: vec![],
+            }
+            .run()
+        }
+    }
+}
+
+impl<'a> SCCPOnFunction<'a> {
+    fn run(&mut self) {
+        self.solve();
+        self.replace_constants();
+        self.fold_branches();
+        self.remove_dead_edges();
+        RemoveUnreachableBlockOnFunction::new(self.func).run();
+    }
+
+    fn solve(&mut self) {
+        let entry = self.func.get_entry_block().unwrap();
+        self.mark_block_executable(entry);
+
+        loop {
+            if let Some((from, to)) = self.flow_worklist.pop() {
+                if !self.executable_edges.insert((from, to)) {
+                    continue;
+                }
+                if self.executable_blocks.contains(&to) {
+                    // Only phis see the new incoming edge.
+                    let iseq = self.func.basic_blocks.arena[to].iseq_ref().clone();
+                    for id in iseq {
+                        if self.func.inst_table[id].opcode != Opcode::Phi {
+                            break;
+                        }
+                        self.visit(id)
+                    }
+                } else {
+                    self.mark_block_executable(to)
+                }
+                continue;
+            }
+
+            if let Some(id) = self.ssa_worklist.pop() {
+                if self
+                    .executable_blocks
+                    .contains(&self.func.inst_table[id].parent)
+                {
+                    self.visit(id)
+                }
+                continue;
+            }
+
+            break;
+        }
+    }
+
+    fn mark_block_executable(&mut self, block: BasicBlockId) {
+        self.executable_blocks.insert(block);
+        let iseq = self.func.basic_blocks.arena[block].iseq_ref().clone();
+        for id in iseq {
+            self.visit(id)
+        }
+    }
+
+    fn visit(&mut self, id: InstructionId) {
+        let inst = &self.func.inst_table[id];
+        match inst.opcode {
+            Opcode::Br => {
+                let dst = inst.operand.blocks()[0];
+                self.flow_worklist.push((inst.parent, dst));
+                return;
+            }
+            Opcode::CondBr | Opcode::Switch => {
+                let block = inst.parent;
+                for dst in self.feasible_successors(id) {
+                    self.flow_worklist.push((block, dst))
+                }
+                return;
+            }
+            Opcode::Ret | Opcode::Store => return,
+            _ => {}
+        }
+
+        let new = self.evaluate(inst);
+        let old = self.lattice_value(id);
+        if new == old {
+            return;
+        }
+        self.lattice.insert(id, new);
+        self.ssa_worklist
+            .extend(self.func.inst_table[id].users.borrow().iter().copied());
+    }
+
+    fn evaluate(&self, inst: &Instruction) -> LatticeValue {
+        match inst.opcode {
+            Opcode::Phi => {
+                let mut val = LatticeValue::Undef;
+                for (arg, &block) in inst.operand.args().iter().zip(inst.operand.blocks().iter()) {
+                    if self.executable_edges.contains(&(block, inst.parent)) {
+                        val = val.meet(self.value(arg))
+                    }
+                }
+                val
+            }
+            Opcode::Select => match self.value(&inst.operand.args()[0]) {
+                LatticeValue::Const(Value::Immediate(ImmediateValue::Int1(cond))) => {
+                    self.value(&inst.operand.args()[if cond { 1 } else { 2 }])
+                }
+                LatticeValue::Undef => LatticeValue::Undef,
+                _ => self
+                    .value(&inst.operand.args()[1])
+                    .meet(self.value(&inst.operand.args()[2])),
+            },
+            Opcode::Add
+            | Opcode::Sub
+            | Opcode::Mul
+            | Opcode::Div
+            | Opcode::Rem
+            | Opcode::UDiv
+            | Opcode::URem
+            | Opcode::Shl
+            | Opcode::LShr
+            | Opcode::AShr
+            | Opcode::And
+            | Opcode::Or
+            | Opcode::Xor
+            | Opcode::ICmp
+            | Opcode::FCmp
+            | Opcode::Sext
+            | Opcode::Zext
+            | Opcode::Trunc
+            | Opcode::SIToFP
+            | Opcode::UIToFP
+            | Opcode::FPToSI
+            | Opcode::FPToUI
+            | Opcode::FPExt
+            | Opcode::FPTrunc => {
+                let mut folded = inst.clone();
+                for arg in folded.operand.args_mut() {
+                    match self.value(arg) {
+                        LatticeValue::Const(konst) => *arg = konst,
+                        val => return val,
+                    }
+                }
+                // Division by zero traps, and by -1 may overflow. Leave them to runtime.
+                if matches!(
+                    inst.opcode,
+                    Opcode::Div | Opcode::Rem | Opcode::UDiv | Opcode::URem
+                ) && folded.operand.args()[1]
+                    .as_imm()
+                    .to_i64()
+                    .map_or(false, |i| i == 0 || i == -1)
+                {
+                    return LatticeValue::Overdefined;
+                }
+                match folded.fold_const() {
+                    Some(konst @ Value::Immediate(_)) => LatticeValue::Const(konst),
+                    _ => LatticeValue::Overdefined,
+                }
+            }
+            _ => LatticeValue::Overdefined,
+        }
+    }
+
+    /// Returns the successors of a conditional branch or a switch that may be executed.
+    fn feasible_successors(&self, id: InstructionId) -> Vec<BasicBlockId> {
+        let inst = &self.func.inst_table[id];
+        let blocks = inst.operand.blocks();
+        match self.value(&inst.operand.args()[0]) {
+            LatticeValue::Undef => vec![],
+            LatticeValue::Overdefined => blocks.to_vec(),
+            LatticeValue::Const(cond) => match &inst.operand {
+                InstOperand::CondBranch { dsts, .. } => {
+                    vec![dsts[if cond.as_imm().as_int1() { 0 } else { 1 }]]
+                }
+                InstOperand::Switch { args, blocks } => {
+                    let pos = args[1..].iter().position(|&case| case == cond);
+                    vec![blocks[pos.map_or(0, |pos| pos + 1)]]
+                }
+                _ => unreachable!(),
+            },
+        }
+    }
+
+    fn value(&self, val: &Value) -> LatticeValue {
+        match val {
+            Value::Immediate(_) => LatticeValue::Const(*val),
+            Value::Instruction(InstructionValue { id, .. }) => self.lattice_value(*id),
+            _ => LatticeValue::Overdefined,
+        }
+    }
+
+    fn lattice_value(&self, id: InstructionId) -> LatticeValue {
+        *self.lattice.get(&id).unwrap_or(&LatticeValue::Undef)
+    }
+
+    fn replace_constants(&mut self) {
+        let mut consts = vec![];
+        for &block in &self.func.basic_blocks.order {
+            if !self.executable_blocks.contains(&block) {
+                continue;
+            }
+            for &id in &*self.func.basic_blocks.arena[block].iseq_ref() {
+                if let LatticeValue::Const(konst) = self.lattice_value(id) {
+                    consts.push((id, konst))
+                }
+            }
+        }
+
+        for (id, konst) in consts {
+            Instruction::replace_all_uses(&mut self.func.inst_table, id, konst);
+            self.func.remove_inst(id);
+        }
+    }
+
+    /// Replaces conditional branches and switches with a single feasible successor with `br`.
+    fn fold_branches(&mut self) {
+        let mut branches = vec![];
+        for &block in &self.func.basic_blocks.order {
+            if !self.executable_blocks.contains(&block) {
+                continue;
+            }
+            if let Some(&id) = self.func.basic_blocks.arena[block].iseq_ref().last() {
+                let inst = &self.func.inst_table[id];
+                if matches!(inst.opcode, Opcode::CondBr | Opcode::Switch)
+                    && matches!(inst.operand.args()[0], Value::Immediate(_))
+                {
+                    branches.push((block, id))
+                }
+            }
+        }
+
+        for (block, id) in branches {
+            let dst = self.feasible_successors(id)[0];
+            self.func.remove_inst(id);
+            let mut builder = self.func.ir_builder();
+            builder.set_insert_point(block);
+            builder.build_br(dst);
+        }
+    }
+
+    /// Cuts every edge never found executable, with the incoming values of phis for the edge, so
+    /// that blocks never found executable are left with no predecessors.
+    fn remove_dead_edges(&mut self) {
+        let mut dead_edges = vec![];
+        for &block in &self.func.basic_blocks.order {
+            for &succ in &self.func.basic_blocks.arena[block].succ {
+                if !self.executable_edges.contains(&(block, succ)) {
+                    dead_edges.push((block, succ))
+                }
+            }
+        }
+
+        for (block, succ) in dead_edges {
+            self.func.basic_blocks.delete_edge(block, succ);
+            if !self.executable_blocks.contains(&succ) {
+                continue;
+            }
+            let iseq = self.func.basic_blocks.arena[succ].iseq_ref().clone();
+            for id in iseq {
+                if self.func.inst_table[id].opcode != Opcode::Phi {
+                    break;
+                }
+                self.remove_phi_incoming(id, block)
+            }
+        }
+
+        // Instructions in the dead blocks no longer use any value.
+        for &block in &self.func.basic_blocks.order {
+            if self.executable_blocks.contains(&block) {
+                continue;
+            }
+            for &id in &*self.func.basic_blocks.arena[block].iseq_ref() {
+                self.func.inst_table[id].remove(&self.func.inst_table)
+            }
+        }
+    }
+
+    fn remove_phi_incoming(&mut self, phi: InstructionId, block: BasicBlockId) {
+        self.func.inst_table[phi].remove(&self.func.inst_table);
+        if let InstOperand::Phi { blocks, args } = &mut self.func.inst_table[phi].operand {
+            while let Some(pos) = blocks.iter().position(|&b| b == block) {
+                blocks.remove(pos);
+                args.remove(pos);
+            }
+        }
+        self.func.inst_table[phi].set_users(&self.func.inst_table);
+
+        if self.func.inst_table[phi].operand.args().len() == 1 {
+            let val = self.func.inst_table[phi].operand.args()[0];
+            Instruction::replace_all_uses(&mut self.func.inst_table, phi, val);
+            self.func.remove_inst(phi);
+        }
+    }
+}
+
+impl LatticeValue {
+    fn meet(self, other: Self) -> Self {
+        match (self, other) {
+            (LatticeValue::Undef, x) | (x, LatticeValue::Undef) => x,
+            (LatticeValue::Const(x), LatticeValue::Const(y)) if x == y => self,
+            _ => LatticeValue::Overdefined,
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::analysis::dom_tree::{DominatorTree, DominatorTreeConstructor};
+use crate::ir::{
+    alias_analysis::{AliasAnalysis, AliasInfo, AliasResult},
+    basic_block::{BasicBlock, BasicBlockId},
+    function::Function,
+    module::Module,
+    opcode::{Instruction, InstructionId, Opcode},
+    value::{InstructionValue, Value},
+};
+
+/// Replaces loads with the value last stored to or loaded from the same address.
+///
+/// Blocks are visited in preorder of the dominator tree, and the values in memory known at the
+/// end of a block are also known at the start of a child only reachable from it. A store forgets
+/// the values at addresses it may alias, and a call those at addresses it may access, according
+/// to the `AliasInfo` of the function.
+pub struct StoreToLoadForwarding {}
+
+struct StoreToLoadForwardingOnFunction<'a> {
+    func: &'a mut Function,
+    removal_list: Vec<InstructionId>,
+}
+
+/// Values known to be in memory, with their addresses.
+type AvailableValues = Vec<(Value, Value)>;
+
+impl StoreToLoadForwarding {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn run_on_module(&mut self, module: &mut Module) {
+        for (_, func) in &mut module.functions {
+            if func.is_internal || func.is_empty() {
+                continue;
+            }
+
+            StoreToLoadForwardingOnFunction {
+                func,
+                removal_list: vec![],
+            }
+            .run()
+        }
+    }
+}
+
+impl<'a> StoreToLoadForwardingOnFunction<'a> {
+    fn run(mut self) {
+        AliasAnalysis::new().run_on_function(self.func);
+        let dom_tree = DominatorTreeConstructor::new(&self.func.basic_blocks).construct();
+        let entry = self.func.get_entry_block().unwrap();
+        self.run_on_block(&dom_tree, entry, vec![]);
+
+        debug!(println!(
+            "function '{}': {} loads removed",
+            self.func.name,
+            self.removal_list.len()
+        ));
+
+        for id in self.removal_list {
+            self.func.remove_inst(id)
+        }
+    }
+
+    fn run_on_block(
+        &mut self,
+        dom_tree: &DominatorTree<BasicBlock>,
+        block: BasicBlockId,
+        mut vals: AvailableValues,
+    ) {
+        let iseq = self.func.basic_blocks.arena[block].iseq_ref().clone();
+        for id in iseq {
+            let func = &*self.func;
+            let info = func.get_analysis::<AliasInfo>().unwrap();
+            let inst = &func.inst_table[id];
+            match inst.opcode {
+                Opcode::Load => {
+                    let addr = inst.operand.args()[0];
+                    if let Some(&(_, val)) = vals.iter().find(|&&(addr_, val)| {
+                        info.alias(func, addr_, addr) == AliasResult::MustAlias
+                            && func.get_value_type(&val) == inst.ty
+                    }) {
+                        Instruction::replace_all_uses(&mut self.func.inst_table, id, val);
+                        self.removal_list.push(id);
+                        continue;
+                    }
+                    let val = Value::Instruction(InstructionValue {
+                        func_id: func.id.unwrap(),
+                        id,
+                    });
+                    vals.push((addr, val))
+                }
+                Opcode::Store => {
+                    let (val, dst) = (inst.operand.args()[0], inst.operand.args()[1]);
+                    vals.retain(|&(addr, _)| info.alias(func, addr, dst) == AliasResult::NoAlias);
+                    vals.push((dst, val))
+                }
+                Opcode::Call => vals.retain(|&(addr, _)| info.points_to_local(func, addr)),
+                _ => {}
+            }
+        }
+
+        let mut children = dom_tree
+            .children_of(block)
+            .map_or(vec![], |children| children.iter().copied().collect());
+        children.sort_by_key(|child| child.index());
+        for child in children {
+            let pred = &self.func.basic_blocks.arena[child].pred;
+            let vals = if pred.len() == 1 && pred.contains(&block) {
+                vals.clone()
+            } else {
+                vec![]
+            };
+            self.run_on_block(dom_tree, child, vals)
+        }
+    }
+}
@@ -39,8 +39,10 @@ pub enum Type {
     Void,
     i1,
     i8,
+    i16,
     i32,
     i64,
+    f32,
     f64,
     Pointer(CompoundTypeId),
     Array(CompoundTypeId),
@@ -175,8 +177,10 @@ impl Types {
             Type::Void
             | Type::i1
             | Type::i8
+            | Type::i16
             | Type::i32
             | Type::i64
+            | Type::f32
             | Type::f64
             | Type::Function(_) => Some(ty),
         }
@@ -191,8 +195,10 @@ impl Types {
             Type::Void
             | Type::i1
             | Type::i8
+            | Type::i16
             | Type::i32
             | Type::i64
+            | Type::f32
             | Type::f64
             | Type::Function(_) => None,
             Type::Pointer(id) => match indices.len() {
@@ -311,8 +317,10 @@ impl TypesBase {
             Type::Void
             | Type::i1
             | Type::i8
+            | Type::i16
             | Type::i32
             | Type::i64
+            | Type::f32
             | Type::f64
             | Type::Function(_) => Some(ty),
         }
@@ -327,8 +335,10 @@ impl TypesBase {
             Type::Void
             | Type::i1
             | Type::i8
+            | Type::i16
             | Type::i32
             | Type::i64
+            | Type::f32
             | Type::f64
             | Type::Function(_) => None,
             Type::Pointer(id) => match indices.len() {
@@ -369,8 +379,10 @@ impl TypesBase {
             Type::Void => "void".to_string(),
             Type::i1 => "i1".to_string(),
             Type::i8 => "i8".to_string(),
+            Type::i16 => "i16".to_string(),
             Type::i32 => "i32".to_string(),
             Type::i64 => "i64".to_string(),
+            Type::f32 => "f32".to_string(),
             Type::f64 => "f64".to_string(),
             Type::Pointer(id) => {
                 let elem_ty = self.compound_types[id].as_pointer();
@@ -440,16 +452,42 @@ impl Type {
     pub fn is_atomic(&self) -> bool {
         matches!(
             self,
-            Self::Void | Self::i1 | Self::i8 | Self::i32 | Self::i64 | Self::f64
+            Self::Void
+                | Self::i1
+                | Self::i8
+                | Self::i16
+                | Self::i32
+                | Self::i64
+                | Self::f32
+                | Self::f64
         )
     }
 
     pub fn is_integer(&self) -> bool {
-        matches!(self, Self::i1 | Self::i8 | Self::i32 | Self::i64)
+        matches!(
+            self,
+            Self::i1 | Self::i8 | Self::i16 | Self::i32 | Self::i64
+        )
     }
 
     pub fn is_float(&self) -> bool {
-        matches!(self, Self::f64)
+        matches!(self, Self::f32 | Self::f64)
+    }
+
+    pub fn is_pointer(&self) -> bool {
+        matches!(self, Self::Pointer(_))
+    }
+
+    /// Returns the width of an integer type in bits.
+    pub fn int_bits(&self) -> Option<usize> {
+        match self {
+            Self::i1 => Some(1),
+            Self::i8 => Some(8),
+            Self::i16 => Some(16),
+            Self::i32 => Some(32),
+            Self::i64 => Some(64),
+            _ => None,
+        }
     }
 
     pub fn to_string(&self) -> String {
@@ -457,8 +495,10 @@ impl Type {
             Type::Void => "void".to_string(),
             Type::i1 => "i1".to_string(),
             Type::i8 => "i8".to_string(),
+            Type::i16 => "i16".to_string(),
             Type::i32 => "i32".to_string(),
             Type::i64 => "i64".to_string(),
+            Type::f32 => "f32".to_string(),
             Type::f64 => "f64".to_string(),
             Type::Pointer(id) => format!("(ty:{})*", id.index()),
             Type::Array(id) => format!("arrty:{}", id.index()),
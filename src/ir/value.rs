@@ -6,13 +6,32 @@ use id_arena::Arena;
 use std::hash;
 
 macro_rules! const_op {
-    ($name:ident, $op:tt) => {
+    (wrapping $name:ident, $int_op:ident, $fp_op:tt) => {
     pub fn $name(&self, v: &Value) -> Option<Value> {
         use ImmediateValue::*;
         match (self, v) {
-            (Value::Immediate(Int8(x)), Value::Immediate(Int8(y))) => Some(Value::Immediate(Int8(x $op y))),
-            (Value::Immediate(Int32(x)), Value::Immediate(Int32(y))) => Some(Value::Immediate(Int32(x $op y))),
-            (Value::Immediate(F64(x)), Value::Immediate(F64(y))) => Some(Value::Immediate(F64(x $op y))),
+            (Value::Immediate(Int8(x)), Value::Immediate(Int8(y))) => Some(Value::Immediate(Int8(x.$int_op(*y)))),
+            (Value::Immediate(Int16(x)), Value::Immediate(Int16(y))) => Some(Value::Immediate(Int16(x.$int_op(*y)))),
+            (Value::Immediate(Int64(x)), Value::Immediate(Int64(y))) => Some(Value::Immediate(Int64(x.$int_op(*y)))),
+            (Value::Immediate(Int32(x)), Value::Immediate(Int32(y))) => Some(Value::Immediate(Int32(x.$int_op(*y)))),
+            (Value::Immediate(F32(x)), Value::Immediate(F32(y))) => Some(Value::Immediate(F32(x $fp_op y))),
+            (Value::Immediate(F64(x)), Value::Immediate(F64(y))) => Some(Value::Immediate(F64(x $fp_op y))),
+            _ => None,
+        }
+    } };
+    // Division by zero and the overflowing division are left unfolded.
+    (checked $name:ident, $int_op:ident $(, $fp_op:tt)?) => {
+    pub fn $name(&self, v: &Value) -> Option<Value> {
+        use ImmediateValue::*;
+        match (self, v) {
+            (Value::Immediate(Int8(x)), Value::Immediate(Int8(y))) => Some(Value::Immediate(Int8(x.$int_op(*y)?))),
+            (Value::Immediate(Int16(x)), Value::Immediate(Int16(y))) => Some(Value::Immediate(Int16(x.$int_op(*y)?))),
+            (Value::Immediate(Int64(x)), Value::Immediate(Int64(y))) => Some(Value::Immediate(Int64(x.$int_op(*y)?))),
+            (Value::Immediate(Int32(x)), Value::Immediate(Int32(y))) => Some(Value::Immediate(Int32(x.$int_op(*y)?))),
+            $(
+            (Value::Immediate(F32(x)), Value::Immediate(F32(y))) => Some(Value::Immediate(F32(x $fp_op y))),
+            (Value::Immediate(F64(x)), Value::Immediate(F64(y))) => Some(Value::Immediate(F64(x $fp_op y))),
+            )?
             _ => None,
         }
     } };
@@ -21,16 +40,56 @@ macro_rules! const_op {
         use ImmediateValue::*;
         match (self, v) {
             (Value::Immediate(Int8(x)), Value::Immediate(Int8(y))) => Some(Value::Immediate(Int8(x $op y))),
+            (Value::Immediate(Int16(x)), Value::Immediate(Int16(y))) => Some(Value::Immediate(Int16(x $op y))),
+            (Value::Immediate(Int64(x)), Value::Immediate(Int64(y))) => Some(Value::Immediate(Int64(x $op y))),
             (Value::Immediate(Int32(x)), Value::Immediate(Int32(y))) => Some(Value::Immediate(Int32(x $op y))),
             _ => None,
         }
     } };
+    (shift $name:ident, $op:ident, $i8:ty, $i16:ty, $i32:ty, $i64:ty) => {
+    pub fn $name(&self, v: &Value) -> Option<Value> {
+        use ImmediateValue::*;
+        // The shift amount may be of another type (e.g. i8 after mul is turned into shl).
+        let y = v.get_imm()?.to_i64()? as u32;
+        match self {
+            Value::Immediate(Int8(x)) => Some(Value::Immediate(Int8((*x as $i8).$op(y) as i8))),
+            Value::Immediate(Int16(x)) => Some(Value::Immediate(Int16((*x as $i16).$op(y) as i16))),
+            Value::Immediate(Int64(x)) => Some(Value::Immediate(Int64((*x as $i64).$op(y) as i64))),
+            Value::Immediate(Int32(x)) => Some(Value::Immediate(Int32((*x as $i32).$op(y) as i32))),
+            _ => None,
+        }
+    } };
+    (unsigned $name:ident, $op:ident) => {
+    pub fn $name(&self, v: &Value) -> Option<Value> {
+        use ImmediateValue::*;
+        match (self, v) {
+            (Value::Immediate(Int8(x)), Value::Immediate(Int8(y))) => Some(Value::Immediate(Int8((*x as u8).$op(*y as u8)? as i8))),
+            (Value::Immediate(Int16(x)), Value::Immediate(Int16(y))) => Some(Value::Immediate(Int16((*x as u16).$op(*y as u16)? as i16))),
+            (Value::Immediate(Int64(x)), Value::Immediate(Int64(y))) => Some(Value::Immediate(Int64((*x as u64).$op(*y as u64)? as i64))),
+            (Value::Immediate(Int32(x)), Value::Immediate(Int32(y))) => Some(Value::Immediate(Int32((*x as u32).$op(*y as u32)? as i32))),
+            _ => None,
+        }
+    } };
+    (ucmp $name:ident, $op:tt) => {
+    pub fn $name(&self, v: &Value) -> Option<Value> {
+        use ImmediateValue::*;
+        match (self, v) {
+            (Value::Immediate(Int8(x)), Value::Immediate(Int8(y))) => Some(Value::Immediate(Int1((*x as u8) $op (*y as u8)))),
+            (Value::Immediate(Int16(x)), Value::Immediate(Int16(y))) => Some(Value::Immediate(Int1((*x as u16) $op (*y as u16)))),
+            (Value::Immediate(Int64(x)), Value::Immediate(Int64(y))) => Some(Value::Immediate(Int1((*x as u64) $op (*y as u64)))),
+            (Value::Immediate(Int32(x)), Value::Immediate(Int32(y))) => Some(Value::Immediate(Int1((*x as u32) $op (*y as u32)))),
+            _ => None,
+        }
+    } };
     (cmp $name:ident, $op:tt) => {
     pub fn $name(&self, v: &Value) -> Option<Value> {
         use ImmediateValue::*;
         match (self, v) {
             (Value::Immediate(Int8(x)), Value::Immediate(Int8(y))) => Some(Value::Immediate(Int1((x $op y) as bool))),
+            (Value::Immediate(Int16(x)), Value::Immediate(Int16(y))) => Some(Value::Immediate(Int1((x $op y) as bool))),
+            (Value::Immediate(Int64(x)), Value::Immediate(Int64(y))) => Some(Value::Immediate(Int1((x $op y) as bool))),
             (Value::Immediate(Int32(x)), Value::Immediate(Int32(y))) => Some(Value::Immediate(Int1((x $op y) as bool))),
+            (Value::Immediate(F32(x)), Value::Immediate(F32(y))) => Some(Value::Immediate(Int1((x $op y) as bool))),
             (Value::Immediate(F64(x)), Value::Immediate(F64(y))) => Some(Value::Immediate(Int1((x $op y) as bool))),
             _ => None,
         }
@@ -74,8 +133,10 @@ pub struct ConstantValue {
 pub enum ImmediateValue {
     Int1(bool),
     Int8(i8),
+    Int16(i16),
     Int32(i32),
     Int64(i64),
+    F32(f32),
     F64(f64),
 }
 
@@ -84,8 +145,10 @@ impl hash::Hash for ImmediateValue {
         match self {
             Self::Int1(i) => i.hash(state),
             Self::Int8(i) => i.hash(state),
+            Self::Int16(i) => i.hash(state),
             Self::Int32(i) => i.hash(state),
             Self::Int64(i) => i.hash(state),
+            Self::F32(f) => f.to_bits().hash(state),
             Self::F64(f) => unsafe { ::std::mem::transmute::<f64, u64>(*f) }.hash(state),
         }
     }
@@ -98,10 +161,22 @@ impl Value {
         Self::Immediate(ImmediateValue::Int8(i))
     }
 
+    pub fn new_imm_int16(i: i16) -> Self {
+        Self::Immediate(ImmediateValue::Int16(i))
+    }
+
     pub fn new_imm_int32(i: i32) -> Self {
         Self::Immediate(ImmediateValue::Int32(i))
     }
 
+    pub fn new_imm_int64(i: i64) -> Self {
+        Self::Immediate(ImmediateValue::Int64(i))
+    }
+
+    pub fn new_imm_f32(f: f32) -> Self {
+        Self::Immediate(ImmediateValue::F32(f))
+    }
+
     pub fn new_imm_f64(f: f64) -> Self {
         Self::Immediate(ImmediateValue::F64(f))
     }
@@ -119,8 +194,10 @@ impl Value {
             Type::Void => Value::None,
             Type::i1 => Value::Immediate(ImmediateValue::Int8(0)),
             Type::i8 => Value::Immediate(ImmediateValue::Int8(0)),
+            Type::i16 => Value::Immediate(ImmediateValue::Int16(0)),
             Type::i32 => Value::Immediate(ImmediateValue::Int32(0)),
             Type::i64 => Value::Immediate(ImmediateValue::Int64(0)),
+            Type::f32 => Value::Immediate(ImmediateValue::F32(0.0)),
             Type::f64 => Value::Immediate(ImmediateValue::F64(0.0)),
             _ => todo!(),
         }
@@ -135,13 +212,29 @@ impl Value {
 
     // Constant folding
 
-    const_op!(const_add, +);
-    const_op!(const_sub, -);
-    const_op!(const_mul, *);
-    const_op!(const_div, /);
-    const_op!(int_only const_rem, %);
+    const_op!(wrapping const_add, wrapping_add, +);
+    const_op!(wrapping const_sub, wrapping_sub, -);
+    const_op!(wrapping const_mul, wrapping_mul, *);
+    const_op!(checked const_div, checked_div, /);
+    const_op!(checked const_rem, checked_rem);
+    const_op!(unsigned const_udiv, checked_div);
+    const_op!(unsigned const_urem, checked_rem);
+    const_op!(int_only const_and, &);
+    const_op!(int_only const_or, |);
+    const_op!(int_only const_xor, ^);
+    const_op!(shift const_shl, wrapping_shl, i8, i16, i32, i64);
+    const_op!(shift const_lshr, wrapping_shr, u8, u16, u32, u64);
+    const_op!(shift const_ashr, wrapping_shr, i8, i16, i32, i64);
     const_op!(cmp const_eq, ==);
+    const_op!(cmp const_ne, !=);
     const_op!(cmp const_lt, <);
+    const_op!(cmp const_le, <=);
+    const_op!(cmp const_gt, >);
+    const_op!(cmp const_ge, >=);
+    const_op!(ucmp const_ule, <=);
+    const_op!(ucmp const_ult, <);
+    const_op!(ucmp const_uge, >=);
+    const_op!(ucmp const_ugt, >);
 
     // Utils
 
@@ -150,13 +243,15 @@ impl Value {
             Value::Argument(ArgumentValue { index, func_id, .. }) => {
                 let f = parent.function_ref(*func_id);
                 let ty = f.get_param_type(*index).unwrap();
-                format!("{} %arg.{}", ty.to_string(), index)
+                format!("{} %arg.{}", parent.types.to_string(ty), index)
             }
             Value::Immediate(iv) => match iv {
                 ImmediateValue::Int1(i) => format!("i1 {}", i),
                 ImmediateValue::Int8(i) => format!("i8 {}", i),
+                ImmediateValue::Int16(i) => format!("i16 {}", i),
                 ImmediateValue::Int32(i) => format!("i32 {}", i),
                 ImmediateValue::Int64(i) => format!("i64 {}", i),
+                ImmediateValue::F32(f) => format!("f32 {}", f),
                 ImmediateValue::F64(f) => format!("f64 {}", f),
             },
             Value::Instruction(InstructionValue { func_id, id, .. }) if inst => {
@@ -245,9 +340,15 @@ impl ImmediateValue {
         match self {
             Self::Int1(x) => Some(1 - *x as u32),
             Self::Int8(x) if (*x as usize).is_power_of_two() => Some(x.trailing_zeros()),
+            Self::Int16(x) if (*x as usize).is_power_of_two() => Some(x.trailing_zeros()),
             Self::Int32(x) if (*x as usize).is_power_of_two() => Some(x.trailing_zeros()),
             Self::Int64(x) if (*x as usize).is_power_of_two() => Some(x.trailing_zeros()),
-            Self::Int64(_) | Self::Int32(_) | Self::Int8(_) | Self::F64(_) => None,
+            Self::Int64(_)
+            | Self::Int32(_)
+            | Self::Int16(_)
+            | Self::Int8(_)
+            | Self::F32(_)
+            | Self::F64(_) => None,
         }
     }
 
@@ -255,8 +356,10 @@ impl ImmediateValue {
         match self {
             ImmediateValue::Int1(_) => &Type::i1,
             ImmediateValue::Int8(_) => &Type::i8,
+            ImmediateValue::Int16(_) => &Type::i16,
             ImmediateValue::Int32(_) => &Type::i32,
             ImmediateValue::Int64(_) => &Type::i64,
+            ImmediateValue::F32(_) => &Type::f32,
             ImmediateValue::F64(_) => &Type::f64,
         }
     }
@@ -275,6 +378,13 @@ impl ImmediateValue {
         }
     }
 
+    pub fn as_f32(&self) -> f32 {
+        match self {
+            ImmediateValue::F32(f) => *f,
+            _ => panic!(),
+        }
+    }
+
     pub fn as_f64(&self) -> f64 {
         match self {
             ImmediateValue::F64(f) => *f,
@@ -285,11 +395,99 @@ impl ImmediateValue {
     pub fn to_i32(&self) -> Option<i32> {
         match self {
             ImmediateValue::Int8(i) => Some(*i as i32),
+            ImmediateValue::Int16(i) => Some(*i as i32),
             ImmediateValue::Int32(i) => Some(*i),
             ImmediateValue::Int64(i) => Some(*i as i32),
             _ => todo!(),
         }
     }
+
+    /// Returns the sign-extended value of an integer immediate.
+    pub fn to_i64(&self) -> Option<i64> {
+        match self {
+            ImmediateValue::Int1(i) => Some(*i as i64),
+            ImmediateValue::Int8(i) => Some(*i as i64),
+            ImmediateValue::Int16(i) => Some(*i as i64),
+            ImmediateValue::Int32(i) => Some(*i as i64),
+            ImmediateValue::Int64(i) => Some(*i),
+            ImmediateValue::F32(_) | ImmediateValue::F64(_) => None,
+        }
+    }
+
+    /// Sign-extends (or zero-extends if `unsigned`) an integer immediate to `ty`.
+    pub fn extend_to(&self, ty: Type, unsigned: bool) -> Option<Value> {
+        let i = match self {
+            ImmediateValue::Int1(i) => *i as i64,
+            ImmediateValue::Int8(i) if unsigned => *i as u8 as i64,
+            ImmediateValue::Int8(i) => *i as i64,
+            ImmediateValue::Int16(i) if unsigned => *i as u16 as i64,
+            ImmediateValue::Int16(i) => *i as i64,
+            ImmediateValue::Int32(i) if unsigned => *i as u32 as i64,
+            ImmediateValue::Int32(i) => *i as i64,
+            ImmediateValue::Int64(i) => *i,
+            ImmediateValue::F32(_) | ImmediateValue::F64(_) => return None,
+        };
+        match ty {
+            Type::i8 => Some(Value::new_imm_int8(i as i8)),
+            Type::i16 => Some(Value::new_imm_int16(i as i16)),
+            Type::i32 => Some(Value::new_imm_int32(i as i32)),
+            Type::i64 => Some(Value::new_imm_int64(i)),
+            _ => None,
+        }
+    }
+
+    /// Truncates an integer immediate to the narrower integer type `ty`.
+    pub fn truncate_to(&self, ty: Type) -> Option<Value> {
+        if ty == Type::i1 {
+            return match self.extend_to(Type::i64, false)? {
+                Value::Immediate(ImmediateValue::Int64(i)) => {
+                    Some(Value::Immediate(ImmediateValue::Int1(i & 1 == 1)))
+                }
+                _ => None,
+            };
+        }
+        self.extend_to(ty, false)
+    }
+
+    /// Converts an integer immediate to the floating-point type `ty` (`sitofp`/`uitofp`).
+    pub fn int_to_fp(&self, ty: Type, unsigned: bool) -> Option<Value> {
+        let i = match self.extend_to(Type::i64, unsigned)? {
+            Value::Immediate(ImmediateValue::Int64(i)) => i,
+            _ => return None,
+        };
+        match ty {
+            Type::f32 if unsigned => Some(Value::new_imm_f32(i as u64 as f32)),
+            Type::f32 => Some(Value::new_imm_f32(i as f32)),
+            Type::f64 if unsigned => Some(Value::new_imm_f64(i as u64 as f64)),
+            Type::f64 => Some(Value::new_imm_f64(i as f64)),
+            _ => None,
+        }
+    }
+
+    /// Converts a floating-point immediate to the integer type `ty` (`fptosi`/`fptoui`).
+    pub fn fp_to_int(&self, ty: Type, unsigned: bool) -> Option<Value> {
+        let f = match self {
+            ImmediateValue::F32(f) => *f as f64,
+            ImmediateValue::F64(f) => *f,
+            _ => return None,
+        };
+        let i = if unsigned { f as u64 as i64 } else { f as i64 };
+        ImmediateValue::Int64(i).truncate_to(ty)
+    }
+
+    /// Converts a floating-point immediate to `ty` (`fpext`/`fptrunc`).
+    pub fn fp_convert_to(&self, ty: Type) -> Option<Value> {
+        let f = match self {
+            ImmediateValue::F32(f) => *f as f64,
+            ImmediateValue::F64(f) => *f,
+            _ => return None,
+        };
+        match ty {
+            Type::f32 => Some(Value::new_imm_f32(f as f32)),
+            Type::f64 => Some(Value::new_imm_f64(f)),
+            _ => None,
+        }
+    }
 }
 
 impl Into<Value> for i32 {
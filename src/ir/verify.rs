@@ -1,9 +1,46 @@
-use crate::{function::Function, module::Module};
+use crate::{
+    analysis::dom_tree::{DominatorTree, DominatorTreeConstructor},
+    ir::{
+        basic_block::{BasicBlock, BasicBlockId},
+        function::Function,
+        module::Module,
+        opcode::{Instruction, InstructionId, Opcode},
+        types::Type,
+        value::{InstructionValue, Value},
+    },
+};
+use rustc_hash::FxHashSet;
 use std::{error::Error, fmt};
 
-#[derive(Debug)]
-pub enum VerifyError {
-    Message(&'static str),
+/// An error found by the verifier.
+/// `block` and `inst` point at the place where the verifier stopped, if any.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifyError {
+    pub func_name: String,
+    pub block: Option<BasicBlockId>,
+    pub inst: Option<InstructionId>,
+    pub kind: VerifyErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerifyErrorKind {
+    EmptyBlock,
+    MissingTerminator,
+    TerminatorNotAtEnd,
+    PhiNotAtBeginning,
+    WrongParent,
+    SuccessorsMismatch,
+    PredecessorsMismatch,
+    PhiIncomingMismatch,
+    DuplicateSwitchCase,
+    TypeMismatch(&'static str),
+    UseOfRemovedInstruction(InstructionId),
+    UseOfForeignValue,
+    UseNotDominated(InstructionId),
+    UserNotRegistered(InstructionId),
+    StaleUser(InstructionId),
+    CalleeNotFunction,
+    CallArgCountMismatch { expected: usize, found: usize },
 }
 
 type Result<T> = ::std::result::Result<T, VerifyError>;
@@ -16,14 +53,452 @@ pub fn verify_module(module: &Module) -> Result<()> {
     Ok(())
 }
 
-pub fn verify_function(_func: &Function) -> Result<()> {
+pub fn verify_function(func: &Function) -> Result<()> {
+    if func.is_internal || func.is_empty() {
+        return Ok(());
+    }
+
+    let dom_tree = DominatorTreeConstructor::new(&func.basic_blocks).construct();
+    let verifier = FunctionVerifier {
+        func,
+        dom_tree,
+        live: func
+            .basic_blocks
+            .order
+            .iter()
+            .flat_map(|&b| func.basic_blocks.arena[b].iseq_ref().clone())
+            .collect(),
+    };
+
+    for &block_id in &func.basic_blocks.order {
+        verifier.verify_block(block_id)?;
+    }
+
+    for &block_id in &func.basic_blocks.order {
+        for &inst_id in &*func.basic_blocks.arena[block_id].iseq_ref() {
+            verifier.verify_inst(&func.inst_table[inst_id])?;
+        }
+    }
+
     Ok(())
 }
 
+struct FunctionVerifier<'a> {
+    func: &'a Function,
+    dom_tree: DominatorTree<BasicBlock>,
+    live: FxHashSet<InstructionId>,
+}
+
+impl<'a> FunctionVerifier<'a> {
+    fn verify_block(&self, block_id: BasicBlockId) -> Result<()> {
+        let block = &self.func.basic_blocks.arena[block_id];
+        let iseq = block.iseq_ref();
+        let err = |inst: Option<InstructionId>, kind| self.error(Some(block_id), inst, kind);
+
+        let last = match iseq.last() {
+            Some(last) => *last,
+            None => return Err(err(None, VerifyErrorKind::EmptyBlock)),
+        };
+
+        let mut phi_allowed = true;
+        for &inst_id in &*iseq {
+            let inst = &self.func.inst_table[inst_id];
+            if inst.parent != block_id {
+                return Err(err(Some(inst_id), VerifyErrorKind::WrongParent));
+            }
+            if inst.opcode.is_terminator() && inst_id != last {
+                return Err(err(Some(inst_id), VerifyErrorKind::TerminatorNotAtEnd));
+            }
+            if inst.opcode == Opcode::Phi && !phi_allowed {
+                return Err(err(Some(inst_id), VerifyErrorKind::PhiNotAtBeginning));
+            }
+            phi_allowed &= inst.opcode == Opcode::Phi;
+        }
+
+        let terminator = &self.func.inst_table[last];
+        if !terminator.opcode.is_terminator() {
+            return Err(err(Some(last), VerifyErrorKind::MissingTerminator));
+        }
+
+        let succ: FxHashSet<BasicBlockId> = terminator.operand.blocks().iter().copied().collect();
+        if succ != block.succ {
+            return Err(err(Some(last), VerifyErrorKind::SuccessorsMismatch));
+        }
+
+        for pred in &block.pred {
+            if !self.func.basic_blocks.arena[*pred].succ.contains(&block_id) {
+                return Err(err(None, VerifyErrorKind::PredecessorsMismatch));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn verify_inst(&self, inst: &Instruction) -> Result<()> {
+        let inst_id = inst.id.unwrap();
+        let err = |kind| self.error(Some(inst.parent), Some(inst_id), kind);
+        let mismatch = |what| err(VerifyErrorKind::TypeMismatch(what));
+        let args = inst.operand.args();
+        let ty_of = |v: &Value| self.func.get_value_type(v);
+
+        match inst.opcode {
+            Opcode::Alloca => {
+                let ty = inst.operand.types()[0];
+                if self.func.types.new_pointer_ty(ty) != inst.ty {
+                    return Err(mismatch(
+                        "alloca must return a pointer to the allocated type",
+                    ));
+                }
+            }
+            Opcode::Load => {
+                if self.pointee(ty_of(&args[0])) != Some(inst.ty) {
+                    return Err(mismatch("load must read its type through a pointer"));
+                }
+            }
+            Opcode::Store => {
+                let compatible = self
+                    .pointee(ty_of(&args[1]))
+                    .map_or(false, |ty| self.compatible(ty, &args[0]));
+                if !compatible {
+                    return Err(mismatch(
+                        "store must write its source type through a pointer",
+                    ));
+                }
+            }
+            Opcode::GetElementPtr => {
+                let ty = self
+                    .func
+                    .types
+                    .get_element_ty_with_indices(ty_of(&args[0]), &args[1..])
+                    .map(|ty| self.func.types.new_pointer_ty(ty));
+                if ty != Some(inst.ty) {
+                    return Err(mismatch("getelementptr result type doesn't match indices"));
+                }
+            }
+            Opcode::Add | Opcode::Sub | Opcode::Mul | Opcode::Div | Opcode::Rem => {
+                if !self.compatible(inst.ty, &args[0]) || !self.compatible(inst.ty, &args[1]) {
+                    return Err(mismatch("binary operands must have the result type"));
+                }
+            }
+            Opcode::UDiv | Opcode::URem => {
+                if !inst.ty.is_integer()
+                    || !self.compatible(inst.ty, &args[0])
+                    || !self.compatible(inst.ty, &args[1])
+                {
+                    return Err(mismatch(
+                        "unsigned division operands must be integers of the result type",
+                    ));
+                }
+            }
+            Opcode::And | Opcode::Or | Opcode::Xor => {
+                if !inst.ty.is_integer()
+                    || !self.compatible(inst.ty, &args[0])
+                    || !self.compatible(inst.ty, &args[1])
+                {
+                    return Err(mismatch(
+                        "bitwise operands must be integers of the result type",
+                    ));
+                }
+            }
+            Opcode::Shl | Opcode::LShr | Opcode::AShr => {
+                if ty_of(&args[0]) != inst.ty || !ty_of(&args[1]).is_integer() {
+                    return Err(mismatch("shift operands must be integers"));
+                }
+            }
+            Opcode::SIToFP | Opcode::UIToFP => {
+                if !ty_of(&args[0]).is_integer() || !inst.ty.is_float() {
+                    return Err(mismatch(
+                        "sitofp and uitofp must convert an integer to a float",
+                    ));
+                }
+            }
+            Opcode::FPToSI | Opcode::FPToUI => {
+                if !ty_of(&args[0]).is_float() || !inst.ty.is_integer() {
+                    return Err(mismatch(
+                        "fptosi and fptoui must convert a float to an integer",
+                    ));
+                }
+            }
+            Opcode::FPExt | Opcode::FPTrunc => {
+                if !ty_of(&args[0]).is_float() || !inst.ty.is_float() {
+                    return Err(mismatch(
+                        "fpext and fptrunc must convert a float to a float",
+                    ));
+                }
+            }
+            Opcode::Sext | Opcode::Zext => {
+                if !ty_of(&args[0]).is_integer() || !inst.ty.is_integer() {
+                    return Err(mismatch(
+                        "sext and zext must convert an integer to an integer",
+                    ));
+                }
+            }
+            Opcode::Trunc => match (ty_of(&args[0]).int_bits(), inst.ty.int_bits()) {
+                (Some(from), Some(to)) if to < from => {}
+                _ => {
+                    return Err(mismatch(
+                        "trunc must convert an integer to a narrower integer",
+                    ))
+                }
+            },
+            Opcode::PtrToInt => {
+                if !ty_of(&args[0]).is_pointer() || !inst.ty.is_integer() {
+                    return Err(mismatch("ptrtoint must convert a pointer to an integer"));
+                }
+            }
+            Opcode::IntToPtr => {
+                if !ty_of(&args[0]).is_integer() || !inst.ty.is_pointer() {
+                    return Err(mismatch("inttoptr must convert an integer to a pointer"));
+                }
+            }
+            Opcode::Bitcast => {}
+            Opcode::ICmp | Opcode::FCmp => {
+                let same = self.compatible(ty_of(&args[0]), &args[1])
+                    || self.compatible(ty_of(&args[1]), &args[0]);
+                if !same || inst.ty != Type::i1 {
+                    return Err(mismatch("compared operands must have the same type"));
+                }
+            }
+            Opcode::CondBr => {
+                if ty_of(&args[0]) != Type::i1 {
+                    return Err(mismatch("branch condition must be i1"));
+                }
+            }
+            Opcode::Switch => {
+                let ty = ty_of(&args[0]);
+                if !ty.is_integer() {
+                    return Err(mismatch("switch condition must be an integer"));
+                }
+                let mut cases = FxHashSet::default();
+                for case in &args[1..] {
+                    let imm = match case {
+                        Value::Immediate(imm) if *imm.get_type() == ty => imm,
+                        _ => {
+                            return Err(mismatch(
+                                "switch cases must be immediates of the condition type",
+                            ))
+                        }
+                    };
+                    if !cases.insert(imm.to_i64()) {
+                        return Err(err(VerifyErrorKind::DuplicateSwitchCase));
+                    }
+                }
+            }
+            Opcode::Select => {
+                if ty_of(&args[0]) != Type::i1 {
+                    return Err(mismatch("select condition must be i1"));
+                }
+                if !self.compatible(inst.ty, &args[1]) || !self.compatible(inst.ty, &args[2]) {
+                    return Err(mismatch("select operands must have the result type"));
+                }
+            }
+            Opcode::Br => {}
+            Opcode::Ret => {
+                if !self.compatible(self.func.get_return_type(), &args[0]) {
+                    return Err(mismatch(
+                        "returned value must have the function's return type",
+                    ));
+                }
+            }
+            Opcode::Phi => {
+                if args.iter().any(|a| !self.compatible(inst.ty, a)) {
+                    return Err(mismatch("phi incoming values must have the result type"));
+                }
+                let blocks = inst.operand.blocks();
+                let incoming: FxHashSet<BasicBlockId> = blocks.iter().copied().collect();
+                if incoming.len() != blocks.len()
+                    || incoming != self.func.basic_block_ref(inst.parent).pred
+                {
+                    return Err(err(VerifyErrorKind::PhiIncomingMismatch));
+                }
+            }
+            Opcode::Call => self.verify_call(inst)?,
+        }
+
+        self.verify_operands(inst)
+    }
+
+    fn verify_call(&self, inst: &Instruction) -> Result<()> {
+        let err = |kind| self.error(Some(inst.parent), inst.id, kind);
+        let args = inst.operand.args();
+        let callee_ty = self
+            .pointee(self.func.get_value_type(&args[0]))
+            .ok_or_else(|| err(VerifyErrorKind::CalleeNotFunction))?;
+        let base = self.func.types.base.borrow();
+        let func_ty = base
+            .as_function_ty(callee_ty)
+            .ok_or_else(|| err(VerifyErrorKind::CalleeNotFunction))?;
+
+        if func_ty.params_ty.len() != args.len() - 1 {
+            return Err(err(VerifyErrorKind::CallArgCountMismatch {
+                expected: func_ty.params_ty.len(),
+                found: args.len() - 1,
+            }));
+        }
+        for (param_ty, arg) in func_ty.params_ty.iter().zip(args[1..].iter()) {
+            // Integer arguments are passed in whole registers, so their width may differ
+            let both_integers = param_ty.is_integer() && self.func.get_value_type(arg).is_integer();
+            if !self.compatible(*param_ty, arg) && !both_integers {
+                return Err(err(VerifyErrorKind::TypeMismatch(
+                    "call argument must have the parameter type",
+                )));
+            }
+        }
+        if func_ty.ret_ty != inst.ty {
+            return Err(err(VerifyErrorKind::TypeMismatch(
+                "call must return the callee's return type",
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Checks that operands are defined before used and that `users` lists agree with operands.
+    fn verify_operands(&self, inst: &Instruction) -> Result<()> {
+        let inst_id = inst.id.unwrap();
+        let err = |kind| self.error(Some(inst.parent), Some(inst_id), kind);
+
+        for (i, arg) in inst.operand.args().iter().enumerate() {
+            let (func_id, def_id) = match arg {
+                Value::Instruction(InstructionValue { func_id, id }) => (*func_id, *id),
+                Value::Argument(a) if Some(a.func_id) != self.func.id => {
+                    return Err(err(VerifyErrorKind::UseOfForeignValue))
+                }
+                _ => continue,
+            };
+            if Some(func_id) != self.func.id {
+                return Err(err(VerifyErrorKind::UseOfForeignValue));
+            }
+            if !self.live.contains(&def_id) {
+                return Err(err(VerifyErrorKind::UseOfRemovedInstruction(def_id)));
+            }
+            if !self.func.inst_table[def_id]
+                .users
+                .borrow()
+                .contains(&inst_id)
+            {
+                return Err(err(VerifyErrorKind::UserNotRegistered(def_id)));
+            }
+
+            // A phi uses its incoming value at the end of the incoming block
+            let use_block = if inst.opcode == Opcode::Phi {
+                inst.operand.blocks()[i]
+            } else {
+                inst.parent
+            };
+            if !self.dominates(def_id, use_block, inst_id) {
+                return Err(err(VerifyErrorKind::UseNotDominated(def_id)));
+            }
+        }
+
+        for &user in &*inst.users.borrow() {
+            let uses_inst = self.func.inst_table[user]
+                .operand
+                .args()
+                .iter()
+                .any(|a| a.get_inst_id() == Some(inst_id));
+            if !self.live.contains(&user) || !uses_inst {
+                return Err(err(VerifyErrorKind::StaleUser(user)));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn dominates(
+        &self,
+        def_id: InstructionId,
+        use_block: BasicBlockId,
+        user: InstructionId,
+    ) -> bool {
+        let def_block = self.func.inst_table[def_id].parent;
+        // Uses in unreachable blocks are never executed
+        if !self.dom_tree.level.contains_key(&use_block) {
+            return true;
+        }
+        if def_block != use_block {
+            return self.dom_tree.dominate_bb(def_block, use_block);
+        }
+        if self.func.inst_table[user].opcode == Opcode::Phi {
+            return true;
+        }
+        let iseq = self.func.basic_block_ref(def_block).iseq_ref();
+        let pos = |id| iseq.iter().position(|&i| i == id);
+        pos(def_id) < pos(user)
+    }
+
+    /// Returns true if `val` may be used where a value of type `expected` is required.
+    /// Any pointer may be used where a pointer is expected (e.g. `sericum.memset.*` or storing
+    /// the result of `sericum.malloc.*`), and integer immediates are encoded with the width of
+    /// the expected type by the backends, so frontends may leave them as e.g. `i32`.
+    fn compatible(&self, expected: Type, val: &Value) -> bool {
+        let ty = self.func.get_value_type(val);
+        ty == expected
+            || matches!(expected, Type::Pointer(_)) && matches!(ty, Type::Pointer(_))
+            || matches!(val, Value::Immediate(_)) && expected.is_integer() && ty.is_integer()
+    }
+
+    fn pointee(&self, ty: Type) -> Option<Type> {
+        match ty {
+            Type::Pointer(id) => Some(*self.func.types.compound_ty(id).as_pointer()),
+            _ => None,
+        }
+    }
+
+    fn error(
+        &self,
+        block: Option<BasicBlockId>,
+        inst: Option<InstructionId>,
+        kind: VerifyErrorKind,
+    ) -> VerifyError {
+        VerifyError {
+            func_name: self.func.name.clone(),
+            block,
+            inst,
+            kind,
+        }
+    }
+}
+
 impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "VerifyError: in function '{}'", self.func_name)?;
+        if let Some(block) = self.block {
+            write!(f, ", label.{}", block.index())?;
+        }
+        if let Some(inst) = self.inst {
+            write!(f, ", %{}", inst.index())?;
+        }
+        write!(f, ": {}", self.kind)
+    }
+}
+
+impl fmt::Display for VerifyErrorKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            VerifyError::Message(msg) => write!(f, "VerifyError: Message: {}", msg),
+            Self::EmptyBlock => write!(f, "block is empty"),
+            Self::MissingTerminator => write!(f, "block doesn't end with a terminator"),
+            Self::TerminatorNotAtEnd => write!(f, "terminator in the middle of block"),
+            Self::PhiNotAtBeginning => write!(f, "phi must be at the beginning of block"),
+            Self::WrongParent => write!(f, "instruction's parent is not the block containing it"),
+            Self::SuccessorsMismatch => write!(f, "successors don't match the terminator"),
+            Self::PredecessorsMismatch => write!(f, "predecessor doesn't have block as successor"),
+            Self::PhiIncomingMismatch => write!(f, "phi incoming blocks don't match predecessors"),
+            Self::DuplicateSwitchCase => write!(f, "switch has duplicate case values"),
+            Self::TypeMismatch(msg) => write!(f, "type mismatch: {}", msg),
+            Self::UseOfRemovedInstruction(id) => {
+                write!(f, "use of %{} which is not in any block", id.index())
+            }
+            Self::UseOfForeignValue => write!(f, "use of value defined in another function"),
+            Self::UseNotDominated(id) => write!(f, "%{} doesn't dominate its use", id.index()),
+            Self::UserNotRegistered(id) => write!(f, "not registered as a user of %{}", id.index()),
+            Self::StaleUser(id) => {
+                write!(f, "%{} is listed as a user but doesn't use it", id.index())
+            }
+            Self::CalleeNotFunction => write!(f, "callee is not a function"),
+            Self::CallArgCountMismatch { expected, found } => {
+                write!(f, "call expects {} arguments but {} given", expected, found)
+            }
         }
     }
 }
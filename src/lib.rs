@@ -5,7 +5,7 @@
 #![feature(stmt_expr_attributes)]
 #![feature(drain_filter)]
 #![feature(vec_remove_item)]
-#![recursion_limit = "256"]
+#![recursion_limit = "512"]
 
 #[macro_use]
 pub mod macros;
@@ -19,11 +19,7 @@ pub mod util;
 pub use ir::*;
 
 extern crate defs;
-#[cfg(feature = "x86_64")]
-#[macro_use]
-extern crate dynasm;
 extern crate dyn_clone;
-extern crate dynasmrt;
 extern crate mmap;
 extern crate num;
 #[macro_use]
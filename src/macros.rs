@@ -27,15 +27,24 @@ macro_rules! debug {
 
 #[macro_export]
 macro_rules! sericum_parse_ty {
+    ($_:expr, i1) => {
+        types::Type::i1
+    };
     ($_:expr, i8) => {
         types::Type::i8
     };
+    ($_:expr, i16) => {
+        types::Type::i16
+    };
     ($_:expr, i32) => {
         types::Type::i32
     };
     ($_:expr, i64) => {
         types::Type::i64
     };
+    ($_:expr, f32) => {
+        types::Type::f32
+    };
     ($_:expr, f64) => {
         types::Type::f64
     };
@@ -67,12 +76,18 @@ macro_rules! sericum_value {
     ($builder:expr; i8 $n:expr) => {{
         value::Value::Immediate(value::ImmediateValue::Int8($n))
     }};
+    ($builder:expr; i16 $n:expr) => {{
+        value::Value::Immediate(value::ImmediateValue::Int16($n))
+    }};
     ($builder:expr; i32 $n:expr) => {{
         value::Value::Immediate(value::ImmediateValue::Int32($n))
     }};
     ($builder:expr; i64 $n:expr) => {{
         value::Value::Immediate(value::ImmediateValue::Int64($n))
     }};
+    ($builder:expr; f32 $n:expr) => {{
+        value::Value::Immediate(value::ImmediateValue::F32($n))
+    }};
     ($builder:expr; f64 $n:expr) => {{
         value::Value::Immediate(value::ImmediateValue::F64($n))
     }};
@@ -92,6 +107,18 @@ macro_rules! icmp_kind {
     (lt) => {
         opcode::ICmpKind::Lt
     };
+    (ule) => {
+        opcode::ICmpKind::ULe
+    };
+    (ult) => {
+        opcode::ICmpKind::ULt
+    };
+    (uge) => {
+        opcode::ICmpKind::UGe
+    };
+    (ugt) => {
+        opcode::ICmpKind::UGt
+    };
 }
 
 #[macro_export]
@@ -167,18 +194,139 @@ macro_rules! sericum_expr {
         let $x = $builder.build_rem(val1, val2);
         $crate::sericum_expr!($builder; $bb_map; $( $remain )*);
     };
+    ($builder:expr; $bb_map:expr; $x:ident = udiv ($($val1:tt)*), ($($val2:tt)*); $($remain:tt)*) => {
+        let val1 = $crate::sericum_value!($builder; $( $val1 )*);
+        let val2 = $crate::sericum_value!($builder; $( $val2 )*);
+        let $x = $builder.build_udiv(val1, val2);
+        $crate::sericum_expr!($builder; $bb_map; $( $remain )*);
+    };
+    ($builder:expr; $bb_map:expr; $x:ident = urem ($($val1:tt)*), ($($val2:tt)*); $($remain:tt)*) => {
+        let val1 = $crate::sericum_value!($builder; $( $val1 )*);
+        let val2 = $crate::sericum_value!($builder; $( $val2 )*);
+        let $x = $builder.build_urem(val1, val2);
+        $crate::sericum_expr!($builder; $bb_map; $( $remain )*);
+    };
+    ($builder:expr; $bb_map:expr; $x:ident = shl ($($val1:tt)*), ($($val2:tt)*); $($remain:tt)*) => {
+        let val1 = $crate::sericum_value!($builder; $( $val1 )*);
+        let val2 = $crate::sericum_value!($builder; $( $val2 )*);
+        let $x = $builder.build_shl(val1, val2);
+        $crate::sericum_expr!($builder; $bb_map; $( $remain )*);
+    };
+    ($builder:expr; $bb_map:expr; $x:ident = lshr ($($val1:tt)*), ($($val2:tt)*); $($remain:tt)*) => {
+        let val1 = $crate::sericum_value!($builder; $( $val1 )*);
+        let val2 = $crate::sericum_value!($builder; $( $val2 )*);
+        let $x = $builder.build_lshr(val1, val2);
+        $crate::sericum_expr!($builder; $bb_map; $( $remain )*);
+    };
+    ($builder:expr; $bb_map:expr; $x:ident = ashr ($($val1:tt)*), ($($val2:tt)*); $($remain:tt)*) => {
+        let val1 = $crate::sericum_value!($builder; $( $val1 )*);
+        let val2 = $crate::sericum_value!($builder; $( $val2 )*);
+        let $x = $builder.build_ashr(val1, val2);
+        $crate::sericum_expr!($builder; $bb_map; $( $remain )*);
+    };
+    ($builder:expr; $bb_map:expr; $x:ident = and ($($val1:tt)*), ($($val2:tt)*); $($remain:tt)*) => {
+        let val1 = $crate::sericum_value!($builder; $( $val1 )*);
+        let val2 = $crate::sericum_value!($builder; $( $val2 )*);
+        let $x = $builder.build_and(val1, val2);
+        $crate::sericum_expr!($builder; $bb_map; $( $remain )*);
+    };
+    ($builder:expr; $bb_map:expr; $x:ident = or ($($val1:tt)*), ($($val2:tt)*); $($remain:tt)*) => {
+        let val1 = $crate::sericum_value!($builder; $( $val1 )*);
+        let val2 = $crate::sericum_value!($builder; $( $val2 )*);
+        let $x = $builder.build_or(val1, val2);
+        $crate::sericum_expr!($builder; $bb_map; $( $remain )*);
+    };
+    ($builder:expr; $bb_map:expr; $x:ident = xor ($($val1:tt)*), ($($val2:tt)*); $($remain:tt)*) => {
+        let val1 = $crate::sericum_value!($builder; $( $val1 )*);
+        let val2 = $crate::sericum_value!($builder; $( $val2 )*);
+        let $x = $builder.build_xor(val1, val2);
+        $crate::sericum_expr!($builder; $bb_map; $( $remain )*);
+    };
     ($builder:expr; $bb_map:expr; $x:ident = sext [$($ty:tt)*] ($($val:tt)*); $($remain:tt)*) => {
         let val = $crate::sericum_value!($builder; $( $val )*);
-        let ty = $crate::sericum_parse_ty!($builder.func.module.types, $($ty)*);
+        let ty = $crate::sericum_parse_ty!($builder.func_ref_mut().types, $($ty)*);
         let $x = $builder.build_sext(val, ty);
         $crate::sericum_expr!($builder; $bb_map; $( $remain )*);
     };
+    ($builder:expr; $bb_map:expr; $x:ident = zext [$($ty:tt)*] ($($val:tt)*); $($remain:tt)*) => {
+        let val = $crate::sericum_value!($builder; $( $val )*);
+        let ty = $crate::sericum_parse_ty!($builder.func_ref_mut().types, $($ty)*);
+        let $x = $builder.build_zext(val, ty);
+        $crate::sericum_expr!($builder; $bb_map; $( $remain )*);
+    };
+    ($builder:expr; $bb_map:expr; $x:ident = trunc [$($ty:tt)*] ($($val:tt)*); $($remain:tt)*) => {
+        let val = $crate::sericum_value!($builder; $( $val )*);
+        let ty = $crate::sericum_parse_ty!($builder.func_ref_mut().types, $($ty)*);
+        let $x = $builder.build_trunc(val, ty);
+        $crate::sericum_expr!($builder; $bb_map; $( $remain )*);
+    };
+    ($builder:expr; $bb_map:expr; $x:ident = sitofp [$($ty:tt)*] ($($val:tt)*); $($remain:tt)*) => {
+        let val = $crate::sericum_value!($builder; $( $val )*);
+        let ty = $crate::sericum_parse_ty!($builder.func_ref_mut().types, $($ty)*);
+        let $x = $builder.build_sitofp(val, ty);
+        $crate::sericum_expr!($builder; $bb_map; $( $remain )*);
+    };
+    ($builder:expr; $bb_map:expr; $x:ident = fptosi [$($ty:tt)*] ($($val:tt)*); $($remain:tt)*) => {
+        let val = $crate::sericum_value!($builder; $( $val )*);
+        let ty = $crate::sericum_parse_ty!($builder.func_ref_mut().types, $($ty)*);
+        let $x = $builder.build_fptosi(val, ty);
+        $crate::sericum_expr!($builder; $bb_map; $( $remain )*);
+    };
+    ($builder:expr; $bb_map:expr; $x:ident = uitofp [$($ty:tt)*] ($($val:tt)*); $($remain:tt)*) => {
+        let val = $crate::sericum_value!($builder; $( $val )*);
+        let ty = $crate::sericum_parse_ty!($builder.func_ref_mut().types, $($ty)*);
+        let $x = $builder.build_uitofp(val, ty);
+        $crate::sericum_expr!($builder; $bb_map; $( $remain )*);
+    };
+    ($builder:expr; $bb_map:expr; $x:ident = fptoui [$($ty:tt)*] ($($val:tt)*); $($remain:tt)*) => {
+        let val = $crate::sericum_value!($builder; $( $val )*);
+        let ty = $crate::sericum_parse_ty!($builder.func_ref_mut().types, $($ty)*);
+        let $x = $builder.build_fptoui(val, ty);
+        $crate::sericum_expr!($builder; $bb_map; $( $remain )*);
+    };
+    ($builder:expr; $bb_map:expr; $x:ident = ptrtoint [$($ty:tt)*] ($($val:tt)*); $($remain:tt)*) => {
+        let val = $crate::sericum_value!($builder; $( $val )*);
+        let ty = $crate::sericum_parse_ty!($builder.func_ref_mut().types, $($ty)*);
+        let $x = $builder.build_ptrtoint(val, ty);
+        $crate::sericum_expr!($builder; $bb_map; $( $remain )*);
+    };
+    ($builder:expr; $bb_map:expr; $x:ident = inttoptr [$($ty:tt)*] ($($val:tt)*); $($remain:tt)*) => {
+        let val = $crate::sericum_value!($builder; $( $val )*);
+        let ty = $crate::sericum_parse_ty!($builder.func_ref_mut().types, $($ty)*);
+        let $x = $builder.build_inttoptr(val, ty);
+        $crate::sericum_expr!($builder; $bb_map; $( $remain )*);
+    };
+    ($builder:expr; $bb_map:expr; $x:ident = fpext [$($ty:tt)*] ($($val:tt)*); $($remain:tt)*) => {
+        let val = $crate::sericum_value!($builder; $( $val )*);
+        let ty = $crate::sericum_parse_ty!($builder.func_ref_mut().types, $($ty)*);
+        let $x = $builder.build_fpext(val, ty);
+        $crate::sericum_expr!($builder; $bb_map; $( $remain )*);
+    };
+    ($builder:expr; $bb_map:expr; $x:ident = fptrunc [$($ty:tt)*] ($($val:tt)*); $($remain:tt)*) => {
+        let val = $crate::sericum_value!($builder; $( $val )*);
+        let ty = $crate::sericum_parse_ty!($builder.func_ref_mut().types, $($ty)*);
+        let $x = $builder.build_fptrunc(val, ty);
+        $crate::sericum_expr!($builder; $bb_map; $( $remain )*);
+    };
+    ($builder:expr; $bb_map:expr; $x:ident = bitcast [$($ty:tt)*] ($($val:tt)*); $($remain:tt)*) => {
+        let val = $crate::sericum_value!($builder; $( $val )*);
+        let ty = $crate::sericum_parse_ty!($builder.func_ref_mut().types, $($ty)*);
+        let $x = $builder.build_bitcast(val, ty);
+        $crate::sericum_expr!($builder; $bb_map; $( $remain )*);
+    };
     ($builder:expr; $bb_map:expr; $x:ident = gep ($($val:tt)*), [$( ( $($idx:tt)* ) ),*] ; $($remain:tt)*) => {
         let val = $crate::sericum_value!($builder; $( $val )*);
         let indices = vec![$( $crate::sericum_value!($builder; $( $idx )*) ),*];
         let $x = $builder.build_gep(val, indices);
         $crate::sericum_expr!($builder; $bb_map; $( $remain )*);
     };
+    ($builder:expr; $bb_map:expr; $x:ident = select ($($cond:tt)*), ($($val1:tt)*), ($($val2:tt)*); $($remain:tt)*) => {
+        let cond = $crate::sericum_value!($builder; $( $cond )*);
+        let val1 = $crate::sericum_value!($builder; $( $val1 )*);
+        let val2 = $crate::sericum_value!($builder; $( $val2 )*);
+        let $x = $builder.build_select(cond, val1, val2);
+        $crate::sericum_expr!($builder; $bb_map; $( $remain )*);
+    };
     ($builder:expr; $bb_map:expr; $x:ident = phi [$( [ ($($arg:tt)*), $bb:ident ] ),*] ; $($remain:tt)*) => {
         let args = vec![$(
             ($crate::sericum_value!($builder; $( $arg )*),
@@ -218,6 +366,16 @@ macro_rules! sericum_expr {
         $builder.build_cond_br(cond, bb1, bb2);
         $crate::sericum_expr!($builder; $bb_map; $( $remain )*);
     };
+    ($builder:expr; $bb_map:expr; switch ($($cond:tt)*) $default:ident, [$( [ ($($case:tt)*), $bb:ident ] ),*] ; $($remain:tt)*) => {
+        let default = *$bb_map.entry(stringify!($default)).or_insert_with(|| $builder.append_basic_block());
+        let cases = vec![$(
+            ($crate::sericum_value!($builder; $( $case )*),
+            *$bb_map.entry(stringify!($bb)).or_insert_with(|| $builder.append_basic_block()))
+        ),*];
+        let cond = $crate::sericum_value!($builder; $( $cond )*);
+        $builder.build_switch(cond, default, cases);
+        $crate::sericum_expr!($builder; $bb_map; $( $remain )*);
+    };
     ($builder:expr; $bb_map:expr; br $label:ident; $($remain:tt)*) => {
         let bb = *$bb_map.entry(stringify!($label)).or_insert_with(|| $builder.append_basic_block());
         $builder.build_br(bb);
@@ -0,0 +1,72 @@
+use sericum::{
+    ir::{
+        alias_analysis::{AliasAnalysis, AliasInfo, AliasResult},
+        prelude::*,
+        value::{ArgumentValue, GlobalValue, InstructionValue},
+    },
+    sericum_ir,
+};
+
+#[test]
+fn alias() {
+    let mut m = Module::new("sericum");
+    let arr_ty = m.types.new_array_ty(types::Type::i32, 4);
+    let g = Value::Global(GlobalValue {
+        id: m
+            .global_vars
+            .new_global_var_with_name(arr_ty, global_val::Linkage::Common, "g"),
+    });
+    sericum_ir!(m; define [i32] f [(ptr i32), (i32)] {
+    entry:
+        a = alloca_ ([4; i32]);
+        b = alloca i32;
+        c = alloca i32;
+        pc = alloca_ (ptr i32);
+        a0 = gep (%a), [(i32 0), (i32 0)];
+        a1 = gep (%a), [(i32 0), (i32 1)];
+        a1_ = gep (%a), [(i32 0), (i32 1)];
+        ai = gep (%a), [(i32 0), (%arg.1)];
+        g1 = gep (%g), [(i32 0), (i32 1)];
+        store (%c), (%pc);
+        p = load (%pc);
+        ret (i32 0);
+    });
+
+    AliasAnalysis::new().run_on_module(&mut m);
+
+    let f = m.find_function("f").unwrap();
+    let func = m.function_ref(f);
+    let entry = func.basic_blocks.order[0];
+    let v = func.basic_blocks.arena[entry]
+        .iseq_ref()
+        .iter()
+        .map(|&id| Value::Instruction(InstructionValue { func_id: f, id }))
+        .collect::<Vec<_>>();
+    let (a, b, c, a0, a1, a1_, ai, g1, p) = (v[0], v[1], v[2], v[4], v[5], v[6], v[7], v[8], v[10]);
+    let arg = Value::Argument(ArgumentValue {
+        func_id: f,
+        index: 0,
+    });
+
+    let info = func.get_analysis::<AliasInfo>().unwrap();
+    let alias = |x, y| info.alias(func, x, y);
+    assert_eq!(alias(a, b), AliasResult::NoAlias);
+    assert_eq!(alias(a0, a), AliasResult::MustAlias);
+    assert_eq!(alias(a0, a1), AliasResult::NoAlias);
+    assert_eq!(alias(a1, a1_), AliasResult::MustAlias);
+    assert_eq!(alias(ai, a1), AliasResult::MayAlias);
+    assert_eq!(alias(g1, a1), AliasResult::NoAlias);
+    assert_eq!(alias(g1, g), AliasResult::MayAlias);
+    assert_eq!(alias(arg, b), AliasResult::NoAlias);
+    assert_eq!(alias(arg, g1), AliasResult::MayAlias);
+    // `c` escapes through the store, so the loaded pointer may point to it.
+    assert_eq!(alias(p, b), AliasResult::NoAlias);
+    assert_eq!(alias(p, c), AliasResult::MayAlias);
+
+    assert!(info.points_to_local(func, b));
+    assert!(!info.points_to_local(func, c));
+    assert!(info.is_dereferenceable(func, a1));
+    assert!(info.is_dereferenceable(func, g1));
+    assert!(!info.is_dereferenceable(func, ai));
+    assert!(!info.is_dereferenceable(func, arg));
+}
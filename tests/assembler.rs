@@ -6,7 +6,7 @@ mod x86_64 {
             exec::executor::{Executor, GenericValue},
             standard_conversion_into_machine_module,
         },
-        ir::{/*global_val,*/ types, value},
+        ir::{global_val, types, value},
         module::Module,
         sericum_ir,
         *, // for macro
@@ -51,12 +51,13 @@ mod x86_64 {
         println!("{}", obj_name);
 
         let output_name = unique_file_name("out");
-        let compilation = process::Command::new("clang")
+        let compilation = process::Command::new("cc")
             .args(&[
                 obj_name.as_str(),
                 parent_name.as_str(),
                 "-o",
                 output_name.as_str(),
+                "-no-pie",
             ])
             .status()
             .unwrap();
@@ -94,6 +95,32 @@ mod x86_64 {
         );
     }
 
+    #[test]
+    fn asmer_jit_bitwise() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [i32] test [(i32), (i32)] {
+            entry:
+                a = and (%arg.0), (i32 4080);
+                a = or (%a), (i32 1);
+                a = xor (%a), (%arg.1);
+                n = and (%arg.1), (i32 3);
+                a = lshr (%a), (%n);
+                a = shl (%a), (i32 2);
+                a = ashr (%a), (i32 1);
+                ret (%a);
+        });
+
+        let machine_module = standard_conversion_into_machine_module(m);
+
+        let mut exec = Executor::new(machine_module);
+        exec.compile();
+        let f = exec.find_function_by_name("test").unwrap();
+        assert_eq!(
+            exec.execute(f, vec![GenericValue::i32(0x1234), GenericValue::i32(-3)]),
+            GenericValue::i32((((((0x1234 & 4080) | 1) ^ -3) as u32 >> 1) << 2) as i32 >> 1)
+        );
+    }
+
     #[test]
     fn asmer_jit_local_var() {
         let mut m = Module::new("sericum");
@@ -226,4 +253,513 @@ mod x86_64 {
             m,
         );
     }
+
+    #[test]
+    fn asmer_i8_i64() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [i8] add8 [(i8), (i8)] {
+            entry: x = add (%arg.0), (%arg.1);
+                   x = sub (%x), (i8 3);
+                   ret (%x); });
+        sericum_ir!(m; define [i64] mul64 [(i64), (i64), (i64), (i64), (i64), (i64)] {
+            entry: x = mul (%arg.0), (%arg.1);
+                   y = add (%arg.4), (%arg.5);
+                   x = add (%x), (%y);
+                   z = sub (%arg.2), (%arg.3);
+                   x = mul (%x), (%z);
+                   x = add (%x), (i64 4294967296);
+                   ret (%x); });
+        compile(
+            "#include <assert.h>
+             extern signed char add8(signed char, signed char);
+             extern long mul64(long, long, long, long, long, long);
+             int main() {
+                 assert(add8(10, 20) == 27);
+                 assert(add8(-128, 1) == 126);
+                 assert(mul64(1l << 33, 3, 7, 2, 1, 2) == ((3l << 33) + 3) * 5 + 4294967296);
+                 return 0;
+             }",
+            m,
+        );
+    }
+
+    #[test]
+    fn asmer_float() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [f64] average [(i32), (i32)] {
+            entry: a = sitofp [f64] (%arg.0);
+                   b = sitofp [f64] (%arg.1);
+                   x = add (%a), (%b);
+                   x = div (%x), (f64 2.0);
+                   ret (%x); });
+        sericum_ir!(m; define [i32] scale [(f32)] {
+            entry: x = mul (%arg.0), (f32 2.5);
+                   c = fcmp ult (%x), (f32 10.0);
+                   br (%c) small, large;
+            small: i = fptosi [i32] (%x);
+                   ret (%i);
+            large: ret (i32 -1); });
+        compile(
+            "#include <assert.h>
+             extern double average(int, int);
+             extern int scale(float);
+             int main() {
+                 assert(average(3, 4) == 3.5);
+                 assert(scale(2.0f) == 5);
+                 assert(scale(4.0f) == -1);
+                 return 0;
+             }",
+            m,
+        );
+    }
+
+    #[test]
+    fn asmer_global_var() {
+        let mut m = Module::new("sericum");
+        let ty = m.types.new_array_ty(types::Type::i32, 8);
+        let arr = m
+            .global_vars
+            .new_global_var_with_name(ty, global_val::Linkage::Common, "arr");
+        let arr = value::Value::Global(value::GlobalValue { id: arr });
+        let cnt = m.global_vars.new_global_var_with_name(
+            types::Type::i32,
+            global_val::Linkage::Common,
+            "cnt",
+        );
+        let cnt = value::Value::Global(value::GlobalValue { id: cnt });
+
+        sericum_ir!(m; define [i32] test [(i32)] {
+            entry:
+                p = gep (%arr), [(i32 0), (%arg.0)];
+                store (i32 123), (%p);
+                pc = gep (%cnt), [(i32 0)];
+                c = load (%pc);
+                c = add (%c), (i32 1);
+                store (%c), (%pc);
+                q = gep (%arr), [(i32 0), (i32 7)];
+                x = load (%q);
+                ret (%x);
+        });
+
+        compile(
+            "#include <assert.h>
+             extern int arr[8], cnt;
+             extern int test(int);
+             int main() {
+                 arr[7] = 7;
+                 assert(test(2) == 7);
+                 assert(arr[2] == 123);
+                 assert(test(7) == 123);
+                 assert(cnt == 2);
+                 return 0;
+             }",
+            m,
+        );
+    }
+
+    #[test]
+    fn asmer_string_and_external_call() {
+        let mut m = Module::new("sericum");
+        let s = m.create_string("hello".to_string());
+        let ptr_i8 = m.types.new_pointer_ty(types::Type::i8);
+        m.create_function("strlen", types::Type::i32, vec![ptr_i8]);
+
+        sericum_ir!(m; define [i32] test [] {
+            entry:
+                a = gep (%s), [(i32 0), (i32 0)];
+                l = call strlen [(%a)];
+                ret (%l);
+        });
+
+        compile(
+            "#include <assert.h>
+             extern int test();
+             int main() { assert(test() == 5); return 0; }",
+            m,
+        );
+    }
+
+    #[test]
+    fn asmer_switch() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [i32] test [(i32)] {
+            entry:
+                switch (%arg.0) other, [
+                    [(i32 0), l0], [(i32 1), l1], [(i32 2), l2], [(i32 3), l0], [(i32 5), l1]
+                ];
+            l0:
+                ret (i32 10);
+            l1:
+                ret (i32 11);
+            l2:
+                ret (i32 12);
+            other:
+                ret (i32 -1);
+        });
+
+        compile(
+            "#include <assert.h>
+             extern int test(int);
+             int main() {
+                 assert(test(-1) == -1);
+                 assert(test(0) == 10);
+                 assert(test(1) == 11);
+                 assert(test(2) == 12);
+                 assert(test(3) == 10);
+                 assert(test(4) == -1);
+                 assert(test(5) == 11);
+                 assert(test(6) == -1);
+                 return 0;
+             }",
+            m,
+        );
+    }
+
+    #[test]
+    fn asmer_inst_description() {
+        use sericum::codegen::x64::{
+            common::{dag::node::IROpcode, machine::inst_def::TargetImmediate, types::MVType},
+            machine::{
+                inst::MachineOpcode,
+                inst_def::{Encoding, SelectOperand},
+                register::RegisterClassKind,
+            },
+        };
+
+        let ops = [
+            SelectOperand::Reg(RegisterClassKind::GR64),
+            SelectOperand::Imm(TargetImmediate::I32),
+        ];
+        let opcode = MachineOpcode::select(IROpcode::Add, MVType::i64, &ops).unwrap();
+        assert_eq!(opcode, MachineOpcode::ADDr64i32);
+        assert_eq!(opcode.inst_def().unwrap().name, "add");
+        assert!(matches!(opcode.encoding(), Some(Encoding::Ri(0x81, 0, 4))));
+
+        let ops = [
+            SelectOperand::Reg(RegisterClassKind::XMM),
+            SelectOperand::Reg(RegisterClassKind::XMM),
+        ];
+        assert_eq!(
+            MachineOpcode::select(IROpcode::Div, MVType::f64, &ops),
+            Some(MachineOpcode::DIVSDrr)
+        );
+        assert_eq!(
+            MachineOpcode::select(IROpcode::Div, MVType::f32, &ops),
+            None
+        );
+
+        let load = MachineOpcode::select(IROpcode::Load, MVType::i16, &[SelectOperand::Mem]);
+        assert_eq!(load, Some(MachineOpcode::MOVrm16));
+        assert_eq!(load.unwrap().mem_word(), Some("word"));
+        let ops = [
+            SelectOperand::Mem,
+            SelectOperand::Reg(RegisterClassKind::XMM),
+        ];
+        let store = MachineOpcode::select(IROpcode::Store, MVType::Void, &ops);
+        assert_eq!(store, Some(MachineOpcode::MOVSDmr));
+        assert_eq!(store.unwrap().mem_word(), Some("qword"));
+        assert_eq!(
+            MachineOpcode::select(
+                IROpcode::Sext,
+                MVType::i64,
+                &[SelectOperand::Reg(RegisterClassKind::GR32)]
+            ),
+            Some(MachineOpcode::MOVSXDr64r32)
+        );
+        assert_eq!(MachineOpcode::ADDSDrm.mem_word(), Some("qword"));
+        assert_eq!(MachineOpcode::ADDSDrr.mem_word(), None);
+        assert!(MachineOpcode::Phi.inst_def().is_none());
+    }
+}
+
+#[cfg(feature = "aarch64")]
+mod aarch64 {
+    use sericum::{
+        codegen::aarch64::{asm::assembler::Assembler, standard_conversion_into_machine_module},
+        ir::{types, value},
+        module::Module,
+        sericum_ir,
+        *, // for macro
+    };
+    use std::{fs, process};
+    use {rand, rand::Rng};
+
+    fn unique_file_name(extension: &str) -> String {
+        const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ\
+                            abcdefghijklmnopqrstuvwxyz\
+                            0123456789";
+        const LEN: usize = 16;
+        let mut rng = rand::thread_rng();
+        let name: String = (0..LEN)
+            .map(|_| {
+                let idx = rng.gen_range(0, CHARSET.len());
+                CHARSET[idx] as char
+            })
+            .collect();
+        format!("/tmp/{}.{}", name, extension)
+    }
+
+    // Assembles `module` into an aarch64 object and compares its disassembly with
+    // `tests/aarch64_obj/<expected>.txt`, which was produced by `llvm-objdump -d -r`.
+    fn compile_and_compare(expected: &str, module: Module) {
+        let machine_module = standard_conversion_into_machine_module(module);
+        println!("{:?}", machine_module);
+
+        let mut asmer = Assembler::new(&machine_module);
+        asmer.assemble();
+
+        let obj_name = unique_file_name("o");
+        asmer.write_to_file(&obj_name);
+
+        let objdump = process::Command::new("llvm-objdump")
+            .args(&["-d", "-r", "--no-leading-addr", obj_name.as_str()])
+            .output()
+            .unwrap();
+        assert!(objdump.status.success());
+        fs::remove_file(obj_name).unwrap();
+
+        let output = String::from_utf8(objdump.stdout).unwrap();
+        let disasm = output
+            .lines()
+            .filter(|l| {
+                !l.is_empty() && !l.contains("file format") && !l.starts_with("Disassembly")
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        println!("{}", disasm);
+
+        let expected = fs::read_to_string(format!(
+            "{}/tests/aarch64_obj/{}.txt",
+            env!("CARGO_MANIFEST_DIR"),
+            expected
+        ))
+        .unwrap();
+        assert_eq!(disasm, expected.trim_end());
+    }
+
+    #[test]
+    fn asmer_minimum() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [i32] test [] {
+            entry:
+                ret (i32 42);
+        });
+        compile_and_compare("minimum", m);
+    }
+
+    #[test]
+    fn asmer_arith() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [i32] test_mul [(i32)] {
+            entry:
+                i = alloca i32;
+                store (i32 21), (%i);
+                ii = load (%i);
+                x = mul (%ii), (i32 2);
+                x = mul (%x), (%arg.0);
+                ret (%x);
+        });
+        sericum_ir!(m; define [i32] test_div [(i32)] {
+            entry:
+                i = alloca i32;
+                store (i32 85), (%i);
+                ii = load (%i);
+                x = div (%ii), (i32 2);
+                x = div (%x), (%arg.0);
+                x = rem (%x), (i32 5);
+                ret (%x);
+        });
+        sericum_ir!(m; define [i32] test_large_imm [(i32)] {
+            entry:
+                x = add (%arg.0), (i32 100000);
+                x = sub (%x), (i32 -7);
+                ret (%x);
+        });
+        compile_and_compare("arith", m);
+    }
+
+    #[test]
+    fn asmer_bitwise() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [i32] bits [(i32), (i32)] {
+            entry:
+                n = and (%arg.1), (i32 31);
+                a = shl (%arg.0), (%n);
+                b = lshr (%arg.0), (i32 3);
+                c = ashr (%arg.0), (%n);
+                d = shl (%arg.0), (i32 5);
+                x = or (%a), (%b);
+                x = xor (%x), (%c);
+                x = add (%x), (%d);
+                x = and (%x), (i32 4095);
+                ret (%x);
+        });
+        compile_and_compare("bitwise", m);
+    }
+
+    #[test]
+    fn asmer_array() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [i32] test [] {
+            entry:
+                x = alloca_ ([8; i32]);
+                y = alloca i32;
+                store (i32 2), (%y);
+                yy = load (%y);
+                a = gep (%x), [(i32 0), (%yy)];
+                store (i32 42), (%a);
+                a = load (%a);
+                ret (%a);
+        });
+        compile_and_compare("array", m);
+    }
+
+    #[test]
+    fn asmer_fibo() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [i32] fibo [(i32)] {
+            entry:
+                cond = icmp le (%arg.0), (i32 2);
+                br (%cond) l1, l2;
+            l1:
+                ret (i32 1);
+            l2:
+                a1 = sub (%arg.0), (i32 1);
+                r1 = call fibo [(%a1)];
+                a2 = sub (%arg.0), (i32 2);
+                r2 = call fibo [(%a2)];
+                r3 = add (%r1), (%r2);
+                ret (%r3);
+        });
+        compile_and_compare("fibo", m);
+    }
+
+    #[test]
+    fn asmer_external_call() {
+        let mut m = Module::new("sericum");
+        m.create_function("ext", types::Type::i32, vec![types::Type::i32]);
+        sericum_ir!(m; define [i32] test [(i32)] {
+            entry:
+                x = call ext [(%arg.0)];
+                c = icmp lt (%x), (i32 0);
+                br (%c) neg, pos;
+            neg:
+                ret (i32 0);
+            pos:
+                ret (%x);
+        });
+        compile_and_compare("external_call", m);
+    }
+
+    #[test]
+    fn asmer_float() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [f32] test [(f32), (f64)] {
+            entry:
+                a = alloca f32;
+                store (%arg.0), (%a);
+                la = load (%a);
+                b = mul (%la), (f32 3.0);
+                c = sub (%b), (f32 0.25);
+                d = fpext [f64] (%c);
+                e = add (%d), (%arg.1);
+                e = div (%e), (f64 2.0);
+                f = fptrunc [f32] (%e);
+                ret (%f);
+        });
+        sericum_ir!(m; define [f64] call_test [] {
+            entry:
+                x = call test [(f32 1.5), (f64 0.5)];
+                y = fpext [f64] (%x);
+                ret (%y);
+        });
+        compile_and_compare("float", m);
+    }
+
+    #[test]
+    fn asmer_i16() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [i16] test [(i16), (i16)] {
+            entry:
+                a = alloca i16;
+                store (%arg.0), (%a);
+                la = load (%a);
+                x = add (%la), (%arg.1);
+                x = mul (%x), (i16 3);
+                y = udiv (%x), (%arg.1);
+                y = rem (%y), (i16 7);
+                y = lshr (%y), (i16 2);
+                ret (%y);
+        });
+        sericum_ir!(m; define [i64] ext [(i16), (i32)] {
+            entry:
+                x = sext [i64] (%arg.0);
+                y = zext [i64] (%arg.1);
+                z = trunc [i16] (%arg.1);
+                z = zext [i32] (%z);
+                w = sext [i64] (%z);
+                x = add (%x), (%y);
+                x = add (%x), (%w);
+                ret (%x);
+        });
+        compile_and_compare("i16", m);
+    }
+
+    #[test]
+    fn asmer_i64() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [i64] test [(i64), (i64)] {
+            entry:
+                a = alloca i64;
+                store (i64 4294967296), (%a);
+                la = load (%a);
+                x = sub (%arg.0), (%la);
+                x = sub (%x), (i64 5);
+                x = sub (i64 100), (%x);
+                x = mul (%x), (%arg.1);
+                x = urem (%x), (i64 10);
+                x = shl (%x), (i64 40);
+                x = xor (%x), (i64 -1);
+                c = icmp lt (%x), (i64 100000);
+                br (%c) l1, l2;
+            l1:
+                ret (%x);
+            l2:
+                ret (i64 0);
+        });
+        compile_and_compare("i64", m);
+    }
+
+    #[test]
+    fn asmer_cvt() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [f64] to_fp [(i32), (i64), (i16), (i32)] {
+            entry:
+                a = sitofp [f64] (%arg.0);
+                b = uitofp [f64] (%arg.1);
+                c = sitofp [f32] (%arg.2);
+                d = uitofp [f32] (%arg.3);
+                e = uitofp [f32] (%arg.2);
+                c = add (%c), (%d);
+                c = add (%c), (%e);
+                c = fpext [f64] (%c);
+                a = add (%a), (%b);
+                a = add (%a), (%c);
+                ret (%a);
+        });
+        sericum_ir!(m; define [i64] to_int [(f64), (f32)] {
+            entry:
+                a = fptosi [i32] (%arg.0);
+                b = fptoui [i64] (%arg.0);
+                c = fptosi [i16] (%arg.1);
+                d = fptoui [i32] (%arg.1);
+                a = add (%a), (%d);
+                c = sext [i32] (%c);
+                a = add (%a), (%c);
+                a = sext [i64] (%a);
+                b = add (%a), (%b);
+                ret (%b);
+        });
+        compile_and_compare("cvt", m);
+    }
 }
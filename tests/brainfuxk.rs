@@ -303,12 +303,7 @@ mod x86_64 {
 
 #[cfg(feature = "riscv64")]
 mod riscv64 {
-    use sericum::{
-        // codegen::riscv64::exec,
-        // exec::{interpreter::interp, jit::riscv64::compiler},
-        ir::{builder, opcode, types, value},
-        *,
-    };
+    use sericum::{ir, ir::prelude::*, sericum_ir};
 
     #[test]
     // #[ignore]
@@ -463,7 +458,7 @@ mod riscv64 {
         +[-[->>>>>>>>>+<<<<<<<<<]>>>>>>>>>]>>>>>->>>>>>>>>>>>>>>>>>>>>>>>>>>-<<<<<<[<<<<
         <<<<<]]>>>]";
 
-        let mut m = module::Module::new("brainfuxk");
+        let mut m = Module::new("brainfuxk");
 
         // Internal function must be defined before you use it
         let sericum_printch_i32 = m.create_function(
@@ -481,15 +476,15 @@ mod riscv64 {
 
         let f_id = m.create_function("main", types::Type::Void, vec![]);
 
-        let mut builder = builder::Builder::new(builder::FunctionIdWithModule::new(&mut m, f_id));
+        let mut builder = m.ir_builder(f_id);
         let entry = builder.append_basic_block();
         builder.set_insert_point(entry);
 
         // tape and index
         let tape_len = 2048;
         let ary_ty = builder
-            .func
-            .module
+            .module()
+            .unwrap()
             .types
             .new_array_ty(types::Type::i32, tape_len);
         let tape = builder.build_alloca(ary_ty);
@@ -595,7 +590,7 @@ mod riscv64 {
 
         use sericum::codegen::riscv64::asm::print::MachineAsmPrinter;
         use sericum::codegen::riscv64::standard_conversion_into_machine_module;
-        let machine_module = standard_conversion_into_machine_module(&mut m);
+        let machine_module = standard_conversion_into_machine_module(m);
         let mut printer = MachineAsmPrinter::new();
         // println!("{:?}", machine_module);
         printer.run_on_module(&machine_module);
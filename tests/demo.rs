@@ -1,10 +1,9 @@
 use sericum::{
-    codegen::arch::{asm::print::MachineAsmPrinter, standard_conversion_into_machine_module},
+    codegen::target::TargetRegistry,
     ir::{builder::IRBuilder, module::Module, opcode::ICmpKind, types::Type},
 };
 
-#[test]
-fn demo() {
+fn fibo_module() -> Module {
     let mut module = Module::new("demo");
     let fibo = module.create_function("fibo", Type::i32, vec![Type::i32]);
     let mut builder = module.ir_builder(fibo);
@@ -29,11 +28,36 @@ fn demo() {
     let add = builder.build_add(ret0, ret1);
     builder.build_ret(add);
 
+    module
+}
+
+#[test]
+fn demo() {
+    let module = fibo_module();
     println!("IR:\n{:?}", module);
 
-    let machine_module = standard_conversion_into_machine_module(module);
-    let mut printer = MachineAsmPrinter::new();
-    printer.run_on_module(&machine_module);
+    let registry = TargetRegistry::new();
+    for triple in registry.triples() {
+        let target = registry.lookup(&triple).unwrap();
+        let asm = target.compile_to_asm(module.clone());
+        println!("Assembly ({}):\n{}", triple, asm);
+        assert!(asm.contains("fibo"));
+    }
+}
 
-    println!("Assembly:\n{}", printer.output);
+#[test]
+#[cfg(all(feature = "x86_64", feature = "aarch64"))]
+fn demo_multiple_targets() {
+    let registry = TargetRegistry::new();
+    let x64 = registry
+        .lookup_str("x86_64-unknown-linux-gnu")
+        .unwrap()
+        .compile_to_asm(fibo_module());
+    let aarch64 = registry
+        .lookup_str("aarch64-unknown-linux-gnu")
+        .unwrap()
+        .compile_to_asm(fibo_module());
+    assert!(x64.contains("call fibo"));
+    assert!(aarch64.contains("bl fibo"));
+    assert!(registry.lookup_str("mips-unknown-linux-gnu").is_none());
 }
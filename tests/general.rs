@@ -1,6 +1,28 @@
-#[cfg(feature = "x86_64")]
-mod x86_64 {
-    use sericum::{codegen::x64::exec, ir, ir::prelude::*, sericum_ir};
+#[cfg(any(
+    all(feature = "x86_64", target_arch = "x86_64"),
+    all(feature = "riscv64", target_arch = "riscv64")
+))]
+mod jit {
+    #[cfg(target_arch = "riscv64")]
+    use sericum::codegen::riscv64::exec;
+    #[cfg(target_arch = "x86_64")]
+    use sericum::codegen::x64::exec;
+    use sericum::{ir, ir::prelude::*, sericum_ir, FxHashMap};
+
+    /// Counts the instructions of `func` whose opcode is `op`.
+    fn count_opcode(func: &Function, op: ir::opcode::Opcode) -> usize {
+        func.basic_blocks
+            .order
+            .iter()
+            .map(|&b| {
+                func.basic_blocks.arena[b]
+                    .iseq_ref()
+                    .iter()
+                    .filter(|&&id| func.inst_table[id].opcode == op)
+                    .count()
+            })
+            .sum()
+    }
 
     #[test]
     fn test0_mem2reg() {
@@ -459,6 +481,7 @@ mod x86_64 {
     }
 
     #[test]
+    #[cfg(target_arch = "x86_64")]
     fn fibo() {
         use sericum::codegen::x64::standard_conversion_into_machine_module;
 
@@ -813,6 +836,30 @@ main:
         assert_eq!(res, exec::jit::GenericValue::Int32(2));
     }
 
+    #[test]
+    fn float_single() {
+        let mut m = Module::new("sericum");
+
+        let _ = sericum_ir!(m; define [f32] func [] {
+            entry:
+                a = alloca f32;
+                store (f32 1.5), (%a);
+                la = load (%a);
+                b = mul (%la), (f32 3.0);
+                c = sub (%b), (f32 0.25);
+                d = fpext [f64] (%c);
+                e = add (%d), (f64 0.5);
+                f = fptrunc [f32] (%e);
+                g = div (%f), (f32 2.0);
+                ret (%g);
+        });
+
+        let mut jit = exec::jit::JITExecutor::new(m);
+        let func = jit.find_function_by_name("func").unwrap();
+        let res = jit.run(func, vec![]);
+        assert_eq!(res, exec::jit::GenericValue::F32(2.375));
+    }
+
     #[test]
     fn pass_arr() {
         let mut m = Module::new("sericum");
@@ -943,6 +990,393 @@ main:
         );
     }
 
+    #[test]
+    fn inline() {
+        let mut m = Module::new("sericum");
+
+        sericum_ir!(m; define [i32] abs [(i32)] {
+        entry:
+            c = icmp lt (%arg.0), (i32 0);
+            br (%c) neg, pos;
+        neg:
+            n = sub (i32 0), (%arg.0);
+            ret (%n);
+        pos:
+            ret (%arg.0);
+        });
+
+        sericum_ir!(m; define [void] set [(ptr i32), (i32)] {
+        entry:
+            store (%arg.1), (%arg.0);
+            ret (void);
+        });
+
+        sericum_ir!(m; define [i32] fibo [(i32)] {
+        entry:
+            c = icmp le (%arg.0), (i32 2);
+            br (%c) l1, l2;
+        l1:
+            ret (i32 1);
+        l2:
+            a1 = sub (%arg.0), (i32 1);
+            r1 = call fibo [(%a1)];
+            a2 = sub (%arg.0), (i32 2);
+            r2 = call fibo [(%a2)];
+            r3 = add (%r1), (%r2);
+            ret (%r3);
+        });
+
+        sericum_ir!(m; define [i32] main [(i32)] {
+        entry:
+            s = alloca i32;
+            i = alloca i32;
+            acc = alloca i32;
+            store (i32 0), (%i);
+            store (i32 0), (%acc);
+            br header;
+        header:
+            li = load (%i);
+            c = icmp lt (%li), (%arg.0);
+            br (%c) body, exit;
+        body:
+            d = sub (%li), (i32 5);
+            x = call abs [(%d)];
+            la = load (%acc);
+            a = add (%la), (%x);
+            store (%a), (%acc);
+            i1 = add (%li), (i32 1);
+            store (%i1), (%i);
+            br header;
+        exit:
+            la = load (%acc);
+            __ = call set [(%s), (%la)];
+            l = load (%s);
+            f = call fibo [(i32 10)];
+            r = add (%l), (%f);
+            ret (%r);
+        });
+
+        ir::inline::Inliner::new().run_on_module(&mut m);
+        println!("{:?}", m);
+
+        let main = m.function_ref(m.find_function("main").unwrap());
+        let callees = main
+            .basic_blocks
+            .order
+            .iter()
+            .flat_map(|&b| main.basic_blocks.arena[b].iseq_ref().clone())
+            .filter(|&id| main.inst_table[id].opcode == ir::opcode::Opcode::Call)
+            .map(|id| main.inst_table[id].operand.args()[0])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            callees,
+            vec![ir::value::Value::Function(m.find_function("fibo").unwrap())]
+        );
+
+        let mut jit = exec::jit::JITExecutor::new(m);
+        let func = jit.find_function_by_name("main").unwrap();
+        assert_eq!(
+            jit.run(func, vec![exec::jit::GenericValue::Int32(10)]),
+            exec::jit::GenericValue::Int32(25 + 55)
+        );
+    }
+
+    #[test]
+    fn sccp() {
+        let mut m = Module::new("sericum");
+
+        sericum_ir!(m; define [i32] func [(i32)] {
+        entry:
+            i = alloca i32;
+            x = alloca i32;
+            store (i32 0), (%i);
+            store (i32 5), (%x);
+            br header;
+        header:
+            li = load (%i);
+            c = icmp lt (%li), (%arg.0);
+            br (%c) body, exit;
+        body:
+            lx = load (%x);
+            c2 = icmp eq (%lx), (i32 5);
+            br (%c2) then_, else_;
+        then_:
+            store (i32 5), (%x);
+            br latch;
+        else_:
+            store (%li), (%x);
+            br latch;
+        latch:
+            i1 = add (%li), (i32 1);
+            store (%i1), (%i);
+            br header;
+        exit:
+            lx = load (%x);
+            r = mul (%lx), (i32 2);
+            ret (%r);
+        });
+
+        ir::mem2reg::Mem2Reg::new().run_on_module(&mut m);
+        ir::sccp::SparseConditionalConstantPropagation::new().run_on_module(&mut m);
+        println!("{:?}", m);
+
+        let func = m.function_ref(m.find_function("func").unwrap());
+        assert_eq!(func.basic_blocks.order.len(), 6);
+        let ret = func
+            .basic_blocks
+            .order
+            .iter()
+            .flat_map(|&b| func.basic_blocks.arena[b].iseq_ref().clone())
+            .find(|&id| func.inst_table[id].opcode == ir::opcode::Opcode::Ret)
+            .unwrap();
+        assert_eq!(
+            func.inst_table[ret].operand.args()[0],
+            ir::value::Value::new_imm_int32(10)
+        );
+
+        let mut jit = exec::jit::JITExecutor::new(m);
+        let func = jit.find_function_by_name("func").unwrap();
+        for &n in &[0, 10] {
+            assert_eq!(
+                jit.run(func, vec![exec::jit::GenericValue::Int32(n)]),
+                exec::jit::GenericValue::Int32(10)
+            );
+        }
+    }
+
+    #[test]
+    fn sccp_wrapping() {
+        let mut m = Module::new("sericum");
+
+        sericum_ir!(m; define [i32] func [] {
+        entry:
+            a = add (i32 2147483647), (i32 1);
+            b = mul (%a), (i32 2);
+            c = sub (%a), (i32 1);
+            d = div (%a), (i32 -1);
+            r = add (%b), (%c);
+            ret (%r);
+        });
+
+        ir::sccp::SparseConditionalConstantPropagation::new().run_on_module(&mut m);
+        ir::const_folding::ConstantFolding::new().run_on_module(&mut m);
+        println!("{:?}", m);
+
+        let func = m.function_ref(m.find_function("func").unwrap());
+        let entry = func.basic_blocks.order[0];
+        let ret = *func.basic_blocks.arena[entry].iseq_ref().last().unwrap();
+        assert_eq!(
+            func.inst_table[ret].operand.args()[0],
+            ir::value::Value::new_imm_int32(2147483647)
+        );
+
+        let mut jit = exec::jit::JITExecutor::new(m);
+        let func = jit.find_function_by_name("func").unwrap();
+        assert_eq!(
+            jit.run(func, vec![]),
+            exec::jit::GenericValue::Int32(2147483647)
+        );
+    }
+
+    #[test]
+    fn gvn() {
+        let mut m = Module::new("sericum");
+
+        sericum_ir!(m; define [i32] func [(i32)] {
+        entry:
+            p = alloca i32;
+            q = alloca i32;
+            store (%arg.0), (%p);
+            a = load (%p);
+            store (i32 1), (%q);
+            b = load (%p);
+            x = add (%a), (i32 3);
+            y = add (i32 3), (%b);
+            c = icmp lt (%x), (i32 10);
+            br (%c) l, r;
+        l:
+            u = add (%y), (i32 1);
+            br merge;
+        r:
+            v = load (%p);
+            w = mul (%v), (i32 2);
+            br merge;
+        merge:
+            p1 = phi [ [(%u), l], [(%w), r] ];
+            p2 = phi [ [(%w), r], [(%u), l] ];
+            z = add (%p1), (%p2);
+            ret (%z);
+        });
+
+        ir::gvn::GlobalValueNumbering::new().run_on_module(&mut m);
+        println!("{:?}", m);
+
+        let func = m.function_ref(m.find_function("func").unwrap());
+        assert_eq!(count_opcode(func, ir::opcode::Opcode::Load), 1);
+        assert_eq!(count_opcode(func, ir::opcode::Opcode::Add), 3);
+        assert_eq!(count_opcode(func, ir::opcode::Opcode::Phi), 1);
+
+        let mut jit = exec::jit::JITExecutor::new(m);
+        let func = jit.find_function_by_name("func").unwrap();
+        for &(n, expected) in &[(2, 12), (20, 80)] {
+            assert_eq!(
+                jit.run(func, vec![exec::jit::GenericValue::Int32(n)]),
+                exec::jit::GenericValue::Int32(expected)
+            );
+        }
+    }
+
+    #[test]
+    fn licm_load() {
+        let mut m = Module::new("sericum");
+
+        sericum_ir!(m; define [i32] func [(i32)] {
+        entry:
+            i = alloca i32;
+            a = alloca_ ([2; i32]);
+            a0 = gep (%a), [(i32 0), (i32 0)];
+            a1 = gep (%a), [(i32 0), (i32 1)];
+            store (%arg.0), (%a1);
+            store (i32 0), (%a0);
+            store (i32 0), (%i);
+            br header;
+        header:
+            li = load (%i);
+            c = icmp lt (%li), (i32 10);
+            br (%c) body, exit;
+        body:
+            x = load (%a1);
+            s = load (%a0);
+            s1 = add (%s), (%x);
+            store (%s1), (%a0);
+            i1 = add (%li), (i32 1);
+            store (%i1), (%i);
+            br header;
+        exit:
+            r = load (%a0);
+            ret (%r);
+        });
+
+        let body = {
+            let func = m.function_ref(m.find_function("func").unwrap());
+            func.basic_blocks.order[2]
+        };
+        ir::licm::LoopInvariantCodeMotion::new().run_on_module(&mut m);
+        println!("{:?}", m);
+
+        // Only the load of `a1` is hoisted, as `a0` and `i` are stored in the loop.
+        let func = m.function_ref(m.find_function("func").unwrap());
+        let loads = func.basic_blocks.arena[body]
+            .iseq_ref()
+            .iter()
+            .filter(|&&id| func.inst_table[id].opcode == ir::opcode::Opcode::Load)
+            .count();
+        assert_eq!(loads, 1);
+
+        let mut jit = exec::jit::JITExecutor::new(m);
+        let func = jit.find_function_by_name("func").unwrap();
+        assert_eq!(
+            jit.run(func, vec![exec::jit::GenericValue::Int32(3)]),
+            exec::jit::GenericValue::Int32(30)
+        );
+    }
+
+    #[test]
+    fn store_forwarding() {
+        let mut m = Module::new("sericum");
+
+        sericum_ir!(m; define [void] g [] {
+        entry:
+            ret (void);
+        });
+
+        sericum_ir!(m; define [i32] func [(i32)] {
+        entry:
+            a = alloca_ ([2; i32]);
+            a0 = gep (%a), [(i32 0), (i32 0)];
+            a1 = gep (%a), [(i32 0), (i32 1)];
+            store (%arg.0), (%a0);
+            store (i32 5), (%a1);
+            __ = call g [];
+            x = load (%a0);
+            c = icmp lt (%x), (i32 10);
+            br (%c) l, r;
+        l:
+            y = load (%a1);
+            store (%y), (%a0);
+            br merge;
+        r:
+            br merge;
+        merge:
+            z = load (%a0);
+            w = load (%a1);
+            s = add (%z), (%w);
+            ret (%s);
+        });
+
+        ir::store_forwarding::StoreToLoadForwarding::new().run_on_module(&mut m);
+        println!("{:?}", m);
+
+        // Only the loads in `merge`, reachable from both `l` and `r`, remain.
+        let func = m.function_ref(m.find_function("func").unwrap());
+        assert_eq!(count_opcode(func, ir::opcode::Opcode::Load), 2);
+
+        let mut jit = exec::jit::JITExecutor::new(m);
+        let func = jit.find_function_by_name("func").unwrap();
+        for &(n, expected) in &[(3, 10), (20, 25)] {
+            assert_eq!(
+                jit.run(func, vec![exec::jit::GenericValue::Int32(n)]),
+                exec::jit::GenericValue::Int32(expected)
+            );
+        }
+    }
+
+    #[test]
+    fn dse() {
+        let mut m = Module::new("sericum");
+
+        sericum_ir!(m; define [i32] h [(ptr i32)] {
+        entry:
+            v = load (%arg.0);
+            ret (%v);
+        });
+
+        sericum_ir!(m; define [i32] func [(i32)] {
+        entry:
+            a = alloca i32;
+            b = alloca i32;
+            c = alloca i32;
+            store (i32 1), (%a);
+            store (i32 7), (%b);
+            br next;
+        next:
+            store (%arg.0), (%a);
+            x = load (%a);
+            y = load (%b);
+            store (i32 3), (%a);
+            store (i32 5), (%c);
+            hv = call h [(%c)];
+            store (i32 6), (%c);
+            s = add (%x), (%y);
+            r = add (%s), (%hv);
+            ret (%r);
+        });
+
+        ir::dse::DeadStoreElimination::new().run_on_module(&mut m);
+        println!("{:?}", m);
+
+        // The first and last stores to `a` are dead. `c` is read by the call, and escapes.
+        let func = m.function_ref(m.find_function("func").unwrap());
+        assert_eq!(count_opcode(func, ir::opcode::Opcode::Store), 4);
+
+        let mut jit = exec::jit::JITExecutor::new(m);
+        let func = jit.find_function_by_name("func").unwrap();
+        assert_eq!(
+            jit.run(func, vec![exec::jit::GenericValue::Int32(1)]),
+            exec::jit::GenericValue::Int32(13)
+        );
+    }
+
     #[test]
     fn pass_struct() {
         let mut m = Module::new("sericum");
@@ -1127,4 +1561,496 @@ main:
         let func = jit.find_function_by_name("main").unwrap();
         assert_eq!(jit.run(func, vec![]), exec::jit::GenericValue::F64(24.6));
     }
+
+    #[test]
+    fn jit_global_var() {
+        let mut m = Module::new("sericum");
+        let g = m.global_vars.new_global_var_with_name(
+            types::Type::i32,
+            global_val::Linkage::Common,
+            "g",
+        );
+        let g = value::Value::Global(value::GlobalValue { id: g });
+
+        sericum_ir!(m; define [void] set [(i32)] {
+        entry:
+            store (%arg.0), (%g);
+            ret (void);
+        });
+
+        sericum_ir!(m; define [i32] func [(i32)] {
+        entry:
+            __ = call set [(%arg.0)];
+            i = load (%g);
+            a = add (%i), (i32 1);
+            ret (%a);
+        });
+
+        let mut jit = exec::jit::JITExecutor::new(m.clone());
+        let func = jit.find_function_by_name("func").unwrap();
+        assert_eq!(
+            jit.run(func, vec![exec::jit::GenericValue::Int32(41)]),
+            exec::jit::GenericValue::Int32(42)
+        );
+
+        // set and func, compiled separately, share g
+        let mut jit = exec::jit::JITExecutor::new_lazy(m);
+        let func = jit.find_function_by_name("func").unwrap();
+        assert_eq!(
+            jit.run(func, vec![exec::jit::GenericValue::Int32(41)]),
+            exec::jit::GenericValue::Int32(42)
+        );
+    }
+
+    #[test]
+    fn jit_i16_and_i1() {
+        let mut m = Module::new("sericum");
+
+        sericum_ir!(m; define [i16] twice [(i16)] {
+        entry:
+            x = add (%arg.0), (%arg.0);
+            ret (%x);
+        });
+        sericum_ir!(m; define [i1] is_neg [(i16)] {
+        entry:
+            c = icmp lt (%arg.0), (i16 0);
+            ret (%c);
+        });
+        sericum_ir!(m; define [i32] to_i32 [(i1)] {
+        entry:
+            x = zext [i32] (%arg.0);
+            ret (%x);
+        });
+
+        let mut jit = exec::jit::JITExecutor::new(m);
+        let twice = jit.find_function_by_name("twice").unwrap();
+        assert_eq!(
+            jit.run(twice, vec![exec::jit::GenericValue::Int16(-300)]),
+            exec::jit::GenericValue::Int16(-600)
+        );
+        assert_eq!(
+            jit.run(twice, vec![exec::jit::GenericValue::Int16(20000)]),
+            exec::jit::GenericValue::Int16(-25536)
+        );
+        let is_neg = jit.find_function_by_name("is_neg").unwrap();
+        assert_eq!(
+            jit.run(is_neg, vec![exec::jit::GenericValue::Int16(-1)]),
+            exec::jit::GenericValue::Int1(true)
+        );
+        assert_eq!(
+            jit.run(is_neg, vec![exec::jit::GenericValue::Int16(1)]),
+            exec::jit::GenericValue::Int1(false)
+        );
+        let to_i32 = jit.find_function_by_name("to_i32").unwrap();
+        assert_eq!(
+            jit.run(to_i32, vec![exec::jit::GenericValue::Int1(true)]),
+            exec::jit::GenericValue::Int32(1)
+        );
+
+        let f = jit
+            .get_function::<extern "C" fn(i16) -> i16>("twice")
+            .unwrap();
+        assert_eq!(f(-7), -14);
+        let f = jit
+            .get_function::<extern "C" fn(i16) -> bool>("is_neg")
+            .unwrap();
+        assert!(f(-7));
+    }
+
+    #[test]
+    fn jit_float_and_int_args() {
+        let mut m = Module::new("sericum");
+
+        sericum_ir!(m; define [f64] func [(f64), (i32)] {
+        entry:
+            n = sitofp [f64] (%arg.1);
+            x = mul (%arg.0), (%n);
+            ret (%x);
+        });
+
+        let mut jit = exec::jit::JITExecutor::new(m);
+        let func = jit.find_function_by_name("func").unwrap();
+        assert_eq!(
+            jit.run(
+                func,
+                vec![
+                    exec::jit::GenericValue::F64(1.5),
+                    exec::jit::GenericValue::Int32(3)
+                ]
+            ),
+            exec::jit::GenericValue::F64(4.5)
+        );
+
+        let f = jit
+            .get_function::<extern "C" fn(f64, i32) -> f64>("func")
+            .unwrap();
+        assert_eq!(f(2.5, 2), 5.0);
+        assert!(jit
+            .get_function::<extern "C" fn(i32, f64) -> f64>("func")
+            .is_none());
+        assert!(jit
+            .get_function::<extern "C" fn(f64) -> f64>("func")
+            .is_none());
+    }
+
+    #[test]
+    fn jit_stack_args() {
+        let mut m = Module::new("sericum");
+
+        sericum_ir!(m; define [i32] ints [(i32), (i32), (i32), (i32), (i32), (i32), (i32), (i8)] {
+        entry:
+            a = add (%arg.0), (%arg.1);
+            a = add (%a), (%arg.2);
+            a = add (%a), (%arg.3);
+            a = add (%a), (%arg.4);
+            a = add (%a), (%arg.5);
+            a = mul (%a), (%arg.6);
+            c = sext [i32] (%arg.7);
+            a = sub (%a), (%c);
+            ret (%a);
+        });
+
+        sericum_ir!(m; define [f64] floats [(f64), (f64), (f64), (f64), (f64), (f64), (f64), (f64), (f64), (i64)] {
+        entry:
+            a = add (%arg.0), (%arg.1);
+            a = add (%a), (%arg.2);
+            a = add (%a), (%arg.3);
+            a = add (%a), (%arg.4);
+            a = add (%a), (%arg.5);
+            a = add (%a), (%arg.6);
+            a = add (%a), (%arg.7);
+            a = mul (%a), (%arg.8);
+            ret (%a);
+        });
+
+        let mut jit = exec::jit::JITExecutor::new(m);
+
+        let ints = jit.find_function_by_name("ints").unwrap();
+        let mut args = (1..=7)
+            .map(exec::jit::GenericValue::Int32)
+            .collect::<Vec<_>>();
+        args.push(exec::jit::GenericValue::Int8(-3));
+        assert_eq!(
+            jit.run(ints, args),
+            exec::jit::GenericValue::Int32((1 + 2 + 3 + 4 + 5 + 6) * 7 + 3)
+        );
+
+        let floats = jit.find_function_by_name("floats").unwrap();
+        let mut args = (1..=9)
+            .map(|i| exec::jit::GenericValue::F64(i as f64))
+            .collect::<Vec<_>>();
+        args.push(exec::jit::GenericValue::Int64(1 << 40));
+        assert_eq!(
+            jit.run(floats, args),
+            exec::jit::GenericValue::F64((1 + 2 + 3 + 4 + 5 + 6 + 7 + 8) as f64 * 9.0)
+        );
+    }
+
+    #[test]
+    fn jit_returns() {
+        let mut m = Module::new("sericum");
+
+        sericum_ir!(m; define [i64] wide [(i64)] {
+        entry:
+            x = add (%arg.0), (%arg.0);
+            ret (%x);
+        });
+
+        sericum_ir!(m; define [i8] byte [(i8)] {
+        entry:
+            x = add (%arg.0), (i8 1);
+            ret (%x);
+        });
+
+        sericum_ir!(m; define [ptr i32] next [(ptr i32)] {
+        entry:
+            x = gep (%arg.0), [(i32 1)];
+            ret (%x);
+        });
+
+        let mut jit = exec::jit::JITExecutor::new(m);
+
+        let wide = jit.find_function_by_name("wide").unwrap();
+        assert_eq!(
+            jit.run(wide, vec![exec::jit::GenericValue::Int64(1 << 40)]),
+            exec::jit::GenericValue::Int64(1 << 41)
+        );
+
+        let byte = jit.find_function_by_name("byte").unwrap();
+        assert_eq!(
+            jit.run(byte, vec![exec::jit::GenericValue::Int8(41)]),
+            exec::jit::GenericValue::Int8(42)
+        );
+
+        let arr = [1i32, 2];
+        let next = jit.find_function_by_name("next").unwrap();
+        assert_eq!(
+            jit.run(
+                next,
+                vec![exec::jit::GenericValue::Address(arr.as_ptr() as *mut u8)]
+            ),
+            exec::jit::GenericValue::Address(arr[1..].as_ptr() as *mut u8)
+        );
+        let next = jit
+            .get_function::<extern "C" fn(*const i32) -> *const i32>("next")
+            .unwrap();
+        assert_eq!(next(arr.as_ptr()), arr[1..].as_ptr());
+    }
+
+    #[test]
+    fn jit_register_symbol() {
+        extern "C" fn host_twice(x: f64) -> f64 {
+            x * 2.0
+        }
+
+        let mut m = Module::new("sericum");
+        let twice = m.create_function("twice", types::Type::f64, vec![types::Type::f64]);
+
+        sericum_ir!(m; define [f64] func [(f64)] {
+        entry:
+            x = call (->twice) [(%arg.0)];
+            x = add (%x), (f64 1.0);
+            ret (%x);
+        });
+
+        let mut jit = exec::jit::JITExecutor::new(m);
+        jit.register_symbol(
+            "twice",
+            host_twice as *const u8,
+            types::FunctionType::new(
+                types::Type::f64,
+                vec![types::Type::f64],
+                FxHashMap::default(),
+            ),
+        );
+        let func = jit.find_function_by_name("func").unwrap();
+        assert_eq!(
+            jit.run(func, vec![exec::jit::GenericValue::F64(1.25)]),
+            exec::jit::GenericValue::F64(3.5)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't agree with the registered symbol")]
+    fn jit_register_symbol_then_mismatched_module() {
+        extern "C" fn host_twice(x: f64) -> f64 {
+            x * 2.0
+        }
+
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [i32] func [] {
+        entry:
+            ret (i32 0);
+        });
+
+        let mut jit = exec::jit::JITExecutor::new(m);
+        jit.register_symbol(
+            "twice",
+            host_twice as *const u8,
+            types::FunctionType::new(
+                types::Type::f64,
+                vec![types::Type::f64],
+                FxHashMap::default(),
+            ),
+        );
+
+        let mut m = Module::new("sericum2");
+        let twice = m.create_function("twice", types::Type::i32, vec![types::Type::i32]);
+        sericum_ir!(m; define [i32] func2 [(i32)] {
+        entry:
+            x = call (->twice) [(%arg.0)];
+            ret (%x);
+        });
+        jit.add_module(m);
+    }
+
+    #[test]
+    fn jit_dlsym() {
+        let mut m = Module::new("sericum");
+        let abs = m.create_function("abs", types::Type::i32, vec![types::Type::i32]);
+
+        sericum_ir!(m; define [i32] func [(i32)] {
+        entry:
+            x = call (->abs) [(%arg.0)];
+            ret (%x);
+        });
+
+        let mut jit = exec::jit::JITExecutor::new(m);
+        jit.enable_dlsym();
+        let func = jit.find_function_by_name("func").unwrap();
+        assert_eq!(
+            jit.run(func, vec![exec::jit::GenericValue::Int32(-7)]),
+            exec::jit::GenericValue::Int32(7)
+        );
+    }
+
+    #[test]
+    fn jit_add_and_replace_functions() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [i32] f [(i32)] {
+        entry:
+            x = add (%arg.0), (i32 1);
+            ret (%x);
+        });
+        sericum_ir!(m; define [i32] g [(i32)] {
+        entry:
+            x = call f [(%arg.0)];
+            x = mul (%x), (i32 2);
+            ret (%x);
+        });
+
+        let mut jit = exec::jit::JITExecutor::new(m);
+        let g = jit.find_function_by_name("g").unwrap();
+        let one = || vec![exec::jit::GenericValue::Int32(1)];
+        assert_eq!(jit.run(g, one()), exec::jit::GenericValue::Int32(4));
+
+        // Use g, defined before, from a new function
+        let mut m = Module::new("sericum");
+        let g_decl = m.create_function("g", types::Type::i32, vec![types::Type::i32]);
+        sericum_ir!(m; define [i32] h [(i32)] {
+        entry:
+            x = call (->g_decl) [(%arg.0)];
+            x = add (%x), (i32 100);
+            ret (%x);
+        });
+        jit.add_module(m);
+        let h = jit.find_function_by_name("h").unwrap();
+        assert_eq!(jit.run(h, one()), exec::jit::GenericValue::Int32(104));
+
+        // Replace f, called by g compiled before
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [i32] f [(i32)] {
+        entry:
+            x = add (%arg.0), (i32 10);
+            ret (%x);
+        });
+        jit.add_module(m);
+        assert_eq!(jit.run(g, one()), exec::jit::GenericValue::Int32(22));
+        assert_eq!(jit.run(h, one()), exec::jit::GenericValue::Int32(122));
+        let f = jit.find_function_by_name("f").unwrap();
+        assert_eq!(jit.run(f, one()), exec::jit::GenericValue::Int32(11));
+    }
+
+    #[test]
+    fn jit_lazy_compilation() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [f64] scale [(f64), (i32)] {
+        entry:
+            n = sitofp [f64] (%arg.1);
+            x = mul (%arg.0), (%n);
+            ret (%x);
+        });
+        sericum_ir!(m; define [i32] fibo [(i32)] {
+        entry:
+            c = icmp le (%arg.0), (i32 2);
+            br (%c) l1, l2;
+        l1:
+            ret (i32 1);
+        l2:
+            a = sub (%arg.0), (i32 1);
+            r1 = call fibo [(%a)];
+            b = sub (%arg.0), (i32 2);
+            r2 = call fibo [(%b)];
+            r3 = add (%r1), (%r2);
+            ret (%r3);
+        });
+        sericum_ir!(m; define [f64] main [] {
+        entry:
+            x = call fibo [(i32 10)];
+            y = call scale [(f64 1.5), (%x)];
+            ret (%y);
+        });
+        sericum_ir!(m; define [i32] unused [] {
+        entry:
+            ret (i32 0);
+        });
+
+        let mut jit = exec::jit::JITExecutor::new_lazy(m);
+        for name in &["scale", "fibo", "main", "unused"] {
+            assert!(!jit.is_compiled(name));
+        }
+
+        let main = jit.find_function_by_name("main").unwrap();
+        assert_eq!(jit.run(main, vec![]), exec::jit::GenericValue::F64(82.5));
+        for name in &["scale", "fibo", "main"] {
+            assert!(jit.is_compiled(name));
+        }
+        assert!(!jit.is_compiled("unused"));
+
+        let unused = jit
+            .get_function::<extern "C" fn() -> i32>("unused")
+            .unwrap();
+        assert_eq!(unused(), 0);
+        assert!(jit.is_compiled("unused"));
+    }
+
+    #[test]
+    fn jit_unsigned_i64_cvt() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [f64] u64_to_f64 [(i64)] {
+        entry:
+            x = uitofp [f64] (%arg.0);
+            ret (%x);
+        });
+        sericum_ir!(m; define [f32] u64_to_f32 [(i64)] {
+        entry:
+            x = uitofp [f32] (%arg.0);
+            ret (%x);
+        });
+        sericum_ir!(m; define [i64] f64_to_u64 [(f64)] {
+        entry:
+            x = fptoui [i64] (%arg.0);
+            ret (%x);
+        });
+        sericum_ir!(m; define [i64] f32_to_u64 [(f32)] {
+        entry:
+            x = fptoui [i64] (%arg.0);
+            ret (%x);
+        });
+
+        let mut jit = exec::jit::JITExecutor::new(m);
+        let u64_to_f64 = jit.find_function_by_name("u64_to_f64").unwrap();
+        let u64_to_f32 = jit.find_function_by_name("u64_to_f32").unwrap();
+        let f64_to_u64 = jit.find_function_by_name("f64_to_u64").unwrap();
+        let f32_to_u64 = jit.find_function_by_name("f32_to_u64").unwrap();
+        for &x in &[
+            0u64,
+            12345,
+            i64::MAX as u64,
+            1 << 63,
+            (1 << 63) + 3,
+            u64::MAX,
+        ] {
+            assert_eq!(
+                jit.run(u64_to_f64, vec![exec::jit::GenericValue::Int64(x as i64)]),
+                exec::jit::GenericValue::F64(x as f64)
+            );
+            assert_eq!(
+                jit.run(u64_to_f32, vec![exec::jit::GenericValue::Int64(x as i64)]),
+                exec::jit::GenericValue::F32(x as f32)
+            );
+        }
+        for &x in &[
+            0.0f64,
+            12345.5,
+            9223372036854775808.0,
+            18446744073709549568.0,
+        ] {
+            assert_eq!(
+                jit.run(f64_to_u64, vec![exec::jit::GenericValue::F64(x)]),
+                exec::jit::GenericValue::Int64(x as u64 as i64)
+            );
+        }
+        for &x in &[
+            0.0f32,
+            12345.5,
+            9223372036854775808.0,
+            18446742974197923840.0,
+        ] {
+            assert_eq!(
+                jit.run(f32_to_u64, vec![exec::jit::GenericValue::F32(x)]),
+                exec::jit::GenericValue::Int64(x as u64 as i64)
+            );
+        }
+    }
 }
@@ -390,6 +390,362 @@ mod x86_64 {
             m,
         );
     }
+
+    #[test]
+    fn asm_bitwise() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [i64] bits [(i32), (i64)] {
+        entry:
+            n = and (%arg.0), (i32 31);
+            a = shl (%arg.0), (%n);
+            b = lshr (%arg.0), (%n);
+            c = ashr (%arg.0), (i32 4);
+            x = or (%a), (%b);
+            x = xor (%x), (%c);
+            y = sext [i64] (%x);
+            z = and (%arg.1), (i32 63);
+            w = ashr (%arg.1), (%z);
+            y = xor (%y), (%w);
+            ret (%y);
+        });
+        compile_and_run(
+            "
+        #include <assert.h>
+        long bits(int, long);
+        long ref(int x, long y) {
+            int n = x & 31;
+            int r = ((int)((unsigned)x << n) | (int)((unsigned)x >> n)) ^ (x >> 4);
+            return (long)r ^ (y >> (y & 63));
+        }
+        int main() {
+            assert(bits(0x12345678, -0x123456789) == ref(0x12345678, -0x123456789));
+            assert(bits(-7, 0x40000003) == ref(-7, 0x40000003));
+            return 0;
+        }
+            ",
+            m,
+        );
+    }
+
+    #[test]
+    fn asm_arith_i16() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [i32] arith16 [(i16), (i16)] {
+        entry:
+            a = add (%arg.0), (%arg.1);
+            b = mul (%a), (i16 3);
+            c = sub (%b), (%arg.1);
+            d = div (%c), (i16 7);
+            e = rem (%c), (%arg.1);
+            f = ashr (%d), (i16 1);
+            g = xor (%f), (%e);
+            x = sext [i32] (%g);
+            y = zext [i32] (%arg.1);
+            z = add (%x), (%y);
+            ret (%z);
+        });
+        compile_and_run(
+            "
+        #include <assert.h>
+        int arith16(short, short);
+        int ref(short x, short y) {
+            short c = (short)((short)(x + y) * 3) - y;
+            return (short)((short)(c / 7 >> 1) ^ (short)(c % y)) + (unsigned short)y;
+        }
+        int main() {
+            assert(arith16(1234, 567) == ref(1234, 567));
+            assert(arith16(-1234, 567) == ref(-1234, 567));
+            assert(arith16(20000, -300) == ref(20000, -300));
+            return 0;
+        }
+            ",
+            m,
+        );
+    }
+
+    #[test]
+    fn asm_arith_i64() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [i64] arith64 [(i64), (i32)] {
+        entry:
+            y = sext [i64] (%arg.1);
+            a = mul (%arg.0), (%y);
+            b = add (%a), (i64 10000000000);
+            c = sub (i64 1), (%b);
+            d = div (%c), (%y);
+            e = rem (%c), (i64 1000003);
+            f = shl (%d), (i64 2);
+            g = ashr (%e), (i64 1);
+            x = add (%f), (%g);
+            l = icmp lt (%x), (i64 -5000000000);
+            br (%l) small, large;
+        small:
+            ret (i64 -1);
+        large:
+            ret (%x);
+        });
+        compile_and_run(
+            "
+        #include <assert.h>
+        long arith64(long, int);
+        long ref(long x, int y) {
+            long c = 1 - (x * y + 10000000000L);
+            long r = ((c / y) << 2) + ((c % 1000003) >> 1);
+            return r < -5000000000L ? -1 : r;
+        }
+        int main() {
+            assert(arith64(123456789, 1000) == ref(123456789, 1000));
+            assert(arith64(-123456789, 7) == ref(-123456789, 7));
+            assert(arith64(3, -3) == ref(3, -3));
+            return 0;
+        }
+            ",
+            m,
+        );
+    }
+
+    #[test]
+    fn asm_float_single() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [f32] scale [(f32), (i32)] {
+        entry:
+            n = sitofp [f32] (%arg.1);
+            a = mul (%arg.0), (%n);
+            b = add (%a), (f32 0.5);
+            c = fpext [f64] (%b);
+            d = div (%c), (f64 4.0);
+            e = fptrunc [f32] (%d);
+            l = fcmp ult (%e), (f32 1.0);
+            br (%l) small, large;
+        small:
+            ret (f32 1.0);
+        large:
+            ret (%e);
+        });
+        sericum_ir!(m; define [i32] trunc_ [(f32)] {
+        entry:
+            x = fptosi [i32] (%arg.0);
+            ret (%x);
+        });
+        compile_and_run(
+            "
+        #include <assert.h>
+        float scale(float, int);
+        int trunc_(float);
+        int main() {
+            assert(scale(1.5f, 10) == 3.875f);
+            assert(scale(0.25f, 2) == 1.0f);
+            assert(trunc_(-7.75f) == -7);
+            return 0;
+        }
+            ",
+            m,
+        );
+    }
+
+    #[test]
+    fn asm_casts() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [i8] low_byte [(i64)] {
+        entry:
+            x = trunc [i8] (%arg.0);
+            ret (%x);
+        });
+        sericum_ir!(m; define [f32] to_float [(i32)] {
+        entry:
+            x = uitofp [f32] (%arg.0);
+            ret (%x);
+        });
+        sericum_ir!(m; define [i16] to_ushort [(f64)] {
+        entry:
+            x = fptoui [i16] (%arg.0);
+            ret (%x);
+        });
+        sericum_ir!(m; define [i64] addr_diff [(ptr i32), (ptr i32)] {
+        entry:
+            x = ptrtoint [i64] (%arg.0);
+            y = ptrtoint [i64] (%arg.1);
+            z = sub (%y), (%x);
+            ret (%z);
+        });
+        compile_and_run(
+            "
+        #include <assert.h>
+        char low_byte(long);
+        float to_float(unsigned);
+        unsigned short to_ushort(double);
+        long addr_diff(int *, int *);
+        int main() {
+            int a[4];
+            assert(low_byte(0x1234567f) == 0x7f);
+            assert(to_float(4000000000u) == 4000000000.0f);
+            assert(to_ushort(65000.75) == 65000);
+            assert(addr_diff(&a[0], &a[3]) == 12);
+            return 0;
+        }
+            ",
+            m,
+        );
+    }
+
+    #[test]
+    fn asm_select() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [i32] max [(i32), (i32)] {
+        entry:
+            c = icmp lt (%arg.0), (%arg.1);
+            x = select (%c), (%arg.1), (%arg.0);
+            ret (%x);
+        });
+        sericum_ir!(m; define [i64] umin [(i64), (i64)] {
+        entry:
+            c = icmp ult (%arg.0), (%arg.1);
+            x = select (%c), (%arg.0), (%arg.1);
+            ret (%x);
+        });
+        sericum_ir!(m; define [i8] pick [(i8), (i8)] {
+        entry:
+            c = icmp eq (%arg.0), (i8 0);
+            x = select (%c), (i8 -1), (%arg.1);
+            ret (%x);
+        });
+        sericum_ir!(m; define [f64] fmin [(f64), (f64)] {
+        entry:
+            c = fcmp ult (%arg.0), (%arg.1);
+            x = select (%c), (%arg.0), (%arg.1);
+            ret (%x);
+        });
+        sericum_ir!(m; define [i32] abs_ [(i32)] {
+        entry:
+            c = icmp lt (%arg.0), (i32 0);
+            br (%c) neg, merge;
+        neg:
+            n = sub (i32 0), (%arg.0);
+            br merge;
+        merge:
+            p = phi [ [(%n), neg], [(%arg.0), entry] ];
+            ret (%p);
+        });
+        sericum::ir::if_conversion::IfConversion::new().run_on_module(&mut m);
+        compile_and_run(
+            "
+        #include <assert.h>
+        int max(int, int);
+        unsigned long umin(unsigned long, unsigned long);
+        char pick(char, char);
+        double fmin(double, double);
+        int abs_(int);
+        int main() {
+            assert(max(3, -4) == 3);
+            assert(max(-3, 4) == 4);
+            assert(umin(-1, 5) == 5);
+            assert(umin(2, 5) == 2);
+            assert(pick(0, 7) == -1);
+            assert(pick(1, 7) == 7);
+            assert(fmin(1.5, -2.5) == -2.5);
+            assert(fmin(1.5, 2.5) == 1.5);
+            assert(abs_(-42) == 42);
+            assert(abs_(42) == 42);
+            return 0;
+        }
+            ",
+            m,
+        );
+    }
+
+    #[test]
+    fn asm_switch() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [i32] dense [(i32)] {
+        entry:
+            switch (%arg.0) other, [
+                [(i32 -2), l0], [(i32 -1), l1], [(i32 0), l2], [(i32 1), l0], [(i32 3), l1]
+            ];
+        l0:
+            br merge;
+        l1:
+            br merge;
+        l2:
+            x = add (%arg.0), (i32 5);
+            br merge;
+        other:
+            br merge;
+        merge:
+            p = phi [ [(i32 10), l0], [(i32 11), l1], [(%x), l2], [(i32 -1), other] ];
+            ret (%p);
+        });
+        sericum_ir!(m; define [i32] wide [(i64)] {
+        entry:
+            switch (%arg.0) other, [
+                [(i64 4294967296), l0], [(i64 4294967297), l1], [(i64 4294967298), l0],
+                [(i64 4294967300), l1], [(i64 -7), l1], [(i64 123456789), l0]
+            ];
+        l0:
+            ret (i32 1);
+        l1:
+            ret (i32 2);
+        other:
+            ret (i32 0);
+        });
+        compile_and_run(
+            "
+        #include <assert.h>
+        int dense(int);
+        int wide(long);
+        int main() {
+            assert(dense(-3) == -1);
+            assert(dense(-2) == 10);
+            assert(dense(-1) == 11);
+            assert(dense(0) == 5);
+            assert(dense(1) == 10);
+            assert(dense(2) == -1);
+            assert(dense(3) == 11);
+            assert(dense(4) == -1);
+            assert(dense(-2147483647 - 1) == -1);
+            assert(dense(2147483647) == -1);
+            assert(wide(4294967296) == 1);
+            assert(wide(4294967297) == 2);
+            assert(wide(4294967299) == 0);
+            assert(wide(4294967300) == 2);
+            assert(wide(-7) == 2);
+            assert(wide(123456789) == 1);
+            assert(wide(0) == 0);
+            return 0;
+        }
+            ",
+            m,
+        );
+    }
+
+    #[test]
+    fn asm_unsigned() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [i32] udiv_ [(i32), (i32)] {
+        entry:
+            q = udiv (%arg.0), (%arg.1);
+            r = urem (%arg.0), (%arg.1);
+            c = icmp ule (%arg.0), (%arg.1);
+            br (%c) le, gt;
+        le:
+            ret (%q);
+        gt:
+            x = add (%q), (%r);
+            ret (%x);
+        });
+        compile_and_run(
+            "
+        #include <assert.h>
+        int udiv_(unsigned, unsigned);
+        int main() {
+            assert(udiv_(-7, 3) == (-7u / 3 + -7u % 3));
+            assert(udiv_(3, -7) == 0);
+            assert(udiv_(100, 7) == 14 + 2);
+            return 0;
+        }
+            ",
+            m,
+        );
+    }
 }
 
 #[cfg(feature = "riscv64")]
@@ -398,8 +754,7 @@ mod riscv64 {
         codegen::riscv64::{
             asm::print::MachineAsmPrinter, standard_conversion_into_machine_module,
         },
-        ir::builder::FuncRef,
-        ir::{builder, types, value},
+        ir::{types, value},
         module::Module,
         sericum_ir,
         *, // for macro
@@ -461,7 +816,7 @@ mod riscv64 {
         fs::remove_file(target_name).unwrap();
     }
 
-    fn compile_and_run(c_parent: &str, module: &mut Module) {
+    fn compile_and_run(c_parent: &str, module: Module) {
         let machine_module = standard_conversion_into_machine_module(module);
         let mut printer = MachineAsmPrinter::new();
         println!("{:?}", machine_module);
@@ -997,10 +1352,7 @@ mod riscv64 {
         let g = m
             .global_vars
             .new_global_var_with_name(ty, global_val::Linkage::Common, "g");
-        let g = value::Value::Global(value::GlobalValue {
-            id: g,
-            ty: m.types.new_pointer_ty(ty),
-        });
+        let g = value::Value::Global(value::GlobalValue { id: g });
 
         sericum_ir!(m; define [i32] test [] {
             entry:
@@ -1026,10 +1378,7 @@ mod riscv64 {
         let g = m
             .global_vars
             .new_global_var_with_name(ty, global_val::Linkage::Common, "g");
-        let g = value::Value::Global(value::GlobalValue {
-            id: g,
-            ty: m.types.new_pointer_ty(ty),
-        });
+        let g = value::Value::Global(value::GlobalValue { id: g });
 
         sericum_ir!(m; define [i32] test [] {
             entry:
@@ -1051,6 +1400,125 @@ mod riscv64 {
             m,
         );
     }
+
+    #[test]
+    fn asm_arith_i16() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [i32] arith16 [(i16), (i16)] {
+        entry:
+            a = add (%arg.0), (%arg.1);
+            b = mul (%a), (i16 3);
+            c = sub (%b), (%arg.1);
+            d = div (%c), (i16 7);
+            e = rem (%c), (%arg.1);
+            f = ashr (%d), (i16 1);
+            g = xor (%f), (%e);
+            x = sext [i32] (%g);
+            y = zext [i32] (%arg.1);
+            z = add (%x), (%y);
+            ret (%z);
+        });
+        compile_and_run(
+            "
+        #include <assert.h>
+        int arith16(short, short);
+        int ref(short x, short y) {
+            short c = (short)((short)(x + y) * 3) - y;
+            return (short)((short)(c / 7 >> 1) ^ (short)(c % y)) + (unsigned short)y;
+        }
+        int main() {
+            assert(arith16(1234, 567) == ref(1234, 567));
+            assert(arith16(-1234, 567) == ref(-1234, 567));
+            assert(arith16(20000, -300) == ref(20000, -300));
+            return 0;
+        }
+            ",
+            m,
+        );
+    }
+
+    #[test]
+    fn asm_arith_i64() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [i64] arith64 [(i64), (i32)] {
+        entry:
+            y = sext [i64] (%arg.1);
+            a = mul (%arg.0), (%y);
+            b = add (%a), (i64 10000000000);
+            c = sub (i64 1), (%b);
+            d = div (%c), (%y);
+            e = rem (%c), (i64 1000003);
+            f = shl (%d), (i64 2);
+            g = ashr (%e), (i64 1);
+            x = add (%f), (%g);
+            l = icmp lt (%x), (i64 -5000000000);
+            br (%l) small, large;
+        small:
+            ret (i64 -1);
+        large:
+            ret (%x);
+        });
+        compile_and_run(
+            "
+        #include <assert.h>
+        long arith64(long, int);
+        long ref(long x, int y) {
+            long c = 1 - (x * y + 10000000000L);
+            long r = ((c / y) << 2) + ((c % 1000003) >> 1);
+            return r < -5000000000L ? -1 : r;
+        }
+        int main() {
+            assert(arith64(123456789, 1000) == ref(123456789, 1000));
+            assert(arith64(-123456789, 7) == ref(-123456789, 7));
+            assert(arith64(3, -3) == ref(3, -3));
+            return 0;
+        }
+            ",
+            m,
+        );
+    }
+
+    #[test]
+    fn asm_unsigned_cvt() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [f64] u64_to_f64 [(i64)] {
+        entry:
+            x = uitofp [f64] (%arg.0);
+            ret (%x);
+        });
+        sericum_ir!(m; define [f32] u32_to_f32 [(i32)] {
+        entry:
+            x = uitofp [f32] (%arg.0);
+            ret (%x);
+        });
+        sericum_ir!(m; define [i64] f64_to_u64 [(f64)] {
+        entry:
+            x = fptoui [i64] (%arg.0);
+            ret (%x);
+        });
+        sericum_ir!(m; define [i32] f32_to_u32 [(f32)] {
+        entry:
+            x = fptoui [i32] (%arg.0);
+            ret (%x);
+        });
+        compile_and_run(
+            "
+        #include <assert.h>
+        double u64_to_f64(unsigned long);
+        float u32_to_f32(unsigned);
+        unsigned long f64_to_u64(double);
+        unsigned f32_to_u32(float);
+        int main() {
+            assert(u64_to_f64(1ul << 63) == 9223372036854775808.0);
+            assert(u32_to_f32(4000000000u) == 4000000000.0f);
+            assert(f64_to_u64(1e19) == 10000000000000000000ul);
+            assert(f32_to_u32(3e9f) == 3000000000u);
+            return 0;
+        }
+            ",
+            m,
+        );
+    }
 }
 
 #[cfg(feature = "aarch64")]
@@ -1493,4 +1961,183 @@ mod aarch64 {
             m,
         );
     }
+
+    #[test]
+    fn asm_bitwise() {
+        let mut m = module::Module::new("sericum");
+        sericum_ir!(m; define [i32] bits [(i32), (i32)] {
+            entry:
+                n = and (%arg.1), (i32 31);
+                a = shl (%arg.0), (%n);
+                b = lshr (%arg.0), (i32 3);
+                c = ashr (%arg.0), (%n);
+                x = or (%a), (%b);
+                x = xor (%x), (%c);
+                x = and (%x), (i32 4095);
+                ret (%x);
+        });
+        compile_and_run(
+            "
+    #include <assert.h>
+    extern int bits(int, int);
+    int ref(int x, int y) {
+        int n = y & 31;
+        return (((int)((unsigned)x << n) | (int)((unsigned)x >> 3)) ^ (x >> n)) & 4095;
+    }
+    int main() {
+        assert(bits(0x12345678, 5) == ref(0x12345678, 5));
+        assert(bits(-123456, 37) == ref(-123456, 37));
+    }
+            ",
+            m,
+        );
+    }
+
+    #[test]
+    fn asm_unsigned() {
+        let mut m = module::Module::new("sericum");
+        sericum_ir!(m; define [i32] udiv_ [(i32), (i32)] {
+            entry:
+                q = udiv (%arg.0), (%arg.1);
+                r = urem (%arg.0), (%arg.1);
+                c = icmp ugt (%q), (i32 2);
+                br (%c) gt, le;
+            gt:
+                x = add (%q), (%r);
+                ret (%x);
+            le:
+                ret (%r);
+        });
+        compile_and_run(
+            "
+    #include <assert.h>
+    extern int udiv_(unsigned, unsigned);
+    int main() {
+        assert(udiv_(-7, 3) == (-7u / 3 + -7u % 3));
+        assert(udiv_(3, -7) == 3);
+        assert(udiv_(100, 7) == 14 + 2);
+    }
+            ",
+            m,
+        );
+    }
+
+    #[test]
+    fn asm_arith_i16() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [i32] arith16 [(i16), (i16)] {
+        entry:
+            a = add (%arg.0), (%arg.1);
+            b = mul (%a), (i16 3);
+            c = sub (%b), (%arg.1);
+            d = div (%c), (i16 7);
+            e = rem (%c), (%arg.1);
+            f = ashr (%d), (i16 1);
+            g = xor (%f), (%e);
+            x = sext [i32] (%g);
+            y = zext [i32] (%arg.1);
+            z = add (%x), (%y);
+            ret (%z);
+        });
+        compile_and_run(
+            "
+        #include <assert.h>
+        int arith16(short, short);
+        int ref(short x, short y) {
+            short c = (short)((short)(x + y) * 3) - y;
+            return (short)((short)(c / 7 >> 1) ^ (short)(c % y)) + (unsigned short)y;
+        }
+        int main() {
+            assert(arith16(1234, 567) == ref(1234, 567));
+            assert(arith16(-1234, 567) == ref(-1234, 567));
+            assert(arith16(20000, -300) == ref(20000, -300));
+            return 0;
+        }
+            ",
+            m,
+        );
+    }
+
+    #[test]
+    fn asm_arith_i64() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [i64] arith64 [(i64), (i32)] {
+        entry:
+            y = sext [i64] (%arg.1);
+            a = mul (%arg.0), (%y);
+            b = add (%a), (i64 10000000000);
+            c = sub (i64 1), (%b);
+            d = div (%c), (%y);
+            e = rem (%c), (i64 1000003);
+            f = shl (%d), (i64 2);
+            g = ashr (%e), (i64 1);
+            x = add (%f), (%g);
+            l = icmp lt (%x), (i64 -5000000000);
+            br (%l) small, large;
+        small:
+            ret (i64 -1);
+        large:
+            ret (%x);
+        });
+        compile_and_run(
+            "
+        #include <assert.h>
+        long arith64(long, int);
+        long ref(long x, int y) {
+            long c = 1 - (x * y + 10000000000L);
+            long r = ((c / y) << 2) + ((c % 1000003) >> 1);
+            return r < -5000000000L ? -1 : r;
+        }
+        int main() {
+            assert(arith64(123456789, 1000) == ref(123456789, 1000));
+            assert(arith64(-123456789, 7) == ref(-123456789, 7));
+            assert(arith64(3, -3) == ref(3, -3));
+            return 0;
+        }
+            ",
+            m,
+        );
+    }
+
+    #[test]
+    fn asm_unsigned_cvt() {
+        let mut m = Module::new("sericum");
+        sericum_ir!(m; define [f64] u64_to_f64 [(i64)] {
+        entry:
+            x = uitofp [f64] (%arg.0);
+            ret (%x);
+        });
+        sericum_ir!(m; define [f32] u32_to_f32 [(i32)] {
+        entry:
+            x = uitofp [f32] (%arg.0);
+            ret (%x);
+        });
+        sericum_ir!(m; define [i64] f64_to_u64 [(f64)] {
+        entry:
+            x = fptoui [i64] (%arg.0);
+            ret (%x);
+        });
+        sericum_ir!(m; define [i32] f32_to_u32 [(f32)] {
+        entry:
+            x = fptoui [i32] (%arg.0);
+            ret (%x);
+        });
+        compile_and_run(
+            "
+        #include <assert.h>
+        double u64_to_f64(unsigned long);
+        float u32_to_f32(unsigned);
+        unsigned long f64_to_u64(double);
+        unsigned f32_to_u32(float);
+        int main() {
+            assert(u64_to_f64(1ul << 63) == 9223372036854775808.0);
+            assert(u32_to_f32(4000000000u) == 4000000000.0f);
+            assert(f64_to_u64(1e19) == 10000000000000000000ul);
+            assert(f32_to_u32(3e9f) == 3000000000u);
+            return 0;
+        }
+            ",
+            m,
+        );
+    }
 }
@@ -0,0 +1,701 @@
+use sericum::{
+    exec::interpreter::{
+        interp::{ConcreteValue, Interpreter},
+        memory::InterpError,
+    },
+    ir,
+    ir::{parser::parse_module, prelude::*},
+    sericum_ir,
+};
+
+fn run(m: &Module, name: &str, args: Vec<ConcreteValue>) -> ConcreteValue {
+    try_run(m, name, args).unwrap()
+}
+
+fn try_run(m: &Module, name: &str, args: Vec<ConcreteValue>) -> Result<ConcreteValue, InterpError> {
+    let id = m.find_function(name).unwrap();
+    Interpreter::new(m).run_function(id, args)
+}
+
+/// Runs `name` in the module built by `build`, both as it is and after mem2reg.
+fn run_both(build: fn(&mut Module), name: &str, args: Vec<ConcreteValue>) -> ConcreteValue {
+    let mut m = Module::new("sericum");
+    build(&mut m);
+    let res = run(&m, name, args.clone());
+
+    let mut m = Module::new("sericum");
+    build(&mut m);
+    ir::mem2reg::Mem2Reg::new().run_on_module(&mut m);
+    assert_eq!(run(&m, name, args), res);
+
+    res
+}
+
+fn sum_array(m: &mut Module) {
+    sericum_ir!(m; define [i32] func [(i32)] {
+    entry:
+        arr = alloca_ ([10; i32]);
+        i = alloca i32;
+        sum = alloca i32;
+        store (i32 0), (%i);
+        store (i32 0), (%sum);
+        br fill;
+    fill:
+        li = load (%i);
+        p = gep (%arr), [(i32 0), (%li)];
+        x = mul (%li), (%arg.0);
+        store (%x), (%p);
+        li2 = add (%li), (i32 1);
+        store (%li2), (%i);
+        c = icmp lt (%li2), (i32 10);
+        br (%c) fill, sum_;
+    sum_:
+        store (i32 0), (%i);
+        br loop_;
+    loop_:
+        li = load (%i);
+        p = gep (%arr), [(i32 0), (%li)];
+        x = load (%p);
+        s = load (%sum);
+        s2 = add (%s), (%x);
+        store (%s2), (%sum);
+        li2 = add (%li), (i32 1);
+        store (%li2), (%i);
+        c = icmp lt (%li2), (i32 10);
+        br (%c) loop_, end;
+    end:
+        s = load (%sum);
+        ret (%s);
+    });
+}
+
+fn struct_global(m: &mut Module) {
+    let inner = m.types.new_struct_ty(vec![Type::i8, Type::f64]);
+    let ty = m
+        .types
+        .new_struct_ty(vec![Type::i8, Type::i32, inner, Type::i64]);
+    let g = m
+        .global_vars
+        .new_global_var_with_name(ty, global_val::Linkage::Common, "g");
+    let g = value::Value::Global(value::GlobalValue { id: g });
+    sericum_ir!(m; define [i64] func [] {
+    entry:
+        a = gep (%g), [(i32 0), (i32 0)];
+        b = gep (%g), [(i32 0), (i32 1)];
+        c = gep (%g), [(i32 0), (i32 2), (i32 1)];
+        d = gep (%g), [(i32 0), (i32 3)];
+        store (i8 1), (%a);
+        store (i32 20), (%b);
+        store (f64 300.0), (%c);
+        store (i64 4000), (%d);
+        la = load (%a);
+        lb = load (%b);
+        lc = load (%c);
+        ld = load (%d);
+        la = sext [i64] (%la);
+        lb = sext [i64] (%lb);
+        lc = fptosi [i64] (%lc);
+        x = add (%la), (%lb);
+        x = add (%x), (%lc);
+        x = add (%x), (%ld);
+        ret (%x);
+    });
+}
+
+fn string_length(m: &mut Module) {
+    let s = m.create_string("hello, world".to_string());
+    sericum_ir!(m; define [i32] func [] {
+    entry:
+        i = alloca i32;
+        store (i32 0), (%i);
+        br loop_;
+    loop_:
+        li = load (%i);
+        p = gep (%s), [(i32 0), (%li)];
+        c = load (%p);
+        z = icmp eq (%c), (i8 0);
+        br (%z) end, body;
+    body:
+        li2 = add (%li), (i32 1);
+        store (%li2), (%i);
+        br loop_;
+    end:
+        ret (%li);
+    });
+}
+
+fn float_internals(m: &mut Module) {
+    let sqrt = m.create_function("sericum.sqrt.f64", Type::f64, vec![Type::f64]);
+    let floor = m.create_function("sericum.floor.f64", Type::f64, vec![Type::f64]);
+    sericum_ir!(m; define [i32] func [(i32)] {
+    entry:
+        x = sitofp [f64] (%arg.0);
+        y = call (->sqrt) [(%x)];
+        y = mul (%y), (f64 10.0);
+        y = call (->floor) [(%y)];
+        c = fcmp ult (%y), (f64 0.0);
+        br (%c) neg, pos;
+    neg:
+        ret (i32 -1);
+    pos:
+        r = fptosi [i32] (%y);
+        ret (%r);
+    });
+}
+
+fn memset_malloc(m: &mut Module) {
+    let ptr_i32 = m.types.new_pointer_ty(Type::i32);
+    let memset = m.create_function(
+        "sericum.memset.p0i32.i32",
+        Type::Void,
+        vec![ptr_i32, Type::i32, Type::i32],
+    );
+    let malloc = m.create_function("sericum.malloc.i32", ptr_i32, vec![Type::i32]);
+    sericum_ir!(m; define [i32] func [] {
+    entry:
+        p = call (->malloc) [(i32 16)];
+        __ = call (->memset) [(%p), (i32 1), (i32 4)];
+        q = gep (%p), [(i32 3)];
+        x = load (%q);
+        ret (%x);
+    });
+}
+
+fn fibo(m: &mut Module) {
+    sericum_ir!(m; define [i32] fibo [(i32)] {
+    entry:
+        c = icmp le (%arg.0), (i32 2);
+        br (%c) l1, l2;
+    l1:
+        ret (i32 1);
+    l2:
+        a1 = sub (%arg.0), (i32 1);
+        r1 = call fibo [(%a1)];
+        a2 = sub (%arg.0), (i32 2);
+        r2 = call fibo [(%a2)];
+        r = add (%r1), (%r2);
+        ret (%r);
+    });
+}
+
+fn bitwise(m: &mut Module) {
+    sericum_ir!(m; define [i32] func [(i32), (i32)] {
+    entry:
+        a = and (%arg.0), (i32 4080);
+        b = or (%a), (%arg.1);
+        c = xor (%b), (i32 -1);
+        n = and (%arg.1), (i32 7);
+        d = shl (%arg.0), (%n);
+        e = lshr (%c), (%n);
+        f = ashr (%c), (i32 3);
+        g = lshr (%arg.0), (i32 28);
+        x = xor (%d), (%e);
+        x = add (%x), (%f);
+        x = or (%x), (%g);
+        ret (%x);
+    });
+}
+
+fn unsigned(m: &mut Module) {
+    sericum_ir!(m; define [i32] func [(i32), (i32)] {
+    entry:
+        q = udiv (%arg.0), (%arg.1);
+        r = urem (%arg.0), (%arg.1);
+        d = udiv (%arg.0), (i32 10);
+        x = add (%q), (%r);
+        x = add (%x), (%d);
+        c = icmp ult (%arg.0), (%arg.1);
+        br (%c) lt, ge;
+    lt:
+        ret (%x);
+    ge:
+        c = icmp ugt (%arg.0), (i32 -100);
+        br (%c) big, small;
+    big:
+        ret (i32 -1);
+    small:
+        ret (%q);
+    });
+}
+
+fn wide_ints(m: &mut Module) {
+    sericum_ir!(m; define [i64] func [(i32), (i32)] {
+    entry:
+        p = alloca i16;
+        store (i16 -300), (%p);
+        s = load (%p);
+        t = mul (%s), (i16 7);
+        u = div (%t), (i16 3);
+        w = sext [i64] (%u);
+        x = sext [i64] (%arg.0);
+        y = zext [i64] (%arg.1);
+        a = mul (%x), (%y);
+        b = sub (%a), (i64 10000000000);
+        c = div (%b), (%w);
+        d = rem (%b), (i64 12345);
+        e = ashr (%c), (i64 3);
+        f = add (%e), (%d);
+        l = icmp lt (%f), (i64 0);
+        br (%l) neg, pos;
+    neg:
+        g = sub (i64 0), (%f);
+        ret (%g);
+    pos:
+        ret (%f);
+    });
+}
+
+fn single_float(m: &mut Module) {
+    sericum_ir!(m; define [i32] func [(i32)] {
+    entry:
+        p = alloca f32;
+        store (f32 0.1), (%p);
+        s = load (%p);
+        n = sitofp [f32] (%arg.0);
+        a = mul (%s), (%n);
+        d = fpext [f64] (%a);
+        e = mul (%d), (f64 1000.0);
+        t = fptrunc [f32] (%e);
+        r = fptosi [i32] (%t);
+        ret (%r);
+    });
+}
+
+fn casts(m: &mut Module) {
+    sericum_ir!(m; define [i32] func [(i32)] {
+    entry:
+        t = trunc [i8] (%arg.0);
+        z = zext [i32] (%t);
+        u = uitofp [f64] (%arg.0);
+        h = div (%u), (f64 2.0);
+        v = fptoui [i32] (%h);
+        p = alloca i32;
+        pi = ptrtoint [i64] (%p);
+        q = inttoptr [ptr i32] (%pi);
+        store (%z), (%q);
+        l = load (%p);
+        x = add (%v), (%l);
+        ret (%x);
+    });
+}
+
+fn select(m: &mut Module) {
+    sericum_ir!(m; define [i32] func [(i32), (i32)] {
+    entry:
+        c = icmp lt (%arg.0), (%arg.1);
+        min = select (%c), (%arg.0), (%arg.1);
+        d = icmp ugt (%arg.0), (%arg.1);
+        umax = select (%d), (%arg.0), (%arg.1);
+        a8 = trunc [i8] (%arg.0);
+        b8 = trunc [i8] (%arg.1);
+        e = icmp le (%a8), (%b8);
+        s8 = select (%e), (%a8), (i8 100);
+        z = sext [i32] (%s8);
+        fa = sitofp [f64] (%arg.0);
+        fb = sitofp [f64] (%arg.1);
+        f = fcmp ult (%fa), (%fb);
+        fs = select (%f), (%fb), (%fa);
+        fi = fptosi [i32] (%fs);
+        br next;
+    next:
+        s = select (%c), (i32 3), (i32 5);
+        x = add (%min), (%umax);
+        x = add (%x), (%z);
+        x = add (%x), (%fi);
+        x = add (%x), (%s);
+        ret (%x);
+    });
+}
+
+fn switch(m: &mut Module) {
+    sericum_ir!(m; define [i32] func [(i32)] {
+    entry:
+        switch (%arg.0) other, [
+            [(i32 0), l0], [(i32 1), l1], [(i32 2), l2], [(i32 3), l3],
+            [(i32 5), l1], [(i32 100), l2], [(i32 -50), l3], [(i32 1000), l0]
+        ];
+    l0:
+        br join;
+    l1:
+        br join;
+    l2:
+        x = mul (%arg.0), (i32 3);
+        br join;
+    l3:
+        br join;
+    other:
+        br join;
+    join:
+        p = phi [ [(i32 10), l0], [(i32 11), l1], [(%x), l2], [(i32 13), l3], [(i32 -1), other] ];
+        ret (%p);
+    });
+}
+
+fn switch_loop(m: &mut Module) {
+    sericum_ir!(m; define [i32] func [(i32)] {
+    entry:
+        i = alloca i32;
+        s = alloca i32;
+        store (i32 0), (%i);
+        store (i32 0), (%s);
+        br loop_;
+    loop_:
+        li = load (%i);
+        ls = load (%s);
+        k = rem (%li), (i32 6);
+        k8 = trunc [i8] (%k);
+        switch (%k8) other, [ [(i8 0), add_], [(i8 1), mul_], [(i8 2), sub_], [(i8 3), next] ];
+    add_:
+        ta = add (%ls), (%li);
+        store (%ta), (%s);
+        br next;
+    mul_:
+        tm = mul (%ls), (i32 2);
+        store (%tm), (%s);
+        br next;
+    sub_:
+        ts = sub (%ls), (i32 7);
+        store (%ts), (%s);
+        br next;
+    other:
+        to = add (%ls), (i32 1);
+        store (%to), (%s);
+        br next;
+    next:
+        i1 = add (%li), (i32 1);
+        store (%i1), (%i);
+        c = icmp lt (%i1), (%arg.0);
+        br (%c) loop_, end;
+    end:
+        r = load (%s);
+        ret (%r);
+    });
+}
+
+fn array_index(m: &mut Module) {
+    sericum_ir!(m; define [i32] func [(i32)] {
+    entry:
+        arr = alloca_ ([4; i32]);
+        p = gep (%arr), [(i32 0), (%arg.0)];
+        x = load (%p);
+        ret (%x);
+    });
+}
+
+fn dangling(m: &mut Module) {
+    let local = sericum_ir!(m; define [ptr i32] local [] {
+    entry:
+        a = alloca i32;
+        store (i32 1), (%a);
+        ret (%a);
+    });
+    sericum_ir!(m; define [i32] func [] {
+    entry:
+        p = call (->local) [];
+        x = load (%p);
+        ret (%x);
+    });
+}
+
+fn deref(m: &mut Module) {
+    sericum_ir!(m; define [i32] func [(ptr i32)] {
+    entry:
+        x = load (%arg.0);
+        ret (%x);
+    });
+}
+
+fn divide(m: &mut Module) {
+    sericum_ir!(m; define [i32] func [(i32), (i32)] {
+    entry:
+        q = div (%arg.0), (%arg.1);
+        r = rem (%arg.0), (%arg.1);
+        x = add (%q), (%r);
+        ret (%x);
+    });
+}
+
+fn expected_switch(x: i32) -> i32 {
+    match x {
+        0 | 1000 => 10,
+        1 | 5 => 11,
+        2 | 100 => x * 3,
+        3 | -50 => 13,
+        _ => -1,
+    }
+}
+
+fn expected_switch_loop(n: i32) -> i32 {
+    let mut s = 0i32;
+    for i in 0..n.max(1) {
+        s = match i % 6 {
+            0 => s + i,
+            1 => s * 2,
+            2 => s - 7,
+            3 => s,
+            _ => s + 1,
+        };
+    }
+    s
+}
+
+#[test]
+fn interp_sum_array() {
+    let res = run_both(sum_array, "func", vec![ConcreteValue::Int32(3)]);
+    assert_eq!(res, ConcreteValue::Int32(135));
+}
+
+#[test]
+fn interp_struct_global() {
+    let res = run_both(struct_global, "func", vec![]);
+    assert_eq!(res, ConcreteValue::Int64(4321));
+}
+
+#[test]
+fn interp_string_length() {
+    let res = run_both(string_length, "func", vec![]);
+    assert_eq!(res, ConcreteValue::Int32(12));
+}
+
+#[test]
+fn interp_float_internals() {
+    let res = run_both(float_internals, "func", vec![ConcreteValue::Int32(2)]);
+    assert_eq!(res, ConcreteValue::Int32(14));
+}
+
+#[test]
+fn interp_memset_malloc() {
+    let res = run_both(memset_malloc, "func", vec![]);
+    assert_eq!(res, ConcreteValue::Int32(0x01010101));
+}
+
+#[test]
+fn interp_fibo() {
+    let res = run_both(fibo, "fibo", vec![ConcreteValue::Int32(15)]);
+    assert_eq!(res, ConcreteValue::Int32(610));
+}
+
+#[test]
+fn interp_bitwise() {
+    let res = run_both(
+        bitwise,
+        "func",
+        vec![ConcreteValue::Int32(-123456), ConcreteValue::Int32(0x1235)],
+    );
+    let (x, y) = (-123456i32, 0x1235i32);
+    let c = !((x & 4080) | y);
+    let n = (y & 7) as u32;
+    let expected =
+        ((x << n) ^ ((c as u32 >> n) as i32)).wrapping_add(c >> 3) | (x as u32 >> 28) as i32;
+    assert_eq!(res, ConcreteValue::Int32(expected));
+}
+
+#[test]
+fn interp_unsigned() {
+    let res = |x: i32, y: i32| {
+        run_both(
+            unsigned,
+            "func",
+            vec![ConcreteValue::Int32(x), ConcreteValue::Int32(y)],
+        )
+    };
+    assert_eq!(res(3, -7), ConcreteValue::Int32(3));
+    assert_eq!(res(-7, 3), ConcreteValue::Int32(-1));
+    assert_eq!(res(-1000, 3), ConcreteValue::Int32(1431655432));
+}
+
+#[test]
+fn interp_wide_ints() {
+    let res = run_both(
+        wide_ints,
+        "func",
+        vec![ConcreteValue::Int32(-123456), ConcreteValue::Int32(-1000)],
+    );
+    let b = -123456i64 * 0xffff_fc18i64 - 10000000000;
+    let f = (b / -700 >> 3) + b % 12345;
+    assert_eq!(res, ConcreteValue::Int64(f.abs()));
+}
+
+#[test]
+fn interp_single_float() {
+    let res = run_both(single_float, "func", vec![ConcreteValue::Int32(7)]);
+    let expected = ((0.1f32 * 7.0f32) as f64 * 1000.0) as f32 as i32;
+    assert_eq!(res, ConcreteValue::Int32(expected));
+}
+
+#[test]
+fn interp_casts() {
+    let res = |x: i32| run_both(casts, "func", vec![ConcreteValue::Int32(x)]);
+    let expected = |x: i32| ((x as u32 as f64 / 2.0) as u32 as i32).wrapping_add(x & 0xff);
+    assert_eq!(res(1000), ConcreteValue::Int32(expected(1000)));
+    assert_eq!(res(-7), ConcreteValue::Int32(expected(-7)));
+}
+
+#[test]
+fn interp_select() {
+    let res = |x: i32, y: i32| {
+        run_both(
+            select,
+            "func",
+            vec![ConcreteValue::Int32(x), ConcreteValue::Int32(y)],
+        )
+    };
+    let expected = |x: i32, y: i32| {
+        let s8 = if x as i8 <= y as i8 { x as i8 } else { 100 };
+        x.min(y)
+            + (x as u32).max(y as u32) as i32
+            + s8 as i32
+            + x.max(y)
+            + if x < y { 3 } else { 5 }
+    };
+    for &(x, y) in &[(1, 2), (2, 1), (-5, 300), (7, 7)] {
+        assert_eq!(res(x, y), ConcreteValue::Int32(expected(x, y)));
+    }
+}
+
+#[test]
+fn interp_switch() {
+    for &x in &[0, 1, 2, 3, 4, 5, 6, 100, -50, 1000, 999, -1, i32::MIN] {
+        let res = run_both(switch, "func", vec![ConcreteValue::Int32(x)]);
+        assert_eq!(res, ConcreteValue::Int32(expected_switch(x)));
+    }
+    let res = run_both(switch_loop, "func", vec![ConcreteValue::Int32(20)]);
+    assert_eq!(res, ConcreteValue::Int32(expected_switch_loop(20)));
+}
+
+#[test]
+fn interp_lower_switch() {
+    let lowered = |build: fn(&mut Module), jump_tables: bool| {
+        let mut m = Module::new("sericum");
+        build(&mut m);
+        let mut lower = ir::lower_switch::LowerSwitch::new();
+        if !jump_tables {
+            lower = lower.without_jump_tables();
+        }
+        lower.run_on_module(&mut m);
+        ir::verify::verify_module(&m).unwrap();
+        m
+    };
+    for &jump_tables in &[true, false] {
+        let m = lowered(switch, jump_tables);
+        for &x in &[0, 1, 2, 3, 4, 5, 100, -50, 1000, 999, i32::MIN] {
+            let res = run(&m, "func", vec![ConcreteValue::Int32(x)]);
+            assert_eq!(res, ConcreteValue::Int32(expected_switch(x)));
+        }
+        let m = lowered(switch_loop, jump_tables);
+        let res = run(&m, "func", vec![ConcreteValue::Int32(20)]);
+        assert_eq!(res, ConcreteValue::Int32(expected_switch_loop(20)));
+    }
+}
+
+#[test]
+fn interp_bad_access() {
+    let mut m = Module::new("sericum");
+    array_index(&mut m);
+    assert_eq!(
+        try_run(&m, "func", vec![ConcreteValue::Int32(3)]),
+        Ok(ConcreteValue::Int32(0))
+    );
+    assert!(matches!(
+        try_run(&m, "func", vec![ConcreteValue::Int32(4)]),
+        Err(InterpError::OutOfBounds { size: 4, .. })
+    ));
+    assert!(matches!(
+        try_run(&m, "func", vec![ConcreteValue::Int32(-1)]),
+        Err(InterpError::OutOfBounds { size: 0, .. })
+    ));
+
+    let mut m = Module::new("sericum");
+    dangling(&mut m);
+    assert!(matches!(
+        try_run(&m, "func", vec![]),
+        Err(InterpError::UseAfterFree(_))
+    ));
+
+    let mut m = Module::new("sericum");
+    deref(&mut m);
+    let ptr_i32 = m.types.new_pointer_ty(Type::i32);
+    assert_eq!(
+        try_run(&m, "func", vec![ConcreteValue::Mem(0, ptr_i32)]),
+        Err(InterpError::InvalidAddress(0))
+    );
+}
+
+#[test]
+fn interp_division_by_zero() {
+    let args = |x: i32, y: i32| vec![ConcreteValue::Int32(x), ConcreteValue::Int32(y)];
+
+    let mut m = Module::new("sericum");
+    divide(&mut m);
+    assert_eq!(try_run(&m, "func", args(7, 2)), Ok(ConcreteValue::Int32(4)));
+    assert_eq!(
+        try_run(&m, "func", args(7, 0)),
+        Err(InterpError::DivisionByZero)
+    );
+
+    let mut m = Module::new("sericum");
+    unsigned(&mut m);
+    assert_eq!(
+        try_run(&m, "func", args(7, 0)),
+        Err(InterpError::DivisionByZero)
+    );
+}
+
+#[test]
+fn interp_parsed_module() {
+    let m = parse_module(include_str!("sir/fibo.sir")).unwrap();
+    let res = run(&m, "fibo", vec![ConcreteValue::Int32(10)]);
+    assert_eq!(res, ConcreteValue::Int32(55));
+}
+
+#[cfg(feature = "x86_64")]
+mod x86_64 {
+    use super::*;
+    use sericum::codegen::x64::exec::jit::{GenericValue, JITExecutor};
+
+    fn assert_same_as_jit(build: fn(&mut Module), name: &str, args: Vec<i32>) {
+        let mut m = Module::new("sericum");
+        build(&mut m);
+        let expected = run(
+            &m,
+            name,
+            args.iter().map(|&i| ConcreteValue::Int32(i)).collect(),
+        );
+
+        let mut jit = JITExecutor::new(m);
+        let func = jit.find_function_by_name(name).unwrap();
+        let res = jit.run(func, args.into_iter().map(GenericValue::Int32).collect());
+        match (res, expected) {
+            (GenericValue::Int32(x), ConcreteValue::Int32(y)) => assert_eq!(x, y),
+            (GenericValue::Int64(x), ConcreteValue::Int64(y)) => assert_eq!(x, y),
+            (res, expected) => panic!("{:?} vs {:?}", res, expected),
+        }
+    }
+
+    #[test]
+    fn interp_same_as_jit() {
+        assert_same_as_jit(sum_array, "func", vec![7]);
+        assert_same_as_jit(memset_malloc, "func", vec![]);
+        assert_same_as_jit(fibo, "fibo", vec![20]);
+        assert_same_as_jit(bitwise, "func", vec![-123456, 0x1235]);
+        assert_same_as_jit(bitwise, "func", vec![0x7fff_1234, -3]);
+        assert_same_as_jit(unsigned, "func", vec![-7, 3]);
+        assert_same_as_jit(unsigned, "func", vec![-1000, 3]);
+        assert_same_as_jit(unsigned, "func", vec![3, -7]);
+        assert_same_as_jit(unsigned, "func", vec![123456, 1000]);
+        assert_same_as_jit(wide_ints, "func", vec![-123456, -1000]);
+        assert_same_as_jit(wide_ints, "func", vec![987654, 321]);
+        assert_same_as_jit(single_float, "func", vec![7]);
+        assert_same_as_jit(single_float, "func", vec![-33]);
+        assert_same_as_jit(casts, "func", vec![1000]);
+        assert_same_as_jit(casts, "func", vec![-7]);
+        assert_same_as_jit(select, "func", vec![1, 2]);
+        assert_same_as_jit(select, "func", vec![-5, 300]);
+        assert_same_as_jit(select, "func", vec![7, 7]);
+        for &x in &[0, 1, 2, 3, 4, 5, 6, 100, -50, 1000, 999, -1, i32::MIN] {
+            assert_same_as_jit(switch, "func", vec![x]);
+        }
+        assert_same_as_jit(switch_loop, "func", vec![20]);
+        assert_same_as_jit(switch_loop, "func", vec![1]);
+    }
+}
@@ -0,0 +1,178 @@
+use sericum::{ir, ir::parser::parse_module, ir::prelude::*, sericum_ir};
+
+fn assert_round_trip(m: &Module) {
+    let text = format!("{:?}", m);
+    let parsed = parse_module(text.as_str()).unwrap();
+    assert_eq!(text, format!("{:?}", parsed));
+}
+
+#[test]
+fn round_trip() {
+    let mut m = Module::new("sericum");
+    let s = m.create_string("hello \"world\"\n".to_string());
+    let sty = m
+        .types
+        .new_struct_ty(vec![types::Type::i8, types::Type::i32]);
+    m.global_vars
+        .new_global_var_with_name(sty, global_val::Linkage::Common, "g");
+    m.create_function("ext", Type::f64, vec![Type::f64, sty]);
+    sericum_ir!(m; define [i32] f [(i32), (ptr i8)] {
+    entry:
+        a = alloca_ ([4; i32]);
+        p = gep (%a), [(i32 0), (i32 1)];
+        store (%arg.0), (%p);
+        x = load (%p);
+        c = icmp le (%x), (i32 2);
+        sp = gep (%s), [(i32 0), (i32 0)];
+        br (%c) l1, l2;
+    l1:
+        y = add (%x), (i32 -1);
+        br l2;
+    l2:
+        z = phi [ [(%x), entry], [(%y), l1] ];
+        r = call f [(%z), (%sp)];
+        ff = fcmp ult (f64 1.5), (f64 2.0);
+        e = sext [i64] (%r);
+        bw = xor (%r), (i32 255);
+        sh = lshr (%bw), (%z);
+        ud = udiv (%sh), (%arg.0);
+        uc = icmp uge (%ud), (i32 7);
+        h = alloca i16;
+        store (i16 -3), (%h);
+        w = add (%e), (i64 5000000000);
+        fp = alloca f32;
+        store (f32 0.5), (%fp);
+        lf = load (%fp);
+        fe = fpext [f64] (%lf);
+        ft = fptrunc [f32] (%fe);
+        t = trunc [i8] (%r);
+        uf = uitofp [f64] (%r);
+        fu = fptoui [i32] (%fe);
+        pi = ptrtoint [i64] (%arg.1);
+        ip = inttoptr [ptr i8] (%pi);
+        sl = select (%uc), (%r), (%ud);
+        ret (%r);
+    });
+    sericum_ir!(m; define [void] g [] {
+    entry:
+        ret (void);
+    });
+    sericum_ir!(m; define [i32] sw [(i8)] {
+    entry:
+        switch (%arg.0) l3, [ [(i8 1), l1], [(i8 -4), l2], [(i8 7), l1] ];
+    l1:
+        ret (i32 1);
+    l2:
+        ret (i32 2);
+    l3:
+        ret (i32 0);
+    });
+    assert_round_trip(&m);
+}
+
+#[test]
+fn round_trip_after_mem2reg() {
+    let mut m = Module::new("sericum");
+    sericum_ir!(m; define [i32] func [(i32)] {
+    entry:
+        i = alloca i32;
+        store (i32 0), (%i);
+        br loop_;
+    loop_:
+        li = load (%i);
+        c = icmp lt (%li), (%arg.0);
+        br (%c) body, end;
+    body:
+        li = load (%i);
+        inc = add (%li), (i32 1);
+        store (%inc), (%i);
+        br loop_;
+    end:
+        li = load (%i);
+        ret (%li);
+    });
+    ir::mem2reg::Mem2Reg::new().run_on_module(&mut m);
+    assert_round_trip(&m);
+}
+
+#[test]
+fn round_trip_named_struct() {
+    let mut m = Module::new("sericum");
+    let node = m.types.new_named_struct_ty("node".to_string(), vec![]);
+    let node_ptr = m.types.new_pointer_ty(node);
+    let mut strct = m.types.compound_ty(node).as_struct().clone();
+    strct.set_fields(&m.types, vec![Type::i32, node_ptr]);
+    *m.types.compound_ty_mut(node).as_struct_mut() = strct;
+    m.global_vars
+        .new_global_var_with_name(node, global_val::Linkage::External, "head");
+    m.create_function("sericum.println.i32", Type::Void, vec![Type::i32]);
+    assert_round_trip(&m);
+}
+
+#[test]
+fn parse_sir_file() {
+    let src = include_str!("sir/fibo.sir");
+    let m = parse_module(src).unwrap();
+    let f = m.function_ref(m.find_function("fibo").unwrap());
+    assert_eq!(f.basic_blocks.order.len(), 4);
+    let loop_header = f.basic_blocks.order[1];
+    assert_eq!(f.basic_block_ref(loop_header).pred.len(), 2);
+    assert_round_trip(&m);
+}
+
+#[test]
+fn parse_named_labels_and_values() {
+    let m = parse_module(
+        "define i32 f(i32) {
+        entry:
+            %x = add i32 %arg.0, i32 1
+            br %exit
+        exit:
+            ret i32 %x
+        }",
+    )
+    .unwrap();
+    let f = m.function_ref(m.find_function("f").unwrap());
+    assert_eq!(f.basic_blocks.order.len(), 2);
+    let entry = f.basic_blocks.order[0];
+    let add = f.basic_block_ref(entry).iseq_ref()[0];
+    assert_eq!(f.inst_table[add].opcode, Opcode::Add);
+    assert_eq!(f.inst_table[add].users.borrow().len(), 1);
+}
+
+#[test]
+fn parse_errors() {
+    let err = parse_module(
+        "define i32 f() {
+        label.0:
+            ret i32 %0
+        }",
+    )
+    .unwrap_err();
+    assert_eq!((err.line, err.column), (3, 21));
+
+    let err = parse_module(
+        "define i32 f() {
+        label.0:
+            %0 = frobnicate i32 1
+        }",
+    )
+    .unwrap_err();
+    assert_eq!((err.line, err.column), (3, 18));
+
+    let err = parse_module("define i32 f() {\n  label.0:\n    ret i32 ?\n}").unwrap_err();
+    assert_eq!((err.line, err.column), (3, 13));
+}
+
+#[cfg(feature = "x86_64")]
+#[test]
+fn run_parsed_module() {
+    use sericum::codegen::x64::exec;
+    let m = parse_module(include_str!("sir/fibo.sir")).unwrap();
+    let mut jit = exec::jit::JITExecutor::new(m);
+    let func = jit.find_function_by_name("fibo").unwrap();
+    assert_eq!(
+        jit.run(func, vec![exec::jit::GenericValue::Int32(10)]),
+        exec::jit::GenericValue::Int32(55)
+    );
+}
@@ -0,0 +1,104 @@
+use sericum::ir::{
+    parser::parse_module,
+    verify::{verify_module, VerifyErrorKind},
+};
+
+fn verify_err(src: &str) -> VerifyErrorKind {
+    let m = parse_module(src).unwrap();
+    verify_module(&m).unwrap_err().kind
+}
+
+#[test]
+fn verify_valid() {
+    let m = parse_module(include_str!("sir/fibo.sir")).unwrap();
+    assert!(verify_module(&m).is_ok())
+}
+
+#[test]
+fn verify_missing_terminator() {
+    let kind = verify_err(
+        "define i32 f(i32) {
+         label.0:
+             %0 = add i32 %arg.0, i32 1
+         }",
+    );
+    assert_eq!(kind, VerifyErrorKind::MissingTerminator)
+}
+
+#[test]
+fn verify_type_mismatch() {
+    let kind = verify_err(
+        "define i32 f(i32, i64) {
+         label.0:
+             %0 = add i32 %arg.0, i64 %arg.1
+             ret i32 %0
+         }",
+    );
+    assert!(matches!(kind, VerifyErrorKind::TypeMismatch(_)))
+}
+
+#[test]
+fn verify_use_not_dominated() {
+    let kind = verify_err(
+        "define i32 f(i32) {
+         label.0:
+             %0 = icmp eq i32 %arg.0, i32 0
+             br i1 %0, %label.1, %label.2
+         label.1:
+             %1 = add i32 %arg.0, i32 1
+             br %label.2
+         label.2:
+             ret i32 %1
+         }",
+    );
+    assert!(matches!(kind, VerifyErrorKind::UseNotDominated(_)))
+}
+
+#[test]
+fn verify_phi_incoming_mismatch() {
+    let kind = verify_err(
+        "define i32 f(i32) {
+         label.0:
+             br %label.1
+         label.1:
+             %0 = phi (%label.0, i32 1), (%label.2, i32 2)
+             ret i32 %0
+         label.2:
+             ret i32 %arg.0
+         }",
+    );
+    assert_eq!(kind, VerifyErrorKind::PhiIncomingMismatch)
+}
+
+#[test]
+fn verify_duplicate_switch_case() {
+    let kind = verify_err(
+        "define i32 f(i32) {
+         label.0:
+             switch i32 %arg.0, %label.1, (i32 1, %label.2), (i32 1, %label.1)
+         label.1:
+             ret i32 0
+         label.2:
+             ret i32 1
+         }",
+    );
+    assert_eq!(kind, VerifyErrorKind::DuplicateSwitchCase)
+}
+
+#[test]
+fn verify_immediate_width() {
+    // Backends encode immediates with the width of the other operand
+    let m = parse_module(
+        "define i32 f(i8) {
+         label.0:
+             %0 = icmp le i8 %arg.0, i32 57
+             br i1 %0, %label.1, %label.2
+         label.1:
+             ret i32 1
+         label.2:
+             ret i32 0
+         }",
+    )
+    .unwrap();
+    assert!(verify_module(&m).is_ok())
+}